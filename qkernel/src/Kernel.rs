@@ -44,6 +44,10 @@ impl HostSpace {
         HyperCall64(HYPERCALL_WAKEUP_VCPU, vcpuId, 0, 0);
     }
 
+    pub fn TlbShootdown(vcpuId: u64) {
+        HyperCall64(HYPERCALL_TLB_SHOOTDOWN, vcpuId, 0, 0);
+    }
+
     pub fn IOWait() {
         HyperCall64(HYPERCALL_IOWAIT, 0, 0, 0);
     }
@@ -852,6 +856,15 @@ impl HostSpace {
         HostSpace::HCall(&mut msg, true);
     }
 
+    pub fn ReleaseMemory(addr: u64, len: u64) -> i64 {
+        let mut msg = Msg::ReleaseMemory(qmsg::qcall::ReleaseMemory {
+            addr,
+            len,
+        });
+
+        return HostSpace::HCall(&mut msg, false) as i64;
+    }
+
     fn Call(msg: &mut Msg, mustAsync: bool) -> u64 {
         /*if !mustAsync  {
             return Self::HCall(msg, true) as u64
@@ -132,6 +132,13 @@ pub struct SigChld {
     pub sTime: i32,
 }
 
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SigPoll {
+    pub band: i64,
+    pub fd: i32,
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone, Default)]
 pub struct SigFault {
@@ -225,6 +232,13 @@ impl SignalInfo {
         }
     }
 
+    pub fn SigPoll(&mut self) -> &mut SigPoll {
+        let addr = &self.fields[0] as *const _ as u64;
+        return unsafe {
+            &mut *(addr as *mut SigPoll)
+        }
+    }
+
     pub fn SigFault(&self) -> &mut SigFault {
         let addr = &self.fields[0] as *const _ as u64;
         return unsafe {
@@ -699,12 +713,23 @@ impl PendingSignals {
     pub fn Discard(&mut self, sig: Signal) {
         self.pendingSet.0 &= !(1 << sig.Index());
 
+        // A timer whose signal is discarded counts it as an overrun and can
+        // send the next one.
         if sig.0 <= STD_SIGNAL_COUNT as i32 {
-            self.stdSignals[sig.Index()] = None;
+            if let Some(ps) = self.stdSignals[sig.Index()].take() {
+                if let Some(timer) = ps.timer {
+                    timer.lock().signalRejectedLocked();
+                }
+            }
             return
         }
 
-        self.rtSignals[sig.0 as usize - RT_SIGNAL_START].Clear()
+        let q = &mut self.rtSignals[sig.0 as usize - RT_SIGNAL_START];
+        while let Some(ps) = q.Deque() {
+            if let Some(timer) = ps.timer {
+                timer.lock().signalRejectedLocked();
+            }
+        }
     }
 }
 
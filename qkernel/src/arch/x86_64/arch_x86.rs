@@ -15,6 +15,8 @@
 use alloc::sync::Arc;
 use ::qlib::mutex::*;
 
+use super::super::super::qlib::common::*;
+use super::super::super::qlib::linux_def::*;
 use super::super::super::qlib::cpuid::*;
 use super::super::super::SignalDef::*;
 use super::super::super::asm::*;
@@ -87,6 +89,140 @@ pub const USER_DS: u64 = 0x2b; // guest ring 3 data selector
 pub const FS_TLS_SEL: u64 = 0x63; // Linux FS thread-local storage selector
 pub const GS_TLS_SEL: u64 = 0x6b; // Linux GS thread-local storage selector
 
+// USER_AREA_SIZE is sizeof(struct user), which bounds the offsets accepted by
+// ptrace(PTRACE_PEEKUSER) and ptrace(PTRACE_POKEUSER).
+pub const USER_AREA_SIZE: u64 = 912;
+
+// TASK_SIZE_MAX is the limit of user addresses, which a segment base set
+// with ptrace must be below. See arch/x86/kernel/ptrace.c:putreg().
+pub const TASK_SIZE_MAX: u64 = (1 << 47) - 4096;
+
+// PtraceRegs is Linux's struct user_regs_struct, the register set of
+// ptrace(PTRACE_GETREGS) and the start of struct user.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct PtraceRegs {
+    pub r15: u64,
+    pub r14: u64,
+    pub r13: u64,
+    pub r12: u64,
+    pub rbp: u64,
+    pub rbx: u64,
+    pub r11: u64,
+    pub r10: u64,
+    pub r9: u64,
+    pub r8: u64,
+    pub rax: u64,
+    pub rcx: u64,
+    pub rdx: u64,
+    pub rsi: u64,
+    pub rdi: u64,
+    pub orig_rax: u64,
+    pub rip: u64,
+    pub cs: u64,
+    pub eflags: u64,
+    pub rsp: u64,
+    pub ss: u64,
+    pub fs_base: u64,
+    pub gs_base: u64,
+    pub ds: u64,
+    pub es: u64,
+    pub fs: u64,
+    pub gs: u64,
+}
+
+impl PtraceRegs {
+    pub const SIZE: u64 = core::mem::size_of::<PtraceRegs>() as u64;
+
+    pub fn New(regs: &PtRegs, fsBase: u64) -> Self {
+        return Self {
+            r15: regs.r15,
+            r14: regs.r14,
+            r13: regs.r13,
+            r12: regs.r12,
+            rbp: regs.rbp,
+            rbx: regs.rbx,
+            r11: regs.r11,
+            r10: regs.r10,
+            r9: regs.r9,
+            r8: regs.r8,
+            rax: regs.rax,
+            rcx: regs.rcx,
+            rdx: regs.rdx,
+            rsi: regs.rsi,
+            rdi: regs.rdi,
+            orig_rax: regs.orig_rax,
+            rip: regs.rip,
+            cs: regs.cs,
+            eflags: regs.eflags,
+            rsp: regs.rsp,
+            ss: regs.ss,
+            fs_base: fsBase,
+            gs_base: 0,
+            ds: 0,
+            es: 0,
+            fs: 0,
+            gs: 0,
+        }
+    }
+
+    // Apply writes the registers to regs and returns the new fs base. Only
+    // the EFLAGS bits in EFLAGS_PTRACE_MUTABLE can be changed, and the
+    // segment registers and gs base are left as they are.
+    pub fn Apply(&self, regs: &mut PtRegs) -> Result<u64> {
+        if self.fs_base >= TASK_SIZE_MAX {
+            return Err(Error::SysError(SysErr::EIO))
+        }
+
+        regs.r15 = self.r15;
+        regs.r14 = self.r14;
+        regs.r13 = self.r13;
+        regs.r12 = self.r12;
+        regs.rbp = self.rbp;
+        regs.rbx = self.rbx;
+        regs.r11 = self.r11;
+        regs.r10 = self.r10;
+        regs.r9 = self.r9;
+        regs.r8 = self.r8;
+        regs.rax = self.rax;
+        regs.rcx = self.rcx;
+        regs.rdx = self.rdx;
+        regs.rsi = self.rsi;
+        regs.rdi = self.rdi;
+        regs.orig_rax = self.orig_rax;
+        regs.rip = self.rip;
+        regs.eflags = (regs.eflags & !EFLAGS_PTRACE_MUTABLE) | (self.eflags & EFLAGS_PTRACE_MUTABLE);
+        regs.rsp = self.rsp;
+        return Ok(self.fs_base)
+    }
+
+    // Peek returns the register at byte offset off of struct user. The
+    // rest of struct user reads as 0.
+    pub fn Peek(&self, off: u64) -> u64 {
+        if off >= Self::SIZE {
+            return 0
+        }
+
+        let words = unsafe {
+            &*(self as *const Self as *const [u64; 27])
+        };
+        return words[(off / 8) as usize]
+    }
+
+    // Poke sets the register at byte offset off of struct user. Writes to
+    // the rest of struct user are ignored.
+    pub fn Poke(&mut self, off: u64, val: u64) {
+        if off >= Self::SIZE {
+            return
+        }
+
+        let words = unsafe {
+            &mut *(self as *mut Self as *mut [u64; 27])
+        };
+        words[(off / 8) as usize] = val;
+    }
+}
+
 extern "C" {
     pub fn initX86FPState(data: u64, useXsave: bool);
 }
@@ -98,6 +234,10 @@ pub fn InitX86FPState(data: u64, useXsave: bool) {
 }
 
 // x86FPState is x86 floating point state.
+//
+// data holds an FXSAVE or XSAVE image, so its first 512 bytes have the layout
+// of user_fpregs_struct, i.e. the NT_PRFPREG core note, and its first size
+// bytes the layout of the NT_X86_XSTATE note, see threadmgr/task_coredump.rs.
 #[repr(align(4096))]
 #[repr(C)]
 #[derive(Debug)]
@@ -147,8 +287,14 @@ impl X86fpstate {
         return &self.data[0] as *const _ as u64;
     }
 
+    // SaveFp saves the live floating point state into data, with XSAVE if
+    // the host has it so that size bytes are valid.
     pub fn SaveFp(&self) {
-        SaveFloatingPoint(self.FloatingPointData());
+        if HostFeatureSet().UseXsave() {
+            xsave(self.FloatingPointData());
+        } else {
+            SaveFloatingPoint(self.FloatingPointData());
+        }
     }
 
     pub fn RestoreFp(&self) {
@@ -89,6 +89,16 @@ pub const PREFERRED_TOP_DOWN_BASE_MIN: u64 = PREFERRED_TOP_DOWN_ALLOC_MIN + PREF
 // randomization to stay above PREFERRED_TOP_DOWN_BASE_MIN.
 pub const MIN_MMAP_RAND64: u64 = (1 << 26) * MemoryDef::PAGE_SIZE;
 
+// MAX_BRK_RAND64 is the maximum randomization to apply to the start of the
+// heap. It is defined by arch/x86/kernel/process.c:arch_randomize_brk in
+// Linux.
+pub const MAX_BRK_RAND64: u64 = 0x02000000;
+
+// MAX_STACK_ALIGN_RAND64 is the maximum sub-page randomization to apply to
+// the initial stack pointer. It is defined by
+// arch/x86/kernel/process.c:arch_align_stack in Linux.
+pub const MAX_STACK_ALIGN_RAND64: u64 = 8192;
+
 pub struct Context64 {
     pub state: State,
     pub sigFPState: Vec<Arc<QMutex<X86fpstate>>>,
@@ -156,7 +166,8 @@ impl Context64 {
     }
 
     // NewMmapLayout implements Context.NewMmapLayout consistently with Linux.
-    pub fn NewMmapLayout(min: u64, max: u64, r: &LimitSet) -> Result<MmapLayout> {
+    // If randomize is false, the bases aren't randomized.
+    pub fn NewMmapLayout(min: u64, max: u64, r: &LimitSet, randomize: bool) -> Result<MmapLayout> {
         let min = Addr(min).RoundUp()?.0;
 
         let mut max = if max > MAX_ADDR64 {
@@ -201,6 +212,10 @@ impl Context64 {
             }
         }
 
+        if !randomize {
+            maxRand = 0;
+        }
+
         let rnd = MMapRand(maxRand)?;
         let l = MmapLayout {
             MinAddr: min,
@@ -226,7 +241,7 @@ impl Context64 {
     }
 
     // PIELoadAddress implements Context.PIELoadAddress.
-    pub fn PIELoadAddress(l: &MmapLayout) -> Result<u64> {
+    pub fn PIELoadAddress(l: &MmapLayout, randomize: bool) -> Result<u64> {
         let mut base = PREFERRED_PIELOAD_ADDR;
 
         let max = match Addr(base).AddLen(MAX_MMAP_RAND64) {
@@ -242,8 +257,10 @@ impl Context64 {
             base = l.TopDownBase / 3 * 2;
         }
 
-        let addr = base + MMapRand(MAX_MMAP_RAND64)?;
-
+        let mut addr = base;
+        if randomize {
+            addr += MMapRand(MAX_MMAP_RAND64)?;
+        }
 
         return Ok(Addr(addr).RoundDown().unwrap().0);
     }
@@ -251,6 +268,10 @@ impl Context64 {
 
 // mmapRand returns a random adjustment for randomizing an mmap layout.
 pub fn MMapRand(max: u64) -> Result<u64> {
+    if max == 0 {
+        return Ok(0)
+    }
+
     let addr = RandU64()? % max;
     return Ok(Addr(addr).RoundDown().unwrap().0)
 }
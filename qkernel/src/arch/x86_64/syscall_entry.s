@@ -21,6 +21,7 @@
 .globl simd_fp_handler
 .globl virtualization_handler
 .globl security_handler
+.globl tlb_shootdown_handler
 
 .extern syscall_handler, CopyData,
 
@@ -44,6 +45,7 @@
 .extern SIMDFPHandler
 .extern VirtualizationHandler
 .extern SecurityHandler
+.extern TlbShootdownHandler
 
 .intel_syntax noprefix
 
@@ -388,6 +390,9 @@ virtualization_handler:
 security_handler:
     HandlerWithoutErrorCode SecurityHandler
 
+tlb_shootdown_handler:
+    HandlerWithoutErrorCode TlbShootdownHandler
+
 initX86FPState:
     // Save MXCSR (callee-save)
     STMXCSR     [rsp - 8]
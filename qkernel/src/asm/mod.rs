@@ -397,6 +397,16 @@ pub fn xsaveopt(addr: u64) {
     };
 }
 
+pub fn xsave(addr: u64) {
+    let negtive1 : u64 = 0xffffffff;
+    unsafe {
+        llvm_asm!("\
+            xsave64 [rdi + 0]
+        " : : "{rdi}"(addr), "{rax}"(negtive1), "{rdx}"(negtive1)
+        : "memory" : "intel", "volatile")
+    };
+}
+
 pub fn fxsave(addr: u64) {
     unsafe {
         llvm_asm!("\
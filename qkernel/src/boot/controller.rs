@@ -24,6 +24,7 @@ use super::super::taskMgr;
 use super::super::task::*;
 use super::super::{StartRootContainer, StartExecProcess};
 use super::super::LOADER;
+use super::super::fs::host::tty::*;
 use super::process::*;
 use super::super::qlib::singleton::*;
 
@@ -82,11 +83,38 @@ pub fn Run() -> Result<()> {
                             Ok(())=> ()
                         }
                     }
+                    SignalDeliveryMode::DeliverToProcessGroup => {
+                        match LOADER.Lock(task).unwrap().SignalProcessGroup(signalArgs.PID, signalArgs.Signo) {
+                            Err(e) => {
+                                info!("signal DeliverToProcessGroup fail with error {:?}", e);
+                            }
+                            Ok(())=> ()
+                        }
+                    }
                 };
 
                 ControlMsgRet(msg.msgId, &UCallResp::SignalResp);
                 continue;
             }
+            Payload::WindowSize(winsizeArgs) => {
+                info!("get window size {:?}", &winsizeArgs);
+                let w = Winsize {
+                    Row: winsizeArgs.Rows,
+                    Col: winsizeArgs.Cols,
+                    Xpixel: winsizeArgs.Xpixel,
+                    Ypixel: winsizeArgs.Ypixel,
+                };
+
+                match LOADER.Lock(task).unwrap().SetWindowSize(winsizeArgs.PID, &w) {
+                    Err(e) => {
+                        info!("set window size fail with error {:?}", e);
+                    }
+                    Ok(()) => ()
+                }
+
+                ControlMsgRet(msg.msgId, &UCallResp::WindowSizeResp);
+                continue;
+            }
             Payload::ContainerDestroy => {
                 LOADER.Lock(task).unwrap().DestroyContainer()?;
                 ControlMsgRet(msg.msgId, &UCallResp::ContainerDestroyResp);
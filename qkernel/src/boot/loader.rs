@@ -163,6 +163,7 @@ impl Loader {
                 .expect("TTYFileOps convert fail").clone();
 
             ttyops.InitForegroundProcessGroup(&tg.ProcessGroup().unwrap());
+            tg.lock().tty = Some(ttyops.clone());
             ttyFileOps = Some(ttyops);
         } else {
             task.NewStdFds(&procArgs.Stdiofds[..], false).expect("Task: create std fds");
@@ -200,6 +201,7 @@ impl Loader {
                 .expect("TTYFileOps convert fail").clone();
 
             ttyops.InitForegroundProcessGroup(&tg.ProcessGroup().unwrap());
+            tg.lock().tty = Some(ttyops.clone());
             ttyFileOps = Some(ttyops);
         } else {
             task.NewStdFds(&procArgs.Stdiofds[..], false).expect("Task: create std fds");
@@ -357,6 +359,25 @@ impl LoaderInternal {
         return lastErr
     }
 
+    pub fn SignalProcessGroup(&self, tgid: ThreadID, signo: i32) -> Result<()> {
+        let (tg, _) = match self.ThreadGroupFromID(tgid) {
+            None => return Err(Error::Common(format!("no thread group found for {}", tgid))),
+            Some(r) => r,
+        };
+
+        let info = SignalInfo{
+            Signo: signo,
+            ..Default::default()
+        };
+
+        match tg.ProcessGroup() {
+            // The process has been orphaned from its process group; signal
+            // it directly.
+            None => return tg.SendSignal(&info),
+            Some(pg) => return pg.SendSignal(&info),
+        }
+    }
+
     pub fn SignalProcess(&self, tgid: ThreadID, signo: i32) -> Result<()> {
         match self.ThreadGroupFromID(tgid) {
             None => (),
@@ -399,6 +420,20 @@ impl LoaderInternal {
         }
     }
 
+    pub fn SetWindowSize(&self, tgid: ThreadID, w: &Winsize) -> Result<()> {
+        let (_, tty) = match self.ThreadGroupFromID(tgid) {
+            None => return Err(Error::Common(format!("no thread group found for {}", tgid))),
+            Some(r) => r,
+        };
+
+        let tty = match tty {
+            None => return Err(Error::Common("no tty attached".to_string())),
+            Some(t) => t,
+        };
+
+        return tty.SetWindowSize(w)
+    }
+
     pub fn ThreadGroupFromIDLocked(&self, key: ThreadID) -> Result<(ThreadGroup, Option<TTYFileOps>)> {
         let ep = match self.processes.get(&key) {
             None => return Err(Error::Common("container not found".to_string())),
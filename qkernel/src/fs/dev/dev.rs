@@ -236,9 +236,10 @@ pub fn NewDev(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
     // runtime uses sys_random and /dev/urandom internally.
     // According to 'man 4 random', this will be sufficient unless
     // application uses this to generate long-lived GPG/SSL/SSH
-    // keys.
-    contents.insert("random".to_string(), NewRandomDevice(&Arc::new(RandomDevice::New(task, &ROOT_OWNER, &FileMode(0o0666))), msrc, RANDOM_DEV_MINOR));
-    contents.insert("urandom".to_string(), NewRandomDevice(&Arc::new(RandomDevice::New(task, &ROOT_OWNER, &FileMode(0o0666))), msrc, URANDOM_DEV_MINOR));
+    // keys. /dev/random reads wait for the host's entropy pool to be
+    // seeded, /dev/urandom reads never block.
+    contents.insert("random".to_string(), NewRandomDevice(&Arc::new(RandomDevice::New(task, &ROOT_OWNER, &FileMode(0o0666), true)), msrc, RANDOM_DEV_MINOR));
+    contents.insert("urandom".to_string(), NewRandomDevice(&Arc::new(RandomDevice::New(task, &ROOT_OWNER, &FileMode(0o0666), false)), msrc, URANDOM_DEV_MINOR));
 
     // A devpts is typically mounted at /dev/pts to provide
     // pseudoterminal support. Place an empty directory there for
@@ -273,8 +273,9 @@ impl FileOperations for FullFileOperations {
         return Err(Error::SysError(SysErr::ENOSPC))
     }
 
-    fn Append(&self, _task: &Task, _f: &File, _srcs: &[IoVec]) -> Result<(i64, i64)> {
-        return Err(Error::SysError(SysErr::ESPIPE))
+    fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
+        let n = self.WriteAt(task, f, srcs, 0, false)?;
+        return Ok((n, 0))
     }
 
     fn Fsync(&self, _task: &Task, _f: &File, _start: i64, _end: i64, _syncType: SyncType) -> Result<()> {
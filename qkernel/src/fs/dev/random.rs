@@ -18,6 +18,8 @@ use core::ops::Deref;
 use core::any::Any;
 use alloc::vec::Vec;
 use alloc::sync::Arc;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering;
 
 use socket::unix::transport::unix::BoundEndpoint;
 use super::super::host::hostinodeop::*;
@@ -43,11 +45,48 @@ use super::super::flags::*;
 use super::super::fsutil::inode::*;
 use super::super::fsutil::file::*;
 
-pub struct RandomDevice(pub QRwLock<InodeSimpleAttributesInternal>);
+// How long a blocking /dev/random read first sleeps before checking whether
+// the host's CSPRNG is seeded again. The interval doubles on every check up
+// to ENTROPY_POLL_MAX_INTERVAL.
+const ENTROPY_POLL_INTERVAL: i64 = 10 * 1000 * 1000; // 10 ms
+const ENTROPY_POLL_MAX_INTERVAL: i64 = 1000 * 1000 * 1000; // 1 s
+
+// ENTROPY_SEEDED is set once the host's CSPRNG has been seen seeded. The pool
+// never becomes unseeded again, so later checks don't need the host.
+static ENTROPY_SEEDED: AtomicBool = AtomicBool::new(false);
+
+// EntropySeeded returns whether the host's CSPRNG is seeded, i.e. whether a
+// /dev/random read can return without waiting. As on Linux 5.6 and later,
+// /dev/random never blocks after that.
+fn EntropySeeded() -> bool {
+    if ENTROPY_SEEDED.load(Ordering::Acquire) {
+        return true
+    }
+
+    let buf: [u8; 1] = [0; 1];
+    let res = Kernel::HostSpace::GetRandom(&buf[0] as *const _ as u64, buf.len() as u64, _GRND_NONBLOCK as u32);
+    if res < 0 {
+        return false
+    }
+
+    ENTROPY_SEEDED.store(true, Ordering::Release);
+    return true
+}
+
+pub struct RandomDevice {
+    pub attr: QRwLock<InodeSimpleAttributesInternal>,
+
+    // blocking is true for /dev/random, whose reads wait for the CSPRNG to
+    // be seeded, and false for /dev/urandom, which never waits.
+    pub blocking: bool,
+}
 
 impl Default for RandomDevice {
     fn default() -> Self {
-        return Self(QRwLock::new(Default::default()))
+        return Self {
+            attr: QRwLock::new(Default::default()),
+            blocking: false,
+        }
     }
 }
 
@@ -55,14 +94,17 @@ impl Deref for RandomDevice {
     type Target = QRwLock<InodeSimpleAttributesInternal>;
 
     fn deref(&self) -> &QRwLock<InodeSimpleAttributesInternal> {
-        &self.0
+        &self.attr
     }
 }
 
 impl RandomDevice {
-    pub fn New(task: &Task, owner: &FileOwner, mode: &FileMode) -> Self {
+    pub fn New(task: &Task, owner: &FileOwner, mode: &FileMode, blocking: bool) -> Self {
         let attr = InodeSimpleAttributesInternal::New(task, owner, &FilePermissions::FromMode(*mode), FSMagic::TMPFS_MAGIC);
-        return Self(QRwLock::new(attr))
+        return Self {
+            attr: QRwLock::new(attr),
+            blocking: blocking,
+        }
     }
 }
 
@@ -136,7 +178,9 @@ impl InodeOperations for RandomDevice {
         flags.Pread = true;
         flags.PWrite = true;
 
-        let fops = RandomFileOperations {};
+        let fops = RandomFileOperations {
+            blocking: self.blocking,
+        };
 
         let f = FileInternal {
             UniqueId: NewUID(),
@@ -226,10 +270,16 @@ impl InodeOperations for RandomDevice {
     }
 }
 
-pub struct RandomFileOperations {}
+pub struct RandomFileOperations {
+    pub blocking: bool,
+}
 
 impl Waitable for RandomFileOperations {
     fn Readiness(&self, _task: &Task, mask: EventMask) -> EventMask {
+        if self.blocking && mask & EVENT_IN != 0 && !EntropySeeded() {
+            return mask & !EVENT_IN
+        }
+
         return mask;
     }
 
@@ -263,10 +313,33 @@ impl FileOperations for RandomFileOperations {
         return Err(Error::SysError(SysErr::ENOTDIR))
     }
 
-    fn ReadAt(&self, task: &Task, _f: &File, dsts: &mut [IoVec], _offset: i64, _blocking: bool) -> Result<i64> {
+    fn ReadAt(&self, task: &Task, _f: &File, dsts: &mut [IoVec], _offset: i64, blocking: bool) -> Result<i64> {
         let len = IoVec::NumBytes(dsts);
-        let buf = DataBuff::New(len);
+        if len == 0 {
+            return Ok(0)
+        }
+
+        if self.blocking {
+            // The host doesn't notify us when its pool is seeded, so a
+            // blocking read polls until it is.
+            let mut interval = ENTROPY_POLL_INTERVAL;
+            while !EntropySeeded() {
+                if !blocking {
+                    return Err(Error::SysError(SysErr::EAGAIN))
+                }
+
+                match task.blocker.BlockWithMonoTimeout(false, Some(interval)) {
+                    (_, Err(Error::SysError(SysErr::ETIMEDOUT))) => (),
+                    (_, Err(Error::ErrInterrupted)) => return Err(Error::SysError(SysErr::ERESTARTSYS)),
+                    (_, Err(e)) => return Err(e),
+                    (_, Ok(())) => (),
+                }
+
+                interval = core::cmp::min(interval * 2, ENTROPY_POLL_MAX_INTERVAL);
+            }
+        }
 
+        let mut buf = DataBuff::New(len);
         let mut ioReader = RandomReader {};
         let mut reader = FromIOReader {
             reader: &mut ioReader,
@@ -29,12 +29,14 @@ use super::super::qlib::common::*;
 use super::super::qlib::singleton::*;
 use super::super::task::*;
 use super::super::qlib::linux_def::*;
+use super::super::qlib::linux::inotify::*;
 use super::super::socket::unix::transport::unix::*;
 use super::inode::*;
 use super::flags::*;
 use super::file::*;
 use super::dentry::*;
 use super::mount::*;
+use super::inotify::*;
 
 pub static RENAME : Singleton<RwLock<()>> = Singleton::<RwLock<()>>::New();
 pub unsafe fn InitSingleton() {
@@ -112,6 +114,32 @@ impl Dirent {
         return (self.0).0.lock().Inode.clone();
     }
 
+    // InotifyEvent sends events to the watches on the dirent's parent and on
+    // the dirent itself. Like Linux, the parent is notified first.
+    pub fn InotifyEvent(&self, events: u32, cookie: u32) {
+        // Most writes happen with nothing watched at all.
+        if !HasWatches() {
+            return
+        }
+
+        let (inode, parent, name) = {
+            let d = (self.0).0.lock();
+            (d.Inode.clone(), d.Parent.clone(), d.Name.clone())
+        };
+
+        let mut events = events;
+        if inode.StableAttr().IsDir() {
+            events |= IN_ISDIR;
+        }
+
+        match parent {
+            None => (),
+            Some(p) => p.Inode().Watches().Notify(&name, events, cookie),
+        }
+
+        inode.Watches().Notify("", events, cookie);
+    }
+
     pub fn MyFullName(&self) -> String {
         let _a = RENAME.read();
 
@@ -391,10 +419,12 @@ impl Dirent {
         self.AddChild(&child);
         child.ExtendReference();
 
+        inode.Watches().Notify(name, IN_CREATE, 0);
+
         return Ok(file)
     }
 
-    fn genericCreate(&self, task: &Task, root: &Dirent, name: &str, create: &mut FnMut() -> Result<()>) -> Result<()> {
+    fn genericCreate(&self, task: &Task, root: &Dirent, name: &str, events: u32, create: &mut FnMut() -> Result<()>) -> Result<()> {
         let _a = RENAME.write();
 
         if self.exists(task, root, name) {
@@ -422,11 +452,14 @@ impl Dirent {
             (self.0).0.lock().Children.remove(name);
         }
 
-        return create()
+        create()?;
+
+        inode.Watches().Notify(name, events, 0);
+        return Ok(())
     }
 
     pub fn CreateLink(&self, task: &Task, root: &Dirent, oldname: &str, newname: &str) -> Result<()> {
-        return self.genericCreate(task, root, newname, &mut || -> Result<()> {
+        return self.genericCreate(task, root, newname, IN_CREATE, &mut || -> Result<()> {
             let mut inode = self.Inode();
             return inode.CreateLink(task, self, oldname, newname)
         });
@@ -443,13 +476,13 @@ impl Dirent {
             return Err(Error::SysError(SysErr::EPERM))
         }
 
-        return self.genericCreate(task, root, name, &mut || -> Result<()> {
+        return self.genericCreate(task, root, name, IN_CREATE, &mut || -> Result<()> {
             return inode.CreateHardLink(task, self, &target, name)
         });
     }
 
     pub fn CreateDirectory(&self, task: &Task, root: &Dirent, name: &str, perms: &FilePermissions) -> Result<()> {
-        return self.genericCreate(task, root, name, &mut || -> Result<()> {
+        return self.genericCreate(task, root, name, IN_CREATE | IN_ISDIR, &mut || -> Result<()> {
             let mut inode = self.Inode();
             let ret = inode.CreateDirectory(task, self, name, perms);
             return ret;
@@ -457,7 +490,7 @@ impl Dirent {
     }
 
     pub fn Bind(&self, task: &Task, root: &Dirent, name: &str, data: &BoundEndpoint, perms: &FilePermissions) -> Result<Dirent> {
-        let result = self.genericCreate(task, root, name, &mut || -> Result<()> {
+        let result = self.genericCreate(task, root, name, IN_CREATE, &mut || -> Result<()> {
             let inode = self.Inode();
             let childDir = inode.Bind(task, name, data, perms)?;
             self.AddChild(&childDir);
@@ -478,7 +511,7 @@ impl Dirent {
     }
 
     pub fn CreateFifo(&self, task: &Task, root: &Dirent, name: &str, perms: &FilePermissions) -> Result<()> {
-        return self.genericCreate(task, root, name, &mut || -> Result<()> {
+        return self.genericCreate(task, root, name, IN_CREATE, &mut || -> Result<()> {
             let mut inode = self.Inode();
             return inode.CreateFifo(task, self, name, perms)
         });
@@ -551,6 +584,36 @@ impl Dirent {
         }
     }
 
+    // CheckPermission is Inode::CheckPermission, but also honors the
+    // per-mount flags of the mount the dirent was reached through, which the
+    // inode, shared by every bind mount of its filesystem, doesn't know.
+    pub fn CheckPermission(&self, task: &Task, p: &PermMask) -> Result<()> {
+        let inode = self.Inode();
+        let flags = task.mountNS.MountFlags(self);
+        if p.write && flags.ReadOnly {
+            return Err(Error::SysError(SysErr::EROFS))
+        }
+
+        if p.execute && flags.NoExec && inode.StableAttr().IsFile() {
+            return Err(Error::SysError(SysErr::EACCES))
+        }
+
+        return inode.CheckPermission(task, p)
+    }
+
+    // CheckWritableMount returns EROFS if the dirent is on a read-only
+    // filesystem or was reached through a read-only mount. Attribute changes
+    // check it even when ownership alone allows them, as Linux's
+    // mnt_want_write() does.
+    pub fn CheckWritableMount(&self, task: &Task) -> Result<()> {
+        let readOnly = self.Inode().lock().MountSource.lock().Flags.ReadOnly;
+        if readOnly || task.mountNS.MountFlags(self).ReadOnly {
+            return Err(Error::SysError(SysErr::EROFS))
+        }
+
+        return Ok(())
+    }
+
     pub fn IsMountPoint(&self) -> bool {
         let d = (self.0).0.lock();
 
@@ -619,6 +682,8 @@ impl Dirent {
         (self.0).0.lock().Children.remove(name);
         child.DropExtendedReference();
 
+        inode.Watches().Notify(name, IN_DELETE, 0);
+
         return Ok(())
     }
 
@@ -653,6 +718,8 @@ impl Dirent {
 
         child.DropExtendedReference();
 
+        inode.Watches().Notify(name, IN_DELETE | IN_ISDIR, 0);
+
         return Ok(())
     }
 
@@ -695,8 +762,8 @@ impl Dirent {
             return Err(Error::SysError(SysErr::ENOENT))
         }
 
-        oldInode.CheckPermission(task, &PermMask { write: true, execute: true, read: false })?;
-        newInode.CheckPermission(task, &PermMask { write: true, execute: true, read: false })?;
+        oldParent.CheckPermission(task, &PermMask { write: true, execute: true, read: false })?;
+        newParent.CheckPermission(task, &PermMask { write: true, execute: true, read: false })?;
 
         let renamed = oldParent.walk(task, root, oldName)?;
         oldParent.mayDelete(task, &renamed)?;
@@ -759,6 +826,8 @@ impl Dirent {
         renamed.DropExtendedReference();
         renamed.flush();
 
+        notifyRename(oldParent, oldName, newParent, newName, &renamed);
+
         return Ok(())
     }
 
@@ -769,7 +838,7 @@ impl Dirent {
             return Err(Error::SysError(SysErr::ENOENT))
         }
 
-        inode.CheckPermission(task, &PermMask { write: true, execute: true, read: false })?;
+        parent.CheckPermission(task, &PermMask { write: true, execute: true, read: false })?;
 
         let renamed = parent.walk(task, root, oldName)?;
 
@@ -822,20 +891,22 @@ impl Dirent {
 
         (renamed.0).0.lock().Name = newName.to_string();
 
-        let mut p = (parent.0).0.lock();
-        p.Children.remove(oldName);
-        p.Children.insert(newName.to_string(), Arc::downgrade(&renamed.0));
+        {
+            let mut p = (parent.0).0.lock();
+            p.Children.remove(oldName);
+            p.Children.insert(newName.to_string(), Arc::downgrade(&renamed.0));
+        }
 
         renamed.DropExtendedReference();
         renamed.flush();
 
+        notifyRename(parent, oldName, parent, newName, &renamed);
+
         return Ok(())
     }
 
     pub fn MayDelete(&self, task: &Task, root: &Dirent, name: &str) -> Result<()> {
-        let inode = self.Inode();
-
-        inode.CheckPermission(task, &PermMask { write: true, execute: true, ..Default::default() })?;
+        self.CheckPermission(task, &PermMask { write: true, execute: true, ..Default::default() })?;
 
         let victim = self.Walk(task, root, name)?;
 
@@ -905,6 +976,22 @@ impl Dirent {
     }
 }
 
+// notifyRename sends the inotify events of a rename, tied together by a
+// cookie.
+fn notifyRename(oldParent: &Dirent, oldName: &str, newParent: &Dirent, newName: &str, renamed: &Dirent) {
+    let inode = renamed.Inode();
+    let dirEvent = if inode.StableAttr().IsDir() {
+        IN_ISDIR
+    } else {
+        0
+    };
+
+    let cookie = NewInotifyCookie();
+    oldParent.Inode().Watches().Notify(oldName, IN_MOVED_FROM | dirEvent, cookie);
+    newParent.Inode().Watches().Notify(newName, IN_MOVED_TO | dirEvent, cookie);
+    inode.Watches().Notify("", IN_MOVE_SELF, 0);
+}
+
 pub fn DirentReadDir(task: &Task, d: &Dirent, it: &FileOperations, root: &Dirent, dirCtx: &mut DirCtx, offset: i64) -> Result<i64> {
     let (offset, err) = direntReadDir(task, d, it, root, dirCtx, offset);
 
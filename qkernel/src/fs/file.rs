@@ -30,7 +30,11 @@ use super::super::qlib::auth::*;
 use super::super::qlib::range::*;
 use super::super::kernel::waiter::*;
 use super::super::qlib::linux_def::*;
-//use super::super::socket::unix::transport::unix::*;
+use super::super::qlib::linux::inotify::*;
+use super::super::qlib::limits::*;
+use super::super::qlib::mem::block::*;
+use super::super::socket::hostinet::socket::NewHostSocketFile;
+use super::super::socket::unix::unix::NewUnixSocketFromHostFd;
 use super::super::task::*;
 use super::super::memmgr::*;
 use super::super::fs::flags::*;
@@ -38,12 +42,16 @@ use super::super::fs::host::hostfileop::*;
 use super::super::tcpip::tcpip::*;
 use super::super::kernel::fasync::*;
 use super::super::qlib::singleton::*;
+use super::super::IOURING;
+use super::super::Kernel;
+use super::super::Kernel::HostSpace;
 
 use super::attr::*;
 use super::dirent::*;
 //use super::flags::*;
 use super::dentry::*;
 use super::inode::*;
+use super::lock::*;
 use super::mount::*;
 use super::filesystems::*;
 use super::host::fs::*;
@@ -88,6 +96,10 @@ pub struct SpliceOpts {
     // DstStart is the start of the destination file. This is used only if
     // DstOffset is false.
     pub DstStart: i64,
+
+    // NonBlocking indicates that the splice must not wait for either file,
+    // as with SPLICE_F_NONBLOCK, whatever the files' own O_NONBLOCK flags.
+    pub NonBlocking: bool,
 }
 
 pub const FILE_MAX_OFFSET: i64 = core::i64::MAX;
@@ -221,8 +233,12 @@ pub enum FileOpsType {
     SocketOperations,
     UnixSocketOperations,
     ReadonlyFileOperations,
+    ReadWriteFileOperations,
     DynamicDirFileOperations,
     SignalOperation,
+    Inotify,
+    MemfdFileOperations,
+    PidFdFileOperations,
 }
 
 pub trait FileOperations: Sync + Send + Waitable + SockOperations + SpliceOperations {
@@ -259,6 +275,12 @@ pub trait FileOperations: Sync + Send + Waitable + SockOperations + SpliceOperat
     fn IterateDir(&self, task: &Task,d: &Dirent, dirCtx: &mut DirCtx, offset: i32) -> (i32, Result<i64>);
 
     fn Mappable(&self) -> Result<HostInodeOp>;
+
+    // Truncate changes the size of the file for ftruncate(2). Files without a
+    // size of their own can't be truncated.
+    fn Truncate(&self, _task: &Task, _f: &File, _size: i64) -> Result<()> {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
 }
 
 pub struct FileInternal {
@@ -305,9 +327,9 @@ impl Drop for File {
             let inode = self.Dirent.Inode();
             let lockCtx = inode.lock().LockCtx.clone();
             let task = Task::Current();
-            let lockUniqueID = task.fdTbl.ID();
-            lockCtx.BSD.UnlockRegion(task, lockUniqueID, &Range::Max());
-            lockCtx.Posix.UnlockRegion(task, lockUniqueID, &Range::Max());
+            // BSD locks are owned by the File, see Flock.
+            lockCtx.BSD.UnlockRegion(task, self.UniqueId, &Range::Max());
+            lockCtx.Posix.UnlockRegion(task, task.fdTbl.ID(), &Range::Max());
 
             // Only unregister if we are currently registered. There is nothing
             // to register if f.async is nil (this happens when async mode is
@@ -319,6 +341,15 @@ impl Drop for File {
             }
 
             f.1 = None;
+            let write = f.0.Write;
+            core::mem::drop(f);
+
+            let ev = if write {
+                IN_CLOSE_WRITE
+            } else {
+                IN_CLOSE_NOWRITE
+            };
+            self.Dirent.InotifyEvent(ev, 0);
         }
     }
 }
@@ -461,9 +492,12 @@ impl File {
                     return Err(Error::Common("cannot import host socket as TTY".to_string()))
                 }
 
-                panic!("NewFileFromFd: not support socket fd");
-                //let s = NewUnixSocket(task, fd, fileFlags.NonBlocking)?;
-                //return Ok(s)
+                let family = Kernel::GetSockOptI32(fd, LibcConst::SOL_SOCKET as i32, LibcConst::SO_DOMAIN as i32)?;
+                if family == AFType::AF_UNIX {
+                    return NewUnixSocketFromHostFd(task, fd, fileFlags.NonBlocking)
+                }
+
+                return NewHostSocketFile(task, fd, fileFlags.NonBlocking)
             }
 
             _ => {
@@ -520,6 +554,61 @@ impl File {
         return self.flags.lock().0;
     }
 
+    // Flock takes, converts or releases a whole-file BSD lock on f as in
+    // flock(2).
+    pub fn Flock(&self, task: &Task, operation: i32) -> Result<()> {
+        let nonblocking = operation & LibcConst::LOCK_NB as i32 != 0;
+        let operation = operation & !(LibcConst::LOCK_NB as i32);
+
+        // flock(2):
+        // Locks created by flock() are associated with an open file table entry. This means that
+        // duplicate file descriptors (created by, for example, fork(2) or dup(2)) refer to the
+        // same lock, and this lock may be modified or released using any of these descriptors. Furthermore,
+        // the lock is released either by an explicit LOCK_UN operation on any of these duplicate
+        // descriptors, or when all such descriptors have been closed.
+        //
+        // If a process uses open(2) (or similar) to obtain more than one descriptor for the same file,
+        // these descriptors are treated independently by flock(). An attempt to lock the file using
+        // one of these file descriptors may be denied by a lock that the calling process has already placed via
+        // another descriptor.
+        //
+        // We use the File UniqueID as the lock UniqueID because it needs to reference the same lock across dup(2)
+        // and fork(2).
+        let lockUniqueId = self.UniqueId();
+
+        let rng = Range::New(0, MAX_RANGE);
+        let inode = self.Dirent.Inode();
+        let bsd = inode.lock().LockCtx.BSD.clone();
+
+        let t = match operation as u64 {
+            LibcConst::LOCK_EX => LockType::WriteLock,
+            LibcConst::LOCK_SH => LockType::ReadLock,
+            LibcConst::LOCK_UN => {
+                bsd.UnlockRegion(task, lockUniqueId, &rng);
+                return Ok(())
+            }
+            _ => {
+                // flock(2): EINVAL operation is invalid.
+                return Err(Error::SysError(SysErr::EINVAL))
+            }
+        };
+
+        // A lock already held through this File is converted in place:
+        // shared to exclusive if it's the only reader, and exclusive to
+        // shared.
+        if !bsd.LockRegion(task, lockUniqueId, t, &rng, !nonblocking)? {
+            if nonblocking {
+                return Err(Error::SysError(SysErr::EWOULDBLOCK))
+            }
+
+            // Interrupted: restarted unless the signal handler lacks
+            // SA_RESTART, as in Linux's locks_lock_inode_wait().
+            return Err(Error::SysError(SysErr::ERESTARTSYS))
+        }
+
+        return Ok(())
+    }
+
     pub fn SetFlags(&self, task: &Task, newFlags: SettableFileFlags) {
         let mut f = self.flags.lock();
         f.0.Direct = newFlags.Direct;
@@ -566,6 +655,10 @@ impl File {
     }
 
     pub fn Readv(&self, task: &Task, dsts: &mut [IoVec]) -> Result<i64> {
+        if self.Flags().Path {
+            return Err(Error::SysError(SysErr::EBADF))
+        }
+
         let fops = self.FileOp.clone();
         let seekable = fops.Seekable();
 
@@ -593,6 +686,10 @@ impl File {
     }
 
     pub fn Preadv(&self, task: &Task, dsts: &mut [IoVec], offset: i64) -> Result<i64> {
+        if self.Flags().Path {
+            return Err(Error::SysError(SysErr::EBADF))
+        }
+
         let fops = self.FileOp.clone();
         let blocking = self.Blocking();
         let n = fops.ReadAt(task, self, dsts, offset, blocking)?;
@@ -612,12 +709,27 @@ impl File {
     // checkLimit checks the offset that the write will be performed at. The
     // returned boolean indicates that the write must be limited. The returned
     // integer indicates the new maximum write length.
-    pub fn checkLimit(&self, _offset: i64) -> (i64, bool) {
-        //todo: implement this
+    pub fn checkLimit(&self, task: &Task, offset: i64) -> (i64, bool) {
+        if self.Dirent.Inode().StableAttr().IsRegular() {
+            // Enforce size limit.
+            let fileSizeLimit = task.Thread().ThreadGroup().Limits().Get(LimitType::FileSize).Cur;
+            if fileSizeLimit <= core::i64::MAX as u64 {
+                if offset >= fileSizeLimit as i64 {
+                    return (0, true)
+                }
+
+                return (fileSizeLimit as i64 - offset, true)
+            }
+        }
+
         return (0, false)
     }
 
     pub fn Writev(&self, task: &Task, srcs: &[IoVec]) -> Result<i64> {
+        if self.Flags().Path {
+            return Err(Error::SysError(SysErr::EBADF))
+        }
+
         let fops = self.FileOp.clone();
         let seekable = fops.Seekable();
 
@@ -629,22 +741,34 @@ impl File {
             if self.flags.lock().0.Append {
                 let (cnt, len) = fops.Append(task, self, srcs)?;
                 *offsetLock = len;
+                self.syncWrite(task, len - cnt, cnt)?;
                 return Ok(cnt)
             }
 
             let current = *offsetLock;
 
-            let (limit, ok) = self.checkLimit(current);
+            let (limit, ok) = self.checkLimit(task, current);
             if ok && limit == 0 {
                 return Err(Error::ErrExceedsFileSizeLimit);
             }
 
+            // Write up to the limit; the count may end in the middle of an
+            // iovec.
+            let limited;
+            let srcs = if ok && (limit as usize) < Iovs(srcs).Count() {
+                limited = Iovs(srcs).TakeFirst(limit as usize);
+                &limited[..]
+            } else {
+                srcs
+            };
+
             let blocking = self.Blocking();
             let n = fops.WriteAt(task, self, srcs, current, blocking)?;
             if n > 0 {
                 *offsetLock = current + n;
             }
 
+            self.syncWrite(task, current, n)?;
             return Ok(n)
         } else {
             let blocking = self.Blocking();
@@ -655,6 +779,10 @@ impl File {
     }
 
     pub fn Pwritev(&self, task: &Task, srcs: &[IoVec], offset: i64) -> Result<i64> {
+        if self.Flags().Path {
+            return Err(Error::SysError(SysErr::EBADF))
+        }
+
         let fops = self.FileOp.clone();
 
         /*
@@ -670,17 +798,44 @@ impl File {
             return Ok(cnt)
         }*/
 
-        let (limit, ok) = self.checkLimit(offset);
+        let (limit, ok) = self.checkLimit(task, offset);
         if ok && limit == 0 {
             return Err(Error::ErrExceedsFileSizeLimit);
         }
 
+        let limited;
+        let srcs = if ok && (limit as usize) < Iovs(srcs).Count() {
+            limited = Iovs(srcs).TakeFirst(limit as usize);
+            &limited[..]
+        } else {
+            srcs
+        };
+
         let blocking = self.Blocking();
         let n = fops.WriteAt(task, self, srcs, offset, blocking)?;
 
+        self.syncWrite(task, offset, n)?;
         return Ok(n)
     }
 
+    // syncWrite makes the n bytes just written at offset durable when the file
+    // was opened with O_SYNC (data and metadata) or O_DSYNC (data only), as
+    // generic_write_sync does in Linux.
+    fn syncWrite(&self, task: &Task, offset: i64, n: i64) -> Result<()> {
+        let flags = self.Flags();
+        if n <= 0 || !flags.DSync {
+            return Ok(())
+        }
+
+        let syncType = if flags.Sync {
+            SyncType::SyncAll
+        } else {
+            SyncType::SyncData
+        };
+
+        return self.Fsync(task, offset, offset + n - 1, syncType)
+    }
+
     pub fn Fsync(&self, task: &Task, start: i64, end: i64, syncType: SyncType) -> Result<()> {
         let fops = self.FileOp.clone();
         return fops.Fsync(task, self, start, end, syncType);
@@ -703,7 +858,113 @@ impl File {
         return fops.UnstableAttr(task, self);
     }
 
+    // Readahead starts reading [offset, offset+count) into the host page cache
+    // without waiting for it. A count of 0 reads ahead to the end of the file.
+    // It does nothing for files the host doesn't cache.
+    pub fn Readahead(&self, _task: &Task, offset: i64, count: i64) -> Result<()> {
+        if !self.FileOp.Seekable() {
+            return Err(Error::SysError(SysErr::EINVAL))
+        }
+
+        if let Ok(iops) = self.FileOp.Mappable() {
+            IOURING.Readahead(iops.HostFd(), offset, count);
+        }
+
+        return Ok(())
+    }
+
+    // Fadvise implements posix_fadvise(2). The advice is passed on to the host
+    // page cache of host backed files and ignored for the others.
+    pub fn Fadvise(&self, _task: &Task, offset: i64, len: i64, advice: i32) -> Result<()> {
+        if len < 0 {
+            return Err(Error::SysError(SysErr::EINVAL))
+        }
+
+        let inode = self.Dirent.Inode();
+        if inode.StableAttr().IsPipe() {
+            return Err(Error::SysError(SysErr::ESPIPE))
+        }
+
+        match advice {
+            FAdviseOp::POSIX_FADV_NORMAL |
+            FAdviseOp::POSIX_FADV_RANDOM |
+            FAdviseOp::POSIX_FADV_SEQUENTIAL |
+            FAdviseOp::POSIX_FADV_WILLNEED |
+            FAdviseOp::POSIX_FADV_DONTNEED |
+            FAdviseOp::POSIX_FADV_NOREUSE => (),
+            _ => return Err(Error::SysError(SysErr::EINVAL))
+        }
+
+        let iops = match self.FileOp.Mappable() {
+            Ok(iops) => iops,
+            Err(_) => return Ok(())
+        };
+
+        let fd = iops.HostFd();
+        match advice {
+            // Prefetch without waiting for the host to read the range.
+            FAdviseOp::POSIX_FADV_WILLNEED => {
+                IOURING.Readahead(fd, offset, len);
+            }
+            // NORMAL, RANDOM and SEQUENTIAL set the host readahead window and
+            // DONTNEED drops the clean host pages in the range. The advice is
+            // only a hint, so a host failure isn't reported.
+            FAdviseOp::POSIX_FADV_NORMAL |
+            FAdviseOp::POSIX_FADV_RANDOM |
+            FAdviseOp::POSIX_FADV_SEQUENTIAL |
+            FAdviseOp::POSIX_FADV_DONTNEED => {
+                HostSpace::Fadvise(fd, offset as u64, len as u64, advice);
+            }
+            // The host ignores NOREUSE.
+            _ => (),
+        }
+
+        return Ok(())
+    }
+
+    // Truncate implements ftruncate(2). Only regular files opened for writing
+    // can be truncated and the new size is bounded by RLIMIT_FSIZE.
+    pub fn Truncate(&self, task: &Task, size: i64) -> Result<()> {
+        if self.Flags().Path {
+            return Err(Error::SysError(SysErr::EBADF))
+        }
+
+        if !self.Flags().Write || size < 0 {
+            return Err(Error::SysError(SysErr::EINVAL))
+        }
+
+        let inode = self.Dirent.Inode();
+        if !inode.StableAttr().IsFile() {
+            return Err(Error::SysError(SysErr::EINVAL))
+        }
+
+        let fileSizeLimit = task.Thread().ThreadGroup().Limits().Get(LimitType::FileSize).Cur;
+        if fileSizeLimit <= core::i64::MAX as u64 && size > fileSizeLimit as i64 {
+            return Err(Error::ErrExceedsFileSizeLimit)
+        }
+
+        let fops = self.FileOp.clone();
+        return fops.Truncate(task, self, size)
+    }
+
     pub fn Ioctl(&self, task: &Task, fd: i32, request: u64, val: u64) -> Result<()> {
+        // FIONBIO and FIOASYNC apply to every file, like F_SETFL.
+        match request {
+            IoCtlCmd::FIONBIO | IoCtlCmd::FIOASYNC => {
+                let set: i32 = task.CopyInObj(val)?;
+                let mut flags = self.Flags().SettableFileFlags();
+                if request == IoCtlCmd::FIONBIO {
+                    flags.NonBlocking = set != 0;
+                } else {
+                    flags.Async = set != 0;
+                }
+
+                self.SetFlags(task, flags);
+                return Ok(())
+            }
+            _ => (),
+        }
+
         let fops = self.FileOp.clone();
         let res = fops.Ioctl(task, self, fd, request, val);
         return res;
@@ -251,6 +251,13 @@ impl FileOperations for OverlayFileOperations {
         let ops = self.FileOps();
         return ops.Mappable();
     }
+
+    // The overlay inode copies the file up and keeps its cached attributes
+    // in sync with the upper file.
+    fn Truncate(&self, task: &Task, f: &File, size: i64) -> Result<()> {
+        let mut inode = f.Dirent.Inode();
+        return inode.Truncate(task, &f.Dirent, size)
+    }
 }
 
 impl SockOperations for OverlayFileOperations {}
@@ -35,6 +35,17 @@ pub struct FileFlags {
 
 impl FileFlags {
     pub fn FromFlags(mask: u32) -> Self {
+        // An O_PATH file is only a reference to a location in the filesystem:
+        // it can't be read or written and all other flags but O_DIRECTORY
+        // are ignored.
+        if mask & Flags::O_PATH as u32 != 0 {
+            return Self {
+                Directory: mask & Flags::O_DIRECTORY as u32 != 0,
+                Path: true,
+                ..Default::default()
+            }
+        }
+
         return Self {
             Direct: mask & Flags::O_DIRECT as u32 != 0,
             DSync: mask & (Flags::O_DSYNC | Flags::O_SYNC) as u32 != 0,
@@ -115,6 +126,10 @@ impl FileFlags {
             mask |= Flags::O_TRUNC;
         }
 
+        if self.Path {
+            mask |= Flags::O_PATH;
+        }
+
         if self.Read && self.Write {
             mask |= Flags::O_RDWR;
         } else if self.Write {
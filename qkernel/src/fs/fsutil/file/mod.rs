@@ -17,6 +17,7 @@ pub mod static_file;
 pub mod static_dir_file_operations;
 pub mod fileopsutil;
 pub mod readonly_file;
+pub mod readwrite_file;
 pub mod dynamic_dir_file_operations;
 
 pub use self::static_file::*;
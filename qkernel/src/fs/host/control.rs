@@ -69,13 +69,14 @@ fn FdsToFiles(task: &Task, fds: &[i32]) -> Vec<File> {
     let mut files = Vec::new();
 
     for fd in fds {
-        let mut fileFlags : i32 = 0;
-        let ret = HostSpace::Fcntl(*fd, Cmd::F_GETFL, &mut fileFlags as * mut _ as u64);
+        let ret = HostSpace::Fcntl(*fd, Cmd::F_GETFL, 0);
         if ret < 0 {
             info!("Error retrieving host FD flags: {}", -ret);
             break;
         }
 
+        let fileFlags = ret as i32;
+
         let file = match File::NewFileFromFd(task, *fd, &task.FileOwner(), false) {
             Err(e) => {
                 info!("Error creating file from host FD: {:?}", e);
@@ -86,7 +87,7 @@ fn FdsToFiles(task: &Task, fds: &[i32]) -> Vec<File> {
 
         // Set known flags.
         file.SetFlags(task, SettableFileFlags {
-            NonBlocking: fileFlags & Flags::O_NONBLOCK != Flags::O_NONBLOCK,
+            NonBlocking: fileFlags & Flags::O_NONBLOCK == Flags::O_NONBLOCK,
             ..Default::default()
         });
 
@@ -333,6 +333,11 @@ impl FileOperations for HostFileOp {
     fn Mappable(&self) -> Result<HostInodeOp> {
         return self.InodeOp.Mappable();
     }
+
+    fn Truncate(&self, task: &Task, f: &File, size: i64) -> Result<()> {
+        let mut inode = f.Dirent.Inode();
+        return self.InodeOp.Truncate(task, &mut inode, size)
+    }
 }
 
 impl SockOperations for HostFileOp {}
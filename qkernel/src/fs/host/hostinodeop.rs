@@ -20,6 +20,7 @@ use alloc::string::String;
 use core::any::Any;
 use core::ops::Deref;
 use alloc::vec::Vec;
+use alloc::collections::btree_set::BTreeSet;
 
 use socket::unix::transport::unix::BoundEndpoint;
 use super::super::super::guestfdnotifier::*;
@@ -39,6 +40,7 @@ use super::super::super::Kernel::HostSpace;
 use super::super::super::IOURING;
 use super::super::super::memmgr::*;
 use super::super::super::SHARESPACE;
+use super::super::super::quring::fixed_buf::*;
 use super::super::super::fd::*;
 use super::super::attr::*;
 use super::*;
@@ -62,6 +64,10 @@ pub struct MappableInternal {
 
     // file offset to ref count mapping
     pub chunkrefs: BTreeMap<u64, i32>,
+
+    // dirtyChunks holds the file offsets of the chunks which have been mapped
+    // shared and writable, and so may need a write back when unmapped.
+    pub dirtyChunks: BTreeSet<u64>,
 }
 
 impl MappableInternal {
@@ -87,6 +93,15 @@ impl MappableInternal {
         }
     }
 
+    // MarkDirty records that the chunks of fr are mapped shared and writable.
+    pub fn MarkDirty(&mut self, fr: &Range) {
+        let mut chunkStart = fr.Start() & !CHUNK_MASK;
+        while chunkStart < fr.End() {
+            self.dirtyChunks.insert(chunkStart);
+            chunkStart += CHUNK_SIZE;
+        }
+    }
+
     pub fn DecrRefOn(&mut self, fr: &Range) {
         let mut chunkStart = fr.Start() & !CHUNK_MASK;
         while chunkStart < fr.End() {
@@ -108,6 +123,16 @@ impl MappableInternal {
                     Some(offset) => *offset,
                 };
 
+                // Write back a chunk which was mapped shared and writable
+                // before its last mapping goes away, so that munmap of a
+                // shared file mapping flushes it like msync.
+                if self.dirtyChunks.remove(&chunkStart) {
+                    let ret = HostSpace::MSync(phyAddr, CHUNK_SIZE as usize, MSyncType::MsSync.MSyncFlags());
+                    if ret < 0 {
+                        info!("Mappable::DecrRefOn msync of chunk {:x} fail with error {}", chunkStart, -ret);
+                    }
+                }
+
                 HostSpace::MUnmap(phyAddr, CHUNK_SIZE);
 
                 /*error!("DecrRefOn 1 {:x}/{:x}", phyAddr,  phyAddr + CHUNK_SIZE);
@@ -145,6 +170,7 @@ impl Default for MappableInternal {
             f2pmap: BTreeMap::new(),
             mapping: AreaSet::New(0, core::u64::MAX),
             chunkrefs: BTreeMap::new(),
+            dirtyChunks: BTreeSet::new(),
         }
     }
 }
@@ -547,6 +573,18 @@ impl HostInodeOp {
         return self.lock().HostFd
     }
 
+    // HasWriteableMapping returns whether the file is mapped shared and
+    // writable by any address space.
+    pub fn HasWriteableMapping(&self) -> bool {
+        let mappable = match self.lock().mappable.clone() {
+            None => return false,
+            Some(m) => m,
+        };
+
+        let ret = mappable.lock().mapping.HasWriteableMapping();
+        return ret
+    }
+
     pub fn UpdateMaxLen(&self, size: i64) {
         let mut h = self.lock();
         if h.size < size {
@@ -615,12 +653,11 @@ impl HostInodeOp {
         let hostIops = self.clone();
 
         let size = IoVec::NumBytes(dsts);
-        let buf = DataBuff::New(size);
-
-        let iovs = buf.Iovs();
         let inodeType = self.InodeType();
 
         if inodeType != InodeType::RegularFile && inodeType != InodeType::CharacterDevice {
+            let buf = DataBuff::New(size);
+            let iovs = buf.Iovs();
             let ret = IORead(hostIops.HostFd(), &iovs)?;
             task.CopyDataOutToIovs(&buf.buf[0..ret as usize], dsts)?;
             return Ok(ret as i64)
@@ -648,18 +685,35 @@ impl HostInodeOp {
                     self.BufWriteLock().Lock(task);
                 }
 
-                let ret = IOURING.Read(task,
-                                       hostIops.HostFd(),
-                                       buf.Ptr(),
-                                       buf.Len() as u32,
-                                       offset as i64);
+                // The host reads straight into a fixed buffer, which is then
+                // copied out to the task.
+                let ret = match FixedBuf::Alloc(size) {
+                    Some(fixedBuf) => {
+                        let ret = IOURING.ReadFixed(task, hostIops.HostFd(), &fixedBuf, offset as i64);
+                        if ret >= 0 {
+                            task.CopyDataOutToIovs(&fixedBuf.Buf()[0..ret as usize], dsts)?;
+                        }
+                        ret
+                    }
+                    None => {
+                        let buf = DataBuff::New(size);
+                        let ret = IOURING.Read(task,
+                                               hostIops.HostFd(),
+                                               buf.Ptr(),
+                                               buf.Len() as u32,
+                                               offset as i64);
+                        if ret >= 0 {
+                            task.CopyDataOutToIovs(&buf.buf[0..ret as usize], dsts)?;
+                        }
+                        ret
+                    }
+                };
 
                 if ret < 0 {
                     if ret as i32 != -SysErr::EINVAL {
                         return Err(Error::SysError(-ret as i32))
                     }
                 } else if ret >= 0 {
-                    task.CopyDataOutToIovs(&buf.buf[0..ret as usize], dsts)?;
                     return Ok(ret as i64)
                 }
 
@@ -668,6 +722,8 @@ impl HostInodeOp {
                 // todo: handle tmp file elegant
             }
 
+            let buf = DataBuff::New(size);
+            let iovs = buf.Iovs();
             let offset = if inodeType == InodeType::CharacterDevice {
                 let ret = IOTTYRead(hostIops.HostFd(), &iovs)?;
                 task.CopyDataOutToIovs(&buf.buf[0..ret as usize], dsts)?;
@@ -690,13 +746,12 @@ impl HostInodeOp {
         let hostIops = self.clone();
 
         let size = IoVec::NumBytes(srcs);
-        let mut buf = DataBuff::New(size);
-        let iovs = buf.Iovs();
-
-        task.CopyDataInFromIovs(&mut buf.buf, srcs)?;
         let inodeType = self.InodeType();
 
         if inodeType != InodeType::RegularFile && inodeType != InodeType::CharacterDevice {
+            let mut buf = DataBuff::New(size);
+            let iovs = buf.Iovs();
+            task.CopyDataInFromIovs(&mut buf.buf, srcs)?;
             let ret = IOWrite(hostIops.HostFd(), &iovs)?;
             return Ok(ret as i64)
         } else {
@@ -709,15 +764,29 @@ impl HostInodeOp {
             if SHARESPACE.config.read().TcpBuffIO {
                 let ret =
                     if self.BufWriteEnable() {
+                        let mut buf = DataBuff::New(size);
+                        task.CopyDataInFromIovs(&mut buf.buf, srcs)?;
                         let lock = self.BufWriteLock().Lock(task);
                         let count = IOURING.BufFileWrite(hostIops.HostFd(), buf, offset, lock);
                         count
                     } else {
-                        IOURING.Write(task,
-                                      hostIops.HostFd(),
-                                      buf.Ptr(),
-                                      buf.Len() as u32,
-                                      offset as i64)
+                        // The task's data is copied straight into a fixed
+                        // buffer, which the host writes from.
+                        match FixedBuf::Alloc(size) {
+                            Some(mut fixedBuf) => {
+                                task.CopyDataInFromIovs(fixedBuf.BufMut(), srcs)?;
+                                IOURING.WriteFixed(task, hostIops.HostFd(), &fixedBuf, offset as i64)
+                            }
+                            None => {
+                                let mut buf = DataBuff::New(size);
+                                task.CopyDataInFromIovs(&mut buf.buf, srcs)?;
+                                IOURING.Write(task,
+                                              hostIops.HostFd(),
+                                              buf.Ptr(),
+                                              buf.Len() as u32,
+                                              offset as i64)
+                            }
+                        }
                     };
 
                 if ret < 0 {
@@ -737,6 +806,9 @@ impl HostInodeOp {
                 // todo: handle tmp file elegant
             }
 
+            let mut buf = DataBuff::New(size);
+            let iovs = buf.Iovs();
+            task.CopyDataInFromIovs(&mut buf.buf, srcs)?;
             match IOWriteAt(hostIops.HostFd(), &iovs, offset as u64) {
                 Err(e) => return Err(e),
                 Ok(ret) => {
@@ -833,6 +905,9 @@ impl HostInodeOp {
 
         mappableLock.mapping.AddMapping(ms, ar, offset, writeable);
         mappableLock.IncrRefOn(&Range::New(offset, ar.Len()));
+        if writeable {
+            mappableLock.MarkDirty(&Range::New(offset, ar.Len()));
+        }
         return Ok(())
     }
 
@@ -864,12 +939,19 @@ impl HostInodeOp {
         return self.lock().MapFilePage(task, fileOffset)
     }
 
-    pub fn MSync(&self, fr: &Range, msyncType: MSyncType) -> Result<()> {
+    // MSync writes back the file range through the host mappings of its
+    // chunks. Chunks which are not mapped have nothing to write back.
+    pub fn MSync(&self, fr: &Range, msyncType: MSyncType, invalidate: bool) -> Result<()> {
+        let mut flags = msyncType.MSyncFlags();
+        if invalidate {
+            flags |= MSyncType::MsInvalidate.MSyncFlags();
+        }
+
         let ranges = self.GetPhyRanges(fr);
         for r in &ranges {
-            let ret = HostSpace::MSync(r.Start(), r.Len() as usize, msyncType.MSyncFlags());
+            let ret = HostSpace::MSync(r.Start(), r.Len() as usize, flags);
             if ret < 0 {
-                return Err(Error::SysError(ret as i32))
+                return Err(Error::SysError(-ret as i32))
             }
         }
 
@@ -936,12 +1018,12 @@ impl HostInodeOp {
                         0
                     };
 
-                    let len = if fChunckRange.Contains(fr.End()) {
+                    let endOffset = if fChunckRange.Contains(fr.End()) {
                         fr.End() - fChunckRange.Start()
                     } else {
                         HUGE_PAGE_SIZE
                     };
-                    rs.push(Range::New(*phyAddr + startOffset, len));
+                    rs.push(Range::New(*phyAddr + startOffset, endOffset - startOffset));
                 }
             }
 
@@ -25,6 +25,7 @@ use super::super::super::task::*;
 use super::super::super::threadmgr::processgroup::*;
 use super::super::super::threadmgr::session::*;
 use super::super::super::socket::hostinet::socket_buf::*;
+use super::super::super::SignalDef::*;
 use super::super::super::SHARESPACE;
 use super::super::super::IOURING;
 
@@ -41,7 +42,7 @@ use super::ioctl::*;
 pub const NUM_CONTROL_CHARACTERS: usize = 19;
 pub const DISABLED_CHAR: u8 = 0;
 
-#[derive(Clone, Default, Copy)]
+#[derive(Clone, Default, Copy, PartialEq)]
 #[repr(C)]
 pub struct Winsize {
     pub Row: u16,
@@ -380,40 +381,6 @@ pub struct TTYFileOpsInternal {
     pub queue: Queue,
 }
 
-impl TTYFileOpsInternal {
-    fn checkChange(&self, _task: &Task, _sig: Signal) -> Result<()> {
-        return Ok(())
-        /*let thread = match &task.thread {
-            // No task? Linux does not have an analog for this case, but
-            // tty_check_change is more of a blacklist of cases than a
-            // whitelist, and is surprisingly permissive. Allowing the
-            // change seems most appropriate.
-            None => return Ok(()),
-            Some(ref t) => t.clone(),
-        };
-
-        let tg = thread.lock().tg.clone();
-        let pg = tg.ProcessGroup();
-
-        // If the session for the task is different than the session for the
-        // controlling TTY, then the change is allowed. Seems like a bad idea,
-        // but that's exactly what linux does.
-        if tg.Session() != self.fgProcessgroup.Session() {
-            return Ok(())
-        }
-
-        // If we are the foreground process group, then the change is allowed.
-        if pg == self.fgProcessgroup {
-            return Ok(())
-        }
-
-        // We are not the foreground process group.
-
-        // Is the provided signal blocked or ignored?
-        if (thread.SignalMask() & SignalSet::SignalSetOf(&sig).0 != 0 || tg.SignalHandlers())*/
-    }
-}
-
 #[derive(Clone)]
 pub struct TTYFileOps(Arc<QMutex<TTYFileOpsInternal>>);
 
@@ -425,6 +392,12 @@ impl Deref for TTYFileOps {
     }
 }
 
+impl PartialEq for TTYFileOps {
+    fn eq(&self, other: &Self) -> bool {
+        return Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
 pub const ENABLE_RINGBUF : bool = true;
 
 impl TTYFileOps {
@@ -461,6 +434,90 @@ impl TTYFileOps {
     pub fn ForegroundProcessGroup(&self) -> Option<ProcessGroup> {
         return self.lock().fgProcessgroup.clone();
     }
+
+    // checkChange checks that the task may access or change the terminal as
+    // job control allows, see drivers/tty/tty_jobctrl.c:__tty_check_change().
+    // It must be called without the tty lock: sending the signal takes the
+    // TaskSet lock, which is ordered before it.
+    fn checkChange(&self, task: &Task, sig: Signal) -> Result<()> {
+        let thread = match &task.thread {
+            // No task? Linux does not have an analog for this case, but
+            // tty_check_change is more of a blacklist of cases than a
+            // whitelist, and is surprisingly permissive. Allowing the
+            // change seems most appropriate.
+            None => return Ok(()),
+            Some(ref t) => t.clone(),
+        };
+
+        let tg = thread.ThreadGroup();
+        let pg = match tg.ProcessGroup() {
+            None => return Ok(()),
+            Some(pg) => pg,
+        };
+
+        let (session, fgProcessgroup) = {
+            let t = self.lock();
+            (t.session.clone(), t.fgProcessgroup.clone())
+        };
+
+        // If the session for the task is different than the session for the
+        // controlling TTY, then the change is allowed. Seems like a bad idea,
+        // but that's exactly what linux does.
+        if session.is_none() || tg.Session() != session {
+            return Ok(())
+        }
+
+        // If we are the foreground process group, then the change is allowed.
+        if Some(pg.clone()) == fgProcessgroup {
+            return Ok(())
+        }
+
+        // We are not the foreground process group.
+
+        // Is the provided signal blocked or ignored?
+        if thread.SignalMask().0 & SignalSet::New(sig).0 != 0 || tg.SignalHandlers().IsIgored(sig) {
+            // If the signal is SIGTTIN, then we are attempting to read
+            // from the TTY. Don't send the signal and return EIO.
+            if sig.0 == Signal::SIGTTIN {
+                return Err(Error::SysError(SysErr::EIO))
+            }
+
+            // Otherwise, we are writing or changing terminal state. This is allowed.
+            return Ok(())
+        }
+
+        // If the process group is an orphan, return EIO.
+        if pg.IsOrphan() {
+            return Err(Error::SysError(SysErr::EIO))
+        }
+
+        // Otherwise, send the signal to the process group and return ERESTARTSYS.
+        pg.SendSignal(&SignalInfo::SignalInfoPriv(sig)).ok();
+        return Err(Error::SysError(SysErr::ERESTARTSYS))
+    }
+
+    // SetWindowSize sets the window size of the host terminal. The host only
+    // signals its own foreground process group, so like Linux's
+    // tty_do_resize(), a change is signalled to ours with SIGWINCH here.
+    pub fn SetWindowSize(&self, w: &Winsize) -> Result<()> {
+        let fd = self.lock().fd;
+        let mut old = Winsize::default();
+        ioctlGetWinsize(fd, &mut old)?;
+        ioctlSetWinsize(fd, w)?;
+
+        if old == *w {
+            return Ok(())
+        }
+
+        match self.ForegroundProcessGroup() {
+            None => (),
+            Some(pg) => {
+                pg.SendSignal(&SignalInfo::SignalInfoPriv(Signal(Signal::SIGWINCH))).ok();
+            }
+        }
+
+        return Ok(())
+    }
 }
 
 impl Waitable for TTYFileOps {
@@ -520,7 +577,7 @@ impl FileOperations for TTYFileOps {
     }
 
     fn ReadAt(&self, task: &Task, f: &File, dsts: &mut [IoVec], offset: i64, blocking: bool) -> Result<i64> {
-        self.lock().checkChange(task, Signal(Signal::SIGTTIN))?;
+        self.checkChange(task, Signal(Signal::SIGTTIN))?;
 
         if SHARESPACE.config.read().TcpBuffIO  && ENABLE_RINGBUF{
             let size = IoVec::NumBytes(dsts);
@@ -544,11 +601,9 @@ impl FileOperations for TTYFileOps {
     }
 
     fn WriteAt(&self, task: &Task, f: &File, srcs: &[IoVec], offset: i64, blocking: bool) -> Result<i64> {
-        {
-            let t = self.lock();
-            if t.termios.LEnabled(LocalFlags::TOSTOP) {
-                t.checkChange(task, Signal(Signal::SIGTTOU))?;
-            }
+        let tostop = self.lock().termios.LEnabled(LocalFlags::TOSTOP);
+        if tostop {
+            self.checkChange(task, Signal(Signal::SIGTTOU))?;
         }
 
         if SHARESPACE.config.read().TcpBuffIO && ENABLE_RINGBUF {
@@ -569,11 +624,9 @@ impl FileOperations for TTYFileOps {
     }
 
     fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
-        {
-            let t = self.lock();
-            if t.termios.LEnabled(LocalFlags::TOSTOP) {
-                t.checkChange(task, Signal(Signal::SIGTTOU))?;
-            }
+        let tostop = self.lock().termios.LEnabled(LocalFlags::TOSTOP);
+        if tostop {
+            self.checkChange(task, Signal(Signal::SIGTTOU))?;
         }
 
         let fops = self.lock().fileOps.clone();
@@ -599,7 +652,7 @@ impl FileOperations for TTYFileOps {
         return res;
     }
 
-    fn Ioctl(&self, task: &Task, _f: &File, _fd: i32, request: u64, val: u64) -> Result<()> {
+    fn Ioctl(&self, task: &Task, f: &File, _fd: i32, request: u64, val: u64) -> Result<()> {
         let fops = self.lock().fileOps.clone();
         let fd = fops.as_any().downcast_ref::<HostFileOp>().expect("Ioctl: not Hostfilop")
             .InodeOp.as_any().downcast_ref::<HostInodeOp>().expect("Ioctl: not HostInodeOp").HostFd();
@@ -614,7 +667,7 @@ impl FileOperations for TTYFileOps {
             }
 
             IoCtlCmd::TCSETS | IoCtlCmd::TCSETSW | IoCtlCmd::TCSETSF => {
-                self.lock().checkChange(task, Signal(Signal::SIGTTOU))?;
+                self.checkChange(task, Signal(Signal::SIGTTOU))?;
 
                 let t: Termios = task.CopyInObj(val)?;
                 ioctlSetTermios(fd, ioctl, &t)?;
@@ -624,11 +677,19 @@ impl FileOperations for TTYFileOps {
             IoCtlCmd::TIOCGPGRP => {
                 let thread = task.Thread();
                 let tg = thread.ThreadGroup();
-                let pidns = tg.PIDNamespace();
 
-                let internal = self.lock();
+                // Only the controlling terminal reports its foreground
+                // process group.
+                if tg.TTY() != Some(self.clone()) {
+                    return Err(Error::SysError(SysErr::ENOTTY))
+                }
 
-                let pgid = pidns.IDOfProcessGroup(internal.fgProcessgroup.as_ref().unwrap());
+                let pidns = tg.PIDNamespace();
+                let fg = self.ForegroundProcessGroup();
+                let pgid = match fg {
+                    None => 0,
+                    Some(ref pg) => pidns.IDOfProcessGroup(pg),
+                };
                 info!("TIOCGPGRP pgid is {}, val is {:x}", pgid, val);
 
                 task.CopyOutObj(&pgid, val)?;
@@ -641,8 +702,14 @@ impl FileOperations for TTYFileOps {
                     Some(ref t) => t.clone(),
                 };
 
-                let mut t = self.lock();
-                match t.checkChange(task, Signal(Signal::SIGTTOU)) {
+                let tg = thread.ThreadGroup();
+                if tg.TTY() != Some(self.clone()) {
+                    return Err(Error::SysError(SysErr::ENOTTY))
+                }
+
+                let session = tg.Session();
+
+                match self.checkChange(task, Signal(Signal::SIGTTOU)) {
                     // drivers/tty/tty_io.c:tiocspgrp() converts -EIO from
                     // tty_check_change() to -ENOTTY.
                     Err(Error::SysError(SysErr::EIO)) => return Err(Error::SysError(SysErr::ENOTTY)),
@@ -650,11 +717,10 @@ impl FileOperations for TTYFileOps {
                     Ok(()) => (),
                 }
 
-                let tg = thread.ThreadGroup();
-                let session = tg.Session();
-                if session != t.session {
-                    return Err(Error::SysError(SysErr::ENOTTY));
-                }
+                let session = match session {
+                    Some(s) if Some(s.clone()) == self.lock().session => s,
+                    _ => return Err(Error::SysError(SysErr::ENOTTY)),
+                };
 
                 let pgid: i32 = task.CopyInObj(val)?;
                 if pgid < 0 {
@@ -668,13 +734,33 @@ impl FileOperations for TTYFileOps {
                 };
 
                 // Check that new process group is in the TTY session.
-                if pg.Session() != t.session.clone().unwrap() {
+                if pg.Session() != session {
                     return Err(Error::SysError(SysErr::EPERM))
                 }
 
+                // The tty may have been released by the session leader
+                // while it was unlocked.
+                let mut t = self.lock();
+                if t.session != Some(session) {
+                    return Err(Error::SysError(SysErr::ENOTTY))
+                }
+
                 t.fgProcessgroup = Some(pg);
                 return Ok(())
             }
+            IoCtlCmd::TIOCSCTTY => {
+                // "If this terminal is already the controlling terminal of
+                // a different session group, then the ioctl fails with EPERM,
+                // unless the caller has the CAP_SYS_ADMIN capability and arg
+                // equals 1." - tty_ioctl(4)
+                let steal = val == 1;
+                let tg = task.Thread().ThreadGroup();
+                return tg.SetControllingTTY(task, self, steal, f.Flags().Read)
+            }
+            IoCtlCmd::TIOCNOTTY => {
+                let tg = task.Thread().ThreadGroup();
+                return tg.ReleaseControllingTTY(self)
+            }
             IoCtlCmd::TIOCGWINSZ => {
                 let mut win = Winsize::default();
                 ioctlGetWinsize(fd, &mut win)?;
@@ -683,7 +769,7 @@ impl FileOperations for TTYFileOps {
             }
             IoCtlCmd::TIOCSWINSZ => {
                 let w: Winsize = task.CopyInObj(val)?;
-                return ioctlSetWinsize(fd, &w)
+                return self.SetWindowSize(&w)
             }
             IoCtlCmd::TIOCSETD |
             IoCtlCmd::TIOCSBRK |
@@ -696,8 +782,6 @@ impl FileOperations for TTYFileOps {
             IoCtlCmd::TIOCEXCL |
             IoCtlCmd::TIOCNXCL |
             IoCtlCmd::TIOCGEXCL |
-            IoCtlCmd::TIOCNOTTY |
-            IoCtlCmd::TIOCSCTTY |
             IoCtlCmd::TIOCGSID |
             IoCtlCmd::TIOCGETD |
             IoCtlCmd::TIOCVHANGUP |
@@ -39,6 +39,7 @@ use super::overlay::*;
 use super::host::hostinodeop::*;
 use super::inode_overlay::*;
 use super::lock::*;
+use super::inotify::*;
 
 pub fn ContextCanAccessFile(task: &Task, inode: &Inode, reqPerms: &PermMask) -> Result<bool> {
     let creds = task.creds.clone();
@@ -99,6 +100,7 @@ pub enum IopsType {
     TmpfsDir,
     TmpfsFifoInodeOp,
     TmpfsFileInodeOp,
+    MemfdInodeOp,
     TmpfsSocket,
     TmpfsSymlink,
     DirInodeOperations,
@@ -638,6 +640,10 @@ impl Inode {
         return CheckCapability(&creds, cp, &uattr)
     }
 
+    pub fn Watches(&self) -> Watches {
+        return self.lock().Watches.clone();
+    }
+
     pub fn StatFS(&self, task: &Task) -> Result<FsInfo> {
         let overlay = self.lock().Overlay.clone();
         let isOverlay = overlay.is_some();
@@ -659,6 +665,9 @@ pub struct InodeIntern {
     pub LockCtx: LockCtx,
     pub MountSource: Arc<QMutex<MountSource>>,
     pub Overlay: Option<Arc<RwLock<OverlayEntry>>>,
+
+    // Watches are the inotify watches on the inode.
+    pub Watches: Watches,
 }
 
 impl Default for InodeIntern {
@@ -670,6 +679,7 @@ impl Default for InodeIntern {
             LockCtx: LockCtx::default(),
             MountSource: Arc::new(QMutex::new(MountSource::default())),
             Overlay: None,
+            Watches: Watches::default(),
         }
     }
 }
@@ -0,0 +1,468 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ::qlib::mutex::*;
+use core::any::Any;
+use core::ops::Deref;
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+use alloc::sync::Arc;
+use alloc::sync::Weak;
+use alloc::vec::Vec;
+use alloc::collections::btree_map::BTreeMap;
+use alloc::collections::vec_deque::VecDeque;
+
+use super::super::qlib::common::*;
+use super::super::qlib::linux_def::*;
+use super::super::qlib::linux::inotify::*;
+use super::super::task::*;
+use super::super::kernel::waiter::*;
+use super::super::uid::*;
+
+use super::attr::*;
+use super::anon::*;
+use super::file::*;
+use super::flags::*;
+use super::dirent::*;
+use super::dentry::*;
+use super::host::hostinodeop::*;
+
+// INOTIFY_EVENT_BASE_SIZE is the size of a struct inotify_event without the
+// name.
+pub const INOTIFY_EVENT_BASE_SIZE: usize = 16;
+
+// MAX_QUEUED_EVENTS is the default /proc/sys/fs/inotify/max_queued_events.
+pub const MAX_QUEUED_EVENTS: usize = 16384;
+
+static COOKIE: AtomicU32 = AtomicU32::new(1);
+
+// WATCH_COUNT is the number of live watches in the sandbox. While it is 0,
+// events are dropped without looking up any inode's watches.
+static WATCH_COUNT: AtomicU64 = AtomicU64::new(0);
+
+// HasWatches returns whether any inode may be watched.
+pub fn HasWatches() -> bool {
+    return WATCH_COUNT.load(Ordering::Relaxed) != 0
+}
+
+// NewInotifyCookie returns a cookie which ties together the IN_MOVED_FROM
+// and IN_MOVED_TO events of one rename.
+pub fn NewInotifyCookie() -> u32 {
+    return COOKIE.fetch_add(1, Ordering::SeqCst)
+}
+
+// Event represents a struct inotify_event from linux.
+#[derive(Clone, PartialEq)]
+pub struct Event {
+    pub wd: i32,
+    pub mask: u32,
+    pub cookie: u32,
+
+    // len is the length of name including its NUL padding, which rounds the
+    // whole event up to a multiple of INOTIFY_EVENT_BASE_SIZE like Linux
+    // does. It is 0 when there is no name.
+    pub len: u32,
+    pub name: Vec<u8>,
+}
+
+impl Event {
+    pub fn New(wd: i32, name: &str, mask: u32, cookie: u32) -> Self {
+        let mut e = Self {
+            wd: wd,
+            mask: mask,
+            cookie: cookie,
+            len: 0,
+            name: Vec::new(),
+        };
+
+        if name.len() > 0 {
+            // Linux always adds at least one NUL byte.
+            let len = (name.len() + 1 + INOTIFY_EVENT_BASE_SIZE - 1) & !(INOTIFY_EVENT_BASE_SIZE - 1);
+            e.name = name.as_bytes().to_vec();
+            e.name.resize(len, 0);
+            e.len = len as u32;
+        }
+
+        return e
+    }
+
+    pub fn Size(&self) -> usize {
+        return INOTIFY_EVENT_BASE_SIZE + self.len as usize
+    }
+
+    // CopyTo appends the event in the struct inotify_event layout to buf.
+    pub fn CopyTo(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.wd.to_ne_bytes());
+        buf.extend_from_slice(&self.mask.to_ne_bytes());
+        buf.extend_from_slice(&self.cookie.to_ne_bytes());
+        buf.extend_from_slice(&self.len.to_ne_bytes());
+        buf.extend_from_slice(&self.name);
+    }
+}
+
+pub struct WatchInternal {
+    // owner is the inotify instance which owns this watch.
+    pub owner: Weak<InotifyInternal>,
+
+    // wd is the watch descriptor visible to the application.
+    pub wd: i32,
+
+    // target is the dirent whose inode is watched. The watch holds a
+    // reference on it so that the inode, and with it the watch, stays alive.
+    pub target: Dirent,
+
+    // mask is the events the watch is interested in.
+    pub mask: AtomicU32,
+}
+
+impl Drop for WatchInternal {
+    fn drop(&mut self) {
+        WATCH_COUNT.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone)]
+pub struct Watch(Arc<WatchInternal>);
+
+impl Deref for Watch {
+    type Target = Arc<WatchInternal>;
+
+    fn deref(&self) -> &Arc<WatchInternal> {
+        &self.0
+    }
+}
+
+impl Watch {
+    // Notify queues an event on the owner of the watch if the watch is
+    // interested in events.
+    pub fn Notify(&self, name: &str, events: u32, cookie: u32) {
+        let mask = self.mask.load(Ordering::SeqCst);
+        if mask & events == 0 {
+            return
+        }
+
+        let owner = match self.owner.upgrade() {
+            None => return,
+            Some(o) => Inotify(o),
+        };
+
+        // The event carries the matched bits plus all control bits, e.g.
+        // IN_ISDIR.
+        let effectiveMask = !IN_ALL_EVENTS | mask;
+        owner.QueueEvent(Event::New(self.wd, name, effectiveMask & events, cookie));
+
+        if mask & IN_ONESHOT != 0 {
+            owner.RmWatch(self.wd).ok();
+        }
+    }
+}
+
+// Watches is the set of inotify watches on an inode, keyed by the id of the
+// inotify instance owning each of them.
+#[derive(Clone, Default)]
+pub struct Watches(Arc<QMutex<BTreeMap<u64, Watch>>>);
+
+impl Deref for Watches {
+    type Target = Arc<QMutex<BTreeMap<u64, Watch>>>;
+
+    fn deref(&self) -> &Arc<QMutex<BTreeMap<u64, Watch>>> {
+        &self.0
+    }
+}
+
+impl Watches {
+    // Notify sends events to all the watches on the inode. name is empty
+    // when the event is about the inode itself rather than a child of it.
+    pub fn Notify(&self, name: &str, events: u32, cookie: u32) {
+        let watches: Vec<Watch> = {
+            let ws = self.lock();
+            if ws.len() == 0 {
+                return
+            }
+
+            ws.values().cloned().collect()
+        };
+
+        for w in &watches {
+            w.Notify(name, events, cookie);
+        }
+    }
+}
+
+pub struct InotifyInternal {
+    // id identifies the instance in the Watches of the inodes it watches.
+    pub id: u64,
+
+    // queue notifies readers of new events.
+    pub queue: Queue,
+
+    pub data: QMutex<InotifyData>,
+}
+
+#[derive(Default)]
+pub struct InotifyData {
+    // events is the queue of events not read yet.
+    pub events: VecDeque<Event>,
+
+    // nextWatch is the next watch descriptor to hand out.
+    pub nextWatch: i32,
+
+    // watches are the watches of the instance, by watch descriptor.
+    pub watches: BTreeMap<i32, Watch>,
+}
+
+impl Drop for InotifyInternal {
+    fn drop(&mut self) {
+        // The instance is gone, so remove its watches from their inodes.
+        let data = self.data.lock();
+        for (_, w) in &data.watches {
+            w.target.Inode().Watches().lock().remove(&self.id);
+        }
+    }
+}
+
+pub fn NewInotify(task: &Task) -> File {
+    let inode = NewAnonInode(task);
+    let dirent = Dirent::New(&inode, "anon_inode:[inotify]");
+
+    let ops = Inotify(Arc::new(InotifyInternal {
+        id: NewUID(),
+        queue: Queue::default(),
+        data: QMutex::new(InotifyData {
+            nextWatch: 1,
+            ..Default::default()
+        }),
+    }));
+
+    return File::New(&dirent, &FileFlags {
+        Read: true,
+        ..Default::default()
+    }, ops);
+}
+
+#[derive(Clone)]
+pub struct Inotify(Arc<InotifyInternal>);
+
+impl Deref for Inotify {
+    type Target = Arc<InotifyInternal>;
+
+    fn deref(&self) -> &Arc<InotifyInternal> {
+        &self.0
+    }
+}
+
+impl Inotify {
+    pub fn QueueEvent(&self, ev: Event) {
+        {
+            let mut data = self.data.lock();
+
+            // "If successive output inotify events produced on the inotify file
+            // descriptor are identical (same wd, mask, cookie, and name), then
+            // they are coalesced into a single event if the older event has not
+            // yet been read." - inotify(7)
+            if data.events.back() == Some(&ev) {
+                return
+            }
+
+            if data.events.len() >= MAX_QUEUED_EVENTS {
+                let overflow = Event::New(-1, "", IN_Q_OVERFLOW, 0);
+                if data.events.back() != Some(&overflow) {
+                    data.events.push_back(overflow);
+                }
+            } else {
+                data.events.push_back(ev);
+            }
+        }
+
+        self.queue.Notify(EVENT_IN);
+    }
+
+    // AddWatch watches the inode of target, or updates the mask of the
+    // existing watch on it, and returns the watch descriptor.
+    pub fn AddWatch(&self, target: &Dirent, mask: u32) -> i32 {
+        let mut data = self.data.lock();
+        let watches = target.Inode().Watches();
+        let mut ws = watches.lock();
+
+        match ws.get(&self.id) {
+            None => (),
+            Some(w) => {
+                if mask & IN_MASK_ADD != 0 {
+                    w.mask.fetch_or(mask, Ordering::SeqCst);
+                } else {
+                    w.mask.store(mask, Ordering::SeqCst);
+                }
+                return w.wd
+            }
+        }
+
+        let wd = data.nextWatch;
+        data.nextWatch += 1;
+
+        WATCH_COUNT.fetch_add(1, Ordering::Relaxed);
+        let w = Watch(Arc::new(WatchInternal {
+            owner: Arc::downgrade(&self.0),
+            wd: wd,
+            target: target.clone(),
+            mask: AtomicU32::new(mask),
+        }));
+
+        data.watches.insert(wd, w.clone());
+        ws.insert(self.id, w);
+        return wd
+    }
+
+    // RmWatch removes the watch wd and queues an IN_IGNORED event for it.
+    pub fn RmWatch(&self, wd: i32) -> Result<()> {
+        let w = match self.data.lock().watches.remove(&wd) {
+            None => return Err(Error::SysError(SysErr::EINVAL)),
+            Some(w) => w,
+        };
+
+        w.target.Inode().Watches().lock().remove(&self.id);
+        self.QueueEvent(Event::New(wd, "", IN_IGNORED, 0));
+        return Ok(())
+    }
+
+    // pendingSize is the number of bytes of the events not read yet.
+    fn pendingSize(&self) -> usize {
+        let data = self.data.lock();
+        let mut size = 0;
+        for e in &data.events {
+            size += e.Size();
+        }
+
+        return size
+    }
+}
+
+impl Waitable for Inotify {
+    fn Readiness(&self, _task: &Task, mask: EventMask) -> EventMask {
+        let mut ready = 0;
+        if self.data.lock().events.len() > 0 {
+            ready |= EVENT_IN;
+        }
+
+        return mask & ready
+    }
+
+    fn EventRegister(&self, task: &Task, e: &WaitEntry, mask: EventMask) {
+        self.queue.EventRegister(task, e, mask)
+    }
+
+    fn EventUnregister(&self, task: &Task, e: &WaitEntry) {
+        self.queue.EventUnregister(task, e)
+    }
+}
+
+impl SpliceOperations for Inotify {}
+
+impl FileOperations for Inotify {
+    fn as_any(&self) -> &Any {
+        return self
+    }
+
+    fn FopsType(&self) -> FileOpsType {
+        return FileOpsType::Inotify
+    }
+
+    fn Seekable(&self) -> bool {
+        return false;
+    }
+
+    fn Seek(&self, _task: &Task, _f: &File, _whence: i32, _current: i64, _offset: i64) -> Result<i64> {
+        return Err(Error::SysError(SysErr::ESPIPE))
+    }
+
+    fn ReadDir(&self, _task: &Task, _f: &File, _offset: i64, _serializer: &mut DentrySerializer) -> Result<i64> {
+        return Err(Error::SysError(SysErr::ENOTDIR))
+    }
+
+    fn ReadAt(&self, task: &Task, _f: &File, dsts: &mut [IoVec], _offset: i64, _blocking: bool) -> Result<i64> {
+        let size = IoVec::NumBytes(dsts);
+
+        let mut buf = Vec::new();
+        {
+            let mut data = self.data.lock();
+            if data.events.len() == 0 {
+                return Err(Error::SysError(SysErr::EAGAIN))
+            }
+
+            loop {
+                let esize = match data.events.front() {
+                    None => break,
+                    Some(e) => e.Size(),
+                };
+
+                if buf.len() + esize > size {
+                    // Linux fails with EINVAL when even the first event
+                    // doesn't fit.
+                    if buf.len() == 0 {
+                        return Err(Error::SysError(SysErr::EINVAL))
+                    }
+                    break;
+                }
+
+                data.events.pop_front().unwrap().CopyTo(&mut buf);
+            }
+        }
+
+        task.CopyDataOutToIovs(&buf, dsts)?;
+        return Ok(buf.len() as i64)
+    }
+
+    fn WriteAt(&self, _task: &Task, _f: &File, _srcs: &[IoVec], _offset: i64, _blocking: bool) -> Result<i64> {
+        return Err(Error::SysError(SysErr::EBADF))
+    }
+
+    fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
+        let n = self.WriteAt(task, f, srcs, 0, false)?;
+        return Ok((n, 0))
+    }
+
+    fn Fsync(&self, _task: &Task, _f: &File, _start: i64, _end: i64, _syncType: SyncType) -> Result<()> {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
+
+    fn Flush(&self, _task: &Task, _f: &File) -> Result<()> {
+        return Ok(())
+    }
+
+    fn UnstableAttr(&self, task: &Task, f: &File) -> Result<UnstableAttr> {
+        let inode = f.Dirent.Inode();
+        return inode.UnstableAttr(task);
+    }
+
+    fn Ioctl(&self, task: &Task, _f: &File, _fd: i32, request: u64, val: u64) -> Result<()> {
+        match request {
+            IoCtlCmd::FIONREAD => {
+                let n = self.pendingSize() as i32;
+                task.CopyOutObj(&n, val)?;
+                return Ok(())
+            }
+            _ => return Err(Error::SysError(SysErr::ENOTTY)),
+        }
+    }
+
+    fn IterateDir(&self, _task: &Task, _d: &Dirent, _dirCtx: &mut DirCtx, _offset: i32) -> (i32, Result<i64>) {
+        return (0, Err(Error::SysError(SysErr::ENOTDIR)))
+    }
+
+    fn Mappable(&self) -> Result<HostInodeOp> {
+        return Err(Error::SysError(SysErr::ENODEV))
+    }
+}
+
+impl SockOperations for Inotify {}
@@ -36,6 +36,7 @@ pub mod procfs;
 pub mod sys;
 pub mod anon;
 pub mod timerfd;
+pub mod inotify;
 pub mod tmpfs;
 
 pub fn Init() {
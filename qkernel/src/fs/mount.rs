@@ -37,6 +37,7 @@ use super::dirent::*;
 use super::tty::fs::*;
 use super::mount_overlay::*;
 use super::super::qlib::lrc_cache::*;
+use super::super::uid::NewUID;
 
 pub struct LookupContext {
     pub path: String,
@@ -53,6 +54,17 @@ pub struct Mount {
     pub Pid: u64,
     pub root: Dirent,
     pub prev: Option<Arc<QMutex<Mount>>>,
+
+    // rootPath is the path of the mount root relative to the root of its
+    // filesystem. It is "/" unless the mount is a bind mount of a subtree.
+    pub rootPath: String,
+
+    // bind is true if the mount is a bind mount. A bind mount shares its
+    // MountSource with the mount it was bound from.
+    pub bind: bool,
+    // flags are the per-mount flags. They apply on top of the MountSource's
+    // flags, which are shared by every bind mount of the filesystem.
+    pub flags: MountSourceFlags,
 }
 
 impl Mount {
@@ -64,6 +76,9 @@ impl Mount {
             Pid: pid,
             root: root.clone(),
             prev: None,
+            rootPath: "/".to_string(),
+            bind: false,
+            flags: MountSourceFlags::default(),
         }
     }
 
@@ -73,6 +88,9 @@ impl Mount {
             Pid: Self::INVALID_MOUNT_ID,
             root: root.clone(),
             prev: None,
+            rootPath: "/".to_string(),
+            bind: false,
+            flags: MountSourceFlags::default(),
         }
     }
 
@@ -82,6 +100,9 @@ impl Mount {
             Pid: Self::INVALID_MOUNT_ID,
             root: root.clone(),
             prev: None,
+            rootPath: "/".to_string(),
+            bind: false,
+            flags: MountSourceFlags::default(),
         }
     }
 
@@ -89,6 +110,15 @@ impl Mount {
         return self.root.clone();
     }
 
+    // EffectiveFlags merges the per-mount flags with the MountSource's.
+    pub fn EffectiveFlags(&self) -> MountSourceFlags {
+        let mut flags = self.root.Inode().lock().MountSource.lock().Flags;
+        flags.ReadOnly |= self.flags.ReadOnly;
+        flags.NoAtime |= self.flags.NoAtime;
+        flags.NoExec |= self.flags.NoExec;
+        return flags
+    }
+
     //whether it has no parent
     pub fn IsRoot(&self) -> bool {
         return !self.IsUndo() && self.Pid == Self::INVALID_MOUNT_ID;
@@ -104,7 +134,33 @@ impl Mount {
     }
 }
 
+// BindMountInUse returns whether an open file, or the working or root
+// directory of a task, is on the bind mount rooted at root.
+fn BindMountInUse(task: &Task, root: &Dirent) -> bool {
+    let pidns = task.Thread().lock().k.RootPIDNamespace();
+    for t in pidns.Tasks() {
+        let (fsc, fdTbl) = {
+            let t = t.lock();
+            (t.fsc.clone(), t.fdTbl.clone())
+        };
+
+        if fsc.WorkDirectory().DescendantOf(root) || fsc.RootDirectory().DescendantOf(root) {
+            return true
+        }
+
+        for f in fdTbl.GetFiles() {
+            if f.Dirent.DescendantOf(root) {
+                return true
+            }
+        }
+    }
+
+    return false
+}
+
 pub struct MountNsInternal {
+    // id identifies the namespace in /proc/[pid]/ns/mnt.
+    pub id: u64,
     pub userns: UserNameSpace,
     pub root: Dirent,
     pub mounts: QMutex<BTreeMap<u64, Arc<QMutex<Mount>>>>,
@@ -114,6 +170,7 @@ pub struct MountNsInternal {
 impl Default for MountNsInternal {
     fn default() -> Self {
         return Self {
+            id: 0,
             userns: UserNameSpace::default(),
             root: Dirent::default(),
             mounts: QMutex::new(BTreeMap::new()),
@@ -140,6 +197,7 @@ impl MountNs {
         let rootMount = Arc::new(QMutex::new(Mount::NewRootMount(1, &d)));
         mounts.insert(d.ID(), rootMount);
         let internal = MountNsInternal {
+            id: NewUID(),
             userns: task.creds.lock().UserNamespace.clone(),
             root: d,
             mounts: QMutex::new(mounts),
@@ -149,6 +207,10 @@ impl MountNs {
         return Self(Arc::new(internal));
     }
 
+    pub fn ID(&self) -> u64 {
+        return self.id
+    }
+
     pub fn UserNamespace(&self) -> UserNameSpace {
         return self.userns.clone();
     }
@@ -162,10 +224,106 @@ impl MountNs {
     }
 
     pub fn Mount(&self, mountPoint: &Dirent, inode: &Inode) -> Result<()> {
+        self.mount(mountPoint, inode, "/", false, MountSourceFlags::default())?;
+        return Ok(())
+    }
+
+    // BindMount mounts the subtree rooted at source over mountPoint. If
+    // recursive is set, the mounts under source are bound as well.
+    pub fn BindMount(&self, task: &Task, mountPoint: &Dirent, source: &Dirent, recursive: bool) -> Result<()> {
+        let srcInode = source.Inode();
+        let dstInode = mountPoint.Inode();
+        if srcInode.StableAttr().IsDir() != dstInode.StableAttr().IsDir() {
+            return Err(Error::SysError(SysErr::ENOTDIR))
+        }
+
+        let srcMnt = match self.FindMount(source) {
+            None => return Err(Error::SysError(SysErr::EINVAL)),
+            Some(m) => m,
+        };
+
+        let srcMntRoot = srcMnt.lock().Root();
+        let (relPath, _) = source.FullName(&srcMntRoot);
+        let rootPath = Join(&srcMnt.lock().rootPath, &relPath);
+
+        // Collect the mounts below source before mounting, so a bind onto a
+        // directory inside source isn't replayed into itself.
+        let mut submounts = Vec::new();
+        if recursive {
+            for m in self.AllMountsUnder(&srcMnt) {
+                let root = m.lock().Root();
+                if root == *source || !root.DescendantOf(source) {
+                    continue;
+                }
+
+                let (path, _) = root.FullName(source);
+                submounts.push((path, m));
+            }
+
+            // Parents before children.
+            submounts.sort_by(|a, b| a.0.len().cmp(&b.0.len()));
+        }
+
+        // Like Linux's clone_mnt, the bind mount starts with the per-mount
+        // flags of its source.
+        let srcFlags = srcMnt.lock().flags;
+        let replacement = self.mount(mountPoint, &srcInode, &rootPath, true, srcFlags)?;
+
+        // Replay the submounts at the same relative path under the new mount.
+        for (path, m) in submounts {
+            let mut remainingTraversals = MAX_SYMLINK_TRAVERSALS;
+            let target = self.FindLink(task, &replacement, None, &path, &mut remainingTraversals)?;
+            let (root, rootPath, flags) = {
+                let m = m.lock();
+                (m.Root(), m.rootPath.clone(), m.flags)
+            };
+
+            self.mount(&target, &root.Inode(), &rootPath, true, flags)?;
+        }
+
+        return Ok(())
+    }
+
+    // Remount changes the flags of the mount rooted at node. With bind
+    // (MS_REMOUNT|MS_BIND), or if the mount is a bind mount, only the flags
+    // of this mount change. Otherwise the flags of its MountSource do, which
+    // applies to every bind mount of the same filesystem, like a superblock
+    // remount in Linux.
+    pub fn Remount(&self, node: &Dirent, flags: &MountSourceFlags, bind: bool) -> Result<()> {
+        let mnt = match self.mounts.lock().get(&node.ID()) {
+            None => return Err(Error::SysError(SysErr::EINVAL)),
+            Some(m) => m.clone(),
+        };
+
+        let mut mnt = mnt.lock();
+        if bind || mnt.bind {
+            mnt.flags.ReadOnly = flags.ReadOnly;
+            mnt.flags.NoAtime = flags.NoAtime;
+            mnt.flags.NoExec = flags.NoExec;
+            return Ok(())
+        }
+
+        let msrc = node.Inode().lock().MountSource.clone();
+        msrc.lock().Flags = *flags;
+        return Ok(())
+    }
+
+    // MountFlags returns the per-mount flags of the mount d is on.
+    pub fn MountFlags(&self, d: &Dirent) -> MountSourceFlags {
+        match self.FindMount(d) {
+            None => return MountSourceFlags::default(),
+            Some(m) => return m.lock().flags,
+        }
+    }
+
+    fn mount(&self, mountPoint: &Dirent, inode: &Inode, rootPath: &str, bind: bool, flags: MountSourceFlags) -> Result<Dirent> {
         let replacement = mountPoint.Mount(inode)?;
 
         let parentMnt = self.FindMount(mountPoint).unwrap();
         let mut childMnt = Mount::New(self.mountId.fetch_add(1, Ordering::SeqCst), parentMnt.lock().Id, &replacement);
+        childMnt.rootPath = rootPath.to_string();
+        childMnt.bind = bind;
+        childMnt.flags = flags;
 
         mountPoint.clone().DropExtendedReference();
 
@@ -184,15 +342,50 @@ impl MountNs {
             childMnt.prev = Some(prev.unwrap().clone());
             mounts.remove(&mntId);
             mounts.insert(replacement.ID(), Arc::new(QMutex::new(childMnt)));
-            return Ok(())
+            return Ok(replacement)
         }
 
         childMnt.prev = Some(Arc::new(QMutex::new(Mount::NewUndoMount(mountPoint))));
         mounts.insert(replacement.ID(), Arc::new(QMutex::new(childMnt)));
-        return Ok(())
+        return Ok(replacement)
+    }
+
+    // Unmount removes the mount rooted at node. With detachOnly, the mounts
+    // below node are detached as well and the filesystem stays alive until
+    // the last reference held by open files is dropped.
+    pub fn Unmount(&self, task: &Task, node: &Dirent, detachOnly: bool) -> Result<()> {
+        let orig = match self.mounts.lock().get(&node.ID()) {
+            None => return Err(Error::SysError(SysErr::EINVAL)),
+            Some(n) => n.clone(),
+        };
+
+        let mut submounts = Vec::new();
+        for m in self.AllMountsUnder(&orig) {
+            if Arc::ptr_eq(&m, &orig) {
+                continue;
+            }
+
+            let root = m.lock().Root();
+            let (path, _) = root.FullName(node);
+            submounts.push((path, root));
+        }
+
+        if submounts.len() > 0 {
+            if !detachOnly {
+                return Err(Error::SysError(SysErr::EBUSY))
+            }
+
+            // Children before parents.
+            submounts.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+            for (_, root) in &submounts {
+                self.unmount(task, root, true)?;
+            }
+        }
+
+        return self.unmount(task, node, detachOnly);
     }
 
-    pub fn Unmount(&self, node: &Dirent, detachOnly: bool) -> Result<()> {
+    fn unmount(&self, task: &Task, node: &Dirent, detachOnly: bool) -> Result<()> {
         let mut mounts = self.mounts.lock();
         let orig = mounts.get(&node.ID());
         let orig = match orig {
@@ -201,13 +394,24 @@ impl MountNs {
         };
 
         let prev = match &orig.lock().prev {
-            None => panic!("cannot unmount initial dirent"),
+            // the initial dirent can't be unmounted
+            None => return Err(Error::SysError(SysErr::EINVAL)),
             Some(prev) => prev.clone(),
         };
 
-        let m = node.Inode().lock().MountSource.clone();
-        if !detachOnly && Arc::strong_count(&m) != 2 {
-            return Err(Error::SysError(SysErr::EBUSY))
+        if !detachOnly {
+            // A bind mount shares the MountSource with its source, so the
+            // reference count can't tell whether this mount is still in use.
+            let busy = if orig.lock().bind {
+                BindMountInUse(task, node)
+            } else {
+                let m = node.Inode().lock().MountSource.clone();
+                Arc::strong_count(&m) != 2
+            };
+
+            if busy {
+                return Err(Error::SysError(SysErr::EBUSY))
+            }
         }
 
         node.UnMount(&prev.lock().root)?;
@@ -20,6 +20,8 @@ use super::super::super::qlib::common::*;
 use super::super::super::qlib::linux_def::*;
 use super::super::super::qlib::auth::*;
 use super::super::super::task::*;
+use super::super::super::kernel::loadavg::*;
+use super::super::super::SHARESPACE;
 use super::super::fsutil::file::readonly_file::*;
 use super::super::fsutil::inode::simple_file_inode::*;
 use super::super::attr::*;
@@ -49,8 +51,22 @@ pub struct LoadAvgData {
 }
 
 impl LoadAvgData {
-    pub fn GenSnapshot(&self, _task: &Task) -> Vec<u8> {
-        let ret = format!("{:.2} {:.2} {:.2} {}/{} {}\n", 0.00, 0.00, 0.00, 0, 0, 0);
+    pub fn GenSnapshot(&self, task: &Task) -> Vec<u8> {
+        // see fs/proc/loadavg.c:loadavg_proc_show.
+        let loads = LOAD_AVG.Averages(FIXED_1 / 200);
+        let running = SHARESPACE.scheduler.RunnableCount();
+
+        let pidns = task.Thread().PIDNamespace();
+        let (total, last) = {
+            let pidns = pidns.lock();
+            (pidns.tasks.len(), pidns.last)
+        };
+
+        let ret = format!("{}.{:02} {}.{:02} {}.{:02} {}/{} {}\n",
+                          LoadInt(loads[0]), LoadFrac(loads[0]),
+                          LoadInt(loads[1]), LoadFrac(loads[1]),
+                          LoadInt(loads[2]), LoadFrac(loads[2]),
+                          running, total, last);
         return ret.as_bytes().to_vec();
     }
 }
@@ -27,6 +27,7 @@ pub mod loadavg;
 pub mod mounts;
 pub mod stat;
 pub mod sys;
+pub mod sysvipc;
 
 use alloc::sync::Arc;
 use ::qlib::mutex::*;
@@ -51,6 +51,7 @@ use super::filesystems::*;
 use super::loadavg::*;
 use super::mounts::*;
 use super::stat::*;
+use super::sysvipc::*;
 
 pub struct ProcNodeInternal {
     pub kernel: Kernel,
@@ -122,6 +123,7 @@ pub fn NewProc(task: &Task, msrc: &Arc<QMutex<MountSource>>, cgroupControllers:
     contents.insert("thread-self".to_string(), NewThreadSelf(task, &pidns, msrc));
     contents.insert("uptime".to_string(), NewUptime(task, msrc));
     contents.insert("sys".to_string(), NewSys(task, msrc));
+    contents.insert("sysvipc".to_string(), NewSysVIPC(task, msrc));
 
     let iops = Dir::New(task, contents, &ROOT_OWNER, &FilePermissions::FromMode(FileMode(0o0555)));
     let kernel = GetKernel();
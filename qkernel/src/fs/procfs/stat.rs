@@ -23,6 +23,7 @@ use super::super::super::qlib::linux_def::*;
 use super::super::super::qlib::auth::*;
 use super::super::super::kernel::kernel::*;
 use super::super::super::task::*;
+use super::super::super::SHARESPACE;
 use super::super::fsutil::file::readonly_file::*;
 use super::super::fsutil::inode::simple_file_inode::*;
 use super::super::attr::*;
@@ -143,7 +144,7 @@ impl StatData {
         buf += &format!("processes 0\n");
 
         // Number of runnable tasks.
-        buf += &format!("procs_running 0\n");
+        buf += &format!("procs_running {}\n", SHARESPACE.scheduler.RunnableCount());
 
         // Number of tasks waiting on IO.
         buf += &format!("procs_blocked 0\n");
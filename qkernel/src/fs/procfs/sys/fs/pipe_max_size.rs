@@ -0,0 +1,48 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::sync::Arc;
+use ::qlib::mutex::*;
+use core::sync::atomic::Ordering;
+
+use super::super::super::super::super::qlib::common::*;
+use super::super::super::super::super::qlib::linux_def::*;
+use super::super::super::super::super::task::*;
+use super::super::super::super::super::kernel::pipe::pipe::*;
+use super::super::super::super::mount::*;
+use super::super::super::super::inode::*;
+use super::super::sysctl::*;
+
+// NewPipeMaxSize returns /proc/sys/fs/pipe-max-size.
+pub fn NewPipeMaxSize(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    return NewIntSysctl(task, msrc, GetPipeMaxSize, SetPipeMaxSize)
+}
+
+fn GetPipeMaxSize() -> i64 {
+    return PIPE_MAX_SIZE.load(Ordering::Relaxed) as i64
+}
+
+fn SetPipeMaxSize(val: i64) -> Result<()> {
+    if val < 0 {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
+
+    let val = match RoundPipeSize(val as usize) {
+        None => return Err(Error::SysError(SysErr::EINVAL)),
+        Some(v) => v,
+    };
+
+    PIPE_MAX_SIZE.store(val, Ordering::Relaxed);
+    return Ok(())
+}
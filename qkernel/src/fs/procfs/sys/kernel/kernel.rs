@@ -0,0 +1,31 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::sync::Arc;
+use alloc::string::ToString;
+use ::qlib::mutex::*;
+use alloc::collections::btree_map::BTreeMap;
+
+use super::super::super::super::super::task::*;
+use super::super::super::super::mount::*;
+use super::super::super::super::inode::*;
+use super::super::sysctl::*;
+use super::randomize_va_space::*;
+
+pub fn NewKernel(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    let mut contents = BTreeMap::new();
+    contents.insert("randomize_va_space".to_string(), NewRandomizeVASpace(task, msrc));
+
+    return NewSysctlDir(task, msrc, contents)
+}
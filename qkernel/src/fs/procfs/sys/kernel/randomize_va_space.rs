@@ -0,0 +1,44 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::sync::Arc;
+use ::qlib::mutex::*;
+use core::sync::atomic::Ordering;
+
+use super::super::super::super::super::qlib::common::*;
+use super::super::super::super::super::qlib::linux_def::*;
+use super::super::super::super::super::task::*;
+use super::super::super::super::super::memmgr::arch::*;
+use super::super::super::super::mount::*;
+use super::super::super::super::inode::*;
+use super::super::sysctl::*;
+
+// NewRandomizeVASpace returns /proc/sys/kernel/randomize_va_space.
+pub fn NewRandomizeVASpace(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    return NewIntSysctl(task, msrc, GetRandomizeVASpace, SetRandomizeVASpace)
+}
+
+fn GetRandomizeVASpace() -> i64 {
+    return RANDOMIZE_VA_SPACE.load(Ordering::Relaxed) as i64
+}
+
+fn SetRandomizeVASpace(val: i64) -> Result<()> {
+    // Only 0, 1 and 2 are valid, as in Linux.
+    if val < 0 || val > 2 {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
+
+    RANDOMIZE_VA_SPACE.store(val as i32, Ordering::Relaxed);
+    return Ok(())
+}
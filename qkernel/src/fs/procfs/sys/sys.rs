@@ -18,10 +18,7 @@ use ::qlib::mutex::*;
 use alloc::collections::btree_map::BTreeMap;
 
 use super::super::super::super::qlib::common::*;
-use super::super::super::super::qlib::linux_def::*;
-use super::super::super::super::qlib::auth::*;
 use super::super::super::super::task::*;
-use super::super::super::attr::*;
 use super::super::super::file::*;
 use super::super::super::flags::*;
 use super::super::super::dirent::*;
@@ -29,8 +26,10 @@ use super::super::super::mount::*;
 use super::super::super::inode::*;
 use super::super::super::ramfs::dir::*;
 use super::super::dir_proc::*;
-use super::super::inode::*;
+use super::sysctl::*;
 use super::vm::vm::*;
+use super::fs::fs::*;
+use super::kernel::kernel::*;
 
 // ProcSysDirNode represents a /proc/sys directory.
 pub struct ProcSysDirNode {
@@ -48,13 +47,9 @@ impl DirDataNode for ProcSysDirNode {
 
 pub fn NewSys(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
     let mut contents = BTreeMap::new();
+    contents.insert("fs".to_string(), NewFs(task, msrc));
+    contents.insert("kernel".to_string(), NewKernel(task, msrc));
     contents.insert("vm".to_string(), NewVm(task, msrc));
 
-    let taskDir = DirNode {
-        dir: Dir::New(task, contents, &ROOT_OWNER, &FilePermissions::FromMode(FileMode(0o0555))),
-        data: ProcSysDirNode {
-        }
-    };
-
-    return NewProcInode(&Arc::new(taskDir), msrc, InodeType::SpecialDirectory, None)
-}
\ No newline at end of file
+    return NewSysctlDir(task, msrc, contents)
+}
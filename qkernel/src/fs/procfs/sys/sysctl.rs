@@ -0,0 +1,138 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use alloc::string::String;
+use alloc::collections::btree_map::BTreeMap;
+use ::qlib::mutex::*;
+
+use super::super::super::super::qlib::common::*;
+use super::super::super::super::qlib::linux_def::*;
+use super::super::super::super::qlib::auth::*;
+use super::super::super::super::task::*;
+use super::super::super::fsutil::file::readwrite_file::*;
+use super::super::super::fsutil::inode::simple_file_inode::*;
+use super::super::super::attr::*;
+use super::super::super::file::*;
+use super::super::super::flags::*;
+use super::super::super::dirent::*;
+use super::super::super::mount::*;
+use super::super::super::inode::*;
+use super::super::super::ramfs::dir::*;
+use super::super::inode::*;
+use super::sys::*;
+
+// NewSysctlDir returns a /proc/sys directory holding contents.
+pub fn NewSysctlDir(task: &Task, msrc: &Arc<QMutex<MountSource>>, contents: BTreeMap<String, Inode>) -> Inode {
+    let taskDir = DirNode {
+        dir: Dir::New(task, contents, &ROOT_OWNER, &FilePermissions::FromMode(FileMode(0o0555))),
+        data: ProcSysDirNode {
+        }
+    };
+
+    return NewProcInode(&Arc::new(taskDir), msrc, InodeType::SpecialDirectory, None)
+}
+
+// NewIntSysctl returns a writable /proc/sys file holding a single integer.
+// get returns the current value; set validates and stores a new one, failing
+// with EINVAL if it is out of range.
+pub fn NewIntSysctl(task: &Task, msrc: &Arc<QMutex<MountSource>>, get: fn() -> i64, set: fn(i64) -> Result<()>) -> Inode {
+    let v = SimpleFileInode::New(task,
+                                 &ROOT_OWNER,
+                                 &FilePermissions::FromMode(FileMode(0o644)),
+                                 FSMagic::PROC_SUPER_MAGIC,
+                                 false,
+                                 IntSysctlData {
+                                     get: get,
+                                     set: set,
+                                 });
+    return NewProcInode(&Arc::new(v), msrc, InodeType::SpecialFile, None)
+}
+
+pub struct IntSysctlData {
+    pub get: fn() -> i64,
+    pub set: fn(i64) -> Result<()>,
+}
+
+impl SimpleFileTrait for IntSysctlData {
+    fn GetFile(&self, _task: &Task, _dir: &Inode, dirent: &Dirent, flags: FileFlags) -> Result<File> {
+        let fops = ReadWriteFileOperations {
+            node: IntSysctlNode {
+                get: self.get,
+                set: self.set,
+            },
+        };
+
+        let file = File::New(dirent, &flags, fops);
+        return Ok(file);
+    }
+}
+
+// IntSysctlNode implements a /proc/sys file holding a single integer.
+pub struct IntSysctlNode {
+    pub get: fn() -> i64,
+    pub set: fn(i64) -> Result<()>,
+}
+
+impl ReadWriteFileNode for IntSysctlNode {
+    fn ReadAt(&self, task: &Task, _f: &File, dsts: &mut [IoVec], offset: i64, _blocking: bool) -> Result<i64> {
+        if offset < 0 {
+            return Err(Error::SysError(SysErr::EINVAL))
+        }
+
+        let buf = format!("{}\n", (self.get)());
+        if offset as usize >= buf.len() {
+            return Ok(0)
+        }
+
+        let n = task.CopyDataOutToIovs(&buf.as_bytes()[offset as usize..], dsts)?;
+        return Ok(n as i64)
+    }
+
+    fn WriteAt(&self, task: &Task, _f: &File, srcs: &[IoVec], offset: i64, _blocking: bool) -> Result<i64> {
+        if offset != 0 {
+            // No need to handle partial writes thus far.
+            return Err(Error::SysError(SysErr::EINVAL))
+        }
+
+        let size = IoVec::NumBytes(srcs);
+        if size == 0 {
+            return Ok(0)
+        }
+
+        // The sysctl value is a single decimal number; anything longer than
+        // a page is certainly invalid.
+        if size > MemoryDef::PAGE_SIZE as usize {
+            return Err(Error::SysError(SysErr::EINVAL))
+        }
+
+        let mut buf : Vec<u8> = Vec::with_capacity(size);
+        buf.resize(size, 0);
+        task.CopyDataInFromIovs(&mut buf, srcs)?;
+
+        let str = match core::str::from_utf8(&buf) {
+            Err(_) => return Err(Error::SysError(SysErr::EINVAL)),
+            Ok(s) => s,
+        };
+
+        let val = match str.trim().parse::<i64>() {
+            Err(_) => return Err(Error::SysError(SysErr::EINVAL)),
+            Ok(v) => v,
+        };
+
+        (self.set)(val)?;
+        return Ok(size as i64)
+    }
+}
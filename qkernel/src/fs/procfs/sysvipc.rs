@@ -0,0 +1,100 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::sync::Arc;
+use alloc::string::ToString;
+use ::qlib::mutex::*;
+use alloc::collections::btree_map::BTreeMap;
+use alloc::vec::Vec;
+
+use super::super::super::qlib::common::*;
+use super::super::super::qlib::linux_def::*;
+use super::super::super::qlib::auth::*;
+use super::super::super::task::*;
+use super::super::fsutil::file::readonly_file::*;
+use super::super::fsutil::inode::simple_file_inode::*;
+use super::super::attr::*;
+use super::super::file::*;
+use super::super::flags::*;
+use super::super::dirent::*;
+use super::super::mount::*;
+use super::super::inode::*;
+use super::super::ramfs::dir::*;
+use super::dir_proc::*;
+use super::inode::*;
+
+// ProcSysVIPCDirNode represents a /proc/sysvipc directory.
+pub struct ProcSysVIPCDirNode {
+}
+
+impl DirDataNode for ProcSysVIPCDirNode {
+    fn Lookup(&self, d: &Dir, task: &Task, dir: &Inode, name: &str) -> Result<Dirent> {
+        return d.Lookup(task, dir, name);
+    }
+
+    fn GetFile(&self, d: &Dir, task: &Task, dir: &Inode, dirent: &Dirent, flags: FileFlags) -> Result<File> {
+        return d.GetFile(task, dir, dirent, flags)
+    }
+}
+
+pub fn NewSysVIPC(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    let mut contents = BTreeMap::new();
+    contents.insert("shm".to_string(), NewSysVIPCShm(task, msrc));
+
+    let taskDir = DirNode {
+        dir: Dir::New(task, contents, &ROOT_OWNER, &FilePermissions::FromMode(FileMode(0o0555))),
+        data: ProcSysVIPCDirNode {
+        }
+    };
+
+    return NewProcInode(&Arc::new(taskDir), msrc, InodeType::SpecialDirectory, None)
+}
+
+pub fn NewSysVIPCShm(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    let v = SimpleFileInode::New(task, &ROOT_OWNER, &FilePermissions::FromMode(FileMode(0o444)), FSMagic::PROC_SUPER_MAGIC, false, SysVIPCShmData{});
+    return NewProcInode(&Arc::new(v), msrc, InodeType::SpecialFile, None)
+}
+
+// SysVIPCShmData lists the shared memory segments of the reader's IPC
+// namespace, in the format of ipc/shm.c:sysvipc_shm_proc_show().
+pub struct SysVIPCShmData {
+}
+
+impl SysVIPCShmData {
+    pub fn GenSnapshot(&self, task: &Task) -> Vec<u8> {
+        let mut ret = "       key      shmid perms                  size  cpid  lpid nattch   uid   gid  cuid  cgid      atime      dtime      ctime                   rss                  swap\n".to_string();
+
+        let registry = task.ipcns.ShmRegistry();
+        for shm in registry.Shms() {
+            let stat = shm.lock().Stat(task);
+
+            let rss = shm.EffectiveSize();
+            ret += &format!("{:>10} {:>10}  {:>4o} {:>21} {:>5} {:>5}  {:>5} {:>5} {:>5} {:>5} {:>5} {:>10} {:>10} {:>10} {:>21} {:>21}\n",
+                            stat.ShmPerm.Key as i32, shm.ID(), stat.ShmPerm.Mode, stat.ShmSegsz,
+                            stat.ShmCpid, stat.ShmLpid, stat.ShmNattach,
+                            stat.ShmPerm.UID, stat.ShmPerm.GID, stat.ShmPerm.CUID, stat.ShmPerm.CGID,
+                            stat.ShmAtime, stat.ShmDtime, stat.ShmCtime, rss, 0);
+        }
+
+        return ret.as_bytes().to_vec();
+    }
+}
+
+impl SimpleFileTrait for SysVIPCShmData {
+    fn GetFile(&self, task: &Task, _dir: &Inode, dirent: &Dirent, flags: FileFlags) -> Result<File> {
+        let fops = NewSnapshotReadonlyFileOperations(self.GenSnapshot(task));
+        let file = File::New(dirent, &flags, fops);
+        return Ok(file);
+    }
+}
@@ -14,12 +14,12 @@
 
 use alloc::sync::Arc;
 use ::qlib::mutex::*;
-use alloc::string::ToString;
+use alloc::string::String;
 
 use super::super::super::super::qlib::common::*;
 use super::super::super::super::qlib::linux_def::*;
 use super::super::super::super::qlib::auth::*;
-use super::super::super::fsutil::file::readonly_file::*;
+use super::super::super::fsutil::file::readwrite_file::*;
 use super::super::super::fsutil::inode::simple_file_inode::*;
 use super::super::super::super::task::*;
 use super::super::super::attr::*;
@@ -29,10 +29,11 @@ use super::super::super::dirent::*;
 use super::super::super::mount::*;
 use super::super::super::inode::*;
 use super::super::super::super::threadmgr::thread::*;
+use super::super::super::super::loader::loader::TASK_COMM_LEN;
 use super::super::inode::*;
 
 pub fn NewComm(task: &Task, thread: &Thread, msrc: &Arc<QMutex<MountSource>>) -> Inode {
-    let v = NewCommSimpleFileInode(task, thread, &ROOT_OWNER, &FilePermissions::FromMode(FileMode(0o400)), FSMagic::PROC_SUPER_MAGIC);
+    let v = NewCommSimpleFileInode(task, thread, &ROOT_OWNER, &FilePermissions::FromMode(FileMode(0o644)), FSMagic::PROC_SUPER_MAGIC);
     return NewProcInode(&Arc::new(v), msrc, InodeType::SpecialFile, Some(thread.clone()))
 }
 
@@ -53,32 +54,28 @@ pub struct CommSimpleFileTrait {
 
 impl SimpleFileTrait for CommSimpleFileTrait {
     fn GetFile(&self, _task: &Task, _dir: &Inode, dirent: &Dirent, flags: FileFlags) -> Result<File> {
-        let fops = NewCommReadonlyFileOperations(&self.thread);
+        let fops = ReadWriteFileOperations {
+            node: CommFileNode {
+                thread: self.thread.clone(),
+            }
+        };
         let file = File::New(dirent, &flags, fops);
         return Ok(file);
     }
 }
 
-pub fn NewCommReadonlyFileOperations(thread: &Thread) -> ReadonlyFileOperations<CommReadonlyFileNode> {
-    return ReadonlyFileOperations {
-        node: CommReadonlyFileNode {
-            thread: thread.clone(),
-        }
-    }
-}
-
-pub struct CommReadonlyFileNode {
+// CommFileNode implements /proc/[pid]/comm and /proc/[pid]/task/[tid]/comm.
+pub struct CommFileNode {
     pub thread: Thread,
 }
 
-impl ReadonlyFileNode for CommReadonlyFileNode {
+impl ReadWriteFileNode for CommFileNode {
     fn ReadAt(&self, task: &Task, _f: &File, dsts: &mut [IoVec], offset: i64, _blocking: bool) -> Result<i64> {
         if offset < 0 {
             return Err(Error::SysError(SysErr::EINVAL))
         }
 
-        let buf = self.thread.lock().name.to_string() + "\n";
-        info!("CommReadonlyFileNode buf is {}", &buf);
+        let buf = self.thread.Name() + "\n";
         if offset as usize > buf.len() {
             return Ok(0)
         }
@@ -87,4 +84,26 @@ impl ReadonlyFileNode for CommReadonlyFileNode {
 
         return Ok(n as i64)
     }
+
+    // see fs/proc/base.c:comm_write. The name is truncated to
+    // TASK_COMM_LEN - 1 bytes and, as in Linux, a trailing newline is kept.
+    fn WriteAt(&self, task: &Task, _f: &File, srcs: &[IoVec], _offset: i64, _blocking: bool) -> Result<i64> {
+        let size = IoVec::NumBytes(srcs);
+        let mut len = size;
+        if len > TASK_COMM_LEN - 1 {
+            len = TASK_COMM_LEN - 1;
+        }
+
+        let mut buf = [0; TASK_COMM_LEN];
+        task.CopyDataInFromIovs(&mut buf[..len], srcs)?;
+
+        // only a thread of the same process can rename the thread.
+        if task.Thread().ThreadGroup() != self.thread.ThreadGroup() {
+            return Err(Error::SysError(SysErr::EINVAL))
+        }
+
+        let end = buf[..len].iter().position(|&c| c == 0).unwrap_or(len);
+        self.thread.SetName(&String::from_utf8_lossy(&buf[..end]));
+        return Ok(size as i64)
+    }
 }
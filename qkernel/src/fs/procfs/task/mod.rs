@@ -26,4 +26,4 @@ pub mod statm;
 pub mod status;
 pub mod mounts;
 pub mod stat;
-//pub mod namespace_symlink;
\ No newline at end of file
+pub mod namespace_symlink;
\ No newline at end of file
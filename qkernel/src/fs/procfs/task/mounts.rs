@@ -114,17 +114,14 @@ impl MountInfoFile {
 
             // (4) Root: the pathname of the directory in the filesystem
             // which forms the root of this mount.
-            //
-            // NOTE(b/78135857): This will always be "/" until we implement
-            // bind mounts.
-            ret += "/ ";
+            ret += &format!("{} ", m.lock().rootPath);
 
             // (5) Mount point (relative to process root).
-            ret += mountPath;
+            ret += &format!("{} ", mountPath);
 
             // (6) Mount options.
             let mountSource = mroot.Inode().lock().MountSource.clone();
-            let flags = mountSource.lock().Flags;
+            let flags = m.lock().EffectiveFlags();
             let mut opts = "rw".to_string();
             if flags.ReadOnly {
                 opts = "ro".to_string();
@@ -150,9 +147,15 @@ impl MountInfoFile {
             // (10) Mount source: filesystem-specific information or "none".
             ret += "none ";
 
-            // (11) Superblock options. Only "ro/rw" is supported for now,
-            // and is the same as the filesystem option.
-            ret += &format!("{}\n", opts);
+            // (11) Superblock options. Only "ro/rw" is supported for now.
+            // Unlike the mount options, they don't include the per-mount
+            // flags.
+            let superOpts = if mountSource.lock().Flags.ReadOnly {
+                "ro"
+            } else {
+                "rw"
+            };
+            ret += &format!("{}\n", superOpts);
         });
 
         return ret.as_bytes().to_vec();
@@ -195,7 +198,7 @@ impl MountsFile {
         ForEachMount(&self.thread, &mut |mountPath: &str, m: &Arc<QMutex<Mount>>| {
             let mroot = m.lock().Root();
             let mountSource = mroot.Inode().lock().MountSource.clone();
-            let flags = mountSource.lock().Flags;
+            let flags = m.lock().EffectiveFlags();
 
             let opts = if flags.ReadOnly {
                 "ro"
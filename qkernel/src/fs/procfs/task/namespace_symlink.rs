@@ -14,36 +14,203 @@
 
 use alloc::sync::Arc;
 use alloc::string::String;
+use alloc::string::ToString;
 use ::qlib::mutex::*;
+use alloc::collections::btree_map::BTreeMap;
 
 use super::super::super::super::qlib::common::*;
 use super::super::super::super::qlib::linux_def::*;
+use super::super::super::super::qlib::auth::*;
+use super::super::super::super::qlib::auth::userns::*;
+use super::super::super::super::qlib::device::*;
 use super::super::super::super::task::*;
+use super::super::super::super::kernel::ipc_namespace::*;
+use super::super::super::super::kernel::uts_namespace::*;
+use super::super::super::super::threadmgr::pid_namespace::*;
+use super::super::super::super::threadmgr::thread::*;
+use super::super::super::ramfs::dir::*;
 use super::super::super::ramfs::symlink::*;
+use super::super::super::fsutil::file::no_readwrite_file::*;
+use super::super::super::fsutil::inode::simple_file_inode::*;
+use super::super::super::attr::*;
+use super::super::super::file::*;
+use super::super::super::flags::*;
 use super::super::super::dirent::*;
 use super::super::super::mount::*;
 use super::super::super::inode::*;
-use super::super::super::super::threadmgr::thread::*;
 use super::super::symlink_proc::*;
+use super::super::dir_proc::*;
+use super::super::inode::*;
+
+// Namespace is one of the namespaces a task is a member of.
+#[derive(Clone)]
+pub enum Namespace {
+    IPC(IPCNamespace),
+    Mount(MountNs),
+    PID(PIDNamespace),
+    User(UserNameSpace),
+    UTS(UTSNamespace),
+}
+
+impl Namespace {
+    // Name returns the name of the namespace's entry in /proc/[pid]/ns.
+    pub fn Name(&self) -> &'static str {
+        match self {
+            Namespace::IPC(_) => "ipc",
+            Namespace::Mount(_) => "mnt",
+            Namespace::PID(_) => "pid",
+            Namespace::User(_) => "user",
+            Namespace::UTS(_) => "uts",
+        }
+    }
+
+    pub fn ID(&self) -> u64 {
+        match self {
+            Namespace::IPC(ns) => ns.ID(),
+            Namespace::Mount(ns) => ns.ID(),
+            Namespace::PID(ns) => ns.ID(),
+            Namespace::User(ns) => ns.ID(),
+            Namespace::UTS(ns) => ns.ID(),
+        }
+    }
+
+    // CloneFlag returns the CLONE_NEW* flag that selects this kind of
+    // namespace in clone(2), unshare(2) and setns(2).
+    pub fn CloneFlag(&self) -> i32 {
+        match self {
+            Namespace::IPC(_) => CloneOp::CLONE_NEWIPC,
+            Namespace::Mount(_) => CloneOp::CLONE_NEWNS,
+            Namespace::PID(_) => CloneOp::CLONE_NEWPID,
+            Namespace::User(_) => CloneOp::CLONE_NEWUSER,
+            Namespace::UTS(_) => CloneOp::CLONE_NEWUTS,
+        }
+    }
+
+    // Of returns the namespace called name that thread is a member of.
+    pub fn Of(thread: &Thread, name: &str) -> Option<Self> {
+        let ns = match name {
+            "ipc" => Namespace::IPC(thread.lock().ipcns.clone()),
+            "mnt" => Namespace::Mount(thread.MountNamespace()),
+            "pid" => Namespace::PID(thread.PIDNamespace()),
+            "user" => Namespace::User(thread.UserNamespace()),
+            "uts" => Namespace::UTS(thread.UTSNamespace()),
+            _ => return None,
+        };
+
+        return Some(ns)
+    }
+
+    // Link returns the target of the namespace's /proc/[pid]/ns symlink.
+    pub fn Link(&self) -> String {
+        return format!("{}:[{}]", self.Name(), self.ID())
+    }
+}
+
+// NamespaceFile is the file that a /proc/[pid]/ns symlink resolves to. An
+// fd opened on it can be passed to setns(2).
+pub struct NamespaceFile {
+    pub ns: Namespace,
+}
+
+impl SimpleFileTrait for NamespaceFile {
+    fn GetFile(&self, _task: &Task, _dir: &Inode, dirent: &Dirent, flags: FileFlags) -> Result<File> {
+        return Ok(File::New(dirent, &flags, NoReadWriteFile {}))
+    }
+}
+
+// NamespaceOfFile returns the namespace that f was opened on, if f was
+// opened through a /proc/[pid]/ns symlink.
+pub fn NamespaceOfFile(f: &File) -> Option<Namespace> {
+    let inode = f.Dirent.Inode();
+    let iops = inode.lock().InodeOp.clone();
+    match iops.as_any().downcast_ref::<SimpleFileInode<NamespaceFile>>() {
+        None => None,
+        Some(nsf) => Some(nsf.read().data.ns.clone()),
+    }
+}
 
 pub struct NamespaceNode {
     pub thread: Thread,
+    pub name: &'static str,
+}
+
+impl NamespaceNode {
+    pub fn Namespace(&self) -> Result<Namespace> {
+        match Namespace::Of(&self.thread, self.name) {
+            None => return Err(Error::SysError(SysErr::ENOENT)),
+            Some(ns) => Ok(ns),
+        }
+    }
 }
 
 impl ReadLinkNode for NamespaceNode {
-    fn ReadLink(&self, link: &Symlink, task: &Task, dir: &Inode) -> Result<String> {
-        return link.ReadLink(task, dir)
+    fn ReadLink(&self, _link: &Symlink, _task: &Task, _dir: &Inode) -> Result<String> {
+        let ns = self.Namespace()?;
+        return Ok(ns.Link())
     }
 
-    fn GetLink(&self, link: &Symlink, task: &Task, dir: &Inode) -> Result<Dirent> {
-        return link.GetLink(task, dir);
+    fn GetLink(&self, _link: &Symlink, task: &Task, dir: &Inode) -> Result<Dirent> {
+        let ns = self.Namespace()?;
+        let link = ns.Link();
+
+        // The namespace file's inode number is the namespace id, so that
+        // stat(2) through the link agrees with readlink(2).
+        let sattr = StableAttr {
+            Type: InodeType::SpecialFile,
+            DeviceId: PROC_DEVICE.lock().id.DeviceID(),
+            InodeId: ns.ID(),
+            BlockSize: 4096,
+            DeviceFileMajor: 0,
+            DeviceFileMinor: 0,
+        };
+
+        let iops = SimpleFileInode::New(task,
+                                        &ROOT_OWNER,
+                                        &FilePermissions::FromMode(FileMode(0o444)),
+                                        FSMagic::PROC_SUPER_MAGIC,
+                                        false,
+                                        NamespaceFile { ns: ns });
+
+        let msrc = dir.lock().MountSource.clone();
+        let inode = Inode::New(&Arc::new(iops), &msrc, &sattr);
+        return Ok(Dirent::New(&inode, &link))
     }
 }
 
-pub fn NewExe(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
-    let node = ExeNode {
-        thread: task.Thread(),
+pub fn NewNamespaceSymlink(task: &Task, thread: &Thread, msrc: &Arc<QMutex<MountSource>>, name: &'static str) -> Inode {
+    let node = NamespaceNode {
+        thread: thread.clone(),
+        name: name,
+    };
+
+    return SymlinkNode::New(task, msrc, node, Some(thread.clone()))
+}
+
+// NamespaceDirNode represents a /proc/[pid]/ns directory.
+pub struct NamespaceDirNode {
+}
+
+impl DirDataNode for NamespaceDirNode {
+    fn Lookup(&self, d: &Dir, task: &Task, dir: &Inode, name: &str) -> Result<Dirent> {
+        return d.Lookup(task, dir, name);
+    }
+
+    fn GetFile(&self, d: &Dir, task: &Task, dir: &Inode, dirent: &Dirent, flags: FileFlags) -> Result<File> {
+        return d.GetFile(task, dir, dirent, flags)
+    }
+}
+
+pub fn NewNamespaceDir(task: &Task, thread: &Thread, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    let mut contents = BTreeMap::new();
+    for name in &["ipc", "mnt", "pid", "user", "uts"] {
+        contents.insert(name.to_string(), NewNamespaceSymlink(task, thread, msrc, *name));
+    }
+
+    let nsDir = DirNode {
+        dir: Dir::New(task, contents, &ROOT_OWNER, &FilePermissions::FromMode(FileMode(0o0511))),
+        data: NamespaceDirNode {
+        }
     };
 
-    return SymlinkNode::New(task, msrc, node)
+    return NewProcInode(&Arc::new(nsDir), msrc, InodeType::SpecialDirectory, Some(thread.clone()))
 }
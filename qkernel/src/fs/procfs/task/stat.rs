@@ -76,6 +76,8 @@ impl StatData {
     pub fn GenSnapshot(&self, _task: &Task,) -> Vec<u8> {
         let mut output : String = "".to_string();
         output += &format!("{} ", self.pidns.IDOfTask(&self.t));
+        // the name isn't escaped, as in Linux. Readers find its end at the
+        // last ')'.
         output += &format!("({}) ", self.t.Name());
         output += &format!("{} ", self.t.lock().StateStatus().as_bytes()[0] as char);
 
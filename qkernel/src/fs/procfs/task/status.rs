@@ -17,35 +17,18 @@ use ::qlib::mutex::*;
 use alloc::vec::Vec;
 use alloc::string::ToString;
 
-use super::super::super::super::qlib::common::*;
-use super::super::super::super::qlib::linux_def::*;
-use super::super::super::super::qlib::auth::*;
 use super::super::super::super::kernel::kernel::*;
-use super::super::super::fsutil::file::readonly_file::*;
-use super::super::super::fsutil::inode::simple_file_inode::*;
+use super::super::super::super::SignalDef::*;
 use super::super::super::super::task::*;
 use super::super::super::attr::*;
-use super::super::super::file::*;
-use super::super::super::flags::*;
-use super::super::super::dirent::*;
 use super::super::super::mount::*;
 use super::super::super::inode::*;
 use super::super::super::super::threadmgr::thread::*;
 use super::super::super::super::threadmgr::pid_namespace::*;
 use super::super::inode::*;
+use super::super::seqfile::*;
 
 pub fn NewStatus(task: &Task, thread: &Thread, msrc: &Arc<QMutex<MountSource>>) -> Inode {
-    let v = NewStatusSimpleFileInode(task, thread, &ROOT_OWNER, &FilePermissions::FromMode(FileMode(0o400)), FSMagic::PROC_SUPER_MAGIC);
-    return NewProcInode(&Arc::new(v), msrc, InodeType::SpecialFile, Some(thread.clone()))
-
-}
-
-pub fn NewStatusSimpleFileInode(task: &Task,
-                                thread: &Thread,
-                               owner: &FileOwner,
-                               perms: &FilePermissions,
-                               typ: u64)
-                               -> SimpleFileInode<StatusData> {
     let kernel = GetKernel();
     let pidns = kernel.RootPIDNamespace();
 
@@ -54,7 +37,8 @@ pub fn NewStatusSimpleFileInode(task: &Task,
         pidns: pidns,
     };
 
-    return SimpleFileInode::New(task, owner, perms, typ, false, status)
+    let seqFile = SeqFile::New(task, Arc::new(QMutex::new(status)));
+    return NewProcInode(&Arc::new(seqFile), msrc, InodeType::SpecialFile, Some(thread.clone()))
 }
 
 pub struct StatusData {
@@ -66,9 +50,12 @@ impl StatusData {
     pub fn GenSnapshot(&self, _task: &Task) -> Vec<u8> {
         let mut ret = "".to_string();
 
-        ret += &format!("Name:\t{}\n", self.thread.Name());
-        // todo: handle thread state
-        //ret += &format!("State:\t{}\n", self.thread.Name());
+        // a newline or backslash in the name is escaped, see
+        // fs/proc/array.c:proc_task_name.
+        let name = self.thread.Name().replace("\\", "\\\\").replace("\n", "\\n");
+        ret += &format!("Name:\t{}\n", name);
+        let state = self.thread.lock().StateStatus().to_string();
+        ret += &format!("State:\t{}\n", state);
 
         let tg = self.thread.ThreadGroup();
         ret += &format!("Tgid:\t{}\n", self.pidns.IDOfThreadGroup(&tg));
@@ -92,11 +79,40 @@ impl StatusData {
         let _ml = mm.MappingReadLock();
 
         let vss = mm.VirtualMemorySizeLocked();
+        let lck = mm.LockedMemorySizeLocked();
         let rss = mm.ResidentSetSizeLocked();
         ret += &format!("VmSize:\t{} kB\n", vss>>10);
+        ret += &format!("VmLck:\t{} kB\n", lck>>10);
         ret += &format!("VmRSS:\t{} kB\n", rss>>10);
         ret += &format!("Threads:\t{}\n", tg.Count());
 
+        // As on Linux, SigPnd only shows signals directed at this thread;
+        // those sent to the whole thread group are in ShdPnd.
+        let (sigPnd, shdPnd) = {
+            let owner = self.pidns.lock().owner.clone();
+            let _r = owner.read();
+
+            let lock = tg.lock().signalLock.clone();
+            let _s = lock.lock();
+            (self.thread.lock().pendingSignals.pendingSet.0, tg.lock().pendingSignals.pendingSet.0)
+        };
+
+        let mut sigIgn : u64 = 0;
+        let mut sigCgt : u64 = 0;
+        for (sig, act) in &tg.SignalHandlers().lock().actions {
+            match act.handler {
+                SigAct::SIGNAL_ACT_DEFAULT => (),
+                SigAct::SIGNAL_ACT_IGNORE => sigIgn |= 1 << (*sig - 1),
+                _ => sigCgt |= 1 << (*sig - 1),
+            }
+        }
+
+        ret += &format!("SigPnd:\t{:016x}\n", sigPnd);
+        ret += &format!("ShdPnd:\t{:016x}\n", shdPnd);
+        ret += &format!("SigBlk:\t{:016x}\n", self.thread.SignalMask().0);
+        ret += &format!("SigIgn:\t{:016x}\n", sigIgn);
+        ret += &format!("SigCgt:\t{:016x}\n", sigCgt);
+
         let creds = self.thread.Credentials();
         ret += &format!("CapInh:\t{:016x}\n", creds.lock().InheritableCaps.0);
         ret += &format!("CapPrm:\t{:016x}\n", creds.lock().PermittedCaps.0);
@@ -111,10 +127,22 @@ impl StatusData {
     }
 }
 
-impl SimpleFileTrait for StatusData {
-    fn GetFile(&self, task: &Task, _dir: &Inode, dirent: &Dirent, flags: FileFlags) -> Result<File> {
-        let fops = NewSnapshotReadonlyFileOperations(self.GenSnapshot(task));
-        let file = File::New(dirent, &flags, fops);
-        return Ok(file);
+impl SeqSource for StatusData {
+    // The status is regenerated on every read from the start of the file, so
+    // it always reflects the thread's current state.
+    fn NeedsUpdate(&mut self, _generation: i64) -> bool {
+        return true;
     }
-}
\ No newline at end of file
+
+    fn ReadSeqFileData(&mut self, task: &Task, handle: SeqHandle) -> (Vec<SeqData>, i64) {
+        match handle {
+            SeqHandle::None => (),
+            _ => return (Vec::new(), 0),
+        }
+
+        return (vec!(SeqData {
+            Buf: self.GenSnapshot(task),
+            Handle: SeqHandle::Simple(0),
+        }), 0)
+    }
+}
@@ -79,7 +79,7 @@ impl DirDataNode for SubTasksNode {
         }
 
         let ms = dir.lock().MountSource.clone();
-        let td = self.procNode.NewTaskDir(task, &self.thread, &ms, false);
+        let td = self.procNode.NewTaskDir(task, &thread, &ms, false);
         return Ok(Dirent::New(&td, name));
     }
 
@@ -42,6 +42,7 @@ use super::uid_pid_map::*;
 use super::io::*;
 use super::maps::*;
 use super::mounts::*;
+use super::namespace_symlink::*;
 use super::stat::*;
 use super::statm::*;
 use super::status::*;
@@ -77,6 +78,7 @@ impl ProcNode {
         contents.insert("maps".to_string(), NewMaps(task, thread, msrc));
         contents.insert("mountinfo".to_string(), NewMountInfoFile(task, thread, msrc));
         contents.insert("mounts".to_string(), NewMountsFile(task, thread, msrc));
+        contents.insert("ns".to_string(), NewNamespaceDir(task, thread, msrc));
         contents.insert("stat".to_string(), NewStat(task, thread, showSubtasks, self.lock().pidns.clone(), msrc));
         contents.insert("statm".to_string(), NewStatm(task, thread, msrc));
         contents.insert("status".to_string(), NewStatus(task, thread, msrc));
@@ -39,12 +39,25 @@ impl ReadonlyFileNode for UptimeFileNode {
             return Err(Error::SysError(SysErr::EINVAL))
         }
 
+        // the monotonic clock starts at zero at boot, the same source as the
+        // uptime of sysinfo(2).
         let kernel = GetKernel();
-        let startTime = kernel.startTime;
-        let now = task.Now();
+        let uptime = Task::MonoTimeNow().Nanoseconds();
 
-        let dur = now.Sub(startTime) / SECOND;
-        let s = format!("{}", dur);
+        // idle is the vcpu time not spent on any live process.
+        let mut busy = 0;
+        for tg in kernel.tasks.Root().ThreadGroups() {
+            let cs = tg.CPUStats();
+            busy += cs.UserTime + cs.SysTime;
+        }
+        let mut idle = uptime * kernel.ApplicationCores() as i64 - busy;
+        if idle < 0 {
+            idle = 0;
+        }
+
+        let s = format!("{}.{:02} {}.{:02}\n",
+                        uptime / SECOND, uptime % SECOND / (10 * MILLISECOND),
+                        idle / SECOND, idle % SECOND / (10 * MILLISECOND));
         let bytes = s.as_bytes();
         if offset as usize > bytes.len() {
             return Ok(0)
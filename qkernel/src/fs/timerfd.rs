@@ -62,6 +62,10 @@ impl TimerOperationsInternal {
 impl TimerListener for TimerOperationsInternal {
     // Notify implements TimerListener.Notify.
     fn Notify(&self, exp: u64) {
+        if exp == 0 {
+            return
+        }
+
         {
             *self.val.lock() += exp;
         };
@@ -81,7 +85,9 @@ pub fn NewTimerfd(task: &Task, clockId: i32) -> Result<File> {
     let internal = Arc::new(TimerOperationsInternal::New());
 
     let clock = match clockId {
-        CLOCK_MONOTONIC => MONOTONIC_CLOCK.clone(),
+        // CLOCK_BOOTTIME is approximated by CLOCK_MONOTONIC, as there is no
+        // suspend in the sandbox.
+        CLOCK_MONOTONIC | CLOCK_BOOTTIME => MONOTONIC_CLOCK.clone(),
         CLOCK_REALTIME => REALTIME_CLOCK.clone(),
         _ => return Err(Error::SysError(SysErr::EINVAL))
     };
@@ -147,6 +153,14 @@ impl TimerOperations {
     }
 }
 
+impl Drop for TimerOperations {
+    // Stop the timer once the last reference to the timerfd is gone,
+    // otherwise it would keep firing for a file nobody can read.
+    fn drop(&mut self) {
+        self.timer.Destroy();
+    }
+}
+
 impl Waitable for TimerOperations {
     // Readiness returns the ready events for the event fd.
     fn Readiness(&self, _task: &Task, mask: EventMask) -> EventMask {
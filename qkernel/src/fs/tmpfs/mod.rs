@@ -16,6 +16,7 @@ pub mod tmpfs_dir;
 pub mod tmpfs_symlink;
 pub mod tmpfs_socket;
 pub mod tmpfs_file;
+pub mod tmpfs_memfd;
 pub mod tmpfs_fifo;
 pub mod fs;
 
@@ -0,0 +1,399 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::sync::Arc;
+use ::qlib::mutex::*;
+use core::any::Any;
+
+use socket::unix::transport::unix::BoundEndpoint;
+use super::super::super::qlib::common::*;
+use super::super::super::qlib::linux_def::*;
+use super::super::super::qlib::auth::*;
+use super::super::super::kernel::time::*;
+use super::super::super::kernel::waiter::*;
+use super::super::super::task::*;
+use super::super::attr::*;
+use super::super::mount::*;
+use super::super::flags::*;
+use super::super::file::*;
+use super::super::inode::*;
+use super::super::dirent::*;
+use super::super::dentry::*;
+use super::super::host::hostinodeop::*;
+use super::super::host::hostfileop::*;
+use super::tmpfs_file::*;
+
+pub const MEMFD_ALL_SEALS: u32 = SealFlag::F_SEAL_SEAL | SealFlag::F_SEAL_SHRINK |
+    SealFlag::F_SEAL_GROW | SealFlag::F_SEAL_WRITE | SealFlag::F_SEAL_FUTURE_WRITE;
+
+// NewMemfd creates a new anonymous tmpfs file for memfd_create(2). Without
+// allowSeals the file starts with F_SEAL_SEAL, so that no seal can be added.
+pub fn NewMemfd(task: &Task, name: &str, allowSeals: bool) -> Result<File> {
+    let msrc = Arc::new(QMutex::new(MountSource::NewPseudoMountSource()));
+    let uattr = UnstableAttr {
+        Owner: task.FileOwner(),
+        Perms: FilePermissions::FromMode(FileMode(0o777)),
+        Links: 1,
+        ..Default::default()
+    };
+    let uattr = WithCurrentTime(task, &uattr);
+
+    let inode = NewTmpfsFileInode(task, uattr, &msrc)?;
+
+    let seals = if allowSeals {
+        0
+    } else {
+        SealFlag::F_SEAL_SEAL
+    };
+
+    let iops = inode.lock().InodeOp.clone();
+    inode.lock().InodeOp = Arc::new(MemfdInodeOp {
+        inodeops: iops,
+        seals: Arc::new(QMutex::new(seals)),
+    });
+
+    let dirent = Dirent::New(&inode, name);
+    return inode.GetFile(task, &dirent, &FileFlags {
+        Read: true,
+        Write: true,
+        ..Default::default()
+    })
+}
+
+// GetSeals returns the seals of a memfd, see fcntl(2) F_GET_SEALS.
+pub fn GetSeals(file: &File) -> Result<u32> {
+    let fops = file.FileOp.clone();
+    match fops.as_any().downcast_ref::<MemfdFileOperations>() {
+        None => return Err(Error::SysError(SysErr::EINVAL)),
+        Some(memfd) => return Ok(*memfd.seals.lock()),
+    }
+}
+
+// AddSeals adds seals to a memfd, see fcntl(2) F_ADD_SEALS.
+pub fn AddSeals(file: &File, val: u32) -> Result<()> {
+    let fops = file.FileOp.clone();
+    let memfd = match fops.as_any().downcast_ref::<MemfdFileOperations>() {
+        None => return Err(Error::SysError(SysErr::EINVAL)),
+        Some(memfd) => memfd,
+    };
+
+    if val & !MEMFD_ALL_SEALS != 0 {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
+
+    // Seals can only be added through a writable file.
+    if !file.Flags().Write {
+        return Err(Error::SysError(SysErr::EPERM))
+    }
+
+    let mut seals = memfd.seals.lock();
+    if *seals & SealFlag::F_SEAL_SEAL != 0 {
+        return Err(Error::SysError(SysErr::EPERM))
+    }
+
+    // F_SEAL_WRITE can't be added while the file is mapped shared and
+    // writable, as the mapping could still change the contents.
+    if val & SealFlag::F_SEAL_WRITE != 0 && *seals & SealFlag::F_SEAL_WRITE == 0 {
+        if memfd.fops.InodeOp.HasWriteableMapping() {
+            return Err(Error::SysError(SysErr::EBUSY))
+        }
+    }
+
+    *seals |= val;
+    return Ok(())
+}
+
+pub struct MemfdInodeOp {
+    pub inodeops: Arc<InodeOperations>,
+    pub seals: Arc<QMutex<u32>>,
+}
+
+impl InodeOperations for MemfdInodeOp {
+    fn as_any(&self) -> &Any {
+        return self
+    }
+
+    fn IopsType(&self) -> IopsType {
+        return IopsType::MemfdInodeOp;
+    }
+
+    fn InodeType(&self) -> InodeType {
+        return self.inodeops.InodeType();
+    }
+
+    fn InodeFileType(&self) -> InodeFileType{
+        return self.inodeops.InodeFileType();
+    }
+
+    fn WouldBlock(&self) -> bool {
+        return self.inodeops.WouldBlock();
+    }
+
+    fn Lookup(&self, task: &Task, dir: &Inode, name: &str) -> Result<Dirent> {
+        return self.inodeops.Lookup(task, dir, name)
+    }
+
+    fn Create(&self, task: &Task, dir: &mut Inode, name: &str, flags: &FileFlags, perm: &FilePermissions) -> Result<File> {
+        return self.inodeops.Create(task, dir, name, flags, perm)
+    }
+
+    fn CreateDirectory(&self, task: &Task, dir: &mut Inode, name: &str, perm: &FilePermissions) -> Result<()> {
+        return self.inodeops.CreateDirectory(task, dir, name, perm)
+    }
+
+    fn CreateLink(&self, task: &Task, dir: &mut Inode, oldname: &str, newname: &str) -> Result<()> {
+        return self.inodeops.CreateLink(task, dir, oldname, newname)
+    }
+
+    fn CreateHardLink(&self, task: &Task, dir: &mut Inode, target: &Inode, name: &str) -> Result<()> {
+        return self.inodeops.CreateHardLink(task, dir, target, name)
+    }
+
+    fn CreateFifo(&self, task: &Task, dir: &mut Inode, name: &str, perm: &FilePermissions) -> Result<()> {
+        return self.inodeops.CreateFifo(task, dir, name, perm)
+    }
+
+    fn Remove(&self, task: &Task, dir: &mut Inode, name: &str) -> Result<()> {
+        return self.inodeops.Remove(task, dir, name)
+    }
+
+    fn RemoveDirectory(&self, task: &Task, dir: &mut Inode, name: &str) -> Result<()>{
+        return self.inodeops.RemoveDirectory(task, dir, name)
+    }
+
+    fn Rename(&self, task: &Task, dir: &mut Inode, oldParent: &Inode, oldname: &str, newParent: &Inode, newname: &str, replacement: bool) -> Result<()> {
+        return self.inodeops.Rename(task, dir, oldParent, oldname, newParent, newname, replacement)
+    }
+
+    fn Bind(&self, task: &Task, dir: &Inode, name: &str, data: &BoundEndpoint, perms: &FilePermissions) -> Result<Dirent> {
+        return self.inodeops.Bind(task, dir, name, data, perms)
+    }
+
+    fn BoundEndpoint(&self, task: &Task, inode: &Inode, path: &str) -> Option<BoundEndpoint> {
+        return self.inodeops.BoundEndpoint(task, inode, path)
+    }
+
+    fn GetFile(&self, task: &Task, _dir: &Inode, dirent: &Dirent, flags: FileFlags) -> Result<File> {
+        let iops = self.inodeops.Mappable()?;
+
+        let mut flags = flags;
+        flags.Pread = true;
+        flags.PWrite = true;
+
+        let fops = MemfdFileOperations {
+            fops: iops.GetHostFileOp(task),
+            seals: self.seals.clone(),
+        };
+
+        return Ok(File::New(dirent, &flags, fops))
+    }
+
+    fn UnstableAttr(&self, task: &Task, dir: &Inode) -> Result<UnstableAttr> {
+        return self.inodeops.UnstableAttr(task, dir)
+    }
+
+    fn Getxattr(&self, dir: &Inode, name: &str) -> Result<String> {
+        return self.inodeops.Getxattr(dir, name)
+    }
+
+    fn Setxattr(&self, dir: &mut Inode, name: &str, value: &str) -> Result<()> {
+        return self.inodeops.Setxattr(dir, name, value)
+    }
+
+    fn Listxattr(&self, dir: &Inode) -> Result<Vec<String>> {
+        return self.inodeops.Listxattr(dir)
+    }
+
+    fn Check(&self, task: &Task, inode: &Inode, reqPerms: &PermMask) -> Result<bool> {
+        return self.inodeops.Check(task, inode, reqPerms)
+    }
+
+    fn SetPermissions(&self, task: &Task, dir: &mut Inode, f: FilePermissions) -> bool {
+        return self.inodeops.SetPermissions(task, dir, f)
+    }
+
+    fn SetOwner(&self, task: &Task, dir: &mut Inode, owner: &FileOwner) -> Result<()> {
+        return self.inodeops.SetOwner(task, dir, owner)
+    }
+
+    fn SetTimestamps(&self, task: &Task, dir: &mut Inode, ts: &InterTimeSpec) -> Result<()> {
+        return self.inodeops.SetTimestamps(task, dir, ts)
+    }
+
+    fn Truncate(&self, task: &Task, dir: &mut Inode, size: i64) -> Result<()> {
+        let oldSize = self.inodeops.UnstableAttr(task, dir)?.Size;
+        let seals = *self.seals.lock();
+        if size < oldSize && seals & SealFlag::F_SEAL_SHRINK != 0 {
+            return Err(Error::SysError(SysErr::EPERM))
+        }
+
+        if size > oldSize && seals & SealFlag::F_SEAL_GROW != 0 {
+            return Err(Error::SysError(SysErr::EPERM))
+        }
+
+        return self.inodeops.Truncate(task, dir, size)
+    }
+
+    fn Allocate(&self, task: &Task, dir: &mut Inode, offset: i64, length: i64) -> Result<()> {
+        if *self.seals.lock() & SealFlag::F_SEAL_GROW != 0 {
+            let size = self.inodeops.UnstableAttr(task, dir)?.Size;
+            if offset + length > size {
+                return Err(Error::SysError(SysErr::EPERM))
+            }
+        }
+
+        return self.inodeops.Allocate(task, dir, offset, length)
+    }
+
+    fn ReadLink(&self, task: &Task,dir: &Inode) -> Result<String> {
+        return self.inodeops.ReadLink(task, dir)
+    }
+
+    fn GetLink(&self, task: &Task, dir: &Inode) -> Result<Dirent> {
+        return self.inodeops.GetLink(task, dir)
+    }
+
+    fn AddLink(&self, task: &Task) {
+        self.inodeops.AddLink(task)
+    }
+
+    fn DropLink(&self, task: &Task) {
+        self.inodeops.DropLink(task)
+    }
+
+    fn IsVirtual(&self) -> bool {
+        return true;
+    }
+
+    fn Sync(&self) -> Result<()> {
+        return self.inodeops.Sync()
+    }
+
+    fn StatFS(&self, task: &Task) -> Result<FsInfo> {
+        return self.inodeops.StatFS(task)
+    }
+
+    fn Mappable(&self) -> Result<HostInodeOp> {
+        return self.inodeops.Mappable()
+    }
+}
+
+// MemfdFileOperations is the file of a memfd. Its data is kept in a host
+// memfd, so it can be mapped shared by every process holding the file.
+pub struct MemfdFileOperations {
+    pub fops: Arc<HostFileOp>,
+    pub seals: Arc<QMutex<u32>>,
+}
+
+impl MemfdFileOperations {
+    // checkWrite checks a write of len bytes at offset against the seals.
+    fn checkWrite(&self, offset: i64, len: i64) -> Result<()> {
+        let seals = *self.seals.lock();
+        if seals & (SealFlag::F_SEAL_WRITE | SealFlag::F_SEAL_FUTURE_WRITE) != 0 {
+            return Err(Error::SysError(SysErr::EPERM))
+        }
+
+        if seals & SealFlag::F_SEAL_GROW != 0 {
+            let (size, _) = self.fops.InodeOp.Size()?;
+            if offset + len > size {
+                return Err(Error::SysError(SysErr::EPERM))
+            }
+        }
+
+        return Ok(())
+    }
+}
+
+impl Waitable for MemfdFileOperations {
+    fn Readiness(&self, _task: &Task, mask: EventMask) -> EventMask {
+        return mask
+    }
+
+    fn EventRegister(&self, _task: &Task, _e: &WaitEntry, _mask: EventMask) {}
+
+    fn EventUnregister(&self, _task: &Task, _e: &WaitEntry) {}
+}
+
+impl SpliceOperations for MemfdFileOperations {}
+
+impl FileOperations for MemfdFileOperations {
+    fn as_any(&self) -> &Any {
+        return self
+    }
+
+    fn FopsType(&self) -> FileOpsType {
+        return FileOpsType::MemfdFileOperations
+    }
+
+    fn Seekable(&self) -> bool {
+        return true;
+    }
+
+    fn Seek(&self, task: &Task, f: &File, whence: i32, current: i64, offset: i64) -> Result<i64> {
+        return self.fops.Seek(task, f, whence, current, offset)
+    }
+
+    fn ReadDir(&self, _task: &Task, _f: &File, _offset: i64, _serializer: &mut DentrySerializer) -> Result<i64> {
+        return Err(Error::SysError(SysErr::ENOTDIR))
+    }
+
+    fn ReadAt(&self, task: &Task, f: &File, dsts: &mut [IoVec], offset: i64, blocking: bool) -> Result<i64> {
+        return self.fops.ReadAt(task, f, dsts, offset, blocking)
+    }
+
+    fn WriteAt(&self, task: &Task, f: &File, srcs: &[IoVec], offset: i64, blocking: bool) -> Result<i64> {
+        self.checkWrite(offset, IoVec::NumBytes(srcs) as i64)?;
+        return self.fops.WriteAt(task, f, srcs, offset, blocking)
+    }
+
+    fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
+        let (size, _) = self.fops.InodeOp.Size()?;
+        self.checkWrite(size, IoVec::NumBytes(srcs) as i64)?;
+        return self.fops.Append(task, f, srcs)
+    }
+
+    fn Fsync(&self, task: &Task, f: &File, start: i64, end: i64, syncType: SyncType) -> Result<()> {
+        return self.fops.Fsync(task, f, start, end, syncType)
+    }
+
+    fn Flush(&self, _task: &Task, _f: &File) -> Result<()> {
+        return Ok(())
+    }
+
+    fn UnstableAttr(&self, task: &Task, f: &File) -> Result<UnstableAttr> {
+        return self.fops.UnstableAttr(task, f)
+    }
+
+    fn Ioctl(&self, _task: &Task, _f: &File, _fd: i32, _request: u64, _val: u64) -> Result<()> {
+        return Err(Error::SysError(SysErr::ENOTTY))
+    }
+
+    fn IterateDir(&self, _task: &Task, _d: &Dirent, _dirCtx: &mut DirCtx, offset: i32) -> (i32, Result<i64>) {
+        return (offset, Err(Error::SysError(SysErr::ENOTDIR)))
+    }
+
+    fn Mappable(&self) -> Result<HostInodeOp> {
+        return self.fops.Mappable()
+    }
+
+    // The memfd inode checks F_SEAL_SHRINK and F_SEAL_GROW.
+    fn Truncate(&self, task: &Task, f: &File, size: i64) -> Result<()> {
+        let mut inode = f.Dirent.Inode();
+        return inode.Truncate(task, &f.Dirent, size)
+    }
+}
+
+impl SockOperations for MemfdFileOperations {}
@@ -248,6 +248,13 @@ pub struct MasterFileOperations {
     pub t: Arc<Terminal>,
 }
 
+impl Drop for MasterFileOperations {
+    fn drop(&mut self) {
+        // The last reference to the master is gone, so the terminal hangs up.
+        self.t.Hangup();
+    }
+}
+
 impl Waitable for MasterFileOperations {
     fn Readiness(&self, _task: &Task,_mask: EventMask) -> EventMask {
         panic!("MasterFileOperations doesn't support Waitable::Readiness");
@@ -369,6 +369,15 @@ impl FileOperations for SlaveFileOperations {
                 //Implement pty locking. For now just pretend we do.
                 return Ok(())
             }
+            IoCtlCmd::TIOCGPGRP => {
+                let pgid = self.d.read().t.ForegroundProcessGroupID(task)?;
+                task.CopyOutObj(&pgid, val)?;
+                return Ok(())
+            }
+            IoCtlCmd::TIOCSPGRP => {
+                let pgid: i32 = task.CopyInObj(val)?;
+                return self.d.read().t.SetForegroundProcessGroupID(task, pgid)
+            }
             IoCtlCmd::TIOCGWINSZ => {
                 //This should drain the output queue first.
                 return self.d.read().t.ld.lock().GetWindowSize(task, val)
@@ -15,6 +15,11 @@
 use alloc::sync::Arc;
 use ::qlib::mutex::*;
 
+use super::super::super::qlib::common::*;
+use super::super::super::qlib::linux_def::*;
+use super::super::super::task::*;
+use super::super::super::threadmgr::processgroup::*;
+use super::super::super::SignalDef::*;
 use super::super::host::tty::*;
 use super::dir::*;
 use super::line_discipline::*;
@@ -23,6 +28,10 @@ pub struct Terminal {
     pub n: u32,
     pub d: DirInodeOperations,
     pub ld: Arc<QMutex<LineDiscipline>>,
+
+    // fgProcessgroup is the foreground process group of the terminal, as
+    // set through the slave with TIOCSPGRP.
+    pub fgProcessgroup: QMutex<Option<ProcessGroup>>,
 }
 
 impl Terminal {
@@ -30,7 +39,52 @@ impl Terminal {
         return Self {
             d: d.clone(),
             n: n,
-            ld: Arc::new(QMutex::new(LineDiscipline::New(DEFAULT_SLAVE_TERMIOS)))
+            ld: Arc::new(QMutex::new(LineDiscipline::New(DEFAULT_SLAVE_TERMIOS))),
+            fgProcessgroup: QMutex::new(None),
+        }
+    }
+
+    pub fn ForegroundProcessGroupID(&self, task: &Task) -> Result<i32> {
+        let pg = match self.fgProcessgroup.lock().clone() {
+            None => return Err(Error::SysError(SysErr::ENOTTY)),
+            Some(pg) => pg,
+        };
+
+        let pidns = task.Thread().PIDNamespace();
+        return Ok(pidns.IDOfProcessGroup(&pg))
+    }
+
+    pub fn SetForegroundProcessGroupID(&self, task: &Task, pgid: i32) -> Result<()> {
+        if pgid < 0 {
+            return Err(Error::SysError(SysErr::EINVAL))
         }
+
+        let thread = task.Thread();
+        let pidns = thread.PIDNamespace();
+        let pg = match pidns.ProcessGroupWithID(pgid) {
+            None => return Err(Error::SysError(SysErr::ESRCH)),
+            Some(pg) => pg,
+        };
+
+        // The new foreground process group must be in the caller's session.
+        if Some(pg.Session()) != thread.ThreadGroup().Session() {
+            return Err(Error::SysError(SysErr::EPERM))
+        }
+
+        *self.fgProcessgroup.lock() = Some(pg);
+        return Ok(())
+    }
+
+    // Hangup is called when the master side is closed. Like Linux's
+    // tty_vhangup(), it sends SIGHUP and then SIGCONT to the foreground
+    // process group, if there is one.
+    pub fn Hangup(&self) {
+        let pg = match self.fgProcessgroup.lock().take() {
+            None => return,
+            Some(pg) => pg,
+        };
+
+        pg.SendSignal(&SignalInfo::SignalInfoPriv(Signal(Signal::SIGHUP))).ok();
+        pg.SendSignal(&SignalInfo::SignalInfoPriv(Signal(Signal::SIGCONT))).ok();
     }
 }
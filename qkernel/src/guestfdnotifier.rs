@@ -18,11 +18,13 @@ use core::ops::Deref;
 
 use super::Kernel::HostSpace;
 use super::kernel::waiter::*;
+use super::kernel::async_wait::*;
 use super::fs::host::hostinodeop::*;
 use super::qlib::common::*;
 use super::qlib::linux_def::*;
 use super::qlib::singleton::*;
 use super::IOURING;
+use super::SHARESPACE;
 
 pub static GUEST_NOTIFIER : Singleton<Notifier> = Singleton::<Notifier>::New();
 
@@ -42,10 +44,26 @@ pub fn NonBlockingPoll(fd: i32, mask: EventMask) -> EventMask {
     return HostSpace::NonBlockingPoll(fd, mask) as EventMask
 }
 
+// AsyncPoll polls fd for mask with an io_uring POLL_ADD. A host kernel
+// without IORING_OP_POLL_ADD is polled directly instead.
+pub fn AsyncPoll(fd: i32, mask: EventMask, wait: &MultiWait) -> Future<EventMask> {
+    if !SHARESPACE.uringCaps.SupportsPollAdd() {
+        let future = Future::New(0 as EventMask);
+        future.Set(Ok(NonBlockingPoll(fd, mask)));
+        return future;
+    }
+
+    return IOURING.UnblockPollAdd(fd, mask as u32, wait);
+}
+
 pub fn Notify(fd: i32, mask: EventMask) {
     GUEST_NOTIFIER.Notify(fd, mask);
 }
 
+pub fn PollDone(fd: i32, idx: usize) {
+    GUEST_NOTIFIER.PollDone(fd, idx);
+}
+
 pub fn IOBufWriteRespHandle(fd: i32, addr: u64, len: usize, ret: i64) {
     GUEST_NOTIFIER.IOBufWriteRespHandle(fd, addr, len, ret)
 }
@@ -60,6 +78,9 @@ pub struct GuestFdInfo {
     pub mask: EventMask,
     pub waiting: bool,
     pub iops: HostInodeOpWeak,
+    // pollIdx is the uring slot of the multishot poll watching the fd, if
+    // there is one.
+    pub pollIdx: Option<usize>,
 }
 
 // notifier holds all the state necessary to issue notifications when IO events
@@ -98,6 +119,11 @@ impl Notifier {
     }
 
     pub fn UpdateFDAsync(&self, fd: i32) -> Result<()> {
+        // fall back to the host epoll when io_uring can't update it
+        if !SHARESPACE.uringCaps.SupportsEpollCtl() {
+            return self.UpdateFD(fd)
+        }
+
         let op;
         let epollfd;
         let mask = {
@@ -139,6 +165,10 @@ impl Notifier {
     }
 
     pub fn UpdateFD(&self, fd: i32) -> Result<()> {
+        if SHARESPACE.config.read().MultishotPoll && SHARESPACE.uringCaps.SupportsMultishotPoll() {
+            return self.UpdateFDMultishot(fd)
+        }
+
         let mask = {
             let mut n = self.lock();
             let fi = match n.fdMap.get_mut(&fd) {
@@ -172,6 +202,92 @@ impl Notifier {
         return Self::waitfd(fd, mask);
     }
 
+    // UpdateFDMultishot watches fd with a multishot io_uring poll. The poll is
+    // added once and stays armed until the fd is removed, even when nobody
+    // waits on it any more, so its slot, which is the user data of its
+    // completions, doesn't change. Its events only grow, in place, when a
+    // waiter needs new ones.
+    fn UpdateFDMultishot(&self, fd: i32) -> Result<()> {
+        let mut update = None;
+        let mask = {
+            let mut n = self.lock();
+            let fi = match n.fdMap.get_mut(&fd) {
+                None => {
+                    return Ok(())
+                }
+                Some(fi) => fi,
+            };
+
+            let mask = fi.queue.Events();
+            if mask | fi.mask == fi.mask {
+                return Ok(())
+            }
+
+            fi.mask |= mask;
+            if fi.waiting {
+                // The poll is being added and picks up the new mask once it
+                // has its slot.
+                match fi.pollIdx {
+                    None => return Ok(()),
+                    Some(idx) => update = Some(idx),
+                }
+            }
+
+            fi.waiting = true;
+            fi.mask
+        };
+
+        if let Some(idx) = update {
+            IOURING.PollUpdate(idx, mask as u32);
+            return Ok(())
+        }
+
+        let idx = IOURING.PollMulti(fd, mask as u32);
+        let newMask = {
+            let mut n = self.lock();
+            match n.fdMap.get_mut(&fd) {
+                Some(fi) if fi.waiting && fi.pollIdx.is_none() => {
+                    fi.pollIdx = Some(idx);
+                    fi.mask
+                }
+                _ => 0,
+            }
+        };
+
+        if newMask == 0 {
+            // The fd was removed, or its poll replaced, while this one was
+            // being added.
+            IOURING.PollRemove(idx);
+        } else if newMask != mask {
+            IOURING.PollUpdate(idx, newMask as u32);
+        }
+
+        return Ok(())
+    }
+
+    // PollDone is called with the last completion of the multishot poll in
+    // slot idx. Unless the fd was removed, the host ended the poll, e.g. on an
+    // error, and it is added again if there are waiters.
+    pub fn PollDone(&self, fd: i32, idx: usize) {
+        {
+            let mut n = self.lock();
+            let fi = match n.fdMap.get_mut(&fd) {
+                None => return,
+                Some(fi) => fi,
+            };
+
+            if fi.pollIdx != Some(idx) {
+                return
+            }
+
+            fi.pollIdx = None;
+            fi.waiting = false;
+            fi.mask = 0;
+        }
+
+        self.UpdateFD(fd).ok();
+    }
+
     pub fn AddFD(&self, fd: i32, iops: &HostInodeOp) {
         let mut n = self.lock();
 
@@ -186,12 +302,21 @@ impl Notifier {
             mask: 0,
             waiting: false,
             iops: iops.Downgrade(),
+            pollIdx: None,
         });
     }
 
     pub fn RemoveFD(&self, fd: i32) {
-        let mut n = self.lock();
-        n.fdMap.remove(&fd);
+        let fi = match self.lock().fdMap.remove(&fd) {
+            None => return,
+            Some(fi) => fi,
+        };
+
+        // A multishot poll holds on to the host file, so it has to be removed
+        // for the file to be released.
+        if let Some(idx) = fi.pollIdx {
+            IOURING.PollRemove(idx);
+        }
     }
 
     pub fn Notify(&self, fd: i32, mask: EventMask) {
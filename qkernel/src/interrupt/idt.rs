@@ -21,11 +21,11 @@ use x86_64::addr::VirtAddr;
 #[derive(Clone)]
 #[repr(C)]
 #[repr(align(0x100))]
-pub struct Idt([Entry; 32]);
+pub struct Idt([Entry; 256]);
 
 impl Idt {
     pub fn new() -> Idt {
-        Idt([Entry::missing(); 32])
+        Idt([Entry::missing(); 256])
     }
 
     pub fn set_handler(&mut self, entry: u8, handler: HandlerFunc) -> &mut EntryOptions {
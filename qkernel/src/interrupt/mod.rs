@@ -26,6 +26,8 @@ use super::asm::*;
 use super::qlib::perf_tunning::*;
 use super::SHARESPACE;
 use super::qlib::singleton::*;
+use super::qlib::vcpu_mgr::*;
+use super::qlib::TLB_SHOOTDOWN_VECTOR;
 
 #[derive(Clone, Copy, Debug)]
 pub enum ExceptionStackVec {
@@ -80,6 +82,7 @@ extern "C" {
     pub fn simd_fp_handler();
     pub fn virtualization_handler();
     pub fn security_handler();
+    pub fn tlb_shootdown_handler();
 }
 
 pub static IDT : Singleton<idt::Idt> = Singleton::<idt::Idt>::New();
@@ -112,6 +115,8 @@ pub unsafe fn InitSingleton() {
 
     idt.set_handler(30, security_handler).set_stack_index(0);
 
+    idt.set_handler(TLB_SHOOTDOWN_VECTOR as u8, tlb_shootdown_handler).set_stack_index(0);
+
     IDT.Init(idt);
 }
 
@@ -132,6 +137,7 @@ pub fn ExceptionHandler(ev: ExceptionStackVec, sf: &mut PtRegs, errorCode: u64)
     // is this call from user
     if sf.ss & 0x3 != 0 {
         SwapGs();
+        CPULocal::EnterKernel();
         //PerfGofrom(PerfType::User);
         currTask.AccountTaskLeave(SchedState::RunningApp);
     } else {
@@ -290,6 +296,7 @@ pub fn ExceptionHandler(ev: ExceptionStackVec, sf: &mut PtRegs, errorCode: u64)
 }
 
 pub fn ReturnToApp(pt: &mut PtRegs) -> ! {
+    CPULocal::FlushStaleTlb();
     let kernalRsp = pt as *const _ as u64;
     SyscallRet(kernalRsp);
 }
@@ -389,6 +396,7 @@ pub extern fn PageFaultHandler(ptRegs: &mut PtRegs, errorCode: u64) {
     // is this call from user
     let fromUser = if ptRegs.ss & 0x3 != 0 {
         SwapGs();
+        CPULocal::EnterKernel();
 
         let mut rflags = ptRegs.eflags;
         rflags &= !USER_FLAGS_CLEAR;
@@ -446,16 +454,20 @@ pub extern fn PageFaultHandler(ptRegs: &mut PtRegs, errorCode: u64) {
 
         let (vma, range) = match currTask.mm.GetVmaAndRangeLocked(cr2) {
             //vmas.lock().Get(cr2) {
-            None => {
-                if cr2 > 0x1000 {
-                    let map =  currTask.mm.GetSnapshotLocked(currTask, false);
-                    print!("the map is {}", &map);
+            // a fault just below the stack grows it
+            None => match currTask.mm.GrowStackLocked(currTask, cr2) {
+                Ok(vma) => vma,
+                Err(_) => {
+                    if cr2 > 0x1000 {
+                        let map =  currTask.mm.GetSnapshotLocked(currTask, false);
+                        print!("the map is {}", &map);
+                    }
+
+                    //todo: when to send sigbus/SIGSEGV
+                    signal = Signal::SIGSEGV;
+                    break;
                 }
-
-                //todo: when to send sigbus/SIGSEGV
-                signal = Signal::SIGSEGV;
-                break;
-            }
+            },
             Some(vma) => vma.clone(),
         };
 
@@ -515,6 +527,7 @@ pub extern fn PageFaultHandler(ptRegs: &mut PtRegs, errorCode: u64) {
                 if SHARESPACE.config.read().KernelPagetable {
                     currTask.SwitchPageTable();
                 }
+                CPULocal::FlushStaleTlb();
                 SwapGs();
             }
 
@@ -540,6 +553,7 @@ pub extern fn PageFaultHandler(ptRegs: &mut PtRegs, errorCode: u64) {
                 if SHARESPACE.config.read().KernelPagetable {
                     currTask.SwitchPageTable();
                 }
+                CPULocal::FlushStaleTlb();
                 SwapGs();
             }
         } else {
@@ -622,6 +636,22 @@ pub extern fn SecurityHandler(sf: &mut PtRegs, errorCode: u64) {
     ExceptionHandler(ExceptionStackVec::SecurityException, sf, errorCode);
 }
 
+// TlbShootdownHandler handles the interrupt a vcpu running user code gets
+// when another vcpu has changed the page tables, see CPULocal::ShootdownTlb.
+#[no_mangle]
+pub extern fn TlbShootdownHandler(sf: &mut PtRegs) {
+    let fromUser = sf.ss & 0x3 != 0;
+    if fromUser {
+        SwapGs();
+    }
+
+    CPULocal::FlushStaleTlb();
+
+    if fromUser {
+        SwapGs();
+    }
+}
+
 #[no_mangle]
 pub extern fn TripleFaultHandler(sf: &mut PtRegs) {
     info!("\nTripleFaultHandler: at {:#x}\n{:#?}",
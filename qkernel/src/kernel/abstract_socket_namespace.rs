@@ -16,6 +16,8 @@ use alloc::sync::Arc;
 use ::qlib::mutex::*;
 use alloc::collections::btree_map::BTreeMap;
 use core::ops::Deref;
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering;
 use alloc::vec::Vec;
 
 use super::super::socket::unix::transport::unix::*;
@@ -36,6 +38,22 @@ pub fn Bind(name: Vec<u8>, ep: &BoundEndpoint) -> Result<()> {
     return ABSTRACT_SOCKET.Bind(name, ep);
 }
 
+pub fn Autobind(ep: &BoundEndpoint) -> Result<Vec<u8>> {
+    return ABSTRACT_SOCKET.Autobind(ep);
+}
+
+pub fn Unbind(name: &Vec<u8>) {
+    ABSTRACT_SOCKET.Unbind(name);
+}
+
+// AUTOBIND_ORDER is the sequence autobound names are drawn from, like
+// ordernum in Linux's unix_autobind().
+static AUTOBIND_ORDER: AtomicU32 = AtomicU32::new(0);
+
+// AUTOBIND_NAMES is the number of distinct autobind names, "\0" followed by
+// five hex digits.
+const AUTOBIND_NAMES: u32 = 0x100000;
+
 #[derive(Clone, Default)]
 pub struct AbstractSocketNamespace(Arc<QMutex<BTreeMap<Vec<u8>, BoundEndpointWeak>>>);
 
@@ -92,4 +110,33 @@ impl AbstractSocketNamespace {
         a.insert(name, ep.Downgrade());
         return Ok(())
     }
+
+    // Autobind binds the given socket to an unused name made of a NUL byte and
+    // five hex digits, and returns the name.
+    pub fn Autobind(&self, ep: &BoundEndpoint) -> Result<Vec<u8>> {
+        let mut a = self.lock();
+
+        for _ in 0..AUTOBIND_NAMES {
+            let order = AUTOBIND_ORDER.fetch_add(1, Ordering::Relaxed) % AUTOBIND_NAMES;
+            let name = format!("\0{:05x}", order).into_bytes();
+
+            let inUse = match a.get(&name) {
+                None => false,
+                Some(b) => b.Upgrade().is_some(),
+            };
+
+            if !inUse {
+                a.insert(name.clone(), ep.Downgrade());
+                return Ok(name)
+            }
+        }
+
+        return Err(Error::SysError(SysErr::ENOSPC))
+    }
+
+    // Unbind removes the name when the socket bound to it is closed, so that
+    // it can be reused even while connections accepted from it live on.
+    pub fn Unbind(&self, name: &Vec<u8>) {
+        self.lock().remove(name);
+    }
 }
\ No newline at end of file
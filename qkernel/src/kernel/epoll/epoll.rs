@@ -294,7 +294,7 @@ impl EventPoll {
         // that's the value used by linux and we want to emulate it.
         if ep.is_some() {
             let ep = ep.unwrap();
-            if *ep == *self {
+            if *ep == *self || flags & EXCLUSIVE != 0 {
                 return Err(Error::SysError(SysErr::EINVAL))
             }
 
@@ -322,6 +322,7 @@ impl EventPoll {
 
         let entry = PollEntry(Arc::new(QMutex::new(entryInternal)));
         entry.lock().waiter.lock().context = WaitContext::EpollContext(entry.clone());
+        entry.lock().waiter.SetExclusive(flags & EXCLUSIVE != 0);
         files.insert(id, entry.clone());
 
         // Initialize the readiness state of the new entry.
@@ -339,6 +340,11 @@ impl EventPoll {
             Some(e) => e.clone(),
         };
 
+        // Exclusive entries can't be modified, see fs/eventpoll.c.
+        if entry.lock().flags & EXCLUSIVE != 0 {
+            return Err(Error::SysError(SysErr::EINVAL))
+        }
+
         // Unregister the old mask and remove entry from the list it's in, so
         // readyCallback is guaranteed to not be called on this entry anymore.
         let file = entry.lock().id.File.Upgrade();
@@ -25,6 +25,7 @@ pub type EntryFlags = i32;
 
 pub const ONE_SHOT : EntryFlags = 1 << 0;
 pub const EDGE_TRIGGERED: EntryFlags = 1 << 1;
+pub const EXCLUSIVE: EntryFlags = 1 << 2;
 
 #[derive(Clone)]
 pub struct FileIdentifier {
@@ -95,10 +96,16 @@ impl PollEntry {
         }
     }
 
+    // SetReady moves a waiting entry to the ready state and returns the state
+    // it was in. A disabled entry is a one-shot entry which has already been
+    // delivered; it stays disabled (and in the disabled list) until it is
+    // rearmed with EPOLL_CTL_MOD.
     pub fn SetReady(&self) -> PollEntryState {
         let mut e = self.lock();
         let oldstate = e.state;
-        e.state = PollEntryState::Ready;
+        if oldstate == PollEntryState::Waiting {
+            e.state = PollEntryState::Ready;
+        }
         return oldstate;
     }
 
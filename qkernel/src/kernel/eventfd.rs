@@ -205,7 +205,9 @@ impl FileOperations for EventOperations {
             return Err(Error::SysError(SysErr::EINVAL))
         }
 
-        let buf = DataBuff::New(size);
+        // Only the 8 byte counter is copied out; the rest of dsts is left
+        // untouched.
+        let buf = DataBuff::New(8);
         self.Read(task, buf.BlockSeq())?;
         task.CopyDataOutToIovs(&buf.buf, dsts)?;
         return Ok(8)
@@ -217,7 +219,7 @@ impl FileOperations for EventOperations {
             return Err(Error::SysError(SysErr::EINVAL))
         }
 
-        let mut buf = DataBuff::New(size);
+        let mut buf = DataBuff::New(8);
         task.CopyDataInFromIovs(&mut buf.buf, srcs)?;
 
         self.Write(task, buf.BlockSeq())?;
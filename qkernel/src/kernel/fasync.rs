@@ -37,6 +37,13 @@ pub struct FileAsyncInternal {
     pub recipientPG: Option<ProcessGroup>,
     pub recipientTG: Option<ThreadGroup>,
     pub recipientT: Option<Thread>,
+
+    // signal is the signal set by F_SETSIG. 0 means the default SIGIO.
+    pub signal: i32,
+
+    // fd is the file descriptor reported in si_fd when a non-default signal
+    // is delivered.
+    pub fd: i32,
 }
 
 #[derive(Clone, Default)]
@@ -51,7 +58,7 @@ impl Deref for FileAsync {
 }
 
 impl FileAsync {
-    pub fn Callback(&self) {
+    pub fn Callback(&self, mask: EventMask) {
         let a = self.lock();
 
         /*match a.e.lock().context {
@@ -87,7 +94,7 @@ impl FileAsync {
             reqC.EffectiveKUID == threadC.RealKUID ||
             reqC.RealKUID == threadC.SavedKUID ||
             reqC.RealKUID == threadC.RealKUID {
-            t.SendSignal(&SignalInfoPriv(SIGIO.0)).unwrap();
+            t.SendSignal(&a.signalInfo(mask)).unwrap();
         }
     }
 
@@ -161,6 +168,19 @@ impl FileAsync {
         a.recipientPG = recipient;
     }
 
+    // SetSignal sets the signal sent on IO readiness, as F_SETSIG. 0 restores
+    // the default SIGIO.
+    pub fn SetSignal(&self, signal: i32, fd: i32) {
+        let mut a = self.lock();
+        a.signal = signal;
+        a.fd = fd;
+    }
+
+    // Signal returns the signal set by F_SETSIG.
+    pub fn Signal(&self) -> i32 {
+        return self.lock().signal
+    }
+
     pub fn Unset(&self, requester: &Task) {
         let mut a = self.lock();
 
@@ -169,4 +189,38 @@ impl FileAsync {
         a.recipientTG = None;
         a.recipientPG = None;
     }
-}
\ No newline at end of file
+}
+
+impl FileAsyncInternal {
+    // signalInfo builds the signal to send for the events in mask. Like
+    // send_sigio_to_task in fs/fcntl.c, the default SIGIO carries no extra
+    // information while a signal chosen with F_SETSIG reports the reason,
+    // band and fd.
+    fn signalInfo(&self, mask: EventMask) -> SignalInfo {
+        if self.signal == 0 {
+            return SignalInfoPriv(SIGIO.0)
+        }
+
+        let code = if mask & EVENT_IN != 0 {
+            POLL_IN
+        } else if mask & EVENT_OUT != 0 {
+            POLL_OUT
+        } else if mask & EVENT_HUP != 0 {
+            POLL_HUP
+        } else {
+            POLL_ERR
+        };
+
+        let mut info = SignalInfo {
+            Signo: self.signal,
+            Code: code as i32,
+            ..Default::default()
+        };
+        info.FixSignalCodeForUser();
+
+        let poll = info.SigPoll();
+        poll.band = mask as i64;
+        poll.fd = self.fd;
+        return info
+    }
+}
@@ -175,13 +175,15 @@ impl FDTableInternal {
         return Ok(fds)
     }
 
-    pub fn NewFDAt(&mut self, fd: i32, file: &File, flags: &FDFlags) -> Result<()> {
+    // NewFDAt installs file at fd and returns the file it replaced, if any.
+    pub fn NewFDAt(&mut self, fd: i32, file: &File, flags: &FDFlags) -> Result<Option<File>> {
         if fd < 0 {
             return Err(Error::SysError(SysErr::EBADF))
         }
 
+        let old = self.descTbl.get(&fd).map(|d| d.file.clone());
         self.set(fd, file, flags);
-        return Ok(())
+        return Ok(old)
     }
 
     pub fn Dup(&mut self, fd: i32) -> Result<i32> {
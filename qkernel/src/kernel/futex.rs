@@ -593,9 +593,8 @@ impl FutexMgr {
     // calling task is set to 'addr' to indicate the futex is owned. It returns true
     // if the futex was successfully acquired.
     //
-    // FUTEX_OWNER_DIED is only set by the Linux when robust lists are in use (see
-    // exit_robust_list()). Given we don't support robust lists, although handled
-    // below, it's never set.
+    // FUTEX_OWNER_DIED is only set when robust lists are in use (see
+    // Thread.ExitRobustList), and is preserved when the futex is acquired.
     pub fn LockPI(&self, w: &WaitEntry, t: &Target, addr: u64, tid: u32, private: bool, try: bool) -> Result<bool> {
         let k = Getkey(t, addr, private)?;
 
@@ -13,30 +13,39 @@
 // limitations under the License.
 
 use super::semaphore;
-//use super::shm;
+use super::shm;
 use super::super::qlib::auth::userns::*;
+use super::super::uid::NewUID;
 
 #[derive(Clone, Default)]
 pub struct IPCNamespace {
+    // id identifies the namespace in /proc/[pid]/ns/ipc. Clones of an
+    // IPCNamespace share its registries, so they keep the id as well.
+    pub id: u64,
     pub userNS: UserNameSpace,
     pub semphores: semaphore::Registry,
-    //pub shms: shm::Registry,
+    pub shms: shm::Registry,
 }
 
 impl IPCNamespace {
     pub fn New(userNS: &UserNameSpace) -> Self {
         return Self {
+            id: NewUID(),
             userNS: userNS.clone(),
             semphores: semaphore::Registry::New(userNS),
-            //shms: shm::Registry::New(userNS)
+            shms: shm::Registry::New(userNS),
         }
     }
 
+    pub fn ID(&self) -> u64 {
+        return self.id
+    }
+
     pub fn SemaphoreRegistry(&self) -> semaphore::Registry {
         return self.semphores.clone()
     }
 
-    /*pub fn ShmRegistry(&self) -> shm::Registry {
+    pub fn ShmRegistry(&self) -> shm::Registry {
         return self.shms.clone()
-    }*/
+    }
 }
\ No newline at end of file
@@ -34,6 +34,8 @@ use super::super::qlib::auth::userns::*;
 use super::super::qlib::auth::*;
 use super::super::qlib::limits::*;
 use super::super::qlib::linux::time::*;
+use super::super::qlib::linux::personality::*;
+use super::super::qlib::linux::ioprio::*;
 use super::super::qlib::path::*;
 use super::super::asm::*;
 use super::super::loader::loader::*;
@@ -352,6 +354,8 @@ impl Kernel {
             Fdtbl: task.fdTbl.clone(),
             Credentials: args.Credentials.clone(),
             Niceness: 0,
+            Personality: PER_LINUX,
+            IOPrio: IOPRIO_DEFAULT,
             NetworkNamespaced: false,
             AllowedCPUMask: CPUSet::NewFullCPUSet(self.applicationCores),
             UTSNamespace: args.UTSNamespace.clone(),
@@ -0,0 +1,104 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::sync::Arc;
+use ::qlib::mutex::*;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use super::super::qlib::linux::time::*;
+use super::super::qlib::singleton::*;
+use super::super::SHARESPACE;
+use super::timer::timer::*;
+use super::timer::*;
+
+pub static LOAD_AVG : Singleton<LoadAvg> = Singleton::<LoadAvg>::New();
+
+pub unsafe fn InitSingleton() {
+    LOAD_AVG.Init(LoadAvg::default());
+}
+
+// The load averages are fixed point numbers with FSHIFT bits of fraction,
+// see include/linux/sched/loadavg.h.
+pub const FSHIFT : u64 = 11;
+pub const FIXED_1 : u64 = 1 << FSHIFT;
+
+// LOAD_FREQ is the sampling interval of the run queue.
+pub const LOAD_FREQ : Duration = 5 * SECOND;
+
+// EXP is 1/exp(5sec/1min), 1/exp(5sec/5min) and 1/exp(5sec/15min) as fixed
+// point numbers.
+pub const EXP : [u64; 3] = [1884, 2014, 2037];
+
+pub fn LoadInt(x: u64) -> u64 {
+    return x >> FSHIFT
+}
+
+pub fn LoadFrac(x: u64) -> u64 {
+    return LoadInt((x & (FIXED_1 - 1)) * 100)
+}
+
+// CalcLoad is kernel/sched/loadavg.c:calc_load.
+fn CalcLoad(load: u64, exp: u64, active: u64) -> u64 {
+    let mut newload = load * exp + active * (FIXED_1 - exp);
+    if active >= load {
+        newload += FIXED_1 - 1;
+    }
+
+    return newload / FIXED_1
+}
+
+// LoadAvg is the exponentially weighted moving average of the number of
+// runnable tasks over 1, 5 and 15 minutes.
+#[derive(Default)]
+pub struct LoadAvg {
+    pub avenrun: [AtomicU64; 3],
+    pub timer: QMutex<Option<Timer>>,
+}
+
+impl LoadAvg {
+    pub fn Start(&self) {
+        let timer = Timer::Period(&MONOTONIC_CLOCK, &Arc::new(LoadAvgUpdater {}), LOAD_FREQ);
+        *self.timer.lock() = Some(timer);
+    }
+
+    pub fn Sample(&self, runnable: u64) {
+        let active = runnable * FIXED_1;
+        for i in 0..3 {
+            let load = self.avenrun[i].load(Ordering::Relaxed);
+            self.avenrun[i].store(CalcLoad(load, EXP[i], active), Ordering::Relaxed);
+        }
+    }
+
+    // Averages returns the load averages with offset added, which is used
+    // for rounding.
+    pub fn Averages(&self, offset: u64) -> [u64; 3] {
+        let mut ret = [0; 3];
+        for i in 0..3 {
+            ret[i] = self.avenrun[i].load(Ordering::Relaxed) + offset;
+        }
+
+        return ret
+    }
+}
+
+pub struct LoadAvgUpdater {}
+
+impl TimerListener for LoadAvgUpdater {
+    // the run queue is only sampled once however late the timer fired
+    fn Notify(&self, _exp: u64) {
+        LOAD_AVG.Sample(SHARESPACE.scheduler.RunnableCount());
+    }
+
+    fn Destroy(&self) {}
+}
@@ -21,7 +21,7 @@ pub mod vdso;
 //pub mod ktime;
 pub mod uts_namespace;
 pub mod semaphore;
-//pub mod shm;
+pub mod shm;
 pub mod ipc_namespace;
 pub mod fs_context;
 pub mod signal_handler;
@@ -36,4 +36,6 @@ pub mod fasync;
 pub mod platform;
 pub mod aio;
 pub mod signalfd;
+pub mod pidfd;
+pub mod loadavg;
 pub mod async_wait;
\ No newline at end of file
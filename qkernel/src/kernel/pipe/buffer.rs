@@ -60,6 +60,10 @@ pub struct BufferIntern {
     pub data: [u8; PIPE_BUF_SIZE],
     pub read: usize,
     pub write: usize,
+
+    // packet indicates the buffer holds a single O_DIRECT packet: it is
+    // consumed by one read and never appended to.
+    pub packet: bool,
 }
 
 
@@ -69,6 +73,7 @@ impl Default for BufferIntern {
             data: [0; PIPE_BUF_SIZE],
             read: 0,
             write: 0,
+            packet: false,
         }
     }
 }
@@ -81,6 +86,7 @@ impl BufferIntern {
         let mut b = self;
         b.read = 0;
         b.write = 0;
+        b.packet = false;
     }
 
     // Empty indicates the buffer is empty.
@@ -98,6 +104,11 @@ impl BufferIntern {
         let b = self;
         return b.write == b.data.len();
     }
+
+    // Len returns the number of unread bytes in the buffer.
+    pub fn Len(&self) -> usize {
+        return self.write - self.read
+    }
 }
 
 impl BlockSeqReader for Buffer {
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use core::sync::atomic::AtomicI64;
+use core::sync::atomic::AtomicUsize;
 use core::sync::atomic::Ordering;
 use alloc::collections::linked_list::LinkedList;
 use alloc::vec::Vec;
@@ -45,15 +46,53 @@ pub const MINIMUM_PIPE_SIZE : usize = MemoryDef::PAGE_SIZE as usize;
 // DefaultPipeSize is the system-wide default size of a pipe in bytes.
 pub const DEFAULT_PIPE_SIZE : usize = 16 * MemoryDef::PAGE_SIZE as usize;
 
-// MaximumPipeSize is a hard limit on the maximum size of a pipe.
-// It corresponds to fs/pipe.c:pipe_max_size.
+// MaximumPipeSize is the default limit on the size an unprivileged user can
+// set a pipe to. It corresponds to fs/pipe.c:pipe_max_size.
 pub const MAXIMUM_PIPE_SIZE : usize = 1048576;
 
+// PipeSizeLimit is a hard limit on the maximum size of a pipe, which applies
+// to privileged users as well.
+pub const PIPE_SIZE_LIMIT : usize = 1 << 31;
+
+// PIPE_MAX_SIZE is the current limit on the size an unprivileged user can set
+// a pipe to. It is exposed as /proc/sys/fs/pipe-max-size.
+pub static PIPE_MAX_SIZE : AtomicUsize = AtomicUsize::new(MAXIMUM_PIPE_SIZE);
+
+// RoundPipeSize rounds size up to a power-of-two number of pages, or returns
+// None if the result exceeds PIPE_SIZE_LIMIT. It corresponds to
+// fs/pipe.c:round_pipe_size.
+pub fn RoundPipeSize(size: usize) -> Option<usize> {
+    if size > PIPE_SIZE_LIMIT {
+        return None
+    }
+
+    if size < MINIMUM_PIPE_SIZE {
+        return Some(MINIMUM_PIPE_SIZE)
+    }
+
+    return Some(size.next_power_of_two())
+}
+
 // atomicIOBytes is the maximum number of bytes that the pipe will
 // guarantee atomic reads or writes atomically.
 // It corresponds to limits.h:PIPE_BUF.
 pub const ATOMIC_IO_BYTES : usize = 4096;
 
+// PipeOf returns the pipe behind f, or None if f isn't a pipe.
+pub fn PipeOf(f: &File) -> Option<Pipe> {
+    let fops = f.FileOp.clone();
+
+    if let Some(ops) = fops.as_any().downcast_ref::<Reader>() {
+        return Some(ops.pipe.clone())
+    } else if let Some(ops) = fops.as_any().downcast_ref::<Writer>() {
+        return Some(ops.pipe.clone())
+    } else if let Some(ops) = fops.as_any().downcast_ref::<ReaderWriter>() {
+        return Some(ops.pipe.clone())
+    }
+
+    return None
+}
+
 // NewConnectedPipe initializes a pipe and returns a pair of objects
 // representing the read and write ends of the pipe.
 pub fn NewConnectedPipe(task: &Task, sizeBytes: usize, atomicIOBytes: usize) -> (File, File) {
@@ -81,6 +120,10 @@ pub struct PipeInternal {
     // size is the current size of the pipe in bytes.
     pub size: usize,
 
+    // reserved is the capacity ReadFrom has claimed for the data it is
+    // reading from its source while the pipe is unlocked.
+    pub reserved: usize,
+
     // hadWriter indicates if this pipe ever had a writer. Note that this
     // does not necessarily indicate there is *currently* a writer, just
     // that there has been a writer at some point since the pipe was
@@ -95,10 +138,137 @@ pub struct PipeInternal {
 
 impl PipeInternal {
     pub fn Available(&self) -> usize {
-        return (self.max - self.size) as usize;
+        if self.size + self.reserved >= self.max {
+            return 0
+        }
+
+        return (self.max - self.size - self.reserved) as usize;
+    }
+
+    // SlotsFull returns whether the pipe holds as many buffers as Linux has
+    // slots for its size, one per page. Every packet takes a buffer of its
+    // own however small it is, so counting bytes alone doesn't bound the
+    // memory a packet pipe uses.
+    pub fn SlotsFull(&self) -> bool {
+        return self.data.len() >= self.max / MemoryDef::PAGE_SIZE as usize;
+    }
+
+    // Append queues all of src, even if the pipe has no buffer slot left. It
+    // is for data which was already consumed from its source and can't be
+    // put back, and whose capacity the caller has reserved.
+    pub fn Append(&mut self, src: BlockSeq) -> Result<usize> {
+        let mut src = src;
+        let mut done = 0;
+        while src.NumBytes() > 0 {
+            // Need a new buffer? Packets are never appended to.
+            let needNew = match self.data.back() {
+                None => true,
+                Some(b) => {
+                    let b = b.borrow();
+                    b.Full() || b.packet
+                }
+            };
+
+            if needNew {
+                self.data.push_back(NewBuff());
+            }
+
+            let n = src.CopyInTo(self.data.back_mut().unwrap())?;
+            done += n;
+            self.size += n;
+            src = src.DropFirst(n as u64);
+        }
+
+        return Ok(done)
+    }
+
+    // Take consumes up to len bytes from the front of the pipe, stopping
+    // after the first packet. It returns them as one segment per buffer,
+    // each with whether it is a packet, so that Unread can put them back as
+    // they were. A packet longer than len is split: its rest stays queued.
+    pub fn Take(&mut self, len: usize) -> Vec<(Vec<u8>, bool)> {
+        let mut segs = Vec::new();
+        let mut left = len;
+        while left > 0 {
+            let (seg, packet, empty) = {
+                let first = match self.data.front() {
+                    None => break,
+                    Some(f) => f,
+                };
+
+                let mut b = first.borrow_mut();
+                let n = core::cmp::min(left, b.Len());
+                let seg = b.data[b.read..b.read + n].to_vec();
+                b.read += n;
+                (seg, b.packet, b.Empty())
+            };
+
+            left -= seg.len();
+            self.size -= seg.len();
+            if empty {
+                let v = self.data.pop_front().unwrap();
+                ReturnBuff(v);
+            }
+
+            segs.push((seg, packet));
+            if packet {
+                break;
+            }
+        }
+
+        return segs
     }
 
-    pub fn Write(&mut self, _task: &Task, src: BlockSeq, _atomicIOBytes: usize) -> Result<usize> {
+    // Unread puts data, which was consumed from the front of the pipe, back
+    // in front of what is queued. If packet is set, data came from a packet:
+    // it is joined back onto the rest of the packet if Take left that queued,
+    // and goes back as a packet of its own otherwise.
+    pub fn Unread(&mut self, data: &[u8], packet: bool) -> Result<()> {
+        if packet {
+            let joined = match self.data.front() {
+                Some(b) if b.borrow().packet && b.borrow().read >= data.len() => {
+                    let mut b = b.borrow_mut();
+                    let read = b.read - data.len();
+                    b.data[read..b.read].copy_from_slice(data);
+                    b.read = read;
+                    true
+                }
+                _ => false,
+            };
+
+            if !joined {
+                let mut buf = NewBuff();
+                buf.borrow_mut().packet = true;
+                BlockSeq::New(data).CopyInTo(&mut buf)?;
+                self.data.push_front(buf);
+            }
+
+            self.size += data.len();
+            return Ok(())
+        }
+
+        let mut end = data.len();
+        while end > 0 {
+            let start = if end > PIPE_BUF_SIZE {
+                end - PIPE_BUF_SIZE
+            } else {
+                0
+            };
+
+            let mut buf = NewBuff();
+            BlockSeq::New(&data[start..end]).CopyInTo(&mut buf)?;
+            self.data.push_front(buf);
+            self.size += end - start;
+            end = start;
+        }
+
+        return Ok(())
+    }
+
+    // Write appends src to the pipe. If packet is set (the writer was opened
+    // with O_DIRECT), the data is split into packets of at most atomicIOBytes,
+    // each in its own buffer, so that every read returns at most one of them.
+    pub fn Write(&mut self, _task: &Task, src: BlockSeq, atomicIOBytes: usize, packet: bool) -> Result<usize> {
         let mut p = self;
 
         let mut src = src;
@@ -121,20 +291,50 @@ impl PipeInternal {
 
         let mut done = 0;
         while src.NumBytes() > 0 {
-            // Need a new buffer?
-            if p.data.back().is_none() || p.data.back().as_ref().unwrap().borrow().Full() {
-                p.data.push_back(NewBuff());
-            }
+            let n = if packet {
+                if p.SlotsFull() {
+                    break;
+                }
+
+                let buf = NewBuff();
+                buf.borrow_mut().packet = true;
+                p.data.push_back(buf);
+
+                let mut chunk = src;
+                if chunk.NumBytes() as usize > atomicIOBytes {
+                    chunk = chunk.TakeFirst(atomicIOBytes as u64);
+                }
+
+                chunk.CopyInTo(*p.data.back_mut().as_mut().unwrap())?
+            } else {
+                // Need a new buffer? Packets are never appended to.
+                let needNew = match p.data.back() {
+                    None => true,
+                    Some(b) => {
+                        let b = b.borrow();
+                        b.Full() || b.packet
+                    }
+                };
+
+                if needNew {
+                    if p.SlotsFull() {
+                        break;
+                    }
+
+                    p.data.push_back(NewBuff());
+                }
+
+                // Copy user data.
+                src.CopyInTo(*p.data.back_mut().as_mut().unwrap())?
+            };
 
-            // Copy user data.
-            let n = src.CopyInTo(*p.data.back_mut().as_mut().unwrap())?;
             done += n;
             p.size += n;
             src = src.DropFirst(n as u64);
         }
 
         if wanted > done {
-            // Partial write due to full pipe.
+            // Partial write due to full pipe, or no slot left for a packet.
             return Ok(done)
         }
 
@@ -320,6 +520,8 @@ impl Pipe {
         while dst.NumBytes() > 0 {
             let mut needPop = false;
             let n;
+            let isPacket;
+            let dropped;
             {
                 // Pop the first buffer.
                 let first = match p.data.front_mut() {
@@ -327,39 +529,141 @@ impl Pipe {
                     Some(f) => f,
                 };
 
+                isPacket = first.borrow().packet;
+
                 // Copy user data.
                 n = dst.CopyOutFrom(first)?;
                 done += n;
                 dst = dst.DropFirst(n as u64);
 
+                // A read consumes at most one packet; whatever part of it
+                // didn't fit in dst is discarded.
+                dropped = if isPacket {
+                    first.borrow().Len()
+                } else {
+                    0
+                };
+
                 // Empty buffer?
-                if first.borrow().Empty() {
+                if isPacket || first.borrow().Empty() {
                     needPop = true;
                 }
             }
 
-            p.size -= n;
+            p.size -= n + dropped;
             if needPop {
                 // Push to the free list.
                 let v = p.data.pop_front().unwrap();
                 ReturnBuff(v);
             }
+
+            if isPacket {
+                break;
+            }
         }
 
         return Ok(done)
     }
 
+    // Peek copies up to len bytes from the front of the pipe without
+    // consuming them. It returns an empty buffer at EOF, or ErrWouldBlock if
+    // the pipe is empty.
+    pub fn Peek(&self, len: usize) -> Result<Vec<u8>> {
+        let p = self.intern.lock();
+        return self.PeekLocked(&p, len)
+    }
+
+    pub fn PeekLocked(&self, p: &PipeInternal, len: usize) -> Result<Vec<u8>> {
+        if p.size == 0 {
+            if !self.HasWriters() {
+                return Ok(Vec::new())
+            }
+
+            return Err(Error::SysError(SysErr::EAGAIN))
+        }
+
+        let mut len = len;
+        if len > p.size {
+            len = p.size;
+        }
+
+        let mut buf = Vec::with_capacity(len);
+        for b in p.data.iter() {
+            if buf.len() == len {
+                break;
+            }
+
+            let b = b.borrow();
+            let n = core::cmp::min(len - buf.len(), b.Len());
+            buf.extend_from_slice(&b.data[b.read..b.read + n]);
+        }
+
+        return Ok(buf)
+    }
+
+    // Tee duplicates up to len bytes from the front of this pipe into dst
+    // without consuming them, as tee(2). It returns ErrWouldBlock if this pipe
+    // is empty or dst is full.
+    pub fn Tee(&self, task: &Task, dst: &Pipe, len: usize) -> Result<usize> {
+        // Can't write to a pipe with no readers.
+        if !dst.HasReaders() {
+            return Err(Error::SysError(SysErr::EPIPE))
+        }
+
+        // Both pipes stay locked, so that no reader of this pipe or writer of
+        // dst can get between the check, the peek and the write. They are
+        // locked in address order, so that tees in opposite directions can't
+        // deadlock.
+        let n = {
+            let (p, mut d) = if Arc::as_ptr(&self.0) < Arc::as_ptr(&dst.0) {
+                let p = self.intern.lock();
+                (p, dst.intern.lock())
+            } else {
+                let d = dst.intern.lock();
+                (self.intern.lock(), d)
+            };
+
+            let avail = d.Available();
+            if avail == 0 {
+                return Err(Error::SysError(SysErr::EAGAIN))
+            }
+
+            let buf = self.PeekLocked(&p, core::cmp::min(len, avail))?;
+            if buf.len() == 0 {
+                return Ok(0)
+            }
+
+            d.Write(task, BlockSeq::New(&buf), dst.atomicIOBytes, false)?
+        };
+
+        if n == 0 {
+            // dst has no slot left.
+            return Err(Error::SysError(SysErr::EAGAIN))
+        }
+
+        dst.Notify(EVENT_IN);
+        return Ok(n)
+    }
+
+    // ReadFrom fills the pipe with up to opts.Length bytes read from src at
+    // opts.SrcStart, as splice(2) from a file into the pipe. It returns 0 at
+    // the end of src and ErrWouldBlock if the pipe is full.
+    //
+    // src is read blocking unless opts.NonBlocking or src's own O_NONBLOCK is
+    // set. The caller (Splice) holds src's offset lock and advances it.
     pub fn ReadFrom(&self, task: &Task, src: &File, opts: &SpliceOpts) -> Result<usize> {
         if opts.DstOffset {
-            return Err(Error::SysError(SysErr::EINVAL))
+            return Err(Error::SysError(SysErr::ESPIPE))
         }
 
         if opts.SrcOffset && !src.FileOp.Seekable() {
             return Err(Error::SysError(SysErr::EINVAL))
         }
 
+        // The capacity for the read is reserved up front, so that whatever
+        // is read fits even though the pipe isn't locked across the read.
         let len = {
-            let p = self.intern.lock();
+            let mut p = self.intern.lock();
             // Can't write to a pipe with no readers.
             if !self.HasReaders() {
                 return Err(Error::SysError(SysErr::EPIPE))
@@ -375,35 +679,130 @@ impl Pipe {
                 len = opts.Length as usize
             }
 
+            p.reserved += len;
             len
         };
 
+        let buf = DataBuff::New(len);
+        let mut iovs = buf.Iovs();
+        let blocking = !opts.NonBlocking && !src.Flags().NonBlocking;
+        let res = src.FileOp.ReadAt(task, src, &mut iovs, opts.SrcStart, blocking);
 
-        let mut buf = Vec::with_capacity(len);
-        buf.resize(len, 0);
-        let dst = IoVec::New(&buf);
-        let mut iovs = [dst];
-        //let src = BlockSeq::New(&buf);
+        let mut p = self.intern.lock();
+        p.reserved -= len;
+        let readCount = res? as usize;
+        if readCount == 0 {
+            return Ok(0)
+        }
 
-        let readCount = if opts.SrcOffset {
-            src.Preadv(task, &mut iovs, opts.SrcStart)?
-        } else {
-            src.Readv(task, &mut iovs)?
+        // The caller only advances a seekable src's offset by the returned
+        // count, so nothing has moved yet if the readers went away. What was
+        // read from any other src is gone from it, and is queued and counted.
+        if src.FileOp.Seekable() && !self.HasReaders() {
+            return Err(Error::SysError(SysErr::EPIPE))
+        }
+
+        // A packet pipe may have run out of buffer slots meanwhile. A
+        // seekable src is read again from where the pipe filled up.
+        let data = BlockSeq::New(&buf.buf[0..readCount]);
+        let mut n = p.Write(task, data, self.atomicIOBytes, false)?;
+        if n < readCount && !src.FileOp.Seekable() {
+            n += p.Append(data.DropFirst(n as u64))?;
+        }
+
+        if n == 0 {
+            return Err(Error::SysError(SysErr::EAGAIN))
+        }
+
+        return Ok(n)
+    }
+
+    // WriteTo drains up to opts.Length bytes from the pipe into dst at
+    // opts.DstStart, as splice(2) from the pipe into a file. Only the bytes
+    // dst accepted are consumed, or none if opts.Dup is set. It returns 0 if
+    // the pipe is empty and has no writers, and ErrWouldBlock if it is merely
+    // empty.
+    //
+    // dst is written blocking unless opts.NonBlocking or dst's own O_NONBLOCK
+    // is set. The caller (Splice) holds dst's offset lock and advances it.
+    pub fn WriteTo(&self, task: &Task, dst: &File, opts: &SpliceOpts) -> Result<usize> {
+        if opts.SrcOffset {
+            return Err(Error::SysError(SysErr::ESPIPE))
+        }
+
+        if opts.DstOffset && !dst.FileOp.Seekable() {
+            return Err(Error::SysError(SysErr::EINVAL))
+        }
+
+        let blocking = !opts.NonBlocking && !dst.Flags().NonBlocking;
+        if opts.Dup {
+            let buf = self.Peek(opts.Length as usize)?;
+            if buf.len() == 0 {
+                return Ok(0)
+            }
+
+            let iovs = [IoVec::New(&buf)];
+            return Ok(dst.FileOp.WriteAt(task, dst, &iovs, opts.DstStart, blocking)? as usize)
+        }
+
+        // Consume the data up front rather than peek at it and consume it
+        // after the write, when other readers may have taken it already.
+        // Unlike Read, the rest of a packet which doesn't fit in
+        // opts.Length stays queued.
+        let segs = {
+            let mut p = self.intern.lock();
+            if p.size == 0 {
+                if !self.HasWriters() {
+                    return Ok(0)
+                }
+
+                return Err(Error::SysError(SysErr::EAGAIN))
+            }
+
+            p.Take(opts.Length as usize)
         };
 
-        let src = BlockSeq::New(&buf[0..readCount as usize]);
-        let writeCount = self.intern.lock().Write(task, src, self.atomicIOBytes)? as usize;
+        let count = segs.iter().map(|(seg, _)| seg.len()).sum::<usize>();
+        if count == 0 {
+            return Ok(0)
+        }
 
-        assert!(readCount as usize == writeCount);
-        return Ok(writeCount)
+        let iovs : Vec<IoVec> = segs.iter().map(|(seg, _)| IoVec::New(seg)).collect();
+        let res = dst.FileOp.WriteAt(task, dst, &iovs, opts.DstStart, blocking);
+        let n = match res {
+            Ok(n) => n as usize,
+            Err(_) => 0,
+        };
+
+        // Put back whatever dst didn't take, last segment first, each as the
+        // kind of buffer it came from.
+        if n < count {
+            let mut p = self.intern.lock();
+            let mut end = count;
+            for (seg, packet) in segs.iter().rev() {
+                let start = end - seg.len();
+                if end > n {
+                    let from = if n > start { n - start } else { 0 };
+                    p.Unread(&seg[from..], *packet)?;
+                }
+                end = start;
+            }
+
+            self.Notify(EVENT_IN);
+        }
+
+        res?;
+        return Ok(n)
     }
 
     // write writes data from sv into the pipe and returns the number of bytes
     // written. If no bytes are written because the pipe is full (or has less than
     // atomicIOBytes free capacity), write returns ErrWouldBlock.
+    // If packet is set, each chunk of at most atomicIOBytes is queued as a
+    // discrete packet (O_DIRECT pipe, see pipe(2)).
     //
     // Precondition: this pipe must have writers.
-    pub fn Write(&self, task: &Task, src: BlockSeq) -> Result<usize> {
+    pub fn Write(&self, task: &Task, src: BlockSeq, packet: bool) -> Result<usize> {
         let mut p = self.intern.lock();
 
         // Can't write to a pipe with no readers.
@@ -411,7 +810,7 @@ impl Pipe {
             return Err(Error::SysError(SysErr::EPIPE))
         }
 
-        return p.Write(task, src, self.atomicIOBytes)
+        return p.Write(task, src, self.atomicIOBytes, packet)
     }
 
     // rOpen signals a new reader of the pipe.
@@ -491,7 +890,7 @@ impl Pipe {
     // wReadinessLocked calculates the write readiness.
     pub fn WReadinessLocked(&self, intern: &QMutexGuard<PipeInternal>) -> EventMask {
         let mut ready = 0;
-        if self.HasWriters() && intern.size < intern.max {
+        if self.HasWriters() && intern.size < intern.max && !intern.SlotsFull() {
             ready |= EVENT_OUT;
         }
 
@@ -526,27 +925,35 @@ impl Pipe {
         return self.intern.lock().max;
     }
 
-    // SetPipeSize implements PipeSize.SetPipeSize.
-    pub fn SetPipeSize(&self, size: i64) -> Result<usize>  {
+    // SetPipeSize implements PipeSize.SetPipeSize. The data queued in the pipe
+    // is kept, so the pipe can't shrink below it.
+    pub fn SetPipeSize(&self, task: &Task, size: i64) -> Result<usize>  {
         if size < 0 {
             return Err(Error::SysError(SysErr::EINVAL))
         }
 
-        let mut size = size as usize;
-        if size < MINIMUM_PIPE_SIZE {
-            size = MINIMUM_PIPE_SIZE;
-        }
+        let size = match RoundPipeSize(size as usize) {
+            None => return Err(Error::SysError(SysErr::EINVAL)),
+            Some(size) => size,
+        };
 
-        if size > MAXIMUM_PIPE_SIZE {
-            return Err(Error::SysError(SysErr::EINVAL))
+        if size > PIPE_MAX_SIZE.load(Ordering::Relaxed) &&
+            !task.Creds().HasCapability(Capability::CAP_SYS_RESOURCE) {
+            return Err(Error::SysError(SysErr::EPERM))
         }
 
-        let mut intern = self.intern.lock();
-        if size < intern.size {
-            return Err(Error::SysError(SysErr::EBUSY))
+        {
+            let mut intern = self.intern.lock();
+            if size < intern.size + intern.reserved || size / (MemoryDef::PAGE_SIZE as usize) < intern.data.len() {
+                return Err(Error::SysError(SysErr::EBUSY))
+            }
+
+            intern.max = size;
         }
 
-        intern.max = size;
+        // Readers and writers blocked on the old capacity need to re-evaluate
+        // their readiness.
+        self.Notify(EVENT_IN | EVENT_OUT);
         return Ok(size)
     }
 }
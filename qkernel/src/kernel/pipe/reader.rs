@@ -45,11 +45,20 @@ impl Drop for Reader {
         self.pipe.RClose();
 
         // Wake up writers.
-        self.pipe.Notify(EVENT_OUT)
+        self.pipe.Notify(EVENT_OUT | EVENT_ERR)
     }
 }
 
-impl SpliceOperations for Reader {}
+impl SpliceOperations for Reader {
+    fn WriteTo(&self, task: &Task, _file: &File, dst: &File, opts: &SpliceOpts) -> Result<i64> {
+        let n = self.pipe.WriteTo(task, dst, opts)?;
+        if n > 0 && !opts.Dup {
+            self.pipe.Notify(EVENT_OUT)
+        }
+
+        return Ok(n as i64)
+    }
+}
 
 impl FileOperations for Reader {
     fn as_any(&self) -> &Any {
@@ -88,12 +97,12 @@ impl FileOperations for Reader {
         return Ok(n as i64)
     }
 
-    fn WriteAt(&self, task: &Task, _f: &File, srcs: &[IoVec], _offset: i64, _blocking: bool) -> Result<i64> {
+    fn WriteAt(&self, task: &Task, f: &File, srcs: &[IoVec], _offset: i64, _blocking: bool) -> Result<i64> {
         //error!("pipe reader WriteAt id {}, writers is {}", self.pipe.Uid(), self.pipe.Writers());
         let size = IoVec::NumBytes(srcs);
         let mut buf = DataBuff::New(size);
         task.CopyDataInFromIovs(&mut buf.buf, srcs)?;
-        let n = self.pipe.Write(task, buf.BlockSeq())?;
+        let n = self.pipe.Write(task, buf.BlockSeq(), f.Flags().Direct)?;
         if n > 0 {
             self.pipe.Notify(EVENT_IN)
         }
@@ -46,19 +46,28 @@ impl Drop for ReaderWriter{
         self.pipe.WClose();
 
         // Wake up readers and writers.
-        self.pipe.Notify(EVENT_IN | EVENT_OUT)
+        self.pipe.Notify(EVENT_IN | EVENT_OUT | EVENT_HUP | EVENT_ERR)
     }
 }
 
 impl SpliceOperations for ReaderWriter {
-    /*fn ReadFrom(&self, task: &Task, _file: &File, src: &File, opts: &SpliceOpts) -> Result<i64> {
+    fn WriteTo(&self, task: &Task, _file: &File, dst: &File, opts: &SpliceOpts) -> Result<i64> {
+        let n = self.pipe.WriteTo(task, dst, opts)?;
+        if n > 0 && !opts.Dup {
+            self.pipe.Notify(EVENT_OUT)
+        }
+
+        return Ok(n as i64)
+    }
+
+    fn ReadFrom(&self, task: &Task, _file: &File, src: &File, opts: &SpliceOpts) -> Result<i64> {
         let n = self.pipe.ReadFrom(task, src, opts)?;
         if n > 0 {
             self.pipe.Notify(EVENT_IN)
         }
 
         return Ok(n as i64)
-    }*/
+    }
 }
 
 impl FileOperations for ReaderWriter {
@@ -96,17 +105,23 @@ impl FileOperations for ReaderWriter {
         return Ok(n as i64)
     }
 
-    fn WriteAt(&self, task: &Task, _f: &File, srcs: &[IoVec], _offset: i64, _blocking: bool) -> Result<i64> {
+    fn WriteAt(&self, task: &Task, f: &File, srcs: &[IoVec], _offset: i64, _blocking: bool) -> Result<i64> {
         let size = IoVec::NumBytes(srcs);
         let mut buf = DataBuff::New(size);
         task.CopyDataInFromIovs(&mut buf.buf, srcs)?;
         let srcs = BlockSeq::New(&buf.buf);
-        let n = self.pipe.Write(task, srcs)?;
+        let n = self.pipe.Write(task, srcs, f.Flags().Direct)?;
         if n > 0 {
-            self.pipe.Notify(EVENT_IN)
+            self.pipe.Notify(EVENT_IN);
+            return Ok(n as i64)
         }
 
-        return Ok(n as i64)
+        if srcs.NumBytes() == 0 {
+            return Ok(0)
+        }
+
+        // The pipe is full.
+        return Err(Error::SysError(SysErr::EAGAIN));
     }
 
     fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
@@ -45,19 +45,19 @@ impl Drop for Writer {
         self.pipe.WClose();
 
         // Wake up readers.
-        self.pipe.Notify(EVENT_HUP)
+        self.pipe.Notify(EVENT_IN | EVENT_HUP)
     }
 }
 
 impl SpliceOperations for Writer {
-    /*fn ReadFrom(&self, task: &Task, _file: &File, src: &File, opts: &SpliceOpts) -> Result<i64> {
+    fn ReadFrom(&self, task: &Task, _file: &File, src: &File, opts: &SpliceOpts) -> Result<i64> {
         let n = self.pipe.ReadFrom(task, src, opts)?;
         if n > 0 {
             self.pipe.Notify(EVENT_IN)
         }
 
         return Ok(n as i64)
-    }*/
+    }
 }
 
 impl FileOperations for Writer {
@@ -97,14 +97,14 @@ impl FileOperations for Writer {
         return Err(Error::SysError(SysErr::EAGAIN));
     }
 
-    fn WriteAt(&self, task: &Task, _f: &File, srcs: &[IoVec], _offset: i64, _blocking: bool) -> Result<i64> {
+    fn WriteAt(&self, task: &Task, f: &File, srcs: &[IoVec], _offset: i64, _blocking: bool) -> Result<i64> {
         //error!("pipe writer WriteAt id {}, writers is {}", self.pipe.Uid(), self.pipe.Writers());
         let size = IoVec::NumBytes(srcs);
         let mut buf = DataBuff::New(size);
         task.CopyDataInFromIovs(&mut buf.buf, srcs)?;
         let srcs = BlockSeq::New(&buf.buf);
 
-        let n = match self.pipe.Write(task, srcs) {
+        let n = match self.pipe.Write(task, srcs, f.Flags().Direct) {
             Err(e) => {
                 //info!("Pipe::WriteAt 2.... e is {:?}", e);
                 return Err(e);
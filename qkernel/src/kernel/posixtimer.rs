@@ -81,20 +81,8 @@ impl IntervalTimerInternal {
         return self.target.clone().unwrap();
     }
 
-    pub fn timerSettingChanged(&mut self) {
-        if self.target.is_none() {
-            return
-        }
-
-        let target = self.target.clone().unwrap();
-        let tg = target.ThreadGroup();
-        let pidns = tg.PIDNamespace();
-        let owner = pidns.lock().owner.clone();
-        let _r = owner.ReadLock();
-
-        let lock = tg.lock().signalLock.clone();
-        let _l = lock.lock();
-
+    // Preconditions: target's signal mutex must be locked.
+    pub fn timerSettingChangedLocked(&mut self) {
         self.sigorphan = true;
         self.overrunCur = 0;
         self.overrunLast = 0;
@@ -134,13 +122,13 @@ impl Deref for IntervalTimer {
 
 impl timer::TimerListener for IntervalTimer {
     fn Notify(&self, exp: u64) {
-        let mut it = self.lock();
-
-        if it.target.is_none() {
-            return
-        }
+        // The signal mutex is taken before the timer's own lock, the order
+        // used when the signal is dequeued.
+        let target = match self.lock().target.clone() {
+            None => return,
+            Some(t) => t,
+        };
 
-        let target = it.target.clone().unwrap();
         let tg = target.lock().tg.clone();
         let pidns = tg.PIDNamespace();
         let owner = pidns.lock().owner.clone();
@@ -149,6 +137,7 @@ impl timer::TimerListener for IntervalTimer {
         let lock = tg.lock().signalLock.clone();
         let _s = lock.lock();
 
+        let mut it = self.lock();
         if it.sigpending {
             it.overrunCur += exp;
             return
@@ -172,14 +161,12 @@ impl timer::TimerListener for IntervalTimer {
         timer.sigval = it.sigval;
 
         // si_overrun is set when the signal is dequeued.
-        let target = it.Target();
         let group = it.group;
         core::mem::drop(it);
         let err = target.sendSignalTimerLocked(&si, group, Some(self.clone()));
-        let mut it = self.lock();
         match err {
             Err(_) => {
-                it.signalRejectedLocked();
+                self.lock().signalRejectedLocked();
             }
             _ => (),
         }
@@ -200,10 +187,30 @@ impl IntervalTimer {
     }
 
     pub fn DestroyTimer(&self) {
-        let mut t = self.lock();
-        t.Timer().Destroy();
-        t.timerSettingChanged();
-        t.timer = None;
+        let timer = self.lock().Timer();
+        timer.Destroy();
+        self.timerSettingChanged();
+        self.lock().timer = None;
+    }
+
+    // timerSettingChanged is called when the timer's setting changes, after
+    // which expirations no longer count towards the overrun of a signal that
+    // is already pending.
+    pub fn timerSettingChanged(&self) {
+        let target = match self.lock().target.clone() {
+            None => return,
+            Some(t) => t,
+        };
+
+        let tg = target.ThreadGroup();
+        let pidns = tg.PIDNamespace();
+        let owner = pidns.lock().owner.clone();
+        let _r = owner.ReadLock();
+
+        let lock = tg.lock().signalLock.clone();
+        let _l = lock.lock();
+
+        self.lock().timerSettingChangedLocked();
     }
 
     pub fn PauseTimer(&self) {
@@ -224,7 +231,8 @@ fn saturateI32FromU64(x: u64) -> i32 {
 }
 
 impl Thread {
-    pub fn IntervalTimerCreate(&self, c: &Clock, sigev: &mut Sigevent) -> Result<TimerID> {
+    // IntervalTimerCreate implements timer_create(2).
+    pub fn IntervalTimerCreate(&self, c: &Clock, sigev: Option<&Sigevent>) -> Result<TimerID> {
         let tg = self.lock().tg.clone();
         let timerMu = tg.TimerMu();
         let _tm = timerMu.lock();
@@ -249,7 +257,6 @@ impl Thread {
             }
         }
 
-        //todo: fix this
         // "The implementation of the default case where evp [sic] is NULL is
         // handled inside glibc, which invokes the underlying system call with a
         // suitably populated sigevent structure." - timer_create(2). This is
@@ -258,8 +265,17 @@ impl Thread {
         // (kernel/time/posix-timers.c:sys_timer_create(), do_timer_create()). This
         // must be handled here instead of the syscall wrapper since sigval is the
         // timer ID, which isn't available until we allocate it in this function.
+        let defaultSigev = Sigevent {
+            Signo: Signal::SIGALRM,
+            Notify: SIGEV_SIGNAL,
+            Value: id as u64,
+            ..Default::default()
+        };
 
-        //if sigev is none
+        let sigev = match sigev {
+            None => &defaultSigev,
+            Some(sigev) => sigev,
+        };
 
         let it = IntervalTimer::New(id, sigev.Value);
 
@@ -336,7 +352,7 @@ impl Thread {
         let newS = timer::Setting::FromItimerspec(its, abs, &clock)?;
 
         let (tm, oldS) = timer.SwapAnd(&newS, || {
-             it.lock().timerSettingChanged();
+             it.timerSettingChanged();
         });
         let its = timer::ItimerspecFromSetting(tm, oldS);
         return Ok(its)
@@ -13,13 +13,14 @@
 // limitations under the License.
 
 use alloc::sync::Arc;
+use alloc::sync::Weak;
 use ::qlib::mutex::*;
 use core::ops::Deref;
 use alloc::collections::btree_map::BTreeMap;
 use alloc::string::String;
-use core::fmt::Debug;
+use alloc::string::ToString;
+use alloc::vec::Vec;
 
-use super::super::PAGE_MGR;
 use super::super::qlib::auth::userns::*;
 use super::super::qlib::auth::*;
 use super::super::qlib::auth::id::*;
@@ -27,17 +28,24 @@ use super::super::qlib::addr::*;
 use super::super::qlib::common::*;
 use super::super::qlib::linux_def::*;
 use super::super::task::*;
-use super::super::qlib::range::*;
 use super::super::qlib::device::*;
+use super::super::qlib::singleton::*;
 use super::super::memmgr::*;
+use super::super::fs::host::hostinodeop::*;
 use super::super::qlib::linux::ipc::*;
 use super::super::qlib::linux::shm::*;
 use super::time::*;
 
+pub static SHM_DEVICE : Singleton<Arc<QMutex<Device>>> = Singleton::<Arc<QMutex<Device>>>::New();
+
+pub unsafe fn InitSingleton() {
+    SHM_DEVICE.Init(NewAnonDevice());
+}
+
 type Key = i32;
 type ID = i32;
 
-#[derive(Default, Debug)]
+#[derive(Default)]
 pub struct RegistryInternal {
     pub userNS: UserNameSpace,
     pub shms: BTreeMap<ID, Shm>,
@@ -46,9 +54,7 @@ pub struct RegistryInternal {
     pub lastIDUsed: ID,
 }
 
-impl RegistryInternal {}
-
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Default)]
 pub struct Registry(Arc<QMutex<RegistryInternal>>);
 
 impl Deref for Registry {
@@ -80,16 +86,32 @@ impl Registry {
         }
     }
 
-    fn dissociateKey(&self, shm: &Shm) {
-        let mut me = self.lock();
-        let mut s = shm.lock();
+    // FindByMappable returns the segment whose memory is backed by mappable.
+    pub fn FindByMappable(&self, mappable: &HostInodeOp) -> Option<Shm> {
+        let me = self.lock();
+        for (_, shm) in &me.shms {
+            if shm.lock().mappable == *mappable {
+                return Some(shm.clone())
+            }
+        }
 
-        if s.key != IPC_PRIVATE {
-            me.keysToShms.remove(&s.key);
-            s.key = IPC_PRIVATE;
+        return None
+    }
+
+    // HighestIndex returns the highest id in use, as returned by IPC_INFO and
+    // SHM_INFO.
+    pub fn HighestIndex(&self) -> i32 {
+        return match self.lock().shms.keys().next_back() {
+            None => 0,
+            Some(id) => *id,
         }
     }
 
+    // Shms returns all the segments, ordered by id.
+    pub fn Shms(&self) -> Vec<Shm> {
+        return self.lock().shms.values().cloned().collect();
+    }
+
     pub fn FindOrCreate(&self, task: &Task, pid: i32, key: Key, size: u64,
                         mode: &FileMode, private: bool, create: bool, exclusive: bool) -> Result<Shm> {
         if (create || private) && (size < SHMMIN || size > SHMMAX) {
@@ -98,9 +120,6 @@ impl Registry {
 
         {
             let me = self.lock();
-            if me.shms.len() > SHMMNI as usize {
-                return Err(Error::SysError(SysErr::ENOSPC))
-            }
 
             if !private {
                 match me.keysToShms.get(&key) {
@@ -131,6 +150,10 @@ impl Registry {
                 }
             }
 
+            if me.shms.len() >= SHMMNI as usize {
+                return Err(Error::SysError(SysErr::ENOSPC))
+            }
+
             let sizeAligned = match Addr(size).RoundUp() {
                 Err(_) => return Err(Error::SysError(SysErr::EINVAL)),
                 Ok(addr) => addr.0,
@@ -150,18 +173,20 @@ impl Registry {
 
     fn newShm(&self, task: &Task, pid: i32, key: Key, creator: &FileOwner, perms: &FilePermissions, size: u64) -> Result<Shm> {
         let effectiveSize = Addr(size).MustRoundUp().0;
-        let addr = PAGE_MGR.MapAnon(effectiveSize)?;
-        let fr = Range::New(addr, effectiveSize);
 
-        PAGE_MGR.RefRange(&fr)?;
+        // The segment is backed by a memfd, the same as MAP_SHARED anonymous
+        // memory, so that every attach maps the same pages.
+        let mappable = HostInodeOp::NewMemfdIops(effectiveSize as i64)?;
 
+        let userNS = self.lock().userNS.clone();
         let shm = Shm(Arc::new(QMutex::new(ShmInternal {
             registry: self.clone(),
+            userNS: userNS,
             id: 0,
             creator: *creator,
             size: size,
             effectiveSize: effectiveSize,
-            fr: fr,
+            mappable: mappable,
             key: key,
             perms: *perms,
             owner: *creator,
@@ -171,7 +196,7 @@ impl Registry {
             creatorPID: pid,
             lastAttachDetachPID: 0,
             pendingDestruction: false,
-            refCount: 0,
+            attachment: Weak::new(),
         })));
 
         let mut me = self.lock();
@@ -190,7 +215,9 @@ impl Registry {
             me.lastIDUsed = id;
             shm.lock().id = id;
             me.shms.insert(id, shm.clone());
-            me.keysToShms.insert(key, shm.clone());
+            if key != IPC_PRIVATE {
+                me.keysToShms.insert(key, shm.clone());
+            }
             me.totalPages += effectiveSize / MemoryDef::PAGE_SIZE;
             return Ok(shm)
         }
@@ -212,7 +239,7 @@ impl Registry {
     pub fn ShmInfo(&self) -> ShmInfo {
         let me = self.lock();
         return ShmInfo {
-            UsedIDs: me.lastIDUsed,
+            UsedIDs: me.shms.len() as i32,
             ShmTot: me.totalPages,
             ShmRss: me.totalPages,
             // We could probably get a better estimate from memory accounting.
@@ -225,23 +252,24 @@ impl Registry {
         let mut me = self.lock();
         let s = s.lock();
 
-        if s.key == IPC_PRIVATE {
-            panic!("Attempted to remove {:?} from the registry whose key is still associated", s);
+        if s.key != IPC_PRIVATE {
+            panic!("Attempted to remove shm {} from the registry whose key is still associated", s.id);
         }
 
-        me.shms.remove(&s.id);
-        me.totalPages -= s.effectiveSize / MemoryDef::PAGE_SIZE;
+        if me.shms.remove(&s.id).is_some() {
+            me.totalPages -= s.effectiveSize / MemoryDef::PAGE_SIZE;
+        }
     }
 }
 
-#[derive(Clone, Debug)]
 pub struct ShmInternal {
     pub registry: Registry,
+    pub userNS: UserNameSpace,
     pub id: ID,
     pub creator: FileOwner,
     pub size: u64,
     pub effectiveSize: u64,
-    pub fr: Range,
+    pub mappable: HostInodeOp,
     pub key: Key,
     pub perms: FilePermissions,
     pub owner: FileOwner,
@@ -252,7 +280,10 @@ pub struct ShmInternal {
     pub lastAttachDetachPID: i32,
     pub pendingDestruction: bool,
 
-    pub refCount: i64,
+    // attachment is the mapping identity of the vmas which attach the
+    // segment. Each vma holds a reference to it, so its strong count is the
+    // number of attaches.
+    pub attachment: Weak<ShmAttachment>,
 }
 
 impl ShmInternal {
@@ -264,7 +295,7 @@ impl ShmInternal {
             return true
         }
 
-        let userns = self.registry.lock().userNS.clone();
+        let userns = self.userNS.clone();
 
         return creds.HasCapabilityIn(Capability::CAP_SYS_ADMIN, &userns)
     }
@@ -283,12 +314,46 @@ impl ShmInternal {
             return true
         }
 
-        let ns = self.registry.lock().userNS.clone();
+        let ns = self.userNS.clone();
         return creds.HasCapabilityIn(Capability::CAP_IPC_OWNER, &ns)
     }
+
+    // Stat returns the shmid_ds of the segment as seen by task.
+    pub fn Stat(&self, task: &Task) -> ShmidDS {
+        let mut mode: u16 = 0;
+        if self.pendingDestruction {
+            mode |= SHM_DEST;
+        }
+
+        let userns = task.creds.lock().UserNamespace.clone();
+        return ShmidDS {
+            ShmPerm: IPCPerm {
+                Key: self.key as u32,
+                UID: userns.MapFromKUID(self.owner.UID).0,
+                GID: userns.MapFromKGID(self.owner.GID).0,
+                CUID: userns.MapFromKUID(self.creator.UID).0,
+                CGID: userns.MapFromKGID(self.creator.GID).0,
+                Mode: mode | self.perms.LinuxMode() as u16,
+                Seq: 0,
+                ..Default::default()
+            },
+            ShmSegsz: self.size,
+            ShmAtime: self.attachTime.TimeT(),
+            ShmDtime: self.detachTime.TimeT(),
+            ShmCtime: self.changeTime.TimeT(),
+            ShmCpid: self.creatorPID,
+            ShmLpid: self.lastAttachDetachPID,
+            ShmNattach: self.NumAttached(),
+            ..Default::default()
+        }
+    }
+
+    pub fn NumAttached(&self) -> i64 {
+        return self.attachment.strong_count() as i64
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct Shm(Arc<QMutex<ShmInternal>>);
 
 impl Deref for Shm {
@@ -299,9 +364,22 @@ impl Deref for Shm {
     }
 }
 
-impl Mapping for Shm {
+impl PartialEq for Shm {
+    fn eq(&self, other: &Self) -> bool {
+        return Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+// ShmAttachment is the MappingIdentity of attached segments. All the live
+// vmas of a segment share one ShmAttachment, and the segment is told when the
+// last of them goes away.
+pub struct ShmAttachment {
+    pub shm: Shm,
+}
+
+impl Mapping for ShmAttachment {
     fn MappedName(&self, _task: &Task) -> String {
-        return format!("SYSV{}", self.lock().key)
+        return format!("/SYSV{:08x} (deleted)", self.shm.lock().key as u32)
     }
 
     fn DeviceID(&self) -> u64 {
@@ -309,62 +387,113 @@ impl Mapping for Shm {
     }
 
     fn InodeID(&self) -> u64 {
-        return self.lock().id as u64;
+        return self.shm.lock().id as u64;
     }
 }
 
-impl Shm {
-    pub fn DecRef(&self) {
-        {
-            let mut me = self.lock();
-            me.refCount -= 1;
-            if me.refCount != 0 {
-                return
-            }
-        }
+impl Drop for ShmAttachment {
+    fn drop(&mut self) {
+        self.shm.Detached();
+    }
+}
 
-        self.destroy()
+impl Shm {
+    pub fn ID(&self) -> ID {
+        return self.lock().id;
     }
 
     pub fn EffectiveSize(&self) -> u64 {
         return self.lock().effectiveSize;
     }
 
-    pub fn IPCStat(&self, task: &Task) -> Result<ShmidDS> {
-        let me = self.lock();
+    // ConfigureAttach checks that task may attach the segment as described
+    // by opts, and returns the options to map it at addr.
+    pub fn ConfigureAttach(&self, task: &Task, addr: u64, opts: &AttachOpts) -> Result<MMapOpts> {
+        let mut me = self.lock();
 
-        if !me.checkPermission(task, &PermMask { read: true, ..Default::default() }) {
-            return Err(Error::SysError(SysErr::EACCES))
+        if me.pendingDestruction && me.NumAttached() == 0 {
+            return Err(Error::SysError(SysErr::EIDRM))
         }
 
-        let mut mode: u16 = 0;
-        if me.pendingDestruction {
-            mode |= SHM_DEST;
+        let perms = PermMask {
+            read: true,
+            write: !opts.ReadOnly,
+            execute: opts.Execute,
+        };
+
+        if !me.checkPermission(task, &perms) {
+            return Err(Error::SysError(SysErr::EACCES))
         }
 
-        let userns = task.creds.lock().UserNamespace.clone();
-        let ds = ShmidDS {
-            ShmPerm: IPCPerm {
-                Key: me.key as u32,
-                UID: userns.MapFromKUID(me.owner.UID).0,
-                GID: userns.MapFromKGID(me.owner.GID).0,
-                CUID: userns.MapFromKUID(me.creator.UID).0,
-                CGID: userns.MapFromKGID(me.creator.GID).0,
-                Mode: mode | me.perms.LinuxMode() as u16,
-                Seq: 0,
-                ..Default::default()
-            },
-            ShmSegsz: me.size,
-            ShmAtime: me.attachTime.TimeT(),
-            ShmDtime: me.detachTime.TimeT(),
-            ShmCtime: me.changeTime.TimeT(),
-            ShmCpid: me.creatorPID,
-            ShmLpid: me.lastAttachDetachPID,
-            ShmNattach: me.refCount,
-            ..Default::default()
+        let attachment: Arc<ShmAttachment> = match me.attachment.upgrade() {
+            Some(a) => a,
+            None => {
+                let a = Arc::new(ShmAttachment {
+                    shm: self.clone(),
+                });
+                me.attachment = Arc::downgrade(&a);
+                a
+            }
+        };
+
+        let access = AccessType::New(true, !opts.ReadOnly, opts.Execute);
+
+        return Ok(MMapOpts {
+            Length: me.effectiveSize,
+            Addr: addr,
+            Offset: 0,
+            Fixed: addr != 0,
+            Unmap: opts.Remap,
+            Map32Bit: false,
+            Perms: access,
+            MaxPerms: access,
+            Private: false,
+            VDSO: false,
+            GrowsDown: false,
+            Precommit: false,
+            MLockMode: MLockMode::default(),
+            Kernel: false,
+            Mapping: Some(attachment),
+            Mappable: Some(me.mappable.clone()),
+            Hint: "".to_string(),
+        })
+    }
+
+    // Attach records that the task attached the segment with shmat(2).
+    pub fn Attach(&self, task: &Task, pid: i32) {
+        let mut me = self.lock();
+        me.attachTime = task.Now();
+        me.lastAttachDetachPID = pid;
+    }
+
+    // Detach records that the task detached the segment with shmdt(2).
+    pub fn Detach(&self, task: &Task, pid: i32) {
+        let mut me = self.lock();
+        me.detachTime = task.Now();
+        me.lastAttachDetachPID = pid;
+    }
+
+    // Detached is called when the last vma attaching the segment is gone. A
+    // segment marked for destruction is destroyed then.
+    fn Detached(&self) {
+        let destroy = {
+            let me = self.lock();
+            me.pendingDestruction && me.NumAttached() == 0
         };
 
-        return Ok(ds)
+        if destroy {
+            self.destroy();
+        }
+    }
+
+    pub fn IPCStat(&self, task: &Task) -> Result<ShmidDS> {
+        let me = self.lock();
+
+        if !me.checkPermission(task, &PermMask { read: true, ..Default::default() }) {
+            return Err(Error::SysError(SysErr::EACCES))
+        }
+
+        return Ok(me.Stat(task))
     }
 
     pub fn Set(&self, task: &Task, ds: &ShmidDS) -> Result<()> {
@@ -393,27 +522,41 @@ impl Shm {
         return Ok(())
     }
 
-    pub fn MarkDestroyed(&self) {
-        self.lock().registry.dissociateKey(self);
+    // MarkDestroyed marks the segment for destruction with IPC_RMID. It is
+    // destroyed once it is no longer attached.
+    pub fn MarkDestroyed(&self, task: &Task) -> Result<()> {
+        let registry = self.lock().registry.clone();
 
-        let needDec = {
+        let destroy = {
+            let mut r = registry.lock();
             let mut me = self.lock();
-            if !me.pendingDestruction {
-                me.pendingDestruction = true;
-                true
-            } else {
-                false
+            if !me.checkOwnership(task) {
+                return Err(Error::SysError(SysErr::EPERM))
+            }
+
+            if me.pendingDestruction {
+                return Ok(())
             }
+
+            // The key is dissociated right away, so that shmget creates a new
+            // segment for it, while the segment lives on until detached.
+            if me.key != IPC_PRIVATE {
+                r.keysToShms.remove(&me.key);
+                me.key = IPC_PRIVATE;
+            }
+
+            me.pendingDestruction = true;
+            me.NumAttached() == 0
         };
 
-        if needDec {
-            self.DecRef()
+        if destroy {
+            self.destroy();
         }
+
+        return Ok(())
     }
 
-    pub fn destroy(&self) {
-        let me = self.lock();
-        PAGE_MGR.DerefRange(&me.fr).unwrap();
+    fn destroy(&self) {
         let registry = self.lock().registry.clone();
         registry.remove(self)
     }
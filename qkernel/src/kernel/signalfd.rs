@@ -137,7 +137,7 @@ impl FileOperations for SignalOperation {
     }
 
     fn ReadAt(&self, _task: &Task, _f: &File, dsts: &mut [IoVec], _offset: i64, _blocking: bool) -> Result<i64> {
-        let mut info = match self.target.Sigtimedwait(self.Mask(), 0) {
+        let mut info = match self.target.Sigtimedwait(self.Mask(), Some(0)) {
             Ok(info) => info,
             Err(_) => return Err(Error::SysError(SysErr::EAGAIN))
         };
@@ -19,9 +19,12 @@ use ::qlib::mutex::*;
 use super::super::super::kernel::time::*;
 use super::super::super::qlib::common::*;
 use super::super::super::qlib::linux::time::*;
+use super::super::super::asm::*;
 //use super::super::super::qlib::perf_tunning::*;
 use super::super::vdso::*;
 use super::calibratedClock::*;
+use super::parameters::*;
+use super::sampler::*;
 use super::timer::Clock;
 use super::timer::*;
 use super::*;
@@ -44,7 +47,10 @@ impl TimeKeeper {
             internal.Init(vdsoParamPageAddr);
         }
 
-        let timer = Timer::Period(&MONOTONIC_CLOCK, &Arc::new(TimerUpdater {}), 60 * SECOND);
+        // The vdso parameters are extrapolated from the TSC between updates,
+        // so they are refreshed at the interval the error correction in
+        // ErrorAdjust is designed for.
+        let timer = Timer::Period(&MONOTONIC_CLOCK, &Arc::new(TimerUpdater {}), APPROX_UPDATE_INTERVAL);
 
         {
             let mut internal = self.write();
@@ -66,7 +72,19 @@ impl TimeKeeper {
     }
 
     pub fn GetTime(&self, c: ClockID) -> Result<i64> {
-        return self.read().GetTime(c)
+        let (now, stale) = {
+            let tk = self.read();
+            let now = tk.GetTime(c)?;
+            (now, c == REALTIME && tk.RealtimeStale(now))
+        };
+
+        // The host clock was stepped since the last update, refresh the vdso
+        // parameters now instead of at the next tick.
+        if stale {
+            self.Update();
+        }
+
+        return Ok(now)
     }
 
     pub fn BootTime(&self) -> Time {
@@ -94,6 +112,11 @@ pub struct TimeKeeperInternal {
     pub inited: bool,
 
     pub timer: Option<Timer>,
+
+    // monotonicParams and realtimeParams are the clock parameters last
+    // written to the parameter page, before the monotonic offset.
+    pub monotonicParams: Parameters,
+    pub realtimeParams: Parameters,
 }
 
 impl Default for TimeKeeperInternal {
@@ -107,6 +130,8 @@ impl Default for TimeKeeperInternal {
             params: VDSOParamPage::default(),
             inited: false,
             timer: None,
+            monotonicParams: Parameters::default(),
+            realtimeParams: Parameters::default(),
         };
 
         return res;
@@ -144,19 +169,27 @@ impl TimeKeeperInternal {
 
         let mut p = VdsoParams::default();
         if monotonicOk {
+            let params = AdjustParams(MONOTONIC, &self.monotonicParams, &monotonicParams);
+            self.monotonicParams = params;
             p.monotonicReady = 1;
-            p.monotonicBaseCycles = monotonicParams.BaseCycles;
-            p.monotonicBaseRef = monotonicParams.BaseRef + self.monotonicOffset;
-            p.monotonicFrequency = monotonicParams.Frequency;
+            p.monotonicBaseCycles = params.BaseCycles;
+            p.monotonicBaseRef = params.BaseRef + self.monotonicOffset;
+            p.monotonicFrequency = params.Frequency;
+        } else {
+            self.monotonicParams = Parameters::default();
         }
 
         //error!("TimeKeeperInternal::Update monotonicParams is {:?}", &monotonicParams);
 
         if realtimeOk {
+            let params = AdjustParams(REALTIME, &self.realtimeParams, &realtimeParams);
+            self.realtimeParams = params;
             p.realtimeReady = 1;
-            p.realtimeBaseCycles = realtimeParams.BaseCycles;
-            p.realtimeBaseRef = realtimeParams.BaseRef;
-            p.realtimeFrequency = realtimeParams.Frequency;
+            p.realtimeBaseCycles = params.BaseCycles;
+            p.realtimeBaseRef = params.BaseRef;
+            p.realtimeFrequency = params.Frequency;
+        } else {
+            self.realtimeParams = Parameters::default();
         }
 
         match self.params.Write(&p) {
@@ -180,6 +213,18 @@ impl TimeKeeperInternal {
         }
     }
 
+    // RealtimeStale returns whether the realtime the vdso computes from the
+    // parameter page is further than MAX_CLOCK_ERROR from the host realtime
+    // now, i.e. the host clock was stepped since the last update.
+    pub fn RealtimeStale(&self, now: i64) -> bool {
+        if self.realtimeParams.Frequency == 0 {
+            return false
+        }
+
+        let (vdsoNow, ok) = self.realtimeParams.ComputeTime(Rdtsc());
+        return !ok || Magnitude(vdsoNow - now) > MAX_CLOCK_ERROR
+    }
+
     // BootTime returns the system boot real time.
     pub fn BootTime(&self) -> Time {
         assert!(self.inited, "TimeKeeper not inited");
@@ -203,3 +248,22 @@ impl TimeKeeperClock {
         return t.Sub(now)
     }
 }
+
+// AdjustParams returns the parameters to write for clock c, sampled as
+// newParams. The error against the parameters written last is corrected over
+// the next update interval so the clock doesn't jump. An error larger than
+// MAX_CLOCK_ERROR means the host clock was stepped and the new parameters are
+// used as they are.
+fn AdjustParams(c: ClockID, prevParams: &Parameters, newParams: &Parameters) -> Parameters {
+    if prevParams.Frequency == 0 {
+        return *newParams
+    }
+
+    match ErrorAdjust(prevParams, newParams, Rdtsc()) {
+        Ok((adjusted, errorNS)) if Magnitude(errorNS) <= MAX_CLOCK_ERROR => {
+            logErrorAdjustement(c, errorNS, newParams, &adjusted);
+            return adjusted
+        }
+        _ => return *newParams,
+    }
+}
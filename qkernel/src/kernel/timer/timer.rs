@@ -300,8 +300,10 @@ impl Notifier for Timer {
             return 0;
         }
 
-        // +2000ns as process time
-        let (s, exp) = t.setting.At(Time(now.0 + 20000));
+        // Expirations are only generated once the clock has actually reached
+        // the deadline; a kick that arrives a little early is simply rearmed
+        // by NextExpire.
+        let (s, exp) = t.setting.At(now);
         t.setting = s;
         if exp > 0 {
             t.listener.Notify(exp)
@@ -442,7 +444,7 @@ impl Timer {
                 panic!("Timer.Get called on paused Timer")
             }
 
-            let (setting, exp) = t.setting.At(Time(now.0 + 20000));
+            let (setting, exp) = t.setting.At(now);
             t.setting = setting;
             if exp > 0 {
                 t.listener.Notify(exp)
@@ -475,13 +477,12 @@ impl Timer {
     // Preconditions: The Timer must not be paused. f cannot call any Timer methods
     // since it is called with the Timer mutex locked.
     pub fn SwapAnd(&self, s: &Setting, mut f: impl FnMut()) -> (Time, Setting) {
-        let mut now;
+        let now;
         let oldS;
         let delta;
         {
             let mut t = self.lock();
             now = t.clock.Now();
-            now = Time(now.0 + 30000);
 
             oldS = if !t.paused {
                 let (oldS, oldExp) = t.setting.At(now);
@@ -555,8 +556,14 @@ pub struct ITimerRealListener {
 
 impl TimerListener for ITimerRealListener {
     fn Notify(&self, _exp: u64) {
-        let tg = self.tg.Upgrade().expect("TimerListener::Notify upgrade fail");
-        tg.SendSignal(&SignalInfoPriv(Signal::SIGALRM)).expect("TimerListener::Notify fail")
+        // The timer can race with the thread group exiting, in which case
+        // there is nobody left to signal.
+        let tg = match self.tg.Upgrade() {
+            None => return,
+            Some(tg) => tg,
+        };
+
+        tg.SendSignal(&SignalInfoPriv(Signal::SIGALRM)).ok();
     }
 
     fn Destroy(&self) {}
@@ -19,9 +19,12 @@ use ::qlib::mutex::*;
 use core::ops::Deref;
 
 use super::super::qlib::auth::userns::*;
+use super::super::uid::NewUID;
 
 #[derive(Default)]
 pub struct UTSNamespaceInternal {
+    // id identifies the namespace in /proc/[pid]/ns/uts.
+    pub id: u64,
     pub hostName: String,
     pub domainName: String,
     pub userns: UserNameSpace,
@@ -41,6 +44,7 @@ impl Deref for UTSNamespace {
 impl UTSNamespace {
     pub fn New(hostName: String, domainName: String, userns: UserNameSpace) -> Self {
         let internal = UTSNamespaceInternal {
+            id: NewUID(),
             hostName: hostName,
             domainName: domainName,
             userns: userns
@@ -49,6 +53,10 @@ impl UTSNamespace {
         return Self(Arc::new(QMutex::new(internal)))
     }
 
+    pub fn ID(&self) -> u64 {
+        return self.lock().id
+    }
+
     pub fn HostName(&self) -> String {
         return self.lock().hostName.to_string();
     }
@@ -72,6 +80,7 @@ impl UTSNamespace {
     pub fn Fork(&self, userns: &UserNameSpace) -> Self {
         let me = self.lock();
         let internal = UTSNamespaceInternal {
+            id: NewUID(),
             hostName: me.hostName.to_string(),
             domainName: me.domainName.to_string(),
             userns: userns.clone(),
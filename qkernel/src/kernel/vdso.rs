@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use core::mem;
+use core::ptr;
 
 use super::super::asm::*;
 use super::super::qlib::common::*;
@@ -160,8 +160,14 @@ impl VDSOParamPage {
     fn IncrementSeq(&mut self) -> Result<()> {
         let next = self.seq + 1;
 
-        let mut old: u64 = next;
-        mem::swap(&mut old, &mut self.vdsoParams.seq_count);
+        // The vdso readers poll the count, so it must be written through
+        // volatile accesses the compiler can't merge or drop.
+        let old = unsafe {
+            let seq = &mut self.vdsoParams.seq_count as *mut u64;
+            let old = ptr::read_volatile(seq);
+            ptr::write_volatile(seq, next);
+            old
+        };
 
         if old != self.seq {
             return Err(Error::Common(format!("unexpected VDSOParamPage seq value: got {} expected {}. Application may hang or get incorrect time from the VDSO.",
@@ -180,17 +186,17 @@ impl VDSOParamPage {
         }
 
         self.IncrementSeq()?;
+        // A reader that sees the odd count retries, so the parameters must
+        // not be stored before it or after the final even count.
+        WriteBarrier();
 
-        self.vdsoParams.monotonicReady = para.monotonicReady;
-        self.vdsoParams.monotonicBaseCycles = para.monotonicBaseCycles;
-        self.vdsoParams.monotonicBaseRef = para.monotonicBaseRef;
-        self.vdsoParams.monotonicFrequency = para.monotonicFrequency;
-
-        self.vdsoParams.realtimeReady = para.realtimeReady;
-        self.vdsoParams.realtimeBaseCycles = para.realtimeBaseCycles;
-        self.vdsoParams.realtimeBaseRef = para.realtimeBaseRef;
-        self.vdsoParams.realtimeFrequency = para.realtimeFrequency;
+        let mut p = *para;
+        p.seq_count = self.seq;
+        unsafe {
+            ptr::write_volatile(&mut *self.vdsoParams as *mut VdsoParams, p);
+        }
 
+        WriteBarrier();
         return self.IncrementSeq();
     }
 }
@@ -198,6 +204,6 @@ impl VDSOParamPage {
 const NS_PER_SEC: i128 = 1000_000_000;
 
 fn CyclesToNs(freq: u64, cycles: i64) -> i64 {
-    let mult = NS_PER_SEC << 32 / freq as i128;
+    let mult = (NS_PER_SEC << 32) / freq as i128;
     return ((cycles as i128 * mult) >> 32) as i64;
 }
\ No newline at end of file
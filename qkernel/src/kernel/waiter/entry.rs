@@ -53,7 +53,7 @@ impl WaitContext {
         }
     }
 
-    pub fn CallBack(&self) {
+    pub fn CallBack(&self, mask: EventMask) {
         match self {
             WaitContext::EpollContext(p) => {
                 p.CallBack();
@@ -63,7 +63,7 @@ impl WaitContext {
                 context.waiter.Trigger(context.waiterID);
             }
             WaitContext::FileAsync(a) => {
-                a.Callback();
+                a.Callback(mask);
             }
             _ => (),
         }
@@ -84,6 +84,9 @@ pub struct EntryInternal {
     pub prev: Option<WaitEntry>,
     pub mask: EventMask,
 
+    // exclusive entries are woken one at a time, see Queue::Notify.
+    pub exclusive: bool,
+
     pub context: WaitContext,
 }
 
@@ -112,6 +115,7 @@ impl WaitEntry {
             next: None,
             prev: None,
             mask: 0,
+            exclusive: false,
             context: WaitContext::None,
         };
 
@@ -134,6 +138,7 @@ impl WaitEntry {
             next: None,
             prev: None,
             mask: mask,
+            exclusive: false,
             context: WaitContext::ThreadContext(RefCell::new(context)),
         };
 
@@ -148,6 +153,14 @@ impl WaitEntry {
         self.lock().mask = mask;
     }
 
+    pub fn SetExclusive(&self, exclusive: bool) {
+        self.lock().exclusive = exclusive;
+    }
+
+    pub fn Exclusive(&self) -> bool {
+        return self.lock().exclusive;
+    }
+
     pub fn SetTid(&self, tid: u32) {
         self.lock().context.ThreadContext().tid = tid;
     }
@@ -159,7 +172,7 @@ impl WaitEntry {
     pub fn Notify(&self, mask: EventMask) -> bool {
         let e = self.lock();
         if mask & e.mask != 0 {
-            e.context.CallBack();
+            e.context.CallBack(mask);
             return true
         }
 
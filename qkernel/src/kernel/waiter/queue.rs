@@ -50,12 +50,20 @@ impl Waitable for Queue {
 
 impl Queue {
     //notify won't remove the trigged waitentry
+    //
+    // All non-exclusive entries interested in mask are woken, but at most one
+    // exclusive entry is, as with EPOLLEXCLUSIVE in Linux.
     pub fn Notify(&self, mask: EventMask) {
         let q = self.read();
         let mut entry = q.Front();
+        let mut exclusiveWoken = false;
         while entry.is_some() {
             let tmp = entry.clone().unwrap();
-            tmp.Notify(mask);
+            if !tmp.Exclusive() {
+                tmp.Notify(mask);
+            } else if !exclusiveWoken {
+                exclusiveWoken = tmp.Notify(mask);
+            }
             entry = tmp.lock().next.clone();
         }
     }
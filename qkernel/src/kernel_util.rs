@@ -29,12 +29,12 @@ pub fn Random(buf: u64, len: u64, flags: u32) -> Result<()> {
 
 pub fn RandU64() -> Result<u64> {
     let res: u64 = 0;
-    Random(&res as *const _ as u64, 8, GRND_RANDOM)?;
+    Random(&res as *const _ as u64, 8, 0)?;
     return Ok(res)
 }
 
 pub fn RandU128() -> Result<(u64, u64)> {
     let res: [u64; 2] = [0; 2];
-    Random(&res[0] as *const _ as u64, 18, GRND_RANDOM)?;
+    Random(&res[0] as *const _ as u64, 16, 0)?;
     return Ok((res[0], res[1]))
 }
\ No newline at end of file
@@ -181,10 +181,15 @@ pub fn SingletonInit() {
         kernel::abstract_socket_namespace::InitSingleton();
         kernel::futex::InitSingleton();
         kernel::kernel::InitSingleton();
+        kernel::loadavg::InitSingleton();
         kernel::semaphore::InitSingleton();
+        kernel::shm::InitSingleton();
         kernel::epoll::epoll::InitSingleton();
         kernel::timer::InitSingleton();
         loader::vdso::InitSingleton();
+        quring::fixed_buf::InitSingleton();
+        socket::hostinet::interfaces::InitSingleton();
+        socket::hostinet::loopback::InitSingleton();
         socket::socket::InitSingleton();
         syscalls::sys_rlimit::InitSingleton();
         task::InitSingleton();
@@ -205,6 +210,7 @@ pub fn Init() {
 #[no_mangle]
 pub extern fn syscall_handler(arg0: u64, arg1: u64, arg2: u64, arg3: u64, arg4: u64, arg5: u64) -> ! {
     //PerfGofrom(PerfType::User);
+    CPULocal::EnterKernel();
 
     let currTask = task::Task::Current();
     currTask.PerfGofrom(PerfType::User);
@@ -299,6 +305,7 @@ pub extern fn syscall_handler(arg0: u64, arg1: u64, arg2: u64, arg3: u64, arg4:
     if SHARESPACE.config.read().KernelPagetable {
         currTask.SwitchPageTable();
     }
+    CPULocal::FlushStaleTlb();
 
     if !(pt.rip == pt.rcx && pt.r11 == pt.eflags) {
         //error!("iret *****, pt is {:x?}", pt);
@@ -401,6 +408,11 @@ pub extern fn rust_main(heapStart: u64, heapLen: u64, id: u64, vdsoParamAddr: u6
         InitGs(id);
 
         SHARESPACE.scheduler.SetVcpuCnt(vcpuCnt as usize);
+        // the host registers the fixed buffer pool with its rings in HYPERCALL_INIT
+        let fixedBufs = AlignedAllocator::New(qlib::uring::FixedBufs::POOL_SIZE, MemoryDef::PAGE_SIZE as usize)
+            .Allocate()
+            .expect("can't allocate the io_uring fixed buffers");
+        SHARESPACE.fixedBufs.Init(fixedBufs);
         HyperCall64(qlib::HYPERCALL_INIT, (&(*SHARESPACE) as *const ShareSpace) as u64, 0, 0);
         IOURING.Setup(SHARESPACE.config.read().DedicateUring);
         {
@@ -417,6 +429,7 @@ pub extern fn rust_main(heapStart: u64, heapLen: u64, id: u64, vdsoParamAddr: u6
         SetVCPCount(vcpuCnt as usize);
         InitTimeKeeper(vdsoParamAddr);
         VDSO.Initialization(vdsoParamAddr);
+        kernel::loadavg::LOAD_AVG.Start();
     } else {
         InitGs(id);
         //PerfGoto(PerfType::Kernel);
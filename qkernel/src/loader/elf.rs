@@ -24,14 +24,13 @@ use xmas_elf::*;
 use super::super::qlib::common::*;
 use super::super::qlib::linux_def::*;
 use super::super::qlib::addr::*;
-use super::super::qlib::limits::*;
 use super::super::qlib::auxv::*;
 use super::super::util::cstring::*;
-use super::super::qlib::platform::defs_impl::*;
 use super::super::task::*;
 use super::super::fs::file::*;
 use super::super::memmgr::*;
 use super::super::arch::x86_64::context::*;
+use super::loader::*;
 
 pub const ELF_MAGIC : &str = "\x7fELF";
 pub const INTERPRETER_SCRIPT_MAGIC: &str = "#!";
@@ -433,10 +432,10 @@ pub fn LoadParseElf(task: &mut Task, file: &File, info: &mut ElfHeadersInfo, sha
 pub fn LoadInitalElf(task: &mut Task, file: &File) -> Result<LoadedElf> {
     let mut info = ParseHeader(task, file)?;
 
-    let l = task.mm.SetMmapLayout(MIN_USER_ADDR, MAX_USER_ADDR, &LimitSet::default())?;
-    *task.mm.layout.lock() = l;
+    // The layout is set up by Load.
+    let l = *task.mm.layout.lock();
 
-    let loadAddr = Context64::PIELoadAddress(&l)?;
+    let loadAddr = Context64::PIELoadAddress(&l, RandomizeVASpace(task) > 0)?;
 
     let le = LoadParseElf(task, file, &mut info, loadAddr)?;
     return Ok(le)
@@ -15,6 +15,7 @@
 use alloc::string::String;
 use alloc::string::ToString;
 use alloc::vec::Vec;
+use core::sync::atomic::Ordering;
 
 use super::elf::*;
 use super::super::qlib::common::*;
@@ -31,7 +32,12 @@ use super::super::task::*;
 use super::super::kernel::timer::*;
 use super::super::kernel_util::*;
 use super::super::memmgr::*;
+use super::super::memmgr::arch::*;
 //use super::super::memmgr::mm::*;
+use super::super::arch::x86_64::context::*;
+use super::super::qlib::limits::*;
+use super::super::qlib::linux::personality::*;
+use super::super::qlib::platform::defs_impl::*;
 use super::interpreter::*;
 
 // maxLoaderAttempts is the maximum number of attempts to try to load
@@ -117,7 +123,7 @@ pub fn OpenPath(task: &mut Task, filename: &str, maxTraversals: u32) -> Result<(
     };
 
     let inode = d.Inode();
-    inode.CheckPermission(task, &perms)?;
+    d.CheckPermission(task, &perms)?;
 
     let len = filename.len();
     // If they claim it's a directory, then make sure.
@@ -198,7 +204,9 @@ pub fn CreateStack(task: &Task) -> Result<Range> {
     moptions.Addr = stackStart;
     moptions.Fixed = true;
     moptions.Perms = AccessType::ReadWrite();
-    moptions.MaxPerms = AccessType::ReadWrite();
+    // Like any anonymous mapping, the stack may be made executable later,
+    // e.g. by a JVM or by dlopen of a library needing an executable stack.
+    moptions.MaxPerms = AccessType::AnyAccess();
     moptions.Private = true;
     moptions.GrowsDown = true;
 
@@ -210,15 +218,35 @@ pub fn CreateStack(task: &Task) -> Result<Range> {
 
 pub const TASK_COMM_LEN : usize = 16;
 
+// RandomizeVASpace returns the address space randomization level to use for a
+// new image loaded by task: the randomize_va_space sysctl, or 0 if the task's
+// persona has ADDR_NO_RANDOMIZE.
+pub fn RandomizeVASpace(task: &Task) -> i32 {
+    if task.Thread().Personality() & ADDR_NO_RANDOMIZE != 0 {
+        return 0
+    }
+
+    return RANDOMIZE_VA_SPACE.load(Ordering::Relaxed)
+}
+
 // Load loads filename into a MemoryManager.
 //return (entry: u64, usersp: u64, kernelsp: u64)
 pub fn Load(task: &mut Task, filename: &str, argv: &mut Vec<String>, envv: &[String], extraAuxv: &[AuxEntry]) -> Result<(u64, u64, u64)> {
+    let randomize = RandomizeVASpace(task);
+
+    // Set up the layout before anything is mapped so that the vdso is
+    // randomized too.
+    task.mm.SetMmapLayout(MIN_USER_ADDR, MAX_USER_ADDR, &LimitSet::default(), randomize > 0)?;
+
     let vdsoAddr = LoadVDSO(task)?;
 
     let (loaded, executable, tmpArgv) = LoadExecutable(task, filename, argv)?;
     let argv = tmpArgv;
 
-    let e = Addr(loaded.end).RoundUp()?.0;
+    let mut e = Addr(loaded.end).RoundUp()?.0;
+    if randomize > 1 {
+        e += MMapRand(MAX_BRK_RAND64)?;
+    }
 
     task.mm.BrkSetup(e);
     task.mm.SetExecutable(&executable);
@@ -232,7 +260,12 @@ pub fn Load(task: &mut Task, filename: &str, argv: &mut Vec<String>, envv: &[Str
 
     let stackRange = CreateStack(task)?;
 
-    let mut stack = Stack::New(stackRange.End());
+    let mut stackTop = stackRange.End();
+    if randomize > 0 {
+        stackTop -= RandU64()? % MAX_STACK_ALIGN_RAND64;
+    }
+
+    let mut stack = Stack::New(stackTop);
 
     let usersp = SetupUserStack(task, &mut stack, &loaded, filename, &argv, envv, extraAuxv, vdsoAddr)?;
     let kernelsp = Task::TaskId().Addr() + MemoryDef::DEFAULT_STACK_SIZE - 0x10;
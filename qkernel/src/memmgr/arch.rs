@@ -12,11 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use core::sync::atomic::AtomicI32;
+
 use super::super::arch::x86_64::context::*;
 use super::super::qlib::addr::*;
 
 pub type MmapDirection = i32;
 
+// RANDOMIZE_VA_SPACE is the value of /proc/sys/kernel/randomize_va_space. 0
+// disables address space randomization, 1 randomizes the stack, the mmap
+// base, the vdso and the PIE load address, and 2 also randomizes the heap.
+pub static RANDOMIZE_VA_SPACE : AtomicI32 = AtomicI32::new(2);
+
 pub const MMAP_BOTTOM_UP: MmapDirection = 0;
 pub const MMAP_TOP_DOWN: MmapDirection = 1;
 
@@ -80,6 +87,11 @@ impl MmapLayout {
     }
 
     pub fn MapStackAddr(&self) -> u64 {
-        return Addr(self.MaxAddr - MMapRand(self.MaxStackRand).expect("MapStackAddr fail")).RoundDown().unwrap().0;
+        let mut maxRand = self.MaxStackRand;
+        if maxRand > MAX_STACK_RAND64 {
+            maxRand = MAX_STACK_RAND64;
+        }
+
+        return Addr(self.MaxAddr - MMapRand(maxRand).expect("MapStackAddr fail")).RoundDown().unwrap().0;
     }
 }
@@ -279,6 +279,21 @@ impl AreaSet<MappingsOfRange> {
         return ranges;
     }
 
+    // HasWriteableMapping returns whether any mapping of s may write to the
+    // mapped object.
+    pub fn HasWriteableMapping(&self) -> bool {
+        let mut seg = self.FirstSeg();
+        while seg.Ok() {
+            if seg.Value().lock().iter().any(|m| m.Writeable) {
+                return true
+            }
+
+            seg = seg.NextSeg();
+        }
+
+        return false
+    }
+
     // InvalidateAll calls MappingSpace.Invalidate for all mappings of s.
     pub fn InvalidateAll(&mut self, task: &Task, invalidatePrivate: bool) {
         let mut seg = self.FirstSeg();
@@ -34,6 +34,7 @@ use super::super::stack::*;
 use super::super::qlib::auxv::*;
 use super::super::task::*;
 use super::super::qlib::pagetable::*;
+use super::super::qlib::vcpu_mgr::*;
 use super::super::qlib::limits::*;
 use super::super::kernel::aio::aio_context::*;
 use super::super::fs::dirent::*;
@@ -267,8 +268,10 @@ impl MemoryManager {
     }
 
     //Remove virtual memory to the phy mem mapping
-    pub fn RemoveVMAsLocked(&self, ar: &Range) -> Result<()> {
+    // RemoveVMAsLocked returns the number of pages which were mapped in ar.
+    pub fn RemoveVMAsLocked(&self, ar: &Range) -> Result<u64> {
         let mut mapping = self.mapping.lock();
+        let mut unmapped = 0;
         let (mut vseg, vgap) = mapping.vmas.Find(ar.Start());
         if vgap.Ok() {
             vseg = vgap.NextSeg();
@@ -291,13 +294,13 @@ impl MemoryManager {
 
             let mut pt = self.pagetable.write();
 
-            pt.pt.MUnmap(r.Start(), r.Len())?;
+            unmapped += pt.pt.MUnmap(r.Start(), r.Len())?;
             pt.curRSS -= r.Len();
             let vgap = mapping.vmas.Remove(&vseg);
             vseg = vgap.NextSeg();
         }
 
-        return Ok(())
+        return Ok(unmapped)
     }
 
     pub fn MappingReadLock(&self) -> QMutexGuard<()> {
@@ -469,6 +472,32 @@ impl MemoryManager {
         return ret.as_bytes().to_vec();
     }
 
+    // CoreSegments returns the user mappings for a core dump. As with Linux's
+    // default coredump_filter, only anonymous mappings and writable private
+    // file mappings have their contents dumped; the rest can be read back
+    // from the files they map, see fs/coredump.c:vma_dump_size.
+    pub fn CoreSegments(&self) -> Vec<CoreSegment> {
+        let internal = self.mapping.lock();
+        let mut seg = internal.vmas.FirstSeg();
+        let mut ret = Vec::new();
+        while !seg.IsTail() {
+            let vma = seg.Value();
+            if !vma.kernel {
+                let perms = vma.realPerms;
+                let dump = perms.Read() && (vma.mappable.is_none() || (vma.private && perms.Write()));
+                ret.push(CoreSegment {
+                    range: seg.Range(),
+                    perms: perms,
+                    dump: dump,
+                });
+            }
+
+            seg = seg.NextSeg();
+        }
+
+        return ret;
+    }
+
     pub fn SetExecutable(&self, dirent: &Dirent) {
         self.metadata.lock().executable = Some(dirent.clone());
     }
@@ -511,9 +540,24 @@ impl MemoryManager {
         return Ok(())
     }
 
-    pub fn MinCore(&self, _task: &Task, r: &Range) -> Vec<u8> {
+    pub fn MinCore(&self, _task: &Task, r: &Range) -> Result<Vec<u8>> {
         let _ml = self.MappingWriteLock();
 
+        // "ENOMEM: addr to addr + length contained unmapped memory." - mincore(2)
+        {
+            let mapping = self.mapping.lock();
+            let mut vseg = mapping.vmas.LowerBoundSeg(r.Start());
+            let mut lastEnd = r.Start();
+            while lastEnd < r.End() {
+                if !vseg.Ok() || vseg.Range().Start() > lastEnd {
+                    return Err(Error::SysError(SysErr::ENOMEM))
+                }
+
+                lastEnd = vseg.Range().End();
+                vseg = vseg.NextSeg();
+            }
+        }
+
         let mut res = Vec::with_capacity((r.Len() / MemoryDef::PAGE_SIZE) as usize);
         let mut addr = r.Start();
         while addr < r.End() {
@@ -524,12 +568,12 @@ impl MemoryManager {
             addr += MemoryDef::PAGE_SIZE;
         }
 
-        return res;
+        return Ok(res);
     }
 
     // MLock implements the semantics of Linux's mlock()/mlock2()/munlock(),
     // depending on mode.
-    pub fn Mlock(&self, _task: &Task, addr: u64, len: u64, mode: MLockMode) -> Result<()> {
+    pub fn Mlock(&self, task: &Task, addr: u64, len: u64, mode: MLockMode) -> Result<()> {
         let la = match Addr(len + Addr(addr).PageOffset()).RoundUp() {
             Ok(l) => l.0,
             Err(_) => return Err(Error::SysError(SysErr::EINVAL))
@@ -546,62 +590,73 @@ impl MemoryManager {
             return Ok(())
         }
 
-        let mut unmapped = false;
-
-        let mut mapping = self.mapping.lock();
-        let mut vseg = mapping.vmas.FindSeg(ar.Start());
-        loop {
-            if !vseg.Ok() {
-                unmapped = true;
-                break;
-            }
+        {
+            let mut mapping = self.mapping.lock();
 
-            vseg = mapping.vmas.Isolate(&vseg, &ar);
-            let mut vma = vseg.Value();
-            let prevMode = vma.mlockMode;
-            vma.mlockMode = mode;
-            vseg.SetValue(vma);
-            if mode != MLockMode::MlockNone && prevMode == MLockMode::MlockNone {
-                mapping.lockedAS += vseg.Range().Len();
-            } else if mode == MLockMode::MlockNone && prevMode != MLockMode::MlockNone {
-                mapping.lockedAS -= vseg.Range().Len();
+            if mode != MLockMode::MlockNone {
+                // Check against RLIMIT_MEMLOCK.
+                let newLockedAS = mapping.lockedAS + ar.Len() - MLockedBytesRange(&mapping, &ar);
+                CheckMLockLimit(task, newLockedAS)?;
             }
 
-            if ar.End() <= vseg.Range().End() {
-                break;
-            }
-            let (vsegTmp, _) = vseg.NextNonEmpty();
-            vseg = vsegTmp;
-        }
+            let mut unmapped = false;
+            let mut vseg = mapping.vmas.FindSeg(ar.Start());
+            loop {
+                if !vseg.Ok() {
+                    unmapped = true;
+                    break;
+                }
 
-        mapping.vmas.MergeRange(&ar);
-        mapping.vmas.MergeAdjacent(&ar);
-        if unmapped {
-            return Err(Error::SysError(SysErr::ENOMEM))
-        }
+                vseg = mapping.vmas.Isolate(&vseg, &ar);
+                let mut vma = vseg.Value();
+                let prevMode = vma.mlockMode;
+                vma.mlockMode = mode;
+                vseg.SetValue(vma);
+                if mode != MLockMode::MlockNone && prevMode == MLockMode::MlockNone {
+                    mapping.lockedAS += vseg.Range().Len();
+                } else if mode == MLockMode::MlockNone && prevMode != MLockMode::MlockNone {
+                    mapping.lockedAS -= vseg.Range().Len();
+                }
 
-        // todo: populate the pagetable
-        // if mode == MLockMode::MlockEager {}
+                if ar.End() <= vseg.Range().End() {
+                    break;
+                }
+                let (vsegTmp, _) = vseg.NextNonEmpty();
+                vseg = vsegTmp;
+            }
 
-        let mut vseg = mapping.vmas.FindSeg(ar.Start());
-        while vseg.Ok() && vseg.Range().Start() < ar.End() {
-            let vma = vseg.Value();
-            // Linux: mm/gup.c:__get_user_pages() returns EFAULT in this
-            // case, which is converted to ENOMEM by mlock.
-            if !vma.effectivePerms.Any() {
+            mapping.vmas.MergeRange(&ar);
+            mapping.vmas.MergeAdjacent(&ar);
+            if unmapped {
                 return Err(Error::SysError(SysErr::ENOMEM))
             }
 
-            if let Some(iops) = vma.mappable.clone() {
-                let mr = ar.Intersect(&vseg.Range());
-                let fstart = mr.Start() - vseg.Range().Start() + vma.offset;
+            let mut vseg = mapping.vmas.FindSeg(ar.Start());
+            while vseg.Ok() && vseg.Range().Start() < ar.End() {
+                let vma = vseg.Value();
+                // Linux: mm/gup.c:__get_user_pages() returns EFAULT in this
+                // case, which is converted to ENOMEM by mlock.
+                if !vma.effectivePerms.Any() {
+                    return Err(Error::SysError(SysErr::ENOMEM))
+                }
+
+                if let Some(iops) = vma.mappable.clone() {
+                    let mr = ar.Intersect(&vseg.Range());
+                    let fstart = mr.Start() - vseg.Range().Start() + vma.offset;
+
+                    // todo: fix the Munlock, when there are multiple process lock/unlock a memory range.
+                    // with current implementation, the first unlock will work.
+                    iops.Mlock(fstart, mr.Len(), mode)?;
+                }
 
-                // todo: fix the Munlock, when there are multiple process lock/unlock a memory range.
-                // with current implementation, the first unlock will work.
-                iops.Mlock(fstart, mr.Len(), mode)?;
+                vseg = vseg.NextSeg()
             }
+        }
 
-            vseg = vseg.NextSeg()
+        if mode == MLockMode::MlockEager {
+            // Fault in the whole range now so that it is resident when mlock
+            // returns.
+            self.FixPermissionLocked(task, ar.Start(), ar.Len(), false, false)?;
         }
 
         return Ok(())
@@ -609,38 +664,69 @@ impl MemoryManager {
 
     // MLockAll implements the semantics of Linux's mlockall()/munlockall(),
     // depending on opts.
-    pub fn MlockAll(&self, _task: &Task, opts: &MLockAllOpts) -> Result<()> {
+    pub fn MlockAll(&self, task: &Task, opts: &MLockAllOpts) -> Result<()> {
         if !opts.Current && !opts.Future {
             return Err(Error::SysError(SysErr::EINVAL))
         }
 
-        // todo: fully support opts.Current and opts.Future
-        // it is not supported now
         let mode = opts.Mode;
         let _ml = self.MappingWriteLock();
 
-        let mapping = self.mapping.lock();
-        let mut vseg = mapping.vmas.FirstSeg();
-        while vseg.Ok() {
-            let mut vma = vseg.Value();
-            vma.mlockMode = mode;
-            vseg.SetValue(vma.clone());
+        let mut populate = Vec::new();
+        {
+            let mut mapping = self.mapping.lock();
 
-            if !vma.effectivePerms.Any() {
-                vseg = vseg.NextSeg();
-                continue;
-            }
+            if opts.Current {
+                if mode != MLockMode::MlockNone {
+                    // Check against RLIMIT_MEMLOCK.
+                    CheckMLockLimit(task, mapping.usageAS)?;
+                }
 
-            if let Some(iops) = vma.mappable.clone() {
-                let mr = vseg.Range();
-                let fstart = mr.Start() - vseg.Range().Start() + vma.offset;
+                let mut lockedAS = mapping.lockedAS;
+                let mut vseg = mapping.vmas.FirstSeg();
+                while vseg.Ok() {
+                    let mut vma = vseg.Value();
+                    let prevMode = vma.mlockMode;
+                    vma.mlockMode = mode;
+                    vseg.SetValue(vma.clone());
+                    if mode != MLockMode::MlockNone && prevMode == MLockMode::MlockNone {
+                        lockedAS += vseg.Range().Len();
+                    } else if mode == MLockMode::MlockNone && prevMode != MLockMode::MlockNone {
+                        lockedAS -= vseg.Range().Len();
+                    }
 
-                // todo: fix the Munlock, when there are multiple process lock/unlock a memory range.
-                // with current implementation, the first unlock will work.
-                iops.Mlock(fstart, mr.Len(), mode)?;
+                    if !vma.effectivePerms.Any() {
+                        vseg = vseg.NextSeg();
+                        continue;
+                    }
+
+                    if mode == MLockMode::MlockEager && !vma.kernel {
+                        populate.push(vseg.Range());
+                    }
+
+                    if let Some(iops) = vma.mappable.clone() {
+                        let mr = vseg.Range();
+                        let fstart = mr.Start() - vseg.Range().Start() + vma.offset;
+
+                        // todo: fix the Munlock, when there are multiple process lock/unlock a memory range.
+                        // with current implementation, the first unlock will work.
+                        iops.Mlock(fstart, mr.Len(), mode)?;
+                    }
+
+                    vseg = vseg.NextSeg();
+                }
+
+                mapping.lockedAS = lockedAS;
+            }
+
+            if opts.Future {
+                mapping.defMLockMode = mode;
             }
+        }
 
-            vseg = vseg.NextSeg();
+        for r in &populate {
+            // Locking is best effort beyond this point, as in Linux.
+            self.FixPermissionLocked(task, r.Start(), r.Len(), false, true).ok();
         }
 
         return Ok(())
@@ -704,7 +790,7 @@ impl MemoryManager {
                 let mr = ar.Intersect(&vseg.Range());
 
                 let fstart = mr.Start() - vseg.Range().Start() + vma.offset;
-                fops.MSync(&Range::New(fstart, mr.Len()), msyncType)?;
+                fops.MSync(&Range::New(fstart, mr.Len()), msyncType, opts.Invalidate)?;
 
                 if lastEnd >= ar.End() {
                     break;
@@ -726,8 +812,8 @@ impl MemoryManager {
         return Ok(())
     }
 
-    pub fn SetMmapLayout(&self, minUserAddr: u64, maxUserAddr: u64, r: &LimitSet) -> Result<MmapLayout> {
-        let layout = Context64::NewMmapLayout(minUserAddr, maxUserAddr, r)?;
+    pub fn SetMmapLayout(&self, minUserAddr: u64, maxUserAddr: u64, r: &LimitSet, randomize: bool) -> Result<MmapLayout> {
+        let layout = Context64::NewMmapLayout(minUserAddr, maxUserAddr, r, randomize)?;
         *self.layout.lock() = layout;
         return Ok(layout)
     }
@@ -773,7 +859,10 @@ impl MemoryManager {
 
     pub fn InstallPageWithAddrLocked(&self, task: &Task, pageAddr: u64) -> Result<()> {
         let (vma, range) = match self.GetVmaAndRangeLocked(pageAddr) {
-            None => return Err(Error::SysError(SysErr::EFAULT)),
+            None => match self.GrowStackLocked(task, pageAddr) {
+                Err(_) => return Err(Error::SysError(SysErr::EFAULT)),
+                Ok(data) => data,
+            },
             Some(data) => data
         };
 
@@ -867,7 +956,9 @@ impl MemoryManager {
             .expect(&format!("CopyOnWrite PAGE_MGR GetRef addr {:x} fail", phyAddr));
 
         let exec = vma.effectivePerms.Exec();
-        if refCount == 1 && vma.mappable.is_none() {
+        // The pages of a shared mapping are never copied, or the other
+        // processes mapping them, e.g. a forked child, wouldn't see the write.
+        if !vma.private || (refCount == 1 && vma.mappable.is_none()) {
             //print!("CopyOnWriteLocked enable write ... pageaddr is {:x}", pageAddr);
             self.EnableWriteLocked(pageAddr, exec);
         } else {
@@ -955,6 +1046,60 @@ impl MemoryManager {
         return Ok(())
     }
 
+    // V2PRemote is V2P for the memory of another process, e.g. for
+    // process_vm_readv(2). Only the part of the range which is in the user
+    // address space and mapped with the required permission is translated,
+    // and its length is returned. The pages hold a reference until the caller
+    // passes output to DerefPages, so that a concurrent munmap can't free
+    // them while they are copied.
+    pub fn V2PRemote(&self, task: &Task, start: u64, len: u64, output: &mut Vec<IoVec>, writable: bool) -> Result<u64> {
+        let ar = self.ApplicationAddrRange();
+        if start < ar.Start() || start >= ar.End() ||
+            (MemoryDef::PHY_LOWER_ADDR <= start && start < MemoryDef::PHY_UPPER_ADDR) {
+            return Err(Error::SysError(SysErr::EFAULT))
+        }
+
+        let mut end = if ar.End() - start < len {
+            ar.End()
+        } else {
+            start + len
+        };
+
+        // The kernel's memory is in the middle of the user address range.
+        if start < MemoryDef::PHY_LOWER_ADDR && end > MemoryDef::PHY_LOWER_ADDR {
+            end = MemoryDef::PHY_LOWER_ADDR;
+        }
+
+        let _ml = self.MappingWriteLock();
+
+        let n = self.FixPermissionLocked(task, start, end - start, writable, true)?;
+        if n == 0 {
+            return Ok(0)
+        }
+
+        self.V2PLocked(task, start, n, output, writable)?;
+        for iov in output.iter() {
+            let mut page = Addr(iov.start).RoundDown()?.0;
+            while page < iov.End() {
+                PAGE_MGR.Ref(page)?;
+                page += MemoryDef::PAGE_SIZE;
+            }
+        }
+
+        return Ok(n)
+    }
+
+    // DerefPages drops the page references taken by V2PRemote.
+    pub fn DerefPages(iovs: &[IoVec]) {
+        for iov in iovs {
+            let mut page = Addr(iov.start).RoundDown().unwrap().0;
+            while page < iov.End() {
+                PAGE_MGR.DerefPage(page);
+                page += MemoryDef::PAGE_SIZE;
+            }
+        }
+    }
+
     // check whether the address range is legal.
     // 1. whether the range belong to user's space
     // 2. Whether the read/write permission meet requirement
@@ -1095,7 +1240,12 @@ impl MemoryManager {
         };
 
         // todo: change the name to pt.Remap
-        pt.pt.RemapAna(task, &Range::New(ar.Start(), len), oldar.Start(), &perms, true)?;
+        let moved = pt.pt.RemapAna(task, &Range::New(ar.Start(), len), oldar.Start(), &perms, true)?;
+
+        // Other vcpus running this mm may still have the old addresses cached.
+        if moved {
+            CPULocal::ShootdownTlb();
+        }
 
         return Ok(())
     }
@@ -1304,6 +1454,62 @@ impl MemoryManager {
     }
 }
 
+// MLockedBytesRange returns the number of bytes in ar that are mlocked.
+pub fn MLockedBytesRange(mapping: &MMMapping, ar: &Range) -> u64 {
+    let mut total = 0;
+    let mut vseg = mapping.vmas.LowerBoundSeg(ar.Start());
+    while vseg.Ok() && vseg.Range().Start() < ar.End() {
+        if vseg.Value().mlockMode != MLockMode::MlockNone {
+            total += vseg.Range().Intersect(ar).Len();
+        }
+        vseg = vseg.NextSeg();
+    }
+
+    return total
+}
+
+// CheckMLockLimit returns EPERM if the task may not lock memory at all and
+// ENOMEM if lockedAS bytes would exceed its RLIMIT_MEMLOCK. Tasks with
+// CAP_IPC_LOCK are not limited.
+pub fn CheckMLockLimit(task: &Task, lockedAS: u64) -> Result<()> {
+    let thread = task.Thread();
+    let root = thread.Kernel().RootUserNamespace();
+    if thread.HasCapabilityIn(Capability::CAP_IPC_LOCK, &root) {
+        return Ok(())
+    }
+
+    let mlockLimit = thread.ThreadGroup().Limits().Get(LimitType::MemoryLocked).Cur;
+    if mlockLimit == 0 {
+        return Err(Error::SysError(SysErr::EPERM))
+    }
+
+    if lockedAS > mlockLimit {
+        return Err(Error::SysError(SysErr::ENOMEM))
+    }
+
+    return Ok(())
+}
+
+// CheckASLimit returns ENOMEM if usageAS bytes of address space would exceed
+// the RLIMIT_AS of task.
+pub fn CheckASLimit(task: &Task, usageAS: u64) -> Result<()> {
+    let limitAS = task.Thread().ThreadGroup().Limits().Get(LimitType::AS).Cur;
+    if usageAS > limitAS {
+        return Err(Error::SysError(SysErr::ENOMEM))
+    }
+
+    return Ok(())
+}
+
+// CoreSegment is a user mapping as it appears in a core dump.
+pub struct CoreSegment {
+    pub range: Range,
+    pub perms: AccessType,
+
+    // dump is true if the mapping's contents are written to the core file.
+    pub dump: bool,
+}
+
 // MLockAllOpts holds options to MLockAll.
 pub struct MLockAllOpts {
     // If Current is true, change the memory-locking behavior of all mappings
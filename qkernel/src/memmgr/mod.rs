@@ -37,7 +37,7 @@ use super::task::*;
 use super::fs::file::*;
 use self::mapping::*;
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
 pub enum MLockMode {
     // MLockNone specifies that a mapping has no memory locking behavior.
     //
@@ -23,6 +23,7 @@ use x86_64::VirtAddr;
 
 use super::super::task::*;
 use super::super::PAGE_MGR;
+use super::super::Kernel::HostSpace;
 use super::super::asm::*;
 use super::super::qlib::addr::*;
 use super::super::qlib::range::*;
@@ -86,6 +87,79 @@ impl PageMgr {
     pub fn DerefPage(&self, addr: u64) {
         self.lock().allocator.lock().Deref(addr).unwrap();
     }
+
+    pub fn Unref(&self, addr: u64) -> Result<u64> {
+        return self.lock().allocator.lock().Unref(addr);
+    }
+
+    // ReleasedPageCount returns the number of pages whose memory was given
+    // back to the host.
+    pub fn ReleasedPageCount(&self) -> u64 {
+        return self.lock().allocator.lock().released.len() as u64;
+    }
+
+    // ReleasePages gives the memory of freed pages back to the host, one call
+    // per contiguous run, and keeps the pages for reuse. Pages the host can't
+    // release are freed as usual.
+    pub fn ReleasePages(&self, pages: &mut Vec<u64>) {
+        pages.sort();
+
+        let mut i = 0;
+        while i < pages.len() {
+            let mut j = i + 1;
+            while j < pages.len() && pages[j] == pages[j - 1] + MemoryDef::PAGE_SIZE {
+                j += 1;
+            }
+
+            let ret = HostSpace::ReleaseMemory(pages[i], (j - i) as u64 * MemoryDef::PAGE_SIZE);
+            let pool = self.lock().allocator.clone();
+            let mut pool = pool.lock();
+            for addr in &pages[i..j] {
+                if ret == 0 {
+                    pool.released.push(*addr);
+                } else {
+                    pool.Free(*addr).unwrap();
+                }
+            }
+
+            i = j;
+        }
+    }
+}
+
+// PageReleaser is the page pool for an unmap whose freed pages are given back
+// to the host. It collects them instead of freeing them.
+#[derive(Default)]
+pub struct PageReleaser {
+    pub freed: QMutex<Vec<u64>>,
+}
+
+impl RefMgr for PageReleaser {
+    fn Ref(&self, addr: u64) -> Result<u64> {
+        return PAGE_MGR.Ref(addr);
+    }
+
+    fn Deref(&self, addr: u64) -> Result<u64> {
+        let refcount = PAGE_MGR.Unref(addr)?;
+        if refcount == 0 {
+            self.freed.lock().push(addr);
+        }
+        return Ok(refcount)
+    }
+
+    fn GetRef(&self, addr: u64) -> Result<u64> {
+        return PAGE_MGR.GetRef(addr);
+    }
+}
+
+impl Allocator for PageReleaser {
+    fn AllocPage(&self, incrRef: bool) -> Result<u64> {
+        return PAGE_MGR.AllocPage(incrRef);
+    }
+
+    fn FreePage(&self, addr: u64) -> Result<()> {
+        return PAGE_MGR.FreePage(addr);
+    }
 }
 
 pub struct PageMgrInternal {
@@ -239,7 +313,8 @@ impl PageTables {
         return Ok(ret)
     }
 
-    pub fn RemapAna(&self, _task: &Task, newAddrRange: &Range, oldStart: u64, at: &AccessType, user: bool) -> Result<()> {
+    // RemapAna returns whether any page was moved.
+    pub fn RemapAna(&self, _task: &Task, newAddrRange: &Range, oldStart: u64, at: &AccessType, user: bool) -> Result<bool> {
         let pageOpts = if user {
             if at.Write() {
                 PageOpts::UserReadWrite().Val()
@@ -256,13 +331,11 @@ impl PageTables {
             }
         };
 
-        self.Remap(Addr(newAddrRange.Start()),
-                   Addr(newAddrRange.End()),
-                   Addr(oldStart),
-                   pageOpts,
-                   &*PAGE_MGR)?;
-
-        return Ok(())
+        return self.Remap(Addr(newAddrRange.Start()),
+                          Addr(newAddrRange.End()),
+                          Addr(oldStart),
+                          pageOpts,
+                          &*PAGE_MGR);
     }
 
     pub fn RemapHost(&self, _task: &Task, addr: u64, phyRange: &IoVec, oldar: &Range, at: &AccessType, user: bool) -> Result<()> {
@@ -363,7 +436,19 @@ impl PageTables {
         return Ok(())
     }
 
-    pub fn MUnmap(&mut self, addr: u64, len: u64) -> Result<()> {
+    // MUnmap returns the number of pages which were mapped in the range.
+    pub fn MUnmap(&mut self, addr: u64, len: u64) -> Result<u64> {
         return self.Unmap(addr, addr + len, &*PAGE_MGR);
     }
+
+    // MUnmapRelease unmaps the range like MUnmap, but adds the pages it frees
+    // to freed instead of freeing them. The caller gives them back to the
+    // host with PageMgr::ReleasePages once no vcpu can reach them through a
+    // stale TLB entry.
+    pub fn MUnmapRelease(&mut self, addr: u64, len: u64, freed: &mut Vec<u64>) -> Result<u64> {
+        let releaser = PageReleaser::default();
+        let res = self.Unmap(addr, addr + len, &releaser);
+        freed.append(&mut releaser.freed.lock());
+        return res
+    }
 }
\ No newline at end of file
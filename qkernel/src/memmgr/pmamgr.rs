@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use alloc::collections::btree_map::BTreeMap;
+use alloc::vec::Vec;
 
 use super::super::qlib::linux_def::*;
 use super::super::qlib::common::*;
@@ -47,6 +48,9 @@ pub struct PagePool {
     pub refCount: u64,
     pub refs: BTreeMap<u64, u32>,
     pub allocator: AlignedAllocator,
+    // pages whose memory was given back to the host. They are handed out
+    // before allocating new ones.
+    pub released: Vec<u64>,
 }
 
 impl PagePool {
@@ -71,6 +75,16 @@ impl PagePool {
     }
 
     pub fn Deref(&mut self, addr: u64) -> Result<u64> {
+        let refcount = self.Unref(addr)?;
+        if refcount == 0 {
+            self.Free(addr)?;
+        }
+        return Ok(refcount)
+    }
+
+    // Unref drops a reference like Deref, but leaves it to the caller to
+    // free the page when the last reference is gone.
+    pub fn Unref(&mut self, addr: u64) -> Result<u64> {
         assert!(addr & (MemoryDef::PAGE_SIZE-1) == 0);
         let refcount = match self.refs.get_mut(&addr) {
             None => { // the address is not allocated from PagePool
@@ -86,7 +100,6 @@ impl PagePool {
         self.refCount -= 1;
         if refcount == 0 {
             self.refs.remove(&addr);
-            self.Free(addr)?;
         }
         return Ok(refcount as u64)
     }
@@ -127,10 +140,21 @@ impl PagePool {
             //the PagePool won't be free. fake a always nonzero refcount
             refCount: 1,
             allocator: AlignedAllocator::New(MemoryDef::PAGE_SIZE as usize, MemoryDef::PAGE_SIZE as usize),
+            released: Vec::new(),
         };
     }
 
     pub fn Allocate(&mut self) -> Result<u64> {
+        // A released page normally reads as zero again, but that depends on
+        // the host's MADV_REMOVE, so clear it like a new page.
+        match self.released.pop() {
+            Some(addr) => {
+                ZeroPage(addr);
+                return Ok(addr)
+            }
+            None => (),
+        }
+
         let addr = self.allocator.Allocate()?;
         ZeroPage(addr as u64);
         return Ok(addr as u64)
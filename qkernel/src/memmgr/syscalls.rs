@@ -12,9 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use alloc::vec::Vec;
 use core::u64;
 
 use super::super::kernel::futex::*;
+use super::super::kernel::shm;
 use super::super::memmgr::mm::*;
 use super::super::memmgr::vma::*;
 use super::super::task::*;
@@ -23,6 +25,9 @@ use super::super::qlib::linux_def::*;
 use super::super::qlib::addr::*;
 use super::super::qlib::range::*;
 use super::super::qlib::linux::limits::*;
+use super::super::qlib::limits::*;
+use super::super::qlib::vcpu_mgr::*;
+use super::super::PAGE_MGR;
 use super::*;
 
 #[derive(Debug)]
@@ -86,9 +91,14 @@ impl MemoryManager {
             return Err(Error::SysError(SysErr::EINVAL));
         }
 
+        let defMLockMode = self.mapping.lock().defMLockMode;
+        if opts.MLockMode < defMLockMode {
+            opts.MLockMode = defMLockMode;
+        }
+
         let (vseg, ar) = self.CreateVMAlocked(task, opts)?;
 
-        self.PopulateVMALocked(task, &vseg, &ar, opts.Precommit, opts.VDSO)?;
+        self.PopulateVMALocked(task, &vseg, &ar, opts.Precommit || opts.MLockMode == MLockMode::MlockEager, opts.VDSO)?;
 
         return Ok(ar.Start());
     }
@@ -156,7 +166,68 @@ impl MemoryManager {
 
         let ar = Addr(addr).ToRange(length)?;
 
-        return self.RemoveVMAsLocked(&ar);
+        self.RemoveVMAsLocked(&ar)?;
+        return Ok(())
+    }
+
+    // DetachShm unmaps the SysV shared memory segment attached at addr, as by
+    // shmdt(2), and returns it.
+    pub fn DetachShm(&self, _task: &Task, addr: u64, registry: &shm::Registry) -> Result<shm::Shm> {
+        let _ml = self.MappingWriteLock();
+
+        if addr != Addr(addr).RoundDown()?.0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let mut ranges = Vec::new();
+        let detached = {
+            let mapping = self.mapping.lock();
+
+            // Find the segment attached at addr, i.e. whose offset 0 is mapped
+            // there.
+            let mut detached = None;
+            let mut vseg = mapping.vmas.LowerBoundSeg(addr);
+            while vseg.Ok() {
+                let r = vseg.Range();
+                let vma = vseg.Value();
+                if let Some(ref mappable) = vma.mappable {
+                    if r.Start() >= vma.offset && r.Start() - vma.offset == addr {
+                        detached = registry.FindByMappable(mappable);
+                        if detached.is_some() {
+                            break;
+                        }
+                    }
+                }
+
+                vseg = vseg.NextSeg();
+            }
+
+            let detached = match detached {
+                None => return Err(Error::SysError(SysErr::EINVAL)),
+                Some(s) => s,
+            };
+
+            // Remove all vmas that could have been created by the same attach.
+            let end = addr + detached.EffectiveSize();
+            let mappable = Some(detached.lock().mappable.clone());
+            while vseg.Ok() && vseg.Range().End() <= end {
+                let r = vseg.Range();
+                let vma = vseg.Value();
+                if vma.mappable == mappable && r.Start() - addr == vma.offset {
+                    ranges.push(r);
+                }
+
+                vseg = vseg.NextSeg();
+            }
+
+            detached
+        };
+
+        for r in &ranges {
+            self.RemoveVMAsLocked(r)?;
+        }
+
+        return Ok(detached)
     }
 
     // MRemap implements the semantics of Linux's mremap(2).
@@ -184,6 +255,12 @@ impl MemoryManager {
             return Err(Error::SysError(SysErr::EFAULT));
         }
 
+        // The vdso and vvar pages map fixed physical pages which can't be
+        // resized or moved, like Linux's VM_PFNMAP/VM_DONTEXPAND mappings.
+        if vseg.Value().IsSpecial() {
+            return Err(Error::SysError(SysErr::EFAULT));
+        }
+
         // Behavior matrix:
         //
         // Move     | oldSize = 0 | oldSize < newSize | oldSize = newSize | oldSize > newSize
@@ -206,7 +283,9 @@ impl MemoryManager {
                     // If oldAddr+oldSize didn't overflow, oldAddr+newSize can't
                     // either.
                     let newEnd = oldAddr + newSize;
-                    self.RemoveVMAsLocked(&Range::New(newEnd, oldSize - newSize))?;
+                    if self.RemoveVMAsLocked(&Range::New(newEnd, oldSize - newSize))? > 0 {
+                        CPULocal::ShootdownTlb();
+                    }
                 }
 
                 return Ok(oldAddr)
@@ -282,7 +361,9 @@ impl MemoryManager {
                 }
 
                 // Unmap any mappings at the destination.
-                self.RemoveVMAsLocked(&newAR)?;
+                if self.RemoveVMAsLocked(&newAR)? > 0 {
+                    CPULocal::ShootdownTlb();
+                }
 
                 // If the sizes specify shrinking, unmap everything between the new and
                 // old sizes at the source. Unmapping before the following checks is
@@ -310,6 +391,15 @@ impl MemoryManager {
             return Err(Error::SysError(SysErr::EFAULT));
         }
 
+        // Check against RLIMIT_AS. Copying adds newAR, moving replaces oldAR
+        // with it.
+        let mut newUsageAS = self.mapping.lock().usageAS + newAR.Len();
+        if oldSize != 0 {
+            newUsageAS -= oldAR.Len();
+        }
+
+        CheckASLimit(task, newUsageAS)?;
+
         let vma = vseg.Value();
         if vma.mappable.is_some() {
             if core::u64::MAX - vma.offset < newAR.Len() {
@@ -377,7 +467,7 @@ impl MemoryManager {
         return Ok(newAR.Start())
     }
 
-    pub fn MProtect(&self, addr: u64, len: u64, realPerms: &AccessType, growsDown: bool) -> Result<()> {
+    pub fn MProtect(&self, addr: u64, len: u64, realPerms: &AccessType, growsDown: bool, readImpliesExec: bool) -> Result<()> {
         let _ml = self.MappingWriteLock();
 
         if Addr(addr).RoundDown()?.0 != addr {
@@ -395,8 +485,6 @@ impl MemoryManager {
 
         let mut ar = Addr(addr).ToRange(rlen)?;
 
-        let effectivePerms = realPerms.Effective();
-
         // Non-growsDown mprotect requires that all of ar is mapped, and stops at
         // the first non-empty gap. growsDown mprotect requires that the first vma
         // be growsDown, but does not require it to extend all the way to ar.Start;
@@ -425,6 +513,13 @@ impl MemoryManager {
         let mut vseg = vseg;
         loop {
             let vma = vseg.Value();
+            let mut perms = *realPerms;
+            if readImpliesExec && vma.maxPerms.Exec() {
+                perms.SetExec();
+            }
+
+            let effectivePerms = perms.Effective();
+
             // Check for permission validity before splitting vmas, for consistency
             // with Linux.
             if !vma.maxPerms.SupersetOf(&effectivePerms) {
@@ -436,13 +531,16 @@ impl MemoryManager {
             vseg = mapping.vmas.Isolate(&vseg, &ar);
             // Update vma permissions.
             let mut vma = vseg.Value();
-            vma.realPerms = *realPerms;
+            vma.realPerms = perms;
             vma.effectivePerms = effectivePerms;
 
             vseg.SetValue(vma);
             let range = vseg.Range();
 
-            let pageopts = if effectivePerms.Write() {
+            // Pages of a private mapping may still be shared copy-on-write
+            // with another process or with the file, so they stay read only
+            // and the write fault makes them writable.
+            let pageopts = if effectivePerms.Write() && !vma.private {
                 PageOpts::UserReadWrite().Val()
             } else if effectivePerms.Read() || effectivePerms.Exec() {
                 PageOpts::UserReadOnly().Val()
@@ -564,6 +662,13 @@ impl MemoryManager {
         };
 
         if oldbrkpg < newbrkpg {
+            // Check against RLIMIT_DATA.
+            let brkStart = self.mapping.lock().brkInfo.brkStart;
+            let dataLimit = task.Thread().ThreadGroup().Limits().Get(LimitType::Data).Cur;
+            if newbrkpg - brkStart > dataLimit {
+                return Err(Error::SysError(SysErr::ENOMEM));
+            }
+
             let (vseg, ar) = self.CreateVMAlocked(task, &MMapOpts {
                 Length: newbrkpg - oldbrkpg,
                 Addr: oldbrkpg,
@@ -633,30 +738,79 @@ impl MemoryManager {
             Ok(r) => r
         };
 
-        let _ml = self.MappingReadLock();
+        // MADV_DONTNEED and MADV_FREE change the page tables.
+        let _ml = self.MappingWriteLock();
 
         let mapping = self.mapping.lock();
+
+        // "ENOMEM: Addresses in the specified range are not currently mapped."
+        // - madvise(2)
+        if mapping.vmas.SpanRange(&ar) != ar.Len() {
+            return Err(Error::SysError(SysErr::ENOMEM))
+        }
+
         let mut vseg = mapping.vmas.LowerBoundSeg(ar.Start());
         while vseg.Ok() && vseg.Range().Start() < ar.End() {
             let vma = vseg.Value();
-            if vma.mlockMode != MLockMode::MlockNone && advise == MAdviseOp::MADV_DONTNEED {
+            if vma.mlockMode != MLockMode::MlockNone {
                 return Err(Error::SysError(SysErr::EINVAL))
             }
 
+            // MADV_FREE only applies to private anonymous memory. The pages
+            // are reclaimed immediately rather than lazily, which is
+            // indistinguishable to the application except for the contents
+            // of pages it never wrote to again.
+            if advise == MAdviseOp::MADV_FREE && (!vma.private || vma.mappable.is_some()) {
+                return Err(Error::SysError(SysErr::EINVAL))
+            }
+
+            vseg = vseg.NextSeg();
+        }
+
+        // Pages freed from anonymous vmas. Other vcpus may still have them in
+        // their TLB, so they are given back to the host, and so reused, only
+        // after the shootdown.
+        let mut freed = Vec::new();
+        let mut unmapped = 0;
+        let mut res = Ok(0);
+        let mut vseg = mapping.vmas.LowerBoundSeg(ar.Start());
+        while vseg.Ok() && vseg.Range().Start() < ar.End() {
+            let vma = vseg.Value();
             let mr = ar.Intersect(&vseg.Range());
-            self.pagetable.write().pt.MUnmap(mr.Start(), mr.Len())?;
+            if vma.mappable.is_none() {
+                // Give the anonymous pages back to the host, so that the
+                // sandbox's RSS shrinks. The next fault maps a zero page.
+                res = self.pagetable.write().pt.MUnmapRelease(mr.Start(), mr.Len(), &mut freed);
+            } else {
+                res = self.pagetable.write().pt.MUnmap(mr.Start(), mr.Len());
+            }
+
+            match &res {
+                Err(_) => break,
+                Ok(n) => unmapped += *n,
+            }
 
             if let Some(iops) = vma.mappable.clone() {
                 let fstart = mr.Start() - vseg.Range().Start() + vma.offset;
 
                 // todo: fix the Madvise/MADV_DONTNEED, when there are multiple process MAdviseOp::MADV_DONTNEED
                 // with current implementation, the first Madvise/MADV_DONTNEED will work.
-                iops.MAdvise(fstart, mr.Len(), advise)?;
+                res = iops.MAdvise(fstart, mr.Len(), advise).map(|_| 0);
+                if res.is_err() {
+                    break;
+                }
             }
 
             vseg = vseg.NextSeg();
         }
 
+        // Nothing to shoot down if none of the range was faulted in.
+        if unmapped > 0 {
+            CPULocal::ShootdownTlb();
+            PAGE_MGR.ReleasePages(&mut freed);
+        }
+
+        res?;
         return Ok(())
     }
 
@@ -707,6 +861,10 @@ impl MemoryManager {
         return self.VirtualMemorySizeLocked();
     }
 
+    pub fn LockedMemorySizeLocked(&self) -> u64 {
+        return self.mapping.lock().lockedAS;
+    }
+
     pub fn ResidentSetSizeLocked(&self) -> u64 {
         return self.pagetable.read().curRSS;
     }
@@ -26,6 +26,7 @@ use super::super::qlib::addr::*;
 use super::*;
 use super::super::qlib::range::*;
 use super::super::qlib::mem::areaset::*;
+use super::super::qlib::limits::*;
 use super::mm::*;
 use super::arch::*;
 
@@ -220,7 +221,7 @@ impl MemoryManager {
                                                &Range::New(layout.MinAddr, layout.TopDownBase - layout.MinAddr));
     }
 
-    pub fn CreateVMAlocked(&self, _task: &Task, opts: &MMapOpts) -> Result<(AreaSeg<VMA>, Range)> {
+    pub fn CreateVMAlocked(&self, task: &Task, opts: &MMapOpts) -> Result<(AreaSeg<VMA>, Range)> {
         if opts.MaxPerms != opts.MaxPerms.Effective() {
             panic!("Non-effective MaxPerms {:?} cannot be enforced", opts.MaxPerms);
         }
@@ -237,11 +238,31 @@ impl MemoryManager {
 
         let ar = Range::New(addr, opts.Length);
 
-        // todo: Check against RLIMIT_AS.
-        /*let mut newUsageAS = self.usageAS + opts.Length;
-        if opts.Unmap {
-            newUsageAS -= self.vmas.SpanRange(&ar);
-        }*/
+        // Check against RLIMIT_AS.
+        if !opts.Kernel {
+            let mapping = self.mapping.lock();
+            let mut newUsageAS = mapping.usageAS + opts.Length;
+            if opts.Unmap {
+                newUsageAS -= mapping.vmas.SpanRange(&ar);
+            }
+
+            CheckASLimit(task, newUsageAS)?;
+        }
+
+        // Check against RLIMIT_MEMLOCK.
+        if opts.MLockMode != MLockMode::MlockNone {
+            let mapping = self.mapping.lock();
+            let mut newLockedAS = mapping.lockedAS + opts.Length;
+            if opts.Unmap {
+                newLockedAS -= MLockedBytesRange(&mapping, &ar);
+            }
+
+            match CheckMLockLimit(task, newLockedAS) {
+                Err(Error::SysError(SysErr::ENOMEM)) => return Err(Error::SysError(SysErr::EAGAIN)),
+                Err(e) => return Err(e),
+                Ok(()) => (),
+            }
+        }
 
         // Remove overwritten mappings. This ordering is consistent with Linux:
         // compare Linux's mm/mmap.c:mmap_region() => do_munmap(),
@@ -277,6 +298,9 @@ impl MemoryManager {
         };
 
         mapping.usageAS += opts.Length;
+        if opts.MLockMode != MLockMode::MlockNone {
+            mapping.lockedAS += opts.Length;
+        }
 
         let vseg = mapping.vmas.Insert(&gap, &ar, vma);
         let nextvseg = vseg.NextSeg();
@@ -284,6 +308,59 @@ impl MemoryManager {
         return Ok((vseg, ar))
     }
 
+    // GrowStackLocked extends the growsDown vma above addr downward so that it
+    // covers addr, like Linux's mm/mmap.c:expand_downwards(). Only faults
+    // within GUARD_BYTES of the vma grow it; the grown vma may not exceed
+    // RLIMIT_STACK and must stay GUARD_BYTES clear of the vma below it.
+    //
+    // Preconditions: mm.mappingMu must be locked for writing.
+    pub fn GrowStackLocked(&self, task: &Task, addr: u64) -> Result<(VMA, Range)> {
+        let start = Addr(addr).RoundDown()?.0;
+
+        let mut mapping = self.mapping.lock();
+        let vgap = mapping.vmas.FindGap(start);
+        if !vgap.Ok() {
+            return Err(Error::SysError(SysErr::EFAULT));
+        }
+
+        let vseg = vgap.NextSeg();
+        if !vseg.Ok() {
+            return Err(Error::SysError(SysErr::EFAULT));
+        }
+
+        let vma = vseg.Value();
+        let vr = vseg.Range();
+        if !vma.growsDown || vma.kernel || vr.Start() - start > GUARD_BYTES {
+            return Err(Error::SysError(SysErr::EFAULT));
+        }
+
+        // Like Linux, inaccessible and growsDown mappings below don't need
+        // the guard gap.
+        let prev = vgap.PrevSeg();
+        if prev.Ok() && start - prev.Range().End() < GUARD_BYTES {
+            let pvma = prev.Value();
+            if !pvma.growsDown && pvma.effectivePerms.Any() {
+                return Err(Error::SysError(SysErr::ENOMEM));
+            }
+        }
+
+        let stackLimit = task.Thread().ThreadGroup().Limits().Get(LimitType::Stack).Cur;
+        if vr.End() - start > stackLimit {
+            return Err(Error::SysError(SysErr::ENOMEM));
+        }
+
+        let ar = Range::New(start, vr.Start() - start);
+        CheckASLimit(task, mapping.usageAS + ar.Len())?;
+        if vma.mlockMode != MLockMode::MlockNone {
+            CheckMLockLimit(task, mapping.lockedAS + ar.Len())?;
+            mapping.lockedAS += ar.Len();
+        }
+
+        mapping.usageAS += ar.Len();
+        let vseg = mapping.vmas.Insert(&vgap, &ar, vma);
+        return Ok((vseg.Value(), vseg.Range()))
+    }
+
     //find free seg with enough len
     pub fn FindAvailableSeg(&self, _task: &Task, offset: u64, len: u64) -> Result<u64> {
         let _ml = self.MappingWriteLock();
@@ -366,6 +443,12 @@ impl fmt::Debug for VMA {
 }
 
 impl VMA {
+    // IsSpecial returns whether the vma maps kernel provided pages, i.e. the
+    // vdso and vvar pages, rather than memory or a file.
+    pub fn IsSpecial(&self) -> bool {
+        return self.kernel || self.hint == "[vdso]" || self.hint == "[vvar]"
+    }
+
     pub fn Copy(&self) -> Self {
         let copy = VMA {
             mappable: self.mappable.clone(),
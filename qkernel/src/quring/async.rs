@@ -38,6 +38,7 @@ use super::super::kernel::async_wait::*;
 use super::super::SHARESPACE;
 use super::super::kernel::waiter::qlock::*;
 use super::super::Kernel::HostSpace;
+use super::super::guestfdnotifier::*;
 
 #[repr(align(128))]
 pub enum AsyncOps {
@@ -61,6 +62,10 @@ pub enum AsyncOps {
     AsyncBufWrite(AsyncBufWrite),
     AsyncAccept(AsyncAccept),
     AsyncEpollCtl(AsyncEpollCtl),
+    AsyncReadahead(AsyncReadahead),
+    AsyncPollMulti(AsyncPollMulti),
+    AsyncPollUpdate(AsyncPollUpdate),
+    AsyncPollRemove(AsyncPollRemove),
     None,
 }
 
@@ -87,13 +92,19 @@ impl AsyncOps {
             AsyncOps::AsyncBufWrite(ref msg) => return msg.SEntry(),
             AsyncOps::AsyncAccept(ref msg) => return msg.SEntry(),
             AsyncOps::AsyncEpollCtl(ref msg) => return msg.SEntry(),
+            AsyncOps::AsyncReadahead(ref msg) => return msg.SEntry(),
+            AsyncOps::AsyncPollMulti(ref msg) => return msg.SEntry(),
+            AsyncOps::AsyncPollUpdate(ref msg) => return msg.SEntry(),
+            AsyncOps::AsyncPollRemove(ref msg) => return msg.SEntry(),
             AsyncOps::None => ()
         };
 
         panic!("AsyncOps::None SEntry fail")
     }
 
-    pub fn Process(&mut self, result: i32, id: usize) -> bool {
+    // Process handles a completion of the op in slot id. more is set when the
+    // host will post further completions for the same request.
+    pub fn Process(&mut self, result: i32, more: bool, id: usize) -> bool {
         let ret = match self {
             AsyncOps::AsyncTimeout(ref mut msg) => msg.Process(result),
             AsyncOps::AsyncTimerRemove(ref mut msg) => msg.Process(result),
@@ -115,6 +126,10 @@ impl AsyncOps {
             AsyncOps::AsyncBufWrite(ref mut msg) => msg.Process(result),
             AsyncOps::AsyncAccept(ref mut msg) => msg.Process(result),
             AsyncOps::AsyncEpollCtl(ref mut msg) => msg.Process(result),
+            AsyncOps::AsyncReadahead(ref mut msg) => msg.Process(result),
+            AsyncOps::AsyncPollMulti(ref mut msg) => msg.Process(result, more, id),
+            AsyncOps::AsyncPollUpdate(ref mut msg) => msg.Process(result),
+            AsyncOps::AsyncPollRemove(ref mut msg) => msg.Process(result),
             AsyncOps::None => {
                 //panic!("AsyncOps::None SEntry fail")
                 panic!("AsyncOps::None SEntry fail result {} id {}", result, id);
@@ -151,6 +166,10 @@ impl AsyncOps {
             AsyncOps::AsyncBufWrite(_) => return 18,
             AsyncOps::AsyncAccept(_) => return 19,
             AsyncOps::AsyncEpollCtl(_) => return 20,
+            AsyncOps::AsyncReadahead(_) => return 21,
+            AsyncOps::AsyncPollMulti(_) => return 22,
+            AsyncOps::AsyncPollUpdate(_) => return 23,
+            AsyncOps::AsyncPollRemove(_) => return 24,
             AsyncOps::None => ()
         };
 
@@ -594,6 +613,17 @@ impl AsyncAccept {
 
     pub fn Process(&mut self, result: i32) -> bool {
         if result < 0 {
+            // Like accept(2), these only mean that a pending connection went
+            // away or that no connection arrived in time. The listener is
+            // still good, so wait for the next connection instead of
+            // latching the error and reporting readiness without one.
+            match -result {
+                SysErr::EAGAIN | SysErr::EINTR | SysErr::ECONNABORTED | SysErr::EPROTO |
+                SysErr::ENETDOWN | SysErr::ENETUNREACH | SysErr::EHOSTDOWN | SysErr::EHOSTUNREACH |
+                SysErr::ENONET | SysErr::ENOPROTOOPT | SysErr::EOPNOTSUPP => return true,
+                _ => (),
+            }
+
             self.acceptQueue.lock().SetErr(-result);
             self.queue.Notify(EventMaskFromLinux((EVENT_ERR | EVENT_IN) as u32));
             return false;
@@ -653,9 +683,9 @@ impl AsyncFileRead {
         if result == 0 {
             self.buf.SetRClosed();
             if self.buf.ProduceReadBuf(0) {
-                self.queue.Notify(EventMaskFromLinux(EVENT_IN as u32));
+                self.queue.Notify(EventMaskFromLinux((EVENT_IN | EVENT_RDHUP) as u32));
             } else {
-                self.queue.Notify(EventMaskFromLinux(EVENT_HUP as u32)) ;
+                self.queue.Notify(EventMaskFromLinux((EVENT_HUP | EVENT_RDHUP) as u32)) ;
             }
             return false
         }
@@ -809,7 +839,9 @@ impl AsycnRecvMsg {
         if result == 0 {
             buf.SetRClosed();
             if buf.ProduceReadBuf(0) {
-                intern.ops.Notify(EVENT_IN);
+                intern.ops.Notify(EVENT_IN | EVENT_RDHUP);
+            } else {
+                intern.ops.Notify(EVENT_RDHUP);
             }
             return false
         }
@@ -1191,4 +1223,122 @@ impl AsyncEpollCtl {
 
         return false
     }
-}
\ No newline at end of file
+}
+
+// AsyncReadahead starts the host reading a file range into its page cache.
+#[derive(Clone, Debug, Copy)]
+pub struct AsyncReadahead {
+    pub fd: i32,
+    pub offset: i64,
+    pub len: i64,
+}
+
+impl AsyncReadahead {
+    pub fn New(fd: i32, offset: i64, len: i64) -> Self {
+        return Self {
+            fd: fd,
+            offset: offset,
+            len: len,
+        }
+    }
+
+    pub fn SEntry(&self) -> squeue::Entry {
+        let op = Fadvise::new(types::Fd(self.fd), self.len, FAdviseOp::POSIX_FADV_WILLNEED)
+            .offset(self.offset);
+
+        return op.build();
+    }
+
+    pub fn Process(&mut self, _result: i32) -> bool {
+        // readahead is only a hint, a failure has no one to report to.
+        return false
+    }
+}
+
+// AsyncPollMulti is the guest notifier's multishot poll of a host fd. It
+// keeps its slot, and so its user data, for as long as the host flags its
+// completions with IORING_CQE_F_MORE, so the notifier can update or remove it
+// by slot.
+pub struct AsyncPollMulti {
+    pub fd: i32,
+    pub mask: u32,
+}
+
+impl AsyncPollMulti {
+    pub fn New(fd: i32, mask: u32) -> Self {
+        return Self {
+            fd: fd,
+            mask: mask,
+        }
+    }
+
+    pub fn SEntry(&self) -> squeue::Entry {
+        let op = opcode::PollAdd::new(types::Fd(self.fd), self.mask)
+            .multi(true);
+
+        return op.build()
+            .flags(squeue::Flags::FIXED_FILE);
+    }
+
+    pub fn Process(&mut self, result: i32, more: bool, id: usize) -> bool {
+        if result > 0 {
+            Notify(self.fd, result as EventMask);
+        }
+
+        if !more {
+            PollDone(self.fd, id);
+        }
+
+        return false
+    }
+}
+
+// AsyncPollUpdate changes the events of the multishot poll in slot userData.
+pub struct AsyncPollUpdate {
+    pub userData: u64,
+    pub mask: u32,
+}
+
+impl AsyncPollUpdate {
+    pub fn New(userData: u64, mask: u32) -> Self {
+        return Self {
+            userData: userData,
+            mask: mask,
+        }
+    }
+
+    pub fn SEntry(&self) -> squeue::Entry {
+        let op = opcode::PollUpdate::new(self.userData, self.mask);
+
+        return op.build();
+    }
+
+    pub fn Process(&mut self, _result: i32) -> bool {
+        // ENOENT means the poll has already terminated, which its own
+        // completion handles.
+        return false
+    }
+}
+
+// AsyncPollRemove removes the multishot poll in slot userData.
+pub struct AsyncPollRemove {
+    pub userData: u64,
+}
+
+impl AsyncPollRemove {
+    pub fn New(userData: u64) -> Self {
+        return Self {
+            userData: userData,
+        }
+    }
+
+    pub fn SEntry(&self) -> squeue::Entry {
+        let op = opcode::PollRemove::new(self.userData);
+
+        return op.build();
+    }
+
+    pub fn Process(&mut self, _result: i32) -> bool {
+        return false
+    }
+}
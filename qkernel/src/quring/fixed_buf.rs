@@ -0,0 +1,80 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::sync::Arc;
+use core::slice;
+
+use super::super::qlib::metric::*;
+use super::super::qlib::singleton::*;
+use super::super::SHARESPACE;
+
+pub static FIXED_BUF_HITS : Singleton<Arc<U64Metric>> = Singleton::<Arc<U64Metric>>::New();
+pub static FIXED_BUF_MISSES : Singleton<Arc<U64Metric>> = Singleton::<Arc<U64Metric>>::New();
+
+pub unsafe fn InitSingleton() {
+    FIXED_BUF_HITS.Init(NewU64Metric("/uring/fixed_buf_hits", false,
+        "Number of host file reads and writes done with a registered fixed buffer."));
+    FIXED_BUF_MISSES.Init(NewU64Metric("/uring/fixed_buf_misses", false,
+        "Number of host file reads and writes which found no free fixed buffer to fit them."));
+}
+
+// FixedBuf is a buffer of the registered fixed buffer pool, which goes back
+// to the pool when it's dropped.
+pub struct FixedBuf {
+    pub idx: u16,
+    pub len: usize,
+}
+
+impl FixedBuf {
+    // Alloc returns a pooled buffer of len bytes, or None if the host didn't
+    // register the pool or no free buffer fits.
+    pub fn Alloc(len: usize) -> Option<Self> {
+        let bufs = &SHARESPACE.fixedBufs;
+        if !bufs.Registered() {
+            return None
+        }
+
+        match bufs.Alloc(len) {
+            None => {
+                FIXED_BUF_MISSES.Incr();
+                return None
+            }
+            Some(idx) => {
+                FIXED_BUF_HITS.Incr();
+                return Some(Self {
+                    idx: idx,
+                    len: len,
+                })
+            }
+        }
+    }
+
+    pub fn Ptr(&self) -> u64 {
+        return SHARESPACE.fixedBufs.BufAddr(self.idx)
+    }
+
+    pub fn Buf(&self) -> &[u8] {
+        return unsafe { slice::from_raw_parts(self.Ptr() as *const u8, self.len) }
+    }
+
+    pub fn BufMut(&mut self) -> &mut [u8] {
+        return unsafe { slice::from_raw_parts_mut(self.Ptr() as *mut u8, self.len) }
+    }
+}
+
+impl Drop for FixedBuf {
+    fn drop(&mut self) {
+        SHARESPACE.fixedBufs.Free(self.idx);
+    }
+}
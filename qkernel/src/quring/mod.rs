@@ -16,5 +16,6 @@ pub mod uring_mgr;
 pub mod uring_op;
 pub mod uring;
 pub mod async;
+pub mod fixed_buf;
 
 pub use uring_mgr::*;
\ No newline at end of file
@@ -28,6 +28,7 @@ pub use super::super::qlib::uring::*;
 
 use super::super::qlib::uring::util::*;
 use super::super::qlib::linux_def::*;
+use super::super::qlib::linux::ioprio::*;
 use super::super::qlib::vcpu_mgr::*;
 use super::super::kernel::waiter::*;
 use super::super::socket::hostinet::socket_buf::*;
@@ -37,8 +38,24 @@ use super::super::kernel::async_wait::*;
 use super::super::IOURING;
 use super::uring_op::*;
 use super::async::*;
+use super::fixed_buf::*;
 use super::super::kernel::waiter::qlock::*;
 
+// HostIOPrio returns the ioprio of the host requests issued for task. An RT
+// priority fails the request unless the host grants CAP_SYS_ADMIN, so only
+// the BE and IDLE classes are passed on.
+fn HostIOPrio(task: &Task) -> u16 {
+    let ioprio = match task.thread {
+        None => return IOPRIO_DEFAULT,
+        Some(ref t) => t.IOPrio(),
+    };
+
+    match IOPrioClass(ioprio) {
+        IOPRIO_CLASS_BE | IOPRIO_CLASS_IDLE => return ioprio,
+        _ => return IOPRIO_DEFAULT,
+    }
+}
+
 pub fn QUringTrigger() -> usize {
     return IOURING.DrainCompletionQueue();
 }
@@ -236,12 +253,42 @@ impl QUring {
         return idx;
     }
 
+    // PollMulti starts a multishot poll of fd for mask and returns its slot.
+    pub fn PollMulti(&self, fd: i32, mask: u32) -> usize {
+        let ops = AsyncPollMulti::New(fd, mask);
+        let idx = self.AUCall(AsyncOps::AsyncPollMulti(ops));
+
+        return idx;
+    }
+
+    pub fn PollUpdate(&self, idx: usize, mask: u32) -> usize {
+        let ops = AsyncPollUpdate::New(idx as u64, mask);
+        let idx = self.AUCall(AsyncOps::AsyncPollUpdate(ops));
+
+        return idx;
+    }
+
+    pub fn PollRemove(&self, idx: usize) -> usize {
+        let ops = AsyncPollRemove::New(idx as u64);
+        let idx = self.AUCall(AsyncOps::AsyncPollRemove(ops));
+
+        return idx;
+    }
+
+    pub fn Readahead(&self, fd: i32, offset: i64, len: i64) -> usize {
+        let ops = AsyncReadahead::New(fd, offset, len);
+        let idx = self.AUCall(AsyncOps::AsyncReadahead(ops));
+
+        return idx;
+    }
+
     pub fn Read(&self, task: &Task, fd: i32, addr: u64, len: u32, offset: i64) -> i64 {
         let msg = UringOp::Read(ReadOp {
             fd: fd,
             addr: addr,
             len: len,
             offset: offset,
+            ioprio: HostIOPrio(task),
         });
 
         return self.UCall(task, msg);
@@ -253,6 +300,34 @@ impl QUring {
             addr: addr,
             len: len,
             offset: offset,
+            ioprio: HostIOPrio(task),
+        });
+
+        return self.UCall(task, msg);
+    }
+
+    // ReadFixed reads into buf, a buffer of the registered fixed buffer pool.
+    pub fn ReadFixed(&self, task: &Task, fd: i32, buf: &FixedBuf, offset: i64) -> i64 {
+        let msg = UringOp::ReadFixed(ReadFixedOp {
+            fd: fd,
+            addr: buf.Ptr(),
+            len: buf.len as u32,
+            bufIndex: buf.idx,
+            offset: offset,
+            ioprio: HostIOPrio(task),
+        });
+
+        return self.UCall(task, msg);
+    }
+
+    pub fn WriteFixed(&self, task: &Task, fd: i32, buf: &FixedBuf, offset: i64) -> i64 {
+        let msg = UringOp::WriteFixed(WriteFixedOp {
+            fd: fd,
+            addr: buf.Ptr(),
+            len: buf.len as u32,
+            bufIndex: buf.idx,
+            offset: offset,
+            ioprio: HostIOPrio(task),
         });
 
         return self.UCall(task, msg);
@@ -396,8 +471,10 @@ impl QUring {
             let mut ops = self.asyncMgr.ops[idx].lock();
             //error!("uring process2: call is {:?}, idx {}", ops.Type(), idx);
 
-            let rerun = ops.Process(ret, idx);
-            if !rerun {
+            // A multishot request keeps its slot until its last completion.
+            let more = cqe.flags() & sys::IORING_CQE_F_MORE != 0;
+            let rerun = ops.Process(ret, more, idx);
+            if !rerun && !more {
                 *ops = AsyncOps::None;
                 self.asyncMgr.FreeSlot(idx);
             }
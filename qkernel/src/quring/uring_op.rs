@@ -52,6 +52,8 @@ impl UringCall {
             UringOp::TimerRemove(ref msg) => return msg.SEntry(),
             UringOp::Read(ref msg) => return msg.SEntry(),
             UringOp::Write(ref msg) => return msg.SEntry(),
+            UringOp::ReadFixed(ref msg) => return msg.SEntry(),
+            UringOp::WriteFixed(ref msg) => return msg.SEntry(),
             UringOp::Statx(ref msg) => return msg.SEntry(),
             UringOp::Fsync(ref msg) => return msg.SEntry(),
             UringOp::Splice(ref msg) => return msg.SEntry(),
@@ -67,6 +69,8 @@ pub enum UringOp {
     TimerRemove(TimerRemoveOp),
     Read(ReadOp),
     Write(WriteOp),
+    ReadFixed(ReadFixedOp),
+    WriteFixed(WriteFixedOp),
     Statx(StatxOp),
     Fsync(FsyncOp),
     Splice(SpliceOp),
@@ -97,12 +101,14 @@ pub struct ReadOp {
     pub addr: u64,
     pub len: u32,
     pub offset: i64,
+    pub ioprio: u16,
 }
 
 impl ReadOp {
     pub fn SEntry(&self) -> squeue::Entry {
         let op = Read::new(types::Fd(self.fd), self.addr as * mut _, self.len)
-            .offset(self.offset);
+            .offset(self.offset)
+            .ioprio(self.ioprio);
 
         return op.build()
             .flags(squeue::Flags::FIXED_FILE);
@@ -115,12 +121,58 @@ pub struct WriteOp {
     pub addr: u64,
     pub len: u32,
     pub offset: i64,
+    pub ioprio: u16,
 }
 
 impl WriteOp {
     pub fn SEntry(&self) -> squeue::Entry {
         let op = Write::new(types::Fd(self.fd), self.addr as * const _, self.len)
-            .offset(self.offset);
+            .offset(self.offset)
+            .ioprio(self.ioprio);
+
+        return op.build()
+            .flags(squeue::Flags::FIXED_FILE);
+    }
+}
+
+// ReadFixedOp reads into the buffer bufIndex of the registered fixed buffer
+// pool, which starts at addr.
+#[derive(Clone, Debug, Copy)]
+pub struct ReadFixedOp {
+    pub fd: i32,
+    pub addr: u64,
+    pub len: u32,
+    pub bufIndex: u16,
+    pub offset: i64,
+    pub ioprio: u16,
+}
+
+impl ReadFixedOp {
+    pub fn SEntry(&self) -> squeue::Entry {
+        let op = ReadFixed::new(types::Fd(self.fd), self.addr as * mut _, self.len, self.bufIndex)
+            .offset(self.offset)
+            .ioprio(self.ioprio);
+
+        return op.build()
+            .flags(squeue::Flags::FIXED_FILE);
+    }
+}
+
+#[derive(Clone, Debug, Copy)]
+pub struct WriteFixedOp {
+    pub fd: i32,
+    pub addr: u64,
+    pub len: u32,
+    pub bufIndex: u16,
+    pub offset: i64,
+    pub ioprio: u16,
+}
+
+impl WriteFixedOp {
+    pub fn SEntry(&self) -> squeue::Entry {
+        let op = WriteFixed::new(types::Fd(self.fd), self.addr as * const _, self.len, self.bufIndex)
+            .offset(self.offset)
+            .ioprio(self.ioprio);
 
         return op.build()
             .flags(squeue::Flags::FIXED_FILE);
@@ -48,7 +48,6 @@ impl SCMRights {
         if n > l {
             n = l;
         } else if n < l {
-            n = l;
             trunc = true;
         }
 
@@ -79,15 +78,18 @@ impl SCMRights {
         return Self(ret)
     }
 
-    // rightsFDs gets up to the specified maximum number of FDs.
+    // rightsFDs gets up to the specified maximum number of FDs. Files which
+    // don't fit, or can't be installed in the fd table, are released and
+    // reported as truncation.
     pub fn RightsFDs(&mut self, task: &Task, cloexec: bool, max: usize) -> (Vec<i32>, bool) {
         info!("RightsFDs len is {}", self.0.len());
-        let (files, trunc) = self.Files(task, max);
+        let (files, mut trunc) = self.Files(task, max);
         let mut fds = Vec::with_capacity(files.0.len());
-        for i in 0..core::cmp::min(max, files.0.len()) {
+        for i in 0..files.0.len() {
             let fd = match task.NewFDFrom(0, &files.0[i], &FDFlags{CloseOnExec: cloexec}) {
                 Err(e) => {
                     info!("Error inserting FD: {:?}", e);
+                    trunc = true;
                     break;
                 }
                 Ok(fd) => fd,
@@ -134,11 +136,16 @@ impl ScmCredentials {
         };
     }
 
-    pub fn Credentials(&self) -> ControlMessageCredentials {
+    // Credentials returns the credentials as seen by the receiving task t: the
+    // sender's thread group ID in t's PID namespace and its IDs in t's user
+    // namespace.
+    pub fn Credentials(&self, t: &Task) -> ControlMessageCredentials {
+        let userns = t.Creds().lock().UserNamespace.clone();
+        let pidns = t.Thread().PIDNamespace();
         return ControlMessageCredentials {
-            PID: self.thread.ThreadID(),
-            UID: self.kuid.0,
-            GID: self.kgid.0,
+            PID: pidns.IDOfThreadGroup(&self.thread.ThreadGroup()),
+            UID: self.kuid.In(&userns).OrOverflow().0,
+            GID: self.kgid.In(&userns).OrOverflow().0,
         }
     }
 }
@@ -7,11 +7,18 @@ use super::super::super::qlib::linux::time::*;
 use super::super::super::qlib::linux::socket::*;
 use super::super::super::tcpip::tcpip::*;
 use super::super::unix::transport::unix::*;
+use super::super::hostinet::interfaces::*;
+use super::super::control::ControlMessageCredentials;
+use super::super::super::qlib::auth::id::{OVERFLOW_UID, OVERFLOW_GID};
 
 pub fn Ioctl(task: &Task, ep: &BoundEndpoint, _fd: i32, request: u64, val: u64) -> Result<()> {
     let flags = request as i32;
 
     match flags as u64 {
+        LibcConst::SIOCGIFADDR |
+        LibcConst::SIOCGIFNAME => {
+            return InterfaceIoctlIFReq(task, request, val)
+        }
         LibcConst::SIOCGIFFLAGS |
         LibcConst::SIOCGIFBRDADDR |
         LibcConst::SIOCGIFDSTADDR |
@@ -20,7 +27,6 @@ pub fn Ioctl(task: &Task, ep: &BoundEndpoint, _fd: i32, request: u64, val: u64)
         LibcConst::SIOCGIFMAP |
         LibcConst::SIOCGIFMETRIC |
         LibcConst::SIOCGIFMTU |
-        LibcConst::SIOCGIFNAME |
         LibcConst::SIOCGIFNETMASK |
         LibcConst::SIOCGIFTXQLEN => {
             let addr = val;
@@ -30,11 +36,7 @@ pub fn Ioctl(task: &Task, ep: &BoundEndpoint, _fd: i32, request: u64, val: u64)
             return Ok(())
         }
         LibcConst::SIOCGIFCONF => {
-            let addr = val;
-            let bep = ep.BaseEndpoint();
-            bep.HostIoctlIFConf(task, request, addr)?;
-
-            return Ok(())
+            return InterfaceIoctlIFConf(task, val)
         }
         LibcConst::TIOCINQ => {
             let mut v = SockOpt::ReceiveQueueSizeOption(0);
@@ -224,17 +226,21 @@ pub fn GetSockOptSocket(task: &Task,
                 return Err(Error::SysError(SysErr::EINVAL))
             }
 
-            let tcred = task.Creds();
-
-            let pid = task.Thread().ThreadGroup().ID();
-            let userns = tcred.lock().UserNamespace.clone();
-            let uid = tcred.lock().EffectiveKUID.In(&userns).OrOverflow();
-            let gid = tcred.lock().EffectiveKGID.In(&userns).OrOverflow();
+            // Sockets which have never been connected report no pid and the
+            // overflow ids, as Linux does.
+            let creds = match ep.PeerCreds() {
+                None => ControlMessageCredentials {
+                    PID: 0,
+                    UID: OVERFLOW_UID.0,
+                    GID: OVERFLOW_GID.0,
+                },
+                Some(c) => c.Credentials(task),
+            };
 
             let ucred = Ucred {
-                Pid: pid,
-                Uid: uid.0,
-                Gid: gid.0,
+                Pid: creds.PID,
+                Uid: creds.UID,
+                Gid: creds.GID,
             };
 
             return Ok(SockOptResult::Ucred(ucred))
@@ -283,6 +289,19 @@ pub fn GetSockOptSocket(task: &Task,
                 return Err(Error::SysError(SysErr::EINVAL))
             }
 
+            let mut opt = SockOpt::ReuseAddressOption(0);
+            ep.GetSockOpt(&mut opt)?;
+
+            match opt {
+                SockOpt::ReuseAddressOption(v) => return Ok(SockOptResult::I32(v)),
+                _ => ()
+            }
+        }
+        LibcConst::SO_REUSEPORT => {
+            if outlen < SIZEOF_I32 {
+                return Err(Error::SysError(SysErr::EINVAL))
+            }
+
             let mut opt = SockOpt::ReusePortOption(0);
             ep.GetSockOpt(&mut opt)?;
 
@@ -0,0 +1,168 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+use ::qlib::mutex::*;
+
+use super::super::super::qlib::common::*;
+use super::super::super::qlib::linux_def::*;
+use super::super::super::qlib::linux::netdevice::*;
+use super::super::super::qlib::singleton::*;
+use super::super::super::task::*;
+use super::super::super::Kernel::HostSpace;
+
+// MAX_INTERFACES bounds the number of interfaces read from the host.
+const MAX_INTERFACES : usize = 64;
+
+// NetInterface is an interface of the sandbox's network view.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct NetInterface {
+    pub Index: i32,
+
+    // Req is the interface's SIOCGIFCONF record: its name and IPv4 address.
+    pub Req: IFReq,
+}
+
+// INTERFACES is the table of the sandbox's network interfaces which the
+// interface ioctls answer from. It caches the host's interfaces: it is
+// reloaded by every SIOCGIFCONF, and by a lookup which misses, so that
+// interfaces added or removed on the host show up.
+pub static INTERFACES : Singleton<QMutex<Option<Vec<NetInterface>>>> = Singleton::<QMutex<Option<Vec<NetInterface>>>>::New();
+
+pub unsafe fn InitSingleton() {
+    INTERFACES.Init(QMutex::new(None));
+}
+
+// loadInterfaces reads the interfaces which have an IPv4 address from the
+// host. An interface whose index can't be read, e.g. because it went away
+// after SIOCGIFCONF, is skipped.
+fn loadInterfaces() -> Result<Vec<NetInterface>> {
+    let res = HostSpace::Socket(AFType::AF_INET, SocketType::SOCK_DGRAM | SocketFlags::SOCK_CLOEXEC, 0);
+    if res < 0 {
+        return Err(Error::SysError(-res as i32))
+    }
+
+    let fd = res as i32;
+    defer!(HostSpace::Close(fd););
+
+    let reqs = [IFReq::default(); MAX_INTERFACES];
+    let mut ifc = IFConf {
+        Len: (MAX_INTERFACES * SIZE_OF_IFREQ) as i32,
+        Ptr: &reqs[0] as *const _ as u64,
+        ..Default::default()
+    };
+
+    let res = HostSpace::IoCtl(fd, LibcConst::SIOCGIFCONF, &mut ifc as *const _ as u64);
+    if res < 0 {
+        return Err(Error::SysError(-res as i32))
+    }
+
+    let mut interfaces = Vec::new();
+    for req in &reqs[0..ifc.Len as usize / SIZE_OF_IFREQ] {
+        let mut ifr = *req;
+        let res = HostSpace::IoCtl(fd, LibcConst::SIOCGIFINDEX, &mut ifr as *const _ as u64);
+        if res < 0 {
+            continue;
+        }
+
+        interfaces.push(NetInterface {
+            Index: ifrIndex(&ifr),
+            Req: *req,
+        })
+    }
+
+    return Ok(interfaces)
+}
+
+// Interfaces returns the sandbox's network interfaces, reloading them from
+// the host if refresh is set or they haven't been loaded yet.
+pub fn Interfaces(refresh: bool) -> Result<Vec<NetInterface>> {
+    let mut table = INTERFACES.lock();
+    if refresh || table.is_none() {
+        *table = Some(loadInterfaces()?);
+    }
+
+    return Ok(table.as_ref().unwrap().clone())
+}
+
+// ifrName returns the name in ifr up to its terminating null.
+fn ifrName(ifr: &IFReq) -> &[u8] {
+    let len = ifr.IFName.iter().position(|c| *c == 0).unwrap_or(IFNAMSIZ);
+    return &ifr.IFName[0..len]
+}
+
+fn ifrIndex(ifr: &IFReq) -> i32 {
+    return i32::from_ne_bytes([ifr.Data[0], ifr.Data[1], ifr.Data[2], ifr.Data[3]])
+}
+
+// InterfaceIoctlIFReq answers SIOCGIFADDR and SIOCGIFNAME from the interface
+// table.
+pub fn InterfaceIoctlIFReq(task: &Task, request: u64, addr: u64) -> Result<()> {
+    let mut ifr : IFReq = task.CopyInObj(addr)?;
+
+    let find = |interfaces: &Vec<NetInterface>| -> Result<Option<NetInterface>> {
+        let iface = match request {
+            LibcConst::SIOCGIFADDR => {
+                interfaces.iter().find(|i| ifrName(&i.Req) == ifrName(&ifr))
+            }
+            LibcConst::SIOCGIFNAME => {
+                let index = ifrIndex(&ifr);
+                interfaces.iter().find(|i| i.Index == index)
+            }
+            _ => return Err(Error::SysError(SysErr::EINVAL))
+        };
+
+        return Ok(iface.copied())
+    };
+
+    // The interface may have been added since the table was loaded.
+    let iface = match find(&Interfaces(false)?)? {
+        Some(i) => i,
+        None => match find(&Interfaces(true)?)? {
+            None => return Err(Error::SysError(SysErr::ENODEV)),
+            Some(i) => i,
+        }
+    };
+
+    if request == LibcConst::SIOCGIFADDR {
+        ifr.Data = iface.Req.Data;
+    } else {
+        ifr.IFName = iface.Req.IFName;
+    }
+
+    task.CopyOutObj(&ifr, addr)?;
+    return Ok(())
+}
+
+// InterfaceIoctlIFConf answers SIOCGIFCONF from the interface table. As in
+// Linux, a null buffer just returns the length needed for all interfaces.
+pub fn InterfaceIoctlIFConf(task: &Task, addr: u64) -> Result<()> {
+    let mut ifc : IFConf = task.CopyInObj(addr)?;
+
+    let interfaces = Interfaces(true)?;
+    if ifc.Ptr == 0 {
+        ifc.Len = (interfaces.len() * SIZE_OF_IFREQ) as i32;
+        task.CopyOutObj(&ifc, addr)?;
+        return Ok(())
+    }
+
+    let count = core::cmp::min(interfaces.len(), ifc.Len.max(0) as usize / SIZE_OF_IFREQ);
+    for i in 0..count {
+        task.CopyOutObj(&interfaces[i].Req, ifc.Ptr + (i * SIZE_OF_IFREQ) as u64)?;
+    }
+
+    ifc.Len = (count * SIZE_OF_IFREQ) as i32;
+    task.CopyOutObj(&ifc, addr)?;
+    return Ok(())
+}
@@ -0,0 +1,221 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use alloc::collections::btree_map::BTreeMap;
+use alloc::collections::vec_deque::VecDeque;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+use ::qlib::mutex::*;
+
+use super::super::super::fs::file::*;
+use super::super::super::kernel::waiter::*;
+use super::super::super::qlib::linux_def::*;
+use super::super::super::qlib::singleton::*;
+use super::super::super::tcpip::tcpip::*;
+use super::super::unix::unix::*;
+
+// ListenerKey is the full address a listener is bound to: the family, the IP
+// address bytes and the port.
+pub type ListenerKey = (i32, Vec<u8>, u16);
+
+// LOOPBACK_LISTENERS holds the TCP sockets listening on a loopback or
+// wildcard address, keyed by their bound address, so that connections to them
+// from inside the sandbox can bypass the host. Sockets bound to the same
+// address with SO_REUSEPORT form a group which shares the connections.
+//
+// Only TCP takes this path. UDP and other datagram sockets always go through
+// the host, since they have no connect/accept handshake to hook.
+pub static LOOPBACK_LISTENERS : Singleton<QMutex<BTreeMap<ListenerKey, Vec<LoopbackListener>>>> = Singleton::<QMutex<BTreeMap<ListenerKey, Vec<LoopbackListener>>>>::New();
+
+// NEXT_LISTENER spreads the connections over a SO_REUSEPORT group.
+static NEXT_LISTENER: AtomicU64 = AtomicU64::new(0);
+
+pub unsafe fn InitSingleton() {
+    LOOPBACK_LISTENERS.Init(QMutex::new(BTreeMap::new()));
+}
+
+// LoopbackEndpoint is one end of an in-sandbox loopback TCP connection. The
+// data goes through a connected unix stream endpoint pair.
+pub struct LoopbackEndpoint {
+    pub ops: UnixSocketOperations,
+    pub localAddr: Vec<u8>,
+    pub peerAddr: Vec<u8>,
+}
+
+#[derive(Clone)]
+pub struct LoopbackListener {
+    // id tells apart the listeners bound to the same address.
+    pub id: u64,
+    // key is the address the listener is bound to.
+    pub key: ListenerKey,
+    // queue is the waiter queue of the listening socket.
+    pub queue: Queue,
+    // pending holds the connected sockets waiting to be accepted.
+    pub pending: Arc<QMutex<VecDeque<Arc<File>>>>,
+    pub backlog: usize,
+}
+
+impl LoopbackListener {
+    // Enqueue queues file to be accepted. The backlog is checked under the
+    // same lock, so it returns false and drops file if the backlog is full.
+    pub fn Enqueue(&self, file: Arc<File>) -> bool {
+        {
+            let mut pending = self.pending.lock();
+            if pending.len() >= self.backlog {
+                return false
+            }
+
+            pending.push_back(file);
+        }
+
+        self.queue.Notify(EVENT_IN);
+        return true
+    }
+
+    pub fn Dequeue(&self) -> Option<Arc<File>> {
+        return self.pending.lock().pop_front()
+    }
+
+    pub fn Events(&self) -> EventMask {
+        if self.pending.lock().len() > 0 {
+            return EVENT_IN
+        }
+
+        return 0
+    }
+}
+
+// RegisterListener adds listener to the group for its address, or updates
+// it if it is already registered.
+pub fn RegisterListener(listener: &LoopbackListener) {
+    let mut listeners = LOOPBACK_LISTENERS.lock();
+    let group = listeners.entry(listener.key.clone()).or_insert(Vec::new());
+    match group.iter_mut().find(|l| l.id == listener.id) {
+        Some(l) => *l = listener.clone(),
+        None => group.push(listener.clone()),
+    }
+}
+
+pub fn UnregisterListener(listener: &LoopbackListener) {
+    let mut listeners = LOOPBACK_LISTENERS.lock();
+    let empty = match listeners.get_mut(&listener.key) {
+        None => return,
+        Some(group) => {
+            group.retain(|l| l.id != listener.id);
+            group.len() == 0
+        }
+    };
+
+    if empty {
+        listeners.remove(&listener.key);
+    }
+}
+
+// FindListener returns the listener a connection to the loopback address addr
+// of family goes to. Like Linux, a listener bound to the exact address is
+// preferred over one bound to the wildcard address.
+pub fn FindListener(family: i32, addr: &[u8]) -> Option<LoopbackListener> {
+    let ip = AddrIP(family, addr);
+    let port = AddrPort(addr);
+    let wildcard = vec![0; ip.len()];
+
+    let listeners = LOOPBACK_LISTENERS.lock();
+    let group = match listeners.get(&(family, ip.to_vec(), port)) {
+        Some(group) => group,
+        None => match listeners.get(&(family, wildcard, port)) {
+            Some(group) => group,
+            None => return None,
+        }
+    };
+
+    let n = NEXT_LISTENER.fetch_add(1, Ordering::Relaxed) as usize;
+    return Some(group[n % group.len()].clone())
+}
+
+fn Family(addr: &[u8]) -> i32 {
+    return u16::from_ne_bytes([addr[0], addr[1]]) as i32
+}
+
+pub fn AddrPort(addr: &[u8]) -> u16 {
+    return u16::from_be_bytes([addr[2], addr[3]])
+}
+
+// AddrIP returns the IP address bytes of an AF_INET or AF_INET6 sockaddr.
+fn AddrIP(family: i32, addr: &[u8]) -> &[u8] {
+    if family == AFType::AF_INET {
+        return &addr[4..8]
+    }
+
+    return &addr[8..24]
+}
+
+// IsLoopbackIPv6 checks for ::1 and v4-mapped 127.0.0.0/8 addresses.
+fn IsLoopbackIPv6(ip: &[u8]) -> bool {
+    if ip[..15].iter().all(|b| *b == 0) && ip[15] == 1 {
+        return true
+    }
+
+    return ip[..10].iter().all(|b| *b == 0) && ip[10] == 0xff && ip[11] == 0xff && ip[12] == 127
+}
+
+// IsLoopbackAddr returns whether addr is a loopback address of the given
+// family.
+pub fn IsLoopbackAddr(family: i32, addr: &[u8]) -> bool {
+    if family == AFType::AF_INET && addr.len() >= SOCK_ADDR_INET_SIZE &&
+        Family(addr) == AFType::AF_INET && addr[4] == 127 {
+        return true
+    }
+
+    if family == AFType::AF_INET6 && addr.len() >= SOCK_ADDR_INET6_SIZE &&
+        Family(addr) == AFType::AF_INET6 && IsLoopbackIPv6(&addr[8..24]) {
+        return true
+    }
+
+    return false
+}
+
+// ListenKey returns the key a listener's local address is registered under if
+// connections to a loopback address can reach it, i.e. it is bound to a
+// loopback or wildcard address.
+pub fn ListenKey(family: i32, addr: &[u8]) -> Option<ListenerKey> {
+    let wildcard = if family == AFType::AF_INET {
+        addr.len() >= SOCK_ADDR_INET_SIZE && addr[4..8].iter().all(|b| *b == 0)
+    } else {
+        family == AFType::AF_INET6 && addr.len() >= SOCK_ADDR_INET6_SIZE && addr[8..24].iter().all(|b| *b == 0)
+    };
+
+    if !wildcard && !IsLoopbackAddr(family, addr) {
+        return None
+    }
+
+    return Some((family, AddrIP(family, addr).to_vec(), AddrPort(addr)))
+}
+
+// LoopbackAddr is the sockaddr for the loopback address of family with port 0.
+pub fn LoopbackAddr(family: i32) -> Vec<u8> {
+    if family == AFType::AF_INET {
+        let mut addr = vec![0; SOCK_ADDR_INET_SIZE];
+        addr[..2].copy_from_slice(&(AFType::AF_INET as u16).to_ne_bytes());
+        addr[4] = 127;
+        addr[7] = 1;
+        return addr
+    }
+
+    let mut addr = vec![0; SOCK_ADDR_INET6_SIZE];
+    addr[..2].copy_from_slice(&(AFType::AF_INET6 as u16).to_ne_bytes());
+    addr[23] = 1;
+    return addr
+}
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod interfaces;
+pub mod loopback;
 pub mod socket;
 pub mod socket_buf;
 
@@ -30,6 +30,8 @@ use ::socket::control::ControlMessage;
 use super::super::super::guestfdnotifier::*;
 use super::super::socket::*;
 use super::super::unix::transport::unix::*;
+use super::super::unix::transport::connectioned::*;
+use super::super::unix::unix::UnixSocketOperations;
 use super::super::super::fs::file::*;
 use super::super::super::fs::flags::*;
 use super::super::super::fs::dentry::*;
@@ -40,6 +42,7 @@ use super::super::super::kernel::fd_table::*;
 use super::super::super::kernel::waiter::*;
 use super::super::super::kernel::async_wait::*;
 use super::super::super::kernel::time::*;
+use super::super::super::kernel::timer::*;
 use super::super::super::qlib::common::*;
 use super::super::super::task::*;
 use super::super::super::qlib::mem::block::*;
@@ -51,8 +54,11 @@ use super::super::super::qlib::linux_def::*;
 use super::super::super::fd::*;
 use super::super::super::tcpip::tcpip::*;
 use super::super::super::SHARESPACE;
+use super::super::super::uid::*;
 use super::socket_buf::*;
-use super::super::super::qlib::linux::time::Timeval;
+use super::interfaces::*;
+use super::loopback::*;
+use super::super::super::qlib::linux::time::{Timeval, SECOND, MICROSECOND};
 use super::super::control::ControlMessageTCPInq;
 
 fn newSocketFile(task: &Task, family: i32, fd: i32, stype: i32, nonblock: bool, enableBuf: bool, addr: Option<Vec<u8>>) -> Result<File> {
@@ -67,6 +73,40 @@ fn newSocketFile(task: &Task, family: i32, fd: i32, stype: i32, nonblock: bool,
               s))
 }
 
+// NewHostSocketFile wraps an AF_INET or AF_INET6 socket fd imported from the
+// host in a socket file backed by that fd. The family and type are taken from
+// the host socket; nonblock is the application visible O_NONBLOCK setting of
+// the fd.
+pub fn NewHostSocketFile(task: &Task, fd: i32, nonblock: bool) -> Result<File> {
+    let family = Kernel::GetSockOptI32(fd, LibcConst::SOL_SOCKET as i32, LibcConst::SO_DOMAIN as i32)?;
+    match family {
+        AFType::AF_INET | AFType::AF_INET6 => (),
+        _ => return Err(Error::SysError(SysErr::EAFNOSUPPORT)),
+    }
+
+    let stype = Kernel::GetSockOptI32(fd, LibcConst::SOL_SOCKET as i32, LibcConst::SO_TYPE as i32)?;
+    match stype {
+        SockType::SOCK_STREAM | SockType::SOCK_DGRAM | SockType::SOCK_SEQPACKET => (),
+        _ => return Err(Error::SysError(SysErr::ESOCKTNOSUPPORT)),
+    }
+
+    // Host sockets are always driven in non-blocking mode; blocking is done
+    // in the sentry by waiting on the fd notifier.
+    let flags = HostSpace::Fcntl(fd, Cmd::F_GETFL, 0);
+    if flags < 0 {
+        return Err(Error::SysError(-flags as i32))
+    }
+
+    if flags as i32 & Flags::O_NONBLOCK == 0 {
+        let ret = HostSpace::Fcntl(fd, Cmd::F_SETFL, (flags as i32 | Flags::O_NONBLOCK) as u64);
+        if ret < 0 {
+            return Err(Error::SysError(-ret as i32))
+        }
+    }
+
+    return newSocketFile(task, family, fd, stype, nonblock, false, None)
+}
+
 #[derive(Default)]
 pub struct SocketOperationsIntern {
     pub send: AtomicI64,
@@ -81,6 +121,33 @@ pub struct SocketOperationsIntern {
     pub enableAsyncAccept: AtomicBool,
     pub acceptQueue: Arc<QMutex<AsyncAcceptStruct>>,
     passInq: AtomicBool,
+
+    // tcpCork is the TCP_CORK value set by the application.
+    tcpCork: AtomicBool,
+    // msgMoreCorked indicates the host socket is corked on behalf of a
+    // MSG_MORE send which the socket buffer can't pass to the host.
+    msgMoreCorked: AtomicBool,
+    // recvErr is set once IP_RECVERR or IPV6_RECVERR is enabled, so errors
+    // may be queued on the host socket.
+    recvErr: AtomicBool,
+    // netlinkRoute is set for NETLINK_ROUTE sockets, whose requests are
+    // restricted to reading the host's state.
+    netlinkRoute: AtomicBool,
+
+    // loopback is set once the socket is connected to another socket in the
+    // sandbox. The data then bypasses the host socket.
+    pub loopback: QMutex<Option<Arc<LoopbackEndpoint>>>,
+    // loopbackListener is the registration of a listening socket for
+    // connections from inside the sandbox.
+    pub loopbackListener: QMutex<Option<LoopbackListener>>,
+}
+
+impl Drop for SocketOperationsIntern {
+    fn drop(&mut self) {
+        if let Some(ref listener) = *self.loopbackListener.lock() {
+            UnregisterListener(listener);
+        }
+    }
 }
 
 pub const TCP_ADDR_LEN : usize = 128;
@@ -182,7 +249,13 @@ impl SocketOperations {
             enableSocketBuf: AtomicBool::new(false),
             enableAsyncAccept: AtomicBool::new(false),
             acceptQueue: Arc::new(QMutex::new(AsyncAcceptStruct::default())),
-            passInq: AtomicBool::new(false)
+            passInq: AtomicBool::new(false),
+            tcpCork: AtomicBool::new(false),
+            msgMoreCorked: AtomicBool::new(false),
+            recvErr: AtomicBool::new(false),
+            netlinkRoute: AtomicBool::new(false),
+            loopback: QMutex::new(None),
+            loopbackListener: QMutex::new(None),
         };
 
         let ret = Self(Arc::new(ret));
@@ -207,6 +280,17 @@ impl SocketOperations {
         return Ok(ai);
     }
 
+    // ReadSocketBuf reads from the uring socket buffer into iovs. With peek
+    // the data is copied out but left in the buffer.
+    fn ReadSocketBuf(&self, task: &Task, iovs: &mut [IoVec], peek: bool) -> Result<i64> {
+        if peek {
+            let cnt = self.SocketBuf().Peekv(iovs)?;
+            return Ok(cnt as i64)
+        }
+
+        return IOURING.RingFileRead(task, self.fd, self.queue.clone(), self.SocketBuf(), iovs, true)
+    }
+
     fn prepareControlMessage(&self, controlDataLen: usize) -> (i32, Vec<u8>) {
         // shortcut for no controldata wanted
         if controlDataLen == 0 {
@@ -226,6 +310,30 @@ impl SocketOperations {
         }
     }
 
+    fn SetHostCork(&self, cork: bool) {
+        let val : i32 = if cork { 1 } else { 0 };
+        let res = Kernel::HostSpace::SetSockOpt(self.fd, LibcConst::SOL_TCP as i32, LibcConst::TCP_CORK as i32, &val as *const _ as u64, 4);
+        if res < 0 {
+            info!("hostinet socket fd {} set TCP_CORK to {} fail with {}", self.fd, cork, -res);
+        }
+    }
+
+    // The socket buffer writes to the host socket through io_uring, which
+    // drops the send flags. MSG_MORE is emulated by corking the host socket
+    // until a send without MSG_MORE arrives. Either MSG_MORE or TCP_CORK keeps
+    // the socket corked.
+    pub fn CorkForMsgMore(&self) {
+        if !self.msgMoreCorked.swap(true, Ordering::SeqCst) && !self.tcpCork.load(Ordering::SeqCst) {
+            self.SetHostCork(true);
+        }
+    }
+
+    pub fn UncorkForMsgMore(&self) {
+        if self.msgMoreCorked.swap(false, Ordering::SeqCst) && !self.tcpCork.load(Ordering::SeqCst) {
+            self.SetHostCork(false);
+        }
+    }
+
     pub fn AsyncAcceptEnabled(&self) -> bool {
         return self.enableAsyncAccept.load(Ordering::Relaxed);
     }
@@ -277,12 +385,381 @@ impl SocketOperations {
             Some(ref v) => Some(v.ToVec().unwrap()),
         }
     }
+
+    pub fn Loopback(&self) -> Option<Arc<LoopbackEndpoint>> {
+        return self.loopback.lock().clone()
+    }
+
+    // LoopbackEnabled returns whether the socket can take the in-sandbox
+    // loopback path. That is limited to TCP; UDP always goes through the host.
+    fn LoopbackEnabled(&self) -> bool {
+        return SHARESPACE.config.read().LoopbackFastPath &&
+            (self.family == AFType::AF_INET || self.family == AFType::AF_INET6) &&
+            self.stype == SockType::SOCK_STREAM
+    }
 }
 
 pub const SIZEOF_SOCKADDR: usize = SocketSize::SIZEOF_SOCKADDR_INET6;
 
+// SO_SNDBUF and SO_RCVBUF requests are capped before they reach the host so
+// one sandboxed socket can't pin an unbounded amount of host memory.
+pub const MAX_SOCK_BUF_SIZE: i32 = 4 << 20;
+
+// HostSockOptLen returns the minimum value size of a SOL_SOCKET, SOL_TCP,
+// SOL_IP or SOL_IPV6 option that may be passed through to the host socket,
+// or None if the option isn't supported. Get-only options are only accepted
+// when get is set. Options which aren't listed here are never passed to the
+// host.
+fn HostSockOptLen(level: i32, name: i32, get: bool) -> Option<usize> {
+    match level as u64 {
+        LibcConst::SOL_SOCKET => {
+            match name as u64 {
+                LibcConst::SO_REUSEADDR
+                | LibcConst::SO_REUSEPORT
+                | LibcConst::SO_KEEPALIVE
+                | LibcConst::SO_SNDLOWAT
+                | LibcConst::SO_RXQ_OVFL
+                | LibcConst::SO_BROADCAST
+                | LibcConst::SO_SNDBUF
+                | LibcConst::SO_RCVBUF
+                | LibcConst::SO_OOBINLINE
+                | LibcConst::SO_PRIORITY
+                | LibcConst::SO_RCVLOWAT
+                | LibcConst::SO_DONTROUTE
+                | LibcConst::SO_NO_CHECK
+                | LibcConst::SO_PASSCRED
+                | LibcConst::SO_TIMESTAMP
+                | LibcConst::SO_TIMESTAMPNS => Some(SocketSize::SIZEOF_INT32),
+                LibcConst::SO_LINGER => Some(SocketSize::SIZEOF_LINGER),
+                LibcConst::SO_ERROR
+                | LibcConst::SO_TYPE
+                | LibcConst::SO_DOMAIN
+                | LibcConst::SO_PROTOCOL
+                | LibcConst::SO_ACCEPTCONN => {
+                    if get {
+                        Some(SocketSize::SIZEOF_INT32)
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            }
+        }
+        LibcConst::SOL_TCP => {
+            match name as u64 {
+                LibcConst::TCP_NODELAY
+                | LibcConst::TCP_MAXSEG
+                | LibcConst::TCP_CORK
+                | LibcConst::TCP_KEEPIDLE
+                | LibcConst::TCP_KEEPINTVL
+                | LibcConst::TCP_KEEPCNT
+                | LibcConst::TCP_SYNCNT
+                | LibcConst::TCP_LINGER2
+                | LibcConst::TCP_DEFER_ACCEPT
+                | LibcConst::TCP_WINDOW_CLAMP
+                | LibcConst::TCP_QUICKACK
+                | LibcConst::TCP_USER_TIMEOUT
+                | LibcConst::TCP_NOTSENT_LOWAT
+                | LibcConst::TCP_INQ => Some(SocketSize::SIZEOF_INT32),
+                // The congestion control algorithm is a string.
+                LibcConst::TCP_CONGESTION => Some(1),
+                LibcConst::TCP_INFO if get => Some(0),
+                _ => None,
+            }
+        }
+        LibcConst::SOL_IP => {
+            match name as u64 {
+                LibcConst::IP_TTL
+                | LibcConst::IP_MULTICAST_TTL
+                | LibcConst::IP_MULTICAST_LOOP
+                | LibcConst::IP_PKTINFO
+                | LibcConst::IP_RECVTOS
+                | LibcConst::IP_RECVTTL
+                | LibcConst::IP_RECVERR
+                | LibcConst::IP_MTU_DISCOVER
+                | LibcConst::IP_FREEBIND
+                | LibcConst::IP_TRANSPARENT => Some(SocketSize::SIZEOF_INT32),
+                // IP_TOS may be a single byte, see GetSockOpt.
+                LibcConst::IP_TOS => Some(1),
+                // struct ip_mreq; struct ip_mreqn is accepted too.
+                LibcConst::IP_ADD_MEMBERSHIP
+                | LibcConst::IP_DROP_MEMBERSHIP => Some(8),
+                LibcConst::IP_MULTICAST_IF => Some(4),
+                LibcConst::IP_MTU if get => Some(SocketSize::SIZEOF_INT32),
+                _ => None,
+            }
+        }
+        LibcConst::SOL_IPV6 => {
+            match name as u64 {
+                LibcConst::IPV6_V6ONLY
+                | LibcConst::IPV6_UNICAST_HOPS
+                | LibcConst::IPV6_MULTICAST_HOPS
+                | LibcConst::IPV6_MULTICAST_LOOP
+                | LibcConst::IPV6_MULTICAST_IF
+                | LibcConst::IPV6_RECVPKTINFO
+                | LibcConst::IPV6_RECVHOPLIMIT
+                | LibcConst::IPV6_RECVTCLASS
+                | LibcConst::IPV6_RECVERR
+                | LibcConst::IPV6_TCLASS
+                | LibcConst::IPV6_MTU_DISCOVER => Some(SocketSize::SIZEOF_INT32),
+                // struct ipv6_mreq
+                LibcConst::IPV6_ADD_MEMBERSHIP
+                | LibcConst::IPV6_DROP_MEMBERSHIP => Some(20),
+                LibcConst::IPV6_MTU if get => Some(SocketSize::SIZEOF_INT32),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// RTM_GET is the low bits of the rtnetlink request types that only read
+// state (RTM_GETLINK, RTM_GETADDR, RTM_GETROUTE, ...): the types are numbered
+// from NLMSG_MIN_TYPE in groups of NEW, DEL, GET, SET.
+const RTM_GET: u16 = 2;
+
+// CheckNetlinkRequest fails with EPERM if buf holds a NETLINK_ROUTE request
+// that would modify the host's links, addresses or routes. Dumps are passed
+// through, as the host network namespace is the sandbox's view.
+fn CheckNetlinkRequest(buf: &[u8]) -> Result<()> {
+    let hdrLen = LibcConst::NLMSG_HDRLEN as usize;
+    let align = LibcConst::NLMSG_ALIGNTO as usize;
+
+    let mut offset = 0;
+    while offset + hdrLen <= buf.len() {
+        let len = u32::from_ne_bytes([buf[offset], buf[offset+1], buf[offset+2], buf[offset+3]]) as usize;
+        let typ = u16::from_ne_bytes([buf[offset+4], buf[offset+5]]);
+
+        if typ >= LibcConst::NLMSG_MIN_TYPE as u16 && (typ - LibcConst::NLMSG_MIN_TYPE as u16) % 4 != RTM_GET {
+            return Err(Error::SysError(SysErr::EPERM))
+        }
+
+        // Malformed messages are left to the host to reject.
+        if len < hdrLen {
+            break;
+        }
+
+        offset += (len + align - 1) & !(align - 1);
+    }
+
+    return Ok(())
+}
+
+impl SocketOperations {
+    // HostAddr fetches the host socket's local or peer address and copies as
+    // much of it as fits into socketaddr. It returns the full address length.
+    fn HostAddr(&self, peer: bool, socketaddr: &mut [u8]) -> Result<i64> {
+        if let Some(lo) = self.Loopback() {
+            let addr = if peer {
+                &lo.peerAddr
+            } else {
+                &lo.localAddr
+            };
+
+            let n = core::cmp::min(addr.len(), socketaddr.len());
+            socketaddr[..n].copy_from_slice(&addr[..n]);
+            return Ok(addr.len() as i64)
+        }
+
+        let mut buf: [u8; SOCK_ADDR_MAX_LEN] = [0; SOCK_ADDR_MAX_LEN];
+        let mut len = buf.len() as i32;
+
+        let res = if peer {
+            Kernel::HostSpace::GetPeerName(self.fd, &mut buf[0] as *mut _ as u64, &mut len as *mut _ as u64)
+        } else {
+            Kernel::HostSpace::GetSockName(self.fd, &mut buf[0] as *mut _ as u64, &mut len as *mut _ as u64)
+        };
+
+        if res < 0 {
+            return Err(Error::SysError(-res as i32))
+        }
+
+        let len = self.MapV4Addr(&mut buf, len as usize);
+        let n = core::cmp::min(len, socketaddr.len());
+        socketaddr[..n].copy_from_slice(&buf[..n]);
+        return Ok(len as i64)
+    }
+
+    // MapV4Addr rewrites an IPv4 address reported for a dual-stack AF_INET6
+    // socket as the v4-mapped IPv6 address the application expects, and
+    // returns the resulting address length.
+    fn MapV4Addr(&self, buf: &mut [u8; SOCK_ADDR_MAX_LEN], len: usize) -> usize {
+        if self.family != AFType::AF_INET6 || len < SOCK_ADDR_INET_SIZE {
+            return len
+        }
+
+        let inet = unsafe {
+            ptr::read_unaligned(&buf[0] as *const _ as *const SockAddrInet)
+        };
+
+        if inet.Family != AFType::AF_INET as u16 {
+            return len
+        }
+
+        let mut v6only: i32 = 0;
+        let optlen: i32 = 4;
+        let res = Kernel::HostSpace::GetSockOpt(self.fd, LibcConst::SOL_IPV6 as i32, LibcConst::IPV6_V6ONLY as i32, &mut v6only as *mut i32 as u64, &optlen as *const i32 as u64);
+        if res < 0 || v6only != 0 {
+            return len
+        }
+
+        let mut inet6 = SocketAddrInet6 {
+            Family: AFType::AF_INET6 as u16,
+            Port: inet.Port,
+            ..Default::default()
+        };
+        inet6.Addr[10] = 0xff;
+        inet6.Addr[11] = 0xff;
+        inet6.Addr[12..].copy_from_slice(&inet.Addr);
+
+        unsafe {
+            ptr::write_unaligned(&mut buf[0] as *mut _ as *mut SocketAddrInet6, inet6);
+        }
+
+        return inet6.Len()
+    }
+
+    // ConnectLoopback connects the socket to a listener in the sandbox through
+    // a unix stream endpoint pair instead of the host. It returns false if
+    // sockaddr isn't a loopback address with such a listener, or the
+    // listener's backlog is full, and the host is to do the connect. Only TCP
+    // sockets get here, see LoopbackEnabled.
+    fn ConnectLoopback(&self, task: &Task, sockaddr: &[u8]) -> Result<bool> {
+        if !IsLoopbackAddr(self.family, sockaddr) {
+            return Ok(false)
+        }
+
+        let listener = match FindListener(self.family, sockaddr) {
+            None => return Ok(false),
+            Some(listener) => listener,
+        };
+
+        // The host socket is bound so that the connection gets a port of its
+        // own, which getsockname and the accepting side report.
+        let mut addr: [u8; SOCK_ADDR_MAX_LEN] = [0; SOCK_ADDR_MAX_LEN];
+        let mut len = self.HostAddr(false, &mut addr)? as usize;
+        if AddrPort(&addr) == 0 {
+            let local = LoopbackAddr(self.family);
+            let res = Kernel::HostSpace::Bind(self.fd, &local[0] as *const _ as u64, local.len() as u32, task.Umask());
+            if res < 0 {
+                return Err(Error::SysError(-res as i32))
+            }
+
+            len = self.HostAddr(false, &mut addr)? as usize;
+        }
+
+        let localAddr = addr[..len].to_vec();
+        let remoteAddr = sockaddr.to_vec();
+
+        // The accepted socket only keeps a host socket to back its inode.
+        let res = Kernel::HostSpace::Socket(self.family, SockType::SOCK_STREAM | SocketFlags::SOCK_CLOEXEC, 0);
+        if res < 0 {
+            return Err(Error::SysError(-res as i32))
+        }
+
+        let file = newSocketFile(task, self.family, res as i32, SockType::SOCK_STREAM, false, false, Some(localAddr.clone()))?;
+        let peer = file.FileOp.as_any().downcast_ref::<SocketOperations>().unwrap().clone();
+
+        let (a, b) = ConnectionedEndPoint::NewPairWithQueues(task, SockType::SOCK_STREAM, &self.queue, &peer.queue);
+        let lo = Arc::new(LoopbackEndpoint {
+            ops: UnixSocketOperations::New(BoundEndpoint::Connected(a), SockType::SOCK_STREAM, -1),
+            localAddr: localAddr.clone(),
+            peerAddr: remoteAddr.clone(),
+        });
+        *peer.loopback.lock() = Some(Arc::new(LoopbackEndpoint {
+            ops: UnixSocketOperations::New(BoundEndpoint::Connected(b), SockType::SOCK_STREAM, -1),
+            localAddr: remoteAddr.clone(),
+            peerAddr: localAddr,
+        }));
+
+        if !listener.Enqueue(Arc::new(file)) {
+            // The backlog is full; dropping the accepted side closes the pair.
+            return Ok(false)
+        }
+
+        *self.loopback.lock() = Some(lo);
+        self.SetRemoteAddr(remoteAddr)?;
+        return Ok(true)
+    }
+
+    // ListenLoopback makes a socket listening on a loopback or wildcard
+    // address reachable by ConnectLoopback.
+    fn ListenLoopback(&self, backlog: i32) -> Result<()> {
+        let backlog = if backlog <= 0 {
+            5
+        } else {
+            backlog as usize
+        };
+
+        let mut registered = self.loopbackListener.lock();
+        if let Some(ref mut listener) = *registered {
+            listener.backlog = backlog;
+            RegisterListener(listener);
+            return Ok(())
+        }
+
+        let mut addr: [u8; SOCK_ADDR_MAX_LEN] = [0; SOCK_ADDR_MAX_LEN];
+        let len = self.HostAddr(false, &mut addr)? as usize;
+        let key = match ListenKey(self.family, &addr[..len]) {
+            None => return Ok(()),
+            Some(key) => key,
+        };
+
+        let listener = LoopbackListener {
+            id: NewUID(),
+            key: key,
+            queue: self.queue.clone(),
+            pending: Arc::new(QMutex::new(VecDeque::new())),
+            backlog: backlog,
+        };
+
+        RegisterListener(&listener);
+        *registered = Some(listener);
+        return Ok(())
+    }
+
+    // AcceptLoopback installs the next connection made by ConnectLoopback,
+    // if there is one.
+    fn AcceptLoopback(&self, task: &Task, addr: &mut [u8], addrlen: &mut u32, flags: i32) -> Result<Option<i64>> {
+        let file = match *self.loopbackListener.lock() {
+            None => return Ok(None),
+            Some(ref listener) => match listener.Dequeue() {
+                None => return Ok(None),
+                Some(file) => file,
+            }
+        };
+
+        if addr.len() > 0 {
+            let sock = file.FileOp.as_any().downcast_ref::<SocketOperations>().unwrap().clone();
+            let peerAddr = sock.Loopback().unwrap().peerAddr.clone();
+            let len = core::cmp::min(peerAddr.len(), addr.len());
+            addr[..len].copy_from_slice(&peerAddr[..len]);
+            // Like accept(2), addrlen is the full size of the address even
+            // if it was truncated.
+            *addrlen = peerAddr.len() as u32;
+        }
+
+        let mut fileFlags = file.Flags().SettableFileFlags();
+        fileFlags.NonBlocking = flags & SocketFlags::SOCK_NONBLOCK != 0;
+        file.SetFlags(task, fileFlags);
+
+        let fdFlags = FDFlags {
+            CloseOnExec: flags & SocketFlags::SOCK_CLOEXEC != 0
+        };
+
+        let fd = task.NewFDFrom(0, &file, &fdFlags)?;
+        return Ok(Some(fd as i64))
+    }
+}
+
 impl Waitable for SocketOperations {
-    fn AsyncReadiness(&self, _task: &Task, mask: EventMask, wait: &MultiWait) -> Future<EventMask> {
+    fn AsyncReadiness(&self, task: &Task, mask: EventMask, wait: &MultiWait) -> Future<EventMask> {
+        if self.Loopback().is_some() || self.loopbackListener.lock().is_some() {
+            let future = Future::New(0 as EventMask);
+            future.Set(Ok(self.Readiness(task, mask)));
+            return future;
+        }
+
         if self.SocketBufEnabled() {
             let future = Future::New(0 as EventMask);
             let ret = self.SocketBuf().Events() & mask;
@@ -293,13 +770,32 @@ impl Waitable for SocketOperations {
 
 
         let fd = self.fd;
-        let future = IOURING.UnblockPollAdd(fd, mask as u32, wait);
+        let future = AsyncPoll(fd, mask, wait);
         return future;
     }
 
-    fn Readiness(&self, _task: &Task, mask: EventMask) -> EventMask {
+    fn Readiness(&self, task: &Task, mask: EventMask) -> EventMask {
+        if let Some(lo) = self.Loopback() {
+            return lo.ops.Readiness(task, mask)
+        }
+
+        if let Some(ref listener) = *self.loopbackListener.lock() {
+            let events = listener.Events() & mask;
+            if events != 0 {
+                return events
+            }
+        }
+
         if self.SocketBufEnabled() {
-            return self.SocketBuf().Events() & mask
+            let mut events = self.SocketBuf().Events() & mask;
+
+            // The socket buffer only sees the data stream, errors queued with
+            // IP_RECVERR are reported by the host socket.
+            if mask & EVENT_ERR != 0 && self.recvErr.load(Ordering::Relaxed) {
+                events |= NonBlockingPoll(self.fd, EVENT_ERR) & EVENT_ERR;
+            }
+
+            return events
         };
 
         if self.AsyncAcceptEnabled() {
@@ -332,7 +828,7 @@ impl Waitable for SocketOperations {
         let queue = self.queue.clone();
         queue.EventRegister(task, e, mask);
         let fd = self.fd;
-        if !self.SocketBufEnabled() && !self.AsyncAcceptEnabled() {
+        if !self.SocketBufEnabled() && !self.AsyncAcceptEnabled() && self.Loopback().is_none() {
             UpdateFD(fd).unwrap();
         };
     }
@@ -341,7 +837,7 @@ impl Waitable for SocketOperations {
         let queue = self.queue.clone();
         queue.EventUnregister(task, e);
         let fd = self.fd;
-        if !self.SocketBufEnabled() && !self.AsyncAcceptEnabled() {
+        if !self.SocketBufEnabled() && !self.AsyncAcceptEnabled() && self.Loopback().is_none() {
             UpdateFD(fd).unwrap();
         };
     }
@@ -418,7 +914,11 @@ impl FileOperations for SocketOperations {
         return Err(Error::SysError(SysErr::ENOTDIR))
     }
 
-    fn ReadAt(&self, task: &Task, _f: &File, dsts: &mut [IoVec], _offset: i64, _blocking: bool) -> Result<i64> {
+    fn ReadAt(&self, task: &Task, f: &File, dsts: &mut [IoVec], offset: i64, blocking: bool) -> Result<i64> {
+        if let Some(lo) = self.Loopback() {
+            return lo.ops.ReadAt(task, f, dsts, offset, blocking)
+        }
+
         if self.SocketBufEnabled() {
             //todo: optimize to avoid extra memory copy
             let size = IoVec::NumBytes(dsts);
@@ -442,13 +942,18 @@ impl FileOperations for SocketOperations {
         return Ok(ret);
     }
 
-    fn WriteAt(&self, task: &Task, _f: &File, srcs: &[IoVec], _offset: i64, _blocking: bool) -> Result<i64> {
+    fn WriteAt(&self, task: &Task, f: &File, srcs: &[IoVec], offset: i64, blocking: bool) -> Result<i64> {
+        if let Some(lo) = self.Loopback() {
+            return lo.ops.WriteAt(task, f, srcs, offset, blocking)
+        }
+
         if self.SocketBufEnabled() {
             //todo: optimize to avoid extra memory copy
             let size = IoVec::NumBytes(srcs);
             let mut buf = DataBuff::New(size);
             task.CopyDataInFromIovs(&mut buf.buf, &srcs)?;
             let iovs = buf.Iovs();
+            defer!(self.UncorkForMsgMore());
             return IOURING.RingFileWrite(task, self.fd, self.queue.clone(), self.SocketBuf(), &iovs, true)
 
             //return IOURING.RingFileWrite(task, self, srcs)
@@ -461,6 +966,10 @@ impl FileOperations for SocketOperations {
         let mut buf = DataBuff::New(size);
         let iovs = buf.Iovs();
         task.CopyDataInFromIovs(&mut buf.buf, srcs)?;
+        if self.netlinkRoute.load(Ordering::Relaxed) {
+            CheckNetlinkRequest(&buf.buf)?;
+        }
+
         return IOWrite(self.fd, &iovs);
     }
 
@@ -483,11 +992,15 @@ impl FileOperations for SocketOperations {
 
     }
 
-    fn Ioctl(&self, task: &Task, _f: &File, _fd: i32, request: u64, val: u64) -> Result<()> {
+    fn Ioctl(&self, task: &Task, f: &File, fd: i32, request: u64, val: u64) -> Result<()> {
         let flags = request as i32;
 
         let hostfd = self.fd;
         match flags as u64 {
+            LibcConst::SIOCGIFADDR |
+            LibcConst::SIOCGIFNAME => {
+                return InterfaceIoctlIFReq(task, request, val)
+            }
             LibcConst::SIOCGIFFLAGS |
             LibcConst::SIOCGIFBRDADDR |
             LibcConst::SIOCGIFDSTADDR |
@@ -496,7 +1009,6 @@ impl FileOperations for SocketOperations {
             LibcConst::SIOCGIFMAP |
             LibcConst::SIOCGIFMETRIC |
             LibcConst::SIOCGIFMTU |
-            LibcConst::SIOCGIFNAME |
             LibcConst::SIOCGIFNETMASK |
             LibcConst::SIOCGIFTXQLEN => {
                 let addr = val;
@@ -505,12 +1017,13 @@ impl FileOperations for SocketOperations {
                 return Ok(())
             }
             LibcConst::SIOCGIFCONF => {
-                let addr = val;
-                HostIoctlIFConf(task, hostfd, request, addr)?;
-
-                return Ok(())
+                return InterfaceIoctlIFConf(task, val)
             }
             LibcConst::TIOCINQ => {
+                if let Some(lo) = self.Loopback() {
+                    return lo.ops.Ioctl(task, f, fd, request, val)
+                }
+
                 if self.SocketBufEnabled() {
                     let v =  self.SocketBuf().readBuf.lock().available as i32;
                     task.CopyOutObj(&v, val)?;
@@ -525,6 +1038,20 @@ impl FileOperations for SocketOperations {
                     return Ok(())
                 }
             }
+            LibcConst::TIOCOUTQ |
+            LibcConst::SIOCATMARK => {
+                if let Some(lo) = self.Loopback() {
+                    return lo.ops.Ioctl(task, f, fd, request, val)
+                }
+
+                let tmp: i32 = 0;
+                let res = Kernel::HostSpace::IoCtl(self.fd, request, &tmp as *const _ as u64);
+                if res < 0 {
+                    return Err(Error::SysError(-res as i32))
+                }
+                task.CopyOutObj(&tmp, val)?;
+                return Ok(())
+            }
             _ => {
                 let tmp: i32 = 0;
                 let res = Kernel::HostSpace::IoCtl(self.fd, request, &tmp as *const _ as u64);
@@ -555,6 +1082,15 @@ impl SockOperations for SocketOperations {
             socketaddr = &socketaddr[..SIZEOF_SOCKADDR]
         }
 
+        if self.Loopback().is_some() {
+            return Err(Error::SysError(SysErr::EISCONN))
+        }
+
+        // Connections to listeners in the sandbox don't need the host.
+        if self.LoopbackEnabled() && self.ConnectLoopback(task, socketaddr)? {
+            return Ok(0)
+        }
+
         let res = Kernel::HostSpace::IOConnect(self.fd, &socketaddr[0] as *const _ as u64, socketaddr.len() as u32, blocking) as i32;
         if res == 0 {
             self.SetRemoteAddr(socketaddr.to_vec())?;
@@ -566,36 +1102,44 @@ impl SockOperations for SocketOperations {
             return Ok(0)
         }
 
-        let blocking = if blocking {
-            true
-        } else {
-            // in order to enable uring buff, have to do block accept
-            if SHARESPACE.config.read().TcpBuffIO
-                && (self.family == AFType::AF_INET || self.family == AFType::AF_INET6)
-                && self.stype == SockType::SOCK_STREAM {
-                true
-            } else {
-                false
+        if res != 0 {
+            if -res == SysErr::EINPROGRESS && !blocking {
+                // The handshake completes on the host: poll reports the
+                // socket writable once it is done and SO_ERROR carries the
+                // outcome. The uring socket buffer can only be set up on a
+                // connected socket, so this socket does its IO directly on
+                // the host fd.
+                return Err(Error::SysError(SysErr::EINPROGRESS))
             }
 
-            //false
-        };
-
-        if res != 0 {
-            if -res != SysErr::EINPROGRESS || !blocking {
+            if -res != SysErr::EINPROGRESS {
                 return Err(Error::SysError(-res))
             }
 
-            //todo: which one is more efficent?
+            // Like Linux, connect waits at most SO_SNDTIMEO and then leaves
+            // the handshake running in the background.
+            let mut deadline = None;
+            let dl = self.SendTimeout();
+            if dl > 0 {
+                let now = MonotonicNow();
+                deadline = Some(Time(now + dl));
+            }
+
+            // A failed handshake is reported as an error or hangup rather
+            // than writability.
+            let mask = EVENT_OUT | EVENT_ERR | EVENT_HUP;
             let general = task.blocker.generalEntry.clone();
-            self.EventRegister(task, &general, EVENT_OUT);
+            self.EventRegister(task, &general, mask);
             defer!(self.EventUnregister(task, &general));
 
-            if self.Readiness(task, EVENT_OUT) == 0 {
-                match task.blocker.BlockWithMonoTimer(true, None) {
+            while self.Readiness(task, mask) == 0 {
+                match task.blocker.BlockWithMonoTimer(true, deadline) {
                     Err(Error::ErrInterrupted) => {
                         return Err(Error::SysError(SysErr::ERESTARTSYS));
                     }
+                    Err(Error::SysError(SysErr::ETIMEDOUT)) => {
+                        return Err(Error::SysError(SysErr::EINPROGRESS));
+                    }
                     Err(e) => {
                         error!("connect error {:?}", &e);
                         return Err(e);
@@ -629,6 +1173,21 @@ impl SockOperations for SocketOperations {
     fn Accept(&self, task: &Task, addr: &mut [u8], addrlen: &mut u32, flags: i32, blocking: bool) -> Result<i64> {
         let asyncAccept = self.AsyncAcceptEnabled();
 
+        // accept(2) is bounded by SO_RCVTIMEO.
+        let mut blocking = blocking;
+        let mut deadline = None;
+        let dl = self.RecvTimeout();
+        if dl > 0 {
+            let now = MonotonicNow();
+            deadline = Some(Time(now + dl));
+        } else if dl < 0 {
+            blocking = false;
+        }
+
+        if let Some(fd) = self.AcceptLoopback(task, addr, addrlen, flags)? {
+            return Ok(fd)
+        }
+
         let mut acceptItem = AcceptItem::default();
         let ai = if asyncAccept {
             IOURING.Accept(self.fd, &self.queue, &self.acceptQueue)
@@ -653,6 +1212,10 @@ impl SockOperations for SocketOperations {
             defer!(self.EventUnregister(task, &general));
 
             loop {
+                if let Some(fd) = self.AcceptLoopback(task, addr, addrlen, flags)? {
+                    return Ok(fd)
+                }
+
                 let ai = if asyncAccept {
                     IOURING.Accept(self.fd, &self.queue, &self.acceptQueue)
                 } else {
@@ -669,7 +1232,10 @@ impl SockOperations for SocketOperations {
                         break;
                     }
                 }
-                match task.blocker.BlockWithMonoTimer(true, None) {
+                match task.blocker.BlockWithMonoTimer(true, deadline) {
+                    Err(Error::SysError(SysErr::ETIMEDOUT)) => {
+                        return Err(Error::SysError(SysErr::EAGAIN));
+                    }
                     Err(e) => {
                         return Err(e);
                     }
@@ -754,15 +1320,24 @@ impl SockOperations for SocketOperations {
             }
         }
 
+        if self.LoopbackEnabled() {
+            self.ListenLoopback(backlog)?;
+        }
+
         return Ok(res)
     }
 
     fn Shutdown(&self, task: &Task, how: i32) -> Result<i64> {
+        if let Some(lo) = self.Loopback() {
+            return lo.ops.Shutdown(task, how)
+        }
+
         let how = how as u64;
 
-        if how == LibcConst::SHUT_WR || how == LibcConst::SHUT_RDWR {
+        if self.SocketBufEnabled() && (how == LibcConst::SHUT_WR || how == LibcConst::SHUT_RDWR) {
+            // No more data may be queued once the write side is shut down.
+            self.SocketBuf().SetPendingWriteShutdown();
             if self.SocketBuf().HasWritingData() {
-                self.SocketBuf().SetPendingWriteShutdown();
                 let general = task.blocker.generalEntry.clone();
                 self.EventRegister(task, &general, EVENT_PENDING_SHUTDOWN);
                 defer!(self.EventUnregister(task, &general));
@@ -779,6 +1354,13 @@ impl SockOperations for SocketOperations {
                 return Err(Error::SysError(-res as i32))
             }
 
+            // Reads are served from the socket buffer, so they have to see EOF
+            // there once the buffered data is drained.
+            if self.SocketBufEnabled() && (how == LibcConst::SHUT_RD || how == LibcConst::SHUT_RDWR) {
+                self.SocketBuf().SetRClosed();
+                self.Notify(EVENT_IN | EVENT_RDHUP);
+            }
+
             return Ok(res)
         }
 
@@ -859,6 +1441,36 @@ impl SockOperations for SocketOperations {
         return Ok(optlen as i64)
         */
 
+        if (level as u64) == LibcConst::SOL_SOCKET &&
+            ((name as u64) == LibcConst::SO_RCVTIMEO || (name as u64) == LibcConst::SO_SNDTIMEO) {
+                if opt.len() < SocketSize::SIZEOF_TIMEVAL {
+                    return Err(Error::SysError(SysErr::EINVAL))
+                }
+
+                let ns = if (name as u64) == LibcConst::SO_RCVTIMEO {
+                    self.RecvTimeout()
+                } else {
+                    self.SendTimeout()
+                };
+
+                let timeVal = if ns < 0 {
+                    Timeval::default()
+                } else {
+                    Timeval::FromNs(ns)
+                };
+
+                unsafe {
+                    *(&mut opt[0] as * mut _ as u64 as * mut Timeval) = timeVal;
+                }
+
+                return Ok(SocketSize::SIZEOF_TIMEVAL as i64)
+            }
+
+        // A short buffer is fine here: the host truncates the value.
+        if HostSockOptLen(level, name, true).is_none() {
+            return Err(Error::SysError(SysErr::ENOPROTOOPT))
+        }
+
         let mut optLen = opt.len();
         let res = if optLen == 0 {
             Kernel::HostSpace::GetSockOpt(self.fd, level, name, ptr::null::<u8>() as u64, &mut optLen as *mut _ as u64)
@@ -873,7 +1485,7 @@ impl SockOperations for SocketOperations {
         return Ok(optLen as i64)
     }
 
-    fn SetSockOpt(&self, task: &Task, level: i32, name: i32, opt: &[u8]) -> Result<i64> {
+    fn SetSockOpt(&self, _task: &Task, level: i32, name: i32, opt: &[u8]) -> Result<i64> {
         
         /*let optlen = match level as u64 {
             LibcConst::SOL_IPV6 => {
@@ -911,16 +1523,68 @@ impl SockOperations for SocketOperations {
 
         let opt = &opt[..optlen];*/
 
+        // The host fd is always non-blocking, so the timeouts are kept here
+        // and applied by the blocking paths instead of being passed down.
         if (level as u64) == LibcConst::SOL_SOCKET &&
-            (name as u64) == LibcConst::SO_RCVTIMEO {
-                if opt.len() >= SocketSize::SIZEOF_TIMEVAL {
-                    let timeVal = task.CopyInObj::<Timeval>(&opt[0] as *const _ as u64)?;
-                    self.SetRecvTimeout(timeVal.ToDuration() as i64);
-                } else {
+            ((name as u64) == LibcConst::SO_RCVTIMEO || (name as u64) == LibcConst::SO_SNDTIMEO) {
+                if opt.len() < SocketSize::SIZEOF_TIMEVAL {
                     //TODO: to be aligned with Linux, Linux allows shorter length for this flag.
                     return Err(Error::SysError(SysErr::EINVAL));
                 }
+
+                let timeVal = unsafe {
+                    *(&opt[0] as * const _ as u64 as * const Timeval)
+                };
+
+                if timeVal.Usec < 0 || timeVal.Usec >= SECOND / MICROSECOND {
+                    return Err(Error::SysError(SysErr::EDOM))
+                }
+
+                // A negative timeout makes the operation non-blocking.
+                let ns = if timeVal.Sec < 0 {
+                    -1
+                } else {
+                    timeVal.ToDuration()
+                };
+
+                if (name as u64) == LibcConst::SO_RCVTIMEO {
+                    self.SetRecvTimeout(ns);
+                } else {
+                    self.SetSendTimeout(ns);
+                }
+
+                return Ok(0)
+            }
+
+        match HostSockOptLen(level, name, false) {
+            None => return Err(Error::SysError(SysErr::ENOPROTOOPT)),
+            Some(size) => {
+                if opt.len() < size {
+                    return Err(Error::SysError(SysErr::EINVAL))
+                }
             }
+        }
+
+        let bufSize;
+        let opt = if (level as u64) == LibcConst::SOL_SOCKET &&
+            ((name as u64) == LibcConst::SO_SNDBUF || (name as u64) == LibcConst::SO_RCVBUF) {
+                let val = unsafe {
+                    *(&opt[0] as * const _ as u64 as * const i32)
+                };
+
+                // Like Linux, a negative size is taken as unsigned and so as
+                // the largest allowed.
+                let val = if val < 0 || val > MAX_SOCK_BUF_SIZE {
+                    MAX_SOCK_BUF_SIZE
+                } else {
+                    val
+                };
+
+                bufSize = val.to_ne_bytes();
+                &bufSize[..]
+        } else {
+            opt
+        };
 
         // TCP_INQ is bound to buffer implementation
         if (level as u64) == LibcConst::SOL_TCP &&
@@ -935,6 +1599,19 @@ impl SockOperations for SocketOperations {
                 }
         }
 
+        if (level as u64) == LibcConst::SOL_TCP &&
+            (name as u64) == LibcConst::TCP_CORK && opt.len() >= 4 {
+                let val = unsafe {
+                    *(&opt[0] as * const _ as u64 as * const i32)
+                };
+                self.tcpCork.store(val != 0, Ordering::SeqCst);
+                if val == 0 {
+                    // Uncorking pushes out everything pending, including
+                    // data held back by an earlier MSG_MORE.
+                    self.msgMoreCorked.store(false, Ordering::SeqCst);
+                }
+        }
+
         let optLen = opt.len();
         let res = if optLen == 0 {
             Kernel::HostSpace::SetSockOpt(self.fd, level, name, ptr::null::<u8>() as u64, optLen as u32)
@@ -946,28 +1623,23 @@ impl SockOperations for SocketOperations {
             return Err(Error::SysError(-res as i32))
         }
 
+        if ((level as u64) == LibcConst::SOL_IP && (name as u64) == LibcConst::IP_RECVERR ||
+            (level as u64) == LibcConst::SOL_IPV6 && (name as u64) == LibcConst::IPV6_RECVERR) && opt.len() >= 4 {
+                let val = unsafe {
+                    *(&opt[0] as * const _ as u64 as * const i32)
+                };
+                self.recvErr.store(val != 0, Ordering::Relaxed);
+        }
+
         return Ok(res)
     }
 
     fn GetSockName(&self, _task: &Task, socketaddr: &mut [u8]) -> Result<i64> {
-        let len = socketaddr.len() as i32;
-
-        let res = Kernel::HostSpace::GetSockName(self.fd, &socketaddr[0] as *const _ as u64, &len as *const _ as u64);
-        if res < 0 {
-            return Err(Error::SysError(-res as i32))
-        }
-
-        return Ok(len as i64)
+        return self.HostAddr(false, socketaddr)
     }
 
     fn GetPeerName(&self, _task: &Task, socketaddr: &mut [u8]) -> Result<i64> {
-        let len = socketaddr.len() as i32;
-        let res = Kernel::HostSpace::GetPeerName(self.fd, &socketaddr[0] as *const _ as u64, &len as *const _ as u64);
-        if res < 0 {
-            return Err(Error::SysError(-res as i32))
-        }
-
-        return Ok(len as i64)
+        return self.HostAddr(true, socketaddr)
     }
 
     fn RecvMsg(&self, task: &Task, dsts: &mut [IoVec], flags: i32, deadline: Option<Time>, senderRequested: bool, controlDataLen: usize)
@@ -977,7 +1649,16 @@ impl SockOperations for SocketOperations {
         //let stype = self.stype;
 
         //error!("RecvMsg ... host socket  fd {} {}/{}/{}/{}", self.fd, flags & MsgType::MSG_DONTWAIT, self.SocketBufEnabled(), family, stype);
-        if self.SocketBufEnabled() {
+
+        if let Some(lo) = self.Loopback() {
+            return lo.ops.RecvMsg(task, dsts, flags, deadline, false, 0)
+        }
+
+        // The error queue lives on the host socket and is never waited for,
+        // its entries are relayed with their IP_RECVERR control messages.
+        let errQueue = flags & MsgType::MSG_ERRQUEUE != 0;
+
+        if self.SocketBufEnabled() && !errQueue {
             /*
             if controlDataLen != 0 {
                 panic!("Hostnet RecvMsg Socketbuf doesn't support control data");
@@ -986,6 +1667,11 @@ impl SockOperations for SocketOperations {
 
             let controlDataLen = 0;
 
+            // A peek returns whatever is buffered without consuming it, so
+            // it never waits for the rest of the buffer.
+            let peek = flags & MsgType::MSG_PEEK != 0;
+            let waitAll = flags & MsgType::MSG_WAITALL != 0 && !peek;
+
             let len = IoVec::NumBytes(dsts);
             let buf = DataBuff::New(len);
             let mut vec = buf.Iovs();
@@ -994,7 +1680,7 @@ impl SockOperations for SocketOperations {
             let mut count = 0;
             let mut tmp;
             loop {
-                match IOURING.RingFileRead(task, self.fd, self.queue.clone(), self.SocketBuf(), iovs, true) {
+                match self.ReadSocketBuf(task, iovs, peek) {
                     Err(Error::SysError(SysErr::EWOULDBLOCK)) => {
                         if flags & MsgType::MSG_DONTWAIT != 0 {
                             if count > 0 {
@@ -1026,7 +1712,7 @@ impl SockOperations for SocketOperations {
                         }
 
                         count += n;
-                        if count == len as i64 {
+                        if count == len as i64 || peek {
                             let (retFlags, controlData) = self.prepareControlMessage(controlDataLen);
                             task.CopyDataOutToIovs(&buf.buf[0..count as usize], dsts)?;
                             return Ok((count as i64, retFlags, None, controlData))
@@ -1044,9 +1730,9 @@ impl SockOperations for SocketOperations {
 
             'main: loop {
                 loop {
-                    match IOURING.RingFileRead(task, self.fd, self.queue.clone(), self.SocketBuf(), iovs, true) {
+                    match self.ReadSocketBuf(task, iovs, peek) {
                         Err(Error::SysError(SysErr::EWOULDBLOCK)) => {
-                            if count > 0 {
+                            if count > 0 && !waitAll {
                                 break 'main;
                             }
                             break;
@@ -1063,7 +1749,7 @@ impl SockOperations for SocketOperations {
                             }
 
                             count += n;
-                            if count == len as i64 {
+                            if count == len as i64 || peek {
                                 break 'main;
                             }
 
@@ -1110,8 +1796,7 @@ impl SockOperations for SocketOperations {
             return Ok((count as i64, retFlags, senderAddr, controlData))
         }
 
-        //todo: we don't support MSG_ERRQUEUE
-        if flags & !(MsgType::MSG_DONTWAIT | MsgType::MSG_PEEK | MsgType::MSG_TRUNC | MsgType::MSG_CTRUNC | MsgType::MSG_WAITALL) != 0 {
+        if flags & !(MsgType::MSG_DONTWAIT | MsgType::MSG_PEEK | MsgType::MSG_TRUNC | MsgType::MSG_CTRUNC | MsgType::MSG_WAITALL | MsgType::MSG_ERRQUEUE) != 0 {
             return Err(Error::SysError(SysErr::EINVAL))
         }
 
@@ -1153,7 +1838,7 @@ impl SockOperations for SocketOperations {
         }
 
         let mut res = Kernel::HostSpace::IORecvMsg(self.fd, &mut msgHdr as *mut _ as u64, flags | MsgType::MSG_DONTWAIT, false) as i32;
-        while res == -SysErr::EWOULDBLOCK && flags & MsgType::MSG_DONTWAIT == 0 {
+        while res == -SysErr::EWOULDBLOCK && flags & MsgType::MSG_DONTWAIT == 0 && !errQueue {
             let general = task.blocker.generalEntry.clone();
 
             self.EventRegister(task, &general, EVENT_READ);
@@ -1178,6 +1863,43 @@ impl SockOperations for SocketOperations {
             return Err(Error::SysError(-res as i32))
         }
 
+        // The host read is non-blocking, so MSG_WAITALL has to be done here:
+        // keep reading until the buffer is full, the peer shuts down or a
+        // signal arrives.
+        let mut count = res as usize;
+        if flags & MsgType::MSG_WAITALL != 0 && flags & (MsgType::MSG_PEEK | MsgType::MSG_TRUNC | MsgType::MSG_DONTWAIT | MsgType::MSG_ERRQUEUE) == 0
+            && self.stype == SockType::SOCK_STREAM && count > 0 && count < size {
+            let general = task.blocker.generalEntry.clone();
+            self.EventRegister(task, &general, EVENT_READ);
+            defer!(self.EventUnregister(task, &general));
+
+            while count < size {
+                let iov = IoVec {
+                    start: &buf.buf[count] as *const _ as u64,
+                    len: size - count,
+                };
+
+                let mut hdr = MsgHdr::default();
+                hdr.iov = &iov as *const _ as u64;
+                hdr.iovLen = 1;
+
+                let n = Kernel::HostSpace::IORecvMsg(self.fd, &mut hdr as *mut _ as u64, flags | MsgType::MSG_DONTWAIT, false) as i32;
+                if n > 0 {
+                    count += n as usize;
+                    continue;
+                }
+
+                if n != -SysErr::EWOULDBLOCK {
+                    break;
+                }
+
+                match task.blocker.BlockWithMonoTimer(true, deadline) {
+                    Err(_) => break,
+                    _ => (),
+                }
+            }
+        }
+
         let msgFlags = msgHdr.msgFlags & !MsgType::MSG_CTRUNC;
         let senderAddr = if senderRequested
             // for tcp connect, recvmsg get nameLen=0 msg
@@ -1192,16 +1914,34 @@ impl SockOperations for SocketOperations {
 
         controlVec.resize(msgHdr.msgControlLen, 0);
 
-        task.CopyDataOutToIovs(&buf.buf[0..res as usize], dsts)?;
-        return Ok((res as i64, msgFlags, senderAddr, controlVec))
+        // With MSG_TRUNC a datagram's full length is returned even if it
+        // didn't fit in the buffer.
+        let copied = core::cmp::min(count, size);
+        task.CopyDataOutToIovs(&buf.buf[0..copied], dsts)?;
+        return Ok((count as i64, msgFlags, senderAddr, controlVec))
     }
 
     fn SendMsg(&self, task: &Task, srcs: &[IoVec], flags: i32, msgHdr: &mut MsgHdr, deadline: Option<Time>) -> Result<i64> {
+        if let Some(lo) = self.Loopback() {
+            // Like on a connected TCP socket, the destination and control
+            // messages are ignored.
+            let mut hdr = MsgHdr::default();
+            return lo.ops.SendMsg(task, srcs, flags, &mut hdr, deadline)
+        }
+
         if self.SocketBufEnabled() {
             if msgHdr.msgName != 0 || msgHdr.msgControl != 0 {
                 panic!("Hostnet Socketbuf doesn't supprot MsgHdr");
             }
 
+            let more = flags & MsgType::MSG_MORE != 0;
+            if more {
+                self.CorkForMsgMore();
+            }
+            defer!(if !more {
+                self.UncorkForMsgMore()
+            });
+
             let len = Iovs(srcs).Count();
             let mut count = 0;
             let mut srcs = srcs;
@@ -1275,6 +2015,9 @@ impl SockOperations for SocketOperations {
         let iovs = buf.Iovs();
 
         task.CopyDataInFromIovs(&mut buf.buf, srcs)?;
+        if self.netlinkRoute.load(Ordering::Relaxed) {
+            CheckNetlinkRequest(&buf.buf)?;
+        }
 
         if IoVec::NumBytes(srcs) != 0 {
             msgHdr.iov = &iovs[0] as *const _ as u64;
@@ -1291,6 +2034,9 @@ impl SockOperations for SocketOperations {
             self.EventRegister(task, &general, EVENT_WRITE);
             defer!(self.EventUnregister(task, &general));
             match task.blocker.BlockWithMonoTimer(true, deadline) {
+                Err(Error::SysError(SysErr::ETIMEDOUT)) => {
+                    return Err(Error::SysError(SysErr::EAGAIN));
+                }
                 Err(e) => {
                     return Err(e);
                 }
@@ -1340,6 +2086,14 @@ impl Provider for SocketProvider {
        let fd = res as i32;
 
         let file = newSocketFile(task, self.family, fd, stype & SocketType::SOCK_TYPE_MASK, stype & SocketFlags::SOCK_NONBLOCK != 0, false, None)?;
+
+        // NETLINK_ROUTE sockets may only read the sandbox's interfaces,
+        // addresses and routes. Other netlink protocols go to the host as is.
+        if self.family == AFType::AF_NETLINK && protocol as u64 == LibcConst::NETLINK_ROUTE {
+            let sock = file.FileOp.as_any().downcast_ref::<SocketOperations>().unwrap();
+            sock.netlinkRoute.store(true, Ordering::Relaxed);
+        }
+
         return Ok(Some(Arc::new(file)))
     }
 
@@ -67,6 +67,11 @@ impl SocketBuff {
             event |= EVENT_IN
         }
 
+        // the peer has shut down its write side, or we have shut down reading
+        if self.RClosed() {
+            event |= EVENT_RDHUP;
+        }
+
         if self.writeBuf.lock().AvailableSpace() > 0 {
             event |= EVENT_OUT;
         }
@@ -166,6 +171,30 @@ impl SocketBuff {
         }
     }
 
+    // Peekv is Readv for MSG_PEEK: the data stays in the buffer.
+    pub fn Peekv(&self, iovs: &mut [IoVec]) -> Result<usize> {
+        let mut cnt = 0;
+
+        let readbuf = self.readBuf.lock();
+        for iov in iovs {
+            let size = readbuf.peek(cnt, iov.ToSliceMut());
+            cnt += size;
+            if size < iov.len {
+                break;
+            }
+        }
+
+        if cnt > 0 {
+            return Ok(cnt)
+        } else if self.Error() != 0 {
+            return Err(Error::SysError(self.Error()));
+        } else if self.RClosed() {
+            return Ok(0)
+        } else {
+            return Err(Error::SysError(SysErr::EAGAIN))
+        }
+    }
+
     pub fn GetAvailableWriteBuf(&self) -> (u64, usize) {
         return self.writeBuf.lock().GetDataBuf();
     }
@@ -175,7 +204,7 @@ impl SocketBuff {
             return Err(Error::SysError(self.Error()));
         }
 
-        if self.WClosed() {
+        if self.WClosed() || self.PendingWriteShutdown() {
             error!("writev it is closed");
             //return Ok((0, None))
             return Err(Error::SysError(SysErr::EPIPE))
@@ -28,7 +28,7 @@ use super::super::super::super::qlib::linux::socket::*;
 use super::super::super::super::qlib::linux_def::*;
 use super::super::super::super::task::*;
 use super::super::super::super::uid::*;
-//use super::super::super::control::*;
+use super::super::super::control::{ScmCredentials, MakeCreds};
 use super::unix::*;
 use super::queue::*;
 
@@ -91,6 +91,11 @@ pub struct ConnectionedEndPointInternal {
     //
     // If nil, then no listen call has been made.
     pub acceptedChan: Option<BufChan<ConnectionedEndPoint>>,
+
+    // peerCreds are the credentials reported by SO_PEERCRED. For a connected
+    // endpoint they belong to the task on the other end at connect time; for
+    // a listening endpoint they belong to the task which called listen.
+    pub peerCreds: Option<ScmCredentials>,
 }
 
 impl ConnectionedEndPointInternal {
@@ -157,6 +162,7 @@ impl ConnectionedEndPoint {
             stype: stype,
             backlog: 0,
             acceptedChan: None,
+            peerCreds: None,
         };
 
         return Self(Arc::new((QMutex::new(internal), QMutex::new(()))))
@@ -181,14 +187,34 @@ impl ConnectionedEndPoint {
             stype: stype,
             backlog: 0,
             acceptedChan: None,
+            peerCreds: None,
         };
 
         return Self(Arc::new((QMutex::new(internal), QMutex::new(()))))
     }
 
-    pub fn NewPair(stype: i32, hostfd1: i32, hostfd2: i32) -> (Self, Self) {
+    pub fn NewPair(task: &Task, stype: i32, hostfd1: i32, hostfd2: i32) -> (Self, Self) {
         let a = Self::New(stype, hostfd1);
         let b = Self::New(stype, hostfd2);
+        Self::ConnectPair(task, stype, &a, &b);
+        return (a, b)
+    }
+
+    // NewPairWithQueues is NewPair for endpoints that report their events on
+    // the waiter queues of the sockets using them.
+    pub fn NewPairWithQueues(task: &Task, stype: i32, aq: &Queue, bq: &Queue) -> (Self, Self) {
+        let a = Self::New(stype, -1);
+        let b = Self::New(stype, -1);
+        a.lock().baseEndpoint.lock().queue = aq.clone();
+        b.lock().baseEndpoint.lock().queue = bq.clone();
+        Self::ConnectPair(task, stype, &a, &b);
+        return (a, b)
+    }
+
+    fn ConnectPair(task: &Task, stype: i32, a: &Self, b: &Self) {
+        // Both ends of a socketpair report the creating task as their peer.
+        a.lock().peerCreds = MakeCreds(task, None);
+        b.lock().peerCreds = MakeCreds(task, None);
 
         let aq = a.lock().baseEndpoint.lock().queue.clone();
         let bq = b.lock().baseEndpoint.lock().queue.clone();
@@ -213,8 +239,6 @@ impl ConnectionedEndPoint {
             endpoint: Arc::new(a.clone()),
             writeQueue: q1,
         }));
-
-        return (a, b)
     }
 
     // NewExternal creates a new externally backed Endpoint. It behaves like a
@@ -228,6 +252,7 @@ impl ConnectionedEndPoint {
             stype: stype,
             backlog: 0,
             acceptedChan: None,
+            peerCreds: None,
         };
 
         return Self(Arc::new((QMutex::new(internal), QMutex::new(()))))
@@ -289,6 +314,7 @@ impl ConnectionedEndPoint {
         baseEndPoint.lock().path = self.lock().baseEndpoint.lock().path.to_string();
         let stype = self.lock().stype;
         let ne = ConnectionedEndPoint::NewWithBaseEndpoint(baseEndPoint, stype);
+        ne.lock().peerCreds = MakeCreds(task, None);
 
         let readq = ce.WaiterQueue();
         let writeq = ne.lock().baseEndpoint.lock().queue.clone();
@@ -332,6 +358,10 @@ impl ConnectionedEndPoint {
     pub fn UnidirectionalConnect(&self) -> Result<UnixConnectedEndpoint> {
         return Err(Error::SysError(SysErr::ECONNREFUSED))
     }
+
+    pub fn PeerCreds(&self) -> Option<ScmCredentials> {
+        return self.lock().peerCreds.clone()
+    }
 }
 
 impl ConnectingEndpoint for ConnectionedEndPoint {
@@ -473,11 +503,15 @@ impl Endpoint for ConnectionedEndPoint {
             self.lock().baseEndpoint.lock().connected = Some(ce);
         };
 
-        return server.BidirectionalConnect(task, Arc::new(self.clone()), returnConnect)
+        server.BidirectionalConnect(task, Arc::new(self.clone()), returnConnect)?;
+
+        // The connecting side sees the credentials of the listening task.
+        self.lock().peerCreds = server.PeerCreds();
+        return Ok(())
     }
 
     // Listen starts listening on the connection.
-    fn Listen(&self, backlog: i32) -> Result<()> {
+    fn Listen(&self, task: &Task, backlog: i32) -> Result<()> {
         self.TryLock();
         let mut e = self.lock();
 
@@ -510,6 +544,7 @@ impl Endpoint for ConnectionedEndPoint {
         }
 
         e.acceptedChan = Some(BufChan::New(backlog as usize));
+        e.peerCreds = MakeCreds(task, None);
         return Ok(())
     }
 
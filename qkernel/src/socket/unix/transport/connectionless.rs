@@ -210,7 +210,7 @@ impl Endpoint for ConnectionLessEndPoint {
     }
 
     // Listen starts listening on the connection.
-    fn Listen(&self, _: i32) -> Result<()> {
+    fn Listen(&self, _task: &Task, _: i32) -> Result<()> {
         return Err(Error::SysError(SysErr::EOPNOTSUPP))
     }
 
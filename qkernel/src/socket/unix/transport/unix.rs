@@ -150,7 +150,7 @@ pub trait Endpoint : PartialEndPoint + Passcred + ConnectedPasscred + Waitable +
 
     // Listen puts the endpoint in "listen" mode, which allows it to accept
     // new connections.
-    fn Listen(&self, backlog: i32) -> Result<()>;
+    fn Listen(&self, task: &Task, backlog: i32) -> Result<()>;
 
     // Accept returns a new endpoint if a peer has established a connection
     // to an endpoint previously set to listen mode. This method does not
@@ -280,6 +280,19 @@ impl BoundEndpoint {
             }
         }
     }
+
+    // PeerCreds returns the credentials of the connected peer, if any.
+    // Connectionless endpoints have no peer credentials.
+    pub fn PeerCreds(&self) -> Option<ScmCredentials> {
+        match self {
+            BoundEndpoint::Connected(ref c) => {
+                return c.PeerCreds()
+            }
+            BoundEndpoint::ConnectLess(_) => {
+                return None
+            }
+        }
+    }
 }
 
 impl Endpoint for BoundEndpoint {
@@ -343,13 +356,13 @@ impl Endpoint for BoundEndpoint {
         }
     }
 
-    fn Listen(&self, backlog: i32) -> Result<()> {
+    fn Listen(&self, task: &Task, backlog: i32) -> Result<()> {
         match self {
             BoundEndpoint::Connected(ref c) => {
-                return c.Listen(backlog)
+                return c.Listen(task, backlog)
             }
             BoundEndpoint::ConnectLess(ref c) => {
-                return c.Listen(backlog)
+                return c.Listen(task, backlog)
             }
         }
     }
@@ -991,6 +1004,12 @@ pub struct BaseEndpointInternal {
     // enabled on this endpoint. Must be accessed atomically.
     pub passcred: i32,
 
+    // reuseAddr and reusePort hold SO_REUSEADDR and SO_REUSEPORT. As on Linux
+    // they don't change how unix socket addresses are bound; they are only
+    // reported back by getsockopt.
+    pub reuseAddr: i32,
+    pub reusePort: i32,
+
     // receiver allows Messages to be received.
     pub receiver: Option<Arc<Receiver>>,
 
@@ -1015,6 +1034,8 @@ impl Default for BaseEndpointInternal {
             id: NewUID(),
             queue: Queue::default(),
             passcred: 0,
+            reuseAddr: 0,
+            reusePort: 0,
             receiver: None,
             connected: None,
             path: String::default(),
@@ -1204,6 +1225,14 @@ impl BaseEndpoint {
                 self.setPasscred(*v != 0);
                 return Ok(())
             }
+            SockOpt::ReuseAddressOption(ref v) => {
+                self.lock().reuseAddr = (*v != 0) as i32;
+                return Ok(())
+            }
+            SockOpt::ReusePortOption(ref v) => {
+                self.lock().reusePort = (*v != 0) as i32;
+                return Ok(())
+            }
             _ => return Ok(())
         }
     }
@@ -1255,6 +1284,14 @@ impl BaseEndpoint {
                 *opt = SockOpt::PasscredOption(val);
                 return Ok(())
             }
+            SockOpt::ReuseAddressOption(_) => {
+                *opt = SockOpt::ReuseAddressOption(self.lock().reuseAddr);
+                return Ok(())
+            }
+            SockOpt::ReusePortOption(_) => {
+                *opt = SockOpt::ReusePortOption(self.lock().reusePort);
+                return Ok(())
+            }
             SockOpt::SendBufferSizeOption(_) => {
                 let qs = {
                     let e = self.lock();
@@ -35,6 +35,7 @@ use super::super::super::kernel::fd_table::*;
 use super::super::super::kernel::abstract_socket_namespace::*;
 use super::super::super::kernel::waiter::*;
 use super::super::super::kernel::time::*;
+use super::super::super::kernel::timer::*;
 use super::super::super::qlib::common::*;
 use super::super::super::qlib::linux_def::*;
 use super::super::super::qlib::linux::socket::*;
@@ -42,7 +43,7 @@ use super::super::super::task::*;
 //use super::super::super::qlib::mem::io::*;
 use super::super::super::qlib::mem::seq::*;
 use super::super::super::qlib::path::*;
-//use super::super::super::Kernel;
+use super::super::super::Kernel;
 use super::super::super::Kernel::HostSpace;
 //use super::super::super::fd::*;
 use super::super::super::tcpip::tcpip::*;
@@ -64,6 +65,31 @@ pub fn NewUnixSocket(task: &Task, ep: BoundEndpoint, stype: i32, hostfd: i32) ->
     return Ok(File::New(&dirent, &fileFlags, UnixSocketOperations::New(ep, stype, hostfd)))
 }
 
+// NewUnixSocketFromHostFd wraps an AF_UNIX socket fd imported from the host,
+// e.g. one passed in for socket activation, in a unix socket file of the host
+// socket's type. nonblock is the application visible O_NONBLOCK setting of
+// the fd.
+pub fn NewUnixSocketFromHostFd(task: &Task, fd: i32, nonblock: bool) -> Result<File> {
+    let stype = Kernel::GetSockOptI32(fd, LibcConst::SOL_SOCKET as i32, LibcConst::SO_TYPE as i32)?;
+    let ep = match stype {
+        SockType::SOCK_DGRAM => {
+            BoundEndpoint::ConnectLess(ConnectionLessEndPoint::New(fd))
+        }
+        SockType::SOCK_STREAM | SockType::SOCK_SEQPACKET => {
+            BoundEndpoint::Connected(ConnectionedEndPoint::New(stype, fd))
+        }
+        _ => return Err(Error::SysError(SysErr::ESOCKTNOSUPPORT)),
+    };
+
+    let file = NewUnixSocket(task, ep, stype, fd)?;
+    file.SetFlags(task, SettableFileFlags {
+        NonBlocking: nonblock,
+        ..Default::default()
+    });
+
+    return Ok(file)
+}
+
 pub struct UnixSocketOperations {
     pub ep: BoundEndpoint,
     pub stype: i32,
@@ -122,8 +148,9 @@ impl UnixSocketOperations {
     }
 
     // blockingAccept implements a blocking version of accept(2), that is, if no
-    // connections are ready to be accept, it will block until one becomes ready.
-    pub fn BlockingAccept(&self, task: &Task) -> Result<ConnectionedEndPoint> {
+    // connections are ready to be accept, it will block until one becomes ready
+    // or the deadline passes.
+    pub fn BlockingAccept(&self, task: &Task, deadline: Option<Time>) -> Result<ConnectionedEndPoint> {
         let entry = task.blocker.generalEntry.clone();
         self.EventRegister(task, &entry, EVENT_IN);
 
@@ -138,7 +165,13 @@ impl UnixSocketOperations {
                 Err(e) => return Err(e),
             }
 
-            task.blocker.BlockGeneral()?;
+            match task.blocker.BlockWithMonoTimer(true, deadline) {
+                Err(Error::SysError(SysErr::ETIMEDOUT)) => {
+                    return Err(Error::SysError(SysErr::EAGAIN))
+                }
+                Err(e) => return Err(e),
+                _ => (),
+            }
         }
     }
 
@@ -160,7 +193,7 @@ impl UnixSocketOperations {
                         data
                     },
                     Some(ref creds) => {
-                        let (data, flags) = creds.Credentials().EncodeInto(controlData, *mflags);
+                        let (data, flags) = creds.Credentials(task).EncodeInto(controlData, *mflags);
                         *mflags = flags;
                         data
                     },
@@ -196,6 +229,13 @@ impl UnixSocketOperations {
 
 impl Drop for UnixSocketOperations {
     fn drop(&mut self) {
+        // An abstract name goes away with the socket bound to it.
+        if let Some(name) = self.name.lock().as_ref() {
+            if name.len() > 0 && name[0] == 0 {
+                Unbind(name);
+            }
+        }
+
         self.ep.Close();
     }
 }
@@ -422,11 +462,19 @@ impl SockOperations for UnixSocketOperations {
     fn Accept(&self, task: &Task, addr: &mut [u8], addrlen: &mut u32, flags: i32, blocking: bool) -> Result<i64> {
         let ep = match self.ep.Accept() {
             Err(Error::SysError(SysErr::EWOULDBLOCK)) => {
-                if !blocking {
+                // SO_RCVTIMEO bounds how long accept waits.
+                let dl = self.RecvTimeout();
+                if !blocking || dl < 0 {
                     return Err(Error::SysError(SysErr::EWOULDBLOCK));
                 }
 
-                self.BlockingAccept(task)?
+                let deadline = if dl > 0 {
+                    Some(Time(MonotonicNow() + dl))
+                } else {
+                    None
+                };
+
+                self.BlockingAccept(task, deadline)?
             }
             Err(e) => return Err(e),
             Ok(ep) => ep,
@@ -460,21 +508,51 @@ impl SockOperations for UnixSocketOperations {
     }
 
     fn Bind(&self, task: &Task, socketaddr: &[u8]) -> Result<i64> {
+        let bep = self.ep.clone();
+
+        // An address holding nothing but the family autobinds the socket to a
+        // unique abstract name.
+        if socketaddr.len() == 2 {
+            GetAddr(AFType::AF_UNIX as i16, socketaddr)?;
+
+            // Linux leaves an already bound socket as it is.
+            if self.ep.GetLocalAddress()?.Path.len() != 0 {
+                return Ok(0)
+            }
+
+            let name = Autobind(&bep)?;
+            let addr = SockAddrUnix::New(core::str::from_utf8(&name).unwrap());
+            if let Err(e) = self.ep.Bind(&addr) {
+                Unbind(&name);
+                return Err(e)
+            }
+
+            *(self.name.lock()) = Some(name);
+            return Ok(0)
+        }
+
         let p = ExtractPath(socketaddr)?;
 
         info!("Bind p is {:?}", &p);
-        let bep = self.ep.clone();
 
         let addr = SockAddrUnix::New(core::str::from_utf8(&p).expect("Bind to string fail"));
-        self.ep.Bind(&addr)?;
 
         let root = task.fsContext.RootDirectory();
 
         // Is it abstract?
         if p[0] == 0 {
+            // The name is claimed first so that a name in use leaves the
+            // socket unbound.
             Bind(p.clone(), &bep)?;
+            if let Err(e) = self.ep.Bind(&addr) {
+                Unbind(&p);
+                return Err(e)
+            }
+
             *(self.name.lock()) = Some(p);
         } else {
+            self.ep.Bind(&addr)?;
+
             let p = String::from_utf8(p).unwrap();
             info!("bind address is {}", &p);
 
@@ -539,8 +617,8 @@ impl SockOperations for UnixSocketOperations {
         return Ok(0)
     }
 
-    fn Listen(&self, _task: &Task, backlog: i32) -> Result<i64> {
-        self.ep.Listen(backlog)?;
+    fn Listen(&self, task: &Task, backlog: i32) -> Result<i64> {
+        self.ep.Listen(task, backlog)?;
         return Ok(0);
     }
 
@@ -582,10 +660,17 @@ impl SockOperations for UnixSocketOperations {
 
     fn RecvMsg(&self, task: &Task, dsts: &mut [IoVec], flags: i32, deadline: Option<Time>, senderRequested: bool, controlDataLen: usize)
                -> Result<(i64, i32, Option<(SockAddr, usize)>, Vec<u8>)>  {
+        // Unix sockets never queue errors.
+        if flags & MsgType::MSG_ERRQUEUE != 0 {
+            return Err(Error::SysError(SysErr::EAGAIN))
+        }
+
         let trunc = flags & MsgType::MSG_TRUNC != 0;
         let peek = flags & MsgType::MSG_PEEK != 0;
         let dontWait = flags & MsgType::MSG_DONTWAIT != 0;
-        let waitAll = flags & MsgType::MSG_WAITALL != 0;
+        // A peek leaves the data queued, so there is nothing to wait for
+        // beyond the first read.
+        let waitAll = flags & MsgType::MSG_WAITALL != 0 && !peek;
         let cloexec = flags & MsgType::MSG_CMSG_CLOEXEC != 0;
 
         // Calculate the number of FDs for which we have space and if we are
@@ -627,16 +712,14 @@ impl SockOperations for UnixSocketOperations {
                 }
             }
             Err(Error::ErrClosedForReceive) => {
-                if self.IsPacket() {
-                    return Err(Error::SysError(SysErr::EAGAIN))
-                }
+                // End of file, for packet sockets as well as streams.
                 task.CopyDataOutToIovs(&buf.buf[0..total as usize], dsts)?;
                 return Ok((total, msgFlags, sender, ControlVec))
             }
             Err(e) => {
                 return Err(e)
             }
-            Ok((mut n, ms, ctrls, ctrunc)) => {
+            Ok((n, ms, ctrls, ctrunc)) => {
                 sender = if senderRequested {
                     let fromLen = unixAddr.Len();
                     Some((SockAddr::Unix(unixAddr), fromLen))
@@ -656,21 +739,17 @@ impl SockOperations for UnixSocketOperations {
                     msgFlags |= MsgType::MSG_TRUNC;
                 }
 
-                if trunc {
-                    n = ms
-                }
-
                 if dontWait || !waitAll || self.IsPacket() || n >= seq.NumBytes() as usize {
-                    if self.IsPacket() && n < ms {
-                        msgFlags |= MsgType::MSG_TRUNC;
-                    }
-
-                    if trunc {
-                        n = ms;
-                    }
-
                     task.CopyDataOutToIovs(&buf.buf[0..n as usize], dsts)?;
-                    return Ok((n as i64, msgFlags, sender, ControlVec))
+
+                    // MSG_TRUNC returns the real length of the datagram, even
+                    // when it didn't fit.
+                    let ret = if trunc && self.IsPacket() {
+                        ms
+                    } else {
+                        n
+                    };
+                    return Ok((ret as i64, msgFlags, sender, ControlVec))
                 }
 
                 let seq = seq.DropFirst(n as u64);
@@ -711,17 +790,7 @@ impl SockOperations for UnixSocketOperations {
                         msgFlags |= MsgType::MSG_CTRUNC;
                     }
 
-                    if trunc {
-                        total += ms as i64
-                    } else {
-                        total += n as i64
-                    }
-
-                    // todo: handle waitAll
-                    let seq = BlockSeq::NewFromSlice(&bs);
-                    if waitAll && n < seq.NumBytes() as usize {
-                        info!("RecvMsg get waitall, but return partial......")
-                    }
+                    total += n as i64;
 
                     if self.IsPacket() && n < ms {
                         msgFlags |= MsgType::MSG_TRUNC;
@@ -729,12 +798,15 @@ impl SockOperations for UnixSocketOperations {
 
                     let seq = BlockSeq::NewFromSlice(&bs);
                     if !waitAll || self.IsPacket() || n >= seq.NumBytes() as usize {
-                        if self.IsPacket() && n < ms {
-                            msgFlags |= MsgType::MSG_TRUNC;
-                        }
                         let ControlVector = self.encodeControlMsg(task, ctrls, controlDataLen, &mut msgFlags, cloexec);
                         task.CopyDataOutToIovs(&buf.buf[0..total as usize], dsts)?;
-                        return Ok((total, msgFlags, sender, ControlVector))
+
+                        let ret = if trunc && self.IsPacket() {
+                            ms as i64
+                        } else {
+                            total
+                        };
+                        return Ok((ret, msgFlags, sender, ControlVector))
                     }
 
                     let seq = seq.DropFirst(n as u64);
@@ -750,7 +822,14 @@ impl SockOperations for UnixSocketOperations {
                     }
                     return Err(Error::SysError(SysErr::EAGAIN))
                 }
-                Err(e) => return Err(e),
+                Err(e) => {
+                    // A signal ends MSG_WAITALL with what was read so far.
+                    if total > 0 {
+                        task.CopyDataOutToIovs(&buf.buf[0..total as usize], dsts)?;
+                        return Ok((total as i64, msgFlags, sender, ControlVec))
+                    }
+                    return Err(e)
+                }
                 _ =>(),
             }
         }
@@ -929,7 +1008,7 @@ impl Provider for UnixSocketProvider {
         }
 
         // Create the endpoints and sockets.
-        let (ep1, ep2) = ConnectionedEndPoint::NewPair(stype, fd1, fd2);
+        let (ep1, ep2) = ConnectionedEndPoint::NewPair(task, stype, fd1, fd2);
         let ep1 = BoundEndpoint::Connected(ep1);
         let ep2 = BoundEndpoint::Connected(ep2);
         let s1 = NewUnixSocket(task, ep1, stype, fd1)?;
@@ -45,4 +45,9 @@ pub mod sys_capability;
 pub mod sys_membarrier;
 pub mod sys_splice;
 pub mod sys_timer;
-pub mod sys_mempolicy;
\ No newline at end of file
+pub mod sys_mempolicy;
+pub mod sys_mount;
+pub mod sys_process_vm;
+pub mod sys_shm;
+pub mod sys_inotify;
+pub mod sys_ptrace;
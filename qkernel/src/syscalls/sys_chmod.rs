@@ -23,6 +23,8 @@ use super::super::syscalls::syscalls::*;
 use super::sys_file::*;
 
 fn Chmod(task: &Task, d: &Dirent, mode: FileMode) -> Result<()> {
+    d.CheckWritableMount(task)?;
+
     // Must own file to change mode.
     let mut inode = d.Inode();
     if !inode.CheckOwnership(task) {
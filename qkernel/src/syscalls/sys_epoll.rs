@@ -232,6 +232,19 @@ pub fn SysEpollCtl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
             flags |= EDGE_TRIGGERED;
         }
 
+        if e.Events & LibcConst::EPOLLEXCLUSIVE as u32 != 0 {
+            // EPOLLEXCLUSIVE can only be set when adding a file, and only
+            // together with the events below. See fs/eventpoll.c.
+            const EXCLUSIVE_OK_BITS: u32 = (LibcConst::EPOLLIN | LibcConst::EPOLLOUT |
+                LibcConst::EPOLLERR | LibcConst::EPOLLHUP | LibcConst::EPOLLWAKEUP |
+                LibcConst::EPOLLEXCLUSIVE) as u32 | (-LibcConst::EPOLLET) as u32;
+            if op != LibcConst::EPOLL_CTL_ADD as i32 || e.Events & !EXCLUSIVE_OK_BITS != 0 {
+                return Err(Error::SysError(SysErr::EINVAL))
+            }
+
+            flags |= EXCLUSIVE;
+        }
+
         mask = EventMaskFromLinux(e.Events);
         data[0] = e.FD;
         data[1] = e.Pad;
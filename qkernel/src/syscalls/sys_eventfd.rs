@@ -21,7 +21,7 @@ use super::super::kernel::eventfd::*;
 use super::super::kernel::fd_table::*;
 use super::super::syscalls::syscalls::*;
 
-pub fn Eventfd2(task: &mut Task, initVal: i32, flags: i32) -> Result<i64> {
+pub fn Eventfd2(task: &mut Task, initVal: u32, flags: i32) -> Result<i64> {
     let allOps = EFD_SEMAPHORE | EFD_NONBLOCK | EFD_CLOEXEC;
 
     if flags & !allOps != 0 {
@@ -43,14 +43,14 @@ pub fn Eventfd2(task: &mut Task, initVal: i32, flags: i32) -> Result<i64> {
 }
 
 pub fn SysEventfd2(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
-    let initVal = args.arg0 as i32;
+    let initVal = args.arg0 as u32;
     let flags = args.arg1 as i32;
 
     return Eventfd2(task, initVal, flags)
 }
 
 pub fn SysEventfd(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
-    let initVal = args.arg0 as i32;
+    let initVal = args.arg0 as u32;
 
     return Eventfd2(task, initVal, 0)
 }
\ No newline at end of file
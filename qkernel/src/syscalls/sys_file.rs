@@ -27,16 +27,16 @@ use super::super::qlib::common::*;
 use super::super::qlib::linux_def::*;
 use super::super::qlib::path::*;
 use super::super::qlib::linux::fcntl::*;
+use super::super::qlib::linux::inotify::*;
 use super::super::fs::dirent::*;
 use super::super::fs::file::*;
 use super::super::fs::flags::*;
 use super::super::fs::inode::*;
 use super::super::fs::lock::*;
+use super::super::fs::tmpfs::tmpfs_memfd::*;
 use super::super::kernel::fd_table::*;
 use super::super::kernel::fasync::*;
-use super::super::kernel::pipe::reader::*;
-use super::super::kernel::pipe::writer::*;
-use super::super::kernel::pipe::reader_writer::*;
+use super::super::kernel::pipe::pipe::*;
 use super::super::syscalls::syscalls::*;
 use super::super::perflog::*;
 
@@ -111,7 +111,7 @@ pub fn SysOpenAt(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let flags = args.arg2 as u32;
     let mode = args.arg3 as u16 as u32;
 
-    if flags & Flags::O_CREAT as u32 != 0 {
+    if flags & Flags::O_CREAT as u32 != 0 && flags & Flags::O_PATH as u32 == 0 {
         let res = createAt(task, dirFd, addr, flags, FileMode(mode as u16))?;
         return Ok(res as i64)
     }
@@ -125,7 +125,7 @@ pub fn SysOpen(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let flags = args.arg1 as u32;
     let mode = args.arg2 as u16 as u32;
 
-    if flags & Flags::O_CREAT as u32 != 0 {
+    if flags & Flags::O_CREAT as u32 != 0 && flags & Flags::O_PATH as u32 == 0 {
         let res = createAt(task, ATType::AT_FDCWD, addr, flags, FileMode(mode as u16))?;
         return Ok(res as i64)
     }
@@ -154,9 +154,16 @@ pub fn openAt(task: &Task, dirFd: i32, addr: u64, flags: u32) -> Result<i32> {
     let resolve = (flags & Flags::O_NOFOLLOW as u32) == 0;
     let mut fd = -1;
 
+    let opath = flags & Flags::O_PATH as u32 != 0;
+
     fileOpOn(task, dirFd, &path, resolve, &mut |_root: &Dirent, d: &Dirent, _remainingTraversals: u32| -> Result<()> {
         let mut inode = d.Inode();
-        inode.CheckPermission(task, &PermMask::FromFlags(flags))?;
+
+        // O_PATH only needs search permission on the path, which the lookup
+        // has already checked.
+        if !opath {
+            d.CheckPermission(task, &PermMask::FromFlags(flags))?;
+        }
 
         if inode.StableAttr().IsSymlink() && !resolve {
             return Err(Error::SysError(SysErr::ELOOP))
@@ -187,7 +194,7 @@ pub fn openAt(task: &Task, dirFd: i32, addr: u64, flags: u32) -> Result<i32> {
             }
         }
 
-        if flags & Flags::O_TRUNC as u32 != 0 {
+        if fileFlags.Truncate {
             if inode.StableAttr().IsDir() {
                 return Err(Error::SysError(SysErr::EISDIR))
             }
@@ -245,8 +252,7 @@ pub fn mknodeAt(task: &Task, dirFd: i32, addr: u64, mode: FileMode) -> Result<()
     }
 
     return fileOpAt(task, dirFd, &path, &mut |root: &Dirent, d: &Dirent, name: &str, _remainingTraversals: u32| -> Result<()> {
-        let inode = d.Inode();
-        inode.CheckPermission(task, &PermMask {
+        d.CheckPermission(task, &PermMask {
             write: true,
             execute: true,
             ..Default::default()
@@ -400,8 +406,7 @@ pub fn createAt(task: &Task, dirFd: i32, addr: u64, flags: u32, mode: FileMode)
                 // File does not exist. Proceed with creation.
 
                 // Do we have write permissions on the parent?
-                let parentInode = parent.Inode();
-                parentInode.CheckPermission(task, &PermMask {
+                parent.CheckPermission(task, &PermMask {
                     write: true,
                     execute: true,
                     ..Default::default()
@@ -478,8 +483,7 @@ pub fn accessAt(task: &Task, dirFd: i32, addr: u64, resolve: bool, mode: u32) ->
             }
         }
 
-        let inode = d.Inode();
-        return inode.CheckPermission(task, &PermMask {
+        return d.CheckPermission(task, &PermMask {
             read: mode & R_OK != 0,
             write: mode & W_OK != 0,
             execute: mode & X_OK != 0,
@@ -518,34 +522,6 @@ pub fn Ioctl(task: &mut Task, fd: i32, request: u64, val: u64) -> Result<()> {
 
             return Ok(())
         }
-        IoCtlCmd::FIONBIO => {
-            let set: u32 = task.CopyInObj(val)?;
-
-            let mut flags = file.Flags();
-
-            if set != 0 {
-                flags.NonBlocking = true;
-            } else {
-                flags.NonBlocking = false;
-            }
-
-            file.SetFlags(task, flags.SettableFileFlags());
-            return Ok(())
-        }
-        IoCtlCmd::FIOASYNC => {
-            let set: u32 = task.CopyInObj(val)?;
-
-            let mut flags = file.Flags();
-
-            if set != 0 {
-                flags.Async = true;
-            } else {
-                flags.Async = false;
-            }
-
-            file.SetFlags(task, flags.SettableFileFlags());
-            return Ok(())
-        }
         IoCtlCmd::FIOSETOWN | IoCtlCmd::SIOCSPGRP => {
             let set : i32 = task.CopyInObj(val)?;
             FSetOwner(task, &file, set)?;
@@ -717,6 +693,10 @@ pub fn SysDup3(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let newfd = args.arg1 as i32;
     let flags = args.arg2 as u32;
 
+    if flags & !(Flags::O_CLOEXEC as u32) != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
     if oldfd == newfd {
         return Err(Error::SysError(SysErr::EINVAL));
     }
@@ -724,13 +704,20 @@ pub fn SysDup3(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     return Dup3(task, oldfd, newfd, flags)
 }
 
+// Dup3 makes newfd refer to the same open file as oldfd, sharing its offset
+// and status flags. A file already open at newfd is closed first.
 pub fn Dup3(task: &mut Task, oldfd: i32, newfd: i32, flags: u32) -> Result<i64> {
     let oldFile = task.GetFile(oldfd)?;
 
-    task.NewFDAt(newfd, &oldFile, &FDFlags {
+    let replaced = task.NewFDAt(newfd, &oldFile, &FDFlags {
         CloseOnExec: flags & Flags::O_CLOEXEC as u32 != 0
     })?;
 
+    // Like Linux, errors from closing the replaced file are not reported.
+    if let Some(file) = replaced {
+        file.Flush(task).ok();
+    }
+
     return Ok(newfd as i64)
 }
 
@@ -970,6 +957,20 @@ pub fn SysFcntl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
             FSetOwner(task, &file, val as i32)?;
             return Ok(0)
         }
+        Cmd::F_GETSIG => {
+            let a = file.Async(task, Some(FileAsync::default())).unwrap();
+            return Ok(a.Signal() as i64)
+        }
+        Cmd::F_SETSIG => {
+            let sig = val as i32;
+            if sig != 0 && !Signal(sig).IsValid() {
+                return Err(Error::SysError(SysErr::EINVAL))
+            }
+
+            let a = file.Async(task, Some(FileAsync::default())).unwrap();
+            a.SetSignal(sig, fd);
+            return Ok(0)
+        }
         Cmd::F_GETOWN_EX => {
             let addr = val;
             let owner = FGetOwnEx(task, &file);
@@ -1032,41 +1033,29 @@ pub fn SysFcntl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
             }
         }
         Cmd::F_GET_SEALS => {
-            panic!("Fcntl: F_GET_SEALS not implement")
+            let seals = GetSeals(&file)?;
+            return Ok(seals as i64)
         }
         Cmd::F_ADD_SEALS => {
-            panic!("Fcntl: F_ADD_SEALS not implement")
+            AddSeals(&file, val as u32)?;
+            return Ok(0)
         }
         Cmd::F_GETPIPE_SZ => {
-            let fops = file.FileOp.clone();
-
-            let pipe = if let Some(ops) = fops.as_any().downcast_ref::<Reader>() {
-                ops.pipe.clone()
-            } else if let Some(ops) = fops.as_any().downcast_ref::<Writer>() {
-                ops.pipe.clone()
-            } else if let Some(ops) = fops.as_any().downcast_ref::<ReaderWriter>() {
-                ops.pipe.clone()
-            } else {
-                return Err(Error::SysError(SysErr::EINVAL))
+            let pipe = match PipeOf(&file) {
+                None => return Err(Error::SysError(SysErr::EINVAL)),
+                Some(pipe) => pipe,
             };
 
             let n = pipe.PipeSize();
             return Ok(n as i64)
         }
         Cmd::F_SETPIPE_SZ => {
-            let fops = file.FileOp.clone();
-
-            let pipe = if let Some(ops) = fops.as_any().downcast_ref::<Reader>() {
-                ops.pipe.clone()
-            } else if let Some(ops) = fops.as_any().downcast_ref::<Writer>() {
-                ops.pipe.clone()
-            } else if let Some(ops) = fops.as_any().downcast_ref::<ReaderWriter>() {
-                ops.pipe.clone()
-            } else {
-                return Err(Error::SysError(SysErr::EINVAL))
+            let pipe = match PipeOf(&file) {
+                None => return Err(Error::SysError(SysErr::EINVAL)),
+                Some(pipe) => pipe,
             };
 
-            let n = pipe.SetPipeSize(val as i64)?;
+            let n = pipe.SetPipeSize(task, val as i64)?;
             return Ok(n as i64)
         }
         _ => {
@@ -1075,41 +1064,37 @@ pub fn SysFcntl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     }
 }
 
-const _FADV_NORMAL: i32 = 0;
-const _FADV_RANDOM: i32 = 1;
-const _FADV_SEQUENTIAL: i32 = 2;
-const _FADV_WILLNEED: i32 = 3;
-const _FADV_DONTNEED: i32 = 4;
-const _FADV_NOREUSE: i32 = 5;
-
 pub fn SysFadvise64(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let fd = args.arg0 as i32;
-    let _offset = args.arg1 as i64;
+    let offset = args.arg1 as i64;
     let len = args.arg2 as i64;
     let advice = args.arg3 as i32;
 
-    if len < 0 {
-        return Err(Error::SysError(SysErr::EINVAL))
-    }
-
     let file = task.GetFile(fd)?;
 
-    let inode = file.Dirent.Inode();
-    if inode.StableAttr().IsPipe() {
-        return Err(Error::SysError(SysErr::ESPIPE))
-    }
+    file.Fadvise(task, offset, len, advice)?;
+    return Ok(0)
+}
 
-    match advice {
-        _FADV_NORMAL |
-        _FADV_RANDOM |
-        _FADV_SEQUENTIAL |
-        _FADV_WILLNEED |
-        _FADV_DONTNEED |
-        _FADV_NOREUSE => {
-            return Ok(0)
-        }
-        _ => return Err(Error::SysError(SysErr::EINVAL))
+pub fn SysReadahead(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let fd = args.arg0 as i32;
+    let offset = args.arg1 as i64;
+    let count = args.arg2 as i64;
+
+    let file = task.GetFile(fd)?;
+    if !file.Flags().Read {
+        return Err(Error::SysError(SysErr::EBADF))
     }
+
+    // A count beyond i64 covers the rest of the file.
+    let count = if count < 0 {
+        0
+    } else {
+        count
+    };
+
+    file.Readahead(task, offset, count)?;
+    return Ok(0)
 }
 
 fn mkdirAt(task: &Task, dirFd: i32, addr: u64, mode: FileMode) -> Result<i64> {
@@ -1130,7 +1115,7 @@ fn mkdirAt(task: &Task, dirFd: i32, addr: u64, mode: FileMode) -> Result<i64> {
             Err(Error::SysError(SysErr::EACCES)) => return Err(Error::SysError(SysErr::EACCES)),
             _ => {
                 let perms = {
-                    inode.CheckPermission(task, &PermMask {
+                    d.CheckPermission(task, &PermMask {
                         write: true,
                         execute: true,
                         ..Default::default()
@@ -1219,7 +1204,7 @@ fn symlinkAt(task: &Task, newAddr: u64, dirFd: i32, oldAddr: u64) -> Result<u64>
             return Err(Error::SysError(SysErr::ENOTDIR))
         }
 
-        inode.CheckPermission(task, &PermMask {
+        d.CheckPermission(task, &PermMask {
             write: true,
             execute: true,
             ..Default::default()
@@ -1289,7 +1274,7 @@ fn linkAt(task: &Task, oldDirfd: i32, oldAddr: u64, newDirfd: i32, newAddr: u64,
                 return Err(Error::SysError(SysErr::ENOTDIR))
             }
 
-            inode.CheckPermission(task, &PermMask {
+            newParent.CheckPermission(task, &PermMask {
                 write: true,
                 execute: true,
                 ..Default::default()
@@ -1311,7 +1296,7 @@ fn linkAt(task: &Task, oldDirfd: i32, oldAddr: u64, newDirfd: i32, newAddr: u64,
                 return Err(Error::SysError(SysErr::ENOTDIR))
             }
 
-            inode.CheckPermission(task, &PermMask {
+            newParent.CheckPermission(task, &PermMask {
                 write: true,
                 execute: true,
                 ..Default::default()
@@ -1491,12 +1476,15 @@ pub fn SysTruncate(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
             return Err(Error::SysError(SysErr::EINVAL))
         }
 
-        inode.CheckPermission(task, &PermMask {
+        d.CheckPermission(task, &PermMask {
             write: true,
             ..Default::default()
         })?;
 
-        return inode.Truncate(task, d, len)
+        inode.Truncate(task, d, len)?;
+
+        d.InotifyEvent(IN_MODIFY, 0);
+        return Ok(())
     })?;
 
     return Ok(0)
@@ -1507,22 +1495,9 @@ pub fn SysFtruncate(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let len = args.arg1 as i64;
 
     let file = task.GetFile(fd)?;
-    if !file.Flags().Write {
-        return Err(Error::SysError(SysErr::EINVAL))
-    }
-
-    let mut inode = file.Dirent.Inode();
-    if !inode.StableAttr().IsFile() {
-        return Err(Error::SysError(SysErr::EINVAL))
-    }
-
-    if len < 0 {
-        return Err(Error::SysError(SysErr::EINVAL))
-    }
-
-    let dirent = file.Dirent.clone();
-    inode.Truncate(task, &dirent, len)?;
+    file.Truncate(task, len)?;
 
+    file.Dirent.InotifyEvent(IN_MODIFY, 0);
     return Ok(0)
 }
 
@@ -1533,6 +1508,8 @@ pub fn SysUmask(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 }
 
 fn chown(task: &Task, d: &Dirent, uid: UID, gid: GID) -> Result<i64> {
+    d.CheckWritableMount(task)?;
+
     let mut owner = FileOwner {
         UID: KUID(NO_ID),
         GID: KGID(NO_ID),
@@ -1660,13 +1637,15 @@ pub fn SysFchownat(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 
 fn utime(task: &Task, dirfd: i32, addr: u64, ts: &InterTimeSpec, resolve: bool) -> Result<i64> {
     let setTimestamp = &mut |_root: &Dirent, d: &Dirent, _remainingTraversals: u32| -> Result<()> {
+        d.CheckWritableMount(task)?;
+
         let mut inode = d.Inode();
         if !inode.CheckOwnership(task) {
             if (ts.ATimeOmit || !ts.ATimeSetSystemTime) && (ts.MTimeOmit || !ts.MTimeSetSystemTime) {
                 return Err(Error::SysError(SysErr::EPERM))
             }
 
-            inode.CheckPermission(task, &PermMask {
+            d.CheckPermission(task, &PermMask {
                 write: true,
                 ..Default::default()
             })?;
@@ -1887,85 +1866,29 @@ pub fn SysFallocate(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let dirent = file.Dirent.clone();
     inode.Allocate(task, &dirent, offset, len)?;
 
+    dirent.InotifyEvent(IN_MODIFY, 0);
     Ok(0)
 }
 
 pub fn SysFlock(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let fd = args.arg0 as i32;
-    let mut operation = args.arg1 as i32;
+    let operation = args.arg1 as i32;
 
     let file = task.GetFile(fd)?;
-
-    let nonblocking = operation & LibcConst::LOCK_NB as i32 != 0;
-    operation &= !(LibcConst::LOCK_NB as i32);
-
-    // flock(2):
-    // Locks created by flock() are associated with an open file table entry. This means that
-    // duplicate file descriptors (created by, for example, fork(2) or dup(2)) refer to the
-    // same lock, and this lock may be modified or released using any of these descriptors. Furthermore,
-    // the lock is released either by an explicit LOCK_UN operation on any of these duplicate
-    // descriptors, or when all such descriptors have been closed.
-    //
-    // If a process uses open(2) (or similar) to obtain more than one descriptor for the same file,
-    // these descriptors are treated independently by flock(). An attempt to lock the file using
-    // one of these file descriptors may be denied by a lock that the calling process has already placed via
-    // another descriptor.
-    //
-    // We use the File UniqueID as the lock UniqueID because it needs to reference the same lock across dup(2)
-    // and fork(2).
-    let lockUniqueId = file.UniqueId();
-
-    let rng = Range::New(0, MAX_RANGE);
-    let inode = file.Dirent.Inode();
-    let bsd = inode.lock().LockCtx.BSD.clone();
-
-    match operation as u64 {
-        LibcConst::LOCK_EX => {
-            if nonblocking {
-                // Since we're nonblocking we pass a nil lock.Blocker implementation.
-                if !bsd.LockRegion(task, lockUniqueId, LockType::WriteLock, &rng, false)? {
-                    return Err(Error::SysError(SysErr::EWOULDBLOCK))
-                }
-            } else {
-                // Because we're blocking we will pass the task to satisfy the lock.Blocker interface.
-                if !bsd.LockRegion(task, lockUniqueId, LockType::WriteLock, &rng, true)? {
-                    return Err(Error::SysError(SysErr::EINTR))
-                }
-            }
-        }
-        LibcConst::LOCK_SH => {
-            if nonblocking {
-                // Since we're nonblocking we pass a nil lock.Blocker implementation.
-                if !bsd.LockRegion(task, lockUniqueId, LockType::ReadLock, &rng, false)? {
-                    return Err(Error::SysError(SysErr::EWOULDBLOCK))
-                }
-            } else {
-                // Because we're blocking we will pass the task to satisfy the lock.Blocker interface.
-                if !bsd.LockRegion(task, lockUniqueId, LockType::ReadLock, &rng, true)? {
-                    return Err(Error::SysError(SysErr::EINTR))
-                }
-            }
-        }
-        LibcConst::LOCK_UN => {
-            bsd.UnlockRegion(task, lockUniqueId, &rng)
-        }
-        _ => {
-            // flock(2): EINVAL operation is invalid.
-            return Err(Error::SysError(SysErr::EINVAL))
-        }
-    }
-
+    file.Flock(task, operation)?;
     return Ok(0)
 }
 
-/*
-pub fn MemfdCreate(task: &Task, addr: u64, flags: u64) -> Result<u64> {
+// MemfdCreate implements the linux syscall memfd_create(2).
+pub fn SysMemfdCreate(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let addr = args.arg0 as u64;
+    let flags = args.arg1 as u32;
+
     let memfdPrefix     = "/memfd:";
     let memfdAllFlags   = MfdType::MFD_CLOEXEC | MfdType::MFD_ALLOW_SEALING;
+    // The name limit excludes the "memfd:" prefix.
     let memfdMaxNameLen = NAME_MAX - memfdPrefix.len() + 1;
 
-    let flags = flags as u32;
-
     if flags & !memfdAllFlags != 0 {
         return Err(Error::SysError(SysErr::EINVAL))
     }
@@ -1973,11 +1896,19 @@ pub fn MemfdCreate(task: &Task, addr: u64, flags: u64) -> Result<u64> {
     let allowSeals = flags & MfdType::MFD_ALLOW_SEALING != 0;
     let cloExec = flags & MfdType::MFD_CLOEXEC != 0;
 
-    let name = task.CopyInString(addr, PATH_MAX - memfdPrefix.len())?;
-
-    if name.len() > memfdMaxNameLen {
-        return Err(Error::SysError(SysErr::EINVAL))
+    let (name, err) = task.CopyInString(addr, memfdMaxNameLen + 1);
+    match err {
+        Err(Error::SysError(SysErr::ENAMETOOLONG)) => return Err(Error::SysError(SysErr::EINVAL)),
+        Err(e) => return Err(e),
+        _ => ()
     }
 
     let name = memfdPrefix.to_string() + &name;
-}*/
\ No newline at end of file
+    let file = NewMemfd(task, &name, allowSeals)?;
+
+    let fd = task.NewFDFrom(0, &file, &FDFlags {
+        CloseOnExec: cloExec,
+    })?;
+
+    return Ok(fd as i64)
+}
\ No newline at end of file
@@ -21,9 +21,11 @@ use super::super::memmgr::*;
 use super::super::memmgr::mm::*;
 use super::super::memmgr::syscalls::*;
 use super::super::qlib::linux_def::*;
+use super::super::qlib::linux::personality::*;
 use super::super::qlib::addr::*;
 use super::super::syscalls::syscalls::*;
 use super::super::fs::host::hostinodeop::*;
+use super::super::fs::tmpfs::tmpfs_memfd::*;
 
 pub fn SysMmap(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let addr = args.arg0 as u64;
@@ -68,10 +70,31 @@ pub fn SysMmap(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         opts.MLockMode = MLockMode::MlockEager;
     }
 
+    // READ_IMPLIES_EXEC makes readable mappings executable, except for files
+    // on noexec mounts, see mm/mmap.c:do_mmap.
+    let readImpliesExec = opts.Perms.Read() && task.Thread().Personality() & READ_IMPLIES_EXEC != 0;
+    if readImpliesExec && anon {
+        opts.Perms.SetExec();
+    }
+
     if !anon {
         let file = task.GetFile(fd)?;
         let flags = file.Flags();
 
+        // Files on noexec mounts can never be mapped executable, which
+        // mprotect checks per vma through MaxPerms, like VM_MAYEXEC.
+        let noExec = file.Dirent.Inode().lock().MountSource.lock().Flags.NoExec ||
+            task.mountNS.MountFlags(&file.Dirent).NoExec;
+        if noExec {
+            if opts.Perms.Exec() {
+                return Err(Error::SysError(SysErr::EPERM))
+            }
+
+            opts.MaxPerms.ClearExec();
+        } else if readImpliesExec {
+            opts.Perms.SetExec();
+        }
+
         // mmap unconditionally requires that the FD is readable.
         if !flags.Read {
             return Err(Error::SysError(SysErr::EACCES))
@@ -82,11 +105,31 @@ pub fn SysMmap(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
             opts.MaxPerms.ClearWrite();
         }
 
+        // A memfd sealed against writes can't be mapped shared and writable.
+        if shared {
+            if let Ok(seals) = GetSeals(&file) {
+                if seals & (SealFlag::F_SEAL_WRITE | SealFlag::F_SEAL_FUTURE_WRITE) != 0 {
+                    if opts.Perms.Write() {
+                        return Err(Error::SysError(SysErr::EPERM))
+                    }
+
+                    opts.MaxPerms.ClearWrite();
+                }
+            }
+        }
+
         opts.Mapping = Some(Arc::new(file.clone()));
 
         match file.Mappable() {
             Err(Error::ErrDevZeroMap) => {
-                opts.Mappable = None;
+                // A shared mapping of /dev/zero is shared anonymous memory,
+                // see drivers/char/mem.c:mmap_zero.
+                opts.Mappable = if shared {
+                    opts.Offset = 0;
+                    Some(HostInodeOp::NewMemfdIops(len as i64)?)
+                } else {
+                    None
+                };
                 opts.Hint = "/dev/zero".to_string();
             }
             Err(e) => return Err(e),
@@ -110,10 +153,19 @@ pub fn SysMprotect(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let len = args.arg1 as u64;
     let prot = args.arg2 as u64;
 
-    let accessType = AccessType(prot);
+    // PROT_GROWSUP is not supported on x86.
+    if prot & !(MmapProt::PROT_READ | MmapProt::PROT_WRITE | MmapProt::PROT_EXEC | MmapProt::PROT_GROWSDOWN) != 0 {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
+
+    let accessType = AccessType(prot & !MmapProt::PROT_GROWSDOWN);
     let growDown = prot & MmapProt::PROT_GROWSDOWN != 0;
 
-    match task.mm.MProtect(addr, len, &accessType, growDown) {
+    // READ_IMPLIES_EXEC only adds exec to the vmas which may be executable,
+    // see mm/mprotect.c:do_mprotect_pkey.
+    let readImpliesExec = accessType.Read() && task.Thread().Personality() & READ_IMPLIES_EXEC != 0;
+
+    match task.mm.MProtect(addr, len, &accessType, growDown, readImpliesExec) {
         Err(e) => return Err(e),
         _ => return Ok(0),
     }
@@ -134,7 +186,8 @@ pub fn SysBrk(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 
     match task.mm.Brk(task, addr) {
         Ok(addr) => return Ok(addr as i64),
-        Err(e) => return Err(e),
+        // "On failure, the system call returns the current break." - brk(2)
+        Err(_) => return Ok(task.mm.Brk(task, 0)? as i64),
     }
 }
 
@@ -164,7 +217,7 @@ pub fn SysMadvise(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     };
 
     match adv {
-        MAdviseOp::MADV_DONTNEED => {
+        MAdviseOp::MADV_DONTNEED | MAdviseOp::MADV_FREE => {
             task.mm.MAdvise(task, addr, length, adv)?;
         }
         MAdviseOp::MADV_HUGEPAGE | MAdviseOp::MADV_NOHUGEPAGE => {
@@ -179,6 +232,9 @@ pub fn SysMadvise(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         MAdviseOp::MADV_NORMAL | MAdviseOp::MADV_RANDOM | MAdviseOp::MADV_SEQUENTIAL | MAdviseOp::MADV_WILLNEED => {
             //task.mm.MAdvise(task, addr, length, adv)?;
         }
+        MAdviseOp::MADV_COLD | MAdviseOp::MADV_PAGEOUT => {
+            // Reclaim hints; memory is never swapped, so there is nothing to do.
+        }
         MAdviseOp::MADV_DONTFORK => {
             task.mm.SetDontFork(task, addr, length, true)?;
         }
@@ -376,7 +432,7 @@ pub fn SysMincore(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         Ok(r) => r
     };
 
-    let output = task.mm.MinCore(task, &range);
+    let output = task.mm.MinCore(task, &range)?;
     task.CopyOutSlice(&output, vec, output.len())?;
     return Ok(0)
 }
\ No newline at end of file
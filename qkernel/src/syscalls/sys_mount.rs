@@ -0,0 +1,171 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::string::String;
+
+use super::super::task::*;
+use super::super::qlib::common::*;
+use super::super::qlib::linux_def::*;
+use super::super::fs::dirent::*;
+use super::super::fs::filesystems::*;
+use super::super::syscalls::syscalls::*;
+use super::super::syscalls::sys_file::*;
+
+// Mount implements Linux syscall mount(2).
+pub fn SysMount(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let sourceAddr = args.arg0 as u64;
+    let targetAddr = args.arg1 as u64;
+    let typeAddr = args.arg2 as u64;
+    let mut flags = args.arg3 as u64;
+    let dataAddr = args.arg4 as u64;
+
+    // Ignore magic value that was required before Linux 2.4.
+    if flags & LibcConst::MS_MGC_MSK == LibcConst::MS_MGC_VAL {
+        flags &= !LibcConst::MS_MGC_MSK;
+    }
+
+    // Must have CAP_SYS_ADMIN in the mount namespace's associated user
+    // namespace.
+    let userns = task.mountNS.UserNamespace();
+    if !task.Creds().HasCapabilityIn(Capability::CAP_SYS_ADMIN, &userns) {
+        return Err(Error::SysError(SysErr::EPERM))
+    }
+
+    let (targetPath, _) = copyInPath(task, targetAddr, false)?;
+
+    // Propagation types are accepted but ignored: there is only one mount
+    // namespace, so there is nothing to propagate to.
+    const PROPAGATION_OPS: u64 = LibcConst::MS_SHARED | LibcConst::MS_PRIVATE |
+        LibcConst::MS_SLAVE | LibcConst::MS_UNBINDABLE;
+    if flags & PROPAGATION_OPS != 0 {
+        return Ok(0)
+    }
+
+    if flags & LibcConst::MS_MOVE != 0 {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
+
+    // Linux just allows passing any flags to mount(2) - it won't fail when
+    // unknown or unsupported flags are passed. Since we don't implement
+    // everything, we fail explicitly on flags that are unimplemented.
+    const UNSUPPORTED_FLAGS: u64 = LibcConst::MS_NODIRATIME | LibcConst::MS_STRICTATIME;
+    if flags & UNSUPPORTED_FLAGS != 0 {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
+
+    if flags & LibcConst::MS_REMOUNT != 0 {
+        let mns = task.mountNS.clone();
+        return fileOpOn(task, ATType::AT_FDCWD, &targetPath, true, &mut |_root: &Dirent, d: &Dirent, _remainingTraversals: u32| -> Result<()> {
+            let msrc = d.Inode().lock().MountSource.clone();
+            let mut superFlags = msrc.lock().Flags;
+            superFlags.ReadOnly = flags & LibcConst::MS_RDONLY != 0;
+            superFlags.NoAtime = flags & LibcConst::MS_NOATIME != 0;
+            superFlags.NoExec = flags & LibcConst::MS_NOEXEC != 0;
+            return mns.Remount(d, &superFlags, flags & LibcConst::MS_BIND != 0)
+        }).map(|_| 0);
+    }
+
+    if flags & LibcConst::MS_BIND != 0 {
+        let (sourcePath, _) = copyInPath(task, sourceAddr, false)?;
+
+        let mut source = None;
+        fileOpOn(task, ATType::AT_FDCWD, &sourcePath, true, &mut |_root: &Dirent, d: &Dirent, _remainingTraversals: u32| -> Result<()> {
+            source = Some(d.clone());
+            return Ok(())
+        })?;
+
+        let source = source.unwrap();
+        let recursive = flags & LibcConst::MS_REC != 0;
+        let mns = task.mountNS.clone();
+        return fileOpOn(task, ATType::AT_FDCWD, &targetPath, true, &mut |_root: &Dirent, d: &Dirent, _remainingTraversals: u32| -> Result<()> {
+            return mns.BindMount(task, d, &source, recursive)
+        }).map(|_| 0);
+    }
+
+    let (fsType, err) = task.CopyInString(typeAddr, MemoryDef::PAGE_SIZE as usize);
+    err?;
+
+    let (sourcePath, _) = copyInPath(task, sourceAddr, true)?;
+
+    let mut data = String::new();
+    if dataAddr != 0 {
+        // In Linux, a full page is always copied in regardless of null
+        // character placement, and the address is passed to each file system.
+        // Most file systems always treat this data as a string, though, and so
+        // do all of the ones we implement.
+        let (str, err) = task.CopyInString(dataAddr, MemoryDef::PAGE_SIZE as usize);
+        err?;
+        data = str;
+    }
+
+    let rsys = match FindFilesystem(&fsType) {
+        None => return Err(Error::SysError(SysErr::ENODEV)),
+        Some(rsys) => rsys,
+    };
+
+    if !rsys.lock().AllowUserMount() {
+        return Err(Error::SysError(SysErr::EPERM))
+    }
+
+    let superFlags = MountSourceFlags {
+        ReadOnly: flags & LibcConst::MS_RDONLY != 0,
+        NoAtime: flags & LibcConst::MS_NOATIME != 0,
+        NoExec: flags & LibcConst::MS_NOEXEC != 0,
+        ..Default::default()
+    };
+
+    let rootInode = match rsys.lock().Mount(task, &sourcePath, &superFlags, &data) {
+        Err(_) => return Err(Error::SysError(SysErr::EINVAL)),
+        Ok(inode) => inode,
+    };
+
+    let mns = task.mountNS.clone();
+    fileOpOn(task, ATType::AT_FDCWD, &targetPath, true, &mut |_root: &Dirent, d: &Dirent, _remainingTraversals: u32| -> Result<()> {
+        return mns.Mount(d, &rootInode)
+    })?;
+
+    return Ok(0)
+}
+
+// Umount2 implements Linux syscall umount2(2).
+pub fn SysUmount2(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let addr = args.arg0 as u64;
+    let flags = args.arg1 as i32 as u64;
+
+    // Forced unmount is not supported; MNT_EXPIRE doesn't make sense without
+    // it.
+    const UNSUPPORTED: u64 = LibcConst::MNT_FORCE | LibcConst::MNT_EXPIRE;
+    if flags & UNSUPPORTED != 0 {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
+
+    // Must have CAP_SYS_ADMIN in the mount namespace's associated user
+    // namespace.
+    let userns = task.mountNS.UserNamespace();
+    if !task.Creds().HasCapabilityIn(Capability::CAP_SYS_ADMIN, &userns) {
+        return Err(Error::SysError(SysErr::EPERM))
+    }
+
+    let (path, _) = copyInPath(task, addr, false)?;
+
+    let resolve = flags & LibcConst::UMOUNT_NOFOLLOW != LibcConst::UMOUNT_NOFOLLOW;
+    let detachOnly = flags & LibcConst::MNT_DETACH == LibcConst::MNT_DETACH;
+
+    let mns = task.mountNS.clone();
+    fileOpOn(task, ATType::AT_FDCWD, &path, resolve, &mut |_root: &Dirent, d: &Dirent, _remainingTraversals: u32| -> Result<()> {
+        return mns.Unmount(task, d, detachOnly)
+    })?;
+
+    return Ok(0)
+}
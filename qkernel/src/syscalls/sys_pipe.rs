@@ -22,7 +22,7 @@ use super::super::qlib::linux_def::*;
 use super::super::syscalls::syscalls::*;
 
 pub fn Pipe2(task: &mut Task, addr: u64, flags: i32) -> Result<i64> {
-    if flags & !(Flags::O_NONBLOCK | Flags::O_CLOEXEC) != 0 {
+    if flags & !(Flags::O_NONBLOCK | Flags::O_CLOEXEC | Flags::O_DIRECT) != 0 {
         return Err(Error::SysError(SysErr::EINVAL))
     }
 
@@ -241,20 +241,23 @@ pub fn SysPrctl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         }
         PR_GET_NAME => {
             let addr = args.arg1 as u64;
+            // the name is always copied out as TASK_COMM_LEN bytes, NUL
+            // terminated and padded, see kernel/sys.c.
             let mut buf : [u8; TASK_COMM_LEN] = [0; TASK_COMM_LEN];
             let name = thread.Name();
-            let mut len = if name.len() > TASK_COMM_LEN {
-                TASK_COMM_LEN
+            let len = if name.len() > TASK_COMM_LEN - 1 {
+                TASK_COMM_LEN - 1
             } else {
                 name.len()
             };
 
             buf[0..len].clone_from_slice(&name.as_bytes()[0..len]);
-            if len < TASK_COMM_LEN {
-                len += 1;
-            }
-
-            task.CopyOutSlice(&buf[0..len], addr, len)?;
+            task.CopyOutSlice(&buf, addr, TASK_COMM_LEN)?;
+        }
+        PR_GET_TID_ADDRESS => {
+            let addr = args.arg1 as u64;
+            let tidAddr = task.tidInfo.clear_child_tid.unwrap_or(0);
+            task.CopyOutObj(&tidAddr, addr)?;
         }
         PR_SET_MM => {
             if !thread.Credentials().HasCapability(Capability::CAP_SYS_RESOURCE) {
@@ -349,7 +352,6 @@ pub fn SysPrctl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         PR_SET_TIMERSLACK |
         PR_MCE_KILL |
         PR_MCE_KILL_GET |
-        PR_GET_TID_ADDRESS |
         PR_SET_CHILD_SUBREAPER |
         PR_GET_CHILD_SUBREAPER |
         PR_GET_THP_DISABLE |
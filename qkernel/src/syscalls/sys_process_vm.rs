@@ -0,0 +1,134 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+
+use super::super::qlib::common::*;
+use super::super::qlib::linux_def::*;
+use super::super::qlib::mem::block::*;
+use super::super::task::*;
+use super::super::memmgr::mm::*;
+use super::super::syscalls::syscalls::*;
+
+// ProcessVMReadv implements process_vm_readv(2).
+pub fn SysProcessVMReadv(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    return processVMRW(task, args, false)
+}
+
+// ProcessVMWritev implements process_vm_writev(2).
+pub fn SysProcessVMWritev(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    return processVMRW(task, args, true)
+}
+
+fn processVMRW(task: &mut Task, args: &SyscallArguments, isWrite: bool) -> Result<i64> {
+    let pid = args.arg0 as i32;
+    let lvec = args.arg1 as u64;
+    let liovcnt = args.arg2 as u64;
+    let rvec = args.arg3 as u64;
+    let riovcnt = args.arg4 as u64;
+    let flags = args.arg5 as u64;
+
+    // "The flags argument is currently unused and must be set to 0."
+    // - process_vm_readv(2)
+    if flags != 0 {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
+
+    if liovcnt > UIO_MAXIOV as u64 || riovcnt > UIO_MAXIOV as u64 {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
+
+    // The local buffers are read from by process_vm_writev and written to by
+    // process_vm_readv.
+    let localIovs = task.IovsFromAddr(lvec, liovcnt as usize)?;
+    task.CheckIOVecPermission(&localIovs, !isWrite)?;
+    if Iovs(&localIovs).Count() == 0 {
+        return Ok(0)
+    }
+
+    let remoteIovs = task.IovsFromAddr(rvec, riovcnt as usize)?;
+
+    let thread = task.Thread();
+    let target = match thread.PIDNamespace().TaskWithID(pid) {
+        None => return Err(Error::SysError(SysErr::ESRCH)),
+        Some(t) => t,
+    };
+
+    // "Permission to read from or write to another process is governed by a
+    // ptrace access mode PTRACE_MODE_ATTACH_REALCREDS check." - process_vm_readv(2)
+    if !thread.CanTrace(&target) {
+        return Err(Error::SysError(SysErr::EPERM))
+    }
+
+    let remote = target.MemoryManager();
+
+    let mut locals: &[IoVec] = &localIovs;
+    let mut tmp;
+    let mut count = 0;
+    for iov in &remoteIovs {
+        let left = Iovs(locals).Count();
+        if left == 0 {
+            break;
+        }
+
+        if iov.len == 0 {
+            continue;
+        }
+
+        let len = core::cmp::min(iov.len, left);
+
+        // "If a partial read/write occurs, the transfer stops at the first
+        // iovec element that could not be completely transferred, and the
+        // number of bytes transferred is returned."
+        // - process_vm_readv(2)
+        //
+        // The remote pages are referenced until the copy is done.
+        let mut phyIovs = Vec::new();
+        let n = match remote.V2PRemote(task, iov.start, len as u64, &mut phyIovs, isWrite) {
+            Err(e) => {
+                if count == 0 {
+                    return Err(e)
+                }
+                break;
+            }
+            Ok(n) => n as usize,
+        };
+
+        if n == 0 {
+            if count == 0 {
+                return Err(Error::SysError(SysErr::EFAULT))
+            }
+            break;
+        }
+
+        // Copy directly between the remote pages and the local buffers.
+        let copied = if isWrite {
+            task.mm.CopyIovsOutFromIovs(task, locals, &phyIovs)
+        } else {
+            task.mm.CopyIovsOutToIovs(task, &phyIovs, locals)
+        };
+        MemoryManager::DerefPages(&phyIovs);
+        let copied = copied?;
+
+        count += copied;
+        tmp = Iovs(locals).DropFirst(copied);
+        locals = &tmp;
+
+        if n < len {
+            break;
+        }
+    }
+
+    return Ok(count as i64)
+}
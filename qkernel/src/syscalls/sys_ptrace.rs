@@ -0,0 +1,167 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::boxed::Box;
+
+use super::super::task::*;
+use super::super::qlib::common::*;
+use super::super::qlib::linux_def::*;
+use super::super::qlib::task_mgr::*;
+use super::super::syscalls::syscalls::*;
+use super::super::threadmgr::thread::*;
+use super::super::arch::x86_64::arch_x86::*;
+use super::super::SignalDef::*;
+
+// Ptrace implements linux system call ptrace(2).
+//
+// Only the signal-delivery-stop subset is supported: a tracer can attach,
+// wait for the tracee's stops, inspect and replace the pending signal and the
+// registers, and resume or detach the tracee. Memory access returns EIO.
+pub fn SysPtrace(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let req = args.arg0;
+    let pid = args.arg1 as ThreadID;
+    let addr = args.arg2;
+    let data = args.arg3;
+
+    let t = task.Thread();
+
+    // PTRACE_TRACEME ignores all other arguments.
+    if req == LibcConst::PTRACE_TRACEME {
+        t.PtraceTraceme()?;
+        return Ok(0)
+    }
+
+    let pidns = t.PIDNamespace();
+    let target = match pidns.TaskWithID(pid) {
+        None => return Err(Error::SysError(SysErr::ESRCH)),
+        Some(target) => target,
+    };
+
+    if req == LibcConst::PTRACE_ATTACH {
+        t.PtraceAttach(&target)?;
+        return Ok(0)
+    }
+
+    // Requests which read from the tracer's memory do it before any locks
+    // are taken.
+    let mut newInfo = None;
+    let mut newRegs = None;
+    match req {
+        LibcConst::PTRACE_SETSIGINFO => {
+            let info: SignalInfo = task.CopyInObj(data)?;
+            newInfo = Some(info);
+        }
+        LibcConst::PTRACE_SETREGS => {
+            let regs: PtraceRegs = task.CopyInObj(data)?;
+            newRegs = Some(regs);
+        }
+        LibcConst::PTRACE_PEEKUSR | LibcConst::PTRACE_POKEUSR => {
+            if addr & 7 != 0 || addr >= USER_AREA_SIZE {
+                return Err(Error::SysError(SysErr::EIO))
+            }
+        }
+        _ => (),
+    }
+
+    let owner = pidns.lock().owner.clone();
+    let l = owner.WriteLock();
+
+    // All other ptrace requests require that t is the tracer of target.
+    if target.Tracer() != Some(t.clone()) {
+        return Err(Error::SysError(SysErr::ESRCH))
+    }
+
+    if req == LibcConst::PTRACE_KILL {
+        // "The operation is deprecated; do not use it! ... it may not work
+        // if the tracee is not in signal-delivery-stop." - ptrace(2)
+        target.ptraceUnstop(Signal(Signal::SIGKILL)).ok();
+        return Ok(0)
+    }
+
+    // ... and that target is in a ptrace-stop.
+    if !target.ptraceStopped() {
+        return Err(Error::SysError(SysErr::ESRCH))
+    }
+
+    match req {
+        LibcConst::PTRACE_CONT => {
+            target.ptraceUnstop(Signal(data as i32))?;
+        }
+        LibcConst::PTRACE_DETACH => {
+            t.ptraceDetachLocked(&target, Signal(data as i32))?;
+        }
+        LibcConst::PTRACE_GETSIGINFO | LibcConst::PTRACE_SETSIGINFO => {
+            let info = {
+                let tg = target.ThreadGroup();
+                let lock = tg.lock().signalLock.clone();
+                let _s = lock.lock();
+
+                let info = target.lock().ptraceSiginfo.clone();
+                let info = match info {
+                    None => return Err(Error::SysError(SysErr::EINVAL)),
+                    Some(info) => info,
+                };
+
+                match newInfo {
+                    None => info,
+                    Some(newInfo) => {
+                        target.lock().ptraceSiginfo = Some(Box::new(newInfo));
+                        return Ok(0)
+                    }
+                }
+            };
+
+            core::mem::drop(l);
+            task.CopyOutObj(&*info, data)?;
+        }
+        LibcConst::PTRACE_GETREGS => {
+            let regs = PtraceRegs::New(TraceeRegs(&target), TraceeTask(&target).context.fs);
+            core::mem::drop(l);
+            task.CopyOutObj(&regs, data)?;
+        }
+        LibcConst::PTRACE_SETREGS => {
+            let tracee = TraceeTask(&target);
+            tracee.context.fs = newRegs.unwrap().Apply(TraceeRegs(&target))?;
+        }
+        LibcConst::PTRACE_PEEKUSR => {
+            let regs = PtraceRegs::New(TraceeRegs(&target), TraceeTask(&target).context.fs);
+            let val = regs.Peek(addr);
+            core::mem::drop(l);
+            task.CopyOutObj(&val, data)?;
+        }
+        LibcConst::PTRACE_POKEUSR => {
+            let tracee = TraceeTask(&target);
+            let mut regs = PtraceRegs::New(TraceeRegs(&target), tracee.context.fs);
+            regs.Poke(addr, data);
+            tracee.context.fs = regs.Apply(TraceeRegs(&target))?;
+        }
+        _ => {
+            // todo: support memory and option requests
+            return Err(Error::SysError(SysErr::EIO))
+        }
+    }
+
+    return Ok(0)
+}
+
+// TraceeTask returns the task of a ptrace-stopped tracee, whose saved user
+// state stays put until it's resumed.
+fn TraceeTask(target: &Thread) -> &'static mut Task {
+    let taskId = target.lock().taskId;
+    return TaskId::New(taskId).GetTask();
+}
+
+fn TraceeRegs(target: &Thread) -> &'static mut PtRegs {
+    return TraceeTask(target).GetPtRegs();
+}
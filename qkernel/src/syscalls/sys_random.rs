@@ -23,7 +23,7 @@ pub fn SysGetRandom(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let mut length = args.arg1 as u32;
     let flags = args.arg2 as i32;
 
-    // Flags are checked for validity but otherwise ignored. See above.
+    // The flags are passed on to the host getrandom(2).
     if flags & !(_GRND_NONBLOCK | _GRND_RANDOM) != 0 {
         return Err(Error::SysError(SysErr::EINVAL))
     }
@@ -23,6 +23,16 @@ use super::super::qlib::linux_def::*;
 use super::super::syscalls::syscalls::*;
 use super::super::perflog::*;
 
+// CheckNotDir fails with EISDIR for directories, which can only be read with
+// getdents(2).
+fn CheckNotDir(file: &File) -> Result<()> {
+    if file.Dirent.Inode().StableAttr().IsDir() {
+        return Err(Error::SysError(SysErr::EISDIR))
+    }
+
+    return Ok(())
+}
+
 pub fn SysRead(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let fd = args.arg0 as i32;
     let addr = args.arg1 as u64;
@@ -43,6 +53,8 @@ pub fn Read(task: &Task, fd: i32, addr: u64, size: i64) -> Result<i64> {
         return Err(Error::SysError(SysErr::EBADF))
     }
 
+    CheckNotDir(&file)?;
+
     if size < 0 {
         return Err(Error::SysError(SysErr::EINVAL))
     }
@@ -96,6 +108,8 @@ pub fn Pread64(task: &Task, fd: i32, addr: u64, size: i64, offset: i64) -> Resul
         return Err(Error::SysError(SysErr::EBADF))
     }
 
+    CheckNotDir(&file)?;
+
     if size < 0 {
         return Err(Error::SysError(SysErr::EINVAL))
     }
@@ -126,6 +140,8 @@ pub fn Readv(task: &Task, fd: i32, addr: u64, iovcnt: i32) -> Result<i64> {
         return Err(Error::SysError(SysErr::EBADF))
     }
 
+    CheckNotDir(&file)?;
+
     if iovcnt < 0 {
         return Err(Error::SysError(SysErr::EINVAL))
     }
@@ -196,6 +212,8 @@ pub fn Preadv(task: &Task, fd: i32, addr: u64, iovcnt: i32, offset: i64) -> Resu
         return Err(Error::SysError(SysErr::EBADF))
     }
 
+    CheckNotDir(&file)?;
+
     if iovcnt < 0 {
         return Err(Error::SysError(SysErr::EINVAL))
     }
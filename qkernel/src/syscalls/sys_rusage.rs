@@ -91,8 +91,10 @@ pub fn SysGetrusage(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 pub fn SysTimes(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let addr = args.arg0 as u64;
 
+    // times returns the clock ticks since boot, and the buffer is optional.
+    let ticks = ClockTFromDuration(Task::MonoTimeNow().Nanoseconds());
     if addr == 0 {
-        return Ok(0)
+        return Ok(ticks)
     }
 
     let tg = task.Thread().ThreadGroup();
@@ -108,5 +110,5 @@ pub fn SysTimes(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 
     //*task.GetTypeMut(addr)? = r;
     task.CopyOutObj(&r, addr)?;
-    return Ok(0)
+    return Ok(ticks)
 }
\ No newline at end of file
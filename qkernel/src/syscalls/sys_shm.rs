@@ -0,0 +1,159 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::super::qlib::common::*;
+use super::super::qlib::linux_def::*;
+use super::super::qlib::linux::ipc::*;
+use super::super::qlib::linux::shm::*;
+use super::super::task::*;
+use super::super::kernel::shm::*;
+use super::super::syscalls::syscalls::*;
+
+// shmPID returns the pid of the task's thread group, as recorded in the
+// cpid and lpid of segments.
+fn shmPID(task: &Task) -> i32 {
+    let t = task.Thread();
+    let pidns = t.PIDNamespace();
+    return pidns.IDOfThreadGroup(&t.ThreadGroup());
+}
+
+// Shmget implements shmget(2).
+pub fn SysShmget(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let key = args.arg0 as i32;
+    let size = args.arg1 as u64;
+    let flag = args.arg2 as i32;
+
+    let private = key == IPC_PRIVATE;
+    let create = flag & IPC_CREAT as i32 == IPC_CREAT as i32;
+    let exclusive = flag & IPC_EXCL as i32 == IPC_EXCL as i32;
+    let mode = FileMode((flag & 0o777) as u16);
+
+    let pid = shmPID(task);
+    let r = task.ipcns.ShmRegistry();
+    let segment = r.FindOrCreate(task, pid, key, size, &mode, private, create, exclusive)?;
+    return Ok(segment.ID() as i64)
+}
+
+// findSegment returns the segment identified by id.
+fn findSegment(task: &Task, id: i32) -> Result<Shm> {
+    let r = task.ipcns.ShmRegistry();
+    match r.FindByID(id) {
+        // No segment with provided id.
+        None => return Err(Error::SysError(SysErr::EINVAL)),
+        Some(s) => return Ok(s),
+    }
+}
+
+// Shmat implements shmat(2).
+pub fn SysShmat(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let id = args.arg0 as i32;
+    let mut addr = args.arg1 as u64;
+    let flag = args.arg2 as i32;
+
+    if addr != 0 {
+        if flag & SHM_RND as i32 != 0 {
+            // SHMLBA is the page size on x86_64.
+            addr &= !(MemoryDef::PAGE_SIZE - 1);
+        } else if addr & (MemoryDef::PAGE_SIZE - 1) != 0 {
+            return Err(Error::SysError(SysErr::EINVAL))
+        }
+    }
+
+    let remap = flag & SHM_REMAP as i32 != 0;
+    if remap && addr == 0 {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
+
+    let segment = findSegment(task, id)?;
+
+    let mut opts = segment.ConfigureAttach(task, addr, &AttachOpts {
+        Execute: flag & SHM_EXEC as i32 != 0,
+        ReadOnly: flag & SHM_RDONLY as i32 != 0,
+        Remap: remap,
+    })?;
+
+    match task.mm.MMap(task, &mut opts) {
+        Ok(addr) => {
+            segment.Attach(task, shmPID(task));
+            return Ok(addr as i64)
+        }
+        // shmat(2) fails with EINVAL when the range at addr is in use and
+        // SHM_REMAP isn't given.
+        Err(Error::SysError(SysErr::ENOMEM)) if addr != 0 && !remap => {
+            return Err(Error::SysError(SysErr::EINVAL))
+        }
+        Err(e) => return Err(e),
+    }
+}
+
+// Shmdt implements shmdt(2).
+pub fn SysShmdt(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let addr = args.arg0 as u64;
+
+    let r = task.ipcns.ShmRegistry();
+    let segment = task.mm.DetachShm(task, addr, &r)?;
+    segment.Detach(task, shmPID(task));
+    return Ok(0)
+}
+
+// Shmctl implements shmctl(2).
+pub fn SysShmctl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let id = args.arg0 as i32;
+    let cmd = args.arg1 as i32;
+    let buf = args.arg2 as u64;
+
+    let r = task.ipcns.ShmRegistry();
+
+    match cmd {
+        SHM_INFO => {
+            let info = r.ShmInfo();
+            task.CopyOutObj(&info, buf)?;
+            return Ok(r.HighestIndex() as i64)
+        }
+        IPC_INFO => {
+            let params = r.IPCInfo();
+            task.CopyOutObj(&params, buf)?;
+            return Ok(r.HighestIndex() as i64)
+        }
+        _ => (),
+    }
+
+    let segment = findSegment(task, id)?;
+
+    match cmd {
+        SHM_STAT | IPC_STAT => {
+            let stat = segment.IPCStat(task)?;
+            task.CopyOutObj(&stat, buf)?;
+            if cmd == SHM_STAT {
+                return Ok(segment.ID() as i64)
+            }
+            return Ok(0)
+        }
+        IPC_SET => {
+            let ds: ShmidDS = task.CopyInObj(buf)?;
+            segment.Set(task, &ds)?;
+            return Ok(0)
+        }
+        IPC_RMID => {
+            segment.MarkDestroyed(task)?;
+            return Ok(0)
+        }
+        SHM_LOCK | SHM_UNLOCK => {
+            // The segment memory is never swapped out, so there is nothing
+            // to lock.
+            return Ok(0)
+        }
+        _ => return Err(Error::SysError(SysErr::EINVAL)),
+    }
+}
@@ -21,6 +21,7 @@ use super::super::threadmgr::thread::*;
 use super::super::threadmgr::threads::*;
 use super::super::kernel::fd_table::*;
 use super::super::kernel::signalfd::*;
+use super::super::kernel::pidfd::*;
 use super::super::SignalDef::*;
 use super::sys_poll::*;
 
@@ -190,10 +191,11 @@ pub fn SysRtSigtimedwait(task: &mut Task, args: &SyscallArguments) -> Result<i64
 
     let mask = CopyInSigSet(task, sigset, sigsetsize as usize)?;
 
+    // A NULL timeout waits forever, as sigwaitinfo(2) does.
     let timeout = if timespec != 0 {
-        CopyTimespecIntoDuration(task, timespec)?
+        Some(CopyTimespecIntoDuration(task, timespec)?)
     } else {
-        core::i64::MAX
+        None
     };
 
     let thread = task.Thread();
@@ -307,6 +309,121 @@ pub fn SysRtTgsigqueueinfo(task: &mut Task, args: &SyscallArguments) -> Result<i
     return Ok(0)
 }
 
+// PidfdOpen implements linux syscall pidfd_open(2).
+pub fn SysPidfdOpen(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let pid = args.arg0 as i32;
+    let flags = args.arg1 as i32;
+
+    if flags & !PIDFD_NONBLOCK != 0 || pid <= 0 {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
+
+    let t = task.Thread();
+    let pidns = t.PIDNamespace();
+    let target = match pidns.TaskWithID(pid) {
+        None => return Err(Error::SysError(SysErr::ESRCH)),
+        Some(t) => t,
+    };
+
+    // A pidfd refers to a process, so pid must be a thread group leader.
+    let tg = target.ThreadGroup();
+    if tg.Leader() != Some(target) {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
+
+    let file = PidFdFileOperations::NewPidFdFile(task, &tg);
+    file.SetFlags(task, SettableFileFlags{
+        NonBlocking: flags & PIDFD_NONBLOCK != 0,
+        ..Default::default()
+    });
+
+    // "The close-on-exec flag is set on the file descriptor." - pidfd_open(2)
+    let fd = task.NewFDFrom(0, &file, &FDFlags {
+        CloseOnExec: true,
+    })?;
+
+    return Ok(fd as i64)
+}
+
+// PidfdSendSignal implements linux syscall pidfd_send_signal(2).
+pub fn SysPidfdSendSignal(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let pidfd = args.arg0 as i32;
+    let sig = args.arg1 as i32;
+    let infoAddr = args.arg2 as u64;
+    let flags = args.arg3 as u32;
+
+    if flags != 0 {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
+
+    let file = task.GetFile(pidfd)?;
+    let fops = file.FileOp.clone();
+    let pidfdOps = match fops.as_any().downcast_ref::<PidFdFileOperations>() {
+        None => return Err(Error::SysError(SysErr::EBADF)),
+        Some(ops) => ops,
+    };
+
+    let t = task.Thread();
+    let pidns = t.PIDNamespace();
+    let creds = t.Credentials();
+
+    // This loops to handle the race with execve described in Kill.
+    loop {
+        let tg = match pidfdOps.ThreadGroup() {
+            None => return Err(Error::SysError(SysErr::ESRCH)),
+            Some(tg) => tg,
+        };
+
+        // The process must be visible in the caller's PID namespace.
+        if pidns.IDOfThreadGroup(&tg) == 0 {
+            return Err(Error::SysError(SysErr::EINVAL))
+        }
+
+        let target = match tg.Leader() {
+            None => return Err(Error::SysError(SysErr::ESRCH)),
+            Some(t) => t,
+        };
+
+        let info = if infoAddr == 0 {
+            // Without info the signal is sent as kill(2) would send it.
+            let mut info = SignalInfo {
+                Signo: sig,
+                Code: SignalInfo::SIGNAL_INFO_USER,
+                ..Default::default()
+            };
+
+            let sigRt = info.SigRt();
+            sigRt.pid = pidns.IDOfTask(&t);
+            let tuserns = target.UserNamespace();
+            sigRt.uid = creds.lock().RealKUID.In(&tuserns).OrOverflow().0;
+            info
+        } else {
+            // The info is checked as rt_sigqueueinfo(2) checks it, except
+            // that its Signo must match sig.
+            let info : SignalInfo = task.CopyInObj(infoAddr)?;
+            if info.Signo != sig {
+                return Err(Error::SysError(SysErr::EINVAL))
+            }
+
+            if (info.Code >= 0 || info.Code == SignalInfo::SIGNAL_INFO_TKILL) && tg != t.ThreadGroup() {
+                return Err(Error::SysError(SysErr::EPERM))
+            }
+
+            info
+        };
+
+        if !mayKill(&t, &target, Signal(sig)) {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        match target.SendGroupSignal(&info) {
+            Err(Error::SysError(SysErr::ESRCH)) => (),
+            Ok(()) => return Ok(0),
+            Err(e) => return Err(e)
+        }
+    }
+}
+
 pub fn SysKill(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let pid = args.arg0 as i32;
     let sig = args.arg1 as i32;
@@ -518,6 +635,12 @@ pub fn SysTkill(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         Some(t) => t,
     };
 
+    // Like check_kill_permission, an invalid signal is EINVAL even when the
+    // caller isn't allowed to signal the target. Signal 0 only probes it.
+    if sig < 0 || sig > Signal::SIGNAL_MAX {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
     if !mayKill(&t, &target, Signal(sig)) {
         return Err(Error::SysError(SysErr::EPERM));
     }
@@ -540,26 +663,27 @@ pub fn SysTgkill(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 
     let t = task.Thread();
     let pidns = t.PIDNamespace();
-    let targetTG = match pidns.ThreadGroupWithID(tgid) {
-        None => return Err(Error::SysError(SysErr::ESRCH)),
-        Some(tg) => tg.clone(),
-    };
-
     let target = match pidns.TaskWithID(tid) {
         None => return Err(Error::SysError(SysErr::ESRCH)),
         Some(t) => t,
     };
 
-    if target.ThreadGroup() != targetTG {
+    if pidns.IDOfThreadGroup(&target.ThreadGroup()) != tgid {
         return Err(Error::SysError(SysErr::ESRCH))
     }
 
+    if sig < 0 || sig > Signal::SIGNAL_MAX {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
     if !mayKill(&t, &target, Signal(sig)) {
         return Err(Error::SysError(SysErr::EPERM));
     }
 
+    // The group is checked again as the signal is queued, in case the target
+    // exited or execed in the meantime.
     let sigInfo = TkillSignal(&t, &target, Signal(sig));
-    target.SendSignal(&sigInfo)?;
+    target.Tgkill(&pidns, tgid, &sigInfo)?;
     return Ok(0)
 }
 
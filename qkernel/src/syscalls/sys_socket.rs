@@ -25,6 +25,7 @@ use super::super::fs::flags::*;
 use super::super::fs::file::*;
 use super::super::kernel::fd_table::*;
 use super::super::syscalls::syscalls::*;
+use super::super::syscalls::sys_write::*;
 use super::super::kernel::time::*;
 use super::super::qlib::linux::time::*;
 //use super::super::qlib::linux::socket::*;
@@ -355,26 +356,15 @@ pub fn SysGetSockName(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 
     let sock = file.FileOp.clone();
 
-    let mut buf: [u8; MAX_ADDR_LEN as usize] = [0; MAX_ADDR_LEN as usize];
     let len = task.CopyInObj::<i32>(addrlen)?;
-
-    let len = if len > MAX_ADDR_LEN as i32 {
-        MAX_ADDR_LEN as i32
-    } else {
-        len
-    };
-
-    let mut outputlen = sock.GetSockName(task, &mut buf[0..len as usize])? as usize;
-    //*(task.GetTypeMut::<i32>(addrlen)?) = outputlen as i32;
-    task.CopyOutObj(&(outputlen as i32), addrlen)?;
-
-    if len < outputlen as i32 {
-        outputlen = len as usize;
+    if len < 0 {
+        return Err(Error::SysError(SysErr::EINVAL))
     }
 
-    task.CopyOutSlice(&buf[..outputlen as usize], addr, outputlen as usize)?;
+    let mut buf: [u8; MAX_ADDR_LEN as usize] = [0; MAX_ADDR_LEN as usize];
+    let outputlen = sock.GetSockName(task, &mut buf)? as usize;
 
-    return Ok(0)
+    return copyOutAddr(task, &buf, outputlen, len as usize, addr, addrlen)
 }
 
 pub fn SysGetPeerName(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
@@ -385,19 +375,27 @@ pub fn SysGetPeerName(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let file = task.GetFile(fd)?;
 
     let sock = file.FileOp.clone();
-    let mut buf: [u8; MAX_ADDR_LEN as usize] = [0; MAX_ADDR_LEN as usize];
 
-    let mut outputlen = sock.GetPeerName(task, &mut buf)? as usize;
+    let len = task.CopyInObj::<i32>(addrlen)?;
+    if len < 0 {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
 
-    //info!("SysGetPeerName buf is {}", &buf[..outputlen as usize]);
+    let mut buf: [u8; MAX_ADDR_LEN as usize] = [0; MAX_ADDR_LEN as usize];
+    let outputlen = sock.GetPeerName(task, &mut buf)? as usize;
 
-    let len = task.CopyInObj::<i32>(addrlen)?;
-    if len < outputlen as i32 {
-        outputlen = len as usize;
+    return copyOutAddr(task, &buf, outputlen, len as usize, addr, addrlen)
+}
+
+// copyOutAddr copies an address of outputlen bytes to a user buffer of len
+// bytes, truncating it if the buffer is too small, and stores the full
+// address length in addrlen as getsockname(2) and getpeername(2) require.
+fn copyOutAddr(task: &Task, buf: &[u8], outputlen: usize, len: usize, addr: u64, addrlen: u64) -> Result<i64> {
+    let n = core::cmp::min(core::cmp::min(outputlen, len), buf.len());
+    if n > 0 {
+        task.CopyOutSlice(&buf[..n], addr, n)?;
     }
 
-    task.CopyOutSlice(&buf[..outputlen as usize], addr, addrlen as usize)?;
-    //*(task.GetTypeMut::<i32>(addrlen)?) = outputlen as i32;
     task.CopyOutObj(&(outputlen as i32), addrlen)?;
     return Ok(0)
 }
@@ -420,11 +418,6 @@ fn recvSingleMsg(task: &Task, sock: &Arc<FileOperations>, msgPtr: u64, flags: i3
 
     let mut dst = task.IovsFromAddr(msg.iov, msg.iovLen)?;
 
-    if flags & MsgType::MSG_ERRQUEUE != 0 {
-        // Pretend we have an empty error queue.
-        return Err(Error::SysError(SysErr::EAGAIN))
-    }
-
     // Fast path when no control message nor name buffers are provided.
     if msg.msgControlLen == 0 && msg.nameLen == 0 {
         let (n, mut mflags, _ , controlMessageBuffer) = sock.RecvMsg(task, &mut dst, flags, deadline, false, 0)?;
@@ -433,7 +426,10 @@ fn recvSingleMsg(task: &Task, sock: &Arc<FileOperations>, msgPtr: u64, flags: i3
             mflags |= MsgType::MSG_CTRUNC;
         }
 
+        // msg_flags must still be reported, e.g. MSG_TRUNC or the MSG_CTRUNC
+        // for SCM_RIGHTS that had nowhere to go.
         msg.msgFlags = mflags;
+        task.CopyOutObj(&msg, msgPtr)?;
         return Ok(n as i64)
     }
 
@@ -759,8 +755,8 @@ pub fn SysSendMsg(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         flags |= MsgType::MSG_DONTWAIT
     }
 
-    let res = sendSingleMsg(task, &sock, msgPtr, flags, deadline)?;
-    return Ok(res)
+    let res = sendSingleMsg(task, &sock, msgPtr, flags, deadline);
+    return HandleEPIPE(task, res, flags & MsgType::MSG_NOSIGNAL != 0)
 }
 
 pub fn SysSendMMsg(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
@@ -795,7 +791,8 @@ pub fn SysSendMMsg(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     //let msgs = task.GetSliceMut::<MMsgHdr>(msgPtr, vlen as usize)?;
     let mut msgs = task.CopyInVec::<MMsgHdr>(msgPtr, vlen as usize)?;
     for i in 0..vlen as usize {
-        res = sendSingleMsg(task, &sock, &(msgs[i].msgHdr) as *const MsgHdr as u64, flags, deadline)?;
+        let sent = sendSingleMsg(task, &sock, &(msgs[i].msgHdr) as *const MsgHdr as u64, flags, deadline);
+        res = HandleEPIPE(task, sent, flags & MsgType::MSG_NOSIGNAL != 0)?;
 
         if res < 0 {
             break;
@@ -862,8 +859,8 @@ pub fn SysSendTo(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         flags |= MsgType::MSG_DONTWAIT;
     }
 
-    let res = sock.SendMsg(task, &iovs, flags, &mut pMsg, deadline)?;
-    return Ok(res);
+    let res = sock.SendMsg(task, &iovs, flags, &mut pMsg, deadline);
+    return HandleEPIPE(task, res, flags & MsgType::MSG_NOSIGNAL != 0);
 }
 
 
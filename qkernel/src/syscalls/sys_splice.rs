@@ -18,10 +18,14 @@ use super::super::kernel::waiter::*;
 use super::super::kernel::waiter::qlock::*;
 use super::super::fs::attr::*;
 use super::super::fs::file::*;
+use super::super::kernel::pipe::pipe::*;
 use super::super::task::*;
 use super::super::qlib::common::*;
 use super::super::qlib::linux_def::*;
+use super::super::qlib::linux::inotify::*;
+use super::super::qlib::mem::block::*;
 use super::super::syscalls::syscalls::*;
+use super::super::syscalls::sys_write::HandleEPIPE;
 
 // Splice moves data to this file, directly from another.
 //
@@ -71,7 +75,7 @@ pub fn Splice(task: &Task, dst: &File, src: &File, opts: &mut SpliceOpts) -> Res
         opts.DstStart = *dstLock;
     } else if !srcPipe && !opts.SrcOffset {
         srcLock = src.offset.Lock(task)?;
-        opts.SrcStart = *dstLock;
+        opts.SrcStart = *srcLock;
     }
 
     // Check append-only mode and the limit.
@@ -87,10 +91,10 @@ pub fn Splice(task: &Task, dst: &File, src: &File, opts: &mut SpliceOpts) -> Res
         }
 
         // Enforce file limits.
-        let (limit, ok) = dst.checkLimit(opts.DstStart);
+        let (limit, ok) = dst.checkLimit(task, opts.DstStart);
         if ok && limit == 0 {
             return Err(Error::ErrExceedsFileSizeLimit);
-        } else if ok && limit < opts.DstStart {
+        } else if ok && limit < opts.Length {
             opts.Length = limit; // Cap the write.
         }
     }
@@ -118,25 +122,17 @@ pub fn Splice(task: &Task, dst: &File, src: &File, opts: &mut SpliceOpts) -> Res
                     let iov = IoVec::New(&buf);
                     let mut iovs: [IoVec; 1] = [iov];
 
-                    let srcStart = if opts.SrcOffset {
-                        opts.SrcStart
-                    } else {
-                        0
-                    };
-
-                    let readn = src.FileOp.ReadAt(task, src, &mut iovs[..], srcStart, false)?;
+                    // SrcStart and DstStart hold either the explicit offsets or
+                    // the locked file offsets; they are ignored by pipes.
+                    let blocking = !opts.NonBlocking && !src.Flags().NonBlocking;
+                    let readn = src.FileOp.ReadAt(task, src, &mut iovs[..], opts.SrcStart, blocking)?;
 
                     if readn != 0 {
                         let iov = IoVec::NewFromAddr(iov.Start(), readn as usize);
                         let iovs: [IoVec; 1] = [iov];
 
-                        let dstStart = if opts.DstOffset {
-                            opts.DstStart
-                        } else {
-                            0
-                        };
-
-                        let written = dst.FileOp.WriteAt(task, dst, &iovs, dstStart, false)?;
+                        let blocking = !opts.NonBlocking && !dst.Flags().NonBlocking;
+                        let written = dst.FileOp.WriteAt(task, dst, &iovs, opts.DstStart, blocking)?;
                         written
                     } else {
                         0 //EOF
@@ -166,61 +162,39 @@ pub fn Splice(task: &Task, dst: &File, src: &File, opts: &mut SpliceOpts) -> Res
         if !srcPipe && !opts.SrcOffset {
             *srcLock += n;
         }
+
+        dst.Dirent.InotifyEvent(IN_MODIFY, 0);
     }
 
     return Ok(n)
 }
 
-// doSplice implements a blocking splice operation.
-pub fn DoSplice(task: &Task, dstFile: &File, srcFile: &File, opts: &mut SpliceOpts, nonBlocking: bool) -> Result<i64> {
-    let mut inW = true;
-    let mut outW = true;
-
-    let general = task.blocker.generalEntry.clone();
-
+// doSplice implements a blocking splice operation, unless opts.NonBlocking
+// is set.
+//
+// A splice at end of input (a file at EOF, or an empty pipe without writers)
+// returns 0 rather than blocking.
+pub fn DoSplice(task: &Task, dstFile: &File, srcFile: &File, opts: &mut SpliceOpts) -> Result<i64> {
     loop {
         match Splice(task, dstFile, srcFile, opts) {
-            Err(e) => {
-                if e != Error::SysError(SysErr::EWOULDBLOCK) {
-                    return Err(e);
-                }
-
-                if e == Error::SysError(SysErr::EWOULDBLOCK) && nonBlocking {
-                    return Err(e)
-                }
-            }
-            Ok(n) => {
-                return Ok(n)
-            }
+            Err(Error::SysError(SysErr::EWOULDBLOCK)) if !opts.NonBlocking => (),
+            Err(e) => return Err(e),
+            Ok(n) => return Ok(n),
         }
 
-        if !inW && srcFile.Readiness(task, EVENT_READ) == 0 && !srcFile.Flags().NonBlocking {
-            srcFile.EventRegister(task, &general, EVENT_READ);
-            defer!(srcFile.EventUnregister(task, &general));
-
-            inW = true;
-        } else if !outW && dstFile.Readiness(task, EVENT_WRITE) == 0 && !dstFile.Flags().NonBlocking {
-            dstFile.EventRegister(task, &general, EVENT_WRITE);
-            defer!(srcFile.EventUnregister(task, &general));
-
-            outW = true;
-        }
+        // Block on whichever side isn't ready, unless that file is itself
+        // non-blocking.
+        let (f, mask) = if srcFile.Readiness(task, EVENT_READ) == 0 {
+            (srcFile, EVENT_READ)
+        } else {
+            (dstFile, EVENT_WRITE)
+        };
 
-        // Was anything registered? If no, everything is non-blocking.
-        if !inW && !outW {
+        if f.Flags().NonBlocking {
             return Err(Error::SysError(SysErr::EWOULDBLOCK))
         }
 
-        // Block until there's data.
-        match task.blocker.BlockWithMonoTimer(true, None) {
-            Err(Error::ErrInterrupted) => {
-                return Err(Error::SysError(SysErr::ERESTARTNOINTR));
-            }
-            Err(e) => {
-                return Err(e);
-            }
-            _ => ()
-        }
+        waitFile(task, f, mask)?;
     }
 }
 
@@ -258,6 +232,7 @@ pub fn SysSplice(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     // for the semantics of the call.
     let mut opts = SpliceOpts {
         Length: count,
+        NonBlocking: nonBlocking,
         ..Default::default()
     };
 
@@ -312,7 +287,144 @@ pub fn SysSplice(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         return Err(Error::SysError(SysErr::EINVAL));
     }
 
-    return DoSplice(task, &dst, &src, &mut opts, nonBlocking)
+    return HandleEPIPE(task, DoSplice(task, &dst, &src, &mut opts), false)
+}
+
+// waitFile blocks until f is ready for one of the events in mask.
+fn waitFile(task: &Task, f: &File, mask: EventMask) -> Result<()> {
+    let general = task.blocker.generalEntry.clone();
+
+    f.EventRegister(task, &general, mask);
+    defer!(f.EventUnregister(task, &general));
+
+    if f.Readiness(task, mask) != 0 {
+        return Ok(())
+    }
+
+    match task.blocker.BlockWithMonoTimer(true, None) {
+        Err(Error::ErrInterrupted) => {
+            return Err(Error::SysError(SysErr::ERESTARTSYS));
+        }
+        Err(e) => {
+            return Err(e);
+        }
+        _ => return Ok(())
+    }
+}
+
+// Tee implements linux syscall tee(2).
+pub fn SysTee(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let inFD = args.arg0 as i32;
+    let outFD = args.arg1 as i32;
+    let count = args.arg2 as i64;
+    let flags = args.arg3 as i32;
+
+    // Check for invalid flags.
+    if flags & !(SPLICE_F_MOVE | SPLICE_F_NONBLOCK | SPLICE_F_MORE | SPLICE_F_GIFT) != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if count < 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if count == 0 {
+        return Ok(0)
+    }
+
+    let nonBlocking = (flags & SPLICE_F_NONBLOCK) != 0;
+
+    let src = task.GetFile(inFD)?;
+    let dst = task.GetFile(outFD)?;
+
+    if !src.Flags().Read || !dst.Flags().Write {
+        return Err(Error::SysError(SysErr::EBADF))
+    }
+
+    // Both ends must be pipes.
+    let (srcPipe, dstPipe) = match (PipeOf(&src), PipeOf(&dst)) {
+        (Some(s), Some(d)) => (s, d),
+        _ => return Err(Error::SysError(SysErr::EINVAL)),
+    };
+
+    // We may not refer to the same pipe.
+    if srcPipe.Uid() == dstPipe.Uid() {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    loop {
+        match srcPipe.Tee(task, &dstPipe, count as usize) {
+            Err(Error::SysError(SysErr::EWOULDBLOCK)) if !nonBlocking => (),
+            Err(e) => return HandleEPIPE(task, Err(e), false),
+            Ok(n) => return Ok(n as i64),
+        }
+
+        if srcPipe.Queued() == 0 {
+            waitFile(task, &src, EVENT_READ)?;
+        } else {
+            waitFile(task, &dst, EVENT_WRITE)?;
+        }
+    }
+}
+
+// Vmsplice implements linux syscall vmsplice(2).
+//
+// The pages are copied rather than mapped, so SPLICE_F_GIFT has no effect.
+pub fn SysVmsplice(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let fd = args.arg0 as i32;
+    let iovAddr = args.arg1 as u64;
+    let nrSegs = args.arg2 as i64;
+    let flags = args.arg3 as i32;
+
+    // Check for invalid flags.
+    if flags & !(SPLICE_F_MOVE | SPLICE_F_NONBLOCK | SPLICE_F_MORE | SPLICE_F_GIFT) != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if nrSegs < 0 || nrSegs as usize > UIO_MAXIOV {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let file = task.GetFile(fd)?;
+    let pipe = match PipeOf(&file) {
+        None => return Err(Error::SysError(SysErr::EBADF)),
+        Some(p) => p,
+    };
+
+    let nonBlocking = (flags & SPLICE_F_NONBLOCK) != 0 || file.Flags().NonBlocking;
+
+    let mut iovs = task.IovsFromAddr(iovAddr, nrSegs as usize)?;
+    let fops = file.FileOp.clone();
+
+    if file.Flags().Write {
+        // Copy the user memory into the pipe. No more than the pipe capacity
+        // can be queued by one call, so don't copy in the rest.
+        task.CheckIOVecPermission(&iovs, false)?;
+        let iovs = Iovs(&iovs).TakeFirst(pipe.PipeSize());
+        loop {
+            match fops.WriteAt(task, &file, &iovs, 0, false) {
+                Err(Error::SysError(SysErr::EWOULDBLOCK)) if !nonBlocking => (),
+                res => return res,
+            }
+
+            waitFile(task, &file, EVENT_WRITE)?;
+        }
+    }
+
+    if file.Flags().Read {
+        // Copy from the pipe out to the user memory.
+        task.CheckIOVecPermission(&iovs, true)?;
+        loop {
+            match fops.ReadAt(task, &file, &mut iovs, 0, false) {
+                Err(Error::SysError(SysErr::EWOULDBLOCK)) if !nonBlocking => (),
+                res => return res,
+            }
+
+            waitFile(task, &file, EVENT_READ)?;
+        }
+    }
+
+    return Err(Error::SysError(SysErr::EBADF))
 }
 
 pub fn SysSendfile(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
@@ -356,7 +468,8 @@ pub fn SysSendfile(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
             Dup: false,
             DstOffset: false,
             DstStart: 0,
-        }, outFile.Flags().NonBlocking)?;
+            NonBlocking: outFile.Flags().NonBlocking,
+        })?;
 
         //*task.GetTypeMut(offsetAddr)? = offset + n;
         task.CopyOutObj(&(offset + n), offsetAddr)?;
@@ -368,7 +481,8 @@ pub fn SysSendfile(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
             Dup: false,
             DstOffset: false,
             DstStart: 0,
-        }, outFile.Flags().NonBlocking)?;
+            NonBlocking: outFile.Flags().NonBlocking,
+        })?;
     }
 
     return Ok(n)
@@ -12,42 +12,49 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use core::sync::atomic::Ordering;
+
 use super::super::task::*;
 use super::super::qlib::common::*;
 use super::super::qlib::linux_def::*;
-use super::super::qlib::qmsg::qcall::StatmInfo;
-use super::super::qlib::usage::memory::*;
+use super::super::kernel::loadavg::*;
 use super::super::syscalls::syscalls::*;
-use super::super::Kernel;
+use super::super::PAGE_MGR;
+use super::super::ALLOCATOR;
+
+// SI_LOAD_SHIFT is the number of fraction bits of sysinfo.loads.
+pub const SI_LOAD_SHIFT : u64 = 16;
+
+// MemoryInfo returns the total and the free memory of the page allocator,
+// in bytes. Pages given back to the host are free.
+pub fn MemoryInfo() -> (u64, u64) {
+    let total = ALLOCATOR.total.load(Ordering::Acquire) as u64;
+    let released = PAGE_MGR.ReleasedPageCount() * MemoryDef::PAGE_SIZE;
+    let mut free = ALLOCATOR.free.load(Ordering::Acquire) as u64 + released;
+    if free > total {
+        free = total;
+    }
+
+    return (total, free)
+}
 
 pub fn SysInfo(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let addr = args.arg0 as u64;
 
     let mut info : LibcSysinfo = LibcSysinfo::default();
 
-    /*let ret = Kernel::HostSpace::Sysinfo(&mut info as * mut _ as u64);
-    if ret < 0 {
-        return Err(Error::SysError(-ret as i32))
-    }*/
-
-    let mut statm : StatmInfo = StatmInfo::default();
-    Kernel::HostSpace::Statm(&mut statm);
+    let (total, free) = MemoryInfo();
+    let loads = LOAD_AVG.Averages(0);
 
-    let totalUsage = statm.rss;
-    let totalSize = TotalMemory(0, totalUsage);
-
-    //let sysInfo: &mut LibcSysinfo = task.GetTypeMut(addr)?;
-    info.procs = task.Thread().PIDNamespace().Tasks().len() as u16;
     info.uptime = Task::MonoTimeNow().Seconds() as i64;
-    info.totalram = totalSize; //super::super::ALLOCATOR.Total() as u64;
-    info.freeram = totalSize - totalUsage; // super::super::ALLOCATOR.Free() as u64;
+    for i in 0..3 {
+        info.loads[i] = loads[i] << (SI_LOAD_SHIFT - FSHIFT);
+    }
+    info.totalram = total;
+    info.freeram = free;
+    info.procs = task.Thread().PIDNamespace().Tasks().len() as u16;
     info.mem_unit = 1;
 
-    //*sysInfo = info;
     task.CopyOutObj(&info, addr)?;
-    //error!("SysInfo output is {:?}", &info);
-
-    //return Ok(ret)
-
     return Ok(0)
-}
\ No newline at end of file
+}
@@ -31,6 +31,9 @@ use super::super::vcpu::*;
 use super::super::qlib::LoadAddr;
 use super::super::qlib::common::*;
 use super::super::qlib::linux_def::*;
+use super::super::qlib::linux::personality::*;
+use super::super::qlib::linux::ioprio::*;
+use super::super::qlib::auth::id::*;
 use super::super::syscalls::syscalls::*;
 use super::super::kernel::cpuset::*;
 use super::super::threadmgr::thread::*;
@@ -38,6 +41,8 @@ use super::super::threadmgr::task_exit::*;
 use super::super::threadmgr::task_exec::*;
 use super::super::threadmgr::task_clone::*;
 use super::super::threadmgr::task_sched::*;
+use super::super::kernel::pidfd::*;
+use super::super::fs::procfs::task::namespace_symlink::*;
 use super::super::memmgr::mm::*;
 use super::super::SHARESPACE;
 
@@ -62,6 +67,8 @@ pub fn SysGetPpid(task: &mut Task, _args: &SyscallArguments) -> Result<i64> {
         Some(p) => p,
     };
 
+    // The parent is not visible if it lives in an ancestor PID namespace,
+    // in which case IDOfThreadGroup returns 0.
     let pidns = t.PIDNamespace();
     let ptg = parent.ThreadGroup();
     let pid = pidns.IDOfThreadGroup(&ptg);
@@ -70,13 +77,17 @@ pub fn SysGetPpid(task: &mut Task, _args: &SyscallArguments) -> Result<i64> {
 
 // Getpid implements linux syscall getpid(2).
 pub fn SysGetPid(task: &mut Task, _args: &SyscallArguments) -> Result<i64> {
-    let pid = task.Thread().ThreadGroup().ID();
+    let t = task.Thread();
+    let pidns = t.PIDNamespace();
+    let pid = pidns.IDOfThreadGroup(&t.ThreadGroup());
     return Ok(pid as i64)
 }
 
 // Gettid implements linux syscall gettid(2).
 pub fn SysGetTid(task: &mut Task, _args: &SyscallArguments) -> Result<i64> {
-    let tid = task.Thread().ThreadID();
+    let t = task.Thread();
+    let pidns = t.PIDNamespace();
+    let tid = pidns.IDOfTask(&t);
     return Ok(tid as i64)
 }
 
@@ -107,7 +118,12 @@ pub fn SysGetRobustList(task: &mut Task, args: &SyscallArguments) -> Result<i64>
         }
     };
 
-    // todo: check whether the current thread has permission to get the RobustList from target thread
+    // reading another thread's list requires ptrace access, see
+    // kernel/futex/syscalls.c:get_robust_list.
+    if !task.Thread().CanTrace(&thread) {
+        return Err(Error::SysError(SysErr::EPERM));
+    }
+
     //*task.GetTypeMut::<u64>(headAddr)? = thread.lock().robust_list_head;
     //*task.GetTypeMut::<i64>(lenAddr)? = ROBUST_LIST_LEN as i64;
 
@@ -280,6 +296,10 @@ pub fn SysExecve(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 
             t.ExitRobustList(task);
 
+            // the old image's clear_child_tid is cleared like on exit, and
+            // isn't carried into the new one, see fs/exec.c:exec_mm_release.
+            task.ClearChildTID();
+
             t.lock().updateCredsForExecLocked();
 
             t.UnstopVforkParent();
@@ -398,11 +418,51 @@ pub fn SysWaitid(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     };
 
     match idtype {
-        IDType::P_ALL | IDType::P_PID => {
+        IDType::P_ALL => {
+            // id is ignored.
+        }
+        IDType::P_PID => {
+            if id <= 0 {
+                return Err(Error::SysError(SysErr::EINVAL))
+            }
+
             wopts.SpecificTID = id;
         }
         IDType::P_PGID => {
-            wopts.SpecificPGID = id;
+            if id < 0 {
+                return Err(Error::SysError(SysErr::EINVAL))
+            }
+
+            // "If id is zero, wait for any child that is in the same process
+            // group as the caller's process group" - waitid(2)
+            wopts.SpecificPGID = if id == 0 {
+                let pg = task.Thread().ThreadGroup().ProcessGroup();
+                let pidns = task.Thread().PIDNamespace();
+                pidns.IDOfProcessGroup(&pg.unwrap())
+            } else {
+                id
+            };
+        }
+        IDType::P_PIDFD => {
+            let file = task.GetFile(id)?;
+            let fops = file.FileOp.clone();
+            let tg = match fops.as_any().downcast_ref::<PidFdFileOperations>() {
+                None => return Err(Error::SysError(SysErr::EBADF)),
+                Some(pidfd) => pidfd.tg.Upgrade(),
+            };
+
+            // A reaped process is no longer a child.
+            let pidns = task.Thread().PIDNamespace();
+            let tid = match tg {
+                None => 0,
+                Some(tg) => pidns.IDOfThreadGroup(&tg),
+            };
+
+            if tid == 0 {
+                return Err(Error::SysError(SysErr::ECHILD))
+            }
+
+            wopts.SpecificTID = tid;
         }
         _ => {
             return Err(Error::SysError(SysErr::EINVAL))
@@ -462,18 +522,18 @@ pub fn SysWaitid(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     if s.Exited() {
         siCode = SignalInfo::CLD_EXITED;
         sigChld.status = s.ExitStatus();
-    } else if s.Signaled() {
-        siCode = SignalInfo::CLD_KILLED;
-        sigChld.status = s.Signal();
     } else if s.CoreDump() {
         siCode = SignalInfo::CLD_DUMPED;
         sigChld.status = s.Signal();
+    } else if s.Signaled() {
+        siCode = SignalInfo::CLD_KILLED;
+        sigChld.status = s.Signal();
     } else if s.Stopped() {
         if wr.Event == EVENT_TRACEE_STOP {
             siCode = SignalInfo::CLD_TRAPPED;
             sigChld.status = s.TrapCause();
         } else {
-            siCode = SignalInfo::CLD_TRAPPED;
+            siCode = SignalInfo::CLD_STOPPED;
             sigChld.status = s.StopSignal();
         }
     } else if s.Continued() {
@@ -614,6 +674,93 @@ pub fn SysUnshare(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     return Ok(0);
 }
 
+// Setns implements linux syscall setns(2).
+pub fn SysSetns(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let fd = args.arg0 as i32;
+    let nstype = args.arg1 as i32;
+
+    let file = task.GetFile(fd)?;
+    let ns = match NamespaceOfFile(&file) {
+        None => return Err(Error::SysError(SysErr::EINVAL)),
+        Some(ns) => ns,
+    };
+
+    if nstype != 0 && nstype != ns.CloneFlag() {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
+
+    let t = task.Thread();
+    let tg = t.ThreadGroup();
+
+    if let Namespace::User(userns) = &ns {
+        // "EINVAL: The caller tried to join its current user namespace." and a
+        // multithreaded process may not change user namespace. - setns(2)
+        if *userns == t.UserNamespace() || tg.lock().tasksCount != 1 {
+            return Err(Error::SysError(SysErr::EINVAL))
+        }
+
+        t.SetUserNamespace(userns)?;
+        task.creds = t.Credentials();
+        return Ok(0)
+    }
+
+    // Joining any other namespace requires CAP_SYS_ADMIN both in the user
+    // namespace that owns it and in the caller's own user namespace.
+    let owner = match &ns {
+        Namespace::IPC(ipcns) => ipcns.userNS.clone(),
+        Namespace::Mount(mns) => mns.UserNamespace(),
+        Namespace::PID(pidns) => pidns.UserNamespace(),
+        Namespace::UTS(utsns) => utsns.UserNamespace(),
+        Namespace::User(_) => unreachable!(),
+    };
+
+    if !t.HasCapabilityIn(Capability::CAP_SYS_ADMIN, &owner) ||
+        !t.HasCapability(Capability::CAP_SYS_ADMIN) {
+        return Err(Error::SysError(SysErr::EPERM))
+    }
+
+    match ns {
+        Namespace::IPC(ipcns) => {
+            task.ipcns = ipcns;
+            t.lock().ipcns = task.ipcns.clone();
+        }
+        Namespace::Mount(mns) => {
+            // There is only one mount namespace, so the caller is always
+            // already a member of it.
+            if mns.ID() != task.mountNS.ID() {
+                return Err(Error::SysError(SysErr::EINVAL))
+            }
+        }
+        Namespace::PID(pidns) => {
+            // The target must be the caller's PID namespace or one of its
+            // descendants. As with unshare(CLONE_NEWPID), only children
+            // created afterwards become members of it.
+            let current = tg.PIDNamespace();
+            let mut n = Some(pidns.clone());
+            loop {
+                match n {
+                    None => return Err(Error::SysError(SysErr::EINVAL)),
+                    Some(ref p) if *p == current => break,
+                    Some(p) => n = p.lock().parent.clone(),
+                }
+            }
+
+            t.lock().childPIDNamespace = if pidns == current {
+                None
+            } else {
+                Some(pidns)
+            };
+        }
+        Namespace::UTS(utsns) => {
+            task.utsns = utsns;
+            t.lock().utsns = task.utsns.clone();
+        }
+        Namespace::User(_) => unreachable!(),
+    }
+
+    return Ok(0)
+}
+
 // SchedYield implements linux syscall sched_yield(2).
 pub fn SysScheduleYield(_task: &mut Task, _args: &SyscallArguments) -> Result<i64> {
     Yield();
@@ -913,3 +1060,142 @@ pub fn SysSetpriority(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         }
     }
 }
+
+// IOPrioTargets returns the tasks selected by the which and who arguments of
+// ioprio_set(2) and ioprio_get(2), see block/ioprio.c.
+fn IOPrioTargets(task: &Task, which: i32, who: i32) -> Result<Vec<Thread>> {
+    let t = task.Thread();
+    let pidns = t.PIDNamespace();
+    let mut targets = Vec::new();
+    match which {
+        IOPRIO_WHO_PROCESS => {
+            if who == 0 {
+                targets.push(t.clone());
+            } else if let Some(target) = pidns.TaskWithID(who) {
+                targets.push(target);
+            }
+        }
+        IOPRIO_WHO_PGRP => {
+            let pgid = if who == 0 {
+                let pg = t.ThreadGroup().ProcessGroup().unwrap();
+                pidns.IDOfProcessGroup(&pg)
+            } else {
+                who
+            };
+
+            for target in pidns.Tasks() {
+                match target.ThreadGroup().ProcessGroup() {
+                    Some(pg) if pidns.IDOfProcessGroup(&pg) == pgid => targets.push(target),
+                    _ => (),
+                }
+            }
+        }
+        IOPRIO_WHO_USER => {
+            let kuid = if who == 0 {
+                t.Credentials().lock().RealKUID
+            } else {
+                t.UserNamespace().MapToKUID(UID(who as u32))
+            };
+
+            if kuid.Ok() {
+                for target in pidns.Tasks() {
+                    if target.Credentials().lock().RealKUID == kuid {
+                        targets.push(target);
+                    }
+                }
+            }
+        }
+        _ => return Err(Error::SysError(SysErr::EINVAL)),
+    }
+
+    if targets.len() == 0 {
+        return Err(Error::SysError(SysErr::ESRCH))
+    }
+
+    return Ok(targets)
+}
+
+// IOPrioSet implements linux syscall ioprio_set(2).
+//
+// The priority is kept per thread and inherited by children. The host reads
+// and writes issued for a thread carry its priority when it's in the BE or
+// IDLE class; an RT priority needs CAP_SYS_ADMIN on the host, so it's only
+// recorded.
+pub fn SysIOPrioSet(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let which = args.arg0 as i32;
+    let who = args.arg1 as i32;
+    let ioprio = args.arg2 as i32;
+
+    if ioprio < 0 || ioprio > u16::MAX as i32 {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
+
+    let ioprio = ioprio as u16;
+    let t = task.Thread();
+    let data = IOPrioData(ioprio);
+    match IOPrioClass(ioprio) {
+        IOPRIO_CLASS_RT | IOPRIO_CLASS_BE => {
+            if IOPrioClass(ioprio) == IOPRIO_CLASS_RT &&
+                !t.HasCapability(Capability::CAP_SYS_NICE) &&
+                !t.HasCapability(Capability::CAP_SYS_ADMIN) {
+                return Err(Error::SysError(SysErr::EPERM))
+            }
+
+            if data >= IOPRIO_NR_LEVELS {
+                return Err(Error::SysError(SysErr::EINVAL))
+            }
+        }
+        IOPRIO_CLASS_IDLE => (),
+        IOPRIO_CLASS_NONE => {
+            if data != 0 {
+                return Err(Error::SysError(SysErr::EINVAL))
+            }
+        }
+        _ => return Err(Error::SysError(SysErr::EINVAL)),
+    }
+
+    let creds = t.Credentials();
+    let (euid, uid) = {
+        let c = creds.lock();
+        (c.EffectiveKUID, c.RealKUID)
+    };
+    let capNice = t.HasCapability(Capability::CAP_SYS_NICE);
+    for target in IOPrioTargets(task, which, who)? {
+        let tuid = target.Credentials().lock().RealKUID;
+        if tuid != euid && tuid != uid && !capNice {
+            return Err(Error::SysError(SysErr::EPERM))
+        }
+
+        target.SetIOPrio(ioprio);
+    }
+
+    return Ok(0)
+}
+
+// IOPrioGet implements linux syscall ioprio_get(2). A process group or user
+// reports the best priority of its tasks.
+pub fn SysIOPrioGet(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let which = args.arg0 as i32;
+    let who = args.arg1 as i32;
+
+    let ioprio = IOPrioTargets(task, which, who)?
+        .iter()
+        .map(|t| t.IOPrio())
+        .min()
+        .unwrap();
+
+    return Ok(ioprio as i64)
+}
+
+// Personality implements linux syscall personality(2).
+pub fn SysPersonality(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let persona = args.arg0 as u32;
+
+    let t = task.Thread();
+    if persona == PERSONALITY_QUERY {
+        return Ok(t.Personality() as i64)
+    }
+
+    let old = t.SetPersonality(persona);
+    return Ok(old as i64)
+}
@@ -58,13 +58,31 @@ pub fn IsValidCPUClock(c: i32) -> bool {
     return true;
 }
 
+// TargetThread returns the thread whose CPU time the clock c measures. A pid
+// of 0 names the caller, a thread clock must name a thread in the caller's
+// thread group and a process clock must name a thread group leader, see
+// kernel/time/posix-cpu-timers.c:lookup_task().
 pub fn TargetThread(task: &Task, c: i32) -> Option<Thread> {
     let pid = PidOfClockID(c);
-    if c == 0 {
-        return Some(task.Thread());
+    let thread = task.Thread();
+    if pid == 0 {
+        return Some(thread);
     }
 
-    return task.Thread().PIDNamespace().TaskWithID(pid)
+    let target = thread.PIDNamespace().TaskWithID(pid)?;
+    if IsCPUClockPerThread(c) {
+        if target.ThreadGroup() != thread.ThreadGroup() {
+            return None
+        }
+
+        return Some(target)
+    }
+
+    if target.ThreadGroup().lock().leader.Upgrade() != Some(target.clone()) {
+        return None
+    }
+
+    return Some(target)
 }
 
 pub fn GetClock(task: &Task, clockId: i32) -> Result<Clock> {
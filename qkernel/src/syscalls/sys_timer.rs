@@ -66,6 +66,9 @@ pub fn SysSetitimer(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let oldVal = args.arg2 as u64;
 
     let newitv = CopyItimerValIn(task, newVal)?;
+    if !newitv.Value.IsValid() || !newitv.Interval.IsValid() {
+        return Err(Error::SysError(SysErr::EINVAL))
+    }
 
     let thread = task.Thread();
     let olditv = thread.Setitimer(timerID, &newitv)?;
@@ -83,8 +86,9 @@ pub fn SysAlarm(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         Value: Timeval::FromNs(duration),
     })?;
 
+    // As in Linux, the remaining time is rounded to the nearest second.
     let olddur = olditv.Value.ToDuration();
-    let mut secs = olddur / SECOND;
+    let mut secs = (olddur + SECOND / 2) / SECOND;
     if secs == 0 && olddur != 0 {
         // We can't return 0 if an alarm was previously scheduled.
         secs = 1;
@@ -101,12 +105,12 @@ pub fn SysTimerCreate(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 
     let c = GetClock(task, clockID)?;
 
-    let mut sev = Sigevent::default();
-    if sevp != 0 {
-        sev = task.CopyInObj(sevp)?;
-    }
-
-    let id = task.Thread().IntervalTimerCreate(&c, &mut sev)?;
+    let id = if sevp != 0 {
+        let sev : Sigevent = task.CopyInObj(sevp)?;
+        task.Thread().IntervalTimerCreate(&c, Some(&sev))?
+    } else {
+        task.Thread().IntervalTimerCreate(&c, None)?
+    };
 
     //let timerID = task.GetTypeMut(timerIDp)?;
     //*timerID = id;
@@ -20,20 +20,33 @@ use super::super::fs::file::*;
 use super::super::task::*;
 use super::super::qlib::common::*;
 use super::super::qlib::linux_def::*;
+use super::super::qlib::linux::inotify::*;
 use super::super::qlib::mem::block::*;
 use super::super::syscalls::syscalls::*;
 use super::super::perflog::*;
+use super::super::SignalDef::*;
 
 pub fn SysWrite(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let fd = args.arg0 as i32;
     let addr = args.arg1 as u64;
     let size = args.arg2 as i64;
 
-    let n = Write(task, fd, addr, size)?;
+    let n = HandleEPIPE(task, Write(task, fd, addr, size), false)?;
     task.ioUsage.AccountWriteSyscall(n);
     return Ok(n);
 }
 
+// HandleEPIPE raises SIGPIPE in the calling thread when a write fails because
+// the other end of a pipe or socket is gone, unless noSignal is set (e.g. by
+// MSG_NOSIGNAL). The EPIPE is still returned to the caller.
+pub fn HandleEPIPE(task: &Task, res: Result<i64>, noSignal: bool) -> Result<i64> {
+    if res == Err(Error::SysError(SysErr::EPIPE)) && !noSignal {
+        task.Thread().SendSignal(&SignalInfoPriv(Signal::SIGPIPE)).ok();
+    }
+
+    return res
+}
+
 pub fn Write(task: &Task, fd: i32, addr: u64, size: i64) -> Result<i64> {
     //task.PerfGoto(PerfType::Write);
     //defer!(task.PerfGofrom(PerfType::Write));
@@ -73,7 +86,12 @@ pub fn Write(task: &Task, fd: i32, addr: u64, size: i64) -> Result<i64> {
     let iov = IoVec::NewFromAddr(addr, size as usize);
     let iovs: [IoVec; 1] = [iov];
 
-    return writev(task, &file, &iovs);
+    let n = writev(task, &file, &iovs)?;
+    if n > 0 {
+        file.Dirent.InotifyEvent(IN_MODIFY, 0);
+    }
+
+    return Ok(n)
 }
 
 pub fn SysPwrite64(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
@@ -116,7 +134,12 @@ pub fn Pwrite64(task: &Task, fd: i32, addr: u64, size: i64, offset: i64) -> Resu
     let iov = IoVec::NewFromAddr(addr, size as usize);
     let iovs: [IoVec; 1] = [iov];
 
-    return pwritev(task, &file, &iovs, offset);
+    let n = pwritev(task, &file, &iovs, offset)?;
+    if n > 0 {
+        file.Dirent.InotifyEvent(IN_MODIFY, 0);
+    }
+
+    return Ok(n)
 }
 
 pub fn SysPWritev2(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
@@ -150,12 +173,12 @@ pub fn SysPWritev2(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     }
 
     if offset == -1 {
-        let n = Writev(task, fd, addr, iovcnt)?;
+        let n = HandleEPIPE(task, Writev(task, fd, addr, iovcnt), false)?;
         task.ioUsage.AccountWriteSyscall(n);
         return Ok(n);
     }
 
-    let n = Pwritev(task, fd, addr, iovcnt, offset)?;
+    let n = HandleEPIPE(task, Pwritev(task, fd, addr, iovcnt, offset), false)?;
     task.ioUsage.AccountWriteSyscall(n);
     return Ok(n);
 }
@@ -176,7 +199,7 @@ pub fn SysWritev(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         }
     }
 
-    let n = Writev(task, fd, addr, iovcnt)?;
+    let n = HandleEPIPE(task, Writev(task, fd, addr, iovcnt), false)?;
     task.ioUsage.AccountWriteSyscall(n);
     return Ok(n);
 }
@@ -198,7 +221,12 @@ pub fn Writev(task: &Task, fd: i32, addr: u64, iovcnt: i32) -> Result<i64> {
     }
 
     let srcs = task.IovsFromAddr(addr, iovcnt as usize)?;
-    return writev(task, &file, &srcs);
+    let n = writev(task, &file, &srcs)?;
+    if n > 0 {
+        file.Dirent.InotifyEvent(IN_MODIFY, 0);
+    }
+
+    return Ok(n)
 }
 
 pub fn SysPwritev(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
@@ -207,7 +235,7 @@ pub fn SysPwritev(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let iovcnt = args.arg2 as i32;
     let offset = args.arg3 as i64;
 
-    let n = Pwritev(task, fd, addr, iovcnt, offset)?;
+    let n = HandleEPIPE(task, Pwritev(task, fd, addr, iovcnt, offset), false)?;
     task.ioUsage.AccountWriteSyscall(n);
     return Ok(n);
 }
@@ -236,7 +264,12 @@ pub fn Pwritev(task: &Task, fd: i32, addr: u64, iovcnt: i32, offset: i64) -> Res
     }
 
     let srcs = task.IovsFromAddr(addr, iovcnt as usize)?;
-    return pwritev(task, &file, &srcs, offset);
+    let n = pwritev(task, &file, &srcs, offset)?;
+    if n > 0 {
+        file.Dirent.InotifyEvent(IN_MODIFY, 0);
+    }
+
+    return Ok(n)
 }
 
 fn RepWritev(task: &Task, f: &File, srcs: &[IoVec]) -> Result<i64> {
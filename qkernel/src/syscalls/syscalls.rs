@@ -44,11 +44,17 @@ use super::super::syscalls::sys_membarrier::*;
 use super::super::syscalls::sys_splice::*;
 use super::super::syscalls::sys_timer::*;
 use super::super::syscalls::sys_mempolicy::*;
+use super::super::syscalls::sys_mount::*;
+use super::super::syscalls::sys_shm::*;
+use super::super::syscalls::sys_inotify::*;
+use super::super::syscalls::sys_ptrace::*;
+use super::super::syscalls::sys_process_vm::*;
 
 use super::super::task::*;
 use super::super::qlib::SysCallID;
 use super::super::qlib::common::*;
 use super::super::qlib::linux_def::*;
+use super::super::SignalDef::*;
 
 //#[repr(align(128))]
 #[derive(Debug)]
@@ -82,6 +88,15 @@ pub fn SysCall(task: &mut Task, nr: u64, args: &SyscallArguments) -> TaskRunStat
             task.SetReturn(-e as u64);
             return TaskRunState::RunApp
         }
+        Err(Error::ErrExceedsFileSizeLimit) => {
+            // "EFBIG An attempt was made to write a file that exceeds the
+            // implementation-defined maximum file size or the process's file
+            // size limit" - write(2). The process also gets SIGXFSZ.
+            task.Thread().SendSignal(&SignalInfoPriv(Signal::SIGXFSZ)).ok();
+            task.haveSyscallReturn = true;
+            task.SetReturn(-SysErr::EFBIG as u64);
+            return TaskRunState::RunApp
+        }
         Err(Error::SysCallNotImplement) => {
             let callId: SysCallID = unsafe { core::mem::transmute(nr as u64) };
             panic!("Sycall not implement syscall is {:?}", callId);
@@ -125,9 +140,9 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysMsync, //sys_msync,
     SysMincore, //sys_mincore,
     SysMadvise, //sys_madvise,
-    NotImplementSyscall, //sys_shmget,
-    NotImplementSyscall, //sys_shmat,   //30
-    NotImplementSyscall, //sys_shmctl,
+    SysShmget, //sys_shmget,
+    SysShmat, //sys_shmat,   //30
+    SysShmctl, //sys_shmctl,
     SysDup, //sys_dup,
     SysDup2, //sys_dup2,
     SysPause, //sys_pause,
@@ -163,7 +178,7 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     NotImplementSyscall, //sys_semget,
     NotImplementSyscall, //sys_semop,
     NotImplementSyscall, //sys_semctl,
-    NotImplementSyscall, //sys_shmdt,
+    SysShmdt, //sys_shmdt,
     NotImplementSyscall, //sys_msgget,
     NotImplementSyscall, //sys_msgsnd,
     NotImplementSyscall, //sys_msgrcv,    //70
@@ -197,7 +212,7 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysGetrusage, //sys_getrusage,
     SysInfo, //sys_sysinfo,
     SysTimes, //sys_times,    //100
-    NotImplementSyscall, //sys_ptrace,
+    SysPtrace, //sys_ptrace,
     SysGetuid, //sys_getuid,
     NotImplementSyscall, //sys_syslog,
     SysGetgid, //sys_getgid,
@@ -231,7 +246,7 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysUtime, //sys_utime,
     SysMknode, //sys_mknod,
     NotImplementSyscall, //sys_uselib,
-    NotImplementSyscall, //sys_personality,
+    SysPersonality, //sys_personality,
     NotImplementSyscall, //sys_ustat,
     SysStatfs, //sys_statfs,
     SysFstatfs, //sys_fstatfs,
@@ -261,8 +276,8 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysSync, //sys_sync,
     NotImplementSyscall, //sys_acct,
     NotImplementSyscall, //sys_settimeofday,
-    NotImplementSyscall, //sys_mount,
-    NotImplementSyscall, //sys_umount2,
+    SysMount, //sys_mount,
+    SysUmount2, //sys_umount2,
     NotImplementSyscall, //sys_swapon,
     NotImplementSyscall, //sys_swapoff,
     NotImplementSyscall, //sys_reboot,
@@ -283,7 +298,7 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     NotImplementSyscall, //sys_tuxcall,
     NotImplementSyscall, //sys_security,
     SysGetTid, //sys_gettid,
-    NotImplementSyscall, //sys_readahead,
+    SysReadahead, //sys_readahead,
     SysNoSupport, //sys_setxattr,
     SysNoSupport, //sys_lsetxattr,
     SysNoSupport, //sys_fsetxattr,    //190
@@ -347,11 +362,11 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     NotImplementSyscall, //sys_add_key,
     NotImplementSyscall, //sys_request_key,
     NotImplementSyscall, //sys_keyctl,    //250
-    NotImplementSyscall, //sys_ioprio_set,
-    NotImplementSyscall, //sys_ioprio_get,
-    NotImplementSyscall, //sys_inotify_init,
-    NotImplementSyscall, //sys_inotify_add_watch,
-    NotImplementSyscall, //sys_inotify_rm_watch,
+    SysIOPrioSet, //sys_ioprio_set,
+    SysIOPrioGet, //sys_ioprio_get,
+    SysInotifyInit, //sys_inotify_init,
+    SysInotifyAddWatch, //sys_inotify_add_watch,
+    SysInotifyRmWatch, //sys_inotify_rm_watch,
     NotImplementSyscall, //sys_migrate_pages,
     SysOpenAt, //sys_openat,
     SysMkdirat, //sys_mkdirat,
@@ -372,9 +387,9 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysSetRobustList, //sys_set_robust_list,
     SysGetRobustList, //sys_get_robust_list,
     SysSplice, //sys_splice,
-    NotImplementSyscall, //sys_tee,
+    SysTee, //sys_tee,
     SysSyncFileRange, //sys_sync_file_range,
-    NotImplementSyscall, //sys_vmsplice,
+    SysVmsplice, //sys_vmsplice,
     NotImplementSyscall, //sys_move_pages,
     SysUtimensat, //sys_utimensat,    //280
     SysPwait, //sys_epoll_pwait,
@@ -390,7 +405,7 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysEpollCreate1, //sys_epoll_create1,
     SysDup3, //sys_dup3,
     SysPipe2, //sys_pipe2,
-    NotImplementSyscall, //sys_inotify_init1,
+    SysInotifyInit1, //sys_inotify_init1,
     SysPreadv, //sys_preadv,
     SysPwritev, //sys_pwritev,
     SysRtTgsigqueueinfo, //sys_rt_tgsigqueueinfo,
@@ -404,10 +419,10 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     NotImplementSyscall, //sys_clock_adjtime,
     SysSyncFs, //sys_syncfs,
     SysSendMMsg, //sys_sendmmsg,
-    NotImplementSyscall, //sys_setns,
+    SysSetns, //sys_setns,
     SysGetcpu, //sys_getcpu,
-    NotImplementSyscall, //sys_process_vm_readv,//310
-    NotImplementSyscall, //sys_process_vm_writev,
+    SysProcessVMReadv, //sys_process_vm_readv,//310
+    SysProcessVMWritev, //sys_process_vm_writev,
     NotImplementSyscall, //sys_kcmp,
     NotImplementSyscall, //sys_finit_module,
     NotImplementSyscall, //sys_sched_setattr,
@@ -415,7 +430,7 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     NotImplementSyscall, //sys_renameat2,
     NotImplementSyscall, //sys_seccomp,
     SysGetRandom, //sys_getrandom,
-    NotImplementSyscall, //sys_memfd_create,
+    SysMemfdCreate, //sys_memfd_create,
     NotImplementSyscall, //sys_kexec_file_load,//320
     NotImplementSyscall, //sys_bpf,
     NotImplementSyscall, //sys_stub_execveat,
@@ -429,6 +444,109 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     NotImplementSyscall, //sys_pkey_alloc,//330
     NotImplementSyscall, //sys_pkey_free,
     SysStatx, //sys_statx,
+    SysNoSys, //sys_io_pgetevents,
+    SysNoSys, //sys_rseq,
+    // 335 to 423 are unused on x86_64.
+    SysNoSys, //sys_unused335,
+    SysNoSys, //sys_unused336,
+    SysNoSys, //sys_unused337,
+    SysNoSys, //sys_unused338,
+    SysNoSys, //sys_unused339,
+    SysNoSys, //sys_unused340,//340
+    SysNoSys, //sys_unused341,
+    SysNoSys, //sys_unused342,
+    SysNoSys, //sys_unused343,
+    SysNoSys, //sys_unused344,
+    SysNoSys, //sys_unused345,
+    SysNoSys, //sys_unused346,
+    SysNoSys, //sys_unused347,
+    SysNoSys, //sys_unused348,
+    SysNoSys, //sys_unused349,
+    SysNoSys, //sys_unused350,//350
+    SysNoSys, //sys_unused351,
+    SysNoSys, //sys_unused352,
+    SysNoSys, //sys_unused353,
+    SysNoSys, //sys_unused354,
+    SysNoSys, //sys_unused355,
+    SysNoSys, //sys_unused356,
+    SysNoSys, //sys_unused357,
+    SysNoSys, //sys_unused358,
+    SysNoSys, //sys_unused359,
+    SysNoSys, //sys_unused360,//360
+    SysNoSys, //sys_unused361,
+    SysNoSys, //sys_unused362,
+    SysNoSys, //sys_unused363,
+    SysNoSys, //sys_unused364,
+    SysNoSys, //sys_unused365,
+    SysNoSys, //sys_unused366,
+    SysNoSys, //sys_unused367,
+    SysNoSys, //sys_unused368,
+    SysNoSys, //sys_unused369,
+    SysNoSys, //sys_unused370,//370
+    SysNoSys, //sys_unused371,
+    SysNoSys, //sys_unused372,
+    SysNoSys, //sys_unused373,
+    SysNoSys, //sys_unused374,
+    SysNoSys, //sys_unused375,
+    SysNoSys, //sys_unused376,
+    SysNoSys, //sys_unused377,
+    SysNoSys, //sys_unused378,
+    SysNoSys, //sys_unused379,
+    SysNoSys, //sys_unused380,//380
+    SysNoSys, //sys_unused381,
+    SysNoSys, //sys_unused382,
+    SysNoSys, //sys_unused383,
+    SysNoSys, //sys_unused384,
+    SysNoSys, //sys_unused385,
+    SysNoSys, //sys_unused386,
+    SysNoSys, //sys_unused387,
+    SysNoSys, //sys_unused388,
+    SysNoSys, //sys_unused389,
+    SysNoSys, //sys_unused390,//390
+    SysNoSys, //sys_unused391,
+    SysNoSys, //sys_unused392,
+    SysNoSys, //sys_unused393,
+    SysNoSys, //sys_unused394,
+    SysNoSys, //sys_unused395,
+    SysNoSys, //sys_unused396,
+    SysNoSys, //sys_unused397,
+    SysNoSys, //sys_unused398,
+    SysNoSys, //sys_unused399,
+    SysNoSys, //sys_unused400,//400
+    SysNoSys, //sys_unused401,
+    SysNoSys, //sys_unused402,
+    SysNoSys, //sys_unused403,
+    SysNoSys, //sys_unused404,
+    SysNoSys, //sys_unused405,
+    SysNoSys, //sys_unused406,
+    SysNoSys, //sys_unused407,
+    SysNoSys, //sys_unused408,
+    SysNoSys, //sys_unused409,
+    SysNoSys, //sys_unused410,//410
+    SysNoSys, //sys_unused411,
+    SysNoSys, //sys_unused412,
+    SysNoSys, //sys_unused413,
+    SysNoSys, //sys_unused414,
+    SysNoSys, //sys_unused415,
+    SysNoSys, //sys_unused416,
+    SysNoSys, //sys_unused417,
+    SysNoSys, //sys_unused418,
+    SysNoSys, //sys_unused419,
+    SysNoSys, //sys_unused420,//420
+    SysNoSys, //sys_unused421,
+    SysNoSys, //sys_unused422,
+    SysNoSys, //sys_unused423,
+    SysPidfdSendSignal, //sys_pidfd_send_signal,
+    SysNoSys, //sys_io_uring_setup,
+    SysNoSys, //sys_io_uring_enter,
+    SysNoSys, //sys_io_uring_register,
+    SysNoSys, //sys_open_tree,
+    SysNoSys, //sys_move_mount,
+    SysNoSys, //sys_fsopen,//430
+    SysNoSys, //sys_fsconfig,
+    SysNoSys, //sys_fsmount,
+    SysNoSys, //sys_fspick,
+    SysPidfdOpen, //sys_pidfd_open,
 ];
 
 pub fn NotImplementSyscall(_task: &mut Task, _args: &SyscallArguments) -> Result<i64> {
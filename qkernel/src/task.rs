@@ -468,7 +468,7 @@ impl Task {
         return self.fdTbl.lock().NewFDs(fd, file, flags)
     }
 
-    pub fn NewFDAt(&mut self, fd: i32, file: &File, flags: &FDFlags) -> Result<()> {
+    pub fn NewFDAt(&mut self, fd: i32, file: &File, flags: &FDFlags) -> Result<Option<File>> {
         return self.fdTbl.lock().NewFDAt(fd, file, flags)
     }
 
@@ -746,15 +746,6 @@ impl Task {
         if self.isWaitThread {
             panic!("Exit from wait thread!")
         }
-
-        match self.tidInfo.clear_child_tid {
-            None => {
-                //println!("there is no clear_child_tid");
-            }
-            Some(addr) => {
-                self.CopyOutObj(&(0 as i32), addr).ok();
-            }
-        }
     }
 
     pub fn CreateFromThread() -> &'static mut Self {
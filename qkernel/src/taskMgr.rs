@@ -291,6 +291,20 @@ impl Scheduler {
         return total;
     }
 
+    // RunnableCount returns the number of tasks which are running on a vcpu
+    // or waiting in a ready queue.
+    pub fn RunnableCount(&self) -> u64 {
+        let mut count = self.GlobalReadyTaskCnt() as u64;
+        let vcpuCount = self.vcpuCnt.load(Ordering::Relaxed);
+        for i in 0..vcpuCount {
+            if CPULocal::GetCPUState(i) == VcpuState::Running {
+                count += 1;
+            }
+        }
+
+        return count;
+    }
+
     pub fn Print(&self) -> String {
         let mut str = alloc::string::String::new();
         let vcpuCount = self.vcpuCnt.load(Ordering::Relaxed);
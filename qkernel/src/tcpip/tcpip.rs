@@ -275,6 +275,8 @@ pub fn IsLinkLocal(addr: &[u8]) -> bool {
 
 pub const SOCK_ADDR_INET_SIZE: usize = 16;
 pub const SOCK_ADDR_INET6_SIZE: usize = 28;
+// SOCK_ADDR_MAX_LEN is sizeof(struct sockaddr_storage).
+pub const SOCK_ADDR_MAX_LEN: usize = 128;
 
 // ntohs converts a 16-bit number from network byte order to host byte order. It
 // assumes that the host is little endian.
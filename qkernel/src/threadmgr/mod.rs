@@ -34,4 +34,6 @@ pub mod task_acct;
 pub mod task_sched;
 pub mod task_usermem;
 pub mod task_exec;
+pub mod task_coredump;
 pub mod task_futex;
+pub mod task_ptrace;
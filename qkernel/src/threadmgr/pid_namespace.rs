@@ -26,12 +26,15 @@ use super::thread::*;
 use super::threads::*;
 use super::thread_group::*;
 use super::processgroup::*;
+use super::super::uid::NewUID;
 
 const TASKS_LIMIT: ThreadID = 1 << 16;
 const INIT_TID: ThreadID = 1;
 
 #[derive(Default)]
 pub struct PIDNamespaceInternal {
+    // id identifies the namespace in /proc/[pid]/ns/pid.
+    pub id: u64,
     pub owner: TaskSet,
     pub parent: Option<PIDNamespace>,
     pub userns: UserNameSpace,
@@ -77,6 +80,7 @@ impl Eq for PIDNamespace {}
 impl PIDNamespace {
     pub fn New(ts: &TaskSet, parent: Option<PIDNamespace>, userns: &UserNameSpace) -> Self {
         let internal = PIDNamespaceInternal {
+            id: NewUID(),
             owner: ts.clone(),
             parent: parent,
             userns: userns.clone(),
@@ -98,6 +102,10 @@ impl PIDNamespace {
         return Arc::strong_count(&self.0);
     }
 
+    pub fn ID(&self) -> u64 {
+        return self.lock().id
+    }
+
     pub fn Owner(&self) -> TaskSet {
         return self.lock().owner.clone();
     }
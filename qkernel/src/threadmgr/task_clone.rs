@@ -290,10 +290,13 @@ impl Thread {
 
             let kernel = t.k.clone();
             let limit = tg.lock().limits.clone();
+            // The child shares its parent's controlling terminal.
+            let tty = tg.TTY();
             tg = kernel.newThreadGroup(&pidns,
                                        &sh,
                                        opts.sharingOption.TerminationSignal.clone(),
                                        &limit.GetCopy());
+            tg.lock().tty = tty;
         }
 
         let mut cfg = TaskConfig {
@@ -308,6 +311,8 @@ impl Thread {
             Fdtbl: fdTbl,
             Credentials: creds.clone(),
             Niceness: t.niceness,
+            Personality: t.personality,
+            IOPrio: t.ioprio,
             NetworkNamespaced: false,
             AllowedCPUMask: t.allowedCPUMask.Copy(),
             UTSNamespace: utsns,
@@ -0,0 +1,663 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::mem::size_of;
+use core::slice;
+
+use super::super::qlib::common::*;
+use super::super::qlib::linux_def::*;
+use super::super::qlib::addr::*;
+use super::super::qlib::limits::*;
+use super::super::qlib::cpuid::*;
+use super::super::qlib::mem::block::*;
+use super::super::task::*;
+use super::super::fs::file::*;
+use super::super::fs::flags::*;
+use super::super::memmgr::mm::*;
+use super::super::memmgr::metadata::*;
+use super::super::arch::x86_64::arch_x86::*;
+use super::thread::*;
+use super::thread_group::*;
+use super::task_exit::*;
+use super::task_stop::*;
+
+// CORE_FILE_NAME is the name of the core file, written to the working
+// directory of the dumping task. It is the name Linux uses with the default
+// core_pattern and core_uses_pid.
+pub const CORE_FILE_NAME: &str = "core";
+
+const ET_CORE: u16 = 4;
+const EM_X86_64: u16 = 62;
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+
+const NT_PRSTATUS: u32 = 1;
+const NT_PRFPREG: u32 = 2;
+const NT_PRPSINFO: u32 = 3;
+const NT_X86_XSTATE: u32 = 0x202;
+
+// FXSAVE_SIZE is the size of an FXSAVE image, i.e. of user_fpregs_struct.
+const FXSAVE_SIZE: usize = 512;
+
+// The software-reserved bytes 464..512 of the FXSAVE image in an
+// NT_X86_XSTATE note start with the XCR0 mask, which tells a debugger which
+// XSAVE features the image has, see arch/x86/include/asm/user.h.
+const XSTATE_SW_BYTES_OFFSET: usize = 464;
+
+// CoreDumpStop is the internal stop of a task waiting for its siblings to
+// exit so that it can write a core file.
+pub struct CoreDumpStop {}
+
+impl TaskStop for CoreDumpStop {
+    fn Type(&self) -> TaskStopType {
+        return TaskStopType::COREDUMPSTOP;
+    }
+
+    fn Killable(&self) -> bool {
+        return true
+    }
+}
+
+// ThreadCoreState is a thread's registers as saved for a core dump.
+pub struct ThreadCoreState {
+    pub tid: ThreadID,
+    pub signalMask: u64,
+    pub regs: PtraceRegs,
+    pub fpstate: Box<X86fpstate>,
+}
+
+impl ThreadCoreState {
+    // New saves the registers of task, whose thread ID is tid. The kernel
+    // doesn't use the FPU, so the task's floating point registers still hold
+    // its application state.
+    pub fn New(task: &Task, tid: ThreadID, signalMask: u64) -> Self {
+        let fpstate = Box::new(X86fpstate::New());
+        fpstate.SaveFp();
+
+        return Self {
+            tid: tid,
+            signalMask: signalMask,
+            regs: PtraceRegs::New(task.GetPtRegs(), task.context.fs),
+            fpstate: fpstate,
+        }
+    }
+}
+
+// CoreDump is a core dump in progress, see ThreadGroupInternal.coreDump.
+pub struct CoreDump {
+    // dumper is the task that writes the core file.
+    pub dumper: ThreadWeak,
+
+    // threads holds the dumper's registers, then those of its siblings in
+    // the order they exited.
+    pub threads: Vec<ThreadCoreState>,
+}
+
+impl CoreDump {
+    pub fn New(dumper: &Thread, state: ThreadCoreState) -> Self {
+        let mut threads = Vec::new();
+        threads.push(state);
+
+        return Self {
+            dumper: dumper.Downgrade(),
+            threads: threads,
+        }
+    }
+}
+
+impl Thread {
+    // exitCoreDumpLocked records t's registers in the core dump in progress
+    // in its thread group, if there is one, and wakes the dumping task once
+    // it is the only active task left.
+    //
+    // Preconditions: The signal mutex must be locked. t must have just
+    // reached TaskExitInitiated.
+    pub fn exitCoreDumpLocked(&self, tg: &ThreadGroup) {
+        let dumper = match &tg.lock().coreDump {
+            None => return,
+            Some(ref dump) => dump.dumper.Upgrade(),
+        };
+
+        let dumper = match dumper {
+            None => return,
+            Some(d) => d,
+        };
+
+        if dumper == *self {
+            return
+        }
+
+        let tid = tg.PIDNamespace().IDOfTaskLocked(self);
+        let signalMask = self.lock().signalMask.0;
+        let state = ThreadCoreState::New(Task::Current(), tid, signalMask);
+
+        let activeTasks;
+        {
+            let mut tglock = tg.lock();
+            if let Some(ref mut dump) = tglock.coreDump {
+                dump.threads.push(state);
+            }
+            activeTasks = tglock.activeTasks;
+        }
+
+        if activeTasks == 1 {
+            let mut dlock = dumper.lock();
+            match &dlock.stop {
+                None => (),
+                Some(ref stop) => {
+                    if stop.Type() == TaskStopType::COREDUMPSTOP {
+                        dlock.endInternalStopLocked();
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Task {
+    // PrepareGroupCoreDump is PrepareGroupExit for a signal whose default
+    // action dumps core. Unless RLIMIT_CORE is 0 or the process isn't
+    // dumpable, the task writes a core file in RunExit once its siblings
+    // have exited, see fs/coredump.c:do_coredump.
+    pub fn PrepareGroupCoreDump(&self, es: ExitStatus) {
+        let t = self.Thread();
+        let limit = t.ThreadGroup().Limits().Get(LimitType::Core).Cur;
+        if limit == 0 || t.MemoryManager().Dumpability() == NOT_DUMPABLE {
+            t.PrepareGroupExit(es);
+            return
+        }
+
+        // Save the registers before anything else runs on this CPU.
+        let signalMask = t.lock().signalMask.0;
+        let state = ThreadCoreState::New(self, t.ThreadID(), signalMask);
+        t.prepareGroupExit(es, Some(state));
+    }
+
+    // DumpCore writes the thread group's core file if the task started a core
+    // dump, after waiting for its siblings to record their registers.
+    pub fn DumpCore(&self) {
+        let t = self.Thread();
+        let tg = t.ThreadGroup();
+        let pidns = tg.PIDNamespace();
+        let owner = pidns.lock().owner.clone();
+        let lock = tg.lock().signalLock.clone();
+
+        let wait;
+        {
+            let _r = owner.ReadLock();
+            let _s = lock.lock();
+
+            let dumper = match &tg.lock().coreDump {
+                None => return,
+                Some(ref dump) => dump.dumper.Upgrade(),
+            };
+
+            if dumper != Some(t.clone()) {
+                return
+            }
+
+            // The last sibling to exit will wake t.
+            wait = tg.lock().activeTasks > 1;
+            if wait {
+                t.lock().beginInternalStopLocked(&Arc::new(CoreDumpStop {}));
+            }
+        }
+
+        if wait {
+            self.DoStop();
+        }
+
+        let dump = {
+            let _s = lock.lock();
+            tg.lock().coreDump.take()
+        };
+
+        let dump = match dump {
+            None => return,
+            Some(d) => d,
+        };
+
+        // As on Linux, a SIGKILL aborts the dump.
+        if t.killed() {
+            return
+        }
+
+        match self.WriteCore(&dump) {
+            Err(e) => {
+                info!("core dump of thread group {} failed: {:?}", tg.ID(), e);
+            }
+            Ok(()) => {
+                let _s = lock.lock();
+                tg.lock().exitStatus.CoreDumped = true;
+                t.lock().exitStatus.CoreDumped = true;
+            }
+        }
+    }
+
+    // openCoreFile opens the core file for writing, creating it if needed.
+    // Like Linux, it doesn't follow a symlink and only writes a regular file.
+    fn openCoreFile(&self) -> Result<File> {
+        let root = self.Root();
+        let cwd = self.Workdir();
+        let flags = FileFlags {
+            Write: true,
+            LargeFile: true,
+            ..Default::default()
+        };
+
+        let mut remainingTraversals = MAX_SYMLINK_TRAVERSALS;
+        match self.mountNS.FindLink(self, &root, Some(cwd.clone()), CORE_FILE_NAME, &mut remainingTraversals) {
+            Ok(d) => {
+                let mut inode = d.Inode();
+                if !inode.StableAttr().IsRegular() {
+                    return Err(Error::SysError(SysErr::EPERM))
+                }
+
+                d.CheckPermission(self, &PermMask {
+                    write: true,
+                    ..Default::default()
+                })?;
+
+                inode.Truncate(self, &d, 0)?;
+                return inode.GetFile(self, &d, &flags)
+            }
+            // See createAt for why EPERM may mean that the file doesn't exist.
+            Err(Error::SysError(SysErr::ENOENT)) |
+            Err(Error::SysError(SysErr::EPERM)) => {
+                cwd.CheckPermission(self, &PermMask {
+                    write: true,
+                    execute: true,
+                    ..Default::default()
+                })?;
+
+                let perms = FilePermissions::FromMode(FileMode(0o600 & !self.Umask() as u16));
+                return cwd.Create(self, &root, CORE_FILE_NAME, &flags, &perms)
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    // WriteCore writes an ELF core file for dump: a PT_NOTE segment with the
+    // process and per-thread notes that fs/binfmt_elf.c writes on x86_64,
+    // and a PT_LOAD segment for each mapping. The file is cut short at
+    // RLIMIT_CORE.
+    pub fn WriteCore(&self, dump: &CoreDump) -> Result<()> {
+        let t = self.Thread();
+        let limit = t.ThreadGroup().Limits().Get(LimitType::Core).Cur;
+        let segments = self.mm.CoreSegments();
+        let notes = self.coreNotes(dump);
+
+        let phnum = segments.len() + 1;
+        let headerSize = size_of::<Elf64Ehdr>() + phnum * size_of::<Elf64Phdr>();
+        let notesOffset = headerSize as u64;
+        let dataOffset = Addr(notesOffset + notes.len() as u64).MustRoundUp().0;
+        let mut offset = dataOffset;
+
+        let mut ident = [0; 16];
+        ident[..4].copy_from_slice(b"\x7fELF");
+        ident[4] = 2; // ELFCLASS64
+        ident[5] = 1; // ELFDATA2LSB
+        ident[6] = 1; // EV_CURRENT
+
+        let ehdr = Elf64Ehdr {
+            ident: ident,
+            typ: ET_CORE,
+            machine: EM_X86_64,
+            version: 1,
+            phoff: size_of::<Elf64Ehdr>() as u64,
+            ehsize: size_of::<Elf64Ehdr>() as u16,
+            phentsize: size_of::<Elf64Phdr>() as u16,
+            phnum: phnum as u16,
+            ..Default::default()
+        };
+
+        let mut header = Vec::with_capacity(headerSize + notes.len());
+        header.extend_from_slice(Bytes(&ehdr));
+        header.extend_from_slice(Bytes(&Elf64Phdr {
+            typ: PT_NOTE,
+            offset: notesOffset,
+            filesz: notes.len() as u64,
+            align: 4,
+            ..Default::default()
+        }));
+
+        for seg in &segments {
+            let mut flags = 0;
+            if seg.perms.Read() {
+                flags |= PF_R;
+            }
+            if seg.perms.Write() {
+                flags |= PF_W;
+            }
+            if seg.perms.Exec() {
+                flags |= PF_X;
+            }
+
+            let filesz = if seg.dump { seg.range.Len() } else { 0 };
+            header.extend_from_slice(Bytes(&Elf64Phdr {
+                typ: PT_LOAD,
+                flags: flags,
+                offset: offset,
+                vaddr: seg.range.Start(),
+                filesz: filesz,
+                memsz: seg.range.Len(),
+                align: MemoryDef::PAGE_SIZE,
+                ..Default::default()
+            }));
+            offset += filesz;
+        }
+        header.extend_from_slice(&notes);
+
+        let file = self.openCoreFile()?;
+        let mut w = CoreWriter {
+            task: self,
+            file: file,
+            limit: limit,
+        };
+
+        w.WriteAt(&header, 0)?;
+
+        offset = dataOffset;
+        for seg in &segments {
+            if !seg.dump {
+                continue;
+            }
+
+            w.WriteMemory(seg.range.Start(), seg.range.Len(), offset)?;
+            offset += seg.range.Len();
+        }
+
+        return Ok(())
+    }
+
+    // coreNotes returns the PT_NOTE segment for dump: NT_PRPSINFO, then
+    // NT_PRSTATUS, NT_PRFPREG and NT_X86_XSTATE for each thread, the
+    // dumping thread first.
+    fn coreNotes(&self, dump: &CoreDump) -> Vec<u8> {
+        let t = self.Thread();
+        let tg = t.ThreadGroup();
+        let pidns = t.PIDNamespace();
+
+        let pid = pidns.IDOfThreadGroup(&tg);
+        let ppid = match t.Parent() {
+            None => 0,
+            Some(p) => pidns.IDOfThreadGroup(&p.ThreadGroup()),
+        };
+        let pgrp = match tg.ProcessGroup() {
+            None => 0,
+            Some(pg) => pidns.IDOfProcessGroup(&pg),
+        };
+        let sid = match tg.Session() {
+            None => 0,
+            Some(s) => pidns.IDOfSession(&s),
+        };
+        let signo = tg.lock().exitStatus.Signo;
+
+        let creds = t.Credentials();
+        let userns = t.UserNamespace();
+        let uid = creds.lock().RealKUID.In(&userns).OrOverflow().0;
+        let gid = creds.lock().RealKGID.In(&userns).OrOverflow().0;
+
+        let mut psinfo = ElfPrpsinfo {
+            state: 0,
+            sname: b'R',
+            zomb: 0,
+            nice: 0,
+            pad0: 0,
+            flag: 0,
+            uid: uid,
+            gid: gid,
+            pid: pid,
+            ppid: ppid,
+            pgrp: pgrp,
+            sid: sid,
+            fname: [0; 16],
+            psargs: [0; 80],
+        };
+
+        let name = t.Name();
+        let n = core::cmp::min(name.len(), psinfo.fname.len() - 1);
+        psinfo.fname[..n].copy_from_slice(&name.as_bytes()[..n]);
+
+        // psargs is the start of the command line with the arguments
+        // separated by spaces.
+        let argv = self.mm.metadata.lock().argv;
+        let n = core::cmp::min(argv.Len() as usize, psinfo.psargs.len() - 1);
+        if n > 0 && self.mm.CopyDataIn(self, argv.Start(), &mut psinfo.psargs[0] as *mut _ as u64, n).is_ok() {
+            for i in 0..n - 1 {
+                if psinfo.psargs[i] == 0 {
+                    psinfo.psargs[i] = b' ';
+                }
+            }
+        }
+
+        let mut notes = Vec::new();
+        AppendNote(&mut notes, "CORE", NT_PRPSINFO, Bytes(&psinfo));
+
+        let xsave = HostFeatureSet().UseXsave();
+        for thread in &dump.threads {
+            let status = ElfPrstatus {
+                signo: signo,
+                cursig: signo as i16,
+                sighold: thread.signalMask,
+                pid: thread.tid,
+                ppid: ppid,
+                pgrp: pgrp,
+                sid: sid,
+                reg: thread.regs,
+                fpvalid: 1,
+                ..Default::default()
+            };
+            AppendNote(&mut notes, "CORE", NT_PRSTATUS, Bytes(&status));
+
+            let fpstate = &thread.fpstate;
+            AppendNote(&mut notes, "CORE", NT_PRFPREG, &fpstate.data[..FXSAVE_SIZE]);
+
+            if xsave {
+                let mut xstate = fpstate.data[..fpstate.size].to_vec();
+                let sw = &mut xstate[XSTATE_SW_BYTES_OFFSET..FXSAVE_SIZE];
+                for b in sw.iter_mut() {
+                    *b = 0;
+                }
+                sw[0..8].copy_from_slice(&HostFeatureSet().ValidXCR0Mask().to_ne_bytes());
+                AppendNote(&mut notes, "LINUX", NT_X86_XSTATE, &xstate);
+            }
+        }
+
+        return notes;
+    }
+}
+
+// CoreWriter writes a core file, stopping at the RLIMIT_CORE limit.
+struct CoreWriter<'a> {
+    task: &'a Task,
+    file: File,
+    limit: u64,
+}
+
+impl<'a> CoreWriter<'a> {
+    // WriteAt writes buf at offset, failing with EFBIG if it doesn't fit
+    // under the limit.
+    fn WriteAt(&mut self, buf: &[u8], offset: u64) -> Result<()> {
+        if offset >= self.limit {
+            return Err(Error::SysError(SysErr::EFBIG))
+        }
+
+        let mut buf = buf;
+        let mut offset = offset;
+        let truncated = self.limit - offset < buf.len() as u64;
+        if truncated {
+            buf = &buf[..(self.limit - offset) as usize];
+        }
+
+        while buf.len() > 0 {
+            let n = self.file.Pwritev(self.task, &[IoVec::New(buf)], offset as i64)?;
+            if n <= 0 {
+                return Err(Error::SysError(SysErr::EIO))
+            }
+            buf = &buf[n as usize..];
+            offset += n as u64;
+        }
+
+        if truncated {
+            return Err(Error::SysError(SysErr::EFBIG))
+        }
+
+        return Ok(())
+    }
+
+    // WriteMemory writes len bytes of application memory at addr to the
+    // file at offset. Pages that can't be read are written as zeros.
+    fn WriteMemory(&mut self, addr: u64, len: u64, offset: u64) -> Result<()> {
+        const CHUNK: u64 = 16 * MemoryDef::PAGE_SIZE;
+
+        let mm = self.task.mm.clone();
+        let mut buf: Vec<u8> = Vec::with_capacity(CHUNK as usize);
+        let mut done = 0;
+        while done < len {
+            let n = core::cmp::min(CHUNK, len - done);
+            buf.clear();
+            buf.resize(n as usize, 0);
+
+            let ptr = buf.as_mut_ptr() as u64;
+            if mm.CopyDataIn(self.task, addr + done, ptr, n as usize).is_err() {
+                let mut i = 0;
+                while i < n {
+                    if mm.CopyDataIn(self.task, addr + done + i, ptr + i, MemoryDef::PAGE_SIZE as usize).is_err() {
+                        for b in &mut buf[i as usize..(i + MemoryDef::PAGE_SIZE) as usize] {
+                            *b = 0;
+                        }
+                    }
+                    i += MemoryDef::PAGE_SIZE;
+                }
+            }
+
+            self.WriteAt(&buf, offset + done)?;
+            done += n;
+        }
+
+        return Ok(())
+    }
+}
+
+// AppendNote appends an ELF note to buf, with its name and descriptor padded
+// to 4 bytes.
+fn AppendNote(buf: &mut Vec<u8>, name: &str, typ: u32, desc: &[u8]) {
+    buf.extend_from_slice(&(name.len() as u32 + 1).to_ne_bytes());
+    buf.extend_from_slice(&(desc.len() as u32).to_ne_bytes());
+    buf.extend_from_slice(&typ.to_ne_bytes());
+
+    buf.extend_from_slice(name.as_bytes());
+    buf.push(0);
+    while buf.len() % 4 != 0 {
+        buf.push(0);
+    }
+
+    buf.extend_from_slice(desc);
+    while buf.len() % 4 != 0 {
+        buf.push(0);
+    }
+}
+
+fn Bytes<T: Sized + Copy>(obj: &T) -> &[u8] {
+    return unsafe {
+        slice::from_raw_parts(obj as *const T as *const u8, size_of::<T>())
+    }
+}
+
+// Elf64Ehdr is Linux's Elf64_Ehdr.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct Elf64Ehdr {
+    ident: [u8; 16],
+    typ: u16,
+    machine: u16,
+    version: u32,
+    entry: u64,
+    phoff: u64,
+    shoff: u64,
+    flags: u32,
+    ehsize: u16,
+    phentsize: u16,
+    phnum: u16,
+    shentsize: u16,
+    shnum: u16,
+    shstrndx: u16,
+}
+
+// Elf64Phdr is Linux's Elf64_Phdr.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct Elf64Phdr {
+    typ: u32,
+    flags: u32,
+    offset: u64,
+    vaddr: u64,
+    paddr: u64,
+    filesz: u64,
+    memsz: u64,
+    align: u64,
+}
+
+// ElfPrstatus is Linux's struct elf_prstatus on x86_64, the descriptor of
+// an NT_PRSTATUS note. The times aren't filled in.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct ElfPrstatus {
+    signo: i32,
+    code: i32,
+    errno: i32,
+    cursig: i16,
+    pad0: u16,
+    sigpend: u64,
+    sighold: u64,
+    pid: i32,
+    ppid: i32,
+    pgrp: i32,
+    sid: i32,
+    utime: [i64; 2],
+    stime: [i64; 2],
+    cutime: [i64; 2],
+    cstime: [i64; 2],
+    reg: PtraceRegs,
+    fpvalid: i32,
+    pad1: i32,
+}
+
+// ElfPrpsinfo is Linux's struct elf_prpsinfo on x86_64, the descriptor of
+// an NT_PRPSINFO note.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct ElfPrpsinfo {
+    state: u8,
+    sname: u8,
+    zomb: u8,
+    nice: i8,
+    pad0: u32,
+    flag: u64,
+    uid: u32,
+    gid: u32,
+    pid: i32,
+    ppid: i32,
+    pgrp: i32,
+    sid: i32,
+    fname: [u8; 16],
+    psargs: [u8; 80],
+}
@@ -26,6 +26,7 @@ use super::super::qlib::auth::id::*;
 use super::super::SignalDef::*;
 use super::super::task::*;
 use super::task_stop::*;
+use super::task_coredump::*;
 use super::super::qlib::perf_tunning::*;
 
 // An ExitStatus is a value communicated from an exiting task or thread group
@@ -39,6 +40,9 @@ pub struct ExitStatus {
     // Signo is the signal that caused the exit. If the exit was not caused by
     // a signal, Signo is 0.
     pub Signo: i32,
+
+    // CoreDumped is true if the signal that caused the exit dumped core.
+    pub CoreDumped: bool,
 }
 
 impl ExitStatus {
@@ -46,6 +50,7 @@ impl ExitStatus {
         return ExitStatus {
             Code: code,
             Signo: signo,
+            CoreDumped: false,
         }
     }
 
@@ -58,7 +63,12 @@ impl ExitStatus {
     // Status returns the numeric representation of the ExitStatus returned by e.g.
     // the wait4() system call.
     pub fn Status(&self) -> u32 {
-        return (((self.Code as u32) & 0xff) << 8) | ((self.Signo as u32) & 0xff);
+        let mut status = (((self.Code as u32) & 0xff) << 8) | ((self.Signo as u32) & 0xff);
+        if self.CoreDumped {
+            status |= WaitStatus::CORE;
+        }
+
+        return status;
     }
 
     // ShellExitCode returns the numeric exit code that Bash would return for an
@@ -233,6 +243,13 @@ impl Thread {
     //
     // Preconditions: The caller must be running on the task goroutine.
     pub fn PrepareGroupExit(&self, es: ExitStatus) {
+        self.prepareGroupExit(es, None);
+    }
+
+    // prepareGroupExit implements PrepareGroupExit. If dump is not None and
+    // t starts the group exit, t's siblings record their registers for the
+    // core dump that t writes before exiting, and dump holds t's own.
+    pub fn prepareGroupExit(&self, es: ExitStatus, dump: Option<ThreadCoreState>) {
         let tg = self.lock().tg.clone();
         let lock = tg.lock().signalLock.clone();
         let _s = lock.lock();
@@ -257,6 +274,10 @@ impl Thread {
         tg.lock().exiting = true;
         tg.lock().exitStatus = es;
         self.lock().exitStatus = es;
+        if let Some(state) = dump {
+            tg.lock().coreDump = Some(CoreDump::New(self, state));
+        }
+
         let tasks : Vec<Thread> = tg.lock().tasks.iter().cloned().collect();
         for sibling in &tasks {
             if *sibling != *self {
@@ -285,6 +306,8 @@ impl Thread {
             tg.lock().activeTasks -= 1;
             last = tg.lock().activeTasks == 0;
 
+            self.exitCoreDumpLocked(&tg);
+
             // Ensure that someone will handle the signals we can't.
             self.setSignalMaskLocked(SignalSet(!0));
 
@@ -466,7 +489,7 @@ impl Thread {
         let children: Vec<Thread> = parent.lock().children.iter().cloned().collect();
         for child in &children {
             let child = child.clone();
-            if !opts.matchesTask(&child, &pidns) {
+            if !opts.matchesTask(&child, &pidns, false) {
                 continue;
             }
 
@@ -510,6 +533,25 @@ impl Thread {
             }
         }
 
+        let tracees: Vec<Thread> = parent.lock().ptraceTracees.iter().cloned().collect();
+        for tracee in &tracees {
+            if !opts.matchesTask(tracee, &pidns, true) {
+                continue;
+            }
+
+            if tracee.lock().exitState >= TaskExitState::TaskExitInitiated {
+                continue;
+            }
+
+            anyWaitableTasks = true;
+            if opts.Events & EVENT_TRACEE_STOP != 0 {
+                let wr = self.waitCollectTraceeStopLocked(tracee, opts);
+                if wr.is_some() {
+                    return (wr, anyWaitableTasks)
+                }
+            }
+        }
+
         return (None, anyWaitableTasks)
     }
 
@@ -540,6 +582,14 @@ impl Thread {
 
         let mut status = target.lock().exitStatus.Status();
 
+        // Both a consuming and a non-consuming wait report the group exit
+        // code if one is available, see kernel/exit.c:wait_task_zombie().
+        if targetTg.lock().exiting {
+            status = targetTg.lock().exitStatus.Status();
+        }
+
+        // A non-consuming wait (WNOWAIT) leaves the zombie to be reaped by a
+        // later wait.
         if !opts.ConsumeEvent {
             return Some(WaitResult {
                 Thread: target.clone(),
@@ -550,14 +600,6 @@ impl Thread {
             })
         }
 
-        // Surprisingly, the exit status reported by a non-consuming wait can
-        // differ from that reported by a consuming wait; the latter will return
-        // the group exit code if one is available.
-
-        if targetTg.lock().exiting {
-            status = targetTg.lock().exitStatus.Status();
-        }
-
         let targetParent = target.lock().parent.clone();
         let exitParentNotified = target.lock().exitParentNotified;
 
@@ -690,6 +732,16 @@ impl Thread {
         if !exitTracerNotified {
             t.lock().exitTracerNotified = true;
             t.lock().exitTracerAcked = true;
+
+            // The exit is reported to the parent rather than the tracer, so
+            // the tracer is simply detached.
+            let tracer = t.lock().ptraceTracer.take();
+            match tracer {
+                None => (),
+                Some(tracer) => {
+                    tracer.lock().ptraceTracees.remove(&t);
+                }
+            }
         }
 
         let exitTracerAcked = t.lock().exitTracerAcked;
@@ -703,6 +755,8 @@ impl Thread {
                 t.lock().exitParentAcked = true;
             } else if tg.lock().tasksCount == 1 {
                 t.lock().exitParentNotified = true;
+                // The thread group has exited, wake up the pidfd pollers.
+                tg.lock().exitQueue.Notify(EVENT_IN);
                 let parent = t.lock().parent.clone();
                 if parent.is_none() {
                     t.lock().exitParentAcked = true;
@@ -840,13 +894,23 @@ impl Thread {
 
         info.SigChld().uid = kuid.In(&userns).OrOverflow().0;
 
-        let signaled = self.lock().exitStatus.Signaled();
-        if signaled {
+        // A group exit reports the group's status, which says whether the
+        // signal dumped core, see kernel/signal.c:do_notify_parent.
+        let mut es = self.lock().exitStatus;
+        let ownTg = self.lock().tg.clone();
+        if ownTg.lock().exiting {
+            es = ownTg.lock().exitStatus;
+        }
+
+        if es.CoreDumped {
+            info.Code = SignalInfo::CLD_DUMPED;
+            info.SigChld().status = es.Signo;
+        } else if es.Signaled() {
             info.Code = SignalInfo::CLD_KILLED;
-            info.SigChld().status = self.lock().exitStatus.Signo;
+            info.SigChld().status = es.Signo;
         } else {
             info.Code = SignalInfo::CLD_EXITED;
-            info.SigChld().status = self.lock().exitStatus.Code;
+            info.SigChld().status = es.Code;
         }
 
         return info;
@@ -907,6 +971,7 @@ impl Thread {
             tg.release();
         }
 
+        self.exitPtrace();
         self.exitChildren();
         //self.ExitNotify();
     }
@@ -1084,7 +1149,7 @@ pub struct WaitOptions {
 
 impl WaitOptions {
     // Preconditions: The TaskSet mutex must be locked (for reading or writing).
-    pub fn matchesTask(&self, t: &Thread, pidns: &PIDNamespace) -> bool {
+    pub fn matchesTask(&self, t: &Thread, pidns: &PIDNamespace, tracee: bool) -> bool {
         if self.SpecificTID != 0 { // && self.SpecificTID != *pidns.lock().tids.get(t).unwrap() {
             let id = match pidns.lock().tids.get(t) {
                 None => {
@@ -1105,6 +1170,11 @@ impl WaitOptions {
             return false
         }
 
+        // Tracees are waitable regardless of __WCLONE and __WALL.
+        if tracee {
+            return true
+        }
+
         let leader = tg.lock().leader.Upgrade();
         if Some(t.clone()) == leader && tg.lock().terminationSignal.0 == Signal::SIGCHLD {
             return self.NonCloneTasks
@@ -1139,14 +1209,30 @@ pub struct WaitResult {
 impl Task {
     pub fn RunExit(&mut self) -> TaskRunState {
         let t = self.Thread();
+        self.DumpCore();
         t.ExitMain();
         return TaskRunState::RunExitNotify;
     }
 
+    // ClearChildTID zeroes the clear_child_tid address and wakes one futex
+    // waiter on it, which is how pthread_join finds out that the thread is
+    // gone, see kernel/fork.c:mm_release. A bad address is ignored.
+    pub fn ClearChildTID(&mut self) {
+        let addr = match self.tidInfo.clear_child_tid.take() {
+            None => return,
+            Some(addr) => addr,
+        };
+
+        if self.CopyOutObj(&(0 as u32), addr).is_ok() {
+            self.futexMgr.Wake(self, addr, false, !0, 1).ok();
+        }
+    }
+
     pub fn RunExitNotify(&mut self) -> TaskRunState {
         let t = self.Thread();
 
         //info!("RunExitNotify 1 [{:x}]", self.taskId);
+        self.ClearChildTID();
         self.Exit();
 
         //info!("RunExitNotify 2 [{:x}]", self.taskId);
@@ -1169,18 +1255,7 @@ impl Task {
             panic!("Exit from wait thread!")
         }
 
-        if !t.Signaled() {
-            match self.tidInfo.clear_child_tid {
-                None => {
-                    //println!("there is no clear_child_tid");
-                }
-                Some(addr) => {
-                    self.CopyOutObj(&(0 as u32), addr).unwrap();
-                    self.futexMgr.Wake(self, addr, false, !0, 1).unwrap();
-                }
-            }
-        }
-
+        self.ClearChildTID();
         t.ExitNotify();
 
         //won't reach here
@@ -37,11 +37,14 @@ impl Thread {
             Ok(p) => p,
         };
 
-        let mut next = rl.List;
+        // Bit 0 of a list entry marks a PI futex, it isn't part of the
+        // pointer. It's carried over to the lock address.
+        let mut next = rl.List & !1;
+        let mut nextPI = rl.List & 1;
         let mut done = 0;
         let mut pendingLockAddr = 0;
         if rl.ListOpPending != 0 {
-            pendingLockAddr = rl.ListOpPending + rl.FutexOffset;
+            pendingLockAddr = ((rl.ListOpPending & !1) as i64 + rl.FutexOffset as i64) as u64 | (rl.ListOpPending & 1);
         }
 
         // Wake up normal elements.
@@ -49,27 +52,29 @@ impl Thread {
             // We traverse to the next element of the list before we
             // actually wake anything. This prevents the race where waking
             // this futex causes a modification of the list.
-            let thisLockAddr = (next as i64 + rl.FutexOffset as i64) as u64;
+            let thisLockAddr = (next as i64 + rl.FutexOffset as i64) as u64 | nextPI;
 
             // Try to decode the next element in the list before waking the
             // current futex. But don't check the error until after we've
             // woken the current futex. Linux does it in this order too
-            next = match task.CopyInObj(next) {
+            let entry : u64 = match task.CopyInObj(next) {
                 Err(_) => {
                     if thisLockAddr != pendingLockAddr {
-                        self.WakeRobustListOne(task, thisLockAddr)
+                        self.WakeRobustListOne(task, thisLockAddr, false)
                     };
 
                     // ignore error
                     return;
                 }
-                Ok(next) => {
+                Ok(entry) => {
                     if thisLockAddr != pendingLockAddr {
-                        self.WakeRobustListOne(task, thisLockAddr)
+                        self.WakeRobustListOne(task, thisLockAddr, false)
                     };
-                    next
+                    entry
                 }
             };
+            next = entry & !1;
+            nextPI = entry & 1;
 
             // This is a user structure, so it could be a massive list, or
             // even contain a loop if they are trying to mess with us. We
@@ -82,12 +87,13 @@ impl Thread {
 
         // Is there a pending entry to wake?
         if pendingLockAddr != 0 {
-            self.WakeRobustListOne(task, pendingLockAddr)
+            self.WakeRobustListOne(task, pendingLockAddr, true)
         }
     }
 
-    // wakeRobustListOne wakes a single futex from the robust list.
-    pub fn WakeRobustListOne(&self, task: &Task, addr: u64)  {
+    // wakeRobustListOne wakes a single futex from the robust list. pending is
+    // true for the entry of a lock operation the thread was in the middle of.
+    pub fn WakeRobustListOne(&self, task: &Task, addr: u64, pending: bool)  {
         // Bit 0 in address signals PI futex.
         let pi = addr & 1 == 1;
         let addr = addr & !1;
@@ -100,6 +106,14 @@ impl Thread {
             Ok(f) => f,
         };
 
+        // A pending lock which was released, but not yet removed from the
+        // list, may still have a waiter which the unlock didn't wake, see
+        // kernel/futex/core.c:handle_futex_death.
+        if pending && !pi && f == 0 {
+            task.futexMgr.Wake(task, addr, false, FUTEX_BITSET_MATCH_ANY, 1).ok();
+            return
+        }
+
         let tid = self.ThreadID() as u32;
 
         loop {
@@ -123,9 +137,10 @@ impl Thread {
                 continue;
             }
 
-            // Wake waiters if there are any.
+            // Wake waiters if there are any. The wake is never private, as in
+            // Linux, so glibc waits on robust mutexes with shared futexes.
             if f & FUTEX_WAITERS != 0 {
-                let private = f & FUTEX_WAITERS != 0;
+                let private = false;
                 if pi {
                     task.futexMgr.UnlockPI(task, addr, tid, private).ok();
                     return
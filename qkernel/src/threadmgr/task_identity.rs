@@ -313,6 +313,44 @@ impl Thread {
         return self.lock().creds.HasCapability(cp);
     }
 
+    // CanTrace checks that t is permitted to access target's memory, as
+    // required for a ptrace attach. Equivalent to
+    // kernel/ptrace.c:__ptrace_may_access with PTRACE_MODE_ATTACH_REALCREDS.
+    pub fn CanTrace(&self, target: &Thread) -> bool {
+        // "1. If the calling thread and the target thread are in the same thread
+        // group, access is always allowed." - ptrace(2)
+        if self.ThreadGroup() == target.ThreadGroup() {
+            return true
+        }
+
+        // "3. Deny access if neither of the following is true:
+        //
+        // - The real, effective, and saved-set user IDs of the target match the
+        // caller's user ID, and the real, effective, and saved-set group IDs of
+        // the target match the caller's group ID.
+        //
+        // - The caller has the CAP_SYS_PTRACE capability in the user namespace
+        // of the target." - ptrace(2)
+        if self.HasCapabilityIn(Capability::CAP_SYS_PTRACE, &target.UserNamespace()) {
+            return true
+        }
+
+        let creds = self.Credentials();
+        let tcreds = target.Credentials();
+        if creds == tcreds {
+            return true
+        }
+
+        let c = creds.lock();
+        let tc = tcreds.lock();
+        return c.RealKUID == tc.RealKUID &&
+            c.RealKUID == tc.EffectiveKUID &&
+            c.RealKUID == tc.SavedKUID &&
+            c.RealKGID == tc.RealKGID &&
+            c.RealKGID == tc.EffectiveKGID &&
+            c.RealKGID == tc.SavedKGID
+    }
+
     pub fn SetUID(&self, uid: UID) -> Result<()> {
         if !uid.Ok() {
             return Err(Error::SysError(SysErr::EINVAL))
@@ -0,0 +1,381 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use super::super::qlib::common::*;
+use super::super::qlib::linux_def::*;
+use super::super::qlib::auth::id::*;
+use super::super::task::*;
+use super::super::threadmgr::thread::*;
+use super::super::SignalDef::*;
+use super::task_exit::*;
+use super::task_stop::*;
+
+// ptraceStop is a TaskStop placed on tasks in a ptrace-stop.
+pub struct PtraceStop {}
+
+impl TaskStop for PtraceStop {
+    fn Type(&self) -> TaskStopType {
+        return TaskStopType::PTRACESTOP;
+    }
+
+    fn Killable(&self) -> bool {
+        return true;
+    }
+}
+
+impl Thread {
+    // Tracer returns t's ptrace Tracer.
+    pub fn Tracer(&self) -> Option<Thread> {
+        return self.lock().ptraceTracer.clone();
+    }
+
+    // hasTracer returns true if t has a ptrace tracer attached.
+    pub fn hasTracer(&self) -> bool {
+        return self.lock().ptraceTracer.is_some();
+    }
+
+    // ptraceStopped returns true if t is in a ptrace-stop.
+    pub fn ptraceStopped(&self) -> bool {
+        let tg = self.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+        return self.ptraceStoppedLocked()
+    }
+
+    // ptraceStoppedLocked returns true if t is in a ptrace-stop.
+    //
+    // Preconditions: The signal mutex must be locked.
+    pub fn ptraceStoppedLocked(&self) -> bool {
+        return match &self.lock().stop {
+            None => false,
+            Some(stop) => stop.Type() == TaskStopType::PTRACESTOP,
+        }
+    }
+
+    // ptraceSignal is called by the task goroutine with a signal it has just
+    // dequeued, and returns true if the task entered a signal-delivery-stop
+    // for it. See kernel/signal.c:ptrace_signal().
+    pub fn ptraceSignal(&self, info: &SignalInfo) -> bool {
+        if info.Signo == Signal::SIGKILL {
+            return false
+        }
+
+        let tg = self.ThreadGroup();
+        let pidns = tg.PIDNamespace();
+        let owner = pidns.lock().owner.clone();
+        let _r = owner.ReadLock();
+
+        let tracer = match self.Tracer() {
+            None => return false,
+            Some(t) => t,
+        };
+
+        let lock = tg.lock().signalLock.clone();
+        let stopped = {
+            let _s = lock.lock();
+            // The tracer might change this signal into a stop signal, in
+            // which case any SIGCONT received after the signal was originally
+            // dequeued should cancel it. This is consistent with Linux.
+            tg.lock().groupStopDequeued = true;
+
+            let mut t = self.lock();
+            t.ptraceCode = info.Signo;
+            t.ptraceSiginfo = Some(Box::new(*info));
+            t.beginPtraceStopLocked()
+        };
+
+        if stopped {
+            tracer.signalStop(self, SignalInfo::CLD_TRAPPED, info.Signo);
+            let tracertg = tracer.ThreadGroup();
+            tracertg.lock().eventQueue.Notify(EVENT_TRACEE_STOP);
+        }
+
+        return true
+    }
+
+    // ptraceUnstop ends t's ptrace-stop, resuming it with signal sig (0 to
+    // suppress the signal that caused the stop).
+    //
+    // Preconditions: The TaskSet mutex must be locked.
+    pub fn ptraceUnstop(&self, sig: Signal) -> Result<()> {
+        if sig.0 != 0 && !sig.IsValid() {
+            return Err(Error::SysError(SysErr::EIO))
+        }
+
+        let tg = self.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        if !self.ptraceStoppedLocked() {
+            return Err(Error::SysError(SysErr::ESRCH))
+        }
+
+        let mut t = self.lock();
+        t.ptraceCode = sig.0;
+        t.endInternalStopLocked();
+        return Ok(())
+    }
+
+    // PtraceTraceme makes t's parent its tracer, as for
+    // ptrace(PTRACE_TRACEME).
+    pub fn PtraceTraceme(&self) -> Result<()> {
+        let tg = self.ThreadGroup();
+        let pidns = tg.PIDNamespace();
+        let owner = pidns.lock().owner.clone();
+        let _l = owner.WriteLock();
+
+        if self.hasTracer() {
+            return Err(Error::SysError(SysErr::EPERM))
+        }
+
+        // The init task has no parent to trace it.
+        let parent = match self.lock().parent.clone() {
+            None => return Err(Error::SysError(SysErr::EPERM)),
+            Some(p) => p,
+        };
+
+        if parent.lock().exitState >= TaskExitState::TaskExitZombie {
+            return Err(Error::SysError(SysErr::EPERM))
+        }
+
+        self.lock().ptraceTracer = Some(parent.clone());
+        parent.lock().ptraceTracees.insert(self.clone());
+        return Ok(())
+    }
+
+    // PtraceAttach makes t the tracer of target and stops it with SIGSTOP, as
+    // for ptrace(PTRACE_ATTACH).
+    pub fn PtraceAttach(&self, target: &Thread) -> Result<()> {
+        if self.ThreadGroup() == target.ThreadGroup() {
+            return Err(Error::SysError(SysErr::EPERM))
+        }
+
+        if !self.CanTrace(target) {
+            return Err(Error::SysError(SysErr::EPERM))
+        }
+
+        let tg = self.ThreadGroup();
+        let pidns = tg.PIDNamespace();
+        let owner = pidns.lock().owner.clone();
+        let _l = owner.WriteLock();
+
+        if target.hasTracer() {
+            return Err(Error::SysError(SysErr::EPERM))
+        }
+
+        // Attaching to zombies and dead tasks is not permitted; the exit
+        // notification logic relies on this.
+        if target.lock().exitState >= TaskExitState::TaskExitZombie {
+            return Err(Error::SysError(SysErr::EPERM))
+        }
+
+        target.lock().ptraceTracer = Some(self.clone());
+        self.lock().ptraceTracees.insert(target.clone());
+
+        let targettg = target.ThreadGroup();
+        let lock = targettg.lock().signalLock.clone();
+        let _s = lock.lock();
+        target.sendSignalLocked(&SignalInfo {
+            Signo: Signal::SIGSTOP,
+            Code: SignalInfo::SIGNAL_INFO_USER,
+            ..Default::default()
+        }, false)?;
+
+        return Ok(())
+    }
+
+    // ptraceDetachLocked detaches t from its tracee target, resuming it with
+    // signal sig if it's in a ptrace-stop.
+    //
+    // Preconditions: The TaskSet mutex must be locked for writing.
+    pub fn ptraceDetachLocked(&self, target: &Thread, sig: Signal) -> Result<()> {
+        if sig.0 != 0 && !sig.IsValid() {
+            return Err(Error::SysError(SysErr::EIO))
+        }
+
+        {
+            let tg = target.ThreadGroup();
+            let lock = tg.lock().signalLock.clone();
+            let _s = lock.lock();
+            if !target.ptraceStoppedLocked() {
+                return Err(Error::SysError(SysErr::ESRCH))
+            }
+        }
+
+        target.lock().ptraceCode = sig.0;
+        target.forgetTracerLocked();
+        self.lock().ptraceTracees.remove(target);
+        return Ok(())
+    }
+
+    // forgetTracerLocked detaches t's tracer and ends t's ptrace-stop, if any.
+    //
+    // Preconditions: The TaskSet mutex must be locked for writing.
+    pub fn forgetTracerLocked(&self) {
+        self.lock().ptraceTracer = None;
+
+        let tg = self.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+        if self.ptraceStoppedLocked() {
+            self.lock().endInternalStopLocked();
+        }
+    }
+
+    // exitPtrace is called in the exit path to detach all of t's tracees.
+    pub fn exitPtrace(&self) {
+        let tg = self.ThreadGroup();
+        let pidns = tg.PIDNamespace();
+        let owner = pidns.lock().owner.clone();
+        let _l = owner.WriteLock();
+
+        let tracees: Vec<Thread> = self.lock().ptraceTracees.iter().cloned().collect();
+        for target in &tracees {
+            // Leave ptraceCode unchanged so that if the task is ptrace-stopped,
+            // it observes the ptraceCode it set before it entered the stop.
+            target.forgetTracerLocked();
+        }
+
+        self.lock().ptraceTracees.clear();
+    }
+
+    // waitCollectTraceeStopLocked collects a ptrace-stop of the tracee target.
+    //
+    // Preconditions: The TaskSet mutex must be locked for writing.
+    pub fn waitCollectTraceeStopLocked(&self, target: &Thread, opts: &WaitOptions) -> Option<WaitResult> {
+        let targettg = target.ThreadGroup();
+        let lock = targettg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        if !target.ptraceStoppedLocked() {
+            return None;
+        }
+
+        let code = target.lock().ptraceCode;
+        if code == 0 {
+            return None;
+        }
+
+        let pidns = self.PIDNamespace();
+        let pid = pidns.IDOfTaskLocked(target);
+
+        let creds = target.Credentials();
+        let userns = self.UserNamespace();
+        let uid = creds.lock().RealKUID.In(&userns).OrOverflow();
+
+        if opts.ConsumeEvent {
+            target.lock().ptraceCode = 0;
+        }
+
+        return Some(WaitResult {
+            Thread: target.clone(),
+            TID: pid,
+            UID: uid,
+            Event: EVENT_TRACEE_STOP,
+            Status: (code as u32) << 8 | 0x7f,
+        })
+    }
+}
+
+impl ThreadInternal {
+    // beginPtraceStopLocked initiates an unfreezable ptrace-stop and returns
+    // true if the task entered it.
+    //
+    // Preconditions: The signal mutex must be locked.
+    pub fn beginPtraceStopLocked(&mut self) -> bool {
+        // This is analogous to Linux's kernel/signal.c:ptrace_stop() => ... =>
+        // kernel/sched/core.c:__schedule() => signal_pending_state() check,
+        // which is what prevents tasks from entering ptrace-stops after being
+        // killed.
+        if self.killedLocked() {
+            return false
+        }
+
+        self.beginInternalStopLocked(&Arc::new(PtraceStop {}));
+        return true
+    }
+}
+
+impl Task {
+    // RunInterruptAfterSignalDeliveryStop delivers the signal the tracer
+    // resumed a signal-delivery-stop with, if any. See
+    // kernel/signal.c:get_signal() after the ptrace_signal() call.
+    pub fn RunInterruptAfterSignalDeliveryStop(&mut self) -> TaskRunState {
+        let t = self.Thread();
+        let tg = t.ThreadGroup();
+        let pidns = tg.PIDNamespace();
+        let owner = pidns.lock().owner.clone();
+
+        let mut info = {
+            let _r = owner.ReadLock();
+            let sig = Signal(t.lock().ptraceCode);
+            let info = t.lock().ptraceSiginfo.take();
+            let mut info = match info {
+                None => return TaskRunState::RunInterrupt,
+                Some(info) => info,
+            };
+
+            if !sig.IsValid() {
+                return TaskRunState::RunInterrupt
+            }
+
+            if sig.0 != info.Signo {
+                info.Signo = sig.0;
+                info.Errno = 0;
+                info.Code = SignalInfo::SIGNAL_INFO_USER;
+                // Linux uses t->parent for the tid and uid here, which is the
+                // tracer if it hasn't detached or the real parent otherwise.
+                let parent = match t.Tracer() {
+                    None => t.lock().parent.clone(),
+                    Some(tracer) => Some(tracer),
+                };
+
+                let userns = t.UserNamespace();
+                let kill = info.Kill();
+                match parent {
+                    None => {
+                        kill.pid = 0;
+                        kill.uid = ROOT_KUID.In(&userns).OrOverflow().0;
+                    }
+                    Some(parent) => {
+                        kill.pid = pidns.IDOfTaskLocked(&parent);
+                        kill.uid = parent.Credentials().lock().RealKUID.In(&userns).OrOverflow().0;
+                    }
+                }
+            }
+
+            info
+        };
+
+        let lock = tg.lock().signalLock.clone();
+        let locker = lock.lock();
+
+        // If the signal is masked, re-queue it.
+        let sig = Signal(info.Signo);
+        if SignalSet::New(sig).0 & t.lock().signalMask.0 != 0 {
+            t.sendSignalLocked(&info, false).unwrap();
+            return TaskRunState::RunInterrupt
+        }
+
+        let sh = tg.lock().signalHandlers.clone();
+        let act = sh.DequeAct(sig);
+        core::mem::drop(locker);
+
+        return self.ThreadDeliverSignal(&info, &act);
+    }
+}
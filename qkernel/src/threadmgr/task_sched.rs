@@ -124,7 +124,7 @@ impl TaskSchedInfoInternal {
     // Preconditions: As for userTicksAt.
     pub fn sysTicksAt(&self, now: u64) -> u64 {
         if self.Timestamp < now && self.State == SchedState::RunningSys {
-            return self.UserTicks + now - self.Timestamp;
+            return self.SysTicks + now - self.Timestamp;
         }
 
         return self.SysTicks;
@@ -327,6 +327,16 @@ impl Thread {
         self.lock().niceness = n;
     }
 
+    // IOPrio returns t's I/O priority.
+    pub fn IOPrio(&self) -> u16 {
+        return self.lock().ioprio
+    }
+
+    // SetIOPrio sets t's I/O priority to ioprio.
+    pub fn SetIOPrio(&self, ioprio: u16) {
+        self.lock().ioprio = ioprio;
+    }
+
     // NumaPolicy returns t's current numa policy.
     pub fn NumaPolicy(&self) -> (i32, u64) {
         let t = self.lock();
@@ -462,7 +472,15 @@ impl TaskClock {
     }
 
     pub fn WallTimeUntil(&self, t: Time, now: Time) -> Duration {
-        return t.Sub(now)
+        // The task's CPU time can't advance faster than wall time, and is only
+        // sampled once per ClockTick, so round up to avoid kicking the timer
+        // repeatedly before the next tick.
+        let remaining = t.Sub(now);
+        if remaining <= 0 {
+            return remaining
+        }
+
+        return ((remaining + CLOCK_TICK - NANOSECOND) / CLOCK_TICK) * CLOCK_TICK
     }
 }
 
@@ -25,6 +25,7 @@ use super::super::kernel::posixtimer::*;
 use super::super::kernel::waiter::*;
 use super::super::threadmgr::thread::*;
 use super::super::threadmgr::thread_group::*;
+use super::super::threadmgr::pid_namespace::*;
 use super::super::SignalDef::*;
 //use super::super::eventchannel::*;
 use super::task_exit::*;
@@ -456,6 +457,27 @@ impl Thread {
             return Ok(())
         }
 
+        // A group signal that the sending thread can take itself is given to
+        // the sender, so that it is handled on the way back to user space from
+        // the sending syscall. "If the value of pid causes sig to be generated
+        // for the sending process, and if sig is not blocked for the calling
+        // thread ..., either sig or at least one pending unblocked signal
+        // shall be delivered to the sending thread before kill() returns." -
+        // kill(3p)
+        //
+        // Signals sent from kernel wait threads, e.g. by timers, have no
+        // sending thread.
+        let current = Task::Current();
+        if group && !current.isWaitThread {
+            if let Some(curr) = current.thread.clone() {
+                if curr != *self && curr.ThreadGroup() == tg && curr.lock().canReceiveSignalLocked(sig) {
+                    debug!("Thread[{}] Notified of self-directed signal {:?}", curr.lock().id, sig);
+                    curr.lock().interruptSelf();
+                    return Ok(())
+                }
+            }
+        }
+
         // Find a receiver to notify. Note that the task we choose to notify, if
         // any, may not be the task that actually dequeues and handles the signal;
         // e.g. a racing signal mask change may cause the notified task to become
@@ -514,6 +536,25 @@ impl Thread {
         return self.sendSignalLocked(info, false);
     }
 
+    // Tgkill sends a thread-directed signal to t, as tgkill(2) does, provided t
+    // still belongs to the thread group with ID tgid in pidns. The check is
+    // made under the signal mutex so t can't leave the group in between.
+    pub fn Tgkill(&self, pidns: &PIDNamespace, tgid: ThreadID, info: &SignalInfo) -> Result<()> {
+        let owner = pidns.lock().owner.clone();
+        let _r = owner.read();
+
+        let tg = self.lock().tg.clone();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        match pidns.lock().tgids.get(&tg) {
+            Some(id) if *id == tgid => (),
+            _ => return Err(Error::SysError(SysErr::ESRCH)),
+        }
+
+        return self.sendSignalLocked(info, false);
+    }
+
     // SendGroupSignal sends the given signal to t's thread group.
     pub fn SendGroupSignal(&self, info: &SignalInfo) -> Result<()> {
         let tg = self.lock().tg.clone();
@@ -527,11 +568,13 @@ impl Thread {
         return self.sendSignalLocked(info, true);
     }
 
-    // Sigtimedwait implements the semantics of sigtimedwait(2).
+    // Sigtimedwait implements the semantics of sigtimedwait(2). A timeout of
+    // None waits until one of the signals arrives, as sigwaitinfo(2) does,
+    // while Some(0) only polls for an already pending one.
     //
     // Preconditions: The caller must be running on the task context. t.exitState
     // < TaskExitZombie.
-    pub fn Sigtimedwait(&self, set: SignalSet, timeout: Duration) -> Result<Box<SignalInfo>> {
+    pub fn Sigtimedwait(&self, set: SignalSet, timeout: Option<Duration>) -> Result<Box<SignalInfo>> {
         // set is the set of signals we're interested in; invert it to get the set
         // of signals to block.
         let mask = SignalSet(!(set.0 & !UNBLOCKED_SIGNALS.0));
@@ -547,7 +590,7 @@ impl Thread {
                 return Ok(info.unwrap())
             }
 
-            if timeout == 0 {
+            if timeout == Some(0) {
                 return Err(Error::SysError(SysErr::EAGAIN));
             }
 
@@ -560,7 +603,7 @@ impl Thread {
         }
 
         let blocker = self.lock().blocker.clone();
-        let (_, err) = blocker.BlockWithMonoTimeout(false, Some(timeout));
+        let (_, err) = blocker.BlockWithMonoTimeout(false, timeout);
 
         {
             let _s = lock.lock();
@@ -578,6 +621,10 @@ impl Thread {
                 Err(Error::SysError(SysErr::ETIMEDOUT)) => {
                     return Err(Error::SysError(SysErr::EAGAIN));
                 }
+                // Linux doesn't restart sigtimedwait after a handler runs.
+                Err(Error::ErrInterrupted) => {
+                    return Err(Error::SysError(SysErr::EINTR));
+                }
                 Err(e) => return Err(e),
                 e => panic!("TaskExitZombie, unknow return {:?}", e)
             }
@@ -746,8 +793,12 @@ impl ThreadGroup {
         let lock = self.lock().signalLock.clone();
         let _s = lock.lock();
 
-        let leader = self.lock().leader.Upgrade();
-        return leader.unwrap().sendSignalLocked(info, true);
+        let leader = match self.lock().leader.Upgrade() {
+            None => return Err(Error::SysError(SysErr::ESRCH)),
+            Some(leader) => leader,
+        };
+
+        return leader.sendSignalLocked(info, true);
     }
 
     // SetSignalAct atomically sets the thread group's signal action for signal sig
@@ -929,6 +980,23 @@ impl Task {
             tg.lock().groupStopDequeued = true;
         }
 
+        // A traced task reports the signal to its tracer in a
+        // signal-delivery-stop, and delivers whatever signal it's resumed with.
+        if info.Signo != Signal::SIGKILL && t.hasTracer() {
+            core::mem::drop(locker);
+            if t.ptraceSignal(&info) {
+                task.DoStop();
+                return task.RunInterruptAfterSignalDeliveryStop();
+            }
+
+            // The tracer detached before the stop began.
+            let locker = lock.lock();
+            let sh = tg.lock().signalHandlers.clone();
+            let act = sh.DequeAct(Signal(info.Signo));
+            core::mem::drop(locker);
+            return task.ThreadDeliverSignal(&info, &act);
+        }
+
         let sh = tg.lock().signalHandlers.clone();
         let act = sh.DequeAct(Signal(info.Signo));
         core::mem::drop(locker);
@@ -984,10 +1052,12 @@ impl Task {
                     _ => ()
                 }
                 //Emit(&Event::UncaughtSignal(ucs)).unwrap();
-                self.Thread().PrepareGroupExit(ExitStatus {
-                    Code: 0,
-                    Signo: info.Signo,
-                });
+                let es = ExitStatus::New(0, info.Signo);
+                if sigact == SignalAction::CORE {
+                    self.PrepareGroupCoreDump(es);
+                } else {
+                    self.Thread().PrepareGroupExit(es);
+                }
 
                 return TaskRunState::RunExit;
             }
@@ -64,6 +64,12 @@ pub struct TaskConfig {
     // Niceness is the niceness of the new task.
     pub Niceness: i32,
 
+    // Personality is the personality(2) persona of the new task.
+    pub Personality: u32,
+
+    // IOPrio is the ioprio_set(2) priority of the new task.
+    pub IOPrio: u16,
+
     // If NetworkNamespaced is true, the new task should observe a non-root
     // network namespace.
     pub NetworkNamespaced: bool,
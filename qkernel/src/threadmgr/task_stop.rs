@@ -24,6 +24,7 @@ pub enum TaskStopType {
     PTRACESTOP,
     EXECSTOP,
     VFORKSTOP,
+    COREDUMPSTOP,
     OTHER,
     //DUMMY
 }
@@ -14,6 +14,7 @@
 
 use alloc::sync::Arc;
 use alloc::sync::Weak;
+use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::string::ToString;
 use ::qlib::mutex::*;
@@ -98,6 +99,17 @@ pub struct ThreadInternal {
     // children is protected by the TaskSet mutex.
     pub children: BTreeSet<Thread>,
 
+    // ptraceTracer is the task that is ptrace-attached to this one. If
+    // ptraceTracer is nil, this task is not being traced.
+    //
+    // ptraceTracer is protected by the TaskSet mutex.
+    pub ptraceTracer: Option<Thread>,
+
+    // ptraceTracees is the set of tasks that this task is ptrace-attached to.
+    //
+    // ptraceTracees is protected by the TaskSet mutex.
+    pub ptraceTracees: BTreeSet<Thread>,
+
     // If childPIDNamespace is not nil, all new tasks created by this task will
     // be members of childPIDNamespace rather than this one. (As a corollary,
     // this task becomes unable to create sibling tasks in the same thread
@@ -202,6 +214,22 @@ pub struct ThreadInternal {
     // trapNotifyPending is protected by the signal mutex.
     pub trapNotifyPending: bool,
 
+    // ptraceCode is the signal number reported to the tracer while the task
+    // is in a ptrace stop, and the signal number the tracer resumed it with
+    // (0 to suppress the signal) after the stop ends.
+    //
+    // ptraceCode is protected by the TaskSet mutex.
+    pub ptraceCode: i32,
+
+    // ptraceSiginfo is the value returned to the tracer by
+    // ptrace(PTRACE_GETSIGINFO) and modified by ptrace(PTRACE_SETSIGINFO).
+    // (Despite the name, PTRACE_PEEKSIGINFO is completely unrelated.)
+    // ptraceSiginfo is nil if the task is in a ptraced group-stop (this is
+    // required for PTRACE_GETSIGINFO to return EINVAL during such stops).
+    //
+    // ptraceSiginfo is protected by the signal mutex.
+    pub ptraceSiginfo: Option<Box<SignalInfo>>,
+
     //pub containerID: String,
 
     // This is mostly a fake cpumask just for sched_set/getaffinity as we
@@ -229,6 +257,18 @@ pub struct ThreadInternal {
     // niceness is protected by mu.
     pub niceness: i32,
 
+    // personality is the execution domain and flags set by personality(2).
+    // It's inherited by the children and preserved across execve(2).
+    //
+    // personality is protected by mu.
+    pub personality: u32,
+
+    // ioprio is the I/O scheduling class and priority set by ioprio_set(2).
+    // It's inherited by the children.
+    //
+    // ioprio is protected by mu.
+    pub ioprio: u16,
+
     // This is used to track the numa policy for the current thread. This can be
     // modified through a set_mempolicy(2) syscall. Since we always report a
     // single numa node, all policies are no-ops. We only track this information
@@ -428,6 +468,19 @@ impl Thread {
         return self.lock().memoryMgr.clone();
     }
 
+    // Personality returns t's personality(2) persona.
+    pub fn Personality(&self) -> u32 {
+        return self.lock().personality
+    }
+
+    // SetPersonality sets t's persona to p and returns the previous one.
+    pub fn SetPersonality(&self, p: u32) -> u32 {
+        let mut t = self.lock();
+        let old = t.personality;
+        t.personality = p;
+        return old
+    }
+
     pub fn Downgrade(&self) -> ThreadWeak {
         return ThreadWeak {
             uid: self.uid,
@@ -30,12 +30,16 @@ use super::super::kernel::timer::timer::Timer;
 use super::super::kernel::timer::timer::Setting;
 use super::super::kernel::posixtimer::*;
 use super::super::threadmgr::task_exit::*;
+use super::super::threadmgr::task_coredump::*;
 use super::super::qlib::linux;
 use super::super::qlib::usage::cpu::*;
 use super::super::qlib::usage::io::*;
 use super::super::kernel::signal_handler::*;
 use super::super::kernel::waiter::queue::*;
 use super::super::kernel::waiter::waitgroup::*;
+use super::super::fs::host::tty::*;
+use super::super::qlib::auth::*;
+use super::super::task::*;
 
 use super::thread::*;
 use super::threads::*;
@@ -51,6 +55,10 @@ pub struct ThreadGroupInternal {
 
     pub eventQueue: Queue,
 
+    // exitQueue is notified when the thread group exits, for the pidfds
+    // referring to it.
+    pub exitQueue: Queue,
+
     // leader is the thread group's leader, which is the oldest task in the
     // thread group; usually the last task in the thread group to call
     // execve(), or if no such task exists then the first task in the thread
@@ -188,9 +196,20 @@ pub struct ThreadGroupInternal {
     // exitStatus is the thread group's exit status.
     //
     // While exiting is false, exitStatus is protected by the signal mutex.
-    // When exiting becomes true, exitStatus becomes immutable.
+    // When exiting becomes true, exitStatus becomes immutable, except that
+    // the task dumping core sets CoreDumped under the signal mutex once the
+    // core file is written.
     pub exitStatus: ExitStatus,
 
+    // If coreDump is not None, a task in the thread group is waiting for its
+    // siblings to exit so that it can write a core file, and each sibling
+    // records its registers in it on the way out.
+    //
+    // coreDump is analogous to Linux's mm_struct::core_state.
+    //
+    // coreDump is protected by the signal mutex.
+    pub coreDump: Option<CoreDump>,
+
     // terminationSignal is the signal that this thread group's leader will
     // send to its parent when it exits.
     //
@@ -276,8 +295,11 @@ pub struct ThreadGroupInternal {
     pub containerID: String,
 
     pub timerMu: Arc<QMutex<()>>,
-    // todo: handle tty
-    //pub tty: Option<TTY>
+
+    // tty is the controlling terminal of the thread group, if any.
+    //
+    // tty is protected by the TaskSet mutex.
+    pub tty: Option<TTYFileOps>,
 }
 
 #[derive(Default)]
@@ -372,6 +394,24 @@ impl ThreadGroup {
         return self.lock().leader.Upgrade();
     }
 
+    // Exited returns whether every task in the thread group has exited, i.e.
+    // the leader is a zombie or has been reaped and no other task is left.
+    pub fn Exited(&self) -> bool {
+        let ts = self.TaskSet();
+        let _ts = ts.ReadLock();
+        let (leader, tasksCount) = {
+            let tg = self.lock();
+            (tg.leader.Upgrade(), tg.tasksCount)
+        };
+
+        let leader = match leader {
+            None => return true,
+            Some(l) => l,
+        };
+
+        return leader.ExitState() >= TaskExitState::TaskExitZombie && tasksCount <= 1;
+    }
+
     pub fn Count(&self) -> usize {
         let ts = self.TaskSet();
         let _ts = ts.ReadLock();
@@ -384,8 +424,8 @@ impl ThreadGroup {
 
         let mut tasks = Vec::new();
         for thread in &self.lock().tasks {
-            let tid = thread.lock().id;
-            if pidns.lock().tasks.contains_key(&tid) {
+            let tid = pidns.IDOfTaskLocked(thread);
+            if tid != 0 {
                 tasks.push(tid)
             }
         }
@@ -633,6 +673,9 @@ impl ThreadGroup {
         s.lock().processGroups.insert(pg.clone());
         ts.write().sessions.insert(s.clone());
 
+        // A new session starts without a controlling terminal.
+        self.lock().tty = None;
+
         if self.lock().processGroup.clone().is_some() {
             let oldParentPG = self.parentPG();
             self.forEachChildThreadGroupLocked(|childTG: ThreadGroup| {
@@ -672,4 +715,150 @@ impl ThreadGroup {
 
         return Ok(())
     }
-}
\ No newline at end of file
+
+    // IsSessionLeader returns whether the thread group leads its session.
+    pub fn IsSessionLeader(&self) -> bool {
+        return match self.Session() {
+            None => false,
+            Some(s) => s.lock().leader == *self,
+        }
+    }
+
+    // TTY returns the controlling terminal of the thread group.
+    pub fn TTY(&self) -> Option<TTYFileOps> {
+        let ts = self.TaskSet();
+        let _r = ts.ReadLock();
+        return self.lock().tty.clone();
+    }
+
+    // SetControllingTTY makes tty the controlling terminal of the thread
+    // group's session, as by TIOCSCTTY.
+    pub fn SetControllingTTY(&self, task: &Task, tty: &TTYFileOps, steal: bool, isReadable: bool) -> Result<()> {
+        let thread = task.Thread();
+        let root = thread.Kernel().RootUserNamespace();
+        let hasAdmin = thread.HasCapabilityIn(Capability::CAP_SYS_ADMIN, &root);
+
+        // tty is protected by the TaskSet mutex, and other thread groups'
+        // ttys are changed as well, so this needs the write lock.
+        let ts = self.TaskSet();
+        let _w = ts.WriteLock();
+
+        let pg = match self.lock().processGroup.clone() {
+            None => return Err(Error::SysError(SysErr::EPERM)),
+            Some(pg) => pg,
+        };
+        let session = pg.Session();
+
+        // Like drivers/tty/tty_jobctrl.c:tiocsctty(), acquiring the current
+        // controlling terminal again is a no-op.
+        let isLeader = session.lock().leader == *self;
+        if isLeader && self.lock().tty.as_ref() == Some(tty) {
+            return Ok(())
+        }
+
+        // "The calling process must be a session leader and not have a
+        // controlling terminal already." - tty_ioctl(4)
+        if !isLeader || self.lock().tty.is_some() {
+            return Err(Error::SysError(SysErr::EPERM))
+        }
+
+        let mut t = tty.lock();
+
+        // "If this terminal is already the controlling terminal of a different
+        // session group, then the ioctl fails with EPERM, unless the caller
+        // has the CAP_SYS_ADMIN capability and arg equals 1, in which case the
+        // terminal is stolen, and all processes that had it as controlling
+        // terminal lose it." - tty_ioctl(4)
+        match t.session.clone() {
+            Some(ref s) if *s != session => {
+                if !hasAdmin || !steal {
+                    return Err(Error::SysError(SysErr::EPERM))
+                }
+
+                // Steal the TTY away. Unlike TIOCNOTTY, don't send signals.
+                ts.forEachThreadGroupLocked(|othertg: &ThreadGroup| {
+                    let otherpg = othertg.lock().processGroup.clone();
+                    match otherpg {
+                        Some(otherpg) if otherpg.Session() == *s => {
+                            othertg.lock().tty = None;
+                        }
+                        _ => (),
+                    }
+                });
+            }
+            _ => (),
+        }
+
+        if !isReadable && !hasAdmin {
+            return Err(Error::SysError(SysErr::EPERM))
+        }
+
+        // Set the controlling terminal and foreground process group.
+        self.lock().tty = Some(tty.clone());
+        t.session = Some(session);
+        t.fgProcessgroup = Some(pg);
+        return Ok(())
+    }
+
+    // ReleaseControllingTTY gives up tty as the controlling terminal of the
+    // thread group, as by TIOCNOTTY.
+    pub fn ReleaseControllingTTY(&self, tty: &TTYFileOps) -> Result<()> {
+        let ts = self.TaskSet();
+        let _w = ts.WriteLock();
+
+        if self.lock().tty.as_ref() != Some(tty) {
+            return Err(Error::SysError(SysErr::ENOTTY))
+        }
+
+        let session = match self.lock().processGroup.clone() {
+            None => return Err(Error::SysError(SysErr::ENOTTY)),
+            Some(pg) => pg.Session(),
+        };
+
+        // If we're not the session leader, we don't have to do much.
+        if session.lock().leader != *self {
+            self.lock().tty = None;
+            return Ok(())
+        }
+
+        // "If the process was session leader, then send SIGHUP and SIGCONT to
+        // the foreground process group and all processes in the current
+        // session lose their controlling terminal." - tty_ioctl(4)
+        let fg = {
+            let mut t = tty.lock();
+            t.session = None;
+            t.fgProcessgroup.take()
+        };
+
+        let mut lastErr = Ok(());
+        ts.forEachThreadGroupLocked(|othertg: &ThreadGroup| {
+            let otherpg = match othertg.lock().processGroup.clone() {
+                None => return,
+                Some(pg) => pg,
+            };
+
+            if otherpg.Session() != session {
+                return
+            }
+
+            othertg.lock().tty = None;
+            if Some(otherpg) == fg {
+                let lock = othertg.lock().signalLock.clone();
+                let _s = lock.lock();
+                // The leader of a zombie thread group may already be reaped.
+                let leader = match othertg.lock().leader.Upgrade() {
+                    None => return,
+                    Some(t) => t,
+                };
+                for sig in &[Signal::SIGHUP, Signal::SIGCONT] {
+                    match leader.sendSignalLocked(&SignalInfoPriv(*sig), true) {
+                        Err(e) => lastErr = Err(e),
+                        Ok(()) => (),
+                    }
+                }
+            }
+        });
+
+        return lastErr
+    }
+}
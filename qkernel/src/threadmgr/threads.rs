@@ -70,7 +70,7 @@ impl TaskSetInternal {
                         //error!("AssignTids remove tid {}", a.tid);
                         tns.lock().tids.remove(&t);
                         if tg.lock().leader.Upgrade().is_none() {
-                            pidns.lock().tgids.remove(&tg);
+                            tns.lock().tgids.remove(&tg);
                         }
                     }
 
@@ -79,6 +79,8 @@ impl TaskSetInternal {
                 Ok(id) => id,
             };
 
+            // t.id ends up as the TID in the root PID namespace, which is the
+            // last one allocated. Lookups in other namespaces go through tids.
             t.lock().id = tid;
             //error!("AssignTids add tid {}", tid);
             pidns.lock().tasks.insert(tid, t.clone());
@@ -187,6 +189,8 @@ impl TaskSet {
             tg: tg.clone(),
             parent: cfg.Parent.clone(),
             children: BTreeSet::new(),
+            ptraceTracer: None,
+            ptraceTracees: BTreeSet::new(),
             childPIDNamespace: None,
             SysCallReturn: None,
             //scedSeq: SeqCount::default(),
@@ -202,9 +206,13 @@ impl TaskSet {
             groupStopAcknowledged: false,
             trapStopPending: false,
             trapNotifyPending: false,
+            ptraceCode: 0,
+            ptraceSiginfo: None,
             allowedCPUMask: cfg.AllowedCPUMask.Copy(),
             cpu: 0,
             niceness: 0,
+            personality: cfg.Personality,
+            ioprio: cfg.IOPrio,
             numaPolicy: 0,
             numaNodeMask: 0,
             netns: false,
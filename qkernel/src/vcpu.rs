@@ -13,16 +13,23 @@
 // limitations under the License.
 
 use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::AtomicU64;
 use core::sync::atomic::Ordering;
+use core::hint::spin_loop;
 
 use super::asm::*;
 use super::IOURING;
+use super::Kernel::HostSpace;
 use super::qlib::vcpu_mgr::*;
 use super::qlib::singleton::*;
 
 pub static VCPU_COUNT : Singleton<AtomicUsize> = Singleton::<AtomicUsize>::New();
 pub static CPU_LOCAL : Singleton<&'static [CPULocal]> = Singleton::<&'static [CPULocal]>::New();
 
+// TLB_EPOCH is bumped whenever user page table entries are moved, so that
+// the other vcpus drop their stale TLB entries.
+pub static TLB_EPOCH : AtomicU64 = AtomicU64::new(0);
+
 pub fn SetVCPCount(cpuCnt: usize) {
     VCPU_COUNT.store(cpuCnt, Ordering::SeqCst)
 }
@@ -99,6 +106,57 @@ impl CPULocal {
         return &CPU_LOCAL[Self::CpuId() as usize]
     }
 
+    // ShootdownTlb makes every vcpu drop its stale TLB entries before the
+    // caller goes on to free or reuse the unmapped pages. The calling vcpu has
+    // already invalidated the entries with invlpg. A vcpu in the kernel
+    // flushes before it returns to user mode; a vcpu running user code is
+    // kicked with TLB_SHOOTDOWN_VECTOR and waited for.
+    pub fn ShootdownTlb() {
+        let epoch = TLB_EPOCH.fetch_add(1, Ordering::SeqCst) + 1;
+        let me = Self::Myself();
+        me.tlbEpoch.store(epoch, Ordering::SeqCst);
+
+        let cpuCnt = CPU_LOCAL.len();
+        for i in 0..cpuCnt {
+            let cpu = &CPU_LOCAL[i];
+            if i != me.vcpuId && cpu.Stale(epoch) {
+                HostSpace::TlbShootdown(i as u64);
+            }
+        }
+
+        for i in 0..cpuCnt {
+            let cpu = &CPU_LOCAL[i];
+            while i != me.vcpuId && cpu.Stale(epoch) {
+                spin_loop();
+            }
+        }
+    }
+
+    // Stale returns whether the vcpu may still run user code with
+    // translations older than epoch.
+    fn Stale(&self, epoch: u64) -> bool {
+        return self.inUser.load(Ordering::SeqCst) && self.tlbEpoch.load(Ordering::SeqCst) < epoch;
+    }
+
+    // FlushStaleTlb flushes the TLB if page table entries were moved since
+    // this vcpu last flushed it. It is called right before returning to user
+    // mode, so inUser is set first: either ShootdownTlb sees it and waits for
+    // us, or we see its new epoch here.
+    pub fn FlushStaleTlb() {
+        let me = Self::Myself();
+        me.inUser.store(true, Ordering::SeqCst);
+        let epoch = TLB_EPOCH.load(Ordering::SeqCst);
+        if me.tlbEpoch.load(Ordering::SeqCst) != epoch {
+            LoadCr3(CurrentCr3());
+            me.tlbEpoch.store(epoch, Ordering::SeqCst);
+        }
+    }
+
+    // EnterKernel is called on every entry from user mode.
+    pub fn EnterKernel() {
+        Self::Myself().inUser.store(false, Ordering::SeqCst);
+    }
+
     pub fn NextUringIdx(cnt: u64) -> usize {
         let cpuId = Self::CpuId() as usize;
         return CPU_LOCAL[Self::CpuId() as usize].IncrUringMsgCnt(cnt) as usize + cpuId;
@@ -146,6 +146,11 @@ impl AccessType {
         return self
     }
 
+    pub fn ClearExec(&mut self) -> &mut Self {
+        self.0 &= !MmapProt::PROT_EXEC;
+        return self;
+    }
+
     pub fn Val(&self) -> u64 {
         return self.0
     }
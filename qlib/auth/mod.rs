@@ -321,6 +321,7 @@ impl Credentials {
         }
 
         let internal = UserNameSpaceInternal {
+            id: NewUserNamespaceID(),
             parent: Some(ns),
             owner: self.lock().EffectiveKUID,
             ..Default::default()
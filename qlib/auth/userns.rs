@@ -17,14 +17,25 @@ use core::ops::Bound::Included;
 use alloc::vec::Vec;
 use alloc::sync::Arc;
 use core::ops::Deref;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
 use super::super::mutex::*;
 
 use super::super::common::*;
 use super::super::linux_def::*;
 use super::id::*;
 
+static USERNS_ID: AtomicU64 = AtomicU64::new(1);
+
+// NewUserNamespaceID returns a unique id for a new user namespace.
+pub fn NewUserNamespaceID() -> u64 {
+    return USERNS_ID.fetch_add(1, Ordering::SeqCst);
+}
+
 #[derive(Default, Debug)]
 pub struct UserNameSpaceInternal {
+    // id identifies the namespace in /proc/[pid]/ns/user.
+    pub id: u64,
     pub parent: Option<UserNameSpace>,
     pub owner: KUID,
 
@@ -77,8 +88,13 @@ impl PartialEq for UserNameSpace {
 impl Eq for UserNameSpace {}
 
 impl UserNameSpace {
+    pub fn ID(&self) -> u64 {
+        return self.lock().id
+    }
+
     pub fn NewRootUserNamespace() -> Self {
         let internal = UserNameSpaceInternal {
+            id: NewUserNamespaceID(),
             parent: None,
             owner: KUID::default(),
             uidMapFromParent: IdMap::All(),
@@ -131,6 +131,35 @@ impl ByteStream {
         return Ok((full, readSize))
     }
 
+    // peek copies up to buf.len() bytes starting offset bytes into the
+    // available data without consuming them. It returns how much was copied.
+    pub fn peek(&self, offset: usize, buf: &mut [u8]) -> usize {
+        if offset >= self.available {
+            return 0
+        }
+
+        let mut readSize = self.available - offset;
+        if readSize > buf.len() {
+            readSize = buf.len();
+        }
+
+        let pos = (self.readpos + offset) % self.buf.len();
+        let toEnd = self.buf.len() - pos;
+        let firstLen = if toEnd < readSize {
+            toEnd
+        } else {
+            readSize
+        };
+
+        buf[0..firstLen].clone_from_slice(&self.buf[pos..pos + firstLen]);
+        if firstLen < readSize {
+            let secondLen = readSize - firstLen;
+            buf[firstLen..readSize].clone_from_slice(&self.buf[0..secondLen])
+        }
+
+        return readSize
+    }
+
     pub fn readViaAddr(&mut self, buf: u64, count: u64) -> (bool, usize) {
         let ptr = buf as *mut u8;
         let slice = unsafe { slice::from_raw_parts_mut(ptr, count as usize) };
@@ -30,8 +30,22 @@ pub struct Config {
     pub FileBufWrite: bool,
     pub MmapRead: bool,
     pub AsyncAccept: bool,
+    // LoopbackFastPath connects TCP sockets to in-sandbox listeners on the
+    // loopback address directly, without going through the host. UDP is not
+    // covered and always goes through the host.
+    pub LoopbackFastPath: bool,
+    // MultishotPoll watches host fds for the guest notifier with one
+    // multishot io_uring poll each instead of the host epoll, when the host
+    // kernel supports it.
+    pub MultishotPoll: bool,
     pub DedicateUring: usize,
-    pub UringSize: usize
+    pub UringSize: usize,
+    // UringSqpoll creates the host rings with a kernel thread which polls
+    // their submission queues, so that submitting doesn't need a syscall.
+    pub UringSqpoll: bool,
+    // ShutdownGracePeriod is how long, in seconds, the container has to
+    // exit after the sandbox receives SIGTERM before it is killed.
+    pub ShutdownGracePeriod: u64
 }
 
 impl Config {}
@@ -54,8 +68,12 @@ impl Default for Config {
             FileBufWrite: true,
             MmapRead: true,
             AsyncAccept: true,
+            LoopbackFastPath: true,
+            MultishotPoll: true,
             DedicateUring: 1,
             UringSize: 64,
+            UringSqpoll: true,
+            ShutdownGracePeriod: 10,
         }
     }
 }
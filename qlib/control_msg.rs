@@ -64,6 +64,11 @@ pub enum SignalDeliveryMode {
     // process. If PID is 0, then the signal is delivered to the foreground
     // process group for the TTY for the init process.
     DeliverToForegroundProcessGroup,
+
+    // DeliverToProcessGroup delivers the signal to the process group of the
+    // container process with the specified PID. If PID is 0, then the
+    // signal is delivered to the process group of the init process.
+    DeliverToProcessGroup,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
@@ -79,6 +84,21 @@ pub struct SignalArgs {
     pub Mode: SignalDeliveryMode,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct WindowSizeArgs {
+    // PID is the process ID in the given container whose TTY is resized.
+    // If 0, the TTY of the root container is resized.
+    pub PID: i32,
+
+    // Rows and Cols are the new terminal dimensions in characters.
+    pub Rows: u16,
+    pub Cols: u16,
+
+    // Xpixel and Ypixel are the new terminal dimensions in pixels.
+    pub Xpixel: u16,
+    pub Ypixel: u16,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub enum Payload {
     RootContainerStart(RootProcessStart),
@@ -89,6 +109,7 @@ pub enum Payload {
     WaitPid(WaitPid),
     Ps(String),
     Signal(SignalArgs),
+    WindowSize(WindowSizeArgs),
     ContainerDestroy,
 }
 
@@ -108,6 +129,7 @@ pub enum UCallResp {
     WaitContainerResp(u32),
     WaitPidResp(u32),
     SignalResp,
+    WindowSizeResp,
     ContainerDestroyResp,
 }
 
@@ -0,0 +1,41 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+// ioprio_set(2) and ioprio_get(2) constants, from include/uapi/linux/ioprio.h.
+pub const IOPRIO_CLASS_SHIFT : u16 = 13;
+pub const IOPRIO_PRIO_MASK   : u16 = (1 << IOPRIO_CLASS_SHIFT) - 1;
+
+pub const IOPRIO_CLASS_NONE : u16 = 0;
+pub const IOPRIO_CLASS_RT   : u16 = 1;
+pub const IOPRIO_CLASS_BE   : u16 = 2;
+pub const IOPRIO_CLASS_IDLE : u16 = 3;
+
+pub const IOPRIO_WHO_PROCESS : i32 = 1;
+pub const IOPRIO_WHO_PGRP    : i32 = 2;
+pub const IOPRIO_WHO_USER    : i32 = 3;
+
+// IOPRIO_NR_LEVELS is the number of priority levels of the RT and BE classes.
+pub const IOPRIO_NR_LEVELS : u16 = 8;
+
+// IOPRIO_DEFAULT is the priority of a task which never called ioprio_set(2).
+pub const IOPRIO_DEFAULT : u16 = IOPRIO_CLASS_NONE << IOPRIO_CLASS_SHIFT;
+
+pub fn IOPrioClass(ioprio: u16) -> u16 {
+    return ioprio >> IOPRIO_CLASS_SHIFT
+}
+
+pub fn IOPrioData(ioprio: u16) -> u16 {
+    return ioprio & IOPRIO_PRIO_MASK
+}
@@ -25,5 +25,7 @@ pub mod socket;
 pub mod rusage;
 pub mod fcntl;
 pub mod membarrier;
+pub mod personality;
+pub mod ioprio;
 
 pub type TimeID = i32;
\ No newline at end of file
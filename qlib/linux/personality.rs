@@ -0,0 +1,37 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// personality(2) flags, from include/uapi/linux/personality.h.
+pub const UNAME26            : u32 = 0x0020000;
+pub const ADDR_NO_RANDOMIZE  : u32 = 0x0040000;
+pub const FDPIC_FUNCPTRS     : u32 = 0x0080000;
+pub const MMAP_PAGE_ZERO     : u32 = 0x0100000;
+pub const ADDR_COMPAT_LAYOUT : u32 = 0x0200000;
+pub const READ_IMPLIES_EXEC  : u32 = 0x0400000;
+pub const ADDR_LIMIT_32BIT   : u32 = 0x0800000;
+pub const SHORT_INODE        : u32 = 0x1000000;
+pub const WHOLE_SECONDS      : u32 = 0x2000000;
+pub const STICKY_TIMEOUTS    : u32 = 0x4000000;
+pub const ADDR_LIMIT_3GB     : u32 = 0x8000000;
+
+// PER_LINUX is the default execution domain.
+pub const PER_LINUX : u32 = 0x0000;
+
+// PER_CLEAR_ON_SETID are the flags cleared on execve of a set-user-ID or
+// set-group-ID program.
+pub const PER_CLEAR_ON_SETID : u32 = READ_IMPLIES_EXEC | ADDR_NO_RANDOMIZE;
+
+// PERSONALITY_QUERY is passed to personality(2) to read the persona without
+// changing it.
+pub const PERSONALITY_QUERY : u32 = 0xffffffff;
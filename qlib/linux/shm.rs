@@ -30,16 +30,16 @@ pub const SHM_HUGETLB: u16 = 0o04000;  // Segment will use huge TLB pages.
 pub const SHM_NORESERVE: u16 = 0o010000; // Don't check for reservations.
 
 // Additional Linux-only flags for shmctl(2). Source: include/uapi/linux/shm.h
-pub const SHM_LOCK: u16 = 11;
-pub const SHM_UNLOCK: u16 = 12;
-pub const SHM_STAT: u16 = 13;
-pub const SHM_INFO: u16 = 14;
+pub const SHM_LOCK: i32 = 11;
+pub const SHM_UNLOCK: i32 = 12;
+pub const SHM_STAT: i32 = 13;
+pub const SHM_INFO: i32 = 14;
 
 // SHM defaults as specified by linux. Source: include/uapi/linux/shm.h
 pub const SHMMIN: u64 = 1;
 pub const SHMMNI: u64 = 4096;
-pub const SHMMAX: u64 = u64::MAX - 1 << 24;
-pub const SHMALL: u64 = u64::MAX - 1 << 24;
+pub const SHMMAX: u64 = u64::MAX - (1 << 24);
+pub const SHMALL: u64 = u64::MAX - (1 << 24);
 pub const SHMSEG: u64 = 4096;
 
 // ShmidDS is equivalent to struct shmid64_ds. Source:
@@ -76,6 +76,9 @@ pub const TFD_NONBLOCK: i32 = Flags::O_NONBLOCK;
 // TFD_TIMER_ABSTIME is a timerfd_settime flag.
 pub const TFD_TIMER_ABSTIME: i32 = 1;
 
+// TFD_TIMER_CANCEL_ON_SET is a timerfd_settime flag.
+pub const TFD_TIMER_CANCEL_ON_SET: i32 = 2;
+
 // The safe number of seconds you can represent by int64.
 pub const MAX_SEC_IN_DURATION: i64 = core::i64::MAX / SECOND;
 
@@ -169,6 +172,10 @@ impl Timeval {
 
         return self.Sec * Self::E9 + self.Usec * Self::E3
     }
+
+    pub fn IsValid(&self) -> bool {
+        return !(self.Sec < 0 || self.Usec < 0 || self.Usec >= Self::E6)
+    }
 }
 
 // Itimerspec represents struct itimerspec in <time.h>.
@@ -202,11 +202,12 @@ pub const EVENT_PRI: EventMask = 0x02; // POLLPRI
 pub const EVENT_OUT: EventMask = 0x04; // POLLOUT
 pub const EVENT_ERR: EventMask = 0x08; // POLLERR
 pub const EVENT_HUP: EventMask = 0x10; // POLLHUP
+pub const EVENT_RDHUP: EventMask = 0x2000; // POLLRDHUP
 
 // Quark event, when application shutdown the connection, it is used for wait the uring to drain the writing buffer
 pub const EVENT_PENDING_SHUTDOWN: EventMask = 0x20;
 
-pub const ALL_EVENTS: EventMask = 0x1f;
+pub const ALL_EVENTS: EventMask = 0x1f | EVENT_RDHUP;
 pub const EVENT_READ: EventMask = EVENT_IN | EVENT_HUP | EVENT_ERR;
 pub const EVENT_WRITE: EventMask = EVENT_OUT | EVENT_HUP | EVENT_ERR;
 
@@ -390,6 +391,16 @@ impl MfdType {
     pub const MFD_ALLOW_SEALING: u32 = 0x0002;
 }
 
+pub struct SealFlag {}
+
+impl SealFlag {
+    pub const F_SEAL_SEAL: u32 = 0x0001;
+    pub const F_SEAL_SHRINK: u32 = 0x0002;
+    pub const F_SEAL_GROW: u32 = 0x0004;
+    pub const F_SEAL_WRITE: u32 = 0x0008;
+    pub const F_SEAL_FUTURE_WRITE: u32 = 0x0010;
+}
+
 #[repr(C)]
 #[derive(Debug, Default, Copy, Clone)]
 pub struct FlockStruct {
@@ -897,6 +908,7 @@ impl LibcConst {
     pub const DT_WHT: u64 = 0xe;
     pub const EPOLLERR: u64 = 0x8;
     pub const EPOLLET: i64 = -0x80000000;
+    pub const EPOLLEXCLUSIVE: u64 = 0x10000000;
     pub const EPOLLHUP: u64 = 0x10;
     pub const EPOLLIN: u64 = 0x1;
     pub const EPOLLMSG: u64 = 0x400;
@@ -906,6 +918,7 @@ impl LibcConst {
     pub const EPOLLRDBAND: u64 = 0x80;
     pub const EPOLLRDHUP: u64 = 0x2000;
     pub const EPOLLRDNORM: u64 = 0x40;
+    pub const EPOLLWAKEUP: u64 = 0x20000000;
     pub const EPOLLWRBAND: u64 = 0x200;
     pub const EPOLLWRNORM: u64 = 0x100;
     pub const EPOLL_CLOEXEC: u64 = 0x80000;
@@ -1829,6 +1842,8 @@ impl LibcConst {
     pub const TCP_QUICKACK: u64 = 0xc;
     pub const TCP_SYNCNT: u64 = 0x7;
     pub const TCP_WINDOW_CLAMP: u64 = 0xa;
+    pub const TCP_USER_TIMEOUT: u64 = 0x12;
+    pub const TCP_NOTSENT_LOWAT: u64 = 0x19;
     pub const TCP_INQ:u64 = 0x24;
     pub const TIOCCBRK: u64 = 0x5428;
     pub const TIOCCONS: u64 = 0x541d;
@@ -1911,6 +1926,7 @@ impl LibcConst {
     pub const TUNSETSNDBUF: u64 = 0x400454d4;
     pub const TUNSETTXFILTER: u64 = 0x400454d1;
     pub const TUNSETVNETHDRSZ: u64 = 0x400454d8;
+    pub const UMOUNT_NOFOLLOW: u64 = 0x8;
     pub const WALL: u64 = 0x40000000;
     pub const WCLONE: u64 = 0x80000000;
     pub const WCONTINUED: u64 = 0x8;
@@ -1936,6 +1952,8 @@ impl Cmd {
     pub const F_SETLKW: i32 = 7;
     pub const F_SETOWN: i32 = 8;
     pub const F_GETOWN: i32 = 9;
+    pub const F_SETSIG: i32 = 10;
+    pub const F_GETSIG: i32 = 11;
     pub const F_SETOWN_EX: i32 = 15;
     pub const F_GETOWN_EX: i32 = 16;
     pub const F_DUPFD_CLOEXEC: i32 = 1024 + 6;
@@ -2588,6 +2606,18 @@ impl IDType {
     pub const P_ALL: i32 = 0x0;
     pub const P_PID: i32 = 0x1;
     pub const P_PGID: i32 = 0x2;
+    pub const P_PIDFD: i32 = 0x3;
+}
+
+pub struct FAdviseOp {}
+
+impl FAdviseOp {
+    pub const POSIX_FADV_NORMAL: i32 = 0;
+    pub const POSIX_FADV_RANDOM: i32 = 1;
+    pub const POSIX_FADV_SEQUENTIAL: i32 = 2;
+    pub const POSIX_FADV_WILLNEED: i32 = 3;
+    pub const POSIX_FADV_DONTNEED: i32 = 4;
+    pub const POSIX_FADV_NOREUSE: i32 = 5;
 }
 
 pub struct MAdviseOp {}
@@ -2598,6 +2628,7 @@ impl MAdviseOp {
     pub const MADV_SEQUENTIAL: i32 = 2;
     pub const MADV_WILLNEED: i32 = 3;
     pub const MADV_DONTNEED: i32 = 4;
+    pub const MADV_FREE: i32 = 8;
     pub const MADV_REMOVE: i32 = 9;
     pub const MADV_DONTFORK: i32 = 10;
     pub const MADV_DOFORK: i32 = 11;
@@ -2607,6 +2638,8 @@ impl MAdviseOp {
     pub const MADV_NOHUGEPAGE: i32 = 15;
     pub const MADV_DONTDUMP: i32 = 16;
     pub const MADV_DODUMP: i32 = 17;
+    pub const MADV_COLD: i32 = 20;
+    pub const MADV_PAGEOUT: i32 = 21;
     pub const MADV_HWPOISON: i32 = 100;
     pub const MADV_SOFT_OFFLINE: i32 = 101;
     pub const MADV_NOMAJFAULT: i32 = 200;
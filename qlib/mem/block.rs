@@ -49,6 +49,27 @@ impl <'a> Iovs <'a> {
 
         return res;
     }
+
+    pub fn TakeFirst(&self, n: usize) -> Vec<IoVec> {
+        let mut n = n;
+        let mut res = Vec::new();
+
+        for src in self.0 {
+            if n == 0 {
+                break;
+            }
+
+            if src.Len() <= n {
+                res.push(*src);
+                n -= src.Len();
+            } else {
+                res.push(IoVec::NewFromAddr(src.Start(), n));
+                n = 0;
+            }
+        }
+
+        return res;
+    }
 }
 
 impl IoVec {
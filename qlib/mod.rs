@@ -102,6 +102,11 @@ pub const HYPERCALL_VCPU_FREQ: u16 = 19;
 pub const HYPERCALL_VCPU_YIELD: u16 = 20;
 pub const HYPERCALL_VCPU_DEBUG: u16 = 21;
 pub const HYPERCALL_VCPU_PRINT: u16 = 22;
+pub const HYPERCALL_TLB_SHOOTDOWN: u16 = 23;
+
+// TLB_SHOOTDOWN_VECTOR is the interrupt the host injects into a vcpu to make
+// it flush its TLB, see HYPERCALL_TLB_SHOOTDOWN.
+pub const TLB_SHOOTDOWN_VECTOR: u32 = 33;
 
 pub const DUMMY_TASKID: TaskId = TaskId::New(0xffff_ffff);
 
@@ -477,6 +482,110 @@ pub enum SysCallID {
     // 330
     sys_pkey_free,
     sys_statx,
+    sys_io_pgetevents,
+    sys_rseq,
+    // 335 to 423 are unused on x86_64.
+    sys_unused335,
+    sys_unused336,
+    sys_unused337,
+    sys_unused338,
+    sys_unused339,
+    sys_unused340,
+    sys_unused341,
+    sys_unused342,
+    sys_unused343,
+    sys_unused344,
+    sys_unused345,
+    sys_unused346,
+    sys_unused347,
+    sys_unused348,
+    sys_unused349,
+    sys_unused350,
+    sys_unused351,
+    sys_unused352,
+    sys_unused353,
+    sys_unused354,
+    sys_unused355,
+    sys_unused356,
+    sys_unused357,
+    sys_unused358,
+    sys_unused359,
+    sys_unused360,
+    sys_unused361,
+    sys_unused362,
+    sys_unused363,
+    sys_unused364,
+    sys_unused365,
+    sys_unused366,
+    sys_unused367,
+    sys_unused368,
+    sys_unused369,
+    sys_unused370,
+    sys_unused371,
+    sys_unused372,
+    sys_unused373,
+    sys_unused374,
+    sys_unused375,
+    sys_unused376,
+    sys_unused377,
+    sys_unused378,
+    sys_unused379,
+    sys_unused380,
+    sys_unused381,
+    sys_unused382,
+    sys_unused383,
+    sys_unused384,
+    sys_unused385,
+    sys_unused386,
+    sys_unused387,
+    sys_unused388,
+    sys_unused389,
+    sys_unused390,
+    sys_unused391,
+    sys_unused392,
+    sys_unused393,
+    sys_unused394,
+    sys_unused395,
+    sys_unused396,
+    sys_unused397,
+    sys_unused398,
+    sys_unused399,
+    sys_unused400,
+    sys_unused401,
+    sys_unused402,
+    sys_unused403,
+    sys_unused404,
+    sys_unused405,
+    sys_unused406,
+    sys_unused407,
+    sys_unused408,
+    sys_unused409,
+    sys_unused410,
+    sys_unused411,
+    sys_unused412,
+    sys_unused413,
+    sys_unused414,
+    sys_unused415,
+    sys_unused416,
+    sys_unused417,
+    sys_unused418,
+    sys_unused419,
+    sys_unused420,
+    sys_unused421,
+    sys_unused422,
+    sys_unused423,
+    sys_pidfd_send_signal,
+    sys_io_uring_setup,
+    sys_io_uring_enter,
+    sys_io_uring_register,
+    sys_open_tree,
+    sys_move_mount,
+    // 430
+    sys_fsopen,
+    sys_fsconfig,
+    sys_fsmount,
+    sys_fspick,
+    sys_pidfd_open,
 
     maxsupport,
 }
@@ -538,6 +647,11 @@ pub struct ShareSpace {
     pub logBuf: QMutex<Option<ByteStream>>,
     pub logfd: AtomicI32,
 
+    // uringCaps is filled in by the host once its io_uring is set up.
+    pub uringCaps: uring::UringCaps,
+    // fixedBufs is allocated by the kernel and registered by the host.
+    pub fixedBufs: uring::FixedBufs,
+
     pub values: [[AtomicU64; 2]; 16],
 }
 
@@ -558,6 +672,8 @@ impl ShareSpace {
             config: QRwLock::new(Config::default()),
             logBuf: QMutex::new(None),
             logfd: AtomicI32::new(-1),
+            uringCaps: uring::UringCaps::default(),
+            fixedBufs: uring::FixedBufs::default(),
             values: [
                 [AtomicU64::new(0), AtomicU64::new(0)], [AtomicU64::new(0), AtomicU64::new(0)], [AtomicU64::new(0), AtomicU64::new(0)], [AtomicU64::new(0), AtomicU64::new(0)],
                 [AtomicU64::new(0), AtomicU64::new(0)], [AtomicU64::new(0), AtomicU64::new(0)], [AtomicU64::new(0), AtomicU64::new(0)], [AtomicU64::new(0), AtomicU64::new(0)],
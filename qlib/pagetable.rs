@@ -323,6 +323,7 @@ impl PageTables {
         return Ok(res);
     }
 
+    // Remap returns whether any page was mapped at oldStart and so was moved.
     pub fn Remap(&self, start: Addr, end: Addr, oldStart: Addr, flags: PageTableFlags, pagePool: &Allocator) -> Result<bool> {
         start.PageAligned()?;
         oldStart.PageAligned()?;
@@ -363,7 +364,7 @@ impl PageTables {
             idx += 1;
         }
 
-        return Ok(false)
+        return Ok(addrs.iter().any(|a| a.is_some()))
     }
 
     pub fn RemapForFile(&self, start: Addr, end: Addr, physical: Addr, oldStart: Addr, oldEnd: Addr, flags: PageTableFlags, pagePool: &Allocator) -> Result<bool> {
@@ -506,10 +507,12 @@ impl PageTables {
         return start;
     }
 
-    pub fn Unmap(&self, start: u64, end: u64, pagePool: &Allocator) -> Result<()> {
+    // Unmap returns the number of pages which were mapped in [start, end).
+    pub fn Unmap(&self, start: u64, end: u64, pagePool: &Allocator) -> Result<u64> {
         Addr(start).PageAligned()?;
         Addr(end).PageAligned()?;
         let mut start = start;
+        let mut count = 0;
         let pt: *mut PageTable = self.GetRoot() as *mut PageTable;
         unsafe {
             let mut p4Idx : u16 = VirtAddr::new(start).p4_index().into();
@@ -569,6 +572,7 @@ impl PageTables {
                             }
 
                             Invlpg(start);
+                            count += 1;
                             start += MemoryDef::PAGE_SIZE;
                             p1Idx += 1;
                         }
@@ -608,7 +612,7 @@ impl PageTables {
             }
         }
 
-        return Ok(())
+        return Ok(count)
     }
 
     pub fn ToVirtualAddr(p4Idx: PageTableIndex, p3Idx: PageTableIndex, p2Idx: PageTableIndex, p1Idx: PageTableIndex) -> Addr {
@@ -97,6 +97,7 @@ pub enum Msg {
     IOSendMsg(IOSendMsg),
     MMapFile(MMapFile),
     MUnmap(MUnmap),
+    ReleaseMemory(ReleaseMemory),
     UnMapPma(UnMapPma),
     NonBlockingPoll(NonBlockingPoll),
     NewTmpfsFile(NewTmpfsFile),
@@ -153,6 +154,13 @@ pub struct MUnmap {
     pub len: u64,
 }
 
+// give the pages of an anonymous host mapping back to the host
+#[derive(Clone, Default, Debug)]
+pub struct ReleaseMemory {
+    pub addr: u64,
+    pub len: u64,
+}
+
 #[derive(Clone, Default, Debug)]
 pub struct Fallocate {
     pub fd: i32,
@@ -28,6 +28,9 @@ pub use self::submit::Submitter;
 use self::util::{Fd, Mmap};
 use self::porting::*;
 use super::common::*;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use alloc::vec::Vec;
+use super::linux_def::IoVec;
 
 
 
@@ -289,3 +292,140 @@ pub fn io_uring_register(
 }
 
 
+
+// UringCaps records which io_uring opcodes the host kernel supports. The
+// host probes its ring at start up and the kernel consults the result before
+// issuing an opcode that an older host kernel lacks.
+#[derive(Debug, Default)]
+pub struct UringCaps {
+    // ops has bit n set when opcode n is supported.
+    ops: AtomicU64,
+}
+
+impl UringCaps {
+    // Set records the opcodes reported by probe. Without a probe, i.e. on a
+    // host kernel older than 5.6 which lacks IORING_REGISTER_PROBE, only the
+    // opcodes which predate IORING_OP_FALLOCATE are assumed to be supported.
+    pub fn Set(&self, probe: Option<&Probe>) {
+        let mut ops = 0;
+        for op in 0..sys::IORING_OP_LAST {
+            let supported = match probe {
+                None => op < sys::IORING_OP_FALLOCATE,
+                Some(probe) => probe.is_supported(op as u8),
+            };
+
+            if supported {
+                ops |= 1 << op;
+            }
+        }
+
+        self.ops.store(ops, Ordering::Release);
+    }
+
+    pub fn IsSupported(&self, opcode: u32) -> bool {
+        return opcode < 64 && self.ops.load(Ordering::Acquire) & (1 << opcode) != 0
+    }
+
+    pub fn SupportsPollAdd(&self) -> bool {
+        return self.IsSupported(sys::IORING_OP_POLL_ADD)
+    }
+
+    pub fn SupportsReadFixed(&self) -> bool {
+        return self.IsSupported(sys::IORING_OP_READ_FIXED)
+    }
+
+    pub fn SupportsAccept(&self) -> bool {
+        return self.IsSupported(sys::IORING_OP_ACCEPT)
+    }
+
+    pub fn SupportsEpollCtl(&self) -> bool {
+        return self.IsSupported(sys::IORING_OP_EPOLL_CTL)
+    }
+
+    // SupportsMultishotPoll returns whether POLL_ADD takes
+    // IORING_POLL_ADD_MULTI and POLL_REMOVE takes IORING_POLL_UPDATE_EVENTS.
+    // The probe only lists opcodes, not flags, so this is inferred from
+    // IORING_OP_MKDIRAT, which came in 5.15, after both flags in 5.13.
+    pub fn SupportsMultishotPoll(&self) -> bool {
+        return self.IsSupported(sys::IORING_OP_MKDIRAT)
+    }
+}
+
+// FixedBufs is the pool of io_uring fixed buffers. The kernel carves it out of
+// guest memory before HYPERCALL_INIT and the host registers the buffers with
+// IORING_REGISTER_BUFFERS, so a READ_FIXED or WRITE_FIXED on one of them
+// doesn't make the host kernel pin the pages for each transfer.
+#[derive(Debug, Default)]
+pub struct FixedBufs {
+    // addr is the start of the pool, 0 if the kernel didn't allocate one.
+    addr: AtomicU64,
+    registered: AtomicBool,
+    // used has bit n set when buffer n is handed out.
+    used: AtomicU64,
+}
+
+impl FixedBufs {
+    pub const COUNT: usize = 16;
+    pub const BUF_SIZE: usize = 64 * 1024;
+    pub const POOL_SIZE: usize = Self::COUNT * Self::BUF_SIZE;
+
+    pub fn Init(&self, addr: u64) {
+        self.addr.store(addr, Ordering::Release);
+    }
+
+    // IoVecs returns the buffers for IORING_REGISTER_BUFFERS.
+    pub fn IoVecs(&self) -> Vec<IoVec> {
+        let addr = self.addr.load(Ordering::Acquire);
+        if addr == 0 {
+            return Vec::new()
+        }
+
+        let mut iovs = Vec::with_capacity(Self::COUNT);
+        for i in 0..Self::COUNT {
+            iovs.push(IoVec {
+                start: addr + (i * Self::BUF_SIZE) as u64,
+                len: Self::BUF_SIZE,
+            })
+        }
+
+        return iovs
+    }
+
+    pub fn SetRegistered(&self) {
+        self.registered.store(true, Ordering::Release);
+    }
+
+    pub fn Registered(&self) -> bool {
+        return self.registered.load(Ordering::Acquire)
+    }
+
+    // Alloc hands out a free buffer which can hold len bytes and returns its
+    // index, or None if the pool isn't registered, len doesn't fit or all the
+    // buffers are in use.
+    pub fn Alloc(&self, len: usize) -> Option<u16> {
+        if len == 0 || len > Self::BUF_SIZE || !self.Registered() {
+            return None
+        }
+
+        let mut used = self.used.load(Ordering::Acquire);
+        loop {
+            let idx = (!used).trailing_zeros() as usize;
+            if idx >= Self::COUNT {
+                return None
+            }
+
+            match self.used.compare_exchange(used, used | (1 << idx), Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => return Some(idx as u16),
+                Err(cur) => used = cur,
+            }
+        }
+    }
+
+    pub fn Free(&self, idx: u16) {
+        self.used.fetch_and(!(1 << idx), Ordering::AcqRel);
+    }
+
+    pub fn BufAddr(&self, idx: u16) -> u64 {
+        return self.addr.load(Ordering::Acquire) + (idx as usize * Self::BUF_SIZE) as u64
+    }
+}
@@ -428,16 +428,22 @@ opcode!(
         fd: { impl sealed::UseFixed },
         flags: { u32 }
         ;;
+        /// A multishot poll stays armed and posts a completion, flagged with
+        /// `IORING_CQE_F_MORE`, for every readiness event until it is removed.
+        multi: bool = false
     }
 
     pub const CODE = sys::IORING_OP_POLL_ADD;
 
     pub fn build(self) -> Entry {
-        let PollAdd { fd, flags } = self;
+        let PollAdd { fd, flags, multi } = self;
 
         let mut sqe = sqe_zeroed();
         sqe.opcode = Self::CODE;
         assign_fd!(sqe.fd = fd);
+        if multi {
+            sqe.len = sys::IORING_POLL_ADD_MULTI;
+        }
 
         #[cfg(target_endian = "little")] {
             sqe.__bindgen_anon_3.poll32_events = flags;
@@ -478,6 +484,33 @@ opcode!(
     }
 );
 
+opcode!(
+    /// Change the events of an existing poll request in place.
+    ///
+    /// If found, the `result` method of the `cqueue::Entry` will return 0.
+    /// If not found, `result` will return `-libc::ENOENT`.
+    #[derive(Debug)]
+    pub struct PollUpdate {
+        user_data: { u64 },
+        flags: { u32 }
+        ;;
+    }
+
+    pub const CODE = sys::IORING_OP_POLL_REMOVE;
+
+    pub fn build(self) -> Entry {
+        let PollUpdate { user_data, flags } = self;
+
+        let mut sqe = sqe_zeroed();
+        sqe.opcode = Self::CODE;
+        sqe.fd = -1;
+        sqe.len = sys::IORING_POLL_UPDATE_EVENTS;
+        sqe.__bindgen_anon_2.addr = user_data as _;
+        sqe.__bindgen_anon_3.poll32_events = flags;
+        Entry(sqe)
+    }
+);
+
 opcode!(
     /// Issue the equivalent of a `sync_file_range (2)` on the file descriptor.
     ///
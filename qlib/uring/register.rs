@@ -1,4 +1,5 @@
 use core::{mem, ptr};
+use alloc::alloc::{alloc_zeroed, dealloc, Layout};
 
 use super::sys;
 use super::porting::*;
@@ -23,24 +24,25 @@ impl Probe {
 
     #[allow(clippy::cast_ptr_alignment)]
     pub fn new() -> Probe {
-        /*use std::alloc::{alloc_zeroed, Layout};
-
-        let probe_align = Layout::new::<sys::io_uring_probe>().align();
         let ptr = unsafe {
-            let probe_layout = Layout::from_size_align_unchecked(Probe::SIZE, probe_align);
-            alloc_zeroed(probe_layout)
-        };*/
-
-        panic!("alloc zero")
+            alloc_zeroed(Self::ProbeLayout())
+        };
 
-        /*ptr::NonNull::new(ptr)
+        ptr::NonNull::new(ptr)
             .map(ptr::NonNull::cast)
             .map(Probe)
-            .expect("Probe alloc failed!")*/
+            .expect("Probe alloc failed!")
+    }
+
+    fn ProbeLayout() -> Layout {
+        let probe_align = Layout::new::<sys::io_uring_probe>().align();
+        return unsafe {
+            Layout::from_size_align_unchecked(Probe::SIZE, probe_align)
+        }
     }
 
     #[inline]
-    pub(crate) fn as_mut_ptr(&mut self) -> *mut sys::io_uring_probe {
+    pub fn as_mut_ptr(&mut self) -> *mut sys::io_uring_probe {
         self.0.as_ptr()
     }
 
@@ -67,14 +69,9 @@ impl Default for Probe {
 
 impl Drop for Probe {
     fn drop(&mut self) {
-        /*use std::alloc::{dealloc, Layout};
-
-        let probe_align = Layout::new::<sys::io_uring_probe>().align();
         unsafe {
-            let probe_layout = Layout::from_size_align_unchecked(Probe::SIZE, probe_align);
-            dealloc(self.0.as_ptr() as *mut _, probe_layout);
-        }*/
-        panic!("Probe::drop....");
+            dealloc(self.0.as_ptr() as *mut _, Self::ProbeLayout());
+        }
     }
 }
 
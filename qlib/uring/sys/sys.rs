@@ -44,6 +44,10 @@ pub const IORING_FSYNC_DATASYNC: u32 = 1;
 pub const IORING_TIMEOUT_ABS: u32 = 1;
 pub const SPLICE_F_FD_IN_FIXED: u32 = 2147483648;
 pub const IORING_CQE_F_BUFFER: u32 = 1;
+pub const IORING_CQE_F_MORE: u32 = 2;
+pub const IORING_POLL_ADD_MULTI: u32 = 1;
+pub const IORING_POLL_UPDATE_EVENTS: u32 = 2;
+pub const IORING_POLL_UPDATE_USER_DATA: u32 = 4;
 pub const IORING_OFF_SQ_RING: u32 = 0;
 pub const IORING_OFF_CQ_RING: u32 = 134217728;
 pub const IORING_OFF_SQES: u32 = 268435456;
@@ -222,7 +226,11 @@ pub const IORING_OP_SPLICE: u32 = 30;
 pub const IORING_OP_PROVIDE_BUFFERS: u32 = 31;
 pub const IORING_OP_REMOVE_BUFFERS: u32 = 32;
 pub const IORING_OP_TEE: u32 = 33;
-pub const IORING_OP_LAST: u32 = 34;
+pub const IORING_OP_SHUTDOWN: u32 = 34;
+pub const IORING_OP_RENAMEAT: u32 = 35;
+pub const IORING_OP_UNLINKAT: u32 = 36;
+pub const IORING_OP_MKDIRAT: u32 = 37;
+pub const IORING_OP_LAST: u32 = 38;
 pub type _bindgen_ty_5 = u32;
 #[repr(C)]
 #[derive(Debug, Default, Copy, Clone)]
@@ -14,6 +14,7 @@
 
 use core::sync::atomic::Ordering;
 use core::sync::atomic::AtomicU64;
+use core::sync::atomic::AtomicBool;
 
 #[derive(Clone, Debug, PartialEq, Copy)]
 #[repr(u64)]
@@ -40,6 +41,12 @@ pub struct CPULocal {
     pub data: u64, // for eventfd data writing and reading
     pub eventfd: i32,
     pub epollfd: i32,
+
+    // tlbEpoch is the TLB_EPOCH at which the vcpu last flushed its TLB.
+    pub tlbEpoch: AtomicU64,
+    // inUser is set while the vcpu may run user code with its cached
+    // translations, i.e. from FlushStaleTlb until the next kernel entry.
+    pub inUser: AtomicBool,
 }
 
 impl CPULocal {
@@ -13,18 +13,21 @@
 // limitations under the License.
 
 use core::sync::atomic::AtomicU64;
+use core::sync::atomic::AtomicBool;
 use core::sync::atomic::Ordering;
 use kvm_bindings::kvm_sregs;
 use kvm_bindings::kvm_regs;
+use kvm_bindings::kvm_run;
 use kvm_ioctls::VcpuExit;
 use core::mem::size_of;
 use libc::*;
+use std::os::unix::io::AsRawFd;
 
 use super::qlib::mutex::*;
 
 use super::*;
 use super::syncmgr::*;
-//use super::kvm_ctl::*;
+use super::kvm_ctl::*;
 use super::qlib::GetTimeCall;
 use super::qlib::linux::time::Timespec;
 use super::qlib::common::*;
@@ -144,6 +147,79 @@ impl RefMgr for SimplePageAllocator {
     }
 }
 
+// VcpuKick lets a vcpu thread interrupt another vcpu while it runs guest
+// code, see HYPERCALL_TLB_SHOOTDOWN.
+pub struct VcpuKick {
+    pub thread: AtomicU64, // pthread_t of the vcpu thread, 0 before it runs
+    pub kvmRun: AtomicU64, // address of the vcpu's kvm_run
+    pub tlbShootdown: AtomicBool,
+}
+
+const VCPU_KICK_INIT: VcpuKick = VcpuKick {
+    thread: AtomicU64::new(0),
+    kvmRun: AtomicU64::new(0),
+    tlbShootdown: AtomicBool::new(false),
+};
+
+pub static VCPU_KICKS: [VcpuKick; MAX_VCPU_COUNT] = [VCPU_KICK_INIT; MAX_VCPU_COUNT];
+
+// KickSignal interrupts KVM_RUN. The normal signals are forwarded to the
+// container, so a realtime one is used.
+pub fn KickSignal() -> i32 {
+    return SIGRTMIN() + 1;
+}
+
+extern "C" fn HandleKick(_signo: i32) {}
+
+impl VcpuKick {
+    pub fn Init(&self, vcpu: &kvm_ioctls::VcpuFd) -> Result<()> {
+        let mut action: sigaction = unsafe { core::mem::zeroed() };
+        action.sa_sigaction = HandleKick as extern "C" fn(i32) as sighandler_t;
+        let ret = unsafe {
+            sigaction(KickSignal(), &action, core::ptr::null_mut())
+        };
+        if ret < 0 {
+            return Err(Error::SysError(errno::errno().0));
+        }
+
+        // kvm_run is the first page of the vcpu fd mapping; map our own view
+        // of it so that other threads can set immediate_exit.
+        let addr = unsafe {
+            mmap(core::ptr::null_mut(), MemoryDef::PAGE_SIZE as usize, PROT_READ | PROT_WRITE,
+                 MAP_SHARED, vcpu.as_raw_fd(), 0)
+        };
+        if addr == MAP_FAILED {
+            return Err(Error::SysError(errno::errno().0));
+        }
+
+        self.kvmRun.store(addr as u64, Ordering::SeqCst);
+        self.thread.store(unsafe { pthread_self() } as u64, Ordering::SeqCst);
+        return Ok(())
+    }
+
+    pub fn KvmRun(&self) -> &mut kvm_run {
+        return unsafe {
+            &mut *(self.kvmRun.load(Ordering::SeqCst) as *mut kvm_run)
+        }
+    }
+
+    // Kick makes the vcpu leave KVM_RUN and take TLB_SHOOTDOWN_VECTOR.
+    // immediate_exit covers a vcpu thread which is just about to enter
+    // KVM_RUN when the signal arrives.
+    pub fn Kick(&self) {
+        let thread = self.thread.load(Ordering::SeqCst);
+        if thread == 0 {
+            return
+        }
+
+        self.tlbShootdown.store(true, Ordering::SeqCst);
+        unsafe {
+            core::ptr::write_volatile(&mut self.KvmRun().immediate_exit, 1);
+            pthread_kill(thread as pthread_t, KickSignal());
+        }
+    }
+}
+
 pub struct KVMVcpu {
     pub id: usize,
     pub vcpuCnt: usize,
@@ -342,6 +418,31 @@ impl KVMVcpu {
         Ok(())
     }
 
+    // InjectTlbShootdown injects TLB_SHOOTDOWN_VECTOR if another vcpu asked
+    // for it. Without an in-kernel irqchip the interrupt can only be queued
+    // when the guest has interrupts enabled, i.e. in user mode; otherwise
+    // ask KVM to exit once it can take it.
+    pub fn InjectTlbShootdown(&self) -> Result<()> {
+        let kick = &VCPU_KICKS[self.id];
+        if !kick.tlbShootdown.load(Ordering::SeqCst) {
+            return Ok(())
+        }
+
+        let run = kick.KvmRun();
+        if run.ready_for_interrupt_injection != 0 && run.if_flag != 0 {
+            kick.tlbShootdown.store(false, Ordering::SeqCst);
+            run.request_interrupt_window = 0;
+            let ret = QueueInterrupt(&self.vcpu, TLB_SHOOTDOWN_VECTOR);
+            if ret != 0 {
+                return Err(Error::SysError(errno::errno().0));
+            }
+        } else {
+            run.request_interrupt_window = 1;
+        }
+
+        return Ok(())
+    }
+
     pub fn Schedule(&self, taskId: TaskIdQ) {
         self.ShareSpace().scheduler.ScheduleQ(taskId.TaskId(), taskId.Queue());
     }
@@ -381,9 +482,33 @@ impl KVMVcpu {
         let coreid = core_affinity::CoreId{id: self.id + QUARK_CONFIG.lock().DedicateUring}; // skip core #0 for uring
         core_affinity::set_for_current(coreid);
 
+        VCPU_KICKS[self.id].Init(&self.vcpu)?;
+
         info!("start enter guest[{}]: entry is {:x}, stack is {:x}", self.id, self.entry, self.topStackAddr);
         loop {
-            match self.vcpu.run().expect(&format!("kvm virtual cpu[{}] run failed", self.id)) {
+            match self.InjectTlbShootdown() {
+                Ok(()) => (),
+                Err(e) => {
+                    // the shooting vcpu keeps waiting, so kick ourselves to
+                    // try again on the next exit
+                    error!("vcpu[{}] inject tlb shootdown fail: {:?}", self.id, e);
+                    VCPU_KICKS[self.id].Kick();
+                }
+            }
+
+            let exit = match self.vcpu.run() {
+                Ok(exit) => exit,
+                Err(e) if e.errno() == EINTR => {
+                    // kicked by another vcpu, see VcpuKick::Kick
+                    unsafe {
+                        core::ptr::write_volatile(&mut VCPU_KICKS[self.id].KvmRun().immediate_exit, 0);
+                    }
+                    continue
+                }
+                Err(e) => panic!("kvm virtual cpu[{}] run failed: {:?}", self.id, e),
+            };
+
+            match exit {
                 VcpuExit::IoIn(addr, data) => {
                     info!(
                     "[{}]Received an I/O in exit. Address: {:#x}. Data: {:#x}",
@@ -488,7 +613,26 @@ impl KVMVcpu {
 
                             sharespace.Init();
                             let logfd = super::super::print::LOG.lock().Logfd();
-                            super::URING_MGR.lock().Init(sharespace.config.read().DedicateUring);
+                            let (dedicateUring, sqpoll) = {
+                                let config = sharespace.config.read();
+                                (config.DedicateUring, config.UringSqpoll)
+                            };
+                            let sqpoll = super::URING_MGR.lock().Init(dedicateUring, sqpoll);
+                            info!("io_uring SQPOLL is {}", if sqpoll { "active" } else { "inactive" });
+                            let probe = super::URING_MGR.lock().Probe();
+                            if probe.is_none() {
+                                info!("io_uring opcode probing isn't supported by the host kernel");
+                            }
+                            sharespace.uringCaps.Set(probe.as_ref());
+                            let bufs = sharespace.fixedBufs.IoVecs();
+                            if bufs.len() > 0 && sharespace.uringCaps.SupportsReadFixed() {
+                                // the pages are locked against RLIMIT_MEMLOCK on older host
+                                // kernels, so the kernel falls back to the plain ops on failure
+                                match super::URING_MGR.lock().RegisterBuffers(&bufs) {
+                                    Ok(()) => sharespace.fixedBufs.SetRegistered(),
+                                    Err(e) => info!("io_uring fixed buffers can't be registered: {:?}", e),
+                                }
+                            }
                             super::URING_MGR.lock().Addfd(logfd).unwrap();
                             if !sharespace.config.read().SyncPrint {
                                 super::super::print::EnableKernelPrint();
@@ -642,6 +786,12 @@ impl KVMVcpu {
                             error!("[{}] HYPERCALL_VCPU_PRINT regs is {:#x?}", self.id, regs);
                         }
 
+                        qlib::HYPERCALL_TLB_SHOOTDOWN => {
+                            let regs = self.vcpu.get_regs().map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
+                            let vcpuId = regs.rbx as usize;
+                            VCPU_KICKS[vcpuId].Kick();
+                        }
+
                         qlib::HYPERCALL_HCALL => {
                             let regs = self.vcpu.get_regs().map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
                             let addr = regs.rbx;
@@ -690,9 +840,8 @@ impl KVMVcpu {
                     info!("get exception");
                 }
                 VcpuExit::IrqWindowOpen => {
-                    info!("get VcpuExit::IrqWindowOpen");
-                    //QueueTimer(&self.vcpu);
-                    //&self.vcpu.DisableInterruptWindow();
+                    // the pending TLB_SHOOTDOWN_VECTOR is injected at the top
+                    // of the loop
                 }
 
                 r => {
@@ -299,6 +299,17 @@ impl MapOption {
 
         return Ok(())
     }
+
+    pub fn MAdvise(addr: u64, len: u64, advise: i32) -> Result<()> {
+        unsafe {
+            if libc::madvise(addr as *mut libc::c_void, len as usize, advise) != 0 {
+                let errno = errno::errno().0;
+                return Err(Error::SysError(errno));
+            }
+        }
+
+        return Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Default)]
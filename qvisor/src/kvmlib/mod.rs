@@ -28,6 +28,7 @@ mod heap_alloc;
 mod qcall;
 mod vmspace;
 mod kvm_vcpu;
+mod kvm_ctl;
 mod syncmgr;
 pub mod namespace;
 pub mod elf_loader;
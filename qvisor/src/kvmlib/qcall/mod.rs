@@ -106,6 +106,13 @@ pub fn qCall(eventAddr: u64, event: &'static mut Event) -> QcallRet {
             }
             *ret = 0;
         }
+        Event { taskId: _, globalLock: _, ref mut ret, msg: Msg::ReleaseMemory(msg) } => {
+            *ret = match super::PMA_KEEPER.Release(&Range::New(msg.addr, msg.len)) {
+                Ok(()) => 0,
+                Err(Error::SysError(e)) => -e as i64 as u64,
+                Err(err) => panic!("ReleaseMemory: unexpected error {:?}", err),
+            };
+        }
         Event { taskId, globalLock: _, ref mut ret, msg: Msg::LoadProcessKernel(msg) } => {
             *ret = super::VMS.lock().LoadProcessKernel(taskId.Addr(), msg.processAddr, msg.len) as u64;
         }
@@ -240,7 +247,7 @@ pub fn qCall(eventAddr: u64, event: &'static mut Event) -> QcallRet {
             *ret = super::VMSpace::Fgetxattr(taskId.Addr(), msg.fd, msg.name, msg.value, msg.size) as u64;
         }
         Event { taskId, globalLock: _, ref mut ret, msg: Msg::GetRandom(msg) } => {
-            *ret = super::VMS.lock().GetRandom(taskId.Addr(), msg.buf, msg.len, msg.flags) as u64;
+            *ret = super::VMSpace::GetRandom(taskId.Addr(), msg.buf, msg.len, msg.flags) as u64;
         }
         Event { taskId, globalLock: _, ref mut ret, msg: Msg::Fchdir(msg) } => {
             *ret = super::VMSpace::Fchdir(taskId.Addr(), msg.fd) as u64;
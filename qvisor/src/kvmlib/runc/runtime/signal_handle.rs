@@ -15,18 +15,32 @@
 use nix::sys::signal;
 use lazy_static::lazy_static;
 use core::sync::atomic::AtomicBool;
+use core::sync::atomic::AtomicI32;
 use core::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
 
 use super::super::super::qlib::common::*;
 use super::super::super::qlib::control_msg::*;
 use super::super::super::vmspace::*;
 use super::super::super::ucall::usocket::*;
+use super::super::super::ucall::ucall_server;
+use super::super::super::{VMS, KERNEL_IO_THREAD, QUARK_CONFIG};
+use super::vm::*;
 
 lazy_static! {
     static ref SIGNAL_HANDLE_ENABLE : AtomicBool = AtomicBool::new(false);
     static ref CONSOLE : AtomicBool = AtomicBool::new(false);
+    static ref SHUTDOWN_STARTED : AtomicBool = AtomicBool::new(false);
+    // SHUTDOWN_PIPE is the write end of the self-pipe the SIGTERM handler uses
+    // to wake the shutdown watcher thread, or -1 before PrepareHandler.
+    static ref SHUTDOWN_PIPE : AtomicI32 = AtomicI32::new(-1);
 }
 
+// How often the shutdown grace timer checks whether the container has exited.
+const SHUTDOWN_POLL_INTERVAL : Duration = Duration::from_millis(100);
+
 pub fn StartSignalHandle() {
     SIGNAL_HANDLE_ENABLE.store(true, Ordering::SeqCst);
 }
@@ -62,8 +76,10 @@ extern fn handle_sigintAct(signal :i32, signInfo: *mut libc::siginfo_t, _: *mut
         let vms = super::super::super::VMS.lock();
 
         error!("signal debug");
-        for i in 0..8 {
-            error!("vcpu[{}] state is {}/{}", i, vms.GetShareSpace().GetValue(i, 0), vms.GetShareSpace().GetValue(i, 1))
+        let shareSpace = vms.GetShareSpace();
+        let vcpuCount = core::cmp::min(vms.vcpuCount, shareSpace.values.len());
+        for line in VcpuStateDump(vcpuCount, &|cpuId, idx| shareSpace.GetValue(cpuId, idx)) {
+            error!("{}", line);
         }
 
         error!("vcpus is {:x?}", vms.GetShareSpace().scheduler.VcpuArr);
@@ -76,23 +92,214 @@ extern fn handle_sigintAct(signal :i32, signInfo: *mut libc::siginfo_t, _: *mut
 
         error!("get signal {}, action is {:x?}", signal, sigfault);
 
+        // The terminal resize itself carries no payload, so forward the new
+        // host window size to the guest TTY before delivering SIGWINCH.
+        if signal == libc::SIGWINCH && console {
+            match WindowSizePayload(0) {
+                Ok(payload) => {
+                    SendControlMsg(USocket::DummyUSocket(), ControlMsg::New(payload))
+                        .expect("handle_sigint fail when SendControlMsg")
+                }
+                Err(e) => error!("get host window size fail with error {:?}", e),
+            }
+        }
+
 
+        let (mode, pid) = SignalDelivery(signal, console);
         let payload = Payload::Signal({
             SignalArgs {
                 Signo: signal,
-                PID: 0,
-                Mode: if console {
-                    SignalDeliveryMode::DeliverToForegroundProcessGroup
-                } else {
-                    SignalDeliveryMode::DeliverToProcess
-                }
+                PID: pid,
+                Mode: mode,
             }
         });
         SendControlMsg(USocket::DummyUSocket(), ControlMsg::New(payload))
-            .expect("handle_sigint fail when SendControlMsg")
+            .expect("handle_sigint fail when SendControlMsg");
+
+        if signal == libc::SIGTERM {
+            RequestShutdown();
+        }
+    }
+}
+
+// VcpuStateDump formats the per-vcpu debug state for the first vcpuCount
+// vcpus, one line per vcpu.
+pub fn VcpuStateDump(vcpuCount: usize, getValue: &Fn(usize, usize) -> u64) -> Vec<String> {
+    let mut lines = Vec::with_capacity(vcpuCount);
+    for i in 0..vcpuCount {
+        lines.push(format!("vcpu[{}] state is {}/{}", i, getValue(i, 0), getValue(i, 1)));
+    }
+
+    return lines
+}
+
+// SIGNAL_DELIVERY lists the signals that a terminal delivers to its
+// foreground job rather than to the session leader. Signals that are not
+// listed go to the container's init process.
+const SIGNAL_DELIVERY : [(i32, SignalDeliveryMode); 6] = [
+    (libc::SIGINT, SignalDeliveryMode::DeliverToForegroundProcessGroup),
+    (libc::SIGQUIT, SignalDeliveryMode::DeliverToForegroundProcessGroup),
+    (libc::SIGTSTP, SignalDeliveryMode::DeliverToForegroundProcessGroup),
+    (libc::SIGTTIN, SignalDeliveryMode::DeliverToForegroundProcessGroup),
+    (libc::SIGTTOU, SignalDeliveryMode::DeliverToForegroundProcessGroup),
+    (libc::SIGWINCH, SignalDeliveryMode::DeliverToForegroundProcessGroup),
+];
+
+// SignalDelivery returns how a host signal is forwarded into the container:
+// the delivery mode and the PID it targets. SIGTERM asks the whole container
+// to shut down, so it goes to init's process group regardless of the
+// terminal's foreground job. Without a console there is no foreground
+// process group, so everything else goes to the init process.
+pub fn SignalDelivery(signal: i32, console: bool) -> (SignalDeliveryMode, i32) {
+    if signal == libc::SIGTERM {
+        return (SignalDeliveryMode::DeliverToProcessGroup, 0)
+    }
+
+    if console {
+        for (signo, mode) in SIGNAL_DELIVERY.iter() {
+            if *signo == signal {
+                return (*mode, 0)
+            }
+        }
+    }
+
+    return (SignalDeliveryMode::DeliverToProcess, 0)
+}
+
+// RequestShutdown wakes the shutdown watcher thread. It is called from the
+// signal handler, so it only touches an atomic and writes to the self-pipe.
+// Only the first SIGTERM starts the grace timer.
+fn RequestShutdown() {
+    if SHUTDOWN_STARTED.swap(true, Ordering::SeqCst) {
+        return
+    }
+
+    let fd = SHUTDOWN_PIPE.load(Ordering::SeqCst);
+    if fd < 0 {
+        return
+    }
+
+    let b : u8 = 1;
+    unsafe {
+        libc::write(fd, &b as *const u8 as *const libc::c_void, 1);
     }
 }
 
+// StartShutdownWatcher creates the self-pipe written by RequestShutdown and a
+// thread which waits on it. Once woken, the thread gives the container the
+// configured grace period to exit and kills it afterwards.
+fn StartShutdownWatcher() -> Result<()> {
+    let mut fds : [i32; 2] = [0; 2];
+    let ret = unsafe {
+        libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC)
+    };
+
+    if ret < 0 {
+        return Err(Error::SysError(errno::errno().0))
+    }
+
+    let readFd = fds[0];
+    SHUTDOWN_PIPE.store(fds[1], Ordering::SeqCst);
+    thread::spawn(move || {
+        let mut b : u8 = 0;
+        loop {
+            let ret = unsafe {
+                libc::read(readFd, &mut b as *mut u8 as *mut libc::c_void, 1)
+            };
+
+            if ret == 1 {
+                break;
+            }
+
+            if ret < 0 && errno::errno().0 == libc::EINTR {
+                continue;
+            }
+
+            error!("shutdown watcher read fail with ret {}", ret);
+            return;
+        }
+
+        let gracePeriod = Duration::from_secs(QUARK_CONFIG.lock().ShutdownGracePeriod);
+        WaitOrEscalate(gracePeriod, &IsRunning, &mut ForceShutdown);
+    });
+
+    return Ok(())
+}
+
+// WaitOrEscalate waits up to gracePeriod for isRunning to turn false and
+// calls escalate if it is still true afterwards. It returns whether escalate
+// was called.
+pub fn WaitOrEscalate(gracePeriod: Duration, isRunning: &Fn() -> bool, escalate: &mut FnMut()) -> bool {
+    let deadline = Instant::now() + gracePeriod;
+    while Instant::now() < deadline {
+        if !isRunning() {
+            return false
+        }
+
+        thread::sleep(SHUTDOWN_POLL_INTERVAL);
+    }
+
+    if !isRunning() {
+        return false
+    }
+
+    escalate();
+    return true
+}
+
+// ForceShutdown kills every process in the container and stops the sandbox
+// the same way the guest's exit hypercall does.
+fn ForceShutdown() {
+    error!("container didn't exit within the shutdown grace period, killing it");
+
+    let payload = Payload::Signal(SignalArgs {
+        Signo: libc::SIGKILL,
+        PID: 0,
+        Mode: SignalDeliveryMode::DeliverToAllProcesses,
+    });
+
+    match SendControlMsg(USocket::DummyUSocket(), ControlMsg::New(payload)) {
+        Err(e) => error!("ForceShutdown fail when SendControlMsg with error {:?}", e),
+        Ok(()) => (),
+    }
+
+    SetExitStatus(128 + libc::SIGKILL);
+    ucall_server::Stop().ok();
+
+    //wake up Kernel io thread
+    KERNEL_IO_THREAD.Wakeup(VMS.lock().GetShareSpace());
+
+    //wake up workthread
+    VirtualMachine::WakeAll(VMS.lock().GetShareSpace());
+}
+
+// WindowSizePayload reads the window size of the host terminal referred to
+// by fd and builds the control message payload which resizes the guest TTY.
+pub fn WindowSizePayload(fd: i32) -> Result<Payload> {
+    let mut ws = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+
+    let ret = unsafe {
+        libc::ioctl(fd, libc::TIOCGWINSZ, &mut ws as *mut libc::winsize)
+    };
+
+    if ret < 0 {
+        return Err(Error::SysError(errno::errno().0))
+    }
+
+    return Ok(Payload::WindowSize(WindowSizeArgs {
+        PID: 0,
+        Rows: ws.ws_row,
+        Cols: ws.ws_col,
+        Xpixel: ws.ws_xpixel,
+        Ypixel: ws.ws_ypixel,
+    }))
+}
+
 // numSignals is the number of normal (non-realtime) signals on Linux.
 pub const NUM_SIGNALS : usize = 32;
 
@@ -111,6 +318,8 @@ pub fn PrepareHandler() -> Result<()> {
         libc::ioctl( 0, libc::TIOCSCTTY, 0);
     }
 
+    StartShutdownWatcher()?;
+
     let sig_action = signal::SigAction::new(signal::SigHandler::SigAction(handle_sigintAct),
                                             signal::SaFlags::empty(),
                                             signal::SigSet::empty());
@@ -128,4 +337,82 @@ pub fn PrepareHandler() -> Result<()> {
     }
 
     return Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn TestVcpuStateDump() {
+        let lines = VcpuStateDump(3, &|cpuId, idx| (cpuId * 10 + idx) as u64);
+        assert!(lines.len() == 3);
+        assert!(lines[0] == "vcpu[0] state is 0/1");
+        assert!(lines[2] == "vcpu[2] state is 20/21");
+    }
+
+    #[test]
+    fn TestWindowSizePayload() {
+        let mut master = 0;
+        let mut slave = 0;
+        let ret = unsafe {
+            libc::openpty(&mut master, &mut slave, core::ptr::null_mut(), core::ptr::null(), core::ptr::null())
+        };
+        assert!(ret == 0);
+
+        // Simulate a resize of the host terminal.
+        let ws = libc::winsize {
+            ws_row: 42,
+            ws_col: 132,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        let ret = unsafe {
+            libc::ioctl(master, libc::TIOCSWINSZ, &ws as *const libc::winsize)
+        };
+        assert!(ret == 0);
+
+        match WindowSizePayload(slave).unwrap() {
+            Payload::WindowSize(args) => {
+                assert!(args.PID == 0);
+                assert!(args.Rows == 42);
+                assert!(args.Cols == 132);
+            }
+            p => panic!("unexpected payload {:?}", p),
+        }
+
+        unsafe {
+            libc::close(master);
+            libc::close(slave);
+        }
+    }
+
+    #[test]
+    fn TestSignalDelivery() {
+        assert_eq!(SignalDelivery(libc::SIGINT, true), (SignalDeliveryMode::DeliverToForegroundProcessGroup, 0));
+        assert_eq!(SignalDelivery(libc::SIGTSTP, true), (SignalDeliveryMode::DeliverToForegroundProcessGroup, 0));
+        assert_eq!(SignalDelivery(libc::SIGINT, false), (SignalDeliveryMode::DeliverToProcess, 0));
+
+        for console in &[true, false] {
+            assert_eq!(SignalDelivery(libc::SIGTERM, *console), (SignalDeliveryMode::DeliverToProcessGroup, 0));
+            assert_eq!(SignalDelivery(libc::SIGHUP, *console), (SignalDeliveryMode::DeliverToProcess, 0));
+        }
+    }
+
+    #[test]
+    fn TestShutdownEscalation() {
+        // The guest ignores SIGTERM and keeps running.
+        let mut escalated = false;
+        let fired = WaitOrEscalate(Duration::from_millis(300), &|| true, &mut || escalated = true);
+        assert!(fired);
+        assert!(escalated);
+    }
+
+    #[test]
+    fn TestShutdownWithinGracePeriod() {
+        let mut escalated = false;
+        let fired = WaitOrEscalate(Duration::from_millis(300), &|| false, &mut || escalated = true);
+        assert!(!fired);
+        assert!(!escalated);
+    }
+}
@@ -24,15 +24,22 @@ use super::super::IO_MGR;
 
 
 #[derive(Clone, Default)]
-pub struct HostSegment {}
+pub struct HostSegment {
+    // anon is whether the segment is anonymous memory rather than a file mapping
+    pub anon: bool,
+}
 
 impl AreaValue for HostSegment {
-    fn Merge(&self, _r1: &Range, _r2: &Range, _vma2: &HostSegment) -> Option<HostSegment> {
-        return Some(HostSegment {})
+    fn Merge(&self, _r1: &Range, _r2: &Range, vma2: &HostSegment) -> Option<HostSegment> {
+        if self.anon != vma2.anon {
+            return None
+        }
+
+        return Some(self.clone())
     }
 
     fn Split(&self, _r: &Range, _split: u64) -> (HostSegment, HostSegment) {
-        return (HostSegment {}, HostSegment {})
+        return (self.clone(), self.clone())
     }
 }
 
@@ -68,7 +75,7 @@ impl HostPMAKeeper {
     }
 
     pub fn InitHugePages(&self) {
-        let hugePageStart = self.RangeAllocate(Self::HUGE_PAGE_RANGE, MemoryDef::PAGE_SIZE_2M).unwrap();
+        let hugePageStart = self.RangeAllocate(Self::HUGE_PAGE_RANGE, MemoryDef::PAGE_SIZE_2M, false).unwrap();
         let mut addr = hugePageStart;
         while addr < hugePageStart + Self::HUGE_PAGE_RANGE as u64 {
             self.FreeHugePage(addr);
@@ -97,7 +104,7 @@ impl HostPMAKeeper {
         mo = mo.MapAnan().Proto(prot).Len(len);
         mo.MapShare();
 
-        let start = self.Allocate(len, MemoryDef::PAGE_SIZE)?;
+        let start = self.Allocate(len, MemoryDef::PAGE_SIZE, true)?;
         mo.Addr(start);
         return self.Map(&mut mo, &Range::New(start, len));
     }
@@ -113,24 +120,24 @@ impl HostPMAKeeper {
         mo.MapShare();
         mo.MapLocked();
 
-        let start = self.Allocate(len, MemoryDef::PMD_SIZE)?;
+        let start = self.Allocate(len, MemoryDef::PMD_SIZE, false)?;
         mo.Addr(start);
         return self.Map(&mut mo, &Range::New(start, len));
     }
 
-    fn RangeAllocate(&self, len: u64, alignment: u64) -> Result<u64> {
+    fn RangeAllocate(&self, len: u64, alignment: u64, anon: bool) -> Result<u64> {
         let mut ranges = self.ranges.lock();
         let start = ranges.FindAvailable(len, alignment)?;
 
         let r = Range::New(start, len);
         let gap = ranges.FindGap(start);
-        let seg = ranges.Insert(&gap, &r, HostSegment {});
+        let seg = ranges.Insert(&gap, &r, HostSegment { anon: anon });
         assert!(seg.Ok(), "AreaSet <HostSegment>:: insert fail");
 
         return Ok(start)
     }
 
-    fn Allocate(&self, len: u64, alignment: u64) -> Result<u64> {
+    fn Allocate(&self, len: u64, alignment: u64, anon: bool) -> Result<u64> {
         if len != MemoryDef::PAGE_SIZE_2M {
             error!("Allocate len is {:x} alignment {:x}", len, alignment);
         }
@@ -141,7 +148,7 @@ impl HostPMAKeeper {
             return Ok(addr)
         }
 
-        return self.RangeAllocate(len, alignment);
+        return self.RangeAllocate(len, alignment, anon);
     }
 
     pub fn RemoveSeg(&self, r: &Range) {
@@ -169,6 +176,22 @@ impl HostPMAKeeper {
         let res = MapOption::MUnmap(r.Start(), r.Len());
         return res;
     }
+
+    // Release frees the host pages backing r, which must be in an anonymous
+    // mapping. The mappings are shared, so MADV_DONTNEED would only drop the
+    // ptes; MADV_REMOVE punches the pages out of the backing shmem and the
+    // range reads as zero afterwards.
+    pub fn Release(&self, r: &Range) -> Result<()> {
+        {
+            let ranges = self.ranges.lock();
+            let (seg, _gap) = ranges.Find(r.Start());
+            if !seg.Ok() || !seg.Range().IsSupersetOf(r) || !seg.Value().anon {
+                return Err(Error::SysError(SysErr::EINVAL))
+            }
+        }
+
+        return MapOption::MAdvise(r.Start(), r.Len(), libc::MADV_REMOVE);
+    }
 }
 
 impl AreaSet<HostSegment> {
@@ -19,7 +19,6 @@ pub mod timer_keeper;
 pub mod hostfdnotifier;
 pub mod time;
 pub mod host_pma_keeper;
-pub mod random;
 pub mod limits;
 pub mod uringMgr;
 pub mod host_uring;
@@ -62,7 +61,6 @@ use super::*;
 use self::HostFileMap::fdinfo::*;
 use self::syscall::*;
 use self::time::*;
-use self::random::*;
 use self::limits::*;
 use super::runc::runtime::signal_handle::*;
 use super::kvm_vcpu::SimplePageAllocator;
@@ -108,7 +106,6 @@ pub struct VMSpace {
     pub vcpuCount: usize,
 
     pub shareSpace: &'static ShareSpace,
-    pub rng: RandGen,
     pub args: Option<Args>,
     pub pivot: bool,
     pub waitingMsgCall: Option<WaitingMsgCall>,
@@ -1200,13 +1197,18 @@ impl VMSpace {
         }
     }
 
-    pub fn GetRandom(&mut self, _taskId: u64, buf: u64, len: u64, _flags: u32) -> i64 {
-        unsafe {
-            let slice = slice::from_raw_parts_mut(buf as *mut u8, len as usize);
-            self.rng.Fill(slice);
-        }
+    // GetRandom reads from the host's urandom pool. GRND_NONBLOCK is passed
+    // on, so a read only blocks until the host pool is initialized. GRND_RANDOM
+    // is dropped: the blocking pool would let one guest stall the host's
+    // entropy for everyone.
+    pub fn GetRandom(_taskId: u64, buf: u64, len: u64, flags: u32) -> i64 {
+        let flags = flags & !(_GRND_RANDOM as u32);
+        let nr = SysCallID::sys_getrandom as usize;
+        let ret = unsafe {
+            syscall3(nr, buf as usize, len as usize, flags as usize) as i64
+        };
 
-        return len as i64;
+        return ret
     }
 
     pub fn Fchdir(_taskId: u64, fd: i32) -> i64 {
@@ -1232,7 +1234,8 @@ impl VMSpace {
             posix_fadvise(fd, offset as i64, len as i64, advice)
         };
 
-        return Self::GetRet(ret as i64)
+        // posix_fadvise returns the error number instead of setting errno.
+        return -ret as i64
     }
 
     pub fn Mlock2(_taskId: u64, addr: u64, len: u64, flags: u32) -> i64 {
@@ -1568,7 +1571,6 @@ impl VMSpace {
             shareSpace: unsafe {
                 &mut *(0 as * mut ShareSpace)
             },
-            rng: RandGen::Init(),
             args: None,
             pivot: false,
             waitingMsgCall: None,
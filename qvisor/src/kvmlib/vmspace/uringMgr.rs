@@ -15,6 +15,7 @@
 use alloc::vec::Vec;
 
 use super::super::qlib::common::*;
+use super::super::qlib::linux_def::IoVec;
 use super::super::qlib::uring::sys::sys::*;
 use super::super::qlib::uring::*;
 
@@ -50,30 +51,43 @@ impl UringMgr {
         return ret;
     }
 
-    pub fn Init(&mut self, DedicateUringCnt: usize) {
-        if DedicateUringCnt == 0 {
-            let ring = Builder::default()
-                .setup_sqpoll(10)
-                .setup_sqpoll_cpu(0) // vcpu#0
-                .setup_clamp()
-                .setup_cqsize(self.uringSize as u32 * 2)
-                .build(self.uringSize as u32).expect("InitUring fail");
+    // Init creates the rings, one per dedicated uring cpu or a single one if
+    // there are none. With sqpoll, each ring gets a kernel thread pinned to
+    // its cpu which polls the submission queue, so the kernel doesn't need
+    // io_uring_enter to submit. SQPOLL needs CAP_SYS_ADMIN on host kernels
+    // before 5.11, so Init falls back to plain rings when it's refused, and
+    // returns whether SQPOLL is in use.
+    pub fn Init(&mut self, DedicateUringCnt: usize, sqpoll: bool) -> bool {
+        let mut sqpoll = sqpoll;
+        for i in 0..core::cmp::max(DedicateUringCnt, 1) {
+            let ring = match self.NewRing(i, sqpoll) {
+                Ok(ring) => ring,
+                Err(e) if sqpoll => {
+                    info!("io_uring SQPOLL setup fail with {:?}, fall back to io_uring_enter", e);
+                    sqpoll = false;
+                    self.NewRing(i, sqpoll).expect("InitUring fail")
+                }
+                Err(e) => panic!("InitUring fail {:?}", e),
+            };
+
             self.uringfds.push(ring.fd.0);
             self.rings.push(ring);
-        } else {
-            for i in 0..DedicateUringCnt {
-                let ring = Builder::default()
-                    .setup_sqpoll(10)
-                    .setup_sqpoll_cpu(i as u32)
-                    .setup_clamp()
-                    .setup_cqsize(self.uringSize as u32 * 2)
-                    .build(self.uringSize as u32).expect("InitUring fail");
-                self.uringfds.push(ring.fd.0);
-                self.rings.push(ring);
-            }
         }
 
         self.Register(IORING_REGISTER_FILES, &self.fds[0] as * const _ as u64, self.fds.len() as u32).expect("InitUring register files fail");
+        return sqpoll
+    }
+
+    fn NewRing(&self, idx: usize, sqpoll: bool) -> Result<IoUring> {
+        let mut builder = Builder::default();
+        if sqpoll {
+            builder.setup_sqpoll(10)
+                .setup_sqpoll_cpu(idx as u32);
+        }
+
+        return builder.setup_clamp()
+            .setup_cqsize(self.uringSize as u32 * 2)
+            .build(self.uringSize as u32);
     }
 
     pub fn Setup(&mut self, idx: usize, submission: u64, completion: u64) -> Result<i32> {
@@ -81,6 +95,24 @@ impl UringMgr {
         return Ok(0)
     }
 
+    // Probe returns the opcodes the host kernel supports, or None if it
+    // predates IORING_REGISTER_PROBE.
+    pub fn Probe(&self) -> Option<Probe> {
+        let mut probe = Probe::new();
+        let fd = self.uringfds[0];
+        if IOUringRegister(fd, IORING_REGISTER_PROBE, probe.as_mut_ptr() as u64, Probe::COUNT as u32) < 0 {
+            return None
+        }
+
+        return Some(probe)
+    }
+
+    // RegisterBuffers registers the kernel's fixed buffer pool with every
+    // ring, so that READ_FIXED and WRITE_FIXED can use the buffers by index.
+    pub fn RegisterBuffers(&self, bufs: &[IoVec]) -> Result<()> {
+        return self.Register(IORING_REGISTER_BUFFERS, &bufs[0] as * const _ as u64, bufs.len() as u32);
+    }
+
     pub fn SetupEventfd(&mut self, eventfd: i32) {
         self.eventfd = eventfd;
 
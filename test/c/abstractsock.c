@@ -0,0 +1,112 @@
+#define _GNU_SOURCE
+#include <ctype.h>
+#include <errno.h>
+#include <stddef.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <unistd.h>
+
+static const char name[] = "\0quark-abstract\0x";
+
+// abstractAddr fills addr with the abstract name and returns its length.
+static socklen_t abstractAddr(struct sockaddr_un *addr)
+{
+    memset(addr, 0, sizeof(*addr));
+    addr->sun_family = AF_UNIX;
+    memcpy(addr->sun_path, name, sizeof(name) - 1);
+    return offsetof(struct sockaddr_un, sun_path) + sizeof(name) - 1;
+}
+
+int main()
+{
+    struct sockaddr_un addr, got;
+    socklen_t len = abstractAddr(&addr);
+
+    int srv = socket(AF_UNIX, SOCK_STREAM, 0);
+    if (srv < 0 || bind(srv, (struct sockaddr *)&addr, len) < 0 || listen(srv, 4) < 0) {
+        perror("bind abstract");
+        return 1;
+    }
+
+    // the name is returned length-delimited, including the embedded NUL
+    socklen_t glen = sizeof(got);
+    if (getsockname(srv, (struct sockaddr *)&got, &glen) < 0 || glen != len ||
+        memcmp(got.sun_path, name, sizeof(name) - 1) != 0) {
+        printf("fail: getsockname returned length %d\n", glen);
+        return 1;
+    }
+
+    // a name in use is refused and leaves the socket free to bind elsewhere
+    int other = socket(AF_UNIX, SOCK_STREAM, 0);
+    if (bind(other, (struct sockaddr *)&addr, len) != -1 || errno != EADDRINUSE) {
+        printf("fail: expected EADDRINUSE\n");
+        return 1;
+    }
+    if (bind(other, (struct sockaddr *)&addr, len - 1) < 0) {
+        perror("bind after EADDRINUSE");
+        return 1;
+    }
+    close(other);
+
+    int cli = socket(AF_UNIX, SOCK_STREAM, 0);
+    if (cli < 0 || connect(cli, (struct sockaddr *)&addr, len) < 0) {
+        perror("connect abstract");
+        return 1;
+    }
+    int conn = accept(srv, NULL, NULL);
+    char c;
+    if (conn < 0 || write(cli, "a", 1) != 1 || read(conn, &c, 1) != 1 || c != 'a') {
+        printf("fail: no data over the abstract socket\n");
+        return 1;
+    }
+
+    // binding only the family picks a unique "\0XXXXX" name
+    int a1 = socket(AF_UNIX, SOCK_DGRAM, 0);
+    int a2 = socket(AF_UNIX, SOCK_DGRAM, 0);
+    struct sockaddr_un fam = {.sun_family = AF_UNIX};
+    struct sockaddr_un n1, n2;
+    socklen_t l1 = sizeof(n1), l2 = sizeof(n2);
+    if (bind(a1, (struct sockaddr *)&fam, sizeof(sa_family_t)) < 0 ||
+        bind(a2, (struct sockaddr *)&fam, sizeof(sa_family_t)) < 0 ||
+        getsockname(a1, (struct sockaddr *)&n1, &l1) < 0 ||
+        getsockname(a2, (struct sockaddr *)&n2, &l2) < 0) {
+        perror("autobind");
+        return 1;
+    }
+    if (l1 != sizeof(sa_family_t) + 6 || n1.sun_path[0] != 0 || l2 != l1 ||
+        memcmp(n1.sun_path, n2.sun_path, 6) == 0) {
+        printf("fail: bad autobind names\n");
+        return 1;
+    }
+    for (int i = 1; i < 6; i++) {
+        if (!isxdigit((unsigned char)n1.sun_path[i])) {
+            printf("fail: autobind name isn't hex\n");
+            return 1;
+        }
+    }
+
+    // datagrams can be sent to an autobound name
+    if (sendto(a2, "b", 1, 0, (struct sockaddr *)&n1, l1) != 1 || recv(a1, &c, 1, 0) != 1 ||
+        c != 'b') {
+        printf("fail: sendto an autobound name\n");
+        return 1;
+    }
+
+    // the name is released when the listener closes, even with a live connection
+    close(srv);
+    srv = socket(AF_UNIX, SOCK_STREAM, 0);
+    if (bind(srv, (struct sockaddr *)&addr, len) < 0) {
+        perror("rebind after close");
+        return 1;
+    }
+
+    close(srv);
+    close(conn);
+    close(cli);
+    close(a1);
+    close(a2);
+    printf("pass\n");
+    return 0;
+}
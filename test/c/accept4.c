@@ -0,0 +1,99 @@
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <poll.h>
+#include <stdio.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+// pending reports whether poll(2) sees a connection waiting on the listener.
+static int pending(int srv)
+{
+    struct pollfd pfd = {srv, POLLIN, 0};
+    return poll(&pfd, 1, 0) == 1 && (pfd.revents & POLLIN);
+}
+
+int main()
+{
+    int srv = socket(AF_INET, SOCK_STREAM | SOCK_NONBLOCK, 0);
+    struct sockaddr_in addr = {.sin_family = AF_INET};
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    socklen_t alen = sizeof(addr);
+    if (srv < 0 || bind(srv, (struct sockaddr *)&addr, sizeof(addr)) < 0 || listen(srv, 4) < 0 ||
+        getsockname(srv, (struct sockaddr *)&addr, &alen) < 0) {
+        perror("listen");
+        return 1;
+    }
+
+    if (accept4(srv, NULL, NULL, ~(SOCK_NONBLOCK | SOCK_CLOEXEC)) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL for unknown accept4 flags\n");
+        return 1;
+    }
+
+    // an empty backlog is not readable
+    if (pending(srv)) {
+        printf("fail: listener readable without a connection\n");
+        return 1;
+    }
+    if (accept4(srv, NULL, NULL, 0) != -1 || errno != EAGAIN) {
+        printf("fail: expected EAGAIN from an empty backlog\n");
+        return 1;
+    }
+
+    int cli = socket(AF_INET, SOCK_STREAM, 0);
+    if (cli < 0 || connect(cli, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        perror("connect");
+        return 1;
+    }
+
+    struct pollfd pfd = {srv, POLLIN, 0};
+    if (poll(&pfd, 1, 5000) != 1 || !(pfd.revents & POLLIN)) {
+        printf("fail: listener not readable with a pending connection\n");
+        return 1;
+    }
+
+    int conn = accept4(srv, NULL, NULL, SOCK_NONBLOCK | SOCK_CLOEXEC);
+    if (conn < 0) {
+        perror("accept4");
+        return 1;
+    }
+    if (!(fcntl(conn, F_GETFL) & O_NONBLOCK) || !(fcntl(conn, F_GETFD) & FD_CLOEXEC)) {
+        printf("fail: accept4 flags not applied\n");
+        return 1;
+    }
+
+    char c;
+    if (read(conn, &c, 1) != -1 || errno != EAGAIN) {
+        printf("fail: accepted socket isn't non-blocking\n");
+        return 1;
+    }
+
+    // once the only connection is accepted the listener isn't readable again
+    if (pending(srv)) {
+        printf("fail: listener still readable after the backlog drained\n");
+        return 1;
+    }
+    close(conn);
+
+    // plain accept leaves both flags clear
+    int cli2 = socket(AF_INET, SOCK_STREAM, 0);
+    if (cli2 < 0 || connect(cli2, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        perror("connect");
+        return 1;
+    }
+    poll(&pfd, 1, 5000);
+    conn = accept(srv, NULL, NULL);
+    if (conn < 0 || (fcntl(conn, F_GETFL) & O_NONBLOCK) || (fcntl(conn, F_GETFD) & FD_CLOEXEC)) {
+        printf("fail: accept set flags it shouldn't have\n");
+        return 1;
+    }
+
+    close(conn);
+    close(cli);
+    close(cli2);
+    close(srv);
+    printf("pass\n");
+    return 0;
+}
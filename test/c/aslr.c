@@ -0,0 +1,100 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/personality.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// Each execution lays out the stack, heap, mmap area and PIE base at random
+// addresses, unless the persona has ADDR_NO_RANDOMIZE.
+
+// layout runs a copy of this program and reads the addresses it reports.
+static int layout(const char *self, char *buf, size_t len)
+{
+    int p[2];
+    if (pipe(p) < 0) {
+        perror("pipe");
+        return -1;
+    }
+
+    pid_t pid = fork();
+    if (pid == 0) {
+        dup2(p[1], 1);
+        execl(self, self, "child", NULL);
+        _exit(127);
+    }
+    close(p[1]);
+
+    ssize_t n = read(p[0], buf, len - 1);
+    close(p[0]);
+    int status;
+    waitpid(pid, &status, 0);
+    if (n <= 0 || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fail: the child didn't report its layout\n");
+        return -1;
+    }
+    buf[n] = 0;
+    return 0;
+}
+
+static int randomizeLevel()
+{
+    int level = 2;
+    FILE *f = fopen("/proc/sys/kernel/randomize_va_space", "r");
+    if (f) {
+        if (fscanf(f, "%d", &level) != 1) {
+            level = 2;
+        }
+        fclose(f);
+    }
+    return level;
+}
+
+int main(int argc, char **argv)
+{
+    if (argc > 1 && strcmp(argv[1], "child") == 0) {
+        int local;
+        void *m = mmap(NULL, 4096, PROT_READ, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+        printf("stack %p mmap %p brk %p main %p\n", (void *)&local, m, sbrk(0), (void *)main);
+        return 0;
+    }
+
+    int persona = personality(0xffffffff);
+    if (persona < 0) {
+        perror("personality");
+        return 1;
+    }
+
+    char a[256], b[256];
+    if (randomizeLevel() > 0 && !(persona & ADDR_NO_RANDOMIZE)) {
+        if (layout(argv[0], a, sizeof(a)) < 0 || layout(argv[0], b, sizeof(b)) < 0) {
+            return 1;
+        }
+        if (strcmp(a, b) == 0) {
+            printf("fail: two executions have the same layout: %s", a);
+            return 1;
+        }
+    }
+
+    if (personality(persona | ADDR_NO_RANDOMIZE) != persona) {
+        printf("fail: personality didn't return the old persona\n");
+        return 1;
+    }
+    if (personality(0xffffffff) != (persona | ADDR_NO_RANDOMIZE)) {
+        printf("fail: the persona wasn't set\n");
+        return 1;
+    }
+
+    if (layout(argv[0], a, sizeof(a)) < 0 || layout(argv[0], b, sizeof(b)) < 0) {
+        return 1;
+    }
+    if (strcmp(a, b) != 0) {
+        printf("fail: the layout changed with ADDR_NO_RANDOMIZE:\n%s%s", a, b);
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,107 @@
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <sys/time.h>
+#include <unistd.h>
+
+#define SRC "/tmp/bindmount_src"
+#define DST "/tmp/bindmount_dst"
+
+int main()
+{
+    mkdir(SRC, 0755);
+    mkdir(DST, 0755);
+
+    int fd = open(SRC "/f", O_CREAT | O_WRONLY, 0644);
+    if (fd < 0) {
+        perror("open");
+        return 1;
+    }
+    close(fd);
+
+    if (mount(SRC, DST, NULL, MS_BIND, NULL) < 0) {
+        perror("mount bind");
+        return 1;
+    }
+
+    // a read-only remount of the bind mount leaves its source writable
+    if (mount(NULL, DST, NULL, MS_REMOUNT | MS_BIND | MS_RDONLY, NULL) < 0) {
+        perror("mount remount");
+        return 1;
+    }
+
+    fd = open(DST "/f", O_WRONLY);
+    if (fd >= 0 || errno != EROFS) {
+        printf("fail: write open through the read-only bind mount: fd %d, %s\n", fd, strerror(errno));
+        return 1;
+    }
+
+    // attribute changes are refused too, even for the file's owner
+    if (chmod(DST "/f", 0600) == 0 || errno != EROFS) {
+        printf("fail: chmod through the read-only bind mount: %s\n", strerror(errno));
+        return 1;
+    }
+    if (fchmodat(AT_FDCWD, DST "/f", 0600, 0) == 0 || errno != EROFS) {
+        printf("fail: fchmodat through the read-only bind mount: %s\n", strerror(errno));
+        return 1;
+    }
+    if (chown(DST "/f", getuid(), getgid()) == 0 || errno != EROFS) {
+        printf("fail: chown through the read-only bind mount: %s\n", strerror(errno));
+        return 1;
+    }
+    if (utimes(DST "/f", NULL) == 0 || errno != EROFS) {
+        printf("fail: utimes through the read-only bind mount: %s\n", strerror(errno));
+        return 1;
+    }
+    if (truncate(DST "/f", 0) == 0 || errno != EROFS) {
+        printf("fail: truncate through the read-only bind mount: %s\n", strerror(errno));
+        return 1;
+    }
+
+    fd = open(DST "/f", O_RDONLY);
+    if (fd < 0) {
+        perror("open");
+        return 1;
+    }
+    if (fchmod(fd, 0600) == 0 || errno != EROFS) {
+        printf("fail: fchmod through the read-only bind mount: %s\n", strerror(errno));
+        return 1;
+    }
+    close(fd);
+
+    fd = open(SRC "/f", O_WRONLY);
+    if (fd < 0) {
+        printf("fail: the source became read-only: %s\n", strerror(errno));
+        return 1;
+    }
+    close(fd);
+
+    if (chmod(SRC "/f", 0600) < 0 || utimes(SRC "/f", NULL) < 0) {
+        printf("fail: the source's attributes can't be changed: %s\n", strerror(errno));
+        return 1;
+    }
+
+    // an open file keeps the bind mount busy
+    fd = open(DST "/f", O_RDONLY);
+    if (fd < 0) {
+        perror("open");
+        return 1;
+    }
+
+    if (umount(DST) == 0 || errno != EBUSY) {
+        printf("fail: unmounting a busy bind mount: %s\n", strerror(errno));
+        return 1;
+    }
+
+    close(fd);
+    if (umount(DST) < 0) {
+        perror("umount");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
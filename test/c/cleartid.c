@@ -0,0 +1,110 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <linux/futex.h>
+#include <sched.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/prctl.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// CLONE_CHILD_CLEARTID zeroes the child's tid word and wakes a futex waiter
+// on it when the child exits, however it exits. CLONE_PARENT_SETTID stores
+// the tid in the parent and set_tid_address replaces the address.
+
+#define STACK_SIZE (256 * 1024)
+
+static volatile int ctid = -1;
+static int ptid;
+
+static int threadExit(void *arg)
+{
+    usleep(100000);
+    syscall(SYS_exit, 0);
+    return 0;
+}
+
+static int spin(void *arg)
+{
+    for (;;) {
+        pause();
+    }
+    return 0;
+}
+
+// waitClear waits on the futex until the tid word is zero.
+static int waitClear()
+{
+    for (int i = 0; i < 50; i++) {
+        int v = ctid;
+        if (v == 0) {
+            return 0;
+        }
+        struct timespec ts = {.tv_sec = 0, .tv_nsec = 100000000};
+        syscall(SYS_futex, &ctid, FUTEX_WAIT, v, &ts, NULL, 0);
+    }
+    return -1;
+}
+
+int main()
+{
+    char *stack = malloc(STACK_SIZE);
+    if (stack == NULL) {
+        perror("malloc");
+        return 1;
+    }
+
+    // a thread which calls exit
+    int flags = CLONE_VM | CLONE_FS | CLONE_FILES | CLONE_SIGHAND | CLONE_THREAD | CLONE_SYSVSEM |
+                CLONE_PARENT_SETTID | CLONE_CHILD_CLEARTID;
+    int tid = clone(threadExit, stack + STACK_SIZE, flags, NULL, &ptid, NULL, (void *)&ctid);
+    if (tid < 0) {
+        perror("clone");
+        return 1;
+    }
+    if (ptid != tid) {
+        printf("fail: CLONE_PARENT_SETTID stored %d, clone returned %d\n", ptid, tid);
+        return 1;
+    }
+    if (waitClear() < 0) {
+        printf("fail: the exiting thread didn't clear its tid\n");
+        return 1;
+    }
+
+    // a child sharing the address space which is killed by a signal
+    ctid = -1;
+    int pid = clone(spin, stack + STACK_SIZE, CLONE_VM | CLONE_CHILD_CLEARTID | SIGCHLD, NULL, NULL, NULL,
+                    (void *)&ctid);
+    if (pid < 0) {
+        perror("clone");
+        return 1;
+    }
+    usleep(100000);
+    kill(pid, SIGKILL);
+    if (waitClear() < 0) {
+        printf("fail: the killed child didn't clear its tid\n");
+        return 1;
+    }
+    waitpid(pid, NULL, 0);
+
+    // set_tid_address returns the tid and replaces the address
+    static int word;
+    if (syscall(SYS_set_tid_address, &word) != syscall(SYS_gettid)) {
+        printf("fail: set_tid_address didn't return the tid\n");
+        return 1;
+    }
+    int *addr = NULL;
+    if (prctl(PR_GET_TID_ADDRESS, &addr, 0, 0, 0) < 0) {
+        perror("prctl PR_GET_TID_ADDRESS");
+        return 1;
+    }
+    if (addr != &word) {
+        printf("fail: PR_GET_TID_ADDRESS returned %p, want %p\n", (void *)addr, (void *)&word);
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
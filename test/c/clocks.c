@@ -0,0 +1,135 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <pthread.h>
+#include <signal.h>
+#include <stdio.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+// clock_gettime reads every clock both through the vDSO and the syscall,
+// including the CPU clocks returned by clock_getcpuclockid and
+// pthread_getcpuclockid.
+
+static long long ts2ns(struct timespec *ts)
+{
+    return ts->tv_sec * 1000000000LL + ts->tv_nsec;
+}
+
+// readClock returns the time of clock id, or -1 if either path fails.
+static long long readClock(clockid_t id)
+{
+    struct timespec a, b;
+    if (clock_gettime(id, &a) < 0 || syscall(SYS_clock_gettime, id, &b) < 0) {
+        return -1;
+    }
+    return ts2ns(&a) > ts2ns(&b) ? ts2ns(&a) : ts2ns(&b);
+}
+
+static void spin(int ms)
+{
+    long long end = readClock(CLOCK_MONOTONIC) + ms * 1000000LL;
+    while (readClock(CLOCK_MONOTONIC) < end) {
+    }
+}
+
+static volatile int stop;
+
+static void *worker(void *arg)
+{
+    while (!stop) {
+    }
+    return NULL;
+}
+
+int main()
+{
+    clockid_t clocks[] = {
+        CLOCK_REALTIME, CLOCK_MONOTONIC, CLOCK_PROCESS_CPUTIME_ID, CLOCK_THREAD_CPUTIME_ID,
+        CLOCK_MONOTONIC_RAW, CLOCK_REALTIME_COARSE, CLOCK_MONOTONIC_COARSE, CLOCK_BOOTTIME,
+    };
+    for (int i = 0; i < sizeof(clocks) / sizeof(clocks[0]); i++) {
+        struct timespec res;
+        if (readClock(clocks[i]) < 0 || clock_getres(clocks[i], &res) < 0) {
+            printf("fail: clock %d: %d\n", clocks[i], errno);
+            return 1;
+        }
+    }
+
+    // the monotonic clocks move forward together
+    long long mono = readClock(CLOCK_MONOTONIC), raw = readClock(CLOCK_MONOTONIC_RAW), boot = readClock(CLOCK_BOOTTIME);
+    usleep(50 * 1000);
+    if (readClock(CLOCK_MONOTONIC_RAW) - raw < 40000000LL || readClock(CLOCK_BOOTTIME) - boot < 40000000LL) {
+        printf("fail: MONOTONIC_RAW or BOOTTIME didn't advance\n");
+        return 1;
+    }
+    if (boot < mono) {
+        printf("fail: BOOTTIME is behind MONOTONIC\n");
+        return 1;
+    }
+
+    // the clock of the caller's process
+    clockid_t self;
+    if (clock_getcpuclockid(0, &self) != 0 || clock_getcpuclockid(getpid(), &self) != 0) {
+        printf("fail: clock_getcpuclockid of the caller\n");
+        return 1;
+    }
+    long long cpu = readClock(self);
+    spin(100);
+    if (cpu < 0 || readClock(self) - cpu < 50000000LL) {
+        printf("fail: the process cpu clock didn't advance while spinning\n");
+        return 1;
+    }
+
+    // the clock of another thread advances while it runs
+    pthread_t th;
+    clockid_t thc;
+    pthread_create(&th, NULL, worker, NULL);
+    if (pthread_getcpuclockid(th, &thc) != 0) {
+        printf("fail: pthread_getcpuclockid\n");
+        return 1;
+    }
+    cpu = readClock(thc);
+    usleep(200 * 1000);
+    long long workerCpu = readClock(thc) - cpu;
+    stop = 1;
+    pthread_join(th, NULL);
+    if (cpu < 0 || workerCpu < 50000000LL) {
+        printf("fail: the worker's cpu clock advanced %lld\n", workerCpu);
+        return 1;
+    }
+
+    // the clock of a child process
+    pid_t pid = fork();
+    if (pid == 0) {
+        spin(100);
+        pause();
+        _exit(0);
+    }
+    clockid_t child;
+    if (clock_getcpuclockid(pid, &child) != 0) {
+        printf("fail: clock_getcpuclockid of a child\n");
+        return 1;
+    }
+    usleep(300 * 1000);
+    if (readClock(child) < 50000000LL) {
+        printf("fail: the child's cpu clock\n");
+        return 1;
+    }
+    kill(pid, SIGKILL);
+    waitpid(pid, NULL, 0);
+
+    struct timespec ts;
+    if (clock_gettime(child, &ts) != -1 || errno != EINVAL) {
+        printf("fail: read the clock of a reaped process\n");
+        return 1;
+    }
+    if (clock_gettime(100, &ts) != -1 || errno != EINVAL) {
+        printf("fail: read an invalid clock\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,99 @@
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <poll.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+static int soError(int fd)
+{
+    int err = -1;
+    socklen_t len = sizeof(err);
+    if (getsockopt(fd, SOL_SOCKET, SO_ERROR, &err, &len) < 0) {
+        perror("getsockopt(SO_ERROR)");
+        return -1;
+    }
+    return err;
+}
+
+// connectNB starts a non-blocking connect and waits for it to finish, returning
+// the SO_ERROR value.
+static int connectNB(int fd, struct sockaddr_in *addr)
+{
+    int ret = connect(fd, (struct sockaddr *)addr, sizeof(*addr));
+    if (ret == 0) {
+        return 0;
+    }
+    if (errno != EINPROGRESS) {
+        return errno;
+    }
+
+    struct pollfd pfd = {fd, POLLOUT, 0};
+    if (poll(&pfd, 1, 10000) != 1) {
+        printf("fail: connect didn't complete\n");
+        return -1;
+    }
+    return soError(fd);
+}
+
+int main()
+{
+    int srv = socket(AF_INET, SOCK_STREAM, 0);
+    struct sockaddr_in addr = {.sin_family = AF_INET};
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    socklen_t alen = sizeof(addr);
+    if (srv < 0 || bind(srv, (struct sockaddr *)&addr, sizeof(addr)) < 0 || listen(srv, 1) < 0 ||
+        getsockname(srv, (struct sockaddr *)&addr, &alen) < 0) {
+        perror("listen");
+        return 1;
+    }
+
+    int fd = socket(AF_INET, SOCK_STREAM | SOCK_NONBLOCK, 0);
+    if (fd < 0) {
+        perror("socket");
+        return 1;
+    }
+
+    int err = connectNB(fd, &addr);
+    if (err != 0) {
+        printf("fail: connect to listener got error %d\n", err);
+        return 1;
+    }
+
+    int conn = accept(srv, NULL, NULL);
+    if (conn < 0) {
+        perror("accept");
+        return 1;
+    }
+
+    char buf[8];
+    if (write(fd, "ping", 4) != 4 || read(conn, buf, sizeof(buf)) != 4 || memcmp(buf, "ping", 4) != 0) {
+        printf("fail: data didn't flow after non-blocking connect\n");
+        return 1;
+    }
+    close(conn);
+    close(fd);
+
+    // nothing listens on the port any more, so the connection is refused
+    close(srv);
+    fd = socket(AF_INET, SOCK_STREAM | SOCK_NONBLOCK, 0);
+    err = connectNB(fd, &addr);
+    if (err != ECONNREFUSED) {
+        printf("fail: expected ECONNREFUSED, got %d\n", err);
+        return 1;
+    }
+
+    // SO_ERROR is cleared once read
+    if (soError(fd) != 0) {
+        printf("fail: SO_ERROR wasn't cleared\n");
+        return 1;
+    }
+    close(fd);
+
+    printf("pass\n");
+    return 0;
+}
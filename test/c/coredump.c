@@ -0,0 +1,345 @@
+#define _GNU_SOURCE
+#include <elf.h>
+#include <fcntl.h>
+#include <pthread.h>
+#include <signal.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/procfs.h>
+#include <sys/resource.h>
+#include <sys/stat.h>
+#include <sys/user.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// A process killed by SIGSEGV with RLIMIT_CORE set leaves an ELF core file
+// in its working directory, and its parent sees WCOREDUMP. The core has an
+// NT_PRSTATUS and NT_FPREGSET note for each thread, the crashing thread's
+// first, holding the general purpose and xmm registers each thread had when
+// it died, an NT_X86_XSTATE note per thread if the CPU has XSAVE, and the
+// process's written memory. readelf, if installed, must be able to list the
+// notes. With RLIMIT_CORE at 0 no core is written.
+
+#define R15 0x1122334455667788ull
+#define MARKER "written by the crashing process"
+
+static const uint64_t xmm0[2] = {0x0123456789abcdefull, 0xfedcba9876543210ull};
+static const uint64_t xmm1[2] = {0x5555aaaa5555aaaaull, 0x0f0f0f0ff0f0f0f0ull};
+static volatile int ready;
+static char marker[64];
+
+// spin loads xmm1 and spins until the process is killed.
+static void *spin(void *arg)
+{
+    asm volatile("movdqu %0, %%xmm1\n\t"
+                 "movl $1, %1\n"
+                 "1: pause\n\t"
+                 "jmp 1b"
+                 :
+                 : "m"(xmm1), "m"(ready)
+                 : "xmm1", "memory");
+    return NULL;
+}
+
+static void crash(rlim_t limit)
+{
+    struct rlimit rl = {.rlim_cur = limit, .rlim_max = RLIM_INFINITY};
+    if (setrlimit(RLIMIT_CORE, &rl) < 0) {
+        getrlimit(RLIMIT_CORE, &rl);
+        rl.rlim_cur = limit < rl.rlim_max ? limit : rl.rlim_max;
+        setrlimit(RLIMIT_CORE, &rl);
+    }
+
+    strcpy(marker, MARKER);
+    pthread_t th;
+    pthread_create(&th, NULL, spin, NULL);
+    while (!ready) {
+    }
+
+    asm volatile("movdqu %0, %%xmm0\n\t"
+                 "movq %1, %%r15\n\t"
+                 "xorl %%eax, %%eax\n\t"
+                 "movq (%%rax), %%rax"
+                 :
+                 : "m"(xmm0), "i"(R15)
+                 : "rax", "r15", "xmm0", "memory");
+}
+
+// crashChild forks a child that crashes with RLIMIT_CORE at limit and
+// returns its pid and wait status.
+static pid_t crashChild(rlim_t limit, int *status)
+{
+    pid_t pid = fork();
+    if (pid == 0) {
+        crash(limit);
+        _exit(0);
+    }
+    if (waitpid(pid, status, 0) != pid) {
+        perror("waitpid");
+        return -1;
+    }
+    return pid;
+}
+
+// coreName returns the name the core of pid is written to, or NULL if
+// cores don't go to the working directory.
+static const char *coreName(pid_t pid)
+{
+    static char name[64];
+    char buf[256] = "core\n";
+    FILE *f = fopen("/proc/sys/kernel/core_pattern", "r");
+    if (f != NULL) {
+        if (fgets(buf, sizeof(buf), f) == NULL) {
+            buf[0] = 0;
+        }
+        fclose(f);
+    }
+    if (strcmp(buf, "core\n") != 0) {
+        printf("core_pattern is %s", buf);
+        return NULL;
+    }
+
+    int usesPid = 0;
+    f = fopen("/proc/sys/kernel/core_uses_pid", "r");
+    if (f != NULL) {
+        if (fscanf(f, "%d", &usesPid) != 1) {
+            usesPid = 0;
+        }
+        fclose(f);
+    }
+    if (usesPid) {
+        snprintf(name, sizeof(name), "core.%d", pid);
+    } else {
+        snprintf(name, sizeof(name), "core");
+    }
+    return name;
+}
+
+static char *readFile(const char *name, size_t *size)
+{
+    int fd = open(name, O_RDONLY);
+    if (fd < 0) {
+        return NULL;
+    }
+    struct stat st;
+    fstat(fd, &st);
+    char *buf = malloc(st.st_size);
+    size_t n = 0;
+    while (n < (size_t)st.st_size) {
+        ssize_t r = read(fd, buf + n, st.st_size - n);
+        if (r <= 0) {
+            break;
+        }
+        n += r;
+    }
+    close(fd);
+    *size = n;
+    return buf;
+}
+
+struct thread {
+    pid_t pid;
+    int cursig;
+    uint64_t r15;
+    const uint8_t *fpregs;
+    const uint8_t *xstate;
+};
+
+// xmmMatches reports whether xmm register i in the FXSAVE image fx is want.
+static int xmmMatches(const uint8_t *fx, int i, const uint64_t *want)
+{
+    return fx != NULL && memcmp(fx + 160 + 16 * i, want, 16) == 0;
+}
+
+static int checkCore(const char *core, size_t size, pid_t pid)
+{
+    const Elf64_Ehdr *eh = (const Elf64_Ehdr *)core;
+    if (size < sizeof(*eh) || memcmp(eh->e_ident, ELFMAG, SELFMAG) != 0 || eh->e_type != ET_CORE ||
+        eh->e_machine != EM_X86_64) {
+        printf("fail: the core isn't an x86_64 ELF core file\n");
+        return 1;
+    }
+
+    struct thread threads[8];
+    int nthreads = 0, psinfo = 0, markerFound = 0;
+    const Elf64_Phdr *ph = (const Elf64_Phdr *)(core + eh->e_phoff);
+    for (int i = 0; i < eh->e_phnum; i++) {
+        if (ph[i].p_offset + ph[i].p_filesz > size) {
+            printf("fail: segment %d is past the end of the core\n", i);
+            return 1;
+        }
+
+        if (ph[i].p_type == PT_LOAD) {
+            uint64_t addr = (uint64_t)marker;
+            if (ph[i].p_vaddr <= addr && addr + sizeof(marker) <= ph[i].p_vaddr + ph[i].p_filesz &&
+                strcmp(core + ph[i].p_offset + (addr - ph[i].p_vaddr), MARKER) == 0) {
+                markerFound = 1;
+            }
+            continue;
+        }
+        if (ph[i].p_type != PT_NOTE) {
+            continue;
+        }
+
+        const char *p = core + ph[i].p_offset, *end = p + ph[i].p_filesz;
+        while (p + sizeof(Elf64_Nhdr) <= end) {
+            const Elf64_Nhdr *nh = (const Elf64_Nhdr *)p;
+            const char *name = p + sizeof(*nh);
+            const uint8_t *desc = (const uint8_t *)name + ((nh->n_namesz + 3) & ~3);
+            p = (const char *)desc + ((nh->n_descsz + 3) & ~3);
+
+            struct thread *t = nthreads > 0 ? &threads[nthreads - 1] : NULL;
+            if (nh->n_type == NT_PRPSINFO && strcmp(name, "CORE") == 0) {
+                const prpsinfo_t *ps = (const prpsinfo_t *)desc;
+                psinfo = ps->pr_pid == pid && strcmp(ps->pr_fname, "coredump") == 0;
+            } else if (nh->n_type == NT_PRSTATUS && strcmp(name, "CORE") == 0 && nthreads < 8) {
+                const prstatus_t *st = (const prstatus_t *)desc;
+                t = &threads[nthreads++];
+                memset(t, 0, sizeof(*t));
+                t->pid = st->pr_pid;
+                t->cursig = st->pr_cursig;
+                t->r15 = st->pr_reg[offsetof(struct user_regs_struct, r15) / 8];
+            } else if (nh->n_type == NT_FPREGSET && strcmp(name, "CORE") == 0 && t != NULL) {
+                if (nh->n_descsz != sizeof(struct user_fpregs_struct)) {
+                    printf("fail: NT_FPREGSET is %u bytes\n", nh->n_descsz);
+                    return 1;
+                }
+                t->fpregs = desc;
+            } else if (nh->n_type == NT_X86_XSTATE && strcmp(name, "LINUX") == 0 && t != NULL) {
+                // bytes 464..472 of the image hold XCR0, which has x87 and SSE
+                uint64_t xcr0;
+                memcpy(&xcr0, desc + 464, sizeof(xcr0));
+                if (nh->n_descsz < 576 || (xcr0 & 3) != 3) {
+                    printf("fail: NT_X86_XSTATE is %u bytes with XCR0 %llx\n", nh->n_descsz,
+                           (unsigned long long)xcr0);
+                    return 1;
+                }
+                t->xstate = desc;
+            }
+        }
+    }
+
+    if (!psinfo) {
+        printf("fail: no NT_PRPSINFO for the process\n");
+        return 1;
+    }
+    if (nthreads != 2) {
+        printf("fail: the core has %d threads, want 2\n", nthreads);
+        return 1;
+    }
+
+    // the crashing thread comes first
+    struct thread *t = &threads[0];
+    if (t->pid != pid || t->cursig != SIGSEGV || t->r15 != R15) {
+        printf("fail: first thread is %d with signal %d and r15 %llx\n", t->pid, t->cursig,
+               (unsigned long long)t->r15);
+        return 1;
+    }
+    if (!xmmMatches(t->fpregs, 0, xmm0) || (t->xstate != NULL && !xmmMatches(t->xstate, 0, xmm0))) {
+        printf("fail: the crashing thread's xmm0 is wrong\n");
+        return 1;
+    }
+
+    t = &threads[1];
+    if (t->pid == pid || !xmmMatches(t->fpregs, 1, xmm1) ||
+        (t->xstate != NULL && !xmmMatches(t->xstate, 1, xmm1))) {
+        printf("fail: the other thread's xmm1 is wrong\n");
+        return 1;
+    }
+    if ((threads[0].xstate == NULL) != (threads[1].xstate == NULL)) {
+        printf("fail: only one thread has NT_X86_XSTATE\n");
+        return 1;
+    }
+
+    if (!markerFound) {
+        printf("fail: the written memory isn't in the core\n");
+        return 1;
+    }
+    return 0;
+}
+
+// checkReadelf checks that readelf, if installed, lists the notes.
+static int checkReadelf(const char *core)
+{
+    if (access("/usr/bin/readelf", X_OK) != 0) {
+        printf("readelf isn't installed\n");
+        return 0;
+    }
+
+    char cmd[128];
+    snprintf(cmd, sizeof(cmd), "/usr/bin/readelf -n %s", core);
+    FILE *f = popen(cmd, "r");
+    if (f == NULL) {
+        perror("popen");
+        return 1;
+    }
+    int prstatus = 0, fpregset = 0, prpsinfo = 0;
+    char line[256];
+    while (fgets(line, sizeof(line), f) != NULL) {
+        prstatus += strstr(line, "NT_PRSTATUS") != NULL;
+        fpregset += strstr(line, "NT_FPREGSET") != NULL;
+        prpsinfo += strstr(line, "NT_PRPSINFO") != NULL;
+    }
+    if (pclose(f) != 0 || prstatus != 2 || fpregset != 2 || prpsinfo != 1) {
+        printf("fail: readelf -n lists %d NT_PRSTATUS, %d NT_FPREGSET and %d NT_PRPSINFO\n", prstatus,
+               fpregset, prpsinfo);
+        return 1;
+    }
+    return 0;
+}
+
+int main()
+{
+    char dir[] = "/tmp/coredumpXXXXXX";
+    if (mkdtemp(dir) == NULL || chdir(dir) < 0) {
+        perror("mkdtemp");
+        return 1;
+    }
+
+    int status;
+    pid_t pid = crashChild(RLIM_INFINITY, &status);
+    if (pid < 0) {
+        return 1;
+    }
+    const char *core = coreName(pid);
+    if (core == NULL) {
+        printf("skip: cores aren't written to the working directory\n");
+        printf("pass\n");
+        return 0;
+    }
+    if (!WIFSIGNALED(status) || WTERMSIG(status) != SIGSEGV || !WCOREDUMP(status)) {
+        printf("fail: got wait status %x, want SIGSEGV with a core dump\n", status);
+        return 1;
+    }
+
+    size_t size;
+    char *buf = readFile(core, &size);
+    if (buf == NULL) {
+        printf("fail: no %s file\n", core);
+        return 1;
+    }
+    if (checkCore(buf, size, pid) != 0 || checkReadelf(core) != 0) {
+        return 1;
+    }
+    free(buf);
+    unlink(core);
+
+    // no core without RLIMIT_CORE
+    pid = crashChild(0, &status);
+    if (pid < 0) {
+        return 1;
+    }
+    core = coreName(pid);
+    if (!WIFSIGNALED(status) || WTERMSIG(status) != SIGSEGV || WCOREDUMP(status) || access(core, F_OK) == 0) {
+        printf("fail: dumped core with RLIMIT_CORE at 0, wait status %x\n", status);
+        return 1;
+    }
+
+    chdir("/");
+    rmdir(dir);
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,100 @@
+#define _GNU_SOURCE
+#include <pthread.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+
+static volatile sig_atomic_t fired;
+
+static void handler(int sig)
+{
+    fired = 1;
+}
+
+static long long ns(clockid_t id)
+{
+    struct timespec ts;
+    if (clock_gettime(id, &ts) < 0) {
+        perror("clock_gettime");
+        return -1;
+    }
+    return ts.tv_sec * 1000000000LL + ts.tv_nsec;
+}
+
+// spin burns CPU until ms milliseconds of wall time have passed or stop is set.
+static void spin(int ms, volatile sig_atomic_t *stop)
+{
+    long long end = ns(CLOCK_MONOTONIC) + ms * 1000000LL;
+    while (ns(CLOCK_MONOTONIC) < end && (stop == NULL || !*stop)) {
+    }
+}
+
+static long long threadBusy;
+static long long threadCpu;
+
+static void *worker(void *arg)
+{
+    long long cpu = ns(CLOCK_THREAD_CPUTIME_ID);
+    long long wall = ns(CLOCK_MONOTONIC);
+    spin(300, NULL);
+    threadCpu = ns(CLOCK_THREAD_CPUTIME_ID) - cpu;
+    threadBusy = ns(CLOCK_MONOTONIC) - wall;
+    return NULL;
+}
+
+int main()
+{
+    struct timespec res;
+    if (clock_getres(CLOCK_PROCESS_CPUTIME_ID, &res) < 0 ||
+        clock_getres(CLOCK_THREAD_CPUTIME_ID, &res) < 0) {
+        perror("clock_getres");
+        return 1;
+    }
+
+    // a busy thread's CPU clock advances roughly with the wall time it spent
+    pthread_t th;
+    if (pthread_create(&th, NULL, worker, NULL) != 0) {
+        printf("fail: pthread_create\n");
+        return 1;
+    }
+    pthread_join(th, NULL);
+    if (threadCpu < threadBusy / 4 || threadCpu > threadBusy + 50000000LL) {
+        printf("fail: thread cpu time %lld for %lld ns busy\n", threadCpu, threadBusy);
+        return 1;
+    }
+
+    // the process clock includes the exited thread's time
+    if (ns(CLOCK_PROCESS_CPUTIME_ID) < threadCpu) {
+        printf("fail: process cpu time behind the thread's\n");
+        return 1;
+    }
+
+    // a timer on the process CPU clock fires once enough CPU time is used
+    struct sigaction sa = {.sa_handler = handler};
+    sigaction(SIGUSR1, &sa, NULL);
+    struct sigevent sev;
+    memset(&sev, 0, sizeof(sev));
+    sev.sigev_notify = SIGEV_SIGNAL;
+    sev.sigev_signo = SIGUSR1;
+    timer_t timer;
+    if (timer_create(CLOCK_PROCESS_CPUTIME_ID, &sev, &timer) < 0) {
+        perror("timer_create");
+        return 1;
+    }
+    struct itimerspec its = {.it_value = {0, 100000000}};
+    if (timer_settime(timer, 0, &its, NULL) < 0) {
+        perror("timer_settime");
+        return 1;
+    }
+    spin(5000, &fired);
+    if (!fired) {
+        printf("fail: process cpu timer didn't fire\n");
+        return 1;
+    }
+    timer_delete(timer);
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,112 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <sys/ioctl.h>
+#include <sys/wait.h>
+#include <termios.h>
+#include <unistd.h>
+
+// Controlling terminal association: tcgetpgrp/tcsetpgrp only work on the
+// controlling terminal, a new session has none and can't take one held by
+// another session, and TIOCNOTTY in a non-leader only detaches that process.
+// Run it on a terminal, e.g. `script -qc ./ctty /dev/null`.
+
+static int newSession()
+{
+    if (setsid() < 0) {
+        perror("setsid");
+        return 1;
+    }
+    if (tcgetpgrp(0) != -1 || errno != ENOTTY) {
+        printf("fail: tcgetpgrp after setsid\n");
+        return 1;
+    }
+    if (ioctl(0, TIOCNOTTY) != -1 || errno != ENOTTY) {
+        printf("fail: TIOCNOTTY without a controlling terminal\n");
+        return 1;
+    }
+    if (ioctl(0, TIOCSCTTY, 0) != -1 || errno != EPERM) {
+        printf("fail: TIOCSCTTY of another session's terminal\n");
+        return 1;
+    }
+    return 0;
+}
+
+static int notLeader()
+{
+    if (ioctl(0, TIOCSCTTY, 0) != -1 || errno != EPERM) {
+        printf("fail: TIOCSCTTY from a non session leader\n");
+        return 1;
+    }
+    if (ioctl(0, TIOCNOTTY) < 0) {
+        perror("TIOCNOTTY");
+        return 1;
+    }
+    if (tcgetpgrp(0) != -1 || errno != ENOTTY) {
+        printf("fail: tcgetpgrp after TIOCNOTTY\n");
+        return 1;
+    }
+    return 0;
+}
+
+static int run(int (*f)())
+{
+    pid_t pid = fork();
+    if (pid == 0) {
+        _exit(f());
+    }
+    int status;
+    return waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 0;
+}
+
+int main()
+{
+    if (!isatty(0)) {
+        printf("skip: stdin is not a terminal\n");
+        return 0;
+    }
+    if (tcgetpgrp(0) != getpgrp()) {
+        printf("skip: not in the foreground process group\n");
+        return 0;
+    }
+
+    if (run(newSession) || run(notLeader)) {
+        return 1;
+    }
+
+    // the children didn't take the terminal from us
+    if (tcgetpgrp(0) != getpgrp()) {
+        printf("fail: the foreground process group changed\n");
+        return 1;
+    }
+
+    // move the foreground to another process group and back
+    pid_t pid = fork();
+    if (pid == 0) {
+        pause();
+        _exit(0);
+    }
+    setpgid(pid, pid);
+    if (tcsetpgrp(0, pid) < 0 || tcgetpgrp(0) != pid) {
+        printf("fail: tcsetpgrp to the child\n");
+        return 1;
+    }
+
+    // we're in the background now, so restoring needs SIGTTOU ignored
+    signal(SIGTTOU, SIG_IGN);
+    if (tcsetpgrp(0, getpgrp()) < 0 || tcgetpgrp(0) != getpgrp()) {
+        printf("fail: tcsetpgrp back\n");
+        return 1;
+    }
+    kill(pid, SIGKILL);
+    waitpid(pid, NULL, 0);
+
+    if (tcsetpgrp(0, 1 << 22) != -1 || errno != ESRCH) {
+        printf("fail: tcsetpgrp to a missing process group\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
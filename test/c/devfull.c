@@ -0,0 +1,63 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/sysmacros.h>
+#include <unistd.h>
+
+int main()
+{
+    int fd = open("/dev/full", O_RDWR);
+    if (fd < 0) {
+        perror("open");
+        return 1;
+    }
+
+    struct stat st;
+    if (fstat(fd, &st) < 0 || !S_ISCHR(st.st_mode) || major(st.st_rdev) != 1 ||
+        minor(st.st_rdev) != 7) {
+        printf("fail: /dev/full isn't the 1:7 character device\n");
+        return 1;
+    }
+
+    // writes always fail with ENOSPC
+    if (write(fd, "x", 1) != -1 || errno != ENOSPC) {
+        printf("fail: expected ENOSPC from write\n");
+        return 1;
+    }
+    if (pwrite(fd, "x", 1, 100) != -1 || errno != ENOSPC) {
+        printf("fail: expected ENOSPC from pwrite\n");
+        return 1;
+    }
+    int afd = open("/dev/full", O_WRONLY | O_APPEND);
+    if (afd < 0 || write(afd, "x", 1) != -1 || errno != ENOSPC) {
+        printf("fail: expected ENOSPC from an O_APPEND write\n");
+        return 1;
+    }
+    close(afd);
+
+    // reads return as many zero bytes as requested
+    char buf[8192];
+    memset(buf, 0xff, sizeof(buf));
+    if (read(fd, buf, sizeof(buf)) != sizeof(buf)) {
+        printf("fail: short read\n");
+        return 1;
+    }
+    for (size_t i = 0; i < sizeof(buf); i++) {
+        if (buf[i] != 0) {
+            printf("fail: nonzero byte at %zu\n", i);
+            return 1;
+        }
+    }
+
+    if (lseek(fd, 4096, SEEK_SET) < 0 || lseek(fd, 0, SEEK_CUR) < 0) {
+        perror("lseek");
+        return 1;
+    }
+
+    close(fd);
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,58 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+
+int main()
+{
+    char buf[256];
+
+    // /dev/urandom never waits for entropy
+    int fd = open("/dev/urandom", O_RDONLY | O_NONBLOCK);
+    if (fd < 0) {
+        perror("open /dev/urandom");
+        return 1;
+    }
+    struct pollfd pfd = {.fd = fd, .events = POLLIN};
+    if (poll(&pfd, 1, 0) != 1 || !(pfd.revents & POLLIN)) {
+        printf("fail: /dev/urandom isn't readable\n");
+        return 1;
+    }
+    if (read(fd, buf, sizeof(buf)) != sizeof(buf)) {
+        perror("read /dev/urandom");
+        return 1;
+    }
+    close(fd);
+
+    // once the pool is seeded /dev/random doesn't wait for entropy either
+    fd = open("/dev/random", O_RDONLY | O_NONBLOCK);
+    if (fd < 0) {
+        perror("open /dev/random");
+        return 1;
+    }
+    pfd.fd = fd;
+    pfd.revents = 0;
+    if (poll(&pfd, 1, 0) != 1 || !(pfd.revents & POLLIN)) {
+        printf("fail: /dev/random isn't readable\n");
+        return 1;
+    }
+    if (read(fd, buf, sizeof(buf)) != sizeof(buf)) {
+        perror("read /dev/random");
+        return 1;
+    }
+    close(fd);
+
+    // a blocking read waits until it gets something
+    fd = open("/dev/random", O_RDONLY);
+    if (fd < 0 || read(fd, buf, 16) <= 0) {
+        perror("blocking read /dev/random");
+        return 1;
+    }
+    close(fd);
+
+    printf("pass\n");
+    return 0;
+}
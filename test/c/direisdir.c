@@ -0,0 +1,42 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <sys/uio.h>
+#include <unistd.h>
+
+int main()
+{
+    int fd = open("/tmp", O_RDONLY | O_DIRECTORY);
+    if (fd < 0) {
+        perror("open");
+        return 1;
+    }
+
+    char buf[64];
+    struct iovec iov = {buf, sizeof(buf)};
+
+    if (read(fd, buf, sizeof(buf)) != -1 || errno != EISDIR) {
+        printf("fail: read on a directory: %d\n", errno);
+        return 1;
+    }
+
+    if (pread(fd, buf, sizeof(buf), 0) != -1 || errno != EISDIR) {
+        printf("fail: pread on a directory: %d\n", errno);
+        return 1;
+    }
+
+    if (readv(fd, &iov, 1) != -1 || errno != EISDIR) {
+        printf("fail: readv on a directory: %d\n", errno);
+        return 1;
+    }
+
+    if (preadv(fd, &iov, 1, 0) != -1 || errno != EISDIR) {
+        printf("fail: preadv on a directory: %d\n", errno);
+        return 1;
+    }
+
+    close(fd);
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,61 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+
+int main()
+{
+    int fd = open("/tmp/dup3", O_CREAT | O_RDWR | O_TRUNC, 0644);
+    int other = open("/dev/null", O_RDONLY);
+    if (fd < 0 || other < 0 || write(fd, "0123456789", 10) != 10) {
+        perror("open");
+        return 1;
+    }
+
+    // dup3 onto an open fd replaces it and shares the offset
+    if (dup3(fd, other, O_CLOEXEC) != other) {
+        perror("dup3");
+        return 1;
+    }
+    if (lseek(fd, 2, SEEK_SET) != 2 || lseek(other, 0, SEEK_CUR) != 2) {
+        printf("fail: dup3'd fd doesn't share the offset\n");
+        return 1;
+    }
+    char c;
+    if (read(other, &c, 1) != 1 || c != '2') {
+        printf("fail: dup3'd fd still refers to the old file\n");
+        return 1;
+    }
+
+    // only the new descriptor is close-on-exec
+    if (!(fcntl(other, F_GETFD) & FD_CLOEXEC) || (fcntl(fd, F_GETFD) & FD_CLOEXEC)) {
+        printf("fail: O_CLOEXEC not applied to the new fd only\n");
+        return 1;
+    }
+    if (dup3(fd, other, 0) != other || (fcntl(other, F_GETFD) & FD_CLOEXEC)) {
+        printf("fail: dup3 without O_CLOEXEC left FD_CLOEXEC set\n");
+        return 1;
+    }
+
+    // unlike dup2, equal fds and unknown flags are rejected
+    if (dup3(fd, fd, 0) != -1 || errno != EINVAL) {
+        printf("fail: dup3 with equal fds didn't return EINVAL\n");
+        return 1;
+    }
+    if (dup2(fd, fd) != fd) {
+        printf("fail: dup2 with equal fds\n");
+        return 1;
+    }
+    if (dup3(fd, other, O_NONBLOCK) != -1 || errno != EINVAL) {
+        printf("fail: dup3 with bad flags didn't return EINVAL\n");
+        return 1;
+    }
+
+    close(other);
+    close(fd);
+    unlink("/tmp/dup3");
+    printf("pass\n");
+    return 0;
+}
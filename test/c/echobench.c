@@ -0,0 +1,172 @@
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <errno.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/epoll.h>
+#include <sys/resource.h>
+#include <sys/socket.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+// An epoll echo server with 10k connections, driven by a client that sends a
+// message on every connection per round and waits for all the echoes. Every
+// fd the two sides wait on goes through the guest notifier, so this is the
+// workload that multishot poll is for. Run it with "MultishotPoll" on and off
+// in config.json, and with "LoopbackFastPath" and "TcpBuffIO" off so that the
+// sockets are plain host sockets, and count the notifier's host requests on
+// the host with
+//
+//   perf stat -e io_uring:io_uring_submit_sqe -e syscalls:sys_enter_epoll_ctl -p <sandbox pid>
+//
+// With multishot poll each fd costs one POLL_ADD SQE and the epoll_ctl calls
+// go away; without it every change of interest costs an epoll_ctl.
+
+#define CONNS 10000
+#define ROUNDS 20
+#define MSG 64
+
+static double now()
+{
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec + ts.tv_nsec / 1e9;
+}
+
+// maxConns raises the fd limit as far as allowed and returns how many
+// connections fit, both ends living in this process tree.
+static int maxConns()
+{
+    struct rlimit rl;
+    getrlimit(RLIMIT_NOFILE, &rl);
+    rl.rlim_cur = rl.rlim_max;
+    setrlimit(RLIMIT_NOFILE, &rl);
+    getrlimit(RLIMIT_NOFILE, &rl);
+
+    long n = ((long)rl.rlim_cur - 64) / 2;
+    return n < CONNS ? (int)n : CONNS;
+}
+
+static int server(int srv, int conns)
+{
+    int ep = epoll_create1(0);
+    struct epoll_event ev = {.events = EPOLLIN, .data.fd = srv};
+    epoll_ctl(ep, EPOLL_CTL_ADD, srv, &ev);
+
+    static struct epoll_event evs[256];
+    int open = 0, accepted = 0;
+    char buf[MSG];
+    while (accepted < conns || open > 0) {
+        int n = epoll_wait(ep, evs, 256, 10000);
+        if (n <= 0) {
+            return 1;
+        }
+        for (int i = 0; i < n; i++) {
+            int fd = evs[i].data.fd;
+            if (fd == srv) {
+                int c = accept(srv, NULL, NULL);
+                if (c < 0) {
+                    continue;
+                }
+                ev.events = EPOLLIN;
+                ev.data.fd = c;
+                epoll_ctl(ep, EPOLL_CTL_ADD, c, &ev);
+                accepted++;
+                open++;
+                continue;
+            }
+
+            ssize_t r = read(fd, buf, sizeof(buf));
+            if (r <= 0) {
+                close(fd);
+                open--;
+                continue;
+            }
+            if (write(fd, buf, r) != r) {
+                return 1;
+            }
+        }
+    }
+    return 0;
+}
+
+int main()
+{
+    int conns = maxConns();
+
+    int srv = socket(AF_INET, SOCK_STREAM, 0);
+    struct sockaddr_in addr = {.sin_family = AF_INET, .sin_addr.s_addr = htonl(INADDR_LOOPBACK)};
+    socklen_t alen = sizeof(addr);
+    if (srv < 0 || bind(srv, (struct sockaddr *)&addr, sizeof(addr)) < 0 || listen(srv, 1024) < 0 ||
+        getsockname(srv, (struct sockaddr *)&addr, &alen) < 0) {
+        perror("listen");
+        return 1;
+    }
+
+    pid_t pid = fork();
+    if (pid == 0) {
+        _exit(server(srv, conns));
+    }
+    close(srv);
+
+    int *fds = calloc(conns, sizeof(int));
+    int ep = epoll_create1(0);
+    for (int i = 0; i < conns; i++) {
+        fds[i] = socket(AF_INET, SOCK_STREAM, 0);
+        if (fds[i] < 0 || connect(fds[i], (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+            printf("fail: connection %d: %s\n", i, strerror(errno));
+            return 1;
+        }
+        struct epoll_event ev = {.events = EPOLLIN, .data.u32 = i};
+        epoll_ctl(ep, EPOLL_CTL_ADD, fds[i], &ev);
+    }
+
+    static struct epoll_event evs[256];
+    char msg[MSG], buf[MSG];
+    double start = now();
+    for (int round = 0; round < ROUNDS; round++) {
+        memset(msg, 'a' + round % 26, sizeof(msg));
+        for (int i = 0; i < conns; i++) {
+            if (write(fds[i], msg, sizeof(msg)) != sizeof(msg)) {
+                perror("write");
+                return 1;
+            }
+        }
+
+        // the messages are small enough to come back in one piece
+        int pending = conns;
+        while (pending > 0) {
+            int n = epoll_wait(ep, evs, 256, 10000);
+            if (n <= 0) {
+                printf("fail: %d echoes missing in round %d\n", pending, round);
+                return 1;
+            }
+            for (int i = 0; i < n; i++) {
+                int fd = fds[evs[i].data.u32];
+                if (read(fd, buf, sizeof(buf)) != sizeof(buf) || memcmp(buf, msg, sizeof(msg)) != 0) {
+                    printf("fail: bad echo on connection %u\n", evs[i].data.u32);
+                    return 1;
+                }
+                pending--;
+            }
+        }
+    }
+    double secs = now() - start;
+
+    for (int i = 0; i < conns; i++) {
+        close(fds[i]);
+    }
+    int status;
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fail: server failed\n");
+        return 1;
+    }
+
+    printf("echo: %d connections, %d rounds in %.3fs, %.0f msgs/s\n", conns, ROUNDS, secs,
+           (double)conns * ROUNDS / secs);
+    printf("pass\n");
+    return 0;
+}
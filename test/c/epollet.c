@@ -0,0 +1,116 @@
+#include <errno.h>
+#include <stdio.h>
+#include <sys/epoll.h>
+#include <unistd.h>
+
+static int waitOne(int epfd)
+{
+    struct epoll_event ev;
+    return epoll_wait(epfd, &ev, 1, 0);
+}
+
+static int check(const char *what, int got, int want)
+{
+    if (got != want) {
+        printf("fail: %s: got %d events, want %d\n", what, got, want);
+        return 1;
+    }
+    return 0;
+}
+
+static int edgeTriggered(void)
+{
+    int fds[2];
+    pipe(fds);
+    int epfd = epoll_create1(0);
+    struct epoll_event ev = {.events = EPOLLIN | EPOLLET};
+    epoll_ctl(epfd, EPOLL_CTL_ADD, fds[0], &ev);
+
+    char buf[16];
+    if (check("et empty", waitOne(epfd), 0)) return 1;
+
+    write(fds[1], "a", 1);
+    if (check("et first edge", waitOne(epfd), 1)) return 1;
+    // still readable, but no new edge
+    if (check("et no edge", waitOne(epfd), 0)) return 1;
+
+    write(fds[1], "b", 1);
+    if (check("et second edge", waitOne(epfd), 1)) return 1;
+
+    read(fds[0], buf, sizeof(buf));
+    if (check("et drained", waitOne(epfd), 0)) return 1;
+
+    write(fds[1], "c", 1);
+    if (check("et edge after drain", waitOne(epfd), 1)) return 1;
+
+    close(epfd);
+    close(fds[0]);
+    close(fds[1]);
+    return 0;
+}
+
+static int oneShot(void)
+{
+    int fds[2];
+    pipe(fds);
+    int epfd = epoll_create1(0);
+    struct epoll_event ev = {.events = EPOLLIN | EPOLLONESHOT};
+    epoll_ctl(epfd, EPOLL_CTL_ADD, fds[0], &ev);
+
+    write(fds[1], "a", 1);
+    if (check("oneshot first", waitOne(epfd), 1)) return 1;
+    if (check("oneshot disarmed", waitOne(epfd), 0)) return 1;
+
+    // more data doesn't rearm the entry
+    write(fds[1], "b", 1);
+    if (check("oneshot after write", waitOne(epfd), 0)) return 1;
+
+    if (epoll_ctl(epfd, EPOLL_CTL_MOD, fds[0], &ev) < 0) {
+        perror("epoll_ctl mod");
+        return 1;
+    }
+    if (check("oneshot rearmed", waitOne(epfd), 1)) return 1;
+    if (check("oneshot disarmed again", waitOne(epfd), 0)) return 1;
+
+    if (epoll_ctl(epfd, EPOLL_CTL_DEL, fds[0], NULL) < 0) {
+        perror("epoll_ctl del");
+        return 1;
+    }
+
+    close(epfd);
+    close(fds[0]);
+    close(fds[1]);
+    return 0;
+}
+
+static int levelTriggered(void)
+{
+    int fds[2];
+    pipe(fds);
+    int epfd = epoll_create1(0);
+    struct epoll_event ev = {.events = EPOLLIN};
+    epoll_ctl(epfd, EPOLL_CTL_ADD, fds[0], &ev);
+
+    write(fds[1], "a", 1);
+    if (check("lt first", waitOne(epfd), 1)) return 1;
+    if (check("lt still ready", waitOne(epfd), 1)) return 1;
+
+    char c;
+    read(fds[0], &c, 1);
+    if (check("lt drained", waitOne(epfd), 0)) return 1;
+
+    close(epfd);
+    close(fds[0]);
+    close(fds[1]);
+    return 0;
+}
+
+int main()
+{
+    if (edgeTriggered() || oneShot() || levelTriggered()) {
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
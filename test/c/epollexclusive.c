@@ -0,0 +1,85 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <sys/epoll.h>
+#include <unistd.h>
+
+#define WAITERS 4
+
+static int fds[2];
+static int woken;
+static pthread_mutex_t mu = PTHREAD_MUTEX_INITIALIZER;
+
+// waiter blocks on its own epoll instance which watches the shared pipe
+// with EPOLLEXCLUSIVE.
+static void *waiter(void *arg)
+{
+    int epfd = epoll_create1(0);
+    struct epoll_event ev = {.events = EPOLLIN | EPOLLEXCLUSIVE};
+    if (epoll_ctl(epfd, EPOLL_CTL_ADD, fds[0], &ev) < 0) {
+        perror("epoll_ctl");
+        return NULL;
+    }
+
+    if (epoll_wait(epfd, &ev, 1, 500) == 1) {
+        pthread_mutex_lock(&mu);
+        woken++;
+        pthread_mutex_unlock(&mu);
+    }
+    close(epfd);
+    return NULL;
+}
+
+int main()
+{
+    if (pipe(fds) < 0) {
+        perror("pipe");
+        return 1;
+    }
+
+    int epfd = epoll_create1(0);
+    struct epoll_event ev = {.events = EPOLLIN | EPOLLEXCLUSIVE};
+    if (epoll_ctl(epfd, EPOLL_CTL_ADD, fds[0], &ev) < 0) {
+        perror("epoll_ctl add");
+        return 1;
+    }
+
+    // exclusive entries can't be modified
+    if (epoll_ctl(epfd, EPOLL_CTL_MOD, fds[0], &ev) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL modifying an exclusive entry\n");
+        return 1;
+    }
+
+    // EPOLLONESHOT isn't allowed with EPOLLEXCLUSIVE
+    ev.events = EPOLLIN | EPOLLEXCLUSIVE | EPOLLONESHOT;
+    if (epoll_ctl(epfd, EPOLL_CTL_ADD, fds[1], &ev) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL for EPOLLEXCLUSIVE|EPOLLONESHOT\n");
+        return 1;
+    }
+    close(epfd);
+
+    pthread_t threads[WAITERS];
+    for (int i = 0; i < WAITERS; i++) {
+        pthread_create(&threads[i], NULL, waiter, NULL);
+    }
+
+    // let every waiter block before making the pipe readable once
+    usleep(100 * 1000);
+    if (write(fds[1], "x", 1) != 1) {
+        perror("write");
+        return 1;
+    }
+
+    for (int i = 0; i < WAITERS; i++) {
+        pthread_join(threads[i], NULL);
+    }
+
+    if (woken != 1) {
+        printf("fail: %d waiters woken\n", woken);
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,89 @@
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <errno.h>
+#include <linux/errqueue.h>
+#include <netinet/in.h>
+#include <poll.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+int main()
+{
+    // find a port nobody listens on
+    int tmp = socket(AF_INET, SOCK_DGRAM, 0);
+    struct sockaddr_in sa = {.sin_family = AF_INET};
+    sa.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    socklen_t salen = sizeof(sa);
+    if (tmp < 0 || bind(tmp, (struct sockaddr *)&sa, sizeof(sa)) < 0 ||
+        getsockname(tmp, (struct sockaddr *)&sa, &salen) < 0) {
+        perror("pick port");
+        return 1;
+    }
+    close(tmp);
+
+    int fd = socket(AF_INET, SOCK_DGRAM, 0);
+    int on = 1;
+    if (fd < 0 || setsockopt(fd, SOL_IP, IP_RECVERR, &on, sizeof(on)) < 0) {
+        perror("IP_RECVERR");
+        return 1;
+    }
+
+    // the error queue starts out empty and reading it never blocks
+    char data[64];
+    char control[512];
+    struct iovec iov = {.iov_base = data, .iov_len = sizeof(data)};
+    struct sockaddr_in offender;
+    struct msghdr msg = {
+        .msg_name = &offender, .msg_namelen = sizeof(offender),
+        .msg_iov = &iov, .msg_iovlen = 1,
+        .msg_control = control, .msg_controllen = sizeof(control),
+    };
+    if (recvmsg(fd, &msg, MSG_ERRQUEUE) != -1 || errno != EAGAIN) {
+        printf("fail: empty error queue didn't return EAGAIN\n");
+        return 1;
+    }
+
+    if (sendto(fd, "ping", 4, 0, (struct sockaddr *)&sa, sizeof(sa)) != 4) {
+        perror("sendto");
+        return 1;
+    }
+
+    struct pollfd pfd = {.fd = fd, .events = POLLIN};
+    if (poll(&pfd, 1, 1000) != 1 || !(pfd.revents & POLLERR)) {
+        printf("fail: POLLERR not reported, revents %x\n", pfd.revents);
+        return 1;
+    }
+
+    msg.msg_namelen = sizeof(offender);
+    msg.msg_controllen = sizeof(control);
+    if (recvmsg(fd, &msg, MSG_ERRQUEUE) < 0) {
+        perror("recvmsg MSG_ERRQUEUE");
+        return 1;
+    }
+    if (!(msg.msg_flags & MSG_ERRQUEUE)) {
+        printf("fail: MSG_ERRQUEUE not set in msg_flags\n");
+        return 1;
+    }
+
+    struct sock_extended_err *ee = NULL;
+    for (struct cmsghdr *c = CMSG_FIRSTHDR(&msg); c; c = CMSG_NXTHDR(&msg, c)) {
+        if (c->cmsg_level == SOL_IP && c->cmsg_type == IP_RECVERR) {
+            ee = (struct sock_extended_err *)CMSG_DATA(c);
+        }
+    }
+    if (!ee || ee->ee_origin != SO_EE_ORIGIN_ICMP || ee->ee_errno != ECONNREFUSED) {
+        printf("fail: no ICMP port unreachable in the error queue\n");
+        return 1;
+    }
+    struct sockaddr_in *from = (struct sockaddr_in *)SO_EE_OFFENDER(ee);
+    if (from->sin_family != AF_INET || from->sin_addr.s_addr != htonl(INADDR_LOOPBACK)) {
+        printf("fail: offender address missing\n");
+        return 1;
+    }
+
+    close(fd);
+    printf("pass\n");
+    return 0;
+}
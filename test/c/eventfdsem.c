@@ -0,0 +1,191 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <inttypes.h>
+#include <poll.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/eventfd.h>
+#include <unistd.h>
+
+#define MAX_VAL 0xfffffffffffffffeULL
+
+static short revents(int fd)
+{
+    struct pollfd pfd = {.fd = fd, .events = POLLIN | POLLOUT};
+    poll(&pfd, 1, 0);
+    return pfd.revents;
+}
+
+static void *reader(void *arg)
+{
+    int fd = *(int *)arg;
+    uint64_t v;
+    usleep(50 * 1000);
+    read(fd, &v, sizeof(v));
+    return NULL;
+}
+
+static void *writer(void *arg)
+{
+    int fd = *(int *)arg;
+    uint64_t v = 2;
+    usleep(50 * 1000);
+    write(fd, &v, sizeof(v));
+    return NULL;
+}
+
+// semaphoreBlocking checks that a blocking semaphore read waits for a
+// writer and then takes only one of the posted counts.
+static int semaphoreBlocking(void)
+{
+    int fd = eventfd(0, EFD_SEMAPHORE);
+    pthread_t t;
+    pthread_create(&t, NULL, writer, &fd);
+
+    uint64_t v = 0;
+    if (read(fd, &v, sizeof(v)) != sizeof(v) || v != 1) {
+        printf("fail: blocking semaphore read returned %" PRIu64 "\n", v);
+        return 1;
+    }
+    pthread_join(t, NULL);
+
+    if (read(fd, &v, sizeof(v)) != sizeof(v) || v != 1) {
+        printf("fail: second semaphore read returned %" PRIu64 "\n", v);
+        return 1;
+    }
+
+    if (revents(fd) != POLLOUT) {
+        printf("fail: drained semaphore revents %x\n", revents(fd));
+        return 1;
+    }
+
+    close(fd);
+    return 0;
+}
+
+// edges checks the argument checks shared by both modes.
+static int edges(void)
+{
+    int fd = eventfd(0, EFD_NONBLOCK);
+    uint64_t v = 0xffffffffffffffffULL;
+    if (write(fd, &v, sizeof(v)) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL writing UINT64_MAX\n");
+        return 1;
+    }
+
+    uint32_t small = 1;
+    if (write(fd, &small, sizeof(small)) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL for a short write\n");
+        return 1;
+    }
+
+    v = 1;
+    write(fd, &v, sizeof(v));
+    if (read(fd, &small, sizeof(small)) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL for a short read\n");
+        return 1;
+    }
+
+    // a zero write succeeds but doesn't make the counter readable twice
+    v = 0;
+    if (write(fd, &v, sizeof(v)) != sizeof(v)) {
+        printf("fail: zero write failed\n");
+        return 1;
+    }
+    if (read(fd, &v, sizeof(v)) != sizeof(v) || v != 1) {
+        printf("fail: counter read returned %" PRIu64 "\n", v);
+        return 1;
+    }
+    if (read(fd, &v, sizeof(v)) != -1 || errno != EAGAIN) {
+        printf("fail: expected EAGAIN from an empty counter\n");
+        return 1;
+    }
+
+    close(fd);
+    return 0;
+}
+
+int main()
+{
+    if (semaphoreBlocking() || edges()) {
+        return 1;
+    }
+
+    // semaphore mode: every read takes one
+    int fd = eventfd(3, EFD_SEMAPHORE | EFD_NONBLOCK);
+    if (fd < 0) {
+        perror("eventfd");
+        return 1;
+    }
+
+    uint64_t v;
+    for (int i = 0; i < 3; i++) {
+        if (read(fd, &v, sizeof(v)) != sizeof(v) || v != 1) {
+            printf("fail: semaphore read %d returned %" PRIu64 "\n", i, v);
+            return 1;
+        }
+    }
+
+    if (read(fd, &v, sizeof(v)) != -1 || errno != EAGAIN) {
+        printf("fail: expected EAGAIN from an empty semaphore\n");
+        return 1;
+    }
+
+    if (revents(fd) != POLLOUT) {
+        printf("fail: empty eventfd revents %x\n", revents(fd));
+        return 1;
+    }
+
+    // fill it up: no longer writable, overflowing writes fail
+    v = MAX_VAL;
+    if (write(fd, &v, sizeof(v)) != sizeof(v)) {
+        perror("write");
+        return 1;
+    }
+
+    if (revents(fd) != POLLIN) {
+        printf("fail: full eventfd revents %x\n", revents(fd));
+        return 1;
+    }
+
+    v = 1;
+    if (write(fd, &v, sizeof(v)) != -1 || errno != EAGAIN) {
+        printf("fail: expected EAGAIN on overflow\n");
+        return 1;
+    }
+    close(fd);
+
+    // counter mode: reads only touch 8 bytes and reset the counter
+    fd = eventfd(0, 0);
+    v = 5;
+    write(fd, &v, sizeof(v));
+    write(fd, &v, sizeof(v));
+    uint64_t buf[2] = {0, 0x1234};
+    if (read(fd, buf, sizeof(buf)) != 8 || buf[0] != 10 || buf[1] != 0x1234) {
+        printf("fail: read returned %" PRIu64 "/%" PRIx64 "\n", buf[0], buf[1]);
+        return 1;
+    }
+
+    // a blocking write waits until a reader makes room
+    v = MAX_VAL;
+    write(fd, &v, sizeof(v));
+
+    pthread_t t;
+    pthread_create(&t, NULL, reader, &fd);
+    v = 7;
+    if (write(fd, &v, sizeof(v)) != sizeof(v)) {
+        perror("blocking write");
+        return 1;
+    }
+    pthread_join(t, NULL);
+
+    if (read(fd, &v, sizeof(v)) != sizeof(v) || v != 7) {
+        printf("fail: expected 7 after the blocked write, got %" PRIu64 "\n", v);
+        return 1;
+    }
+
+    close(fd);
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,79 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+// posix_fadvise passes the hints on to the host page cache. WILLNEED starts
+// a prefetch and returns at once, DONTNEED drops the cached range, and the
+// data reads back the same after either.
+
+int main()
+{
+    char path[] = "/tmp/fadviseXXXXXX";
+    int fd = mkstemp(path);
+    if (fd < 0) {
+        perror("mkstemp");
+        return 1;
+    }
+    unlink(path);
+
+    static char buf[1 << 20];
+    memset(buf, 'f', sizeof(buf));
+    if (write(fd, buf, sizeof(buf)) != sizeof(buf) || fsync(fd) < 0) {
+        perror("write");
+        return 1;
+    }
+
+    int advices[] = {
+        POSIX_FADV_DONTNEED,
+        POSIX_FADV_WILLNEED,
+        POSIX_FADV_SEQUENTIAL,
+        POSIX_FADV_RANDOM,
+        POSIX_FADV_NOREUSE,
+        POSIX_FADV_NORMAL,
+    };
+    for (int i = 0; i < sizeof(advices) / sizeof(advices[0]); i++) {
+        int ret = posix_fadvise(fd, 0, 0, advices[i]);
+        if (ret != 0) {
+            printf("fail: advice %d: %s\n", advices[i], strerror(ret));
+            return 1;
+        }
+
+        memset(buf, 0, sizeof(buf));
+        if (pread(fd, buf, sizeof(buf), 0) != sizeof(buf) || buf[0] != 'f' || buf[sizeof(buf) - 1] != 'f') {
+            printf("fail: read after advice %d\n", advices[i]);
+            return 1;
+        }
+    }
+
+    // a range past the end is fine too
+    if (posix_fadvise(fd, 1 << 30, 4096, POSIX_FADV_WILLNEED) != 0) {
+        printf("fail: WILLNEED past the end\n");
+        return 1;
+    }
+
+    if (posix_fadvise(fd, 0, -1, POSIX_FADV_WILLNEED) != EINVAL) {
+        printf("fail: a negative len didn't fail with EINVAL\n");
+        return 1;
+    }
+    if (posix_fadvise(fd, 0, 0, 100) != EINVAL) {
+        printf("fail: an unknown advice didn't fail with EINVAL\n");
+        return 1;
+    }
+
+    int fds[2];
+    if (pipe(fds) < 0) {
+        perror("pipe");
+        return 1;
+    }
+    if (posix_fadvise(fds[0], 0, 0, POSIX_FADV_WILLNEED) != ESPIPE) {
+        printf("fail: fadvise on a pipe didn't fail with ESPIPE\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
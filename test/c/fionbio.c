@@ -0,0 +1,80 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <sys/ioctl.h>
+#include <unistd.h>
+
+static volatile sig_atomic_t sigios;
+
+static void handler(int sig)
+{
+    sigios++;
+}
+
+int main()
+{
+    int fds[2];
+    if (pipe(fds) < 0) {
+        perror("pipe");
+        return 1;
+    }
+
+    // FIONBIO makes reads on the empty pipe fail instead of blocking
+    int on = 1, off = 0;
+    if (ioctl(fds[0], FIONBIO, &on) < 0) {
+        perror("FIONBIO");
+        return 1;
+    }
+    if (!(fcntl(fds[0], F_GETFL) & O_NONBLOCK)) {
+        printf("fail: O_NONBLOCK not set by FIONBIO\n");
+        return 1;
+    }
+    char c;
+    if (read(fds[0], &c, 1) != -1 || errno != EAGAIN) {
+        printf("fail: read on empty nonblocking pipe didn't return EAGAIN\n");
+        return 1;
+    }
+    if (ioctl(fds[0], FIONBIO, &off) < 0 || (fcntl(fds[0], F_GETFL) & O_NONBLOCK)) {
+        printf("fail: O_NONBLOCK not cleared by FIONBIO\n");
+        return 1;
+    }
+
+    // FIOASYNC sends SIGIO to the owner once data arrives
+    struct sigaction sa = {.sa_handler = handler};
+    sigaction(SIGIO, &sa, NULL);
+    if (fcntl(fds[0], F_SETOWN, getpid()) < 0 || ioctl(fds[0], FIOASYNC, &on) < 0) {
+        perror("FIOASYNC");
+        return 1;
+    }
+    if (!(fcntl(fds[0], F_GETFL) & O_ASYNC)) {
+        printf("fail: O_ASYNC not set by FIOASYNC\n");
+        return 1;
+    }
+    if (write(fds[1], "x", 1) != 1 || read(fds[0], &c, 1) != 1) {
+        perror("write");
+        return 1;
+    }
+    if (sigios == 0) {
+        printf("fail: no SIGIO with FIOASYNC set\n");
+        return 1;
+    }
+
+    int seen = sigios;
+    if (ioctl(fds[0], FIOASYNC, &off) < 0 || (fcntl(fds[0], F_GETFL) & O_ASYNC)) {
+        printf("fail: O_ASYNC not cleared by FIOASYNC\n");
+        return 1;
+    }
+    if (write(fds[1], "x", 1) != 1 || read(fds[0], &c, 1) != 1) {
+        perror("write");
+        return 1;
+    }
+    if (sigios != seen) {
+        printf("fail: SIGIO after FIOASYNC was cleared\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,164 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/file.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// flock: exclusive locks conflict across open file descriptions, shared
+// locks coexist, locks convert in place and are released on close. A blocked
+// flock interrupted by a signal fails with EINTR, or is restarted if the
+// handler has SA_RESTART.
+
+static void onSignal(int sig)
+{
+}
+
+// blockedChild blocks in flock(fd, LOCK_EX) twice while the parent holds the
+// lock and signals it: first without SA_RESTART, then with it.
+static int blockedChild(int fd)
+{
+    struct sigaction sa = {.sa_handler = onSignal};
+    sigaction(SIGUSR1, &sa, NULL);
+    if (flock(fd, LOCK_EX) != -1 || errno != EINTR) {
+        return 1;
+    }
+
+    sa.sa_flags = SA_RESTART;
+    sigaction(SIGUSR1, &sa, NULL);
+    if (flock(fd, LOCK_EX) != 0) {
+        return 2;
+    }
+    return 0;
+}
+
+static int tryLock(int fd, int op)
+{
+    if (flock(fd, op | LOCK_NB) == 0) {
+        return 0;
+    }
+    return errno;
+}
+
+int main()
+{
+    char path[] = "/tmp/flockXXXXXX";
+    int a = mkstemp(path);
+    if (a < 0) {
+        perror("mkstemp");
+        return 1;
+    }
+    int b = open(path, O_RDONLY);
+    if (b < 0) {
+        perror("open");
+        return 1;
+    }
+    unlink(path);
+
+    // exclusive lock contention
+    if (flock(a, LOCK_EX) < 0) {
+        perror("flock LOCK_EX");
+        return 1;
+    }
+    if (tryLock(b, LOCK_EX) != EWOULDBLOCK || tryLock(b, LOCK_SH) != EWOULDBLOCK) {
+        printf("fail: a second open file description got a conflicting lock\n");
+        return 1;
+    }
+
+    // a dup shares the lock and can release it
+    int d = dup(a);
+    if (tryLock(d, LOCK_EX) != 0 || flock(d, LOCK_UN) < 0) {
+        printf("fail: the lock isn't shared with a dup\n");
+        return 1;
+    }
+    close(d);
+
+    // shared locks coexist
+    if (tryLock(a, LOCK_SH) != 0 || tryLock(b, LOCK_SH) != 0) {
+        printf("fail: shared locks don't coexist\n");
+        return 1;
+    }
+    if (tryLock(b, LOCK_EX) != EWOULDBLOCK) {
+        printf("fail: upgraded a shared lock held by another reader\n");
+        return 1;
+    }
+
+    // upgrade once the other reader is gone, then downgrade
+    if (flock(a, LOCK_UN) < 0 || tryLock(b, LOCK_EX) != 0) {
+        printf("fail: couldn't upgrade the only shared lock\n");
+        return 1;
+    }
+    if (tryLock(a, LOCK_SH) != EWOULDBLOCK) {
+        printf("fail: the upgraded lock isn't exclusive\n");
+        return 1;
+    }
+    if (tryLock(b, LOCK_SH) != 0 || tryLock(a, LOCK_SH) != 0) {
+        printf("fail: couldn't downgrade the exclusive lock\n");
+        return 1;
+    }
+    flock(a, LOCK_UN);
+    flock(b, LOCK_UN);
+
+    // a blocked locker wakes up when the lock is released
+    if (flock(a, LOCK_EX) < 0) {
+        perror("flock");
+        return 1;
+    }
+    pid_t pid = fork();
+    if (pid == 0) {
+        close(a);
+        _exit(flock(b, LOCK_EX) == 0 ? 0 : 1);
+    }
+    usleep(100000);
+    flock(a, LOCK_UN);
+    int status;
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fail: the blocked flock didn't succeed\n");
+        return 1;
+    }
+    flock(b, LOCK_UN);
+
+    // an interrupted flock fails with EINTR or is restarted
+    if (flock(a, LOCK_EX) < 0) {
+        perror("flock");
+        return 1;
+    }
+    pid = fork();
+    if (pid == 0) {
+        close(a);
+        _exit(blockedChild(b));
+    }
+    usleep(100000);
+    kill(pid, SIGUSR1);
+    usleep(100000);
+    kill(pid, SIGUSR1);
+    usleep(100000);
+    flock(a, LOCK_UN);
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fail: interrupted flock, child status %x\n", status);
+        return 1;
+    }
+    flock(b, LOCK_UN);
+
+    // closing the last descriptor releases the lock
+    if (flock(a, LOCK_EX) < 0) {
+        perror("flock");
+        return 1;
+    }
+    close(a);
+    if (tryLock(b, LOCK_EX) != 0) {
+        printf("fail: the lock wasn't released on close\n");
+        return 1;
+    }
+
+    if (flock(b, LOCK_SH | LOCK_EX) != -1 || errno != EINVAL) {
+        printf("fail: an invalid operation was accepted\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
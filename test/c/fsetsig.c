@@ -0,0 +1,74 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+
+static volatile sig_atomic_t gotSig, gotFd, gotCode;
+
+static void handler(int sig, siginfo_t *info, void *ctx)
+{
+    gotSig = sig;
+    gotFd = info->si_fd;
+    gotCode = info->si_code;
+}
+
+int main()
+{
+    int fds[2];
+    if (pipe(fds) < 0) {
+        perror("pipe");
+        return 1;
+    }
+
+    if (fcntl(fds[0], F_GETSIG) != 0) {
+        printf("fail: default F_GETSIG should be 0\n");
+        return 1;
+    }
+
+    if (fcntl(fds[0], F_SETSIG, 1000) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL for an invalid signal\n");
+        return 1;
+    }
+
+    struct sigaction sa;
+    memset(&sa, 0, sizeof(sa));
+    sa.sa_sigaction = handler;
+    sa.sa_flags = SA_SIGINFO;
+    sigemptyset(&sa.sa_mask);
+    if (sigaction(SIGRTMIN, &sa, NULL) < 0) {
+        perror("sigaction");
+        return 1;
+    }
+
+    if (fcntl(fds[0], F_SETOWN, getpid()) < 0 ||
+        fcntl(fds[0], F_SETSIG, SIGRTMIN) < 0 ||
+        fcntl(fds[0], F_SETFL, fcntl(fds[0], F_GETFL) | O_ASYNC | O_NONBLOCK) < 0) {
+        perror("fcntl");
+        return 1;
+    }
+
+    if (fcntl(fds[0], F_GETSIG) != SIGRTMIN) {
+        printf("fail: F_GETSIG returned %d\n", fcntl(fds[0], F_GETSIG));
+        return 1;
+    }
+
+    if (write(fds[1], "x", 1) != 1) {
+        perror("write");
+        return 1;
+    }
+
+    for (int i = 0; i < 100 && !gotSig; i++) {
+        usleep(10000);
+    }
+
+    if (gotSig != SIGRTMIN || gotFd != fds[0] || gotCode != POLL_IN) {
+        printf("fail: sig %d fd %d code %d\n", gotSig, gotFd, gotCode);
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,76 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/resource.h>
+#include <sys/stat.h>
+#include <sys/uio.h>
+#include <unistd.h>
+
+#define LIMIT 1000
+
+int main()
+{
+    // the write past the limit would otherwise kill us with SIGXFSZ
+    signal(SIGXFSZ, SIG_IGN);
+
+    struct rlimit rl = {LIMIT, LIMIT};
+    if (setrlimit(RLIMIT_FSIZE, &rl) < 0) {
+        perror("setrlimit");
+        return 1;
+    }
+
+    char path[] = "/tmp/fsizelimitXXXXXX";
+    int fd = mkstemp(path);
+    if (fd < 0) {
+        perror("mkstemp");
+        return 1;
+    }
+    unlink(path);
+
+    // three 400 byte iovecs: the limit falls in the middle of the third
+    char a[400], b[400], c[400];
+    memset(a, 'a', sizeof(a));
+    memset(b, 'b', sizeof(b));
+    memset(c, 'c', sizeof(c));
+    struct iovec iov[3] = {{a, sizeof(a)}, {b, sizeof(b)}, {c, sizeof(c)}};
+
+    ssize_t n = writev(fd, iov, 3);
+    if (n != LIMIT) {
+        printf("fail: writev returned %zd, want %d\n", n, LIMIT);
+        return 1;
+    }
+
+    off_t off = lseek(fd, 0, SEEK_CUR);
+    struct stat st;
+    if (fstat(fd, &st) < 0 || st.st_size != LIMIT || off != LIMIT) {
+        printf("fail: size %ld offset %ld, want %d\n", (long)st.st_size, (long)off, LIMIT);
+        return 1;
+    }
+
+    char last;
+    if (pread(fd, &last, 1, LIMIT - 1) != 1 || last != 'c') {
+        printf("fail: last byte is %c\n", last);
+        return 1;
+    }
+
+    // nothing more fits
+    if (write(fd, a, 1) != -1 || errno != EFBIG) {
+        printf("fail: expected EFBIG at the limit\n");
+        return 1;
+    }
+
+    // pwritev is capped the same way
+    n = pwritev(fd, iov, 3, 500);
+    if (n != LIMIT - 500) {
+        printf("fail: pwritev returned %zd, want %d\n", n, LIMIT - 500);
+        return 1;
+    }
+
+    close(fd);
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,122 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/resource.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+// ftruncate grows a file with zeros and shrinks it so reads past the new end
+// return EOF. Only regular files opened for writing can be truncated.
+
+static off_t fileSize(int fd)
+{
+    struct stat st;
+    if (fstat(fd, &st) < 0) {
+        return -1;
+    }
+    return st.st_size;
+}
+
+int main()
+{
+    char path[] = "/tmp/ftruncateXXXXXX";
+    int fd = mkstemp(path);
+    if (fd < 0) {
+        perror("mkstemp");
+        return 1;
+    }
+
+    if (write(fd, "hello", 5) != 5) {
+        perror("write");
+        return 1;
+    }
+
+    // grow: the bytes past the old end read as zeros
+    if (ftruncate(fd, 8192) < 0 || fileSize(fd) != 8192) {
+        printf("fail: ftruncate didn't grow the file\n");
+        return 1;
+    }
+    char buf[8192];
+    if (pread(fd, buf, sizeof(buf), 0) != sizeof(buf) || memcmp(buf, "hello", 5) != 0) {
+        printf("fail: read of the grown file\n");
+        return 1;
+    }
+    for (int i = 5; i < sizeof(buf); i++) {
+        if (buf[i] != 0) {
+            printf("fail: byte %d of the grown file is %d\n", i, buf[i]);
+            return 1;
+        }
+    }
+
+    // shrink: reads past the new end return EOF
+    if (ftruncate(fd, 3) < 0 || fileSize(fd) != 3) {
+        printf("fail: ftruncate didn't shrink the file\n");
+        return 1;
+    }
+    if (pread(fd, buf, sizeof(buf), 0) != 3 || memcmp(buf, "hel", 3) != 0) {
+        printf("fail: read of the shrunk file\n");
+        return 1;
+    }
+    if (pread(fd, buf, sizeof(buf), 3) != 0) {
+        printf("fail: read past the new end didn't return EOF\n");
+        return 1;
+    }
+
+    // growing again doesn't bring back the truncated data
+    if (ftruncate(fd, 5) < 0 || pread(fd, buf, 5, 0) != 5 || memcmp(buf, "hel\0\0", 5) != 0) {
+        printf("fail: the truncated data came back\n");
+        return 1;
+    }
+
+    if (ftruncate(fd, -1) != -1 || errno != EINVAL) {
+        printf("fail: ftruncate to a negative size\n");
+        return 1;
+    }
+
+    // a file opened read only
+    int ro = open(path, O_RDONLY);
+    if (ro < 0) {
+        perror("open");
+        return 1;
+    }
+    if (ftruncate(ro, 0) != -1 || errno != EINVAL) {
+        printf("fail: ftruncate of a read only file\n");
+        return 1;
+    }
+    close(ro);
+    unlink(path);
+
+    // a pipe
+    int p[2];
+    if (pipe(p) < 0) {
+        perror("pipe");
+        return 1;
+    }
+    if (ftruncate(p[1], 0) != -1 || errno != EINVAL) {
+        printf("fail: ftruncate of a pipe\n");
+        return 1;
+    }
+
+    // RLIMIT_FSIZE bounds the new size
+    signal(SIGXFSZ, SIG_IGN);
+    struct rlimit rl = {.rlim_cur = 4096, .rlim_max = RLIM_INFINITY};
+    if (setrlimit(RLIMIT_FSIZE, &rl) < 0) {
+        perror("setrlimit");
+        return 1;
+    }
+    if (ftruncate(fd, 8192) != -1 || errno != EFBIG) {
+        printf("fail: ftruncate past RLIMIT_FSIZE\n");
+        return 1;
+    }
+    if (ftruncate(fd, 4096) < 0) {
+        perror("ftruncate");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
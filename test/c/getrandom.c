@@ -0,0 +1,107 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/auxv.h>
+#include <sys/random.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// getrandom fills the buffer from the host's entropy, honoring GRND_NONBLOCK
+// and GRND_RANDOM, and every process gets different AT_RANDOM bytes.
+
+// atRandom runs a copy of this program and reads the AT_RANDOM bytes it
+// reports.
+static int atRandom(const char *self, char *buf, size_t len)
+{
+    int p[2];
+    if (pipe(p) < 0) {
+        perror("pipe");
+        return -1;
+    }
+
+    pid_t pid = fork();
+    if (pid == 0) {
+        dup2(p[1], 1);
+        execl(self, self, "child", NULL);
+        _exit(127);
+    }
+    close(p[1]);
+
+    ssize_t n = read(p[0], buf, len - 1);
+    close(p[0]);
+    int status;
+    waitpid(pid, &status, 0);
+    if (n <= 0 || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fail: the child didn't report AT_RANDOM\n");
+        return -1;
+    }
+    buf[n] = 0;
+    return 0;
+}
+
+int main(int argc, char **argv)
+{
+    if (argc > 1 && strcmp(argv[1], "child") == 0) {
+        unsigned char *r = (unsigned char *)getauxval(AT_RANDOM);
+        for (int i = 0; i < 16; i++) {
+            printf("%02x", r[i]);
+        }
+        printf("\n");
+        return 0;
+    }
+
+    unsigned char a[256], b[256];
+    int flags[] = {0, GRND_NONBLOCK, GRND_RANDOM, GRND_RANDOM | GRND_NONBLOCK};
+    for (int i = 0; i < sizeof(flags) / sizeof(flags[0]); i++) {
+        memset(a, 0, sizeof(a));
+        memset(b, 0, sizeof(b));
+        if (getrandom(a, sizeof(a), flags[i]) != sizeof(a) || getrandom(b, sizeof(b), flags[i]) != sizeof(b)) {
+            printf("fail: getrandom with flags %x: %d\n", flags[i], errno);
+            return 1;
+        }
+        if (memcmp(a, b, sizeof(a)) == 0) {
+            printf("fail: two getrandom calls with flags %x returned the same bytes\n", flags[i]);
+            return 1;
+        }
+    }
+
+    // a large read is filled
+    size_t len = 1 << 20;
+    unsigned char *big = calloc(1, len);
+    size_t zeros = 0;
+    if (getrandom(big, len, 0) != len) {
+        perror("getrandom 1MB");
+        return 1;
+    }
+    for (size_t i = 0; i < len; i++) {
+        zeros += big[i] == 0;
+    }
+    if (zeros > len / 128) {
+        printf("fail: %zu zero bytes in 1MB of random data\n", zeros);
+        return 1;
+    }
+
+    if (getrandom(a, sizeof(a), 0x80) != -1 || errno != EINVAL) {
+        printf("fail: getrandom with an invalid flag\n");
+        return 1;
+    }
+    if (syscall(SYS_getrandom, NULL, 16, 0) != -1 || errno != EFAULT) {
+        printf("fail: getrandom to a bad address\n");
+        return 1;
+    }
+
+    char r1[64], r2[64];
+    if (atRandom(argv[0], r1, sizeof(r1)) < 0 || atRandom(argv[0], r2, sizeof(r2)) < 0) {
+        return 1;
+    }
+    if (strcmp(r1, r2) == 0) {
+        printf("fail: two processes got the same AT_RANDOM %s", r1);
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,102 @@
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <errno.h>
+#include <netinet/in.h>
+#include <poll.h>
+#include <signal.h>
+#include <stdio.h>
+#include <sys/epoll.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+static volatile sig_atomic_t pipes;
+
+static void handler(int sig)
+{
+    pipes++;
+}
+
+int main()
+{
+    struct sigaction sa = {.sa_handler = handler};
+    sigaction(SIGPIPE, &sa, NULL);
+
+    int srv = socket(AF_INET, SOCK_STREAM, 0);
+    struct sockaddr_in addr = {.sin_family = AF_INET};
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    socklen_t alen = sizeof(addr);
+    if (srv < 0 || bind(srv, (struct sockaddr *)&addr, sizeof(addr)) < 0 || listen(srv, 1) < 0 ||
+        getsockname(srv, (struct sockaddr *)&addr, &alen) < 0) {
+        perror("listen");
+        return 1;
+    }
+
+    int cli = socket(AF_INET, SOCK_STREAM, 0);
+    if (cli < 0 || connect(cli, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        perror("connect");
+        return 1;
+    }
+    int conn = accept(srv, NULL, NULL);
+    if (conn < 0) {
+        perror("accept");
+        return 1;
+    }
+
+    int ep = epoll_create1(0);
+    struct epoll_event ev = {.events = EPOLLRDHUP, .data.fd = conn};
+    if (ep < 0 || epoll_ctl(ep, EPOLL_CTL_ADD, conn, &ev) < 0) {
+        perror("epoll");
+        return 1;
+    }
+    if (epoll_wait(ep, &ev, 1, 0) != 0) {
+        printf("fail: EPOLLRDHUP before the peer shut down\n");
+        return 1;
+    }
+
+    // the peer sees EOF and EPOLLRDHUP as soon as the write side is shut down
+    if (write(cli, "x", 1) != 1 || shutdown(cli, SHUT_WR) < 0) {
+        perror("shutdown");
+        return 1;
+    }
+    if (epoll_wait(ep, &ev, 1, 5000) != 1 || !(ev.events & EPOLLRDHUP)) {
+        printf("fail: no EPOLLRDHUP after SHUT_WR\n");
+        return 1;
+    }
+    struct pollfd pfd = {conn, POLLIN | POLLRDHUP, 0};
+    if (poll(&pfd, 1, 5000) != 1 || (pfd.revents & (POLLIN | POLLRDHUP)) != (POLLIN | POLLRDHUP)) {
+        printf("fail: poll revents %x after SHUT_WR\n", pfd.revents);
+        return 1;
+    }
+    char buf[8];
+    if (read(conn, buf, sizeof(buf)) != 1 || read(conn, buf, sizeof(buf)) != 0) {
+        printf("fail: expected the data then EOF\n");
+        return 1;
+    }
+
+    // the other direction still works
+    if (write(conn, "yz", 2) != 2 || read(cli, buf, sizeof(buf)) != 2) {
+        printf("fail: reverse direction broken after SHUT_WR\n");
+        return 1;
+    }
+
+    // further writes fail with EPIPE, raising SIGPIPE unless MSG_NOSIGNAL
+    if (send(cli, "x", 1, MSG_NOSIGNAL) != -1 || errno != EPIPE || pipes != 0) {
+        printf("fail: expected EPIPE without SIGPIPE\n");
+        return 1;
+    }
+    if (write(cli, "x", 1) != -1 || errno != EPIPE || pipes != 1) {
+        printf("fail: expected EPIPE with SIGPIPE from write\n");
+        return 1;
+    }
+    if (send(cli, "x", 1, 0) != -1 || errno != EPIPE || pipes != 2) {
+        printf("fail: expected EPIPE with SIGPIPE from send\n");
+        return 1;
+    }
+
+    close(ep);
+    close(conn);
+    close(cli);
+    close(srv);
+    printf("pass\n");
+    return 0;
+}
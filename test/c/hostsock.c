@@ -0,0 +1,66 @@
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+// A unix socket passed in from the host, e.g. for socket activation, is
+// imported as a unix socket of the host socket's type, with its O_NONBLOCK
+// flag kept. Run it with the socket as stdin, e.g.
+//
+//     socat UNIX-LISTEN:/tmp/hostsock.sock EXEC:./hostsock &
+//     socat - UNIX-CONNECT:/tmp/hostsock.sock
+//
+// or with the fd number to check as its argument.
+
+int main(int argc, char *argv[])
+{
+    int fd = argc > 1 ? atoi(argv[1]) : 0;
+
+    struct stat st;
+    if (fstat(fd, &st) < 0) {
+        perror("fstat");
+        return 1;
+    }
+    if (!S_ISSOCK(st.st_mode)) {
+        printf("fail: fd %d is not a socket, mode %o\n", fd, st.st_mode);
+        return 1;
+    }
+
+    int domain = -1, type = -1;
+    socklen_t len = sizeof(domain);
+    if (getsockopt(fd, SOL_SOCKET, SO_DOMAIN, &domain, &len) < 0) {
+        perror("SO_DOMAIN");
+        return 1;
+    }
+    len = sizeof(type);
+    if (getsockopt(fd, SOL_SOCKET, SO_TYPE, &type, &len) < 0) {
+        perror("SO_TYPE");
+        return 1;
+    }
+    if (domain != AF_UNIX || (type != SOCK_STREAM && type != SOCK_DGRAM && type != SOCK_SEQPACKET)) {
+        printf("fail: imported socket has domain %d, type %d\n", domain, type);
+        return 1;
+    }
+
+    // the socket is blocking unless the host fd was non-blocking
+    int flags = fcntl(fd, F_GETFL);
+    if (flags < 0) {
+        perror("F_GETFL");
+        return 1;
+    }
+    if (fcntl(fd, F_SETFL, flags | O_NONBLOCK) < 0 || !(fcntl(fd, F_GETFL) & O_NONBLOCK)) {
+        printf("fail: O_NONBLOCK can't be set on the imported socket\n");
+        return 1;
+    }
+    if (fcntl(fd, F_SETFL, flags) < 0) {
+        perror("F_SETFL");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,179 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <poll.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/inotify.h>
+#include <sys/ioctl.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+// inotify: a watch on a directory reports the creation, modification,
+// close, rename and deletion of its children, and rm_watch sends
+// IN_IGNORED.
+
+static char buf[4096] __attribute__((aligned(__alignof__(struct inotify_event))));
+static int len, pos;
+
+// next returns the next event, reading more if needed.
+static struct inotify_event *next(int fd)
+{
+    if (pos >= len) {
+        len = read(fd, buf, sizeof(buf));
+        pos = 0;
+        if (len <= 0) {
+            return NULL;
+        }
+    }
+    struct inotify_event *e = (struct inotify_event *)(buf + pos);
+    pos += sizeof(struct inotify_event) + e->len;
+    return e;
+}
+
+static int expect(int fd, int wd, unsigned mask, const char *name, unsigned *cookie)
+{
+    struct inotify_event *e = next(fd);
+    if (e == NULL) {
+        printf("fail: no event, expected mask %x name %s\n", mask, name);
+        return 1;
+    }
+    const char *ename = e->len ? e->name : "";
+    if (e->wd != wd || e->mask != mask || strcmp(ename, name) != 0) {
+        printf("fail: got event wd %d mask %x name '%s', expected wd %d mask %x name '%s'\n",
+               e->wd, e->mask, ename, wd, mask, name);
+        return 1;
+    }
+    if (e->len % sizeof(struct inotify_event) != 0) {
+        printf("fail: the name length %d isn't padded\n", e->len);
+        return 1;
+    }
+    if (cookie) {
+        *cookie = e->cookie;
+    }
+    return 0;
+}
+
+int main()
+{
+    char dir[] = "/tmp/inotifyXXXXXX";
+    if (mkdtemp(dir) == NULL) {
+        perror("mkdtemp");
+        return 1;
+    }
+
+    int fd = inotify_init1(IN_NONBLOCK | IN_CLOEXEC);
+    if (fd < 0) {
+        perror("inotify_init1");
+        return 1;
+    }
+    int wd = inotify_add_watch(fd, dir, IN_CREATE | IN_DELETE | IN_MODIFY | IN_CLOSE_WRITE | IN_MOVE);
+    if (wd < 0) {
+        perror("inotify_add_watch");
+        return 1;
+    }
+
+    if (read(fd, buf, sizeof(buf)) != -1 || errno != EAGAIN) {
+        printf("fail: read without events\n");
+        return 1;
+    }
+
+    char path[PATH_MAX], path2[PATH_MAX];
+    snprintf(path, sizeof(path), "%s/file", dir);
+    snprintf(path2, sizeof(path2), "%s/renamed", dir);
+
+    int f = open(path, O_CREAT | O_WRONLY, 0644);
+    if (f < 0 || write(f, "x", 1) != 1) {
+        perror("create");
+        return 1;
+    }
+    close(f);
+
+    struct pollfd pfd = {.fd = fd, .events = POLLIN};
+    if (poll(&pfd, 1, 1000) != 1 || !(pfd.revents & POLLIN)) {
+        printf("fail: the inotify fd isn't readable\n");
+        return 1;
+    }
+    char b[1];
+    if (read(fd, b, sizeof(b)) != -1 || errno != EINVAL) {
+        printf("fail: read with a too small buffer\n");
+        return 1;
+    }
+    int avail;
+    if (ioctl(fd, FIONREAD, &avail) < 0 || avail != 3 * (int)(sizeof(struct inotify_event) + 16)) {
+        printf("fail: FIONREAD is %d\n", avail);
+        return 1;
+    }
+
+    if (expect(fd, wd, IN_CREATE, "file", NULL) || expect(fd, wd, IN_MODIFY, "file", NULL) ||
+        expect(fd, wd, IN_CLOSE_WRITE, "file", NULL)) {
+        return 1;
+    }
+
+    unsigned from, to;
+    if (rename(path, path2) < 0) {
+        perror("rename");
+        return 1;
+    }
+    if (expect(fd, wd, IN_MOVED_FROM, "file", &from) || expect(fd, wd, IN_MOVED_TO, "renamed", &to)) {
+        return 1;
+    }
+    if (from == 0 || from != to) {
+        printf("fail: rename cookies %u and %u\n", from, to);
+        return 1;
+    }
+
+    if (unlink(path2) < 0) {
+        perror("unlink");
+        return 1;
+    }
+    if (expect(fd, wd, IN_DELETE, "renamed", NULL)) {
+        return 1;
+    }
+
+    snprintf(path, sizeof(path), "%s/sub", dir);
+    if (mkdir(path, 0755) < 0 || rmdir(path) < 0) {
+        perror("mkdir");
+        return 1;
+    }
+    if (expect(fd, wd, IN_CREATE | IN_ISDIR, "sub", NULL) || expect(fd, wd, IN_DELETE | IN_ISDIR, "sub", NULL)) {
+        return 1;
+    }
+
+    if (inotify_rm_watch(fd, wd) < 0) {
+        perror("inotify_rm_watch");
+        return 1;
+    }
+    if (expect(fd, wd, IN_IGNORED, "", NULL)) {
+        return 1;
+    }
+    if (inotify_rm_watch(fd, wd) != -1 || errno != EINVAL) {
+        printf("fail: inotify_rm_watch of a removed watch\n");
+        return 1;
+    }
+
+    // no more events after the watch is gone
+    f = open(path2, O_CREAT | O_WRONLY, 0644);
+    close(f);
+    unlink(path2);
+    if (next(fd) != NULL) {
+        printf("fail: event after inotify_rm_watch\n");
+        return 1;
+    }
+
+    if (inotify_add_watch(fd, path2, IN_CREATE) != -1 || errno != ENOENT) {
+        printf("fail: watching a missing path\n");
+        return 1;
+    }
+    if (inotify_add_watch(fd, dir, 0) != -1 || errno != EINVAL) {
+        printf("fail: watching with an empty mask\n");
+        return 1;
+    }
+
+    close(fd);
+    rmdir(dir);
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,108 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// ioprio_set stores a per-thread I/O priority which ioprio_get reports and
+// children inherit, and invalid priorities and targets are rejected.
+
+#define IOPRIO_CLASS_SHIFT 13
+#define IOPRIO_PRIO_VALUE(class, data) (((class) << IOPRIO_CLASS_SHIFT) | (data))
+
+enum { IOPRIO_CLASS_NONE, IOPRIO_CLASS_RT, IOPRIO_CLASS_BE, IOPRIO_CLASS_IDLE };
+enum { IOPRIO_WHO_PROCESS = 1, IOPRIO_WHO_PGRP, IOPRIO_WHO_USER };
+
+static int ioprioSet(int which, int who, int ioprio)
+{
+    return syscall(SYS_ioprio_set, which, who, ioprio);
+}
+
+static int ioprioGet(int which, int who)
+{
+    return syscall(SYS_ioprio_get, which, who);
+}
+
+int main()
+{
+    int be = IOPRIO_PRIO_VALUE(IOPRIO_CLASS_BE, 7);
+    if (ioprioSet(IOPRIO_WHO_PROCESS, 0, be) < 0) {
+        perror("ioprio_set");
+        return 1;
+    }
+    if (ioprioGet(IOPRIO_WHO_PROCESS, 0) != be || ioprioGet(IOPRIO_WHO_PROCESS, getpid()) != be) {
+        printf("fail: ioprio_get returned %x, want %x\n", ioprioGet(IOPRIO_WHO_PROCESS, 0), be);
+        return 1;
+    }
+
+    // the priority is inherited and the process group reports the best one
+    int up[2], down[2];
+    pipe(up);
+    pipe(down);
+    pid_t pid = fork();
+    if (pid == 0) {
+        char c;
+        int inherited = ioprioGet(IOPRIO_WHO_PROCESS, 0) == be;
+        ioprioSet(IOPRIO_WHO_PROCESS, 0, IOPRIO_PRIO_VALUE(IOPRIO_CLASS_BE, 2));
+        write(up[1], "x", 1);
+        read(down[0], &c, 1);
+        _exit(inherited ? 0 : 1);
+    }
+    char c;
+    read(up[0], &c, 1);
+    int best = ioprioGet(IOPRIO_WHO_PGRP, 0);
+    if (ioprioGet(IOPRIO_WHO_PROCESS, pid) != IOPRIO_PRIO_VALUE(IOPRIO_CLASS_BE, 2) ||
+        best != IOPRIO_PRIO_VALUE(IOPRIO_CLASS_BE, 2)) {
+        printf("fail: the child's priority is %x and the group's %x\n", ioprioGet(IOPRIO_WHO_PROCESS, pid), best);
+        return 1;
+    }
+    write(down[1], "x", 1);
+    int status;
+    waitpid(pid, &status, 0);
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fail: the child didn't inherit the priority\n");
+        return 1;
+    }
+
+    // the idle class needs no privilege
+    int idle = IOPRIO_PRIO_VALUE(IOPRIO_CLASS_IDLE, 0);
+    if (ioprioSet(IOPRIO_WHO_PGRP, 0, idle) < 0 || ioprioGet(IOPRIO_WHO_PROCESS, 0) != idle) {
+        printf("fail: can't set the idle class for the process group\n");
+        return 1;
+    }
+
+    // I/O still works with the priority set
+    int fd = open("/proc/self/exe", O_RDONLY);
+    char buf[4];
+    if (fd < 0 || pread(fd, buf, sizeof(buf), 0) != sizeof(buf) || buf[1] != 'E') {
+        printf("fail: can't read with an idle I/O priority\n");
+        return 1;
+    }
+    close(fd);
+
+    // bad priorities and targets
+    struct {
+        int which, who, ioprio, err;
+    } bad[] = {
+        {IOPRIO_WHO_PROCESS, 0, IOPRIO_PRIO_VALUE(4, 0), EINVAL},
+        {IOPRIO_WHO_PROCESS, 0, -1, EINVAL},
+        {4, 0, be, EINVAL},
+        {IOPRIO_WHO_PROCESS, 0x3fffffff, be, ESRCH},
+    };
+    for (int i = 0; i < sizeof(bad) / sizeof(bad[0]); i++) {
+        if (ioprioSet(bad[i].which, bad[i].who, bad[i].ioprio) != -1 || errno != bad[i].err) {
+            printf("fail: ioprio_set(%d, %d, %x) didn't fail with %d\n", bad[i].which, bad[i].who, bad[i].ioprio,
+                   bad[i].err);
+            return 1;
+        }
+    }
+    if (ioprioGet(IOPRIO_WHO_PROCESS, 0x3fffffff) != -1 || errno != ESRCH) {
+        printf("fail: ioprio_get of a missing process didn't fail with ESRCH\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,108 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <sys/time.h>
+#include <time.h>
+#include <unistd.h>
+
+static volatile sig_atomic_t alrms;
+static volatile sig_atomic_t profs;
+
+static void onAlrm(int sig)
+{
+    alrms++;
+}
+
+static void onProf(int sig)
+{
+    profs++;
+}
+
+static long long now(clockid_t id)
+{
+    struct timespec ts;
+    clock_gettime(id, &ts);
+    return ts.tv_sec * 1000000000LL + ts.tv_nsec;
+}
+
+int main()
+{
+    struct sigaction sa = {.sa_handler = onAlrm};
+    sigaction(SIGALRM, &sa, NULL);
+    sa.sa_handler = onProf;
+    sigaction(SIGPROF, &sa, NULL);
+
+    // a one-shot ITIMER_REAL fires SIGALRM once and then reads as disarmed
+    struct itimerval it = {.it_value = {0, 50000}};
+    if (setitimer(ITIMER_REAL, &it, NULL) < 0) {
+        perror("setitimer");
+        return 1;
+    }
+    long long end = now(CLOCK_MONOTONIC) + 2000000000LL;
+    while (alrms == 0 && now(CLOCK_MONOTONIC) < end) {
+        usleep(1000);
+    }
+    usleep(100000);
+    struct itimerval got;
+    if (alrms != 1 || getitimer(ITIMER_REAL, &got) < 0 || got.it_value.tv_sec != 0 ||
+        got.it_value.tv_usec != 0) {
+        printf("fail: one-shot timer fired %d times\n", alrms);
+        return 1;
+    }
+
+    // a periodic ITIMER_REAL reloads from the interval
+    alrms = 0;
+    it.it_value.tv_usec = 20000;
+    it.it_interval.tv_usec = 20000;
+    setitimer(ITIMER_REAL, &it, NULL);
+    end = now(CLOCK_MONOTONIC) + 5000000000LL;
+    while (alrms < 3 && now(CLOCK_MONOTONIC) < end) {
+        usleep(1000);
+    }
+    if (alrms < 3 || getitimer(ITIMER_REAL, &got) < 0 || got.it_interval.tv_usec != 20000) {
+        printf("fail: periodic timer fired %d times\n", alrms);
+        return 1;
+    }
+
+    // setting a new value returns the old one
+    struct itimerval old;
+    struct itimerval off = {};
+    if (setitimer(ITIMER_REAL, &off, &old) < 0 || old.it_interval.tv_usec != 20000) {
+        printf("fail: old value not returned\n");
+        return 1;
+    }
+
+    // ITIMER_PROF counts CPU time
+    it.it_value.tv_usec = 50000;
+    it.it_interval.tv_usec = 0;
+    setitimer(ITIMER_PROF, &it, NULL);
+    end = now(CLOCK_MONOTONIC) + 5000000000LL;
+    while (profs == 0 && now(CLOCK_MONOTONIC) < end) {
+    }
+    if (profs != 1) {
+        printf("fail: ITIMER_PROF didn't fire\n");
+        return 1;
+    }
+
+    // bad arguments
+    it.it_value.tv_usec = 1000000;
+    if (setitimer(ITIMER_REAL, &it, NULL) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL for tv_usec out of range\n");
+        return 1;
+    }
+    if (getitimer(42, &got) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL for an unknown timer\n");
+        return 1;
+    }
+
+    // alarm reports the time left rounded to the nearest second
+    alarm(10);
+    if (alarm(0) != 10) {
+        printf("fail: alarm didn't report the remaining time\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,209 @@
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <errno.h>
+#include <netinet/in.h>
+#include <poll.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+// Throughput of a TCP stream over 127.0.0.1 between two processes, iperf
+// style. Run it with LoopbackFastPath on and off to compare the in-sandbox
+// loopback with the host path.
+
+#define CHUNK (128 * 1024)
+#define TOTAL (256LL * 1024 * 1024)
+
+static double now()
+{
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec + ts.tv_nsec / 1e9;
+}
+
+static int listener(struct sockaddr_in *addr)
+{
+    int srv = socket(AF_INET, SOCK_STREAM, 0);
+    socklen_t alen = sizeof(*addr);
+    memset(addr, 0, sizeof(*addr));
+    addr->sin_family = AF_INET;
+    addr->sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    if (srv < 0 || bind(srv, (struct sockaddr *)addr, sizeof(*addr)) < 0 || listen(srv, 4) < 0 ||
+        getsockname(srv, (struct sockaddr *)addr, &alen) < 0) {
+        perror("listen");
+        exit(1);
+    }
+    return srv;
+}
+
+static int throughput()
+{
+    struct sockaddr_in addr;
+    int srv = listener(&addr);
+
+    pid_t pid = fork();
+    if (pid == 0) {
+        int cli = socket(AF_INET, SOCK_STREAM, 0);
+        if (cli < 0 || connect(cli, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+            perror("connect");
+            exit(1);
+        }
+        static char buf[CHUNK];
+        long long sent = 0;
+        while (sent < TOTAL) {
+            for (int i = 0; i < CHUNK; i++)
+                buf[i] = (char)((sent + i) & 0xff);
+            ssize_t n = write(cli, buf, CHUNK);
+            if (n <= 0) {
+                perror("write");
+                exit(1);
+            }
+            // resend from where the short write stopped
+            sent += n;
+        }
+        close(cli);
+        exit(0);
+    }
+
+    int conn = accept(srv, NULL, NULL);
+    if (conn < 0) {
+        perror("accept");
+        return 1;
+    }
+
+    static char buf[CHUNK];
+    long long recvd = 0;
+    double start = now();
+    for (;;) {
+        ssize_t n = read(conn, buf, sizeof(buf));
+        if (n < 0) {
+            perror("read");
+            return 1;
+        }
+        if (n == 0)
+            break;
+        for (ssize_t i = 0; i < n; i++) {
+            if (buf[i] != (char)((recvd + i) & 0xff)) {
+                printf("fail: corrupted byte at %lld\n", recvd + i);
+                return 1;
+            }
+        }
+        recvd += n;
+    }
+    double secs = now() - start;
+
+    int status;
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fail: sender failed\n");
+        return 1;
+    }
+    if (recvd != TOTAL) {
+        printf("fail: received %lld of %lld bytes\n", recvd, TOTAL);
+        return 1;
+    }
+
+    printf("loopback throughput: %lld MB in %.3fs, %.1f MB/s\n", TOTAL >> 20, secs, (TOTAL >> 20) / secs);
+    close(conn);
+    close(srv);
+    return 0;
+}
+
+static int semantics()
+{
+    struct sockaddr_in addr;
+    int srv = listener(&addr);
+
+    int cli = socket(AF_INET, SOCK_STREAM, 0);
+    if (cli < 0 || connect(cli, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        perror("connect");
+        return 1;
+    }
+    struct sockaddr_in peer, local;
+    socklen_t plen = sizeof(peer), llen = sizeof(local);
+    int conn = accept(srv, (struct sockaddr *)&peer, &plen);
+    if (conn < 0 || getsockname(cli, (struct sockaddr *)&local, &llen) < 0) {
+        perror("accept");
+        return 1;
+    }
+
+    // the accepted peer is the connecting socket's own address
+    if (plen != sizeof(peer) || peer.sin_port != local.sin_port || peer.sin_port == 0 ||
+        peer.sin_addr.s_addr != htonl(INADDR_LOOPBACK)) {
+        printf("fail: accepted peer doesn't match the client address\n");
+        return 1;
+    }
+    plen = sizeof(peer);
+    if (getpeername(cli, (struct sockaddr *)&peer, &plen) < 0 || peer.sin_port != addr.sin_port) {
+        printf("fail: getpeername doesn't return the listener address\n");
+        return 1;
+    }
+
+    int one = 1;
+    if (setsockopt(cli, IPPROTO_TCP, 1 /* TCP_NODELAY */, &one, sizeof(one)) < 0) {
+        perror("setsockopt");
+        return 1;
+    }
+
+    char buf[16];
+    if (write(cli, "hello", 5) != 5) {
+        perror("write");
+        return 1;
+    }
+    struct pollfd pfd = {conn, POLLIN, 0};
+    if (poll(&pfd, 1, 5000) != 1 || !(pfd.revents & POLLIN)) {
+        printf("fail: data not readable\n");
+        return 1;
+    }
+    if (recv(conn, buf, sizeof(buf), MSG_PEEK) != 5 || recv(conn, buf, sizeof(buf), 0) != 5 ||
+        memcmp(buf, "hello", 5) != 0) {
+        printf("fail: peek then read mismatch\n");
+        return 1;
+    }
+    if (recv(conn, buf, sizeof(buf), MSG_DONTWAIT) != -1 || errno != EAGAIN) {
+        printf("fail: empty socket didn't return EAGAIN\n");
+        return 1;
+    }
+
+    if (shutdown(cli, SHUT_WR) < 0 || read(conn, buf, sizeof(buf)) != 0) {
+        printf("fail: no EOF after SHUT_WR\n");
+        return 1;
+    }
+    if (write(conn, "back", 4) != 4 || read(cli, buf, sizeof(buf)) != 4) {
+        printf("fail: reverse direction broken after SHUT_WR\n");
+        return 1;
+    }
+
+    close(cli);
+    close(conn);
+    close(srv);
+    return 0;
+}
+
+static int fallback()
+{
+    // nothing in the sandbox listens on the port, so the host refuses it
+    struct sockaddr_in addr;
+    int srv = listener(&addr);
+    close(srv);
+
+    int cli = socket(AF_INET, SOCK_STREAM, 0);
+    if (connect(cli, (struct sockaddr *)&addr, sizeof(addr)) != -1 || errno != ECONNREFUSED) {
+        printf("fail: connect to a closed port didn't fail with ECONNREFUSED\n");
+        return 1;
+    }
+    close(cli);
+    return 0;
+}
+
+int main()
+{
+    if (semantics() || fallback() || throughput())
+        return 1;
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,95 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/sysinfo.h>
+#include <unistd.h>
+
+// madvise(MADV_DONTNEED) must give the memory back, so that the host sees the
+// RSS of the sandbox drop, and the range must read as zero afterwards.
+
+#define SIZE (1024L * 1024 * 1024)
+#define MB (1024L * 1024)
+
+static long freeram()
+{
+    struct sysinfo info;
+    if (sysinfo(&info) < 0) {
+        perror("sysinfo");
+        return -1;
+    }
+    return (long)info.freeram * info.mem_unit;
+}
+
+static int release(int advice, const char *name)
+{
+    char *p = mmap(NULL, SIZE, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (p == MAP_FAILED) {
+        perror("mmap");
+        return 1;
+    }
+    memset(p, 0x5a, SIZE);
+
+    long before = freeram();
+    if (madvise(p, SIZE, advice) < 0) {
+        perror("madvise");
+        return 1;
+    }
+    long after = freeram();
+
+    // other processes allocate in the meantime, so only ask for half of it
+    if (before < 0 || after < 0 || after - before < SIZE / 2) {
+        printf("fail: %s freed %ld MB of %ld MB\n", name, (after - before) / MB, SIZE / MB);
+        return 1;
+    }
+
+    long pagesz = sysconf(_SC_PAGESIZE);
+    for (long i = 0; i < SIZE; i += pagesz) {
+        if (p[i] != 0) {
+            printf("fail: %s page at offset %ld isn't zero\n", name, i);
+            return 1;
+        }
+    }
+
+    munmap(p, SIZE);
+    return 0;
+}
+
+// MADV_FREE may free lazily, so the pages hold either the old data or zero,
+// and writing to them again must keep the new data.
+static int lazyfree()
+{
+    long pagesz = sysconf(_SC_PAGESIZE);
+    char *p = mmap(NULL, 16 * pagesz, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    memset(p, 0x5a, 16 * pagesz);
+    if (madvise(p, 16 * pagesz, MADV_FREE) < 0) {
+        perror("madvise");
+        return 1;
+    }
+    for (long i = 0; i < 16 * pagesz; i += pagesz) {
+        if (p[i] != 0 && p[i] != 0x5a) {
+            printf("fail: MADV_FREE page at offset %ld is corrupted\n", i);
+            return 1;
+        }
+        p[i] = 0x33;
+    }
+    for (long i = 0; i < 16 * pagesz; i += pagesz) {
+        if (p[i] != 0x33) {
+            printf("fail: MADV_FREE page at offset %ld lost a write\n", i);
+            return 1;
+        }
+    }
+
+    munmap(p, 16 * pagesz);
+    return 0;
+}
+
+int main()
+{
+    if (release(MADV_DONTNEED, "MADV_DONTNEED") || lazyfree()) {
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
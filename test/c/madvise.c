@@ -0,0 +1,107 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+#ifndef MADV_FREE
+#define MADV_FREE 8
+#endif
+
+static int allZero(const char *p, size_t len)
+{
+    for (size_t i = 0; i < len; i++) {
+        if (p[i] != 0) {
+            return 0;
+        }
+    }
+    return 1;
+}
+
+int main()
+{
+    size_t page = sysconf(_SC_PAGESIZE);
+    size_t len = 4 * page;
+
+    char *p = mmap(NULL, len, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (p == MAP_FAILED) {
+        perror("mmap");
+        return 1;
+    }
+
+    memset(p, 'a', len);
+
+    // drop the middle two pages only
+    if (madvise(p + page, 2 * page, MADV_DONTNEED) < 0) {
+        perror("madvise(MADV_DONTNEED)");
+        return 1;
+    }
+
+    if (!allZero(p + page, 2 * page)) {
+        printf("fail: MADV_DONTNEED pages aren't zero\n");
+        return 1;
+    }
+
+    if (p[0] != 'a' || p[len - 1] != 'a') {
+        printf("fail: MADV_DONTNEED touched pages outside the range\n");
+        return 1;
+    }
+
+    // the discarded pages are usable again
+    memset(p + page, 'b', page);
+    if (p[page] != 'b') {
+        printf("fail: write after MADV_DONTNEED lost\n");
+        return 1;
+    }
+
+    // MADV_FREE pages are either kept or zeroed; writing again keeps them
+    if (madvise(p, len, MADV_FREE) < 0) {
+        perror("madvise(MADV_FREE)");
+        return 1;
+    }
+    if (p[0] != 'a' && p[0] != 0) {
+        printf("fail: MADV_FREE page has garbage\n");
+        return 1;
+    }
+    p[0] = 'c';
+    if (p[0] != 'c') {
+        printf("fail: write after MADV_FREE lost\n");
+        return 1;
+    }
+
+    // unaligned addresses and unmapped ranges are rejected
+    if (madvise(p + 1, page, MADV_DONTNEED) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL for an unaligned address\n");
+        return 1;
+    }
+
+    if (munmap(p + 2 * page, page) < 0) {
+        perror("munmap");
+        return 1;
+    }
+    if (madvise(p, len, MADV_DONTNEED) != -1 || errno != ENOMEM) {
+        printf("fail: expected ENOMEM for a range with a hole\n");
+        return 1;
+    }
+
+    // hints we don't act on still succeed
+    if (madvise(p, page, MADV_WILLNEED) < 0 || madvise(p, page, MADV_SEQUENTIAL) < 0) {
+        perror("madvise(hint)");
+        return 1;
+    }
+
+    // MADV_FREE is only for private anonymous memory
+    char *s = mmap(NULL, page, PROT_READ | PROT_WRITE, MAP_SHARED | MAP_ANONYMOUS, -1, 0);
+    if (s == MAP_FAILED) {
+        perror("mmap shared");
+        return 1;
+    }
+    if (madvise(s, page, MADV_FREE) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL for MADV_FREE on shared memory\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
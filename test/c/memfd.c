@@ -0,0 +1,114 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// memfd_create makes an anonymous file that can be resized, mapped shared
+// across a fork and sealed.
+
+int main()
+{
+    long page = sysconf(_SC_PAGESIZE);
+
+    int fd = memfd_create("test", MFD_CLOEXEC | MFD_ALLOW_SEALING);
+    if (fd < 0) {
+        perror("memfd_create");
+        return 1;
+    }
+
+    if (ftruncate(fd, 2 * page) < 0) {
+        perror("ftruncate");
+        return 1;
+    }
+
+    char *m = mmap(NULL, 2 * page, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    if (m == MAP_FAILED) {
+        perror("mmap");
+        return 1;
+    }
+    strcpy(m, "written through the mapping");
+
+    char buf[64] = {0};
+    if (pread(fd, buf, sizeof(buf) - 1, 0) < 0 || strcmp(buf, "written through the mapping") != 0) {
+        printf("fail: read '%s'\n", buf);
+        return 1;
+    }
+
+    // the child writes to the same pages
+    pid_t pid = fork();
+    if (pid == 0) {
+        strcpy(m + page, "from the child");
+        _exit(0);
+    }
+    int status;
+    waitpid(pid, &status, 0);
+    if (strcmp(m + page, "from the child") != 0) {
+        printf("fail: the child's write isn't visible\n");
+        return 1;
+    }
+
+    // F_SEAL_WRITE is refused while a writable shared mapping exists
+    if (fcntl(fd, F_ADD_SEALS, F_SEAL_WRITE) != -1 || errno != EBUSY) {
+        printf("fail: sealed against writes while mapped writable\n");
+        return 1;
+    }
+    munmap(m, 2 * page);
+
+    if (fcntl(fd, F_ADD_SEALS, F_SEAL_WRITE | F_SEAL_SHRINK | F_SEAL_GROW) < 0) {
+        perror("F_ADD_SEALS");
+        return 1;
+    }
+    int seals = fcntl(fd, F_GET_SEALS);
+    if (seals != (F_SEAL_WRITE | F_SEAL_SHRINK | F_SEAL_GROW)) {
+        printf("fail: F_GET_SEALS %x\n", seals);
+        return 1;
+    }
+
+    if (pwrite(fd, "x", 1, 0) != -1 || errno != EPERM) {
+        printf("fail: write to a sealed memfd\n");
+        return 1;
+    }
+    if (ftruncate(fd, page) != -1 || errno != EPERM || ftruncate(fd, 4 * page) != -1 || errno != EPERM) {
+        printf("fail: resized a sealed memfd\n");
+        return 1;
+    }
+    if (mmap(NULL, page, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0) != MAP_FAILED || errno != EPERM) {
+        printf("fail: writable shared mapping of a sealed memfd\n");
+        return 1;
+    }
+
+    // the contents can still be read and mapped read-only
+    m = mmap(NULL, page, PROT_READ, MAP_SHARED, fd, 0);
+    if (m == MAP_FAILED || strcmp(m, "written through the mapping") != 0) {
+        printf("fail: read-only mapping of a sealed memfd\n");
+        return 1;
+    }
+    if (mprotect(m, page, PROT_READ | PROT_WRITE) != -1 || errno != EACCES) {
+        printf("fail: mprotect made a sealed mapping writable\n");
+        return 1;
+    }
+
+    // without MFD_ALLOW_SEALING the file is sealed with F_SEAL_SEAL
+    int fd2 = memfd_create("noseal", 0);
+    if (fd2 < 0 || fcntl(fd2, F_GET_SEALS) != F_SEAL_SEAL) {
+        printf("fail: F_GET_SEALS without MFD_ALLOW_SEALING\n");
+        return 1;
+    }
+    if (fcntl(fd2, F_ADD_SEALS, F_SEAL_WRITE) != -1 || errno != EPERM) {
+        printf("fail: added a seal to a memfd without MFD_ALLOW_SEALING\n");
+        return 1;
+    }
+
+    if (memfd_create("bad", 0x100) != -1 || errno != EINVAL) {
+        printf("fail: unknown flags accepted\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
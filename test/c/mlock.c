@@ -0,0 +1,178 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <linux/capability.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/resource.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+// dropIpcLock drops CAP_IPC_LOCK so RLIMIT_MEMLOCK applies even to root.
+static int dropIpcLock(void)
+{
+    struct __user_cap_header_struct hdr = {_LINUX_CAPABILITY_VERSION_3, 0};
+    struct __user_cap_data_struct data[2];
+    if (syscall(SYS_capget, &hdr, data) < 0) {
+        perror("capget");
+        return 1;
+    }
+
+    data[0].effective &= ~(1u << CAP_IPC_LOCK);
+    data[0].permitted &= ~(1u << CAP_IPC_LOCK);
+    if (syscall(SYS_capset, &hdr, data) < 0) {
+        perror("capset");
+        return 1;
+    }
+    return 0;
+}
+
+static int setLimit(rlim_t cur)
+{
+    struct rlimit rl;
+    if (getrlimit(RLIMIT_MEMLOCK, &rl) < 0) {
+        perror("getrlimit");
+        return 1;
+    }
+
+    rl.rlim_cur = cur;
+    if (setrlimit(RLIMIT_MEMLOCK, &rl) < 0) {
+        perror("setrlimit");
+        return 1;
+    }
+    return 0;
+}
+
+// vmLck returns VmLck from /proc/self/status in kB, or -1.
+static long vmLck(void)
+{
+    FILE *f = fopen("/proc/self/status", "r");
+    char line[256];
+    long kb = -1;
+    while (f && fgets(line, sizeof(line), f)) {
+        if (sscanf(line, "VmLck: %ld kB", &kb) == 1) {
+            break;
+        }
+    }
+    if (f) {
+        fclose(f);
+    }
+    return kb;
+}
+
+// isLocked uses the fact that MADV_DONTNEED is refused on locked memory.
+static int isLocked(void *p, size_t len)
+{
+    return madvise(p, len, MADV_DONTNEED) == -1 && errno == EINVAL;
+}
+
+int main()
+{
+    size_t page = sysconf(_SC_PAGESIZE);
+
+    if (dropIpcLock() || setLimit(8 * page)) {
+        return 1;
+    }
+
+    char *p = mmap(NULL, 16 * page, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (p == MAP_FAILED) {
+        perror("mmap");
+        return 1;
+    }
+    memset(p, 'x', 16 * page);
+
+    // within the limit
+    if (mlock(p, 4 * page) < 0) {
+        perror("mlock");
+        return 1;
+    }
+    if (!isLocked(p, 4 * page) || p[0] != 'x') {
+        printf("fail: range isn't locked\n");
+        return 1;
+    }
+
+    if (vmLck() != (long)(4 * page >> 10)) {
+        printf("fail: VmLck is %ld kB after locking %zu kB\n", vmLck(), 4 * page >> 10);
+        return 1;
+    }
+
+    // relocking part of the same range doesn't count twice
+    if (mlock(p + 2 * page, 6 * page) < 0) {
+        perror("mlock overlap");
+        return 1;
+    }
+    if (vmLck() != (long)(8 * page >> 10)) {
+        printf("fail: VmLck is %ld kB after an overlapping mlock\n", vmLck());
+        return 1;
+    }
+
+    // beyond the limit
+    if (mlock(p + 8 * page, 4 * page) != -1 || errno != ENOMEM) {
+        printf("fail: expected ENOMEM above RLIMIT_MEMLOCK\n");
+        return 1;
+    }
+
+    // munlock clears the state and frees up the limit
+    if (munlock(p, 8 * page) < 0) {
+        perror("munlock");
+        return 1;
+    }
+    if (isLocked(p, 8 * page) || vmLck() != 0) {
+        printf("fail: range still locked after munlock\n");
+        return 1;
+    }
+    if (mlock(p + 8 * page, 8 * page) < 0) {
+        perror("mlock after munlock");
+        return 1;
+    }
+    munlock(p + 8 * page, 8 * page);
+
+    // unmapped memory
+    if (munmap(p + 15 * page, page) < 0 || mlock(p + 14 * page, 2 * page) != -1 || errno != ENOMEM) {
+        printf("fail: expected ENOMEM for an unmapped range\n");
+        return 1;
+    }
+    munlock(p, 15 * page);
+
+    // MCL_CURRENT can't lock more than the limit allows
+    if (mlockall(MCL_CURRENT) != -1 || errno != ENOMEM) {
+        printf("fail: expected ENOMEM from mlockall(MCL_CURRENT)\n");
+        return 1;
+    }
+
+    // MCL_FUTURE locks new mappings
+    if (mlockall(MCL_FUTURE) < 0) {
+        perror("mlockall(MCL_FUTURE)");
+        return 1;
+    }
+    char *q = mmap(NULL, 2 * page, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (q == MAP_FAILED) {
+        perror("mmap after MCL_FUTURE");
+        return 1;
+    }
+    if (!isLocked(q, 2 * page)) {
+        printf("fail: new mapping isn't locked after MCL_FUTURE\n");
+        return 1;
+    }
+
+    if (munlockall() < 0) {
+        perror("munlockall");
+        return 1;
+    }
+    if (isLocked(q, 2 * page)) {
+        printf("fail: mapping still locked after munlockall\n");
+        return 1;
+    }
+
+    // no locking at all with a zero limit
+    if (setLimit(0)) {
+        return 1;
+    }
+    if (mlock(p, page) != -1 || errno != EPERM) {
+        printf("fail: expected EPERM with a zero RLIMIT_MEMLOCK\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
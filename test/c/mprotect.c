@@ -0,0 +1,169 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <setjmp.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+static sigjmp_buf env;
+
+static void segv(int sig)
+{
+    siglongjmp(env, 1);
+}
+
+// writable reports whether a write to p succeeds without SIGSEGV.
+static int writable(volatile char *p)
+{
+    if (sigsetjmp(env, 1)) {
+        return 0;
+    }
+    *p = *p;
+    return 1;
+}
+
+// countVmas returns the number of /proc/self/maps entries overlapping
+// [start, end) and stores their permission strings in perms.
+static int countVmas(char *start, char *end, char perms[][5])
+{
+    FILE *f = fopen("/proc/self/maps", "r");
+    if (f == NULL) {
+        perror("fopen");
+        return -1;
+    }
+
+    int n = 0;
+    char line[512];
+    while (fgets(line, sizeof(line), f) != NULL) {
+        unsigned long s, e;
+        char p[5];
+        if (sscanf(line, "%lx-%lx %4s", &s, &e, p) != 3) {
+            continue;
+        }
+        if (s < (unsigned long)end && e > (unsigned long)start) {
+            if (n < 8) {
+                strcpy(perms[n], p);
+            }
+            n++;
+        }
+    }
+    fclose(f);
+    return n;
+}
+
+int main()
+{
+    size_t page = sysconf(_SC_PAGESIZE);
+    struct sigaction sa = {.sa_handler = segv};
+    sigaction(SIGSEGV, &sa, NULL);
+
+    char *p = mmap(NULL, 3 * page, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (p == MAP_FAILED) {
+        perror("mmap");
+        return 1;
+    }
+    memset(p, 'a', 3 * page);
+
+    // protecting the middle page splits the mapping in three
+    if (mprotect(p + page, page, PROT_READ) < 0) {
+        perror("mprotect");
+        return 1;
+    }
+    char perms[8][5];
+    if (countVmas(p, p + 3 * page, perms) != 3 || strcmp(perms[0], "rw-p") != 0 ||
+        strcmp(perms[1], "r--p") != 0 || strcmp(perms[2], "rw-p") != 0) {
+        printf("fail: expected a three way split\n");
+        return 1;
+    }
+    if (!writable(p) || writable(p + page) || !writable(p + 2 * page) || p[page] != 'a') {
+        printf("fail: wrong permissions after split\n");
+        return 1;
+    }
+
+    // restoring the permissions merges it back
+    if (mprotect(p + page, page, PROT_READ | PROT_WRITE) < 0 || !writable(p + page) ||
+        countVmas(p, p + 3 * page, perms) != 1) {
+        printf("fail: mapping didn't merge back\n");
+        return 1;
+    }
+
+    // a private page shared with a child stays copy-on-write
+    pid_t pid = fork();
+    if (pid == 0) {
+        mprotect(p, page, PROT_READ);
+        mprotect(p, page, PROT_READ | PROT_WRITE);
+        p[0] = 'c';
+        _exit(0);
+    }
+    int status;
+    waitpid(pid, &status, 0);
+    if (!WIFEXITED(status) || p[0] != 'a') {
+        printf("fail: child's write after mprotect leaked to the parent\n");
+        return 1;
+    }
+
+    // bad arguments
+    if (mprotect(p + 1, page, PROT_READ) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL for an unaligned address\n");
+        return 1;
+    }
+    if (mprotect(p, page, PROT_READ | 0x10000) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL for unknown prot bits\n");
+        return 1;
+    }
+    if (mprotect(p, page, PROT_READ | PROT_GROWSDOWN) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL for PROT_GROWSDOWN on a normal mapping\n");
+        return 1;
+    }
+
+    // holes are rejected
+    munmap(p + page, page);
+    if (mprotect(p, 3 * page, PROT_READ) != -1 || errno != ENOMEM) {
+        printf("fail: expected ENOMEM for a range with a hole\n");
+        return 1;
+    }
+
+    // a read-only shared file mapping can't become writable
+    char path[] = "/tmp/mprotectXXXXXX";
+    int fd = mkstemp(path);
+    if (fd < 0 || write(fd, "x", 1) != 1) {
+        perror("mkstemp");
+        return 1;
+    }
+    close(fd);
+    fd = open(path, O_RDONLY);
+    unlink(path);
+    char *f = mmap(NULL, page, PROT_READ, MAP_SHARED, fd, 0);
+    if (f == MAP_FAILED) {
+        perror("mmap file");
+        return 1;
+    }
+    if (mprotect(f, page, PROT_READ | PROT_WRITE) != -1 || errno != EACCES) {
+        printf("fail: expected EACCES beyond the mapping's max permissions\n");
+        return 1;
+    }
+
+    // PROT_GROWSDOWN extends the change to the start of a growsdown mapping
+    char *g = mmap(NULL, 4 * page, PROT_READ | PROT_WRITE,
+                   MAP_PRIVATE | MAP_ANONYMOUS | MAP_GROWSDOWN, -1, 0);
+    if (g == MAP_FAILED) {
+        perror("mmap growsdown");
+        return 1;
+    }
+    if (mprotect(g + 2 * page, page, PROT_READ | PROT_GROWSDOWN) < 0) {
+        perror("mprotect(PROT_GROWSDOWN)");
+        return 1;
+    }
+    if (writable(g) || writable(g + 2 * page) || !writable(g + 3 * page)) {
+        printf("fail: PROT_GROWSDOWN didn't reach the start of the mapping\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,120 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+static long pagesz;
+
+static int mapped(void *addr)
+{
+    unsigned char vec;
+    return mincore(addr, pagesz, &vec) == 0 || errno != ENOMEM;
+}
+
+static void fill(char *p, size_t len, char seed)
+{
+    for (size_t i = 0; i < len; i++)
+        p[i] = (char)(seed + i / pagesz);
+}
+
+static int check(char *p, size_t len, char seed)
+{
+    for (size_t i = 0; i < len; i += pagesz) {
+        if (p[i] != (char)(seed + i / pagesz))
+            return 0;
+    }
+    return 1;
+}
+
+int main()
+{
+    pagesz = sysconf(_SC_PAGESIZE);
+
+    // shrink in place; the fifth page is reserved for the blocking mapping below
+    char *p = mmap(NULL, 5 * pagesz, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    munmap(p + 4 * pagesz, pagesz);
+    fill(p, 4 * pagesz, 'a');
+    if (mremap(p, 4 * pagesz, 2 * pagesz, 0) != p || !check(p, 2 * pagesz, 'a') || mapped(p + 2 * pagesz)) {
+        printf("fail: shrink in place\n");
+        return 1;
+    }
+
+    // grow in place into the freed range
+    if (mremap(p, 2 * pagesz, 4 * pagesz, 0) != p || !check(p, 2 * pagesz, 'a')) {
+        printf("fail: grow in place\n");
+        return 1;
+    }
+    p[3 * pagesz] = 'z';
+
+    // growing without MREMAP_MAYMOVE fails when the next page is taken
+    char *block = mmap(p + 4 * pagesz, pagesz, PROT_READ, MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED, -1, 0);
+    if (block != p + 4 * pagesz) {
+        perror("mmap");
+        return 1;
+    }
+    if (mremap(p, 4 * pagesz, 8 * pagesz, 0) != MAP_FAILED || errno != ENOMEM) {
+        printf("fail: grow over a mapping didn't fail with ENOMEM\n");
+        return 1;
+    }
+
+    // MREMAP_MAYMOVE relocates and keeps the contents
+    fill(p, 4 * pagesz, 'A');
+    char *q = mremap(p, 4 * pagesz, 8 * pagesz, MREMAP_MAYMOVE);
+    if (q == MAP_FAILED || q == p || !check(q, 4 * pagesz, 'A') || mapped(p)) {
+        printf("fail: MREMAP_MAYMOVE\n");
+        return 1;
+    }
+    q[7 * pagesz] = 'x';
+
+    // MREMAP_FIXED moves to the given address, replacing what was there
+    char *dst = mmap(NULL, 8 * pagesz, PROT_READ, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    char *r = mremap(q, 8 * pagesz, 8 * pagesz, MREMAP_MAYMOVE | MREMAP_FIXED, dst);
+    if (r != dst || !check(r, 4 * pagesz, 'A') || r[7 * pagesz] != 'x' || mapped(q)) {
+        printf("fail: MREMAP_FIXED\n");
+        return 1;
+    }
+    r[0] = 'w';
+    if (mremap(r, pagesz, pagesz, MREMAP_FIXED, dst + 16 * pagesz) != MAP_FAILED || errno != EINVAL) {
+        printf("fail: MREMAP_FIXED without MREMAP_MAYMOVE didn't fail with EINVAL\n");
+        return 1;
+    }
+
+    // a moved file mapping keeps its file offset
+    char path[] = "/tmp/mremapXXXXXX";
+    int fd = mkstemp(path);
+    unlink(path);
+    char *buf = malloc(4 * pagesz);
+    fill(buf, 4 * pagesz, '0');
+    if (fd < 0 || write(fd, buf, 4 * pagesz) != 4 * pagesz) {
+        perror("write");
+        return 1;
+    }
+    char *f = mmap(NULL, 2 * pagesz, PROT_READ, MAP_SHARED, fd, pagesz);
+    char *g = mremap(f, 2 * pagesz, 3 * pagesz, MREMAP_MAYMOVE);
+    if (f == MAP_FAILED || g == MAP_FAILED || !check(g, 3 * pagesz, '1')) {
+        printf("fail: moved file mapping doesn't read the right offset\n");
+        return 1;
+    }
+
+    // the vdso and vvar can't be remapped
+    FILE *maps = fopen("/proc/self/maps", "r");
+    char line[512];
+    while (maps && fgets(line, sizeof(line), maps)) {
+        unsigned long start, end;
+        if (!strstr(line, "[vdso]") && !strstr(line, "[vvar]"))
+            continue;
+        if (sscanf(line, "%lx-%lx", &start, &end) != 2)
+            continue;
+        if (mremap((void *)start, end - start, end - start + pagesz, MREMAP_MAYMOVE) != MAP_FAILED) {
+            printf("fail: special mapping %s was remapped", line);
+            return 1;
+        }
+    }
+
+    printf("pass\n");
+    return 0;
+}
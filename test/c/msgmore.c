@@ -0,0 +1,93 @@
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <netinet/tcp.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+int main()
+{
+    int srv = socket(AF_INET, SOCK_STREAM, 0);
+    if (srv < 0) {
+        perror("socket");
+        return 1;
+    }
+
+    struct sockaddr_in addr;
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    socklen_t len = sizeof(addr);
+    if (bind(srv, (struct sockaddr *)&addr, sizeof(addr)) < 0 || listen(srv, 1) < 0 ||
+        getsockname(srv, (struct sockaddr *)&addr, &len) < 0) {
+        perror("bind/listen");
+        return 1;
+    }
+
+    int cli = socket(AF_INET, SOCK_STREAM, 0);
+    if (connect(cli, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        perror("connect");
+        return 1;
+    }
+
+    int conn = accept(srv, NULL, NULL);
+    if (conn < 0) {
+        perror("accept");
+        return 1;
+    }
+
+    // small chunks held back with MSG_MORE, flushed by the last send
+    const char *chunks[] = {"one ", "two ", "three ", "four"};
+    for (int i = 0; i < 4; i++) {
+        int flags = i < 3 ? MSG_MORE : 0;
+        if (send(cli, chunks[i], strlen(chunks[i]), flags) != (ssize_t)strlen(chunks[i])) {
+            perror("send");
+            return 1;
+        }
+    }
+
+    const char *want = "one two three four";
+    char buf[64];
+    size_t got = 0;
+    while (got < strlen(want)) {
+        ssize_t n = recv(conn, buf + got, sizeof(buf) - got, 0);
+        if (n <= 0) {
+            perror("recv");
+            return 1;
+        }
+        got += n;
+    }
+
+    if (got != strlen(want) || memcmp(buf, want, got) != 0) {
+        printf("fail: received %.*s\n", (int)got, buf);
+        return 1;
+    }
+
+    // MSG_MORE must not clear a TCP_CORK set by the application
+    int one = 1;
+    if (setsockopt(cli, IPPROTO_TCP, TCP_CORK, &one, sizeof(one)) < 0) {
+        perror("setsockopt");
+        return 1;
+    }
+
+    if (send(cli, "x", 1, MSG_MORE) != 1 || send(cli, "y", 1, 0) != 1) {
+        perror("send");
+        return 1;
+    }
+
+    int cork = 0;
+    len = sizeof(cork);
+    if (getsockopt(cli, IPPROTO_TCP, TCP_CORK, &cork, &len) < 0 || cork != 1) {
+        printf("fail: TCP_CORK is %d after MSG_MORE send\n", cork);
+        return 1;
+    }
+
+    close(conn);
+    close(cli);
+    close(srv);
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,89 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+// msync of a shared file mapping writes it back to the file, including a
+// range ending at a 2MB boundary, and the flags are validated.
+
+#define SIZE (4 << 20)
+
+int main()
+{
+    char path[] = "/tmp/msyncXXXXXX";
+    int fd = mkstemp(path);
+    if (fd < 0) {
+        perror("mkstemp");
+        return 1;
+    }
+    unlink(path);
+    if (ftruncate(fd, SIZE) < 0) {
+        perror("ftruncate");
+        return 1;
+    }
+
+    char *p = mmap(NULL, SIZE, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    if (p == MAP_FAILED) {
+        perror("mmap");
+        return 1;
+    }
+
+    long page = sysconf(_SC_PAGESIZE);
+    strcpy(p, "start");
+    strcpy(p + (2 << 20) - page, "boundary");
+
+    if (msync(p, SIZE, MS_SYNC) < 0) {
+        perror("msync");
+        return 1;
+    }
+    if (msync(p + (2 << 20) - page, page, MS_SYNC) < 0) {
+        perror("msync before the 2MB boundary");
+        return 1;
+    }
+    if (msync(p + page, page, MS_ASYNC | MS_INVALIDATE) < 0) {
+        perror("msync MS_INVALIDATE");
+        return 1;
+    }
+
+    char buf[16] = {0};
+    if (pread(fd, buf, 6, 0) != 6 || strcmp(buf, "start") != 0) {
+        printf("fail: the file doesn't have the data written through the mapping\n");
+        return 1;
+    }
+    if (pread(fd, buf, 9, (2 << 20) - page) != 9 || strcmp(buf, "boundary") != 0) {
+        printf("fail: the file doesn't have the data before the 2MB boundary\n");
+        return 1;
+    }
+
+    if (msync(p + 1, page, MS_SYNC) != -1 || errno != EINVAL) {
+        printf("fail: msync of an unaligned address\n");
+        return 1;
+    }
+    if (msync(p, page, MS_SYNC | MS_ASYNC) != -1 || errno != EINVAL) {
+        printf("fail: msync with both MS_SYNC and MS_ASYNC\n");
+        return 1;
+    }
+
+    // the data survives munmap
+    strcpy(p + page, "unmapped");
+    if (munmap(p, SIZE) < 0) {
+        perror("munmap");
+        return 1;
+    }
+    if (pread(fd, buf, 9, page) != 9 || strcmp(buf, "unmapped") != 0) {
+        printf("fail: the data written before munmap is lost\n");
+        return 1;
+    }
+
+    if (msync(p, page, MS_SYNC) != -1 || errno != ENOMEM) {
+        printf("fail: msync of an unmapped range\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,136 @@
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <errno.h>
+#include <linux/netlink.h>
+#include <linux/rtnetlink.h>
+#include <net/if.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+struct req {
+    struct nlmsghdr nh;
+    union {
+        struct ifinfomsg ifi;
+        struct ifaddrmsg ifa;
+    };
+    char attrs[64];
+};
+
+static char buf[65536];
+
+// dump sends a dump request of the given type and calls found for each reply
+// until NLMSG_DONE. It returns the number of replies or -1.
+static int dump(int fd, int type, int (*found)(struct nlmsghdr *))
+{
+    struct req r;
+    memset(&r, 0, sizeof(r));
+    r.nh.nlmsg_len = NLMSG_LENGTH(type == RTM_GETLINK ? sizeof(struct ifinfomsg) : sizeof(struct ifaddrmsg));
+    r.nh.nlmsg_type = type;
+    r.nh.nlmsg_flags = NLM_F_REQUEST | NLM_F_DUMP;
+    r.nh.nlmsg_seq = type;
+    r.ifa.ifa_family = AF_UNSPEC;
+    if (send(fd, &r, r.nh.nlmsg_len, 0) < 0) {
+        perror("send dump");
+        return -1;
+    }
+
+    int n = 0;
+    for (;;) {
+        ssize_t len = recv(fd, buf, sizeof(buf), 0);
+        if (len < 0) {
+            perror("recv");
+            return -1;
+        }
+        for (struct nlmsghdr *nh = (struct nlmsghdr *)buf; NLMSG_OK(nh, len); nh = NLMSG_NEXT(nh, len)) {
+            if (nh->nlmsg_type == NLMSG_DONE) {
+                return n;
+            }
+            if (nh->nlmsg_type == NLMSG_ERROR || !(nh->nlmsg_flags & NLM_F_MULTI) ||
+                nh->nlmsg_seq != (unsigned)type) {
+                printf("fail: unexpected reply type %d flags %x\n", nh->nlmsg_type, nh->nlmsg_flags);
+                return -1;
+            }
+            n += found(nh);
+        }
+    }
+}
+
+static int isLo(struct nlmsghdr *nh)
+{
+    struct ifinfomsg *ifi = NLMSG_DATA(nh);
+    int len = IFLA_PAYLOAD(nh);
+    for (struct rtattr *a = IFLA_RTA(ifi); RTA_OK(a, len); a = RTA_NEXT(a, len)) {
+        if (a->rta_type == IFLA_IFNAME && strcmp(RTA_DATA(a), "lo") == 0) {
+            return (ifi->ifi_flags & IFF_LOOPBACK) != 0;
+        }
+    }
+    return 0;
+}
+
+static int isLoopbackAddr(struct nlmsghdr *nh)
+{
+    struct ifaddrmsg *ifa = NLMSG_DATA(nh);
+    int len = IFA_PAYLOAD(nh);
+    if (ifa->ifa_family != AF_INET) {
+        return 0;
+    }
+    for (struct rtattr *a = IFA_RTA(ifa); RTA_OK(a, len); a = RTA_NEXT(a, len)) {
+        if (a->rta_type == IFA_ADDRESS && *(in_addr_t *)RTA_DATA(a) == htonl(INADDR_LOOPBACK)) {
+            return ifa->ifa_prefixlen == 8;
+        }
+    }
+    return 0;
+}
+
+int main()
+{
+    int fd = socket(AF_NETLINK, SOCK_RAW | SOCK_CLOEXEC, NETLINK_ROUTE);
+    struct sockaddr_nl sa = {.nl_family = AF_NETLINK};
+    if (fd < 0 || bind(fd, (struct sockaddr *)&sa, sizeof(sa)) < 0) {
+        perror("netlink socket");
+        return 1;
+    }
+
+    if (dump(fd, RTM_GETLINK, isLo) != 1) {
+        printf("fail: lo not in the link dump\n");
+        return 1;
+    }
+    if (dump(fd, RTM_GETADDR, isLoopbackAddr) != 1) {
+        printf("fail: 127.0.0.1/8 not in the address dump\n");
+        return 1;
+    }
+
+    // requests that change the configuration aren't allowed through
+    struct req r;
+    memset(&r, 0, sizeof(r));
+    r.nh.nlmsg_len = NLMSG_LENGTH(sizeof(struct ifaddrmsg)) + RTA_LENGTH(4);
+    r.nh.nlmsg_type = RTM_DELADDR;
+    r.nh.nlmsg_flags = NLM_F_REQUEST | NLM_F_ACK;
+    r.ifa.ifa_family = AF_INET;
+    r.ifa.ifa_prefixlen = 32;
+    r.ifa.ifa_index = if_nametoindex("lo");
+    struct rtattr *a = (struct rtattr *)r.attrs;
+    a->rta_type = IFA_LOCAL;
+    a->rta_len = RTA_LENGTH(4);
+    *(in_addr_t *)RTA_DATA(a) = inet_addr("192.0.2.1");
+    if (send(fd, &r, r.nh.nlmsg_len, 0) < 0) {
+        if (errno != EPERM) {
+            perror("send RTM_DELADDR");
+            return 1;
+        }
+    } else {
+        ssize_t len = recv(fd, buf, sizeof(buf), 0);
+        struct nlmsghdr *nh = (struct nlmsghdr *)buf;
+        if (len < (ssize_t)NLMSG_LENGTH(sizeof(struct nlmsgerr)) || nh->nlmsg_type != NLMSG_ERROR ||
+            ((struct nlmsgerr *)NLMSG_DATA(nh))->error == 0) {
+            printf("fail: RTM_DELADDR wasn't refused\n");
+            return 1;
+        }
+    }
+
+    close(fd);
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,133 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// With SA_NOCLDWAIT or SIG_IGN set for SIGCHLD, children are reaped when
+// they exit, and a wait blocked on them fails with ECHILD once the last one
+// is gone. The ignored disposition survives exec but SA_NOCLDWAIT doesn't.
+
+static void handler(int sig)
+{
+}
+
+// gone reports whether pid has no /proc entry, i.e. isn't a zombie.
+static int gone(pid_t pid)
+{
+    char path[32];
+    struct stat st;
+    snprintf(path, sizeof(path), "/proc/%d", pid);
+    for (int i = 0; i < 50; i++) {
+        if (stat(path, &st) < 0 && errno == ENOENT) {
+            return 1;
+        }
+        usleep(10000);
+    }
+    return 0;
+}
+
+// blockedWait forks children which exit after a delay and checks that a
+// wait started before they exit fails with ECHILD.
+static int blockedWait(const char *what)
+{
+    pid_t pids[3];
+    for (int i = 0; i < 3; i++) {
+        pids[i] = fork();
+        if (pids[i] == 0) {
+            usleep(100000 * (i + 1));
+            _exit(i);
+        }
+    }
+
+    int status;
+    if (wait(&status) != -1 || errno != ECHILD) {
+        printf("fail: %s: wait didn't fail with ECHILD\n", what);
+        return -1;
+    }
+    for (int i = 0; i < 3; i++) {
+        if (!gone(pids[i])) {
+            printf("fail: %s: child %d is a zombie\n", what, pids[i]);
+            return -1;
+        }
+    }
+    return 0;
+}
+
+int main(int argc, char **argv)
+{
+    if (argc > 1 && strcmp(argv[1], "child") == 0) {
+        // exec kept SIG_IGN and cleared the flags
+        struct sigaction old;
+        sigaction(SIGCHLD, NULL, &old);
+        if (old.sa_handler != SIG_IGN || (old.sa_flags & SA_NOCLDWAIT)) {
+            return 2;
+        }
+        return blockedWait("after exec") < 0 ? 1 : 0;
+    }
+
+    struct sigaction sa, old;
+    memset(&sa, 0, sizeof(sa));
+    sa.sa_handler = handler;
+    sa.sa_flags = SA_NOCLDWAIT | SA_RESTART;
+    sigaction(SIGCHLD, &sa, NULL);
+    sigaction(SIGCHLD, NULL, &old);
+    if (!(old.sa_flags & SA_NOCLDWAIT)) {
+        printf("fail: sigaction didn't report SA_NOCLDWAIT\n");
+        return 1;
+    }
+    if (blockedWait("SA_NOCLDWAIT") < 0) {
+        return 1;
+    }
+
+    // fork keeps the handler, and a waitpid for an SA_NOCLDWAIT child fails
+    // once it's gone
+    pid_t pid = fork();
+    if (pid == 0) {
+        sigaction(SIGCHLD, NULL, &old);
+        if (old.sa_handler != handler) {
+            _exit(3);
+        }
+        signal(SIGCHLD, SIG_IGN);
+        execl(argv[0], argv[0], "child", NULL);
+        _exit(127);
+    }
+    int status;
+    if (waitpid(pid, &status, 0) != -1 || errno != ECHILD) {
+        printf("fail: an SA_NOCLDWAIT child could be waited for\n");
+        return 1;
+    }
+
+    // an exec'd program keeps SIG_IGN but not SA_NOCLDWAIT
+    sa.sa_flags = SA_RESTART;
+    sigaction(SIGCHLD, &sa, NULL);
+    pid = fork();
+    if (pid == 0) {
+        sa.sa_handler = SIG_IGN;
+        sa.sa_flags = SA_NOCLDWAIT;
+        sigaction(SIGCHLD, &sa, NULL);
+        execl(argv[0], argv[0], "child", NULL);
+        _exit(127);
+    }
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fail: the exec'd program exited with %x\n", status);
+        return 1;
+    }
+
+    // without either, an exited child stays a zombie until it's waited for
+    pid = fork();
+    if (pid == 0) {
+        _exit(5);
+    }
+    usleep(100000);
+    if (waitpid(pid, &status, WNOHANG) != pid || WEXITSTATUS(status) != 5) {
+        printf("fail: the child wasn't left to be waited for\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,71 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+int main()
+{
+    mkdir("/tmp/opath", 0755);
+    int fd = open("/tmp/opath/file", O_CREAT | O_RDWR | O_TRUNC, 0600);
+    if (fd < 0 || write(fd, "hello", 5) != 5) {
+        perror("create");
+        return 1;
+    }
+    close(fd);
+
+    // O_PATH ignores O_TRUNC and needs no permission on the file itself
+    chmod("/tmp/opath/file", 0);
+    fd = open("/tmp/opath/file", O_PATH | O_TRUNC);
+    if (fd < 0) {
+        perror("open O_PATH");
+        return 1;
+    }
+
+    char buf[8];
+    if (read(fd, buf, sizeof(buf)) != -1 || errno != EBADF) {
+        printf("fail: read on O_PATH fd didn't return EBADF\n");
+        return 1;
+    }
+    if (write(fd, "x", 1) != -1 || errno != EBADF) {
+        printf("fail: write on O_PATH fd didn't return EBADF\n");
+        return 1;
+    }
+
+    struct stat st;
+    if (fstat(fd, &st) < 0 || st.st_size != 5) {
+        printf("fail: fstat on O_PATH fd\n");
+        return 1;
+    }
+    if (!(fcntl(fd, F_GETFL) & O_PATH)) {
+        printf("fail: F_GETFL doesn't report O_PATH\n");
+        return 1;
+    }
+    close(fd);
+
+    // an O_PATH directory works as the base of a relative lookup
+    int dir = open("/tmp/opath", O_PATH | O_DIRECTORY);
+    if (dir < 0) {
+        perror("open dir O_PATH");
+        return 1;
+    }
+    if (fstatat(dir, "file", &st, 0) < 0 || st.st_size != 5) {
+        printf("fail: fstatat relative to O_PATH dirfd\n");
+        return 1;
+    }
+    chmod("/tmp/opath/file", 0600);
+    fd = openat(dir, "file", O_RDONLY);
+    if (fd < 0 || read(fd, buf, sizeof(buf)) != 5) {
+        printf("fail: openat relative to O_PATH dirfd\n");
+        return 1;
+    }
+    close(fd);
+    close(dir);
+
+    unlink("/tmp/opath/file");
+    rmdir("/tmp/opath");
+    printf("pass\n");
+    return 0;
+}
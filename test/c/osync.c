@@ -0,0 +1,67 @@
+#define _GNU_SOURCE
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+
+// writeAndCheck writes through fd with write, pwrite and an appending fd and
+// checks another descriptor sees the data.
+static int writeAndCheck(const char *path, int flags, const char *name)
+{
+    int fd = open(path, O_RDWR | O_CREAT | O_TRUNC | flags, 0644);
+    if (fd < 0) {
+        perror("open");
+        return 1;
+    }
+
+    int got = fcntl(fd, F_GETFL);
+    if ((got & flags) != flags) {
+        printf("fail: %s F_GETFL returned %x\n", name, got);
+        return 1;
+    }
+
+    if (write(fd, "hello", 5) != 5 || pwrite(fd, "J", 1, 0) != 1) {
+        perror("write");
+        return 1;
+    }
+
+    int afd = open(path, O_WRONLY | O_APPEND | flags);
+    if (write(afd, " world", 6) != 6) {
+        perror("append");
+        return 1;
+    }
+    close(afd);
+
+    char buf[32] = {0};
+    int rfd = open(path, O_RDONLY);
+    read(rfd, buf, sizeof(buf) - 1);
+    close(rfd);
+    if (strcmp(buf, "Jello world") != 0) {
+        printf("fail: %s file holds %s\n", name, buf);
+        return 1;
+    }
+
+    close(fd);
+    return 0;
+}
+
+int main()
+{
+    const char *path = "/tmp/osync_test";
+
+    if (writeAndCheck(path, O_DSYNC, "O_DSYNC") || writeAndCheck(path, O_SYNC, "O_SYNC")) {
+        return 1;
+    }
+
+    // O_DSYNC alone doesn't imply O_SYNC
+    int fd = open(path, O_RDWR | O_DSYNC);
+    if ((fcntl(fd, F_GETFL) & O_SYNC) == O_SYNC) {
+        printf("fail: O_DSYNC reported as O_SYNC\n");
+        return 1;
+    }
+    close(fd);
+
+    unlink(path);
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,189 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+static int checkPeer(int fd, pid_t pid, const char *what)
+{
+    struct ucred cred;
+    socklen_t len = sizeof(cred);
+    if (getsockopt(fd, SOL_SOCKET, SO_PEERCRED, &cred, &len) < 0) {
+        perror("getsockopt(SO_PEERCRED)");
+        return 1;
+    }
+
+    if (len != sizeof(cred) || cred.pid != pid || cred.uid != getuid() || cred.gid != getgid()) {
+        printf("fail: %s peer is pid %d uid %d gid %d, expected pid %d\n",
+               what, cred.pid, cred.uid, cred.gid, pid);
+        return 1;
+    }
+    return 0;
+}
+
+// recvCreds reads one message from fd and returns the SCM_CREDENTIALS pid in
+// *pid, or -1 if the message carried no credentials.
+static int recvCreds(int fd, pid_t *pid)
+{
+    char data[16];
+    struct iovec iov = {data, sizeof(data)};
+    char control[CMSG_SPACE(sizeof(struct ucred))];
+    struct msghdr msg = {0};
+    msg.msg_iov = &iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control;
+    msg.msg_controllen = sizeof(control);
+
+    if (recvmsg(fd, &msg, 0) <= 0) {
+        perror("recvmsg");
+        return 1;
+    }
+
+    *pid = -1;
+    struct cmsghdr *cmsg = CMSG_FIRSTHDR(&msg);
+    if (cmsg != NULL && cmsg->cmsg_level == SOL_SOCKET && cmsg->cmsg_type == SCM_CREDENTIALS) {
+        struct ucred cred;
+        memcpy(&cred, CMSG_DATA(cmsg), sizeof(cred));
+        *pid = cred.pid;
+    }
+    return 0;
+}
+
+static int pairTest(void)
+{
+    int sv[2];
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, sv) < 0) {
+        perror("socketpair");
+        return 1;
+    }
+
+    if (checkPeer(sv[0], getpid(), "socketpair")) {
+        return 1;
+    }
+
+    // without SO_PASSCRED no credentials are attached
+    pid_t pid;
+    if (write(sv[1], "a", 1) != 1 || recvCreds(sv[0], &pid)) {
+        return 1;
+    }
+    if (pid != -1) {
+        printf("fail: got credentials without SO_PASSCRED\n");
+        return 1;
+    }
+
+    int one = 1;
+    if (setsockopt(sv[0], SOL_SOCKET, SO_PASSCRED, &one, sizeof(one)) < 0) {
+        perror("setsockopt(SO_PASSCRED)");
+        return 1;
+    }
+
+    pid_t child = fork();
+    if (child == 0) {
+        _exit(write(sv[1], "b", 1) != 1);
+    }
+    waitpid(child, NULL, 0);
+
+    if (recvCreds(sv[0], &pid)) {
+        return 1;
+    }
+    if (pid != child) {
+        printf("fail: SCM_CREDENTIALS pid %d, expected %d\n", pid, child);
+        return 1;
+    }
+
+    // an unprivileged sender can't claim to be someone else
+    if (geteuid() != 0) {
+        struct ucred cred = {child, getuid(), getgid()};
+        char control[CMSG_SPACE(sizeof(cred))];
+        struct iovec iov = {"c", 1};
+        struct msghdr msg = {0};
+        msg.msg_iov = &iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control;
+        msg.msg_controllen = sizeof(control);
+        struct cmsghdr *cmsg = CMSG_FIRSTHDR(&msg);
+        cmsg->cmsg_level = SOL_SOCKET;
+        cmsg->cmsg_type = SCM_CREDENTIALS;
+        cmsg->cmsg_len = CMSG_LEN(sizeof(cred));
+        memcpy(CMSG_DATA(cmsg), &cred, sizeof(cred));
+        if (sendmsg(sv[1], &msg, 0) != -1 || errno != EPERM) {
+            printf("fail: spoofed pid was accepted\n");
+            return 1;
+        }
+    }
+
+    close(sv[0]);
+    close(sv[1]);
+    return 0;
+}
+
+static int connectTest(void)
+{
+    struct sockaddr_un addr = {.sun_family = AF_UNIX};
+    snprintf(addr.sun_path, sizeof(addr.sun_path), "/tmp/peercred.%d", getpid());
+    unlink(addr.sun_path);
+
+    int srv = socket(AF_UNIX, SOCK_STREAM, 0);
+    if (srv < 0 || bind(srv, (struct sockaddr *)&addr, sizeof(addr)) < 0 || listen(srv, 1) < 0) {
+        perror("listen");
+        return 1;
+    }
+
+    struct ucred cred;
+    socklen_t len = sizeof(cred);
+    int cli = socket(AF_UNIX, SOCK_STREAM, 0);
+    if (getsockopt(cli, SOL_SOCKET, SO_PEERCRED, &cred, &len) < 0 || cred.pid != 0) {
+        printf("fail: unconnected socket reported pid %d\n", cred.pid);
+        return 1;
+    }
+    close(cli);
+
+    pid_t parent = getpid();
+    pid_t child = fork();
+    if (child == 0) {
+        close(srv);
+        int fd = socket(AF_UNIX, SOCK_STREAM, 0);
+        if (fd < 0 || connect(fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+            _exit(1);
+        }
+        // the client sees the task which called listen
+        int failed = checkPeer(fd, parent, "client");
+        char c;
+        read(fd, &c, 1);
+        _exit(failed);
+    }
+
+    int conn = accept(srv, NULL, NULL);
+    if (conn < 0) {
+        perror("accept");
+        return 1;
+    }
+
+    // the accepted socket sees the task which called connect
+    int failed = checkPeer(conn, child, "server");
+    close(conn);
+
+    int status;
+    waitpid(child, &status, 0);
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fail: client failed\n");
+        failed = 1;
+    }
+
+    close(srv);
+    unlink(addr.sun_path);
+    return failed;
+}
+
+int main()
+{
+    if (pairTest() || connectTest()) {
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,114 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/personality.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// personality(2) stores any persona verbatim, it's inherited across fork and
+// exec, and READ_IMPLIES_EXEC makes readable mappings executable.
+
+// perms returns the permissions /proc/self/maps shows for addr.
+static int perms(void *addr, char *out)
+{
+    FILE *f = fopen("/proc/self/maps", "r");
+    if (f == NULL) {
+        perror("fopen");
+        return -1;
+    }
+
+    char line[512];
+    while (fgets(line, sizeof(line), f)) {
+        unsigned long start, end;
+        char p[5];
+        if (sscanf(line, "%lx-%lx %4s", &start, &end, p) == 3 && start <= (unsigned long)addr &&
+            (unsigned long)addr < end) {
+            fclose(f);
+            strcpy(out, p);
+            return 0;
+        }
+    }
+    fclose(f);
+    printf("fail: %p isn't in /proc/self/maps\n", addr);
+    return -1;
+}
+
+int main(int argc, char **argv)
+{
+    if (argc > 1 && strcmp(argv[1], "child") == 0) {
+        printf("%x", personality(0xffffffff));
+        return 0;
+    }
+
+    int orig = personality(0xffffffff);
+    if (orig < 0) {
+        perror("personality");
+        return 1;
+    }
+
+    // unknown bits are stored and returned verbatim
+    int persona = orig | 0x10000000 | ADDR_NO_RANDOMIZE;
+    if (personality(persona) != orig || personality(0xffffffff) != persona) {
+        printf("fail: the persona %x wasn't stored\n", persona);
+        return 1;
+    }
+
+    // the persona survives fork and exec
+    int p[2];
+    if (pipe(p) < 0) {
+        perror("pipe");
+        return 1;
+    }
+    pid_t pid = fork();
+    if (pid == 0) {
+        dup2(p[1], 1);
+        execl(argv[0], argv[0], "child", NULL);
+        _exit(127);
+    }
+    close(p[1]);
+    char buf[32] = {0};
+    read(p[0], buf, sizeof(buf) - 1);
+    waitpid(pid, NULL, 0);
+    if (strtol(buf, NULL, 16) != persona) {
+        printf("fail: the exec'd child has persona %s, want %x\n", buf, persona);
+        return 1;
+    }
+
+    // READ_IMPLIES_EXEC
+    char perm[5];
+    if (personality(orig | READ_IMPLIES_EXEC) < 0) {
+        perror("personality");
+        return 1;
+    }
+    void *a = mmap(NULL, 4096, PROT_READ, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (a == MAP_FAILED || perms(a, perm) < 0) {
+        return 1;
+    }
+    if (strcmp(perm, "r-xp") != 0) {
+        printf("fail: a PROT_READ mapping is %s with READ_IMPLIES_EXEC\n", perm);
+        return 1;
+    }
+    void *b = mmap(NULL, 4096, PROT_NONE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (b == MAP_FAILED || mprotect(b, 4096, PROT_READ | PROT_WRITE) < 0 || perms(b, perm) < 0) {
+        return 1;
+    }
+    if (strcmp(perm, "rwxp") != 0) {
+        printf("fail: mprotect PROT_READ|PROT_WRITE gave %s with READ_IMPLIES_EXEC\n", perm);
+        return 1;
+    }
+
+    personality(orig);
+    void *c = mmap(NULL, 4096, PROT_READ, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (c == MAP_FAILED || perms(c, perm) < 0) {
+        return 1;
+    }
+    if (strcmp(perm, "r--p") != 0) {
+        printf("fail: a PROT_READ mapping is %s without READ_IMPLIES_EXEC\n", perm);
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,113 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <signal.h>
+#include <stdio.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// A pidfd refers to a process: it becomes readable when the process exits
+// and pidfd_send_signal sends signals to it until then.
+
+static int pidfdOpen(pid_t pid, unsigned int flags)
+{
+    return syscall(SYS_pidfd_open, pid, flags);
+}
+
+static int pidfdSendSignal(int pidfd, int sig, siginfo_t *info, unsigned int flags)
+{
+    return syscall(SYS_pidfd_send_signal, pidfd, sig, info, flags);
+}
+
+int main()
+{
+    // poll waits for the exit of the process
+    pid_t pid = fork();
+    if (pid == 0) {
+        usleep(200 * 1000);
+        _exit(3);
+    }
+    int fd = pidfdOpen(pid, 0);
+    if (fd < 0) {
+        perror("pidfd_open");
+        return 1;
+    }
+    if (!(fcntl(fd, F_GETFD) & FD_CLOEXEC)) {
+        printf("fail: the pidfd isn't close-on-exec\n");
+        return 1;
+    }
+    struct pollfd pfd = {.fd = fd, .events = POLLIN};
+    if (poll(&pfd, 1, 0) != 0) {
+        printf("fail: the pidfd is readable before the exit\n");
+        return 1;
+    }
+    if (poll(&pfd, 1, 5000) != 1 || !(pfd.revents & POLLIN)) {
+        printf("fail: the pidfd didn't become readable on exit\n");
+        return 1;
+    }
+    int status;
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 3) {
+        printf("fail: the child exit status\n");
+        return 1;
+    }
+    if (poll(&pfd, 1, 0) != 1) {
+        printf("fail: the pidfd of a reaped process isn't readable\n");
+        return 1;
+    }
+    if (pidfdSendSignal(fd, SIGTERM, NULL, 0) != -1 || errno != ESRCH) {
+        printf("fail: signaled an exited process\n");
+        return 1;
+    }
+    close(fd);
+
+    // signal a live process through the pidfd
+    pid = fork();
+    if (pid == 0) {
+        pause();
+        _exit(0);
+    }
+    fd = pidfdOpen(pid, 0);
+    if (fd < 0) {
+        perror("pidfd_open");
+        return 1;
+    }
+    if (pidfdSendSignal(fd, 0, NULL, 0) != 0) {
+        perror("pidfd_send_signal 0");
+        return 1;
+    }
+    if (pidfdSendSignal(fd, SIGKILL, NULL, 0x80) != -1 || errno != EINVAL) {
+        printf("fail: pidfd_send_signal with flags\n");
+        return 1;
+    }
+    siginfo_t info = {.si_signo = SIGKILL, .si_code = SI_USER};
+    if (pidfdSendSignal(fd, SIGKILL, &info, 0) != -1 || errno != EPERM) {
+        printf("fail: pidfd_send_signal with a kernel si_code\n");
+        return 1;
+    }
+    if (pidfdSendSignal(fd, SIGKILL, NULL, 0) != 0) {
+        perror("pidfd_send_signal");
+        return 1;
+    }
+    if (waitpid(pid, &status, 0) != pid || !WIFSIGNALED(status) || WTERMSIG(status) != SIGKILL) {
+        printf("fail: the child wasn't killed through the pidfd\n");
+        return 1;
+    }
+    close(fd);
+
+    // a pidfd only refers to processes
+    int p[2];
+    pipe(p);
+    if (pidfdSendSignal(p[0], SIGKILL, NULL, 0) != -1 || errno != EBADF) {
+        printf("fail: pidfd_send_signal on a pipe\n");
+        return 1;
+    }
+    if (pidfdOpen(getpid(), 1) != -1 || errno != EINVAL) {
+        printf("fail: pidfd_open with an invalid flag\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,70 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdlib.h>
+#include <sched.h>
+#include <signal.h>
+#include <unistd.h>
+#include <sys/syscall.h>
+#include <sys/types.h>
+#include <sys/wait.h>
+
+#define STACK_SIZE (1024 * 1024)
+
+static int child(void *arg)
+{
+    pid_t pid = getpid();
+    pid_t ppid = getppid();
+    pid_t tid = syscall(SYS_gettid);
+
+    printf("child: getpid = %d, getppid = %d, gettid = %d\n", pid, ppid, tid);
+
+    // the first process in a new pid namespace is its init
+    if (pid != 1) {
+        printf("fail: expected getpid 1 in the new namespace, got %d\n", pid);
+        return 1;
+    }
+
+    // the parent lives in the ancestor namespace and is not visible
+    if (ppid != 0) {
+        printf("fail: expected getppid 0 at the namespace root, got %d\n", ppid);
+        return 1;
+    }
+
+    if (tid != pid) {
+        printf("fail: expected gettid %d, got %d\n", pid, tid);
+        return 1;
+    }
+
+    return 0;
+}
+
+int main()
+{
+    char *stack = malloc(STACK_SIZE);
+    if (stack == NULL) {
+        perror("malloc");
+        return 1;
+    }
+
+    pid_t pid = clone(child, stack + STACK_SIZE, CLONE_NEWPID | SIGCHLD, NULL);
+    if (pid < 0) {
+        perror("clone");
+        return 1;
+    }
+
+    printf("parent: getpid = %d, child pid = %d\n", getpid(), pid);
+
+    int status;
+    if (waitpid(pid, &status, 0) < 0) {
+        perror("waitpid");
+        return 1;
+    }
+
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fail\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,134 @@
+#define _GNU_SOURCE
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+int main()
+{
+    int fds[2];
+    if (pipe2(fds, O_DIRECT) < 0) {
+        perror("pipe2");
+        return 1;
+    }
+
+    if ((fcntl(fds[1], F_GETFL) & O_DIRECT) == 0) {
+        printf("fail: O_DIRECT not reported by F_GETFL\n");
+        return 1;
+    }
+
+    // each write is a separate packet
+    if (write(fds[1], "hello", 5) != 5 || write(fds[1], "world", 5) != 5) {
+        perror("write");
+        return 1;
+    }
+
+    char buf[64];
+    int n = read(fds[0], buf, sizeof(buf));
+    if (n != 5 || memcmp(buf, "hello", 5) != 0) {
+        printf("fail: first read returned %d bytes\n", n);
+        return 1;
+    }
+
+    // a short read truncates the packet and discards the rest
+    n = read(fds[0], buf, 3);
+    if (n != 3 || memcmp(buf, "wor", 3) != 0) {
+        printf("fail: short read returned %d bytes\n", n);
+        return 1;
+    }
+
+    if (fcntl(fds[0], F_SETFL, O_NONBLOCK) < 0) {
+        perror("fcntl");
+        return 1;
+    }
+
+    n = read(fds[0], buf, sizeof(buf));
+    if (n != -1) {
+        printf("fail: packet remainder was not discarded, read %d bytes\n", n);
+        return 1;
+    }
+
+    // every packet takes a slot, one per page of the pipe's size, however
+    // small it is
+    if (fcntl(fds[1], F_SETFL, O_DIRECT | O_NONBLOCK) < 0) {
+        perror("fcntl");
+        return 1;
+    }
+
+    int slots = fcntl(fds[1], F_GETPIPE_SZ) / 4096;
+    int packets = 0;
+    while (packets <= slots && write(fds[1], "x", 1) == 1) {
+        packets++;
+    }
+
+    if (packets != slots) {
+        printf("fail: queued %d one byte packets, want %d\n", packets, slots);
+        return 1;
+    }
+
+    close(fds[0]);
+    close(fds[1]);
+
+    // splice takes part of a packet and leaves the rest of it queued as a
+    // packet
+    if (pipe2(fds, O_DIRECT) < 0) {
+        perror("pipe2");
+        return 1;
+    }
+
+    if (write(fds[1], "abcdefgh", 8) != 8 || write(fds[1], "ij", 2) != 2) {
+        perror("write");
+        return 1;
+    }
+
+    char path[] = "/tmp/pipedirectXXXXXX";
+    int file = mkstemp(path);
+    if (file < 0) {
+        perror("mkstemp");
+        return 1;
+    }
+    unlink(path);
+
+    n = splice(fds[0], NULL, file, NULL, 3, 0);
+    if (n != 3) {
+        printf("fail: splice from a packet pipe returned %d\n", n);
+        return 1;
+    }
+
+    n = read(fds[0], buf, sizeof(buf));
+    if (n != 5 || memcmp(buf, "defgh", 5) != 0) {
+        printf("fail: read after a partial splice returned %d bytes\n", n);
+        return 1;
+    }
+
+    n = read(fds[0], buf, sizeof(buf));
+    if (n != 2 || memcmp(buf, "ij", 2) != 0) {
+        printf("fail: the next packet read returned %d bytes\n", n);
+        return 1;
+    }
+
+    close(file);
+    close(fds[0]);
+    close(fds[1]);
+
+    // normal pipes stay in stream mode
+    if (pipe(fds) < 0) {
+        perror("pipe");
+        return 1;
+    }
+
+    if (write(fds[1], "hello", 5) != 5 || write(fds[1], "world", 5) != 5) {
+        perror("write");
+        return 1;
+    }
+
+    n = read(fds[0], buf, sizeof(buf));
+    if (n != 10 || memcmp(buf, "helloworld", 10) != 0) {
+        printf("fail: stream read returned %d bytes\n", n);
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
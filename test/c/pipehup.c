@@ -0,0 +1,128 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/epoll.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// readerHup checks that a reader blocked in poll(2) is woken with POLLHUP once
+// the last writer goes away, and that queued data is still reported as POLLIN.
+static int readerHup(void)
+{
+    int fds[2];
+    if (pipe(fds) < 0) {
+        perror("pipe");
+        return 1;
+    }
+
+    if (write(fds[1], "x", 1) != 1) {
+        perror("write");
+        return 1;
+    }
+
+    struct pollfd pfd = {fds[0], POLLIN, 0};
+    if (poll(&pfd, 1, 0) != 1 || pfd.revents != POLLIN) {
+        printf("fail: expected POLLIN with data, got 0x%x\n", pfd.revents);
+        return 1;
+    }
+
+    pid_t pid = fork();
+    if (pid == 0) {
+        close(fds[0]);
+        usleep(100 * 1000);
+        _exit(0);
+    }
+    close(fds[1]);
+
+    char c;
+    if (read(fds[0], &c, 1) != 1) {
+        perror("read");
+        return 1;
+    }
+
+    // the child still holds the write end, so this blocks until it exits
+    pfd.revents = 0;
+    if (poll(&pfd, 1, 5000) != 1 || pfd.revents != POLLHUP) {
+        printf("fail: expected POLLHUP after writer close, got 0x%x\n", pfd.revents);
+        return 1;
+    }
+
+    if (read(fds[0], &c, 1) != 0) {
+        printf("fail: expected EOF\n");
+        return 1;
+    }
+
+    waitpid(pid, NULL, 0);
+    close(fds[0]);
+    return 0;
+}
+
+// writerErr checks that a writer blocked on a full pipe in epoll_wait(2) is
+// woken with EPOLLERR once the last reader goes away.
+static int writerErr(void)
+{
+    int fds[2];
+    if (pipe2(fds, O_NONBLOCK) < 0) {
+        perror("pipe2");
+        return 1;
+    }
+
+    char buf[4096];
+    memset(buf, 'w', sizeof(buf));
+    while (write(fds[1], buf, sizeof(buf)) > 0) {
+    }
+    if (errno != EAGAIN) {
+        perror("fill");
+        return 1;
+    }
+
+    int epfd = epoll_create1(0);
+    struct epoll_event ev = {.events = EPOLLOUT, .data.fd = fds[1]};
+    if (epfd < 0 || epoll_ctl(epfd, EPOLL_CTL_ADD, fds[1], &ev) < 0) {
+        perror("epoll");
+        return 1;
+    }
+
+    if (epoll_wait(epfd, &ev, 1, 0) != 0) {
+        printf("fail: full pipe reported writable\n");
+        return 1;
+    }
+
+    pid_t pid = fork();
+    if (pid == 0) {
+        close(fds[1]);
+        usleep(100 * 1000);
+        _exit(0);
+    }
+    close(fds[0]);
+
+    if (epoll_wait(epfd, &ev, 1, 5000) != 1 || !(ev.events & EPOLLERR)) {
+        printf("fail: expected EPOLLERR after reader close, got 0x%x\n", ev.events);
+        return 1;
+    }
+
+    signal(SIGPIPE, SIG_IGN);
+    if (write(fds[1], "x", 1) != -1 || errno != EPIPE) {
+        printf("fail: expected EPIPE\n");
+        return 1;
+    }
+
+    waitpid(pid, NULL, 0);
+    close(fds[1]);
+    close(epfd);
+    return 0;
+}
+
+int main()
+{
+    if (readerHup() || writerErr()) {
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,75 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#define PAGE 4096
+
+int main()
+{
+    int fds[2];
+    if (pipe(fds) < 0) {
+        perror("pipe");
+        return 1;
+    }
+
+    int size = fcntl(fds[0], F_GETPIPE_SZ);
+    printf("default pipe size = %d\n", size);
+    if (size <= 0) {
+        printf("fail: F_GETPIPE_SZ returned %d\n", size);
+        return 1;
+    }
+
+    // queue some data which must survive the resize
+    char buf[3 * PAGE];
+    memset(buf, 'q', sizeof(buf));
+    if (write(fds[1], buf, sizeof(buf)) != sizeof(buf)) {
+        perror("write");
+        return 1;
+    }
+
+    // can't shrink below what is queued
+    if (fcntl(fds[1], F_SETPIPE_SZ, PAGE) != -1 || errno != EBUSY) {
+        printf("fail: expected EBUSY when shrinking below queued data\n");
+        return 1;
+    }
+
+    int newSize = fcntl(fds[1], F_SETPIPE_SZ, 64 * PAGE);
+    if (newSize != 64 * PAGE || fcntl(fds[0], F_GETPIPE_SZ) != 64 * PAGE) {
+        printf("fail: grow returned %d\n", newSize);
+        return 1;
+    }
+
+    char out[sizeof(buf)];
+    if (read(fds[0], out, sizeof(out)) != sizeof(out) || memcmp(buf, out, sizeof(out)) != 0) {
+        printf("fail: queued data lost after resize\n");
+        return 1;
+    }
+
+    // sizes are rounded up to at least a page
+    newSize = fcntl(fds[1], F_SETPIPE_SZ, 1);
+    if (newSize != PAGE) {
+        printf("fail: expected size %d, got %d\n", PAGE, newSize);
+        return 1;
+    }
+
+    FILE *f = fopen("/proc/sys/fs/pipe-max-size", "r");
+    if (f == NULL) {
+        perror("fopen");
+        return 1;
+    }
+
+    long max = 0;
+    if (fscanf(f, "%ld", &max) != 1 || max < PAGE) {
+        printf("fail: bad pipe-max-size %ld\n", max);
+        return 1;
+    }
+    fclose(f);
+    printf("pipe-max-size = %ld\n", max);
+
+    printf("pass\n");
+    return 0;
+}
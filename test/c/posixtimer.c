@@ -0,0 +1,165 @@
+#define _GNU_SOURCE
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+
+static volatile sig_atomic_t fired = 0;
+
+static void handler(int sig)
+{
+    fired++;
+}
+
+static int periodic(void)
+{
+    sigset_t set;
+    sigemptyset(&set);
+    sigaddset(&set, SIGUSR1);
+    sigprocmask(SIG_BLOCK, &set, NULL);
+
+    struct sigevent sev;
+    memset(&sev, 0, sizeof(sev));
+    sev.sigev_notify = SIGEV_SIGNAL;
+    sev.sigev_signo = SIGUSR1;
+    sev.sigev_value.sival_int = 42;
+
+    timer_t id;
+    if (timer_create(CLOCK_MONOTONIC, &sev, &id) < 0) {
+        perror("timer_create");
+        return 1;
+    }
+
+    struct itimerspec its = {
+        .it_interval = {.tv_sec = 0, .tv_nsec = 10 * 1000 * 1000},
+        .it_value = {.tv_sec = 0, .tv_nsec = 10 * 1000 * 1000},
+    };
+    if (timer_settime(id, 0, &its, NULL) < 0) {
+        perror("timer_settime");
+        return 1;
+    }
+
+    struct itimerspec cur;
+    if (timer_gettime(id, &cur) < 0 || cur.it_interval.tv_nsec != its.it_interval.tv_nsec) {
+        printf("fail: timer_gettime interval %ld\n", cur.it_interval.tv_nsec);
+        return 1;
+    }
+
+    // the signal stays pending while blocked, so further expiries are overruns
+    usleep(100 * 1000);
+
+    siginfo_t si;
+    if (sigwaitinfo(&set, &si) != SIGUSR1) {
+        perror("sigwaitinfo");
+        return 1;
+    }
+
+    if (si.si_code != SI_TIMER || si.si_value.sival_int != 42) {
+        printf("fail: si_code %d sival %d\n", si.si_code, si.si_value.sival_int);
+        return 1;
+    }
+
+    int overrun = timer_getoverrun(id);
+    printf("si_overrun %d timer_getoverrun %d\n", si.si_overrun, overrun);
+    if (si.si_overrun < 1 || overrun != si.si_overrun) {
+        printf("fail: expected overruns while the signal was pending\n");
+        return 1;
+    }
+
+    // it keeps firing periodically
+    if (sigwaitinfo(&set, &si) != SIGUSR1) {
+        perror("sigwaitinfo");
+        return 1;
+    }
+
+    timer_delete(id);
+    sigprocmask(SIG_UNBLOCK, &set, NULL);
+    return 0;
+}
+
+static int defaultSigevent(void)
+{
+    signal(SIGALRM, handler);
+
+    // without a sigevent the timer sends SIGALRM to the process
+    timer_t id;
+    if (timer_create(CLOCK_REALTIME, NULL, &id) < 0) {
+        perror("timer_create");
+        return 1;
+    }
+
+    struct itimerspec its = {
+        .it_value = {.tv_sec = 0, .tv_nsec = 10 * 1000 * 1000},
+    };
+    if (timer_settime(id, 0, &its, NULL) < 0) {
+        perror("timer_settime");
+        return 1;
+    }
+
+    for (int i = 0; i < 100 && !fired; i++) {
+        usleep(10 * 1000);
+    }
+
+    if (!fired) {
+        printf("fail: SIGALRM not delivered\n");
+        return 1;
+    }
+
+    timer_delete(id);
+    return 0;
+}
+
+static int cpuTimer(void)
+{
+    sigset_t set;
+    sigemptyset(&set);
+    sigaddset(&set, SIGUSR2);
+    sigprocmask(SIG_BLOCK, &set, NULL);
+
+    struct sigevent sev;
+    memset(&sev, 0, sizeof(sev));
+    sev.sigev_notify = SIGEV_SIGNAL;
+    sev.sigev_signo = SIGUSR2;
+
+    timer_t id;
+    if (timer_create(CLOCK_PROCESS_CPUTIME_ID, &sev, &id) < 0) {
+        perror("timer_create");
+        return 1;
+    }
+
+    struct itimerspec its = {
+        .it_value = {.tv_sec = 0, .tv_nsec = 20 * 1000 * 1000},
+    };
+    if (timer_settime(id, 0, &its, NULL) < 0) {
+        perror("timer_settime");
+        return 1;
+    }
+
+    // burn cpu until the timer expires
+    struct timespec start, now;
+    clock_gettime(CLOCK_MONOTONIC, &start);
+    sigset_t pending;
+    do {
+        sigpending(&pending);
+        clock_gettime(CLOCK_MONOTONIC, &now);
+    } while (!sigismember(&pending, SIGUSR2) && now.tv_sec - start.tv_sec < 5);
+
+    if (!sigismember(&pending, SIGUSR2)) {
+        printf("fail: CLOCK_PROCESS_CPUTIME_ID timer didn't fire\n");
+        return 1;
+    }
+
+    timer_delete(id);
+    return 0;
+}
+
+int main()
+{
+    if (periodic() || defaultSigevent() || cpuTimer()) {
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,115 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/uio.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// process_vm_readv/process_vm_writev copy between the memory of two
+// processes, stopping at the first remote iovec that faults, and mincore
+// reports the residency of mapped pages.
+
+static char data[64] = "hello from the child";
+
+int main()
+{
+    long page = sysconf(_SC_PAGESIZE);
+
+    // mincore
+    char *m = mmap(NULL, 2 * page, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (m == MAP_FAILED) {
+        perror("mmap");
+        return 1;
+    }
+    m[0] = 1;
+    unsigned char vec[2];
+    if (mincore(m, 2 * page, vec) < 0) {
+        perror("mincore");
+        return 1;
+    }
+    if (!(vec[0] & 1)) {
+        printf("fail: a touched page isn't resident\n");
+        return 1;
+    }
+    munmap(m + page, page);
+    if (mincore(m, 2 * page, vec) != -1 || errno != ENOMEM) {
+        printf("fail: mincore of an unmapped range\n");
+        return 1;
+    }
+    if (mincore(m + 1, page, vec) != -1 || errno != EINVAL) {
+        printf("fail: mincore of an unaligned address\n");
+        return 1;
+    }
+
+    int p[2];
+    if (pipe(p) < 0) {
+        perror("pipe");
+        return 1;
+    }
+
+    pid_t pid = fork();
+    if (pid == 0) {
+        close(p[1]);
+        char c;
+        read(p[0], &c, 1);
+        _exit(strcmp(data, "written by the parent") == 0 ? 0 : 1);
+    }
+    close(p[0]);
+
+    // the child's copy of data is at the same address
+    char buf[64] = {0};
+    struct iovec local = {.iov_base = buf, .iov_len = sizeof(buf)};
+    struct iovec remote = {.iov_base = data, .iov_len = sizeof(data)};
+    if (process_vm_readv(pid, &local, 1, &remote, 1, 0) != sizeof(data)) {
+        perror("process_vm_readv");
+        return 1;
+    }
+    if (strcmp(buf, "hello from the child") != 0) {
+        printf("fail: read '%s'\n", buf);
+        return 1;
+    }
+
+    // the transfer stops at the remote iovec that faults
+    struct iovec remotes[2] = {
+        {.iov_base = data, .iov_len = 8},
+        {.iov_base = NULL, .iov_len = 8},
+    };
+    if (process_vm_readv(pid, &local, 1, remotes, 2, 0) != 8) {
+        printf("fail: partial process_vm_readv\n");
+        return 1;
+    }
+    if (process_vm_readv(pid, &local, 1, &remotes[1], 1, 0) != -1 || errno != EFAULT) {
+        printf("fail: process_vm_readv of a bad address\n");
+        return 1;
+    }
+
+    strcpy(buf, "written by the parent");
+    if (process_vm_writev(pid, &local, 1, &remote, 1, 0) != sizeof(data)) {
+        perror("process_vm_writev");
+        return 1;
+    }
+
+    if (process_vm_readv(pid, &local, 1, &remote, 1, 1) != -1 || errno != EINVAL) {
+        printf("fail: process_vm_readv with flags\n");
+        return 1;
+    }
+
+    write(p[1], "x", 1);
+    int status;
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fail: the child didn't see the data written by process_vm_writev\n");
+        return 1;
+    }
+
+    if (process_vm_readv(pid, &local, 1, &remote, 1, 0) != -1 || errno != ESRCH) {
+        printf("fail: process_vm_readv of a reaped process\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,100 @@
+#define _GNU_SOURCE
+#include <fcntl.h>
+#include <sched.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include <sys/stat.h>
+#include <sys/types.h>
+#include <sys/wait.h>
+
+// read /proc/<pid>/ns/pid and parse the inode out of "pid:[inode]"
+static int readns(const char *path, unsigned long *ino)
+{
+    char buf[64];
+    ssize_t n = readlink(path, buf, sizeof(buf) - 1);
+    if (n < 0) {
+        perror("readlink");
+        return -1;
+    }
+    buf[n] = 0;
+
+    printf("%s -> %s\n", path, buf);
+
+    char end;
+    if (sscanf(buf, "pid:[%lu%c", ino, &end) != 2 || end != ']' || buf[n - 1] != ']') {
+        printf("fail: unexpected link format %s\n", buf);
+        return -1;
+    }
+
+    return 0;
+}
+
+int main()
+{
+    unsigned long ino;
+    if (readns("/proc/self/ns/pid", &ino) < 0) {
+        return 1;
+    }
+
+    // stat through the link reports the same inode
+    struct stat st;
+    if (stat("/proc/self/ns/pid", &st) < 0) {
+        perror("stat");
+        return 1;
+    }
+
+    if (st.st_ino != ino) {
+        printf("fail: stat inode %lu, link inode %lu\n", (unsigned long)st.st_ino, ino);
+        return 1;
+    }
+
+    int fds[2];
+    if (pipe(fds) < 0) {
+        perror("pipe");
+        return 1;
+    }
+
+    pid_t pid = fork();
+    if (pid < 0) {
+        perror("fork");
+        return 1;
+    }
+
+    if (pid == 0) {
+        unsigned long childIno;
+        if (readns("/proc/self/ns/pid", &childIno) < 0) {
+            childIno = 0;
+        }
+        write(fds[1], &childIno, sizeof(childIno));
+        _exit(0);
+    }
+
+    unsigned long childIno = 0;
+    if (read(fds[0], &childIno, sizeof(childIno)) != sizeof(childIno)) {
+        perror("read");
+        return 1;
+    }
+    waitpid(pid, NULL, 0);
+
+    if (childIno != ino) {
+        printf("fail: parent inode %lu, child inode %lu\n", ino, childIno);
+        return 1;
+    }
+
+    // the link can be opened and passed to setns
+    int fd = open("/proc/self/ns/pid", O_RDONLY);
+    if (fd < 0) {
+        perror("open");
+        return 1;
+    }
+
+    if (setns(fd, CLONE_NEWPID) < 0) {
+        perror("setns");
+        return 1;
+    }
+    close(fd);
+
+    printf("pass\n");
+    return 0;
+}
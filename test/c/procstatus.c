@@ -0,0 +1,94 @@
+#define _GNU_SOURCE
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#define BIT(sig) (1ULL << ((sig) - 1))
+
+static void handler(int sig)
+{
+}
+
+// field reads the hex value of the named field from the status file open on
+// fd, rereading it from the start.
+static int field(int fd, const char *name, unsigned long long *val)
+{
+    char buf[4096];
+    ssize_t n = pread(fd, buf, sizeof(buf) - 1, 0);
+    if (n <= 0) {
+        perror("pread");
+        return 1;
+    }
+    buf[n] = 0;
+
+    char key[32];
+    snprintf(key, sizeof(key), "\n%s:\t", name);
+    char *p = strstr(buf, key);
+    if (p == NULL) {
+        printf("fail: no %s in status\n", name);
+        return 1;
+    }
+    *val = strtoull(p + strlen(key), NULL, 16);
+    return 0;
+}
+
+int main()
+{
+    int fd = open("/proc/self/status", O_RDONLY);
+    if (fd < 0) {
+        perror("open");
+        return 1;
+    }
+
+    sigset_t set;
+    sigemptyset(&set);
+    sigaddset(&set, SIGUSR1);
+    if (sigprocmask(SIG_BLOCK, &set, NULL) < 0) {
+        perror("sigprocmask");
+        return 1;
+    }
+    signal(SIGUSR2, SIG_IGN);
+    signal(SIGTERM, handler);
+    raise(SIGUSR1);
+
+    unsigned long long blk, pnd, shd, ign, cgt;
+    if (field(fd, "SigBlk", &blk) || field(fd, "SigPnd", &pnd) || field(fd, "ShdPnd", &shd) ||
+        field(fd, "SigIgn", &ign) || field(fd, "SigCgt", &cgt)) {
+        return 1;
+    }
+
+    if (!(blk & BIT(SIGUSR1))) {
+        printf("fail: SIGUSR1 not in SigBlk %llx\n", blk);
+        return 1;
+    }
+    if (!((pnd | shd) & BIT(SIGUSR1))) {
+        printf("fail: SIGUSR1 not pending\n");
+        return 1;
+    }
+    if (!(ign & BIT(SIGUSR2)) || (cgt & BIT(SIGUSR2))) {
+        printf("fail: SIGUSR2 not in SigIgn %llx\n", ign);
+        return 1;
+    }
+    if (!(cgt & BIT(SIGTERM)) || (ign & BIT(SIGTERM))) {
+        printf("fail: SIGTERM not in SigCgt %llx\n", cgt);
+        return 1;
+    }
+
+    // the same open file shows the new state on the next read
+    signal(SIGUSR1, SIG_IGN);
+    sigprocmask(SIG_UNBLOCK, &set, NULL);
+    if (field(fd, "SigBlk", &blk) || field(fd, "SigPnd", &pnd) || field(fd, "ShdPnd", &shd)) {
+        return 1;
+    }
+    if ((blk & BIT(SIGUSR1)) || ((pnd | shd) & BIT(SIGUSR1))) {
+        printf("fail: status wasn't regenerated\n");
+        return 1;
+    }
+
+    close(fd);
+    printf("pass\n");
+    return 0;
+}
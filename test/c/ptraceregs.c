@@ -0,0 +1,129 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <signal.h>
+#include <stddef.h>
+#include <stdio.h>
+#include <sys/ptrace.h>
+#include <sys/syscall.h>
+#include <sys/user.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// A tracer reads a stopped tracee's registers with PTRACE_GETREGS and
+// PTRACE_PEEKUSER, and the RAX it writes with PTRACE_SETREGS or
+// PTRACE_POKEUSER is what the tracee's interrupted syscall returns.
+
+static void onUsr1(int sig)
+{
+}
+
+static int waitStop(pid_t pid)
+{
+    int status;
+    if (waitpid(pid, &status, 0) != pid) {
+        perror("waitpid");
+        return -1;
+    }
+    if (!WIFSTOPPED(status)) {
+        printf("fail: got wait status %x, want a stop\n", status);
+        return -1;
+    }
+    return 0;
+}
+
+int main()
+{
+    pid_t pid = fork();
+    if (pid == 0) {
+        signal(SIGUSR1, onUsr1);
+        if (ptrace(PTRACE_TRACEME, 0, NULL, NULL) < 0) {
+            _exit(100);
+        }
+        raise(SIGSTOP);
+        // the tracer rewrites what these return
+        long a = syscall(SYS_kill, getpid(), SIGUSR1);
+        long b = syscall(SYS_kill, getpid(), SIGUSR1);
+        _exit(a == 42 && b == 7 ? 0 : 1);
+    }
+
+    if (waitStop(pid) < 0 || ptrace(PTRACE_CONT, pid, NULL, 0) < 0) {
+        return 1;
+    }
+
+    // the tracee stopped right after its kill syscall returned
+    if (waitStop(pid) < 0) {
+        return 1;
+    }
+    struct user_regs_struct regs;
+    if (ptrace(PTRACE_GETREGS, pid, NULL, &regs) < 0) {
+        perror("ptrace PTRACE_GETREGS");
+        return 1;
+    }
+    errno = 0;
+    long rip = ptrace(PTRACE_PEEKUSER, pid, offsetof(struct user, regs.rip), NULL);
+    if (errno != 0 || rip != (long)regs.rip) {
+        printf("fail: PTRACE_PEEKUSER rip %lx, PTRACE_GETREGS rip %llx\n", rip, regs.rip);
+        return 1;
+    }
+    // the child runs the same libc as the parent
+    unsigned char *insn = (unsigned char *)regs.rip - 2;
+    if (insn[0] != 0x0f || insn[1] != 0x05) {
+        printf("fail: rip %llx doesn't follow a syscall instruction\n", regs.rip);
+        return 1;
+    }
+    if (regs.rax != 0) {
+        printf("fail: rax is %llx, want kill's return value 0\n", regs.rax);
+        return 1;
+    }
+    regs.rax = 42;
+    if (ptrace(PTRACE_SETREGS, pid, NULL, &regs) < 0) {
+        perror("ptrace PTRACE_SETREGS");
+        return 1;
+    }
+    if (ptrace(PTRACE_PEEKUSER, pid, offsetof(struct user, regs.rax), NULL) != 42) {
+        printf("fail: PTRACE_SETREGS didn't change rax\n");
+        return 1;
+    }
+    if (ptrace(PTRACE_CONT, pid, NULL, 0) < 0) {
+        perror("ptrace PTRACE_CONT");
+        return 1;
+    }
+
+    if (waitStop(pid) < 0) {
+        return 1;
+    }
+    if (ptrace(PTRACE_POKEUSER, pid, offsetof(struct user, regs.rax), 7) < 0) {
+        perror("ptrace PTRACE_POKEUSER");
+        return 1;
+    }
+    if (ptrace(PTRACE_POKEUSER, pid, 3, 7) != -1 || errno != EIO) {
+        printf("fail: PTRACE_POKEUSER accepted an unaligned offset\n");
+        return 1;
+    }
+    if (ptrace(PTRACE_CONT, pid, NULL, 0) < 0) {
+        perror("ptrace PTRACE_CONT");
+        return 1;
+    }
+
+    int status;
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fail: the tracee didn't see the new return values: status %x\n", status);
+        return 1;
+    }
+
+    // a task which isn't traced by the caller
+    pid = fork();
+    if (pid == 0) {
+        pause();
+        _exit(0);
+    }
+    if (ptrace(PTRACE_GETREGS, pid, NULL, &regs) != -1 || errno != ESRCH) {
+        printf("fail: PTRACE_GETREGS on an untraced child didn't fail with ESRCH\n");
+        return 1;
+    }
+    kill(pid, SIGKILL);
+    waitpid(pid, NULL, 0);
+
+    printf("pass\n");
+    return 0;
+}
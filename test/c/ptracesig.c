@@ -0,0 +1,116 @@
+#define _GNU_SOURCE
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/ptrace.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// A traced child stops in signal-delivery-stop before each signal is
+// delivered, and the tracer can suppress the signal or replace it with
+// another one when it resumes the child.
+
+static volatile int got1, got2;
+
+static void onUsr1(int sig)
+{
+    got1++;
+}
+
+static void onUsr2(int sig)
+{
+    got2++;
+}
+
+// waitStop waits for the child to stop and checks the signal it reports.
+static int waitStop(pid_t pid, int want)
+{
+    int status;
+    if (waitpid(pid, &status, 0) != pid) {
+        perror("waitpid");
+        return -1;
+    }
+    if (!WIFSTOPPED(status) || WSTOPSIG(status) != want) {
+        printf("fail: got wait status %x, want a stop with %s\n", status, strsignal(want));
+        return -1;
+    }
+
+    siginfo_t si;
+    if (ptrace(PTRACE_GETSIGINFO, pid, NULL, &si) < 0) {
+        perror("ptrace PTRACE_GETSIGINFO");
+        return -1;
+    }
+    if (si.si_signo != want) {
+        printf("fail: PTRACE_GETSIGINFO returned signal %d, want %d\n", si.si_signo, want);
+        return -1;
+    }
+    return 0;
+}
+
+int main()
+{
+    pid_t pid = fork();
+    if (pid == 0) {
+        signal(SIGUSR1, onUsr1);
+        signal(SIGUSR2, onUsr2);
+        if (ptrace(PTRACE_TRACEME, 0, NULL, NULL) < 0) {
+            _exit(100);
+        }
+        raise(SIGSTOP);
+        // suppressed by the tracer
+        raise(SIGUSR1);
+        // replaced with SIGUSR2 by the tracer
+        raise(SIGUSR1);
+        _exit(got1 * 10 + got2);
+    }
+
+    // the tracer suppresses the initial SIGSTOP, so the child doesn't
+    // group-stop
+    if (waitStop(pid, SIGSTOP) < 0) {
+        return 1;
+    }
+    if (ptrace(PTRACE_CONT, pid, NULL, 0) < 0) {
+        perror("ptrace PTRACE_CONT");
+        return 1;
+    }
+
+    // a stopped tracee's stop is reported once
+    if (waitStop(pid, SIGUSR1) < 0) {
+        return 1;
+    }
+    int status;
+    if (waitpid(pid, &status, WNOHANG) != 0) {
+        printf("fail: the same stop was reported twice\n");
+        return 1;
+    }
+    if (ptrace(PTRACE_CONT, pid, NULL, 0) < 0) {
+        perror("ptrace PTRACE_CONT");
+        return 1;
+    }
+
+    if (waitStop(pid, SIGUSR1) < 0) {
+        return 1;
+    }
+    if (ptrace(PTRACE_CONT, pid, NULL, SIGUSR2) < 0) {
+        perror("ptrace PTRACE_CONT");
+        return 1;
+    }
+
+    // an exited tracee can't be resumed
+    if (waitpid(pid, &status, 0) != pid) {
+        perror("waitpid");
+        return 1;
+    }
+    if (ptrace(PTRACE_CONT, pid, NULL, 0) != -1) {
+        printf("fail: PTRACE_CONT on an exited tracee succeeded\n");
+        return 1;
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 1) {
+        printf("fail: the child's wait status is %x, want exit 1 (SIGUSR1 suppressed, SIGUSR2 delivered)\n",
+               status);
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
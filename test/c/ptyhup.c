@@ -0,0 +1,75 @@
+#define _GNU_SOURCE
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <unistd.h>
+#include <sys/wait.h>
+#include <termios.h>
+
+// Closing the master side of a pty sends SIGHUP to the foreground process
+// group of the slave.
+
+static volatile sig_atomic_t got;
+
+static void onHup(int sig)
+{
+    got = sig;
+}
+
+int main()
+{
+    int master = posix_openpt(O_RDWR | O_NOCTTY);
+    if (master < 0) {
+        printf("skip: no pty support\n");
+        return 0;
+    }
+    if (grantpt(master) < 0 || unlockpt(master) < 0) {
+        perror("grantpt");
+        return 1;
+    }
+    char *name = ptsname(master);
+
+    int p[2];
+    if (pipe(p) < 0) {
+        perror("pipe");
+        return 1;
+    }
+
+    pid_t pid = fork();
+    if (pid == 0) {
+        close(master);
+        close(p[0]);
+        struct sigaction sa = {.sa_handler = onHup};
+        sigaction(SIGHUP, &sa, NULL);
+
+        setsid();
+        int slave = open(name, O_RDWR);
+        if (slave < 0 || tcsetpgrp(slave, getpgrp()) < 0) {
+            _exit(2);
+        }
+
+        write(p[1], "x", 1);
+        for (int i = 0; i < 500 && !got; i++) {
+            usleep(10000);
+        }
+        _exit(got == SIGHUP ? 0 : 1);
+    }
+
+    close(p[1]);
+    char c;
+    if (read(p[0], &c, 1) != 1) {
+        printf("fail: the child couldn't take the slave as its terminal\n");
+        return 1;
+    }
+    close(master);
+
+    int status;
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fail: the foreground child didn't get SIGHUP\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,84 @@
+#define _GNU_SOURCE
+#include <pthread.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include <sys/syscall.h>
+
+static volatile sig_atomic_t handled = 0;
+static volatile pid_t handledBy = 0;
+
+static void handler(int sig)
+{
+    handled = 1;
+    handledBy = syscall(SYS_gettid);
+}
+
+// raise SIGUSR1 from a second thread while the main thread blocks it: the
+// signal must be handled by the raising thread before kill() returns
+static void *killer(void *arg)
+{
+    pid_t tid = syscall(SYS_gettid);
+
+    handled = 0;
+    kill(getpid(), SIGUSR1);
+    if (!handled || handledBy != tid) {
+        printf("fail: kill(getpid()) not handled by the sending thread before return\n");
+        return (void *)1;
+    }
+
+    return NULL;
+}
+
+int main()
+{
+    struct sigaction sa;
+    memset(&sa, 0, sizeof(sa));
+    sa.sa_handler = handler;
+    if (sigaction(SIGUSR1, &sa, NULL) < 0) {
+        perror("sigaction");
+        return 1;
+    }
+
+    // tgkill to ourselves, without the signal mask dance glibc's raise() does
+    pid_t tid = syscall(SYS_gettid);
+    syscall(SYS_tgkill, getpid(), tid, SIGUSR1);
+    if (!handled) {
+        printf("fail: tgkill to self not handled before return\n");
+        return 1;
+    }
+
+    handled = 0;
+    raise(SIGUSR1);
+    if (!handled) {
+        printf("fail: raise not handled before return\n");
+        return 1;
+    }
+
+    sigset_t set;
+    sigemptyset(&set);
+    sigaddset(&set, SIGUSR1);
+    pthread_sigmask(SIG_BLOCK, &set, NULL);
+
+    // the new thread starts with SIGUSR1 unblocked
+    pthread_t th;
+    sigset_t empty;
+    sigemptyset(&empty);
+    pthread_attr_t attr;
+    pthread_attr_init(&attr);
+    pthread_attr_setsigmask_np(&attr, &empty);
+    if (pthread_create(&th, &attr, killer, NULL) != 0) {
+        printf("fail: pthread_create\n");
+        return 1;
+    }
+
+    void *ret;
+    pthread_join(th, &ret);
+    if (ret != NULL) {
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
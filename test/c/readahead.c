@@ -0,0 +1,61 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+int main()
+{
+    char path[] = "/tmp/readaheadXXXXXX";
+    int fd = mkstemp(path);
+    if (fd < 0) {
+        perror("mkstemp");
+        return 1;
+    }
+    unlink(path);
+
+    static char buf[1 << 20];
+    memset(buf, 'r', sizeof(buf));
+    if (write(fd, buf, sizeof(buf)) != sizeof(buf)) {
+        perror("write");
+        return 1;
+    }
+
+    // readahead is a hint and returns at once, the data is still read as usual
+    if (readahead(fd, 0, sizeof(buf)) < 0) {
+        perror("readahead");
+        return 1;
+    }
+    memset(buf, 0, sizeof(buf));
+    if (pread(fd, buf, sizeof(buf), 0) != sizeof(buf) || buf[0] != 'r' || buf[sizeof(buf) - 1] != 'r') {
+        printf("fail: read after readahead\n");
+        return 1;
+    }
+
+    // a count of 0 and a range past the end are fine too
+    if (readahead(fd, 0, 0) < 0 || readahead(fd, 1 << 30, 4096) < 0) {
+        perror("readahead");
+        return 1;
+    }
+
+    int fds[2];
+    if (pipe(fds) < 0) {
+        perror("pipe");
+        return 1;
+    }
+    if (readahead(fds[0], 0, 4096) != -1 || errno != EINVAL) {
+        printf("fail: readahead on a pipe didn't fail with EINVAL\n");
+        return 1;
+    }
+
+    int wfd = open("/dev/null", O_WRONLY);
+    if (wfd < 0 || readahead(wfd, 0, 4096) != -1 || errno != EBADF) {
+        printf("fail: readahead on a write-only fd didn't fail with EBADF\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,137 @@
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// checkPeek verifies that MSG_PEEK leaves the data queued on fd.
+static int checkPeek(int rfd, int wfd, const char *name)
+{
+    char buf[16] = {0};
+    if (write(wfd, "hello", 5) != 5) {
+        perror("write");
+        return 1;
+    }
+    if (recv(rfd, buf, sizeof(buf), MSG_PEEK) != 5 || memcmp(buf, "hello", 5) != 0) {
+        printf("fail: %s MSG_PEEK returned the wrong data\n", name);
+        return 1;
+    }
+    memset(buf, 0, sizeof(buf));
+    if (recv(rfd, buf, sizeof(buf), 0) != 5 || memcmp(buf, "hello", 5) != 0) {
+        printf("fail: %s data was consumed by MSG_PEEK\n", name);
+        return 1;
+    }
+    return 0;
+}
+
+// checkWaitAll verifies that MSG_WAITALL collects data written in two chunks.
+static int checkWaitAll(int rfd, int wfd, const char *name)
+{
+    pid_t pid = fork();
+    if (pid == 0) {
+        write(wfd, "abc", 3);
+        usleep(100000);
+        write(wfd, "defgh", 5);
+        _exit(0);
+    }
+
+    char buf[8];
+    ssize_t n = recv(rfd, buf, sizeof(buf), MSG_WAITALL);
+    waitpid(pid, NULL, 0);
+    if (n != 8 || memcmp(buf, "abcdefgh", 8) != 0) {
+        printf("fail: %s MSG_WAITALL returned %zd bytes\n", name, n);
+        return 1;
+    }
+    return 0;
+}
+
+// checkTrunc verifies that MSG_TRUNC reports the full datagram length.
+static int checkTrunc(int rfd, int wfd, const char *name)
+{
+    if (write(wfd, "0123456789", 10) != 10) {
+        perror("write");
+        return 1;
+    }
+
+    char buf[4];
+    struct iovec iov = {buf, sizeof(buf)};
+    struct msghdr msg = {.msg_iov = &iov, .msg_iovlen = 1};
+    ssize_t n = recvmsg(rfd, &msg, MSG_TRUNC);
+    if (n != 10 || !(msg.msg_flags & MSG_TRUNC) || memcmp(buf, "0123", 4) != 0) {
+        printf("fail: %s MSG_TRUNC returned %zd flags %x\n", name, n, msg.msg_flags);
+        return 1;
+    }
+    return 0;
+}
+
+int main()
+{
+    int sv[2];
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, sv) < 0) {
+        perror("socketpair");
+        return 1;
+    }
+    if (checkPeek(sv[0], sv[1], "unix stream") || checkWaitAll(sv[0], sv[1], "unix stream")) {
+        return 1;
+    }
+    close(sv[0]);
+    close(sv[1]);
+
+    if (socketpair(AF_UNIX, SOCK_DGRAM, 0, sv) < 0) {
+        perror("socketpair");
+        return 1;
+    }
+    if (checkTrunc(sv[0], sv[1], "unix dgram")) {
+        return 1;
+    }
+    close(sv[0]);
+    close(sv[1]);
+
+    int srv = socket(AF_INET, SOCK_STREAM, 0);
+    struct sockaddr_in addr = {.sin_family = AF_INET};
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    socklen_t alen = sizeof(addr);
+    if (srv < 0 || bind(srv, (struct sockaddr *)&addr, sizeof(addr)) < 0 || listen(srv, 1) < 0 ||
+        getsockname(srv, (struct sockaddr *)&addr, &alen) < 0) {
+        perror("listen");
+        return 1;
+    }
+    int cli = socket(AF_INET, SOCK_STREAM, 0);
+    if (cli < 0 || connect(cli, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        perror("connect");
+        return 1;
+    }
+    int conn = accept(srv, NULL, NULL);
+    if (conn < 0) {
+        perror("accept");
+        return 1;
+    }
+    if (checkPeek(conn, cli, "tcp") || checkWaitAll(conn, cli, "tcp")) {
+        return 1;
+    }
+    close(conn);
+    close(cli);
+    close(srv);
+
+    int u1 = socket(AF_INET, SOCK_DGRAM, 0);
+    int u2 = socket(AF_INET, SOCK_DGRAM, 0);
+    addr.sin_port = 0;
+    alen = sizeof(addr);
+    if (u1 < 0 || u2 < 0 || bind(u1, (struct sockaddr *)&addr, sizeof(addr)) < 0 ||
+        getsockname(u1, (struct sockaddr *)&addr, &alen) < 0 ||
+        connect(u2, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        perror("udp");
+        return 1;
+    }
+    if (checkTrunc(u1, u2, "udp")) {
+        return 1;
+    }
+    close(u1);
+    close(u2);
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,135 @@
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <errno.h>
+#include <netinet/in.h>
+#include <poll.h>
+#include <stdio.h>
+#include <sys/socket.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CONNS 32
+
+// listener returns a loopback listener on port with SO_REUSEPORT set as
+// asked, or -1 with errno set.
+static int listener(int port, int reuse)
+{
+    int fd = socket(AF_INET, SOCK_STREAM, 0);
+    if (fd < 0 || setsockopt(fd, SOL_SOCKET, SO_REUSEPORT, &reuse, sizeof(reuse)) < 0) {
+        return -1;
+    }
+
+    struct sockaddr_in addr = {.sin_family = AF_INET, .sin_port = htons(port)};
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    if (bind(fd, (struct sockaddr *)&addr, sizeof(addr)) < 0 || listen(fd, CONNS) < 0) {
+        int err = errno;
+        close(fd);
+        errno = err;
+        return -1;
+    }
+    return fd;
+}
+
+// drain accepts connections until none arrives for a while.
+static int drain(int fd)
+{
+    int n = 0;
+    struct pollfd pfd = {fd, POLLIN, 0};
+    while (poll(&pfd, 1, 500) == 1) {
+        int c = accept(fd, NULL, NULL);
+        if (c < 0) {
+            break;
+        }
+        close(c);
+        n++;
+    }
+    return n;
+}
+
+static int getInt(int fd, int name)
+{
+    int val = -1;
+    socklen_t len = sizeof(val);
+    if (getsockopt(fd, SOL_SOCKET, name, &val, &len) < 0) {
+        return -1;
+    }
+    return val;
+}
+
+int main()
+{
+    int a = listener(0, 1);
+    struct sockaddr_in addr;
+    socklen_t alen = sizeof(addr);
+    if (a < 0 || getsockname(a, (struct sockaddr *)&addr, &alen) < 0) {
+        perror("listener");
+        return 1;
+    }
+    int port = ntohs(addr.sin_port);
+
+    if (getInt(a, SO_REUSEPORT) != 1) {
+        printf("fail: SO_REUSEPORT not reported by getsockopt\n");
+        return 1;
+    }
+
+    // without the option the port is taken
+    if (listener(port, 0) != -1 || errno != EADDRINUSE) {
+        printf("fail: expected EADDRINUSE without SO_REUSEPORT\n");
+        return 1;
+    }
+
+    int ready[2], result[2];
+    pipe(ready);
+    pipe(result);
+    pid_t pid = fork();
+    if (pid == 0) {
+        int b = listener(port, 1);
+        char c = b < 0 ? 'n' : 'y';
+        write(ready[1], &c, 1);
+        int n = b < 0 ? -1 : drain(b);
+        write(result[1], &n, sizeof(n));
+        _exit(0);
+    }
+
+    char c;
+    if (read(ready[0], &c, 1) != 1 || c != 'y') {
+        printf("fail: second process couldn't bind the port with SO_REUSEPORT\n");
+        return 1;
+    }
+
+    int clients[CONNS];
+    for (int i = 0; i < CONNS; i++) {
+        clients[i] = socket(AF_INET, SOCK_STREAM, 0);
+        if (connect(clients[i], (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+            perror("connect");
+            return 1;
+        }
+    }
+
+    int mine = drain(a);
+    int theirs = -1;
+    read(result[0], &theirs, sizeof(theirs));
+    waitpid(pid, NULL, 0);
+    if (mine + theirs != CONNS || mine == 0 || theirs <= 0) {
+        printf("fail: connections split %d/%d between the listeners\n", mine, theirs);
+        return 1;
+    }
+    for (int i = 0; i < CONNS; i++) {
+        close(clients[i]);
+    }
+    close(a);
+
+    // unix sockets keep the options for getsockopt
+    int u = socket(AF_UNIX, SOCK_STREAM, 0);
+    int one = 1;
+    if (getInt(u, SO_REUSEADDR) != 0 || getInt(u, SO_REUSEPORT) != 0 ||
+        setsockopt(u, SOL_SOCKET, SO_REUSEADDR, &one, sizeof(one)) < 0 ||
+        getInt(u, SO_REUSEADDR) != 1) {
+        printf("fail: unix socket SO_REUSEADDR not reported\n");
+        return 1;
+    }
+    close(u);
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,88 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/mman.h>
+#include <sys/resource.h>
+#include <unistd.h>
+
+// RLIMIT_AS bounds mmap, mremap and brk, and RLIMIT_DATA bounds brk. The
+// allocations fail with ENOMEM and the process keeps running.
+
+#define MB (1UL << 20)
+
+int main()
+{
+    struct rlimit rl = {.rlim_cur = 256 * MB, .rlim_max = RLIM_INFINITY};
+    if (setrlimit(RLIMIT_AS, &rl) < 0) {
+        perror("setrlimit");
+        return 1;
+    }
+
+    void *p = mmap(NULL, 1024 * MB, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (p != MAP_FAILED || errno != ENOMEM) {
+        printf("fail: a 1GB mmap succeeded under a 256MB RLIMIT_AS\n");
+        return 1;
+    }
+    p = mmap(NULL, 1024 * MB, PROT_NONE, MAP_PRIVATE | MAP_ANONYMOUS | MAP_NORESERVE, -1, 0);
+    if (p != MAP_FAILED || errno != ENOMEM) {
+        printf("fail: a MAP_NORESERVE mapping isn't counted\n");
+        return 1;
+    }
+    if (malloc(1024 * MB) != NULL) {
+        printf("fail: malloc of 1GB succeeded\n");
+        return 1;
+    }
+
+    // a small mapping still works but can't grow past the limit
+    char *s = mmap(NULL, MB, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (s == MAP_FAILED) {
+        perror("mmap");
+        return 1;
+    }
+    s[0] = 1;
+    if (mremap(s, MB, 1024 * MB, MREMAP_MAYMOVE) != MAP_FAILED || errno != ENOMEM) {
+        printf("fail: mremap grew past RLIMIT_AS\n");
+        return 1;
+    }
+    munmap(s, MB);
+
+    // brk fails without moving the break
+    void *brk0 = sbrk(0);
+    if (sbrk(1024 * MB) != (void *)-1 || errno != ENOMEM) {
+        printf("fail: sbrk grew past RLIMIT_AS\n");
+        return 1;
+    }
+    if (sbrk(0) != brk0) {
+        printf("fail: the break moved after a failed brk\n");
+        return 1;
+    }
+
+    rl.rlim_cur = RLIM_INFINITY;
+    if (setrlimit(RLIMIT_AS, &rl) < 0) {
+        perror("setrlimit");
+        return 1;
+    }
+
+    // RLIMIT_DATA bounds the heap
+    if (getrlimit(RLIMIT_DATA, &rl) < 0) {
+        perror("getrlimit");
+        return 1;
+    }
+    rl.rlim_cur = 64 * MB;
+    if (setrlimit(RLIMIT_DATA, &rl) < 0) {
+        perror("setrlimit");
+        return 1;
+    }
+    if (sbrk(128 * MB) != (void *)-1 || errno != ENOMEM) {
+        printf("fail: sbrk grew past RLIMIT_DATA\n");
+        return 1;
+    }
+    if (sbrk(MB) == (void *)-1) {
+        perror("sbrk");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,126 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <pthread.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// A robust mutex held by a thread or process that dies is marked
+// FUTEX_OWNER_DIED and the next locker gets EOWNERDEAD, including one which
+// was already blocked on it.
+
+static pthread_mutex_t *mu;
+
+static void *holdAndDie(void *arg)
+{
+    pthread_mutex_lock(mu);
+    usleep(200000);
+    // exit without unlocking
+    syscall(SYS_exit, 0);
+    return NULL;
+}
+
+static int initMutex(pthread_mutex_t *m, int pshared, int protocol)
+{
+    pthread_mutexattr_t attr;
+    pthread_mutexattr_init(&attr);
+    pthread_mutexattr_setrobust(&attr, PTHREAD_MUTEX_ROBUST);
+    pthread_mutexattr_setpshared(&attr, pshared);
+    pthread_mutexattr_setprotocol(&attr, protocol);
+    return pthread_mutex_init(m, &attr);
+}
+
+// recover locks mu, expecting the owner to have died.
+static int recover(const char *what)
+{
+    int ret = pthread_mutex_lock(mu);
+    if (ret != EOWNERDEAD) {
+        printf("fail: %s: lock returned %s, want EOWNERDEAD\n", what, strerror(ret));
+        return -1;
+    }
+    pthread_mutex_consistent(mu);
+    pthread_mutex_unlock(mu);
+    if (pthread_mutex_lock(mu) != 0) {
+        printf("fail: %s: the recovered mutex can't be locked\n", what);
+        return -1;
+    }
+    pthread_mutex_unlock(mu);
+    return 0;
+}
+
+static int threadDies(int protocol, const char *what)
+{
+    if (initMutex(mu, PTHREAD_PROCESS_PRIVATE, protocol) != 0) {
+        printf("fail: %s: pthread_mutex_init\n", what);
+        return -1;
+    }
+
+    // the waiter blocks before the owner dies
+    pthread_t th;
+    pthread_create(&th, NULL, holdAndDie, NULL);
+    usleep(50000);
+    return recover(what);
+}
+
+int main()
+{
+    mu = mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_SHARED | MAP_ANONYMOUS, -1, 0);
+    if (mu == MAP_FAILED) {
+        perror("mmap");
+        return 1;
+    }
+
+    if (threadDies(PTHREAD_PRIO_NONE, "thread") < 0 || threadDies(PTHREAD_PRIO_INHERIT, "pi thread") < 0) {
+        return 1;
+    }
+
+    // a process killed while holding a shared mutex
+    if (initMutex(mu, PTHREAD_PROCESS_SHARED, PTHREAD_PRIO_NONE) != 0) {
+        printf("fail: pthread_mutex_init\n");
+        return 1;
+    }
+    int p[2];
+    pipe(p);
+    pid_t pid = fork();
+    if (pid == 0) {
+        pthread_mutex_lock(mu);
+        write(p[1], "x", 1);
+        for (;;) {
+            pause();
+        }
+    }
+    char c;
+    read(p[0], &c, 1);
+    if (pthread_mutex_trylock(mu) != EBUSY) {
+        printf("fail: the child's lock isn't held\n");
+        return 1;
+    }
+    kill(pid, SIGKILL);
+    waitpid(pid, NULL, 0);
+    if (recover("killed process") < 0) {
+        return 1;
+    }
+
+    // get_robust_list returns what glibc registered
+    void *head;
+    size_t len;
+    if (syscall(SYS_get_robust_list, 0, &head, &len) < 0) {
+        perror("get_robust_list");
+        return 1;
+    }
+    if (head == NULL || len != 3 * sizeof(long)) {
+        printf("fail: get_robust_list returned %p, %zu\n", head, len);
+        return 1;
+    }
+    if (syscall(SYS_set_robust_list, head, len + 1) != -1 || errno != EINVAL) {
+        printf("fail: set_robust_list accepted a bad length\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
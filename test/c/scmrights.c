@@ -0,0 +1,145 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#define MAX_FDS 300
+
+// sendFds sends one byte of data carrying n file descriptors.
+static ssize_t sendFds(int sock, int *fds, int n)
+{
+    char c = 'x';
+    struct iovec iov = {&c, 1};
+    char control[CMSG_SPACE(MAX_FDS * sizeof(int))];
+    struct msghdr msg = {
+        .msg_iov = &iov,
+        .msg_iovlen = 1,
+        .msg_control = control,
+        .msg_controllen = CMSG_SPACE(n * sizeof(int)),
+    };
+    struct cmsghdr *cmsg = CMSG_FIRSTHDR(&msg);
+    cmsg->cmsg_level = SOL_SOCKET;
+    cmsg->cmsg_type = SCM_RIGHTS;
+    cmsg->cmsg_len = CMSG_LEN(n * sizeof(int));
+    memcpy(CMSG_DATA(cmsg), fds, n * sizeof(int));
+    return sendmsg(sock, &msg, 0);
+}
+
+// recvFds receives a message with room for up to max descriptors and returns
+// how many arrived in *got.
+static ssize_t recvFds(int sock, int *fds, int max, int flags, int *got, int *msgFlags)
+{
+    char c;
+    struct iovec iov = {&c, 1};
+    char control[CMSG_SPACE(MAX_FDS * sizeof(int))];
+    struct msghdr msg = {
+        .msg_iov = &iov,
+        .msg_iovlen = 1,
+        .msg_control = max ? control : NULL,
+        .msg_controllen = max ? CMSG_LEN(max * sizeof(int)) : 0,
+    };
+    ssize_t n = recvmsg(sock, &msg, flags);
+    *got = 0;
+    *msgFlags = msg.msg_flags;
+    for (struct cmsghdr *cmsg = CMSG_FIRSTHDR(&msg); cmsg; cmsg = CMSG_NXTHDR(&msg, cmsg)) {
+        if (cmsg->cmsg_level == SOL_SOCKET && cmsg->cmsg_type == SCM_RIGHTS) {
+            *got = (cmsg->cmsg_len - CMSG_LEN(0)) / sizeof(int);
+            memcpy(fds, CMSG_DATA(cmsg), *got * sizeof(int));
+        }
+    }
+    return n;
+}
+
+int main()
+{
+    int sv[2];
+    if (socketpair(AF_UNIX, SOCK_SEQPACKET, 0, sv) < 0) {
+        perror("socketpair");
+        return 1;
+    }
+
+    // pass both ends of a pipe and close them in the sender
+    int p[2];
+    pipe(p);
+    if (sendFds(sv[0], p, 2) != 1) {
+        perror("sendmsg");
+        return 1;
+    }
+    close(p[0]);
+    close(p[1]);
+
+    int fds[MAX_FDS], got, flags;
+    if (recvFds(sv[1], fds, 2, MSG_CMSG_CLOEXEC, &got, &flags) != 1 || got != 2) {
+        printf("fail: received %d fds\n", got);
+        return 1;
+    }
+    if (!(fcntl(fds[0], F_GETFD) & FD_CLOEXEC) || !(fcntl(fds[1], F_GETFD) & FD_CLOEXEC)) {
+        printf("fail: MSG_CMSG_CLOEXEC not honored\n");
+        return 1;
+    }
+
+    char buf[16] = {0};
+    if (write(fds[1], "ok", 2) != 2 || read(fds[0], buf, sizeof(buf)) != 2 || strcmp(buf, "ok") != 0) {
+        printf("fail: passed pipe doesn't work\n");
+        return 1;
+    }
+    close(fds[0]);
+    close(fds[1]);
+
+    // a control buffer with room for one fd truncates the rest
+    int three[3] = {0, 1, 2};
+    sendFds(sv[0], three, 3);
+    if (recvFds(sv[1], fds, 1, 0, &got, &flags) != 1 || got != 1 || !(flags & MSG_CTRUNC)) {
+        printf("fail: small control buffer got %d fds, flags %x\n", got, flags);
+        return 1;
+    }
+    close(fds[0]);
+
+    // no control buffer at all
+    sendFds(sv[0], three, 1);
+    if (recvFds(sv[1], fds, 0, 0, &got, &flags) != 1 || got != 0 || !(flags & MSG_CTRUNC)) {
+        printf("fail: missing control buffer flags %x\n", flags);
+        return 1;
+    }
+
+    // too many fds in one message
+    int many[MAX_FDS];
+    for (int i = 0; i < MAX_FDS; i++) {
+        many[i] = 0;
+    }
+    if (sendFds(sv[0], many, 254) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL above SCM_MAX_FD\n");
+        return 1;
+    }
+
+    // seqpacket keeps record boundaries
+    send(sv[0], "abc", 3, MSG_EOR);
+    send(sv[0], "defgh", 5, MSG_EOR);
+    if (recv(sv[1], buf, sizeof(buf), 0) != 3 || recv(sv[1], buf, sizeof(buf), 0) != 5) {
+        printf("fail: record boundaries lost\n");
+        return 1;
+    }
+
+    // a short read truncates the record and reports it
+    send(sv[0], "ijklmn", 6, 0);
+    struct iovec iov = {buf, 2};
+    struct msghdr msg = {.msg_iov = &iov, .msg_iovlen = 1};
+    if (recvmsg(sv[1], &msg, 0) != 2 || !(msg.msg_flags & MSG_TRUNC)) {
+        printf("fail: expected MSG_TRUNC, flags %x\n", msg.msg_flags);
+        return 1;
+    }
+
+    // the peer going away is end of file
+    close(sv[0]);
+    if (recv(sv[1], buf, sizeof(buf), 0) != 0) {
+        printf("fail: expected EOF after the peer closed\n");
+        return 1;
+    }
+
+    close(sv[1]);
+    printf("pass\n");
+    return 0;
+}
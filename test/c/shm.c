@@ -0,0 +1,238 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/ipc.h>
+#include <sys/shm.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// System V shared memory: attaches share memory, nattch counts them, and
+// IPC_RMID only destroys the segment after the last detach.
+
+#define SIZE (3 * 4096 + 100)
+
+static int nattch(int id)
+{
+    struct shmid_ds ds;
+    if (shmctl(id, IPC_STAT, &ds) < 0) {
+        return -1;
+    }
+    return ds.shm_nattch;
+}
+
+// inProcSysvipc checks whether the segment is listed in /proc/sysvipc/shm.
+static int inProcSysvipc(int id)
+{
+    FILE *f = fopen("/proc/sysvipc/shm", "r");
+    char line[512];
+    int found = 0;
+    while (f && fgets(line, sizeof(line), f)) {
+        int key, shmid;
+        if (sscanf(line, "%d %d", &key, &shmid) == 2 && shmid == id) {
+            found = 1;
+        }
+    }
+    if (f) {
+        fclose(f);
+    }
+    return found;
+}
+
+static int private(void)
+{
+    int id = shmget(IPC_PRIVATE, SIZE, IPC_CREAT | 0600);
+    if (id < 0) {
+        perror("shmget");
+        return 1;
+    }
+
+    struct shmid_ds ds;
+    if (shmctl(id, IPC_STAT, &ds) < 0) {
+        perror("IPC_STAT");
+        return 1;
+    }
+    if (ds.shm_segsz != SIZE || ds.shm_cpid != getpid() || ds.shm_nattch != 0 || ds.shm_atime != 0 ||
+        (ds.shm_perm.mode & 0777) != 0600 || ds.shm_perm.uid != getuid()) {
+        printf("fail: IPC_STAT of a new segment\n");
+        return 1;
+    }
+
+    char *a = shmat(id, NULL, 0);
+    char *b = shmat(id, NULL, SHM_RDONLY);
+    if (a == (void *)-1 || b == (void *)-1 || a == b) {
+        perror("shmat");
+        return 1;
+    }
+    if (nattch(id) != 2) {
+        printf("fail: nattch is %d after two attaches\n", nattch(id));
+        return 1;
+    }
+
+    // both attaches see the same memory
+    strcpy(a + 3 * 4096, "shared");
+    if (strcmp(b + 3 * 4096, "shared") != 0) {
+        printf("fail: the attaches don't share memory\n");
+        return 1;
+    }
+
+    if (shmctl(id, IPC_STAT, &ds) < 0 || ds.shm_atime == 0 || ds.shm_lpid != getpid()) {
+        printf("fail: shmat didn't update atime and lpid\n");
+        return 1;
+    }
+
+    // the read-only attach can't be written
+    pid_t pid = fork();
+    if (pid == 0) {
+        b[0] = 1;
+        _exit(0);
+    }
+    int status;
+    if (waitpid(pid, &status, 0) != pid || !WIFSIGNALED(status) || WTERMSIG(status) != SIGSEGV) {
+        printf("fail: writing a SHM_RDONLY attach didn't fault\n");
+        return 1;
+    }
+
+    // a forked child inherits the attaches, and its writes are shared
+    pid = fork();
+    if (pid == 0) {
+        a[0] = 'c';
+        _exit(nattch(id) == 4 ? 0 : 1);
+    }
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fail: the forked child didn't see 4 attaches\n");
+        return 1;
+    }
+    if (a[0] != 'c' || nattch(id) != 2) {
+        printf("fail: after the child exited\n");
+        return 1;
+    }
+
+    if (!inProcSysvipc(id)) {
+        printf("fail: segment %d isn't in /proc/sysvipc/shm\n", id);
+        return 1;
+    }
+
+    // IPC_RMID waits for the last detach
+    if (shmctl(id, IPC_RMID, NULL) < 0) {
+        perror("IPC_RMID");
+        return 1;
+    }
+    if (shmctl(id, IPC_STAT, &ds) < 0 || !(ds.shm_perm.mode & SHM_DEST)) {
+        printf("fail: the segment is gone or not marked SHM_DEST after IPC_RMID\n");
+        return 1;
+    }
+    if (shmdt(b) < 0 || nattch(id) != 1 || strcmp(a + 3 * 4096, "shared") != 0) {
+        printf("fail: the segment didn't survive IPC_RMID while attached\n");
+        return 1;
+    }
+    if (shmctl(id, IPC_STAT, &ds) < 0 || ds.shm_dtime == 0) {
+        printf("fail: shmdt didn't update dtime\n");
+        return 1;
+    }
+
+    if (shmdt(a) < 0) {
+        perror("shmdt");
+        return 1;
+    }
+    if (shmctl(id, IPC_STAT, &ds) != -1 || errno != EINVAL) {
+        printf("fail: the segment outlived its last detach after IPC_RMID\n");
+        return 1;
+    }
+    if (inProcSysvipc(id)) {
+        printf("fail: the destroyed segment is still in /proc/sysvipc/shm\n");
+        return 1;
+    }
+
+    // nothing is attached there anymore
+    if (shmdt(a) != -1 || errno != EINVAL) {
+        printf("fail: shmdt of a detached address\n");
+        return 1;
+    }
+    return 0;
+}
+
+static int keyed(void)
+{
+    key_t key = 0x51a70000 | (getpid() & 0xffff);
+    int id = shmget(key, 4096, IPC_CREAT | IPC_EXCL | 0600);
+    if (id < 0) {
+        perror("shmget");
+        return 1;
+    }
+    if (shmget(key, 4096, 0) != id) {
+        printf("fail: shmget of an existing key\n");
+        return 1;
+    }
+    if (shmget(key, 4096, IPC_CREAT | IPC_EXCL | 0600) != -1 || errno != EEXIST) {
+        printf("fail: IPC_EXCL on an existing key\n");
+        return 1;
+    }
+    if (shmget(key, 8192, 0) != -1 || errno != EINVAL) {
+        printf("fail: shmget bigger than the segment\n");
+        return 1;
+    }
+
+    // IPC_SET changes the mode
+    struct shmid_ds ds;
+    if (shmctl(id, IPC_STAT, &ds) < 0) {
+        perror("IPC_STAT");
+        return 1;
+    }
+    ds.shm_perm.mode = 0640;
+    if (shmctl(id, IPC_SET, &ds) < 0 || shmctl(id, IPC_STAT, &ds) < 0 || (ds.shm_perm.mode & 0777) != 0640) {
+        printf("fail: IPC_SET of the mode\n");
+        return 1;
+    }
+
+    // attach at a given address, and not over it without SHM_REMAP
+    char *p = shmat(id, NULL, 0);
+    if (p == (void *)-1 || shmdt(p) < 0) {
+        perror("shmat");
+        return 1;
+    }
+    char *q = shmat(id, p, 0);
+    if (q != p) {
+        printf("fail: shmat at a hint address\n");
+        return 1;
+    }
+    if (shmat(id, p, 0) != (void *)-1 || errno != EINVAL) {
+        printf("fail: shmat over an attach without SHM_REMAP\n");
+        return 1;
+    }
+    if (shmat(id, p, SHM_REMAP) != p) {
+        printf("fail: shmat with SHM_REMAP\n");
+        return 1;
+    }
+    if (shmat(id, p + 1, 0) != (void *)-1 || errno != EINVAL) {
+        printf("fail: shmat at an unaligned address\n");
+        return 1;
+    }
+    if (shmat(id, p + 1, SHM_RND | SHM_REMAP) != p) {
+        printf("fail: shmat with SHM_RND\n");
+        return 1;
+    }
+
+    // the key is free again right after IPC_RMID
+    if (shmctl(id, IPC_RMID, NULL) < 0) {
+        perror("IPC_RMID");
+        return 1;
+    }
+    if (shmget(key, 4096, 0) != -1 || errno != ENOENT) {
+        printf("fail: shmget of a removed key\n");
+        return 1;
+    }
+    shmdt(p);
+    return 0;
+}
+
+int main()
+{
+    if (private() || keyed()) {
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,123 @@
+#define _GNU_SOURCE
+#include <fcntl.h>
+#include <sched.h>
+#include <stdatomic.h>
+#include <stdio.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// A parent and a forked child increment a counter in a MAP_SHARED mapping in
+// turns, so each one only goes on once it has seen the other's write. The
+// mappings are first touched after the fork.
+
+#define ROUNDS 1000
+
+// pingPong runs the parent's side, returns 0 if both sides saw every round.
+static int pingPong(const char *name, atomic_int *counter)
+{
+    pid_t pid = fork();
+    if (pid == 0) {
+        for (int i = 0; i < ROUNDS; i++) {
+            while (atomic_load(counter) != 2 * i + 1) {
+                sched_yield();
+            }
+            atomic_fetch_add(counter, 1);
+        }
+        _exit(0);
+    }
+
+    for (int i = 0; i < ROUNDS; i++) {
+        // if either side doesn't see the other's writes, it spins here
+        // until the test times out
+        while (atomic_load(counter) != 2 * i) {
+            sched_yield();
+        }
+        atomic_fetch_add(counter, 1);
+    }
+
+    int status;
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fail: %s: child status %x\n", name, status);
+        return -1;
+    }
+    if (atomic_load(counter) != 2 * ROUNDS) {
+        printf("fail: %s: counter is %d, want %d\n", name, atomic_load(counter), 2 * ROUNDS);
+        return -1;
+    }
+    return 0;
+}
+
+static atomic_int *mapFd(const char *name, int fd)
+{
+    void *p = mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    if (p == MAP_FAILED) {
+        perror(name);
+        return NULL;
+    }
+    return p;
+}
+
+int main()
+{
+    atomic_int *anon = mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_SHARED | MAP_ANONYMOUS, -1, 0);
+    if (anon == MAP_FAILED) {
+        perror("mmap MAP_SHARED|MAP_ANONYMOUS");
+        return 1;
+    }
+    if (pingPong("MAP_SHARED|MAP_ANONYMOUS", anon) < 0) {
+        return 1;
+    }
+
+    int zero = open("/dev/zero", O_RDWR);
+    if (zero < 0) {
+        perror("open /dev/zero");
+        return 1;
+    }
+    atomic_int *counter = mapFd("mmap /dev/zero", zero);
+    if (counter == NULL || pingPong("/dev/zero", counter) < 0) {
+        return 1;
+    }
+
+    int shm = shm_open("/shmfork", O_RDWR | O_CREAT | O_EXCL, 0600);
+    if (shm < 0) {
+        perror("shm_open");
+        return 1;
+    }
+    shm_unlink("/shmfork");
+    if (ftruncate(shm, 4096) < 0) {
+        perror("ftruncate");
+        return 1;
+    }
+    counter = mapFd("mmap shm", shm);
+    if (counter == NULL || pingPong("shm_open", counter) < 0) {
+        return 1;
+    }
+
+    // the child's exit dropped its references, the parent's pages stay
+    if (atomic_load(anon) != 2 * ROUNDS) {
+        printf("fail: the anonymous mapping lost its contents\n");
+        return 1;
+    }
+
+    // a partial munmap in the child leaves the rest shared
+    char *two = mmap(NULL, 8192, PROT_READ | PROT_WRITE, MAP_SHARED | MAP_ANONYMOUS, -1, 0);
+    if (two == MAP_FAILED) {
+        perror("mmap");
+        return 1;
+    }
+    pid_t pid = fork();
+    if (pid == 0) {
+        munmap(two, 4096);
+        two[4096] = 42;
+        _exit(0);
+    }
+    waitpid(pid, NULL, 0);
+    if (two[4096] != 42) {
+        printf("fail: a write after a partial munmap in the child wasn't shared\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
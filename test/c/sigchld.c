@@ -0,0 +1,118 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// A child which exits or is killed sends SIGCHLD to its parent with
+// CLD_EXITED or CLD_KILLED and its pid, uid and status. No SIGCHLD is sent
+// when it's ignored, and SA_NOCLDWAIT reaps the child without suppressing it.
+
+static volatile siginfo_t got;
+static volatile int count;
+
+static void handler(int sig, siginfo_t *info, void *ucontext)
+{
+    got = *info;
+    count++;
+}
+
+// expect waits for the SIGCHLD from pid and checks it.
+static int expect(pid_t pid, int code, int status, const char *what)
+{
+    sigset_t empty;
+    sigemptyset(&empty);
+    while (count == 0) {
+        sigsuspend(&empty);
+    }
+    if (got.si_signo != SIGCHLD || got.si_code != code || got.si_pid != pid || got.si_uid != getuid() ||
+        got.si_status != status) {
+        printf("fail: %s: got signo %d code %d pid %d uid %d status %d, want code %d pid %d status %d\n", what,
+               got.si_signo, got.si_code, got.si_pid, got.si_uid, got.si_status, code, pid, status);
+        return -1;
+    }
+    count = 0;
+    return 0;
+}
+
+int main()
+{
+    // SIGCHLD stays blocked except while waiting for it
+    sigset_t set;
+    sigemptyset(&set);
+    sigaddset(&set, SIGCHLD);
+    sigprocmask(SIG_BLOCK, &set, NULL);
+
+    struct sigaction sa;
+    memset(&sa, 0, sizeof(sa));
+    sa.sa_sigaction = handler;
+    sa.sa_flags = SA_SIGINFO;
+    sigaction(SIGCHLD, &sa, NULL);
+
+    // a normal exit
+    pid_t pid = fork();
+    if (pid == 0) {
+        _exit(42);
+    }
+    if (expect(pid, CLD_EXITED, 42, "exit") < 0) {
+        return 1;
+    }
+    int status;
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 42) {
+        printf("fail: waitpid returned status %x for the exited child\n", status);
+        return 1;
+    }
+
+    // a killed child
+    pid = fork();
+    if (pid == 0) {
+        for (;;) {
+            pause();
+        }
+    }
+    kill(pid, SIGKILL);
+    if (expect(pid, CLD_KILLED, SIGKILL, "kill") < 0) {
+        return 1;
+    }
+    if (waitpid(pid, &status, 0) != pid || !WIFSIGNALED(status) || WTERMSIG(status) != SIGKILL) {
+        printf("fail: waitpid returned status %x for the killed child\n", status);
+        return 1;
+    }
+
+    // SA_NOCLDWAIT still sends SIGCHLD but leaves no zombie
+    sa.sa_flags = SA_SIGINFO | SA_NOCLDWAIT;
+    sigaction(SIGCHLD, &sa, NULL);
+    pid = fork();
+    if (pid == 0) {
+        _exit(7);
+    }
+    if (expect(pid, CLD_EXITED, 7, "SA_NOCLDWAIT") < 0) {
+        return 1;
+    }
+    if (waitpid(pid, &status, 0) != -1 || errno != ECHILD) {
+        printf("fail: an SA_NOCLDWAIT child was left as a zombie\n");
+        return 1;
+    }
+
+    // an ignored SIGCHLD isn't queued and the child is reaped
+    signal(SIGCHLD, SIG_IGN);
+    pid = fork();
+    if (pid == 0) {
+        _exit(0);
+    }
+    if (waitpid(pid, &status, 0) != -1 || errno != ECHILD) {
+        printf("fail: a child was left as a zombie with SIGCHLD ignored\n");
+        return 1;
+    }
+    sigset_t pending;
+    sigpending(&pending);
+    if (sigismember(&pending, SIGCHLD)) {
+        printf("fail: an ignored SIGCHLD is pending\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,83 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/uio.h>
+#include <unistd.h>
+
+// sendmmsg, pwritev2 and splice to a broken pipe or socket fail with EPIPE and
+// raise SIGPIPE, unless MSG_NOSIGNAL is given.
+
+static volatile sig_atomic_t sigpipes;
+
+static void onSigpipe(int sig)
+{
+    sigpipes++;
+}
+
+static int expectEPIPE(ssize_t n, int signals, const char *what)
+{
+    if (n != -1 || errno != EPIPE) {
+        printf("fail: %s returned %zd (%s) instead of EPIPE\n", what, n, strerror(errno));
+        return 1;
+    }
+    if (sigpipes != signals) {
+        printf("fail: %s raised %d SIGPIPEs, expected %d\n", what, (int)sigpipes, signals);
+        return 1;
+    }
+    return 0;
+}
+
+int main()
+{
+    signal(SIGPIPE, onSigpipe);
+
+    char buf[] = "data";
+    struct iovec iov = {.iov_base = buf, .iov_len = sizeof(buf)};
+
+    int p[2];
+    if (pipe(p) < 0) {
+        perror("pipe");
+        return 1;
+    }
+    close(p[0]);
+
+    if (expectEPIPE(pwritev2(p[1], &iov, 1, -1, 0), 1, "pwritev2"))
+        return 1;
+
+    char path[] = "/tmp/sigpipeXXXXXX";
+    int fd = mkstemp(path);
+    unlink(path);
+    if (fd < 0 || write(fd, buf, sizeof(buf)) != sizeof(buf)) {
+        perror("file");
+        return 1;
+    }
+
+    loff_t off = 0;
+    if (expectEPIPE(splice(fd, &off, p[1], NULL, sizeof(buf), 0), 2, "splice"))
+        return 1;
+
+    int sv[2];
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, sv) < 0) {
+        perror("socketpair");
+        return 1;
+    }
+    close(sv[1]);
+
+    struct mmsghdr msg;
+    memset(&msg, 0, sizeof(msg));
+    msg.msg_hdr.msg_iov = &iov;
+    msg.msg_hdr.msg_iovlen = 1;
+
+    if (expectEPIPE(sendmmsg(sv[0], &msg, 1, 0), 3, "sendmmsg"))
+        return 1;
+    if (expectEPIPE(sendmmsg(sv[0], &msg, 1, MSG_NOSIGNAL), 3, "sendmmsg with MSG_NOSIGNAL"))
+        return 1;
+
+    printf("pass\n");
+    return 0;
+}
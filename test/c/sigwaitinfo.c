@@ -0,0 +1,73 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+static void handler(int sig)
+{
+}
+
+// sendLater forks a child that sends sig to the parent after a short delay.
+static pid_t sendLater(int sig)
+{
+    pid_t parent = getpid();
+    pid_t pid = fork();
+    if (pid == 0) {
+        usleep(100000);
+        kill(parent, sig);
+        _exit(0);
+    }
+    return pid;
+}
+
+int main()
+{
+    sigset_t set;
+    sigemptyset(&set);
+    sigaddset(&set, SIGUSR1);
+    sigprocmask(SIG_BLOCK, &set, NULL);
+
+    // a zero timeout only polls
+    struct timespec zero = {0, 0};
+    siginfo_t info;
+    if (sigtimedwait(&set, &info, &zero) != -1 || errno != EAGAIN) {
+        printf("fail: expected EAGAIN from a zero timeout poll\n");
+        return 1;
+    }
+    raise(SIGUSR1);
+    if (sigtimedwait(&set, &info, &zero) != SIGUSR1) {
+        printf("fail: zero timeout poll missed a pending signal\n");
+        return 1;
+    }
+
+    // no timeout waits until the signal arrives
+    pid_t child = sendLater(SIGUSR1);
+    if (sigwaitinfo(&set, &info) != SIGUSR1 || info.si_pid != child) {
+        printf("fail: sigwaitinfo didn't return the delivered signal\n");
+        return 1;
+    }
+    waitpid(child, NULL, 0);
+
+    // the same through rt_sigtimedwait with a NULL timeout
+    child = sendLater(SIGUSR1);
+    if (sigtimedwait(&set, &info, NULL) != SIGUSR1) {
+        printf("fail: sigtimedwait with a NULL timeout didn't return the signal\n");
+        return 1;
+    }
+    waitpid(child, NULL, 0);
+
+    // a handled signal outside the set interrupts the wait
+    signal(SIGUSR2, handler);
+    child = sendLater(SIGUSR2);
+    if (sigwaitinfo(&set, &info) != -1 || errno != EINTR) {
+        printf("fail: expected EINTR when another signal is handled\n");
+        return 1;
+    }
+    waitpid(child, NULL, 0);
+
+    printf("pass\n");
+    return 0;
+}
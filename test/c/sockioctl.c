@@ -0,0 +1,86 @@
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <errno.h>
+#include <net/if.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/ioctl.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+int main()
+{
+    int srv = socket(AF_INET, SOCK_STREAM, 0);
+    struct sockaddr_in addr = {.sin_family = AF_INET};
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    socklen_t alen = sizeof(addr);
+    if (srv < 0 || bind(srv, (struct sockaddr *)&addr, sizeof(addr)) < 0 || listen(srv, 1) < 0 ||
+        getsockname(srv, (struct sockaddr *)&addr, &alen) < 0) {
+        perror("listen");
+        return 1;
+    }
+
+    int cli = socket(AF_INET, SOCK_STREAM, 0);
+    if (cli < 0 || connect(cli, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        perror("connect");
+        return 1;
+    }
+    int conn = accept(srv, NULL, NULL);
+    if (conn < 0) {
+        perror("accept");
+        return 1;
+    }
+
+    // FIONREAD reports the bytes waiting to be read
+    int avail = -1;
+    if (ioctl(conn, FIONREAD, &avail) < 0 || avail != 0) {
+        printf("fail: FIONREAD %d on an empty socket\n", avail);
+        return 1;
+    }
+    if (write(cli, "hello", 5) != 5) {
+        perror("write");
+        return 1;
+    }
+    for (int i = 0; i < 100 && avail != 5; i++) {
+        if (ioctl(conn, FIONREAD, &avail) < 0) {
+            perror("FIONREAD");
+            return 1;
+        }
+        usleep(10000);
+    }
+    if (avail != 5) {
+        printf("fail: FIONREAD %d, expected 5\n", avail);
+        return 1;
+    }
+
+    // the interface ioctls see the loopback interface
+    struct ifreq ifr;
+    memset(&ifr, 0, sizeof(ifr));
+    ifr.ifr_ifindex = if_nametoindex("lo");
+    if (ifr.ifr_ifindex == 0 || ioctl(cli, SIOCGIFNAME, &ifr) < 0 || strcmp(ifr.ifr_name, "lo") != 0) {
+        printf("fail: SIOCGIFNAME for lo\n");
+        return 1;
+    }
+    if (ioctl(cli, SIOCGIFADDR, &ifr) < 0 ||
+        ((struct sockaddr_in *)&ifr.ifr_addr)->sin_addr.s_addr != htonl(INADDR_LOOPBACK)) {
+        printf("fail: SIOCGIFADDR for lo\n");
+        return 1;
+    }
+
+    struct ifreq ifrs[32];
+    struct ifconf ifc = {.ifc_len = sizeof(ifrs), .ifc_req = ifrs};
+    if (ioctl(cli, SIOCGIFCONF, &ifc) < 0 || ifc.ifc_len < (int)sizeof(struct ifreq)) {
+        printf("fail: SIOCGIFCONF returned no interfaces\n");
+        return 1;
+    }
+
+    // unknown ioctls are ENOTTY, as on Linux
+    if (ioctl(cli, 0x1234, &avail) != -1 || errno != ENOTTY) {
+        printf("fail: unknown socket ioctl didn't fail with ENOTTY\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,85 @@
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+// check calls getsockname or getpeername with an addrlen of len and checks
+// that at most len bytes are written and the full length is returned.
+static int check(int fd, int peer, socklen_t len, const char *what)
+{
+    unsigned char buf[256];
+    memset(buf, 0xaa, sizeof(buf));
+    socklen_t addrlen = len;
+    int ret = peer ? getpeername(fd, (struct sockaddr *)buf, &addrlen)
+                   : getsockname(fd, (struct sockaddr *)buf, &addrlen);
+    if (ret < 0) {
+        perror(what);
+        return -1;
+    }
+    if (addrlen != sizeof(struct sockaddr_in6)) {
+        printf("fail: %s with addrlen %u returned length %u\n", what, len, addrlen);
+        return -1;
+    }
+    for (size_t i = len; i < sizeof(buf); i++) {
+        if (buf[i] != 0xaa) {
+            printf("fail: %s with addrlen %u wrote byte %zu\n", what, len, i);
+            return -1;
+        }
+    }
+    if (len >= 2 && ((struct sockaddr *)buf)->sa_family != AF_INET6) {
+        printf("fail: %s returned family %d\n", what, ((struct sockaddr *)buf)->sa_family);
+        return -1;
+    }
+    return 0;
+}
+
+int main()
+{
+    int srv = socket(AF_INET6, SOCK_STREAM, 0);
+    int off = 0;
+    struct sockaddr_in6 sa = {.sin6_family = AF_INET6, .sin6_addr = IN6ADDR_ANY_INIT};
+    socklen_t salen = sizeof(sa);
+    if (srv < 0 || setsockopt(srv, IPPROTO_IPV6, IPV6_V6ONLY, &off, sizeof(off)) < 0 ||
+        bind(srv, (struct sockaddr *)&sa, sizeof(sa)) < 0 || listen(srv, 1) < 0 ||
+        getsockname(srv, (struct sockaddr *)&sa, &salen) < 0) {
+        perror("server");
+        return 1;
+    }
+
+    // connect over IPv4 so the server sees a v4-mapped peer
+    int cli = socket(AF_INET, SOCK_STREAM, 0);
+    struct sockaddr_in sin = {.sin_family = AF_INET, .sin_port = sa.sin6_port};
+    sin.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    if (cli < 0 || connect(cli, (struct sockaddr *)&sin, sizeof(sin)) < 0) {
+        perror("connect");
+        return 1;
+    }
+    int conn = accept(srv, NULL, NULL);
+    if (conn < 0) {
+        perror("accept");
+        return 1;
+    }
+
+    socklen_t lens[] = {0, 4, 128};
+    for (int i = 0; i < 3; i++) {
+        if (check(conn, 0, lens[i], "getsockname") < 0 || check(conn, 1, lens[i], "getpeername") < 0) {
+            return 1;
+        }
+    }
+
+    struct sockaddr_in6 peer;
+    socklen_t peerlen = sizeof(peer);
+    if (getpeername(conn, (struct sockaddr *)&peer, &peerlen) < 0 || !IN6_IS_ADDR_V4MAPPED(&peer.sin6_addr)) {
+        printf("fail: peer isn't v4-mapped\n");
+        return 1;
+    }
+
+    close(conn);
+    close(cli);
+    close(srv);
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,144 @@
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/time.h>
+#include <time.h>
+#include <unistd.h>
+
+static long long nowMs()
+{
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec * 1000LL + ts.tv_nsec / 1000000;
+}
+
+// setTimeout sets SO_RCVTIMEO or SO_SNDTIMEO to 200ms and checks that
+// getsockopt reads back the same value.
+static int setTimeout(int fd, int opt)
+{
+    struct timeval tv = {0, 200000};
+    if (setsockopt(fd, SOL_SOCKET, opt, &tv, sizeof(tv)) < 0) {
+        perror("setsockopt");
+        return 1;
+    }
+
+    struct timeval got;
+    socklen_t len = sizeof(got);
+    if (getsockopt(fd, SOL_SOCKET, opt, &got, &len) < 0 || len != sizeof(got) ||
+        got.tv_sec != 0 || got.tv_usec != 200000) {
+        printf("fail: getsockopt returned %ld.%06ld\n", (long)got.tv_sec, (long)got.tv_usec);
+        return 1;
+    }
+    return 0;
+}
+
+// timedOut checks that a blocking call failed with EAGAIN after roughly the
+// 200ms timeout.
+static int timedOut(ssize_t res, long long start, const char *what)
+{
+    long long elapsed = nowMs() - start;
+    if (res != -1 || (errno != EAGAIN && errno != EWOULDBLOCK)) {
+        printf("fail: %s returned %zd errno %d instead of timing out\n", what, res, errno);
+        return 1;
+    }
+    if (elapsed < 150 || elapsed > 2000) {
+        printf("fail: %s timed out after %lldms\n", what, elapsed);
+        return 1;
+    }
+    return 0;
+}
+
+int main()
+{
+    char buf[4096];
+
+    // recv on a unix socket
+    int sv[2];
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, sv) < 0) {
+        perror("socketpair");
+        return 1;
+    }
+    if (setTimeout(sv[0], SO_RCVTIMEO)) {
+        return 1;
+    }
+    long long start = nowMs();
+    if (timedOut(recv(sv[0], buf, sizeof(buf), 0), start, "unix recv")) {
+        return 1;
+    }
+
+    // send on a unix socket with a full buffer
+    if (setTimeout(sv[1], SO_SNDTIMEO)) {
+        return 1;
+    }
+    fcntl(sv[1], F_SETFL, O_NONBLOCK);
+    while (write(sv[1], buf, sizeof(buf)) > 0) {
+    }
+    fcntl(sv[1], F_SETFL, 0);
+    start = nowMs();
+    if (timedOut(send(sv[1], buf, sizeof(buf), 0), start, "unix send")) {
+        return 1;
+    }
+    close(sv[0]);
+    close(sv[1]);
+
+    // out of range microseconds
+    int s = socket(AF_INET, SOCK_STREAM, 0);
+    struct timeval bad = {0, 1000000};
+    if (setsockopt(s, SOL_SOCKET, SO_RCVTIMEO, &bad, sizeof(bad)) != -1 || errno != EDOM) {
+        printf("fail: expected EDOM for tv_usec out of range\n");
+        return 1;
+    }
+
+    // accept and recv on tcp sockets
+    struct sockaddr_in addr = {.sin_family = AF_INET};
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    socklen_t alen = sizeof(addr);
+    if (bind(s, (struct sockaddr *)&addr, sizeof(addr)) < 0 || listen(s, 1) < 0 ||
+        getsockname(s, (struct sockaddr *)&addr, &alen) < 0) {
+        perror("listen");
+        return 1;
+    }
+    if (setTimeout(s, SO_RCVTIMEO)) {
+        return 1;
+    }
+    start = nowMs();
+    if (timedOut(accept(s, NULL, NULL), start, "accept")) {
+        return 1;
+    }
+
+    int cli = socket(AF_INET, SOCK_STREAM, 0);
+    if (cli < 0 || connect(cli, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        perror("connect");
+        return 1;
+    }
+    int conn = accept(s, NULL, NULL);
+    if (conn < 0) {
+        perror("accept");
+        return 1;
+    }
+    if (setTimeout(conn, SO_RCVTIMEO)) {
+        return 1;
+    }
+    start = nowMs();
+    if (timedOut(read(conn, buf, sizeof(buf)), start, "tcp read")) {
+        return 1;
+    }
+
+    // data that arrives in time is still returned
+    write(cli, "x", 1);
+    if (recv(conn, buf, sizeof(buf), 0) != 1) {
+        printf("fail: recv with a timeout lost data\n");
+        return 1;
+    }
+
+    close(conn);
+    close(cli);
+    close(s);
+    printf("pass\n");
+    return 0;
+}
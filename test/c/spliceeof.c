@@ -0,0 +1,86 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+int main()
+{
+    char path[] = "/tmp/spliceeofXXXXXX";
+    int fd = mkstemp(path);
+    if (fd < 0) {
+        perror("mkstemp");
+        return 1;
+    }
+    unlink(path);
+
+    const char *msg = "splice me";
+    write(fd, msg, strlen(msg));
+
+    int p[2];
+    pipe(p);
+
+    // the file offset is at the end, so there's nothing left to splice
+    ssize_t n = splice(fd, NULL, p[1], NULL, 64, 0);
+    if (n != 0) {
+        printf("fail: splice from a fully read file returned %zd\n", n);
+        return 1;
+    }
+
+    // splicing from the current offset moves it forward
+    lseek(fd, 0, SEEK_SET);
+    n = splice(fd, NULL, p[1], NULL, 64, 0);
+    if (n != (ssize_t)strlen(msg) || lseek(fd, 0, SEEK_CUR) != n) {
+        printf("fail: splice from offset 0 returned %zd\n", n);
+        return 1;
+    }
+
+    // an explicit offset doesn't touch the file offset
+    loff_t off = 7;
+    n = splice(fd, &off, p[1], NULL, 64, 0);
+    if (n != 2 || off != 9 || lseek(fd, 0, SEEK_CUR) != (off_t)strlen(msg)) {
+        printf("fail: splice at offset 7 returned %zd, off %lld\n", n, (long long)off);
+        return 1;
+    }
+
+    char buf[64] = {0};
+    read(p[0], buf, sizeof(buf));
+    if (strcmp(buf, "splice meme") != 0) {
+        printf("fail: pipe holds %s\n", buf);
+        return 1;
+    }
+
+    // an empty pipe with a live writer is only a transient condition
+    n = splice(p[0], NULL, fd, NULL, 64, SPLICE_F_NONBLOCK);
+    if (n != -1 || errno != EAGAIN) {
+        printf("fail: expected EAGAIN from an empty pipe, got %zd\n", n);
+        return 1;
+    }
+
+    // with the writers gone it is end of input
+    close(p[1]);
+    n = splice(p[0], NULL, fd, NULL, 64, 0);
+    if (n != 0) {
+        printf("fail: splice from a closed empty pipe returned %zd\n", n);
+        return 1;
+    }
+
+    // a blocking splice waits for the writer
+    pipe(p);
+    if (fork() == 0) {
+        usleep(50 * 1000);
+        write(p[1], "late", 4);
+        _exit(0);
+    }
+    n = splice(p[0], NULL, fd, NULL, 64, 0);
+    if (n != 4) {
+        printf("fail: blocking splice returned %zd\n", n);
+        return 1;
+    }
+
+    close(fd);
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,111 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#define SIZE (200 * 1024 + 123)
+
+static char in[SIZE], out[SIZE];
+
+int main()
+{
+    const char *srcPath = "/tmp/splicefile_src";
+    const char *dstPath = "/tmp/splicefile_dst";
+
+    for (int i = 0; i < SIZE; i++) {
+        in[i] = (char)(i * 13 + 5);
+    }
+
+    int src = open(srcPath, O_RDWR | O_CREAT | O_TRUNC, 0644);
+    int dst = open(dstPath, O_RDWR | O_CREAT | O_TRUNC, 0644);
+    if (src < 0 || dst < 0 || write(src, in, SIZE) != SIZE) {
+        perror("setup");
+        return 1;
+    }
+    lseek(src, 0, SEEK_SET);
+
+    int p[2];
+    pipe(p);
+
+    // move the file through the pipe using the file offsets
+    long total = 0;
+    for (;;) {
+        ssize_t n = splice(src, NULL, p[1], NULL, 16384, 0);
+        if (n < 0) {
+            perror("splice file to pipe");
+            return 1;
+        }
+        if (n == 0) {
+            break;
+        }
+
+        while (n > 0) {
+            ssize_t m = splice(p[0], NULL, dst, NULL, n, 0);
+            if (m <= 0) {
+                perror("splice pipe to file");
+                return 1;
+            }
+            n -= m;
+            total += m;
+        }
+    }
+
+    if (total != SIZE || lseek(src, 0, SEEK_CUR) != SIZE || lseek(dst, 0, SEEK_CUR) != SIZE) {
+        printf("fail: moved %ld bytes, offsets %ld/%ld\n", total,
+               (long)lseek(src, 0, SEEK_CUR), (long)lseek(dst, 0, SEEK_CUR));
+        return 1;
+    }
+
+    if (pread(dst, out, SIZE, 0) != SIZE || memcmp(in, out, SIZE) != 0) {
+        printf("fail: destination differs from source\n");
+        return 1;
+    }
+
+    // explicit offsets are updated while the file offsets stay put
+    loff_t inOff = 1000, outOff = 5;
+    if (splice(src, &inOff, p[1], NULL, 100, 0) != 100 || inOff != 1100) {
+        printf("fail: splice with an input offset, inOff %lld\n", (long long)inOff);
+        return 1;
+    }
+    if (splice(p[0], NULL, dst, &outOff, 100, 0) != 100 || outOff != 105) {
+        printf("fail: splice with an output offset, outOff %lld\n", (long long)outOff);
+        return 1;
+    }
+    if (lseek(src, 0, SEEK_CUR) != SIZE || lseek(dst, 0, SEEK_CUR) != SIZE) {
+        printf("fail: explicit offsets moved the file offsets\n");
+        return 1;
+    }
+
+    char buf[100];
+    pread(dst, buf, 100, 5);
+    if (memcmp(buf, in + 1000, 100) != 0) {
+        printf("fail: data spliced at an offset differs\n");
+        return 1;
+    }
+
+    // a pipe can't take an offset
+    loff_t off = 0;
+    if (splice(src, NULL, p[1], &off, 1, 0) != -1 || errno != ESPIPE) {
+        printf("fail: expected ESPIPE for a pipe offset\n");
+        return 1;
+    }
+
+    // nonblocking splice into a full pipe
+    fcntl(p[1], F_SETFL, O_NONBLOCK);
+    while (write(p[1], in, 4096) > 0) {
+    }
+    if (splice(src, &inOff, p[1], NULL, 100, SPLICE_F_NONBLOCK) != -1 || errno != EAGAIN) {
+        printf("fail: expected EAGAIN splicing into a full pipe\n");
+        return 1;
+    }
+
+    close(src);
+    close(dst);
+    unlink(srcPath);
+    unlink(dstPath);
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,80 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+// SPLICE_F_NONBLOCK makes splice and tee return EAGAIN instead of waiting,
+// even though the pipes themselves are blocking.
+
+static void hang(int sig)
+{
+    printf("fail: splice with SPLICE_F_NONBLOCK blocked\n");
+    exit(1);
+}
+
+static int expectEAGAIN(ssize_t n, const char *what)
+{
+    if (n != -1 || errno != EAGAIN) {
+        printf("fail: %s returned %zd (errno %d) instead of EAGAIN\n", what, n, errno);
+        return 1;
+    }
+    return 0;
+}
+
+int main()
+{
+    signal(SIGALRM, hang);
+    alarm(5);
+
+    int in[2], out[2];
+    if (pipe(in) < 0 || pipe(out) < 0) {
+        perror("pipe");
+        return 1;
+    }
+
+    char path[] = "/tmp/splicenbXXXXXX";
+    int fd = mkstemp(path);
+    unlink(path);
+    if (fd < 0 || write(fd, "data", 4) != 4) {
+        perror("file");
+        return 1;
+    }
+
+    // nothing to read from the empty pipe
+    if (expectEAGAIN(splice(in[0], NULL, out[1], NULL, 4096, SPLICE_F_NONBLOCK), "pipe to pipe splice") ||
+        expectEAGAIN(splice(in[0], NULL, fd, NULL, 4096, SPLICE_F_NONBLOCK), "pipe to file splice") ||
+        expectEAGAIN(tee(in[0], out[1], 4096, SPLICE_F_NONBLOCK), "tee")) {
+        return 1;
+    }
+
+    // no room in the full pipe
+    long size = fcntl(out[1], F_GETPIPE_SZ);
+    char *buf = calloc(1, size);
+    if (write(out[1], buf, size) != size) {
+        perror("fill");
+        return 1;
+    }
+    loff_t off = 0;
+    if (expectEAGAIN(splice(fd, &off, out[1], NULL, 4, SPLICE_F_NONBLOCK), "file to pipe splice")) {
+        return 1;
+    }
+
+    // the pipes are still blocking
+    if ((fcntl(in[0], F_GETFL) & O_NONBLOCK) || (fcntl(out[1], F_GETFL) & O_NONBLOCK)) {
+        printf("fail: SPLICE_F_NONBLOCK changed the file flags\n");
+        return 1;
+    }
+
+    // and it still moves data that is there
+    if (write(in[1], "abc", 3) != 3 || splice(in[0], NULL, fd, NULL, 3, SPLICE_F_NONBLOCK) != 3) {
+        printf("fail: nonblocking splice of queued data\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
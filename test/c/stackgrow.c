@@ -0,0 +1,95 @@
+#define _GNU_SOURCE
+#include <setjmp.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/mman.h>
+#include <sys/resource.h>
+#include <unistd.h>
+
+static sigjmp_buf env;
+
+static void segv(int sig)
+{
+    siglongjmp(env, 1);
+}
+
+// touch writes to p and returns 0, or 1 if the write raised SIGSEGV.
+static int touch(volatile char *p)
+{
+    if (sigsetjmp(env, 1)) {
+        return 1;
+    }
+    *p = 's';
+    return *p != 's';
+}
+
+int main()
+{
+    size_t page = sysconf(_SC_PAGESIZE);
+
+    static char altstack[64 * 1024];
+    stack_t ss = {.ss_sp = altstack, .ss_size = sizeof(altstack)};
+    struct sigaction sa = {.sa_handler = segv, .sa_flags = SA_ONSTACK};
+    if (sigaltstack(&ss, NULL) < 0 || sigaction(SIGSEGV, &sa, NULL) < 0) {
+        perror("sigaction");
+        return 1;
+    }
+
+    struct rlimit rl;
+    getrlimit(RLIMIT_STACK, &rl);
+    rl.rlim_cur = 256 * page;
+    if (setrlimit(RLIMIT_STACK, &rl) < 0) {
+        perror("setrlimit");
+        return 1;
+    }
+
+    // keep the space below the stack free so nothing else lands there
+    char *base = mmap(NULL, 1024 * page, PROT_NONE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (base == MAP_FAILED || munmap(base, 1024 * page) < 0) {
+        perror("mmap reserve");
+        return 1;
+    }
+    char *stk = mmap(base + 1020 * page, 4 * page, PROT_READ | PROT_WRITE,
+                     MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED | MAP_GROWSDOWN, -1, 0);
+    if (stk != base + 1020 * page) {
+        perror("mmap");
+        return 1;
+    }
+    char *end = stk + 4 * page;
+
+    // a fault just below the mapping grows it
+    if (touch(stk - 1) || touch(stk - 100 * page)) {
+        printf("fail: fault below a MAP_GROWSDOWN mapping didn't grow it\n");
+        return 1;
+    }
+
+    // growing right up to RLIMIT_STACK is fine, one more page is not
+    if (touch(end - 256 * page)) {
+        printf("fail: growth within RLIMIT_STACK was refused\n");
+        return 1;
+    }
+    if (!touch(end - 257 * page)) {
+        printf("fail: growth beyond RLIMIT_STACK was allowed\n");
+        return 1;
+    }
+    munmap(end - 256 * page, 256 * page);
+
+    // the stack may not grow into the guard gap above another mapping
+    char *r = mmap(NULL, 64 * page, PROT_NONE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (r == MAP_FAILED ||
+        mmap(r, 4 * page, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED, -1, 0) != r ||
+        mmap(r + 8 * page, 4 * page, PROT_READ | PROT_WRITE,
+             MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED | MAP_GROWSDOWN, -1, 0) != r + 8 * page ||
+        munmap(r + 4 * page, 4 * page) < 0 || munmap(r + 12 * page, 52 * page) < 0) {
+        perror("mmap guard");
+        return 1;
+    }
+    if (!touch(r + 7 * page)) {
+        printf("fail: stack grew into the guard gap\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
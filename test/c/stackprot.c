@@ -0,0 +1,123 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+// mprotect on the main thread's stack after it grew by several MB, with and
+// without PROT_GROWSDOWN.
+
+#define FRAME (64 * 1024)
+#define DEPTH 96 // 6 MB of stack
+
+static char *deepest;
+
+static int recurse(int depth)
+{
+    volatile char buf[FRAME];
+    memset((char *)buf, depth, sizeof(buf));
+    if (depth == DEPTH) {
+        deepest = (char *)buf;
+        return buf[0];
+    }
+    return recurse(depth + 1) + buf[FRAME - 1];
+}
+
+// stackStart returns the lowest address of the [stack] mapping, and its
+// permissions in perms. The mapping may be split into several vmas by
+// mprotect, and only the one holding the stack top is labeled.
+static unsigned long stackStart(char *perms)
+{
+    FILE *maps = fopen("/proc/self/maps", "r");
+    char line[512];
+    unsigned long starts[512], ends[512];
+    char prots[512][5];
+    int n = 0, stack = -1;
+    while (maps && n < 512 && fgets(line, sizeof(line), maps)) {
+        if (sscanf(line, "%lx-%lx %4s", &starts[n], &ends[n], prots[n]) != 3) {
+            continue;
+        }
+        if (strstr(line, "[stack]")) {
+            stack = n;
+        }
+        n++;
+    }
+    if (maps) {
+        fclose(maps);
+    }
+    if (stack < 0) {
+        return 0;
+    }
+    while (stack > 0 && ends[stack - 1] == starts[stack]) {
+        stack--;
+    }
+    memcpy(perms, prots[stack], 5);
+    return starts[stack];
+}
+
+int main()
+{
+    long pagesz = sysconf(_SC_PAGESIZE);
+    char *top = (char *)&pagesz;
+
+    recurse(0);
+    if (top - deepest < DEPTH * FRAME) {
+        printf("fail: the stack only grew by %ld bytes\n", (long)(top - deepest));
+        return 1;
+    }
+
+    // protect part of the grown stack and then restore it
+    char *page = (char *)((unsigned long)(deepest + FRAME) & ~(pagesz - 1));
+    if (mprotect(page, 4 * pagesz, PROT_READ) < 0 || mprotect(page, 4 * pagesz, PROT_READ | PROT_WRITE) < 0) {
+        perror("mprotect");
+        return 1;
+    }
+
+    // like a JVM making its stack executable
+    if (mprotect(page, pagesz, PROT_READ | PROT_WRITE | PROT_EXEC) < 0 ||
+        mprotect(page, pagesz, PROT_READ | PROT_WRITE) < 0) {
+        perror("mprotect exec");
+        return 1;
+    }
+
+    // a range starting in the gap below the stack is not mapped
+    char perms[5];
+    unsigned long start = stackStart(perms);
+    if (start == 0) {
+        printf("fail: no [stack] in /proc/self/maps\n");
+        return 1;
+    }
+    if (mprotect((char *)start - 2 * pagesz, 4 * pagesz, PROT_READ | PROT_WRITE) != -1) {
+        printf("fail: mprotect starting below the stack succeeded\n");
+        return 1;
+    }
+
+    // PROT_GROWSDOWN extends the change down to the start of the stack vma,
+    // even from an address below it
+    if (mprotect((char *)start - 2 * pagesz, 4 * pagesz, PROT_READ | PROT_GROWSDOWN) < 0) {
+        perror("mprotect PROT_GROWSDOWN");
+        return 1;
+    }
+    if (stackStart(perms) != start || strncmp(perms, "r--", 3) != 0) {
+        printf("fail: PROT_GROWSDOWN didn't protect from the stack start, got %s\n", perms);
+        return 1;
+    }
+    if (mprotect((char *)start, 2 * pagesz, PROT_READ | PROT_WRITE | PROT_GROWSDOWN) < 0) {
+        perror("mprotect PROT_GROWSDOWN restore");
+        return 1;
+    }
+
+    // PROT_GROWSDOWN is only for growsdown mappings
+    char *heap = mmap(NULL, pagesz, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (mprotect(heap, pagesz, PROT_READ | PROT_GROWSDOWN) != -1) {
+        printf("fail: PROT_GROWSDOWN on a normal mapping succeeded\n");
+        return 1;
+    }
+
+    // the stack still works after all that
+    recurse(DEPTH / 2);
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,119 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/sysinfo.h>
+#include <sys/times.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+// times, sysinfo, /proc/uptime and /proc/loadavg report real accounting and
+// agree with each other.
+
+static void spin(double secs)
+{
+    struct timespec start, now;
+    clock_gettime(CLOCK_PROCESS_CPUTIME_ID, &start);
+    do {
+        clock_gettime(CLOCK_PROCESS_CPUTIME_ID, &now);
+    } while ((now.tv_sec - start.tv_sec) + (now.tv_nsec - start.tv_nsec) / 1e9 < secs);
+}
+
+int main()
+{
+    long hz = sysconf(_SC_CLK_TCK);
+
+    // times returns a tick count that moves forward and counts the caller's
+    // CPU time
+    struct tms t0, t1;
+    clock_t c0 = times(&t0);
+    spin(0.5);
+    clock_t c1 = times(&t1);
+    if (c0 == (clock_t)-1 || c0 == 0 || c1 <= c0) {
+        printf("fail: times returned %ld then %ld\n", (long)c0, (long)c1);
+        return 1;
+    }
+    if (times(NULL) < c1) {
+        printf("fail: times(NULL) didn't return the ticks\n");
+        return 1;
+    }
+    clock_t used = (t1.tms_utime + t1.tms_stime) - (t0.tms_utime + t0.tms_stime);
+    if (used < hz / 4) {
+        printf("fail: spinning 0.5s added only %ld ticks\n", (long)used);
+        return 1;
+    }
+
+    // reaped children are counted in the children's totals
+    pid_t pid = fork();
+    if (pid == 0) {
+        spin(0.5);
+        _exit(0);
+    }
+    waitpid(pid, NULL, 0);
+    times(&t1);
+    if (t1.tms_cutime + t1.tms_cstime < hz / 4) {
+        printf("fail: the reaped child's time isn't counted: %ld\n", (long)(t1.tms_cutime + t1.tms_cstime));
+        return 1;
+    }
+
+    // sysinfo
+    struct sysinfo si;
+    if (sysinfo(&si) < 0) {
+        perror("sysinfo");
+        return 1;
+    }
+    unsigned long long total = (unsigned long long)si.totalram * si.mem_unit;
+    unsigned long long freeram = (unsigned long long)si.freeram * si.mem_unit;
+    if (total == 0 || freeram == 0 || freeram >= total) {
+        printf("fail: totalram %llu freeram %llu\n", total, freeram);
+        return 1;
+    }
+
+    long pages, rss;
+    FILE *f = fopen("/proc/self/statm", "r");
+    if (!f || fscanf(f, "%ld %ld", &pages, &rss) != 2) {
+        printf("fail: can't read /proc/self/statm\n");
+        return 1;
+    }
+    fclose(f);
+    if (total - freeram < (unsigned long long)rss * sysconf(_SC_PAGESIZE)) {
+        printf("fail: used memory %llu is below this process's rss\n", total - freeram);
+        return 1;
+    }
+
+    // /proc/uptime agrees with sysinfo
+    double up, idle;
+    f = fopen("/proc/uptime", "r");
+    if (!f || fscanf(f, "%lf %lf", &up, &idle) != 2) {
+        printf("fail: can't parse /proc/uptime\n");
+        return 1;
+    }
+    fclose(f);
+    if (up < si.uptime - 1 || up > si.uptime + 2 || idle < 0) {
+        printf("fail: /proc/uptime %.2f, sysinfo uptime %ld\n", up, si.uptime);
+        return 1;
+    }
+
+    // /proc/loadavg agrees with sysinfo
+    double l1, l5, l15;
+    int running, threads, last;
+    f = fopen("/proc/loadavg", "r");
+    if (!f || fscanf(f, "%lf %lf %lf %d/%d %d", &l1, &l5, &l15, &running, &threads, &last) != 6) {
+        printf("fail: can't parse /proc/loadavg\n");
+        return 1;
+    }
+    fclose(f);
+    if (running < 1 || threads < running || last < getpid()) {
+        printf("fail: /proc/loadavg reports %d/%d %d\n", running, threads, last);
+        return 1;
+    }
+    double s1 = (double)si.loads[0] / (1 << SI_LOAD_SHIFT);
+    if (l1 < 0 || l1 < s1 - 1 || l1 > s1 + 1) {
+        printf("fail: /proc/loadavg %.2f, sysinfo %.2f\n", l1, s1);
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,109 @@
+#define _GNU_SOURCE
+#include <fcntl.h>
+#include <pthread.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#define BIT(sig) (1ULL << ((sig) - 1))
+
+static pid_t tids[2];
+static pthread_barrier_t ready, done;
+
+static void *worker(void *arg)
+{
+    tids[(long)arg] = syscall(SYS_gettid);
+    pthread_barrier_wait(&ready);
+    pthread_barrier_wait(&done);
+    return NULL;
+}
+
+// sigPnd reads the SigPnd and ShdPnd fields of /proc/self/task/<tid>/status.
+static int sigPnd(pid_t tid, unsigned long long *pnd, unsigned long long *shd)
+{
+    char path[64];
+    snprintf(path, sizeof(path), "/proc/self/task/%d/status", tid);
+    int fd = open(path, O_RDONLY);
+    if (fd < 0) {
+        perror(path);
+        return 1;
+    }
+
+    char buf[4096];
+    ssize_t n = read(fd, buf, sizeof(buf) - 1);
+    close(fd);
+    if (n <= 0) {
+        perror("read");
+        return 1;
+    }
+    buf[n] = 0;
+
+    char key[64];
+    snprintf(key, sizeof(key), "\nPid:\t%d\n", tid);
+    char *p = strstr(buf, key);
+    char *s = strstr(buf, "\nSigPnd:\t");
+    char *g = strstr(buf, "\nShdPnd:\t");
+    if (p == NULL || s == NULL || g == NULL) {
+        printf("fail: %s doesn't describe thread %d\n", path, tid);
+        return 1;
+    }
+    *pnd = strtoull(s + strlen("\nSigPnd:\t"), NULL, 16);
+    *shd = strtoull(g + strlen("\nShdPnd:\t"), NULL, 16);
+    return 0;
+}
+
+int main()
+{
+    // block SIGUSR1 everywhere so that it stays pending
+    sigset_t set;
+    sigemptyset(&set);
+    sigaddset(&set, SIGUSR1);
+    pthread_sigmask(SIG_BLOCK, &set, NULL);
+
+    pthread_barrier_init(&ready, NULL, 3);
+    pthread_barrier_init(&done, NULL, 3);
+    pthread_t th[2];
+    for (long i = 0; i < 2; i++) {
+        pthread_create(&th[i], NULL, worker, (void *)i);
+    }
+    pthread_barrier_wait(&ready);
+
+    if (syscall(SYS_tgkill, getpid(), tids[0], SIGUSR1) < 0) {
+        perror("tgkill");
+        return 1;
+    }
+
+    unsigned long long pnd0, shd0, pnd1, shd1;
+    if (sigPnd(tids[0], &pnd0, &shd0) || sigPnd(tids[1], &pnd1, &shd1)) {
+        return 1;
+    }
+    if (!(pnd0 & BIT(SIGUSR1))) {
+        printf("fail: SIGUSR1 not in the target thread's SigPnd %llx\n", pnd0);
+        return 1;
+    }
+    if ((pnd1 & BIT(SIGUSR1)) || (shd0 & BIT(SIGUSR1)) || (shd1 & BIT(SIGUSR1))) {
+        printf("fail: thread-directed SIGUSR1 leaked into other pending sets\n");
+        return 1;
+    }
+
+    // a process-directed signal shows up in ShdPnd of every thread instead
+    kill(getpid(), SIGUSR1);
+    if (sigPnd(tids[1], &pnd1, &shd1)) {
+        return 1;
+    }
+    if ((pnd1 & BIT(SIGUSR1)) || !(shd1 & BIT(SIGUSR1))) {
+        printf("fail: process-directed SIGUSR1 not in ShdPnd\n");
+        return 1;
+    }
+
+    signal(SIGUSR1, SIG_IGN);
+    pthread_barrier_wait(&done);
+    for (int i = 0; i < 2; i++) {
+        pthread_join(th[i], NULL);
+    }
+    printf("pass\n");
+    return 0;
+}
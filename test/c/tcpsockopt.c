@@ -0,0 +1,76 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <netinet/in.h>
+#include <netinet/tcp.h>
+#include <stdio.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+// roundTrip sets an int option and checks getsockopt reads it back.
+static int roundTrip(int fd, int level, int name, int val, const char *what)
+{
+    if (setsockopt(fd, level, name, &val, sizeof(val)) < 0) {
+        printf("fail: setsockopt(%s): %d\n", what, errno);
+        return 1;
+    }
+
+    int got = -1;
+    socklen_t len = sizeof(got);
+    if (getsockopt(fd, level, name, &got, &len) < 0 || len != sizeof(got) || got != val) {
+        printf("fail: getsockopt(%s) returned %d\n", what, got);
+        return 1;
+    }
+    return 0;
+}
+
+int main()
+{
+    int fd = socket(AF_INET, SOCK_STREAM, 0);
+    if (fd < 0) {
+        perror("socket");
+        return 1;
+    }
+
+    if (roundTrip(fd, SOL_TCP, TCP_NODELAY, 1, "TCP_NODELAY") ||
+        roundTrip(fd, SOL_TCP, TCP_NODELAY, 0, "TCP_NODELAY") ||
+        roundTrip(fd, SOL_SOCKET, SO_KEEPALIVE, 1, "SO_KEEPALIVE") ||
+        roundTrip(fd, SOL_TCP, TCP_KEEPIDLE, 30, "TCP_KEEPIDLE") ||
+        roundTrip(fd, SOL_TCP, TCP_KEEPINTVL, 5, "TCP_KEEPINTVL") ||
+        roundTrip(fd, SOL_TCP, TCP_KEEPCNT, 3, "TCP_KEEPCNT") ||
+        roundTrip(fd, SOL_TCP, TCP_USER_TIMEOUT, 1500, "TCP_USER_TIMEOUT")) {
+        return 1;
+    }
+
+    // the kernel doubles the buffer size to leave room for bookkeeping
+    int val = 65536;
+    int got = 0;
+    socklen_t len = sizeof(got);
+    if (setsockopt(fd, SOL_SOCKET, SO_RCVBUF, &val, sizeof(val)) < 0 ||
+        getsockopt(fd, SOL_SOCKET, SO_RCVBUF, &got, &len) < 0 || got < val) {
+        printf("fail: SO_RCVBUF is %d after setting %d\n", got, val);
+        return 1;
+    }
+
+    // a value shorter than an int is rejected
+    short s = 1;
+    if (setsockopt(fd, SOL_TCP, TCP_NODELAY, &s, sizeof(s)) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL for a short TCP_NODELAY\n");
+        return 1;
+    }
+
+    // unknown options aren't silently accepted
+    val = 1;
+    if (setsockopt(fd, SOL_TCP, 9999, &val, sizeof(val)) != -1 || errno != ENOPROTOOPT) {
+        printf("fail: expected ENOPROTOOPT from setsockopt\n");
+        return 1;
+    }
+    len = sizeof(got);
+    if (getsockopt(fd, SOL_TCP, 9999, &got, &len) != -1 || errno != ENOPROTOOPT) {
+        printf("fail: expected ENOPROTOOPT from getsockopt\n");
+        return 1;
+    }
+
+    close(fd);
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,175 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/uio.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define TOTAL (256 * 1024)
+
+static unsigned char pattern(long i)
+{
+    return (unsigned char)(i * 7 + 3);
+}
+
+// consume reads the whole stream from fd and checks it against the pattern.
+static int consume(int fd)
+{
+    unsigned char buf[4096];
+    long got = 0;
+    for (;;) {
+        ssize_t n = read(fd, buf, sizeof(buf));
+        if (n < 0) {
+            perror("read");
+            return 1;
+        }
+        if (n == 0) {
+            break;
+        }
+        for (ssize_t i = 0; i < n; i++) {
+            if (buf[i] != pattern(got + i)) {
+                printf("fail: mismatch at byte %ld\n", got + i);
+                return 1;
+            }
+        }
+        got += n;
+    }
+
+    if (got != TOTAL) {
+        printf("fail: consumer got %ld bytes\n", got);
+        return 1;
+    }
+    return 0;
+}
+
+static int vmspliceTest(void)
+{
+    int fds[2];
+    if (pipe(fds) < 0) {
+        perror("pipe");
+        return 1;
+    }
+
+    char a[] = "hello ", b[] = "world";
+    struct iovec iov[2] = {{a, 6}, {b, 5}};
+    if (vmsplice(fds[1], iov, 2, 0) != 11) {
+        perror("vmsplice write");
+        return 1;
+    }
+
+    char out[16] = {0};
+    struct iovec oiov = {out, sizeof(out)};
+    if (vmsplice(fds[0], &oiov, 1, 0) != 11 || strcmp(out, "hello world") != 0) {
+        printf("fail: vmsplice read got %s\n", out);
+        return 1;
+    }
+
+    if (vmsplice(fds[0], &oiov, 1, SPLICE_F_NONBLOCK) != -1 || errno != EAGAIN) {
+        printf("fail: expected EAGAIN from an empty pipe\n");
+        return 1;
+    }
+
+    close(fds[0]);
+    close(fds[1]);
+    return 0;
+}
+
+int main()
+{
+    if (vmspliceTest()) {
+        return 1;
+    }
+
+    int in[2], out1[2], out2[2];
+    if (pipe(in) < 0 || pipe(out1) < 0 || pipe(out2) < 0) {
+        perror("pipe");
+        return 1;
+    }
+
+    // tee on an empty pipe with SPLICE_F_NONBLOCK
+    if (tee(in[0], out1[1], 4096, SPLICE_F_NONBLOCK) != -1 || errno != EAGAIN) {
+        printf("fail: expected EAGAIN from tee on an empty pipe\n");
+        return 1;
+    }
+
+    pid_t producer = fork();
+    if (producer == 0) {
+        close(in[0]);
+        close(out1[0]);
+        close(out1[1]);
+        close(out2[0]);
+        close(out2[1]);
+        unsigned char buf[1000];
+        long sent = 0;
+        while (sent < TOTAL) {
+            long n = TOTAL - sent < (long)sizeof(buf) ? TOTAL - sent : (long)sizeof(buf);
+            for (long i = 0; i < n; i++) {
+                buf[i] = pattern(sent + i);
+            }
+            if (write(in[1], buf, n) != n) {
+                _exit(1);
+            }
+            sent += n;
+        }
+        _exit(0);
+    }
+    close(in[1]);
+
+    pid_t consumers[2];
+    int *outs[2] = {out1, out2};
+    for (int i = 0; i < 2; i++) {
+        consumers[i] = fork();
+        if (consumers[i] == 0) {
+            close(in[0]);
+            close(out1[1]);
+            close(out2[1]);
+            _exit(consume(outs[i][0]));
+        }
+        close(outs[i][0]);
+    }
+
+    // classic tee: duplicate into out1, then move the same bytes into out2
+    for (;;) {
+        ssize_t n = tee(in[0], out1[1], TOTAL, 0);
+        if (n < 0) {
+            perror("tee");
+            return 1;
+        }
+        if (n == 0) {
+            break;
+        }
+        while (n > 0) {
+            ssize_t m = splice(in[0], NULL, out2[1], NULL, n, 0);
+            if (m <= 0) {
+                // fall back to read/write when pipe to pipe splice isn't there
+                char buf[4096];
+                m = read(in[0], buf, n < (ssize_t)sizeof(buf) ? n : (ssize_t)sizeof(buf));
+                if (m <= 0 || write(out2[1], buf, m) != m) {
+                    perror("copy");
+                    return 1;
+                }
+            }
+            n -= m;
+        }
+    }
+    close(out1[1]);
+    close(out2[1]);
+
+    int status, failed = 0;
+    for (int i = 0; i < 3; i++) {
+        wait(&status);
+        if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+            failed = 1;
+        }
+    }
+
+    if (failed) {
+        printf("fail: a child failed\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
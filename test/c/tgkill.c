@@ -0,0 +1,119 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <pthread.h>
+#include <signal.h>
+#include <stdio.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+static volatile pid_t handledBy;
+static volatile int handled;
+static pid_t workerTid;
+static pthread_barrier_t ready;
+static volatile int stop;
+
+static void handler(int sig)
+{
+    handledBy = syscall(SYS_gettid);
+    handled++;
+}
+
+static void *worker(void *arg)
+{
+    workerTid = syscall(SYS_gettid);
+    pthread_barrier_wait(&ready);
+    while (!stop) {
+        usleep(1000);
+    }
+    return NULL;
+}
+
+// waitHandled waits for the handler to run count times in total.
+static int waitHandled(int count)
+{
+    for (int i = 0; i < 5000 && handled < count; i++) {
+        usleep(1000);
+    }
+    return handled == count;
+}
+
+int main()
+{
+    signal(SIGUSR1, handler);
+
+    // keep the signal away from the main thread so a group-wide delivery
+    // would be visible as the wrong handler thread
+    sigset_t set;
+    sigemptyset(&set);
+    sigaddset(&set, SIGUSR1);
+
+    pthread_barrier_init(&ready, NULL, 2);
+    pthread_t th;
+    pthread_create(&th, NULL, worker, NULL);
+    pthread_barrier_wait(&ready);
+    pthread_sigmask(SIG_BLOCK, &set, NULL);
+
+    if (syscall(SYS_tgkill, getpid(), workerTid, SIGUSR1) < 0) {
+        perror("tgkill");
+        return 1;
+    }
+    if (!waitHandled(1) || handledBy != workerTid) {
+        printf("fail: tgkill wasn't delivered to the target thread\n");
+        return 1;
+    }
+
+    if (syscall(SYS_tkill, workerTid, SIGUSR1) < 0) {
+        perror("tkill");
+        return 1;
+    }
+    if (!waitHandled(2) || handledBy != workerTid) {
+        printf("fail: tkill wasn't delivered to the target thread\n");
+        return 1;
+    }
+
+    // signal 0 only checks that the thread exists
+    if (syscall(SYS_tgkill, getpid(), workerTid, 0) != 0 || syscall(SYS_tkill, workerTid, 0) != 0) {
+        printf("fail: signal 0 probe failed\n");
+        return 1;
+    }
+
+    // the tid must belong to tgid
+    pid_t child = fork();
+    if (child == 0) {
+        pause();
+        _exit(0);
+    }
+    if (syscall(SYS_tgkill, child, workerTid, SIGUSR1) != -1 || errno != ESRCH) {
+        printf("fail: expected ESRCH for a tid outside tgid\n");
+        return 1;
+    }
+    if (syscall(SYS_tgkill, getpid(), child, SIGUSR1) != -1 || errno != ESRCH) {
+        printf("fail: expected ESRCH for another process's tid\n");
+        return 1;
+    }
+    kill(child, SIGKILL);
+    waitpid(child, NULL, 0);
+
+    // invalid arguments
+    if (syscall(SYS_tgkill, getpid(), workerTid, -1) != -1 || errno != EINVAL ||
+        syscall(SYS_tgkill, getpid(), workerTid, 65) != -1 || errno != EINVAL ||
+        syscall(SYS_tkill, workerTid, 65) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL for an invalid signal\n");
+        return 1;
+    }
+    if (syscall(SYS_tgkill, 0, workerTid, SIGUSR1) != -1 || errno != EINVAL ||
+        syscall(SYS_tkill, 0, SIGUSR1) != -1 || errno != EINVAL) {
+        printf("fail: expected EINVAL for a zero id\n");
+        return 1;
+    }
+
+    stop = 1;
+    pthread_join(th, NULL);
+    if (handled != 2) {
+        printf("fail: unexpected extra signal\n");
+        return 1;
+    }
+    printf("pass\n");
+    return 0;
+}
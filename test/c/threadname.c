@@ -0,0 +1,155 @@
+#define _GNU_SOURCE
+#include <fcntl.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/prctl.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+// Thread names set with prctl(PR_SET_NAME) show up in /proc/[pid]/task/[tid]/
+// comm, stat and status, and writing comm renames the thread.
+
+static pthread_barrier_t barrier;
+static pid_t workerTid;
+
+static void *worker(void *arg)
+{
+    workerTid = syscall(SYS_gettid);
+    pthread_setname_np(pthread_self(), "worker");
+    pthread_barrier_wait(&barrier);
+    pthread_barrier_wait(&barrier);
+    return NULL;
+}
+
+static int readFile(const char *path, char *buf, size_t len)
+{
+    int fd = open(path, O_RDONLY);
+    if (fd < 0) {
+        perror(path);
+        return -1;
+    }
+    ssize_t n = read(fd, buf, len - 1);
+    close(fd);
+    if (n < 0) {
+        perror(path);
+        return -1;
+    }
+    buf[n] = 0;
+    return 0;
+}
+
+static int writeFile(const char *path, const char *s)
+{
+    int fd = open(path, O_WRONLY);
+    if (fd < 0) {
+        perror(path);
+        return -1;
+    }
+    ssize_t n = write(fd, s, strlen(s));
+    close(fd);
+    return n == (ssize_t)strlen(s) ? 0 : -1;
+}
+
+int main()
+{
+    char name[32], buf[512], path[64];
+
+    // names longer than 15 bytes are truncated, and PR_GET_NAME always
+    // writes 16 bytes
+    if (prctl(PR_SET_NAME, "a-very-long-thread-name", 0, 0, 0) < 0) {
+        perror("prctl PR_SET_NAME");
+        return 1;
+    }
+    memset(name, 'x', sizeof(name));
+    if (prctl(PR_GET_NAME, name, 0, 0, 0) < 0) {
+        perror("prctl PR_GET_NAME");
+        return 1;
+    }
+    if (strcmp(name, "a-very-long-thr") != 0 || name[16] != 'x') {
+        printf("fail: PR_GET_NAME returned %s\n", name);
+        return 1;
+    }
+    if (prctl(PR_SET_NAME, "short", 0, 0, 0) < 0 || prctl(PR_GET_NAME, name, 0, 0, 0) < 0) {
+        perror("prctl");
+        return 1;
+    }
+    for (int i = strlen("short"); i < 16; i++) {
+        if (name[i] != 0) {
+            printf("fail: PR_GET_NAME isn't padded\n");
+            return 1;
+        }
+    }
+
+    // another thread's name through procfs
+    pthread_t th;
+    pthread_barrier_init(&barrier, NULL, 2);
+    pthread_create(&th, NULL, worker, NULL);
+    pthread_barrier_wait(&barrier);
+
+    snprintf(path, sizeof(path), "/proc/self/task/%d/comm", workerTid);
+    if (readFile(path, buf, sizeof(buf)) < 0) {
+        return 1;
+    }
+    if (strcmp(buf, "worker\n") != 0) {
+        printf("fail: the worker's comm is %s", buf);
+        return 1;
+    }
+    if (pthread_getname_np(th, name, sizeof(name)) != 0 || strcmp(name, "worker") != 0) {
+        printf("fail: pthread_getname_np returned %s\n", name);
+        return 1;
+    }
+
+    // writing comm renames the thread, including parentheses
+    if (writeFile(path, "w (1) x") < 0) {
+        printf("fail: can't write %s\n", path);
+        return 1;
+    }
+    snprintf(path, sizeof(path), "/proc/self/task/%d/stat", workerTid);
+    if (readFile(path, buf, sizeof(buf)) < 0) {
+        return 1;
+    }
+    char *lp = strchr(buf, '('), *rp = strrchr(buf, ')');
+    if (!lp || !rp || rp - lp - 1 != 7 || strncmp(lp + 1, "w (1) x", 7) != 0 || rp[1] != ' ' ||
+        (rp[2] != 'S' && rp[2] != 'R')) {
+        printf("fail: stat is %s\n", buf);
+        return 1;
+    }
+    snprintf(path, sizeof(path), "/proc/self/task/%d/status", workerTid);
+    if (readFile(path, buf, sizeof(buf)) < 0) {
+        return 1;
+    }
+    if (strncmp(buf, "Name:\tw (1) x\n", 14) != 0) {
+        printf("fail: status starts with %.20s\n", buf);
+        return 1;
+    }
+
+    // the main thread kept its own name
+    if (readFile("/proc/self/comm", buf, sizeof(buf)) < 0) {
+        return 1;
+    }
+    if (strcmp(buf, "short\n") != 0) {
+        printf("fail: the main thread's comm is %s", buf);
+        return 1;
+    }
+
+    pthread_barrier_wait(&barrier);
+    pthread_join(th, NULL);
+
+    // dumpable
+    if (prctl(PR_SET_DUMPABLE, 0, 0, 0, 0) < 0 || prctl(PR_GET_DUMPABLE, 0, 0, 0, 0) != 0) {
+        printf("fail: PR_SET_DUMPABLE 0\n");
+        return 1;
+    }
+    if (prctl(PR_SET_DUMPABLE, 1, 0, 0, 0) < 0 || prctl(PR_GET_DUMPABLE, 0, 0, 0, 0) != 1) {
+        printf("fail: PR_SET_DUMPABLE 1\n");
+        return 1;
+    }
+    if (prctl(PR_SET_DUMPABLE, 2, 0, 0, 0) != -1) {
+        printf("fail: PR_SET_DUMPABLE 2 was accepted\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
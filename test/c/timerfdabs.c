@@ -0,0 +1,117 @@
+#include <errno.h>
+#include <inttypes.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/epoll.h>
+#include <sys/timerfd.h>
+#include <time.h>
+#include <unistd.h>
+
+#define MS 1000000L
+
+static long long nowNs(int clock)
+{
+    struct timespec ts;
+    clock_gettime(clock, &ts);
+    return ts.tv_sec * 1000000000LL + ts.tv_nsec;
+}
+
+// absTest arms an absolute one shot timer on clock and checks the read
+// doesn't complete before the deadline.
+static int absTest(int clock, const char *name)
+{
+    int fd = timerfd_create(clock, 0);
+    if (fd < 0) {
+        printf("fail: timerfd_create(%s): %s\n", name, strerror(errno));
+        return 1;
+    }
+
+    long long deadline = nowNs(clock) + 50 * MS;
+    struct itimerspec its = {
+        .it_value = {deadline / 1000000000LL, deadline % 1000000000LL},
+    };
+    if (timerfd_settime(fd, TFD_TIMER_ABSTIME | TFD_TIMER_CANCEL_ON_SET, &its, NULL) < 0) {
+        printf("fail: timerfd_settime(%s): %s\n", name, strerror(errno));
+        return 1;
+    }
+
+    uint64_t exp = 0;
+    if (read(fd, &exp, sizeof(exp)) != sizeof(exp) || exp != 1) {
+        printf("fail: %s read returned %" PRIu64 "\n", name, exp);
+        return 1;
+    }
+
+    if (nowNs(clock) < deadline) {
+        printf("fail: %s timer fired early\n", name);
+        return 1;
+    }
+
+    close(fd);
+    return 0;
+}
+
+int main()
+{
+    if (absTest(CLOCK_MONOTONIC, "CLOCK_MONOTONIC") ||
+        absTest(CLOCK_REALTIME, "CLOCK_REALTIME") ||
+        absTest(CLOCK_BOOTTIME, "CLOCK_BOOTTIME")) {
+        return 1;
+    }
+
+    int fd = timerfd_create(CLOCK_MONOTONIC, TFD_NONBLOCK);
+    struct itimerspec its = {
+        .it_value = {0, 20 * MS},
+        .it_interval = {0, 20 * MS},
+    };
+    timerfd_settime(fd, 0, &its, NULL);
+
+    // gettime reports a positive remaining time no larger than the interval
+    struct itimerspec cur;
+    timerfd_gettime(fd, &cur);
+    long long left = cur.it_value.tv_sec * 1000000000LL + cur.it_value.tv_nsec;
+    if (left <= 0 || left > 20 * MS || cur.it_value.tv_nsec < 0 ||
+        cur.it_interval.tv_nsec != 20 * MS) {
+        printf("fail: gettime value %lld interval %ld\n", left, cur.it_interval.tv_nsec);
+        return 1;
+    }
+
+    // readiness asserts on expiration and clears after a read
+    int epfd = epoll_create1(0);
+    struct epoll_event ev = {.events = EPOLLIN};
+    epoll_ctl(epfd, EPOLL_CTL_ADD, fd, &ev);
+    if (epoll_wait(epfd, &ev, 1, 1000) != 1) {
+        printf("fail: timerfd never became readable\n");
+        return 1;
+    }
+
+    uint64_t exp = 0;
+    if (read(fd, &exp, sizeof(exp)) != sizeof(exp) || exp == 0) {
+        printf("fail: periodic read returned %" PRIu64 "\n", exp);
+        return 1;
+    }
+
+    if (epoll_wait(epfd, &ev, 1, 0) != 0) {
+        printf("fail: timerfd still readable after a read\n");
+        return 1;
+    }
+
+    if (read(fd, &exp, sizeof(exp)) != -1 || errno != EAGAIN) {
+        printf("fail: expected EAGAIN after draining the timer\n");
+        return 1;
+    }
+
+    // rearming discards the expirations that haven't been read yet
+    usleep(50 * 1000);
+    its.it_value.tv_sec = 10;
+    its.it_interval.tv_nsec = 0;
+    timerfd_settime(fd, 0, &its, NULL);
+    if (read(fd, &exp, sizeof(exp)) != -1 || errno != EAGAIN) {
+        printf("fail: rearm kept stale expirations\n");
+        return 1;
+    }
+
+    close(epfd);
+    close(fd);
+    printf("pass\n");
+    return 0;
+}
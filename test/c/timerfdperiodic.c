@@ -0,0 +1,92 @@
+#include <errno.h>
+#include <inttypes.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/epoll.h>
+#include <sys/timerfd.h>
+#include <time.h>
+#include <unistd.h>
+
+#define PERIOD_NS (10 * 1000 * 1000)
+
+int main()
+{
+	int tfd = timerfd_create(CLOCK_MONOTONIC, TFD_NONBLOCK);
+	if (tfd < 0) {
+		perror("timerfd_create");
+		return EXIT_FAILURE;
+	}
+
+	// first expiry given as an absolute time
+	struct timespec now;
+	clock_gettime(CLOCK_MONOTONIC, &now);
+	struct itimerspec ts = {
+		.it_interval = {.tv_sec = 0, .tv_nsec = PERIOD_NS},
+		.it_value = {.tv_sec = now.tv_sec, .tv_nsec = now.tv_nsec + PERIOD_NS},
+	};
+	if (ts.it_value.tv_nsec >= 1000000000) {
+		ts.it_value.tv_sec++;
+		ts.it_value.tv_nsec -= 1000000000;
+	}
+
+	if (timerfd_settime(tfd, TFD_TIMER_ABSTIME, &ts, NULL) < 0) {
+		perror("timerfd_settime");
+		return EXIT_FAILURE;
+	}
+
+	struct itimerspec cur;
+	if (timerfd_gettime(tfd, &cur) < 0 || cur.it_interval.tv_nsec != PERIOD_NS) {
+		printf("fail: timerfd_gettime interval %ld\n", cur.it_interval.tv_nsec);
+		return EXIT_FAILURE;
+	}
+
+	int epfd = epoll_create1(0);
+	struct epoll_event ev = {.events = EPOLLIN};
+	if (epfd < 0 || epoll_ctl(epfd, EPOLL_CTL_ADD, tfd, &ev) < 0) {
+		perror("epoll");
+		return EXIT_FAILURE;
+	}
+
+	uint64_t total = 0;
+	for (int i = 0; i < 5; i++) {
+		memset(&ev, 0, sizeof(ev));
+		int ret = epoll_wait(epfd, &ev, 1, 1000);
+		if (ret != 1 || !(ev.events & EPOLLIN)) {
+			printf("fail: epoll_wait returned %d events %x\n", ret, ev.events);
+			return EXIT_FAILURE;
+		}
+
+		uint64_t exp = 0;
+		if (read(tfd, &exp, sizeof(exp)) != sizeof(exp) || exp == 0) {
+			printf("fail: read expirations %" PRIu64 "\n", exp);
+			return EXIT_FAILURE;
+		}
+		total += exp;
+
+		// the count is reset by the read
+		if (read(tfd, &exp, sizeof(exp)) != -1 || errno != EAGAIN) {
+			printf("fail: expected EAGAIN after reading the count\n");
+			return EXIT_FAILURE;
+		}
+	}
+	printf("expirations %" PRIu64 "\n", total);
+
+	if (total < 5) {
+		printf("fail: expected at least 5 expirations\n");
+		return EXIT_FAILURE;
+	}
+
+	close(epfd);
+	close(tfd);
+
+	tfd = timerfd_create(CLOCK_BOOTTIME, 0);
+	if (tfd < 0) {
+		perror("timerfd_create(CLOCK_BOOTTIME)");
+		return EXIT_FAILURE;
+	}
+	close(tfd);
+
+	printf("pass\n");
+	return EXIT_SUCCESS;
+}
@@ -0,0 +1,156 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <pthread.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+// Timers created with SIGEV_THREAD_ID signal only the thread they name, and a
+// CLOCK_THREAD_CPUTIME_ID timer expires as that thread burns cpu.
+
+#ifndef sigev_notify_thread_id
+#define sigev_notify_thread_id _sigev_un._tid
+#endif
+
+static volatile pid_t workerTid;
+static volatile int workerGot;
+
+static void *worker(void *arg)
+{
+    workerTid = syscall(SYS_gettid);
+
+    sigset_t set;
+    sigemptyset(&set);
+    sigaddset(&set, SIGUSR1);
+    siginfo_t si;
+    struct timespec timeout = {.tv_sec = 5};
+    if (sigtimedwait(&set, &si, &timeout) == SIGUSR1 && si.si_code == SI_TIMER && si.si_value.sival_int == 7) {
+        workerGot = 1;
+    }
+    return NULL;
+}
+
+static int threadDirected(void)
+{
+    pthread_t th;
+    if (pthread_create(&th, NULL, worker, NULL) != 0) {
+        printf("fail: pthread_create\n");
+        return 1;
+    }
+    while (workerTid == 0) {
+        usleep(1000);
+    }
+
+    struct sigevent sev;
+    memset(&sev, 0, sizeof(sev));
+    sev.sigev_notify = SIGEV_THREAD_ID;
+    sev.sigev_signo = SIGUSR1;
+    sev.sigev_value.sival_int = 7;
+    sev.sigev_notify_thread_id = workerTid;
+
+    timer_t id;
+    if (timer_create(CLOCK_MONOTONIC, &sev, &id) < 0) {
+        perror("timer_create");
+        return 1;
+    }
+
+    struct itimerspec its = {
+        .it_value = {.tv_sec = 0, .tv_nsec = 10 * 1000 * 1000},
+    };
+    if (timer_settime(id, 0, &its, NULL) < 0) {
+        perror("timer_settime");
+        return 1;
+    }
+
+    pthread_join(th, NULL);
+    if (!workerGot) {
+        printf("fail: the named thread didn't get the timer signal\n");
+        return 1;
+    }
+
+    // the main thread never sees the thread-directed signal
+    sigset_t pending;
+    sigpending(&pending);
+    if (sigismember(&pending, SIGUSR1)) {
+        printf("fail: the signal is pending on the main thread\n");
+        return 1;
+    }
+    timer_delete(id);
+
+    // the thread must be in the calling thread group
+    pid_t pid = fork();
+    if (pid == 0) {
+        pause();
+        _exit(0);
+    }
+    sev.sigev_notify_thread_id = pid;
+    int ret = timer_create(CLOCK_MONOTONIC, &sev, &id);
+    int err = errno;
+    kill(pid, SIGKILL);
+    waitpid(pid, NULL, 0);
+    if (ret != -1 || err != EINVAL) {
+        printf("fail: SIGEV_THREAD_ID accepted a tid of another process\n");
+        return 1;
+    }
+
+    return 0;
+}
+
+static int threadCPUTimer(void)
+{
+    struct sigevent sev;
+    memset(&sev, 0, sizeof(sev));
+    sev.sigev_notify = SIGEV_THREAD_ID;
+    sev.sigev_signo = SIGUSR1;
+    sev.sigev_notify_thread_id = syscall(SYS_gettid);
+
+    timer_t id;
+    if (timer_create(CLOCK_THREAD_CPUTIME_ID, &sev, &id) < 0) {
+        perror("timer_create");
+        return 1;
+    }
+
+    struct itimerspec its = {
+        .it_value = {.tv_sec = 0, .tv_nsec = 20 * 1000 * 1000},
+    };
+    if (timer_settime(id, 0, &its, NULL) < 0) {
+        perror("timer_settime");
+        return 1;
+    }
+
+    // burn cpu until the timer expires
+    struct timespec start, now;
+    clock_gettime(CLOCK_MONOTONIC, &start);
+    sigset_t pending;
+    do {
+        sigpending(&pending);
+        clock_gettime(CLOCK_MONOTONIC, &now);
+    } while (!sigismember(&pending, SIGUSR1) && now.tv_sec - start.tv_sec < 5);
+
+    if (!sigismember(&pending, SIGUSR1)) {
+        printf("fail: CLOCK_THREAD_CPUTIME_ID timer didn't fire\n");
+        return 1;
+    }
+
+    timer_delete(id);
+    return 0;
+}
+
+int main()
+{
+    sigset_t set;
+    sigemptyset(&set);
+    sigaddset(&set, SIGUSR1);
+    sigprocmask(SIG_BLOCK, &set, NULL);
+
+    if (threadDirected() || threadCPUTimer()) {
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,102 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <termios.h>
+#include <unistd.h>
+
+// A background process group reading from the controlling terminal gets
+// SIGTTIN, and writing to it with TOSTOP set gets SIGTTOU. With the signal
+// ignored, the read fails with EIO and the write goes through.
+// Run it on a terminal, e.g. `script -qc ./ttyjobctl /dev/null`.
+
+static volatile sig_atomic_t got;
+
+static void onSig(int sig)
+{
+    got = sig;
+}
+
+static int background()
+{
+    struct sigaction sa = {.sa_handler = onSig};
+    sigaction(SIGTTIN, &sa, NULL);
+    sigaction(SIGTTOU, &sa, NULL);
+
+    char c;
+    if (read(0, &c, 1) != -1 || errno != EINTR || got != SIGTTIN) {
+        printf("fail: background read didn't get SIGTTIN\n");
+        return 1;
+    }
+
+    got = 0;
+    if (write(1, "x\n", 2) != -1 || errno != EINTR || got != SIGTTOU) {
+        printf("fail: background write with TOSTOP didn't get SIGTTOU\n");
+        return 1;
+    }
+
+    signal(SIGTTIN, SIG_IGN);
+    if (read(0, &c, 1) != -1 || errno != EIO) {
+        printf("fail: background read with SIGTTIN ignored didn't fail with EIO\n");
+        return 1;
+    }
+
+    signal(SIGTTOU, SIG_IGN);
+    got = 0;
+    if (write(1, "background write\n", 17) != 17 || got != 0) {
+        printf("fail: background write with SIGTTOU ignored\n");
+        return 1;
+    }
+    return 0;
+}
+
+int main()
+{
+    if (!isatty(0) || !isatty(1)) {
+        printf("skip: stdin or stdout is not a terminal\n");
+        return 0;
+    }
+    if (tcgetpgrp(0) != getpgrp()) {
+        printf("skip: not in the foreground process group\n");
+        return 0;
+    }
+
+    struct termios orig, t;
+    if (tcgetattr(0, &orig) < 0) {
+        perror("tcgetattr");
+        return 1;
+    }
+    t = orig;
+    t.c_lflag |= TOSTOP;
+    if (tcsetattr(0, TCSANOW, &t) < 0) {
+        perror("tcsetattr");
+        return 1;
+    }
+
+    pid_t pid = fork();
+    if (pid == 0) {
+        setpgid(0, 0);
+        _exit(background());
+    }
+    setpgid(pid, pid);
+
+    int status;
+    int ok = waitpid(pid, &status, 0) == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0;
+    tcsetattr(0, TCSANOW, &orig);
+    if (!ok) {
+        return 1;
+    }
+
+    // the foreground process group is not affected
+    char buf[64];
+    int n = snprintf(buf, sizeof(buf), "foreground write\n");
+    if (write(1, buf, n) != n) {
+        printf("fail: foreground write\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,79 @@
+#define _GNU_SOURCE
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/syscall.h>
+#include <time.h>
+#include <unistd.h>
+
+// The vDSO CLOCK_REALTIME stays within 1ms of the host clock, read through
+// the clock_gettime syscall, and CLOCK_MONOTONIC never goes backwards while
+// the time parameters are refreshed. Runs for 10 minutes unless the number
+// of seconds is given as the first argument.
+
+#define MAX_DRIFT_NS 1000000LL
+
+static long long ts2ns(struct timespec *ts)
+{
+    return ts->tv_sec * 1000000000LL + ts->tv_nsec;
+}
+
+// drift returns the smallest difference between the vDSO and the syscall
+// CLOCK_REALTIME over a few tries, so a preemption between the reads isn't
+// counted as drift.
+static long long drift()
+{
+    long long best = -1;
+    for (int i = 0; i < 5; i++) {
+        struct timespec before, host, after;
+        clock_gettime(CLOCK_REALTIME, &before);
+        syscall(SYS_clock_gettime, CLOCK_REALTIME, &host);
+        clock_gettime(CLOCK_REALTIME, &after);
+
+        long long mid = (ts2ns(&before) + ts2ns(&after)) / 2;
+        long long d = llabs(mid - ts2ns(&host));
+        if (best < 0 || d < best) {
+            best = d;
+        }
+    }
+    return best;
+}
+
+int main(int argc, char **argv)
+{
+    int seconds = argc > 1 ? atoi(argv[1]) : 600;
+
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    long long last = ts2ns(&ts);
+    long long end = last + seconds * 1000000000LL;
+    long long worst = 0;
+
+    while (last < end) {
+        long long d = drift();
+        if (d > worst) {
+            worst = d;
+        }
+        if (d > MAX_DRIFT_NS) {
+            printf("fail: the vDSO realtime is %lldns away from the host after %llds\n",
+                   d, seconds - (end - last) / 1000000000LL);
+            return 1;
+        }
+
+        // spin for a while, checking that monotonic time doesn't go back
+        for (int i = 0; i < 100000; i++) {
+            clock_gettime(CLOCK_MONOTONIC, &ts);
+            if (ts2ns(&ts) < last) {
+                printf("fail: CLOCK_MONOTONIC went back by %lldns\n", last - ts2ns(&ts));
+                return 1;
+            }
+            last = ts2ns(&ts);
+        }
+        usleep(200 * 1000);
+        clock_gettime(CLOCK_MONOTONIC, &ts);
+        last = ts2ns(&ts);
+    }
+
+    printf("worst drift %lldns\n", worst);
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,175 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/uio.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define TOTAL (1024 * 1024)
+#define SEGS 8
+
+static unsigned char pattern(long i)
+{
+    return (unsigned char)(i * 13 + 5);
+}
+
+// stream vmsplices TOTAL bytes from several iovecs into a pipe, more than it
+// can hold at once, while a child reads them back.
+static int stream(void)
+{
+    int fds[2];
+    if (pipe(fds) < 0) {
+        perror("pipe");
+        return 1;
+    }
+
+    pid_t pid = fork();
+    if (pid == 0) {
+        close(fds[1]);
+        unsigned char buf[3000];
+        long got = 0;
+        for (;;) {
+            ssize_t n = read(fds[0], buf, sizeof(buf));
+            if (n < 0) {
+                _exit(1);
+            }
+            if (n == 0) {
+                break;
+            }
+            for (ssize_t i = 0; i < n; i++) {
+                if (buf[i] != pattern(got + i)) {
+                    printf("fail: mismatch at byte %ld\n", got + i);
+                    _exit(1);
+                }
+            }
+            got += n;
+        }
+        _exit(got == TOTAL ? 0 : 1);
+    }
+    close(fds[0]);
+
+    unsigned char *data = malloc(TOTAL);
+    for (long i = 0; i < TOTAL; i++) {
+        data[i] = pattern(i);
+    }
+
+    long sent = 0;
+    while (sent < TOTAL) {
+        // split what's left into uneven segments
+        struct iovec iov[SEGS];
+        long left = TOTAL - sent, off = sent;
+        int nr = 0;
+        for (; nr < SEGS && left > 0; nr++) {
+            long len = nr == SEGS - 1 ? left : (left < 777 * (nr + 1) ? left : 777 * (nr + 1));
+            iov[nr].iov_base = data + off;
+            iov[nr].iov_len = len;
+            off += len;
+            left -= len;
+        }
+
+        ssize_t n = vmsplice(fds[1], iov, nr, 0);
+        if (n <= 0) {
+            perror("vmsplice");
+            return 1;
+        }
+        sent += n;
+    }
+    close(fds[1]);
+
+    int status;
+    if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        printf("fail: reader didn't get the stream back\n");
+        return 1;
+    }
+    free(data);
+    return 0;
+}
+
+// capacity checks that vmsplice stops at the pipe capacity.
+static int capacity(void)
+{
+    int fds[2];
+    if (pipe(fds) < 0) {
+        perror("pipe");
+        return 1;
+    }
+
+    long size = fcntl(fds[1], F_GETPIPE_SZ);
+    char *buf = malloc(2 * size);
+    memset(buf, 'c', 2 * size);
+    struct iovec iov = {buf, 2 * size};
+    ssize_t n = vmsplice(fds[1], &iov, 1, SPLICE_F_NONBLOCK);
+    if (n <= 0 || n > size) {
+        printf("fail: vmsplice of twice the capacity queued %zd bytes\n", n);
+        return 1;
+    }
+
+    // fill whatever is left, then the pipe is full
+    while ((n = vmsplice(fds[1], &iov, 1, SPLICE_F_NONBLOCK)) > 0) {
+    }
+    if (n != -1 || errno != EAGAIN) {
+        printf("fail: expected EAGAIN from a full pipe\n");
+        return 1;
+    }
+
+    free(buf);
+    close(fds[0]);
+    close(fds[1]);
+    return 0;
+}
+
+// gift hands whole pages over with SPLICE_F_GIFT and reads them back into
+// scattered iovecs.
+static int gift(void)
+{
+    int fds[2];
+    if (pipe(fds) < 0) {
+        perror("pipe");
+        return 1;
+    }
+
+    long pagesz = sysconf(_SC_PAGESIZE);
+    unsigned char *pages = mmap(NULL, 2 * pagesz, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    for (long i = 0; i < 2 * pagesz; i++) {
+        pages[i] = pattern(i);
+    }
+
+    struct iovec iov = {pages, 2 * pagesz};
+    if (vmsplice(fds[1], &iov, 1, SPLICE_F_GIFT) != 2 * pagesz) {
+        perror("vmsplice gift");
+        return 1;
+    }
+    munmap(pages, 2 * pagesz);
+
+    unsigned char a[100], b[2 * 4096];
+    struct iovec oiov[2] = {{a, sizeof(a)}, {b, 2 * pagesz - sizeof(a)}};
+    if (vmsplice(fds[0], oiov, 2, 0) != 2 * pagesz) {
+        perror("vmsplice read");
+        return 1;
+    }
+    for (long i = 0; i < 2 * pagesz; i++) {
+        unsigned char c = i < (long)sizeof(a) ? a[i] : b[i - sizeof(a)];
+        if (c != pattern(i)) {
+            printf("fail: gifted page mismatch at byte %ld\n", i);
+            return 1;
+        }
+    }
+
+    close(fds[0]);
+    close(fds[1]);
+    return 0;
+}
+
+int main()
+{
+    if (capacity() || gift() || stream()) {
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
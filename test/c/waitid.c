@@ -0,0 +1,122 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+// waitid reports exits, stops and continues selected by WEXITED, WSTOPPED
+// and WCONTINUED. With WNOWAIT the child stays waitable, so a later wait
+// still reaps it.
+
+#ifndef P_PIDFD
+#define P_PIDFD 3
+#endif
+
+static int check(siginfo_t *info, pid_t pid, int code, int status, const char *what)
+{
+    if (info->si_signo != SIGCHLD || info->si_pid != pid || info->si_code != code || info->si_status != status) {
+        printf("fail: %s: signo %d pid %d code %d status %d\n", what, info->si_signo, info->si_pid,
+               info->si_code, info->si_status);
+        return -1;
+    }
+    return 0;
+}
+
+int main()
+{
+    siginfo_t info;
+
+    // WNOWAIT peeks at the exit status and leaves the zombie
+    pid_t pid = fork();
+    if (pid == 0) {
+        _exit(7);
+    }
+    memset(&info, 0, sizeof(info));
+    if (waitid(P_PID, pid, &info, WEXITED | WNOWAIT) < 0 || check(&info, pid, CLD_EXITED, 7, "WNOWAIT") < 0) {
+        return 1;
+    }
+    memset(&info, 0, sizeof(info));
+    if (waitid(P_ALL, 0, &info, WEXITED | WNOWAIT) < 0 || check(&info, pid, CLD_EXITED, 7, "second WNOWAIT") < 0) {
+        return 1;
+    }
+    int status;
+    if (wait4(pid, &status, 0, NULL) != pid || !WIFEXITED(status) || WEXITSTATUS(status) != 7) {
+        printf("fail: wait4 didn't reap the child after WNOWAIT\n");
+        return 1;
+    }
+    if (waitid(P_PID, pid, &info, WEXITED | WNOHANG) != -1 || errno != ECHILD) {
+        printf("fail: the child is still waitable after wait4\n");
+        return 1;
+    }
+
+    // stop and continue are reported with WSTOPPED and WCONTINUED only
+    pid = fork();
+    if (pid == 0) {
+        for (;;) {
+            pause();
+        }
+    }
+    kill(pid, SIGSTOP);
+    memset(&info, 0, sizeof(info));
+    if (waitid(P_PID, pid, &info, WSTOPPED | WNOWAIT) < 0 || check(&info, pid, CLD_STOPPED, SIGSTOP, "stop") < 0) {
+        return 1;
+    }
+    memset(&info, 0, sizeof(info));
+    if (waitid(P_PID, pid, &info, WEXITED | WNOHANG) < 0 || info.si_pid != 0) {
+        printf("fail: WEXITED reported a stopped child\n");
+        return 1;
+    }
+    if (waitid(P_PID, pid, &info, WSTOPPED) < 0 || check(&info, pid, CLD_STOPPED, SIGSTOP, "consumed stop") < 0) {
+        return 1;
+    }
+    memset(&info, 0, sizeof(info));
+    if (waitid(P_PID, pid, &info, WSTOPPED | WNOHANG) < 0 || info.si_pid != 0) {
+        printf("fail: the stop was reported twice\n");
+        return 1;
+    }
+
+    kill(pid, SIGCONT);
+    memset(&info, 0, sizeof(info));
+    if (waitid(P_PID, pid, &info, WCONTINUED | WNOWAIT) < 0 || check(&info, pid, CLD_CONTINUED, SIGCONT, "continue") < 0) {
+        return 1;
+    }
+    if (waitpid(pid, &status, WCONTINUED) != pid || !WIFCONTINUED(status)) {
+        printf("fail: waitpid didn't report the continue after WNOWAIT\n");
+        return 1;
+    }
+
+    kill(pid, SIGKILL);
+    if (waitid(P_PID, pid, &info, WEXITED) < 0 || check(&info, pid, CLD_KILLED, SIGKILL, "kill") < 0) {
+        return 1;
+    }
+
+    // waitid on a pidfd
+    pid = fork();
+    if (pid == 0) {
+        _exit(5);
+    }
+    int fd = syscall(SYS_pidfd_open, pid, 0);
+    if (fd < 0) {
+        perror("pidfd_open");
+        return 1;
+    }
+    memset(&info, 0, sizeof(info));
+    if (waitid(P_PIDFD, fd, &info, WEXITED) < 0 || check(&info, pid, CLD_EXITED, 5, "P_PIDFD") < 0) {
+        return 1;
+    }
+
+    if (waitid(P_ALL, 0, &info, WNOHANG) != -1 || errno != EINVAL) {
+        printf("fail: waitid without a selector\n");
+        return 1;
+    }
+    if (waitid(P_PID, 0, &info, WEXITED) != -1 || errno != EINVAL) {
+        printf("fail: waitid of pid 0\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}
@@ -0,0 +1,84 @@
+#define _GNU_SOURCE
+#include <signal.h>
+#include <stdio.h>
+#include <sys/ioctl.h>
+#include <unistd.h>
+
+// TIOCSWINSZ on the controlling terminal stores the window size and sends
+// SIGWINCH to the foreground process group, but only when the size changes.
+// Run it on a terminal, e.g. `script -qc ./winsize /dev/null`.
+
+static volatile sig_atomic_t winch;
+
+static void onWinch(int sig)
+{
+    winch++;
+}
+
+// waitWinch waits a little for the count of SIGWINCHs to reach want.
+static int waitWinch(int want)
+{
+    for (int i = 0; i < 100 && winch < want; i++) {
+        usleep(10000);
+    }
+    return winch == want;
+}
+
+int main()
+{
+    if (!isatty(0)) {
+        printf("skip: stdin is not a terminal\n");
+        return 0;
+    }
+    if (tcgetpgrp(0) != getpgrp()) {
+        printf("skip: not in the foreground process group\n");
+        return 0;
+    }
+
+    struct sigaction sa = {.sa_handler = onWinch};
+    if (sigaction(SIGWINCH, &sa, NULL) < 0) {
+        perror("sigaction");
+        return 1;
+    }
+
+    struct winsize orig, ws, got;
+    if (ioctl(0, TIOCGWINSZ, &orig) < 0) {
+        perror("TIOCGWINSZ");
+        return 1;
+    }
+
+    ws = orig;
+    ws.ws_row = orig.ws_row == 33 ? 34 : 33;
+    ws.ws_col = orig.ws_col == 101 ? 102 : 101;
+    if (ioctl(0, TIOCSWINSZ, &ws) < 0 || ioctl(0, TIOCGWINSZ, &got) < 0) {
+        perror("TIOCSWINSZ");
+        return 1;
+    }
+    if (got.ws_row != ws.ws_row || got.ws_col != ws.ws_col) {
+        printf("fail: read back %dx%d, set %dx%d\n", got.ws_row, got.ws_col, ws.ws_row, ws.ws_col);
+        return 1;
+    }
+    if (!waitWinch(1)) {
+        printf("fail: got %d SIGWINCH after a resize\n", (int)winch);
+        return 1;
+    }
+
+    // setting the same size again isn't a change
+    if (ioctl(0, TIOCSWINSZ, &ws) < 0) {
+        perror("TIOCSWINSZ");
+        return 1;
+    }
+    usleep(100000);
+    if (winch != 1) {
+        printf("fail: SIGWINCH sent without a size change\n");
+        return 1;
+    }
+
+    if (ioctl(0, TIOCSWINSZ, &orig) < 0 || !waitWinch(2)) {
+        printf("fail: restoring the size didn't send SIGWINCH\n");
+        return 1;
+    }
+
+    printf("pass\n");
+    return 0;
+}